@@ -0,0 +1,158 @@
+//! Same-binary A/B for the per-command stats collector: `dispatch_argv` vs
+//! `dispatch_argv_with_stats` on PING, the cheapest command and so the worst case for a fixed
+//! per-call cost (one classify and one relaxed atomic add; the clock is read on one call in 64).
+//!
+//! `ping_roundtrip` is what an embedder serving clients pays per request: parse the RESP frame,
+//! dispatch, encode the reply. `ping_dispatch` isolates the dispatch call itself, a sub-100ns
+//! command where any fixed cost shows at its largest.
+//!
+//! Substrate = the cc bench roster: ONE binary, adjacent-pair interleave (swap on odd rounds),
+//! black_box, reps calibrated per workload, median of 41 paired ratios against the null
+//! (plain-vs-plain) p5..p95. Gated: the round-trip overhead must stay under 2%.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use fr_command::stats::StatsHandle;
+use fr_command::{CommandError, argv_from_frame, dispatch_argv, dispatch_argv_with_stats};
+use fr_protocol::{RespFrame, parse_frame};
+use fr_store::Store;
+
+const ROUNDS: usize = 41;
+const TARGET_SEGMENT_SECS: f64 = 0.004;
+const NULL_LO: f64 = 0.05;
+const NULL_HI: f64 = 0.95;
+const MAX_ROUNDTRIP_OVERHEAD: f64 = 0.02;
+const PING: &[u8] = b"*1\r\n$4\r\nPING\r\n";
+
+type Dispatch = fn(&[Vec<u8>], &mut Store, &StatsHandle) -> Result<RespFrame, CommandError>;
+
+fn plain(argv: &[Vec<u8>], store: &mut Store, _: &StatsHandle) -> Result<RespFrame, CommandError> {
+    dispatch_argv(argv, store, 0)
+}
+
+fn counted(
+    argv: &[Vec<u8>],
+    store: &mut Store,
+    stats: &StatsHandle,
+) -> Result<RespFrame, CommandError> {
+    dispatch_argv_with_stats(argv, store, 0, stats)
+}
+
+fn roundtrip(dispatch: Dispatch, store: &mut Store, stats: &StatsHandle, out: &mut Vec<u8>) {
+    let parsed = parse_frame(black_box(PING)).expect("PING frame");
+    let argv = argv_from_frame(parsed.frame).expect("PING argv");
+    let reply = dispatch(&argv, store, stats).expect("PING reply");
+    out.clear();
+    reply.encode_into(out);
+}
+
+fn dispatch_only(dispatch: Dispatch, store: &mut Store, stats: &StatsHandle, out: &mut Vec<u8>) {
+    let argv = [b"PING".to_vec()];
+    let reply = dispatch(black_box(&argv), store, stats).expect("PING reply");
+    out.clear();
+    out.push(matches!(reply, RespFrame::SimpleString(_)) as u8);
+}
+
+type Workload = fn(Dispatch, &mut Store, &StatsHandle, &mut Vec<u8>);
+
+fn median(r: &mut [f64]) -> f64 {
+    r.sort_by(|a, b| a.partial_cmp(b).expect("no NaN"));
+    r[r.len() / 2]
+}
+
+fn pct(sorted: &[f64], p: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+fn time(
+    work: Workload,
+    dispatch: Dispatch,
+    store: &mut Store,
+    stats: &StatsHandle,
+    reps: usize,
+) -> f64 {
+    let mut out = Vec::with_capacity(16);
+    let start = Instant::now();
+    for _ in 0..reps {
+        work(dispatch, black_box(&mut *store), stats, &mut out);
+        black_box(&out);
+    }
+    start.elapsed().as_secs_f64()
+}
+
+/// Median with-stats / plain time ratio and the null p5..p95.
+fn measure(work: Workload, stats: &StatsHandle) -> (usize, f64, f64, f64) {
+    let mut store = Store::new();
+    let mut reps = 1usize;
+    loop {
+        let e = time(work, plain, &mut store, stats, reps);
+        if e >= TARGET_SEGMENT_SECS || reps > 1 << 24 {
+            reps = ((reps as f64) * (TARGET_SEGMENT_SECS / e.max(1e-9)).max(1.0)).ceil() as usize;
+            break;
+        }
+        reps *= 4;
+    }
+
+    let mut nulls = Vec::with_capacity(ROUNDS);
+    let mut ratios = Vec::with_capacity(ROUNDS);
+    for round in 0..=ROUNDS {
+        let swap = round % 2 == 1;
+        let mut pair = |base: Dispatch, cand: Dispatch| {
+            if swap {
+                let c = time(work, cand, &mut store, stats, reps);
+                c / time(work, base, &mut store, stats, reps)
+            } else {
+                let b = time(work, base, &mut store, stats, reps);
+                time(work, cand, &mut store, stats, reps) / b
+            }
+        };
+        let null = pair(plain, plain);
+        let ratio = pair(plain, counted);
+        if round == 0 {
+            continue;
+        }
+        nulls.push(null);
+        ratios.push(ratio);
+    }
+    median(&mut nulls);
+    let ratio = median(&mut ratios);
+    (reps, ratio, pct(&nulls, NULL_LO), pct(&nulls, NULL_HI))
+}
+
+fn main() {
+    println!(
+        "\n{:<16} {:>9} {:>16} {:>12} {:>10}",
+        "workload", "reps", "null p5..p95", "stats/plain", "overhead"
+    );
+    let stats = StatsHandle::new();
+    let mut roundtrip_overhead = 0.0;
+    for (name, work) in [
+        ("ping_roundtrip", roundtrip as Workload),
+        ("ping_dispatch", dispatch_only as Workload),
+    ] {
+        let (reps, ratio, lo, hi) = measure(work, &stats);
+        let overhead = ratio - 1.0;
+        println!(
+            "{:<16} {:>9} {:>16} {:>11.4}x {:>9.2}%",
+            name,
+            reps,
+            format!("[{lo:.3}, {hi:.3}]"),
+            ratio,
+            overhead * 100.0
+        );
+        if name == "ping_roundtrip" {
+            roundtrip_overhead = overhead;
+        }
+    }
+
+    let ping = stats.snapshot();
+    let ping = ping.get("ping").expect("PING counted");
+    assert!(ping.calls > 0 && ping.errors == 0, "{ping:?}");
+    assert!(
+        roundtrip_overhead < MAX_ROUNDTRIP_OVERHEAD,
+        "stats overhead on the PING round trip is {:.2}% (gate {:.0}%)",
+        roundtrip_overhead * 100.0,
+        MAX_ROUNDTRIP_OVERHEAD * 100.0
+    );
+}
@@ -0,0 +1,65 @@
+//! An axum service running commands against an in-process `SharedStore`.
+//!
+//! POST a JSON command array and get the reply back as JSON:
+//!
+//!   cargo run -p fr-command --example axum_shared_store -- 127.0.0.1:8080
+//!   curl -s -d '["SET","k","v"]' http://127.0.0.1:8080/   # => "OK"
+//!   curl -s -d '["GET","k"]' http://127.0.0.1:8080/       # => "v"
+//!
+//! Each request is one `dispatch`. The keyspace is split over 16 shards, so
+//! requests on different keys do not wait for each other. A command over a
+//! large value moves to tokio's blocking pool instead of stalling the other
+//! requests on its worker. A client that disconnects mid-request drops the
+//! handler future; that never leaves a command half applied.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use fr_command::SharedStore;
+use fr_protocol::{json_to_argv, resp_to_json};
+use fr_store::Store;
+use serde_json::{Value, json};
+
+const SHARDS: usize = 16;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let listen = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let store = SharedStore::sharded(SHARDS, Store::new);
+    let app = Router::new().route("/", post(command)).with_state(store);
+
+    let listener = tokio::net::TcpListener::bind(&listen).await?;
+    println!("serving http://{listen}/ over {SHARDS} shards");
+    axum::serve(listener, app).await
+}
+
+async fn command(
+    State(store): State<SharedStore>,
+    body: Bytes,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let bad_request =
+        |message: String| (StatusCode::BAD_REQUEST, Json(json!({ "error": message })));
+    let command: Value =
+        serde_json::from_slice(&body).map_err(|err| bad_request(format!("invalid JSON: {err}")))?;
+    let argv = json_to_argv(&command).map_err(|err| bad_request(err.to_string()))?;
+    // Command errors (WRONGTYPE, CROSSSLOT, ...) are replies, not HTTP errors.
+    let reply = store
+        .dispatch(argv, now_ms())
+        .await
+        .unwrap_or_else(|err| err.to_resp());
+    Ok(Json(resp_to_json(&reply)))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
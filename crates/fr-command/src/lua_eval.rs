@@ -42,9 +42,17 @@ fn i64_to_ascii_bytes(v: i64) -> Vec<u8> {
 use std::time::Instant;
 
 use fr_protocol::RespFrame;
-use fr_store::{SCRIPT_PROPAGATE_ALL, SCRIPT_PROPAGATE_AOF, SCRIPT_PROPAGATE_REPLICA, Store};
-
-use crate::{CommandError, SCRIPT_NOSCRIPT_ERROR, dispatch_argv, parse_i64_arg};
+use fr_store::{
+    CommandRename, SCRIPT_PROPAGATE_ALL, SCRIPT_PROPAGATE_AOF, SCRIPT_PROPAGATE_REPLICA, Store,
+    crc16_slot,
+};
+
+use crate::{
+    CommandError, SCRIPT_NONDETERMINISTIC_WRITE_ERROR, SCRIPT_NOSCRIPT_ERROR,
+    command_has_nondeterministic_output, command_key_indexes,
+    command_writes_or_may_replicate_in_readonly_script, dispatch_argv,
+    downconvert_resp3_reply_to_resp2, parse_i64_arg, reply_len,
+};
 
 // ── Lua cycle-breaking GC (frankenredis-qqq17) ──────────────────────────────
 //
@@ -3137,6 +3145,13 @@ pub struct LuaState<'a> {
     /// the real line into the `user_script:N:` error prefix at the uncaught-
     /// error boundary (default 1 == upstream chunk start). (frankenredis-m7oy8)
     current_line: u32,
+    /// Set once the script has called a nondeterministic command (the
+    /// `nondeterministic_output` tip). Only tracked while the script
+    /// propagates verbatim, where a later write would replay differently.
+    random_dirty: bool,
+    /// Set once the script has called a write command; after that
+    /// `redis.replicate_commands()` can no longer switch to effects.
+    write_dirty: bool,
     /// True iff exec_stmt is currently dispatching a bare
     /// Stmt::Expression (a stmt that's a single discarded-result
     /// expression). Combined with nested_exec_stmts_depth == 0,
@@ -3254,7 +3269,7 @@ impl RedisLrand48 {
     /// integer assembled from the top two limbs.
     fn rand(&mut self) -> i32 {
         self.next();
-        ((self.x[2] as i32) << (Self::N - 1)) + ((self.x[1] >> 1) as i32)
+        (self.x[2].cast_signed() << (Self::N - 1)) + (self.x[1] >> 1).cast_signed()
     }
 }
 
@@ -3539,10 +3554,6 @@ impl LuaGlobals {
                 .filter(|(key, _)| !self.overlay.contains_key(key.as_str())),
         )
     }
-
-    fn values(&self) -> impl Iterator<Item = &LuaValue> {
-        self.iter().map(|(_, value)| value)
-    }
 }
 
 impl<'a> IntoIterator for &'a LuaGlobals {
@@ -3843,7 +3854,7 @@ impl<'a> LuaState<'a> {
         let mut lua_random = RedisLrand48::new();
         let initial_seed = rng_seed as u32;
         if initial_seed != 0 {
-            lua_random.srand(initial_seed as i32);
+            lua_random.srand(initial_seed.cast_signed());
         }
         Self {
             store,
@@ -3855,6 +3866,8 @@ impl<'a> LuaState<'a> {
             lua_frame_kinds: Vec::new(),
             iterations: 0,
             current_line: 1,
+            random_dirty: false,
+            write_dirty: false,
             rng_seed,
             lua_random,
             script_started_at: None,
@@ -6923,8 +6936,18 @@ impl<'a> LuaState<'a> {
                 Ok(vec![LuaValue::Nil])
             }
             "redis.replicate_commands" => {
-                // No-op: effects replication was removed in Redis 7.0+
-                // Always returns true for compatibility
+                // Effects propagation is the default, so this is a no-op
+                // returning true. A verbatim-propagated script switches to
+                // effects here, unless it already wrote: the writes so far
+                // would be lost from both forms (pre-7.0
+                // luaRedisReplicateCommandsCommand returns false then).
+                if self.store.script_replicate_effects {
+                    return Ok(vec![LuaValue::Bool(true)]);
+                }
+                if self.write_dirty {
+                    return Ok(vec![LuaValue::Bool(false)]);
+                }
+                self.store.script_replicate_effects = true;
                 Ok(vec![LuaValue::Bool(true)])
             }
             "redis.set_repl" => {
@@ -6940,6 +6963,11 @@ impl<'a> LuaState<'a> {
                 // "ERR " on both error bodies; pcall sees the prefixed
                 // form and the direct-call wrapper recognises it via
                 // error_has_resp_code_prefix.
+                // A verbatim-propagated script has no per-command effects to
+                // filter, so set_repl needs redis.replicate_commands() first.
+                if !self.store.script_replicate_effects {
+                    return Err("ERR You can set the replication behavior only after turning on single commands replication with redis.replicate_commands().".to_string());
+                }
                 if args.len() != 1 {
                     return Err("ERR redis.set_repl() requires one argument.".to_string());
                 }
@@ -7879,7 +7907,7 @@ impl<'a> LuaState<'a> {
                     inv,
                     3,
                     args.get(2),
-                    t.inner.borrow().array.len() as i64,
+                    reply_len(t.inner.borrow().array.len()),
                 )?;
                 if start <= end && end.saturating_sub(start) >= 8000 {
                     return Err("too many results to unpack".to_string());
@@ -7919,7 +7947,7 @@ impl<'a> LuaState<'a> {
                                 &format!("number expected, got {}", lua_arg_got_label(idx_opt),),
                             )
                         })?;
-                        let arg_count = rest.len() as i64;
+                        let arg_count = reply_len(rest.len());
                         // NaN → 0 via C-style cast; trips "index out of
                         // range" alongside zero and negative-out-of-range.
                         let index = if raw_index.is_nan() {
@@ -8518,12 +8546,12 @@ impl<'a> LuaState<'a> {
                 } else {
                     // frexp: n = m * 2^e where 0.5 <= |m| < 1
                     let bits = n.to_bits();
-                    let exp_raw = ((bits >> 52) & 0x7FF) as i64;
+                    let exp_raw = ((bits >> 52) & 0x7FF).cast_signed();
                     if exp_raw == 0 {
                         // subnormal
                         let norm = n * (1u64 << 52) as f64;
                         let bits2 = norm.to_bits();
-                        let exp2 = ((bits2 >> 52) & 0x7FF) as i64;
+                        let exp2 = ((bits2 >> 52) & 0x7FF).cast_signed();
                         let e = exp2 - 1023 - 52;
                         let mantissa_bits = (bits2 & 0x000F_FFFF_FFFF_FFFF) | 0x3FE0_0000_0000_0000;
                         let m = f64::from_bits(mantissa_bits).copysign(n);
@@ -8692,7 +8720,7 @@ impl<'a> LuaState<'a> {
             }
             "string.sub" => {
                 let s = lua_check_string(self.current_invocation_name.as_deref(), args, 0, "sub")?;
-                let len = s.len() as i64;
+                let len = reply_len(s.len());
                 let mut i =
                     lua_check_number(self.current_invocation_name.as_deref(), args, 1, "sub")?
                         as i64;
@@ -8791,7 +8819,7 @@ impl<'a> LuaState<'a> {
                 // error.
                 let inv = self.current_invocation_name.as_deref();
                 let s = lua_check_string(inv, args, 0, "byte")?;
-                let len = s.len() as i64;
+                let len = reply_len(s.len());
                 let mut i = lua_optional_integer_arg(inv, 2, args.get(1), 1)?;
                 let mut j = lua_optional_integer_arg(inv, 3, args.get(2), i)?;
                 if i < 0 {
@@ -8896,7 +8924,7 @@ impl<'a> LuaState<'a> {
                     1,
                 )?;
                 let init = if init_raw < 0 {
-                    (s.len() as i64 + init_raw).max(0) as usize
+                    (reply_len(s.len()) + init_raw).max(0) as usize
                 } else {
                     (init_raw as usize).saturating_sub(1)
                 };
@@ -8973,7 +9001,7 @@ impl<'a> LuaState<'a> {
                     1,
                 )?;
                 let init = if init_raw < 0 {
-                    (s.len() as i64 + init_raw).max(0) as usize
+                    (reply_len(s.len()) + init_raw).max(0) as usize
                 } else {
                     (init_raw as usize).saturating_sub(1)
                 };
@@ -9243,7 +9271,7 @@ impl<'a> LuaState<'a> {
                 }
                 let pos_arg = args.get(1).cloned();
                 if let LuaValue::Table(ref mut t) = args[0] {
-                    let len = t.inner.borrow().array.len() as i64;
+                    let len = reply_len(t.inner.borrow().array.len());
                     let pos = lua_optional_integer_arg(inv, 2, pos_arg.as_ref(), len)?;
                     // (frankenredis-2hgg1) Redis's vendored Lua 5.1's
                     // ltablib.c::tremove returns 0 Lua values (not a
@@ -9296,7 +9324,7 @@ impl<'a> LuaState<'a> {
                 // silently truncates at the first nil instead of raising,
                 // because fr's array previously dropped nil-hole slots and
                 // now retains them.
-                let array_len = t.inner.borrow().border_len() as i64;
+                let array_len = reply_len(t.inner.borrow().border_len());
                 let start = lua_optional_integer_arg(inv, 3, args.get(2), 1)?;
                 let end = lua_optional_integer_arg(inv, 4, args.get(3), array_len)?;
                 // (frankenredis-jwkhc) Upstream ltablib.c::tconcat validates
@@ -9667,7 +9695,7 @@ impl<'a> LuaState<'a> {
                 for (i, a) in args.iter().enumerate() {
                     acc &= lua_value_to_u32_for_bitop(inv, i + 1, "band", Some(a))?;
                 }
-                Ok(vec![LuaValue::Number(acc as i32 as f64)])
+                Ok(vec![LuaValue::Number(acc.cast_signed() as f64)])
             }
             "bit.bor" => {
                 let inv = self.current_invocation_name.as_deref();
@@ -9678,7 +9706,7 @@ impl<'a> LuaState<'a> {
                 for (i, a) in args.iter().enumerate() {
                     acc |= lua_value_to_u32_for_bitop(inv, i + 1, "bor", Some(a))?;
                 }
-                Ok(vec![LuaValue::Number(acc as i32 as f64)])
+                Ok(vec![LuaValue::Number(acc.cast_signed() as f64)])
             }
             "bit.bxor" => {
                 let inv = self.current_invocation_name.as_deref();
@@ -9689,28 +9717,28 @@ impl<'a> LuaState<'a> {
                 for (i, a) in args.iter().enumerate() {
                     acc ^= lua_value_to_u32_for_bitop(inv, i + 1, "bxor", Some(a))?;
                 }
-                Ok(vec![LuaValue::Number(acc as i32 as f64)])
+                Ok(vec![LuaValue::Number(acc.cast_signed() as f64)])
             }
             "bit.bnot" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "bnot", args.first())?;
-                Ok(vec![LuaValue::Number(!x as i32 as f64)])
+                Ok(vec![LuaValue::Number((!x).cast_signed() as f64)])
             }
             "bit.lshift" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "lshift", args.first())?;
                 let n = lua_value_to_u32_for_bitop(inv, 2, "lshift", args.get(1))? & 31;
-                Ok(vec![LuaValue::Number((x << n) as i32 as f64)])
+                Ok(vec![LuaValue::Number((x << n).cast_signed() as f64)])
             }
             "bit.rshift" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "rshift", args.first())?;
                 let n = lua_value_to_u32_for_bitop(inv, 2, "rshift", args.get(1))? & 31;
-                Ok(vec![LuaValue::Number((x >> n) as i32 as f64)])
+                Ok(vec![LuaValue::Number((x >> n).cast_signed() as f64)])
             }
             "bit.arshift" => {
                 let inv = self.current_invocation_name.as_deref();
-                let x = lua_value_to_u32_for_bitop(inv, 1, "arshift", args.first())? as i32;
+                let x = lua_value_to_u32_for_bitop(inv, 1, "arshift", args.first())?.cast_signed();
                 let n = lua_value_to_u32_for_bitop(inv, 2, "arshift", args.get(1))? & 31;
                 Ok(vec![LuaValue::Number((x >> n) as f64)])
             }
@@ -9718,30 +9746,32 @@ impl<'a> LuaState<'a> {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "rol", args.first())?;
                 let n = lua_value_to_u32_for_bitop(inv, 2, "rol", args.get(1))? & 31;
-                Ok(vec![LuaValue::Number(x.rotate_left(n) as i32 as f64)])
+                let rotated = x.rotate_left(n).cast_signed();
+                Ok(vec![LuaValue::Number(f64::from(rotated))])
             }
             "bit.ror" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "ror", args.first())?;
                 let n = lua_value_to_u32_for_bitop(inv, 2, "ror", args.get(1))? & 31;
-                Ok(vec![LuaValue::Number(x.rotate_right(n) as i32 as f64)])
+                let rotated = x.rotate_right(n).cast_signed();
+                Ok(vec![LuaValue::Number(f64::from(rotated))])
             }
             "bit.bswap" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "bswap", args.first())?;
-                Ok(vec![LuaValue::Number(x.swap_bytes() as i32 as f64)])
+                Ok(vec![LuaValue::Number(x.swap_bytes().cast_signed() as f64)])
             }
             "bit.tobit" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "tobit", args.first())?;
-                Ok(vec![LuaValue::Number(x as i32 as f64)])
+                Ok(vec![LuaValue::Number(x.cast_signed() as f64)])
             }
             "bit.tohex" => {
                 let inv = self.current_invocation_name.as_deref();
                 let x = lua_value_to_u32_for_bitop(inv, 1, "tohex", args.first())?;
                 // Second arg (optional): digit count; negative = upper case.
                 let n = match args.get(1) {
-                    Some(v) => lua_value_to_u32_for_bitop(inv, 2, "tohex", Some(v))? as i32,
+                    Some(v) => lua_value_to_u32_for_bitop(inv, 2, "tohex", Some(v))?.cast_signed(),
                     None => 8,
                 };
                 let abs_n = n.unsigned_abs().min(8) as usize;
@@ -10002,6 +10032,46 @@ impl<'a> LuaState<'a> {
             }
         }
 
+        // Scripts look commands up in the same re-keyed table clients do
+        // (upstream rename-command edits server.commands in place): a
+        // replacement name reaches its canonical command, and a renamed or
+        // disabled original is unknown.
+        match self.store.command_renames.resolve(&argv[0]) {
+            CommandRename::Alias(canonical) => argv[0] = canonical.to_vec(),
+            CommandRename::Hidden => {
+                return arg_error("Unknown Redis command called from script", is_pcall);
+            }
+            CommandRename::Unchanged => {}
+        }
+
+        // On one shard of a split keyspace a script may only touch keys that
+        // shard owns, declared or not (upstream scriptVerifyClusterState).
+        if let Some((shard, shards)) = self.store.script_key_shard
+            && command_key_indexes(&argv)
+                .into_iter()
+                .filter_map(|idx| argv.get(idx))
+                .any(|key| usize::from(crc16_slot(key)) % shards != shard)
+        {
+            return arg_error(
+                "Script attempted to access a non local key in a cluster node script",
+                is_pcall,
+            );
+        }
+
+        // A verbatim-propagated script is replayed by re-running it, so a
+        // write must not depend on a nondeterministic read made earlier in the
+        // same run (pre-7.0 luaRedisGenericCommand's lua_random_dirty gate).
+        // Effects propagation ships the resolved writes instead, so the
+        // flags are only consulted in verbatim mode.
+        if !self.store.script_replicate_effects {
+            let writes = command_writes_or_may_replicate_in_readonly_script(&argv);
+            if writes && self.random_dirty {
+                return arg_error(SCRIPT_NONDETERMINISTIC_WRITE_ERROR, is_pcall);
+            }
+            self.write_dirty |= writes;
+            self.random_dirty |= command_has_nondeterministic_output(&argv);
+        }
+
         let dirty_before = self.store.dirty;
         // (frankenredis-vr8rg) Dispatch the command with the script's RESP
         // version so handlers materialize RESP3 frames (Double/Map/Set/Null/
@@ -10009,6 +10079,11 @@ impl<'a> LuaState<'a> {
         // afterward so the script's own reply to the client is unaffected.
         let saved_resp_version = self.store.dispatch_client_ctx.resp_protocol_version;
         self.store.dispatch_client_ctx.resp_protocol_version = self.resp_version;
+        // Upstream runs script calls with CLIENT_DENY_BLOCKING, so a blocking
+        // command answers from its immediate path (BLPOP on an empty list is
+        // nil) instead of waiting.
+        let saved_deny_blocking =
+            std::mem::replace(&mut self.store.dispatch_client_ctx.deny_blocking, true);
         let command_result = if let Some(intercepted) = script_command_intercept(&argv) {
             intercepted
         } else {
@@ -10034,6 +10109,7 @@ impl<'a> LuaState<'a> {
             }
         };
         self.store.dispatch_client_ctx.resp_protocol_version = saved_resp_version;
+        self.store.dispatch_client_ctx.deny_blocking = saved_deny_blocking;
 
         match command_result {
             Ok(frame) => {
@@ -10044,7 +10120,9 @@ impl<'a> LuaState<'a> {
                 // MONITOR clients are attached.
                 self.store.record_script_monitor(&argv);
                 let dirty_after = self.store.dirty;
-                if dirty_after > dirty_before || command_may_propagate_from_script(&argv) {
+                if self.store.script_replicate_effects
+                    && (dirty_after > dirty_before || command_may_propagate_from_script(&argv))
+                {
                     // (frankenredis-x1225) Record the DETERMINISTIC effect form so
                     // a script's XADD `*` / SPOP / INCRBYFLOAT (etc.) propagates a
                     // concrete command, not the non-deterministic one — otherwise
@@ -11212,11 +11290,12 @@ fn resp_to_lua(frame: &RespFrame, resp3: bool) -> LuaValue {
         // conversion. No 7.2 command returns an attribute reply through
         // redis.call, so this is a defensive arm. (frankenredis-01weh)
         RespFrame::Attribute(pairs) => resp_to_lua(&RespFrame::Map(Some(pairs.clone())), resp3),
-        RespFrame::SimpleString(s) => {
+        RespFrame::SimpleString(_) | RespFrame::StaticSimple(_) => {
+            let text = frame.as_simple_str().unwrap_or_default();
             let t = LuaTable::new();
             t.set(
                 LuaValue::Str(b"ok".to_vec()),
-                LuaValue::Str(s.as_bytes().to_vec()),
+                LuaValue::Str(text.as_bytes().to_vec()),
             );
             LuaValue::Table(t)
         }
@@ -11307,6 +11386,8 @@ fn resp_to_lua(frame: &RespFrame, resp3: bool) -> LuaValue {
             );
             LuaValue::Table(t)
         }
+        // `RespFrame` is non-exhaustive; a kind added later reads as a null.
+        _ => null(),
     }
 }
 
@@ -11318,7 +11399,7 @@ pub fn lua_to_resp(val: &LuaValue, resp3: bool) -> RespFrame {
         // RESP3 `#t`/`#f` that downgrades to `:1`/`:0` for a RESP2 client. In the
         // default RESP2 script the historical mapping holds: true -> :1, false ->
         // nil. The RespFrame::Bool RESP2 downgrade happens in
-        // downconvert_lua_reply_to_resp2.
+        // downconvert_resp3_reply_to_resp2.
         LuaValue::Bool(b) if resp3 => RespFrame::Bool(*b),
         LuaValue::Bool(true) => RespFrame::Integer(1),
         LuaValue::Bool(false) => RespFrame::BulkString(None),
@@ -11406,9 +11487,7 @@ pub fn lua_to_resp(val: &LuaValue, resp3: bool) -> RespFrame {
                     if matches!(value, LuaValue::Nil) {
                         continue;
                     }
-                    items.push(RespFrame::Integer(
-                        i64::try_from(idx + 1).unwrap_or(i64::MAX),
-                    ));
+                    items.push(RespFrame::Integer(reply_len(idx + 1)));
                 }
                 // String hash keys.
                 for k in inner_borrow.string_hash.keys() {
@@ -11438,7 +11517,7 @@ pub fn lua_to_resp(val: &LuaValue, resp3: bool) -> RespFrame {
 
             // {big_number = "..."}: upstream luaReplyToRedisReply emits a
             // RESP3 Big Number (`(<digits>\r\n`), downconverted to a bulk
-            // string under RESP2 (handled in downconvert_lua_reply_to_resp2).
+            // string under RESP2 (handled in downconvert_resp3_reply_to_resp2).
             // It also maps CR/LF to spaces before writing the line-based frame.
             // (frankenredis-h2uga, frankenredis-sg1nm)
             let bn_field = t.get(&LuaValue::Str(b"big_number".to_vec()));
@@ -12115,7 +12194,7 @@ fn lua_fmt_g(n: f64, prec: usize, upper: bool) -> String {
     }
     let abs = n.abs();
     let exp = abs.log10().floor() as i32;
-    if exp < -4 || exp >= prec as i32 {
+    if exp < -4 || exp >= i32::try_from(prec).unwrap_or(i32::MAX) {
         // (frankenredis-u5qgq follow-up) C's %g also strips trailing
         // zeros from the scientific notation mantissa — '1.00000e-05'
         // collapses to '1e-05'. lua_fmt_scientific keeps the zeros for
@@ -12136,7 +12215,7 @@ fn lua_fmt_g(n: f64, prec: usize, upper: bool) -> String {
             s
         }
     } else {
-        let decimal_prec = (prec as i32 - 1 - exp).max(0) as usize;
+        let decimal_prec = (i32::try_from(prec).unwrap_or(i32::MAX) - 1 - exp).max(0) as usize;
         let s = format!("{n:.decimal_prec$}");
         // Remove trailing zeros after decimal point
         if s.contains('.') {
@@ -12279,7 +12358,7 @@ fn cmsgpack_table_shape(table: &LuaTable) -> (Option<Vec<LuaValue>>, Vec<(LuaVal
         if matches!(value, LuaValue::Nil) {
             continue;
         }
-        let key = (idx + 1) as i64;
+        let key = reply_len(idx + 1);
         numeric_values.insert(key, value.clone());
         count += 1;
         max = max.max(key);
@@ -12423,7 +12502,7 @@ impl<'a> MsgpackCursor<'a> {
         let tag = self.read_u8()?;
         match tag {
             0x00..=0x7f => Ok(LuaValue::Number(tag as f64)),
-            0xe0..=0xff => Ok(LuaValue::Number((tag as i8) as f64)),
+            0xe0..=0xff => Ok(LuaValue::Number(tag.cast_signed() as f64)),
             0xc0 => Ok(LuaValue::Nil),
             0xc2 => Ok(LuaValue::Bool(false)),
             0xc3 => Ok(LuaValue::Bool(true)),
@@ -12431,10 +12510,10 @@ impl<'a> MsgpackCursor<'a> {
             0xcd => Ok(LuaValue::Number(self.read_u16()? as f64)),
             0xce => Ok(LuaValue::Number(self.read_u32()? as f64)),
             0xcf => Ok(LuaValue::Number(self.read_u64()? as f64)),
-            0xd0 => Ok(LuaValue::Number((self.read_u8()? as i8) as f64)),
-            0xd1 => Ok(LuaValue::Number((self.read_u16()? as i16) as f64)),
-            0xd2 => Ok(LuaValue::Number((self.read_u32()? as i32) as f64)),
-            0xd3 => Ok(LuaValue::Number((self.read_u64()? as i64) as f64)),
+            0xd0 => Ok(LuaValue::Number(self.read_u8()?.cast_signed() as f64)),
+            0xd1 => Ok(LuaValue::Number(self.read_u16()?.cast_signed() as f64)),
+            0xd2 => Ok(LuaValue::Number(self.read_u32()?.cast_signed() as f64)),
+            0xd3 => Ok(LuaValue::Number(self.read_u64()?.cast_signed() as f64)),
             0xca => Ok(LuaValue::Number(f32::from_bits(self.read_u32()?) as f64)),
             0xcb => Ok(LuaValue::Number(f64::from_bits(self.read_u64()?))),
             0xd9 => {
@@ -12998,12 +13077,12 @@ fn lua_struct_get_integer(bytes: &[u8], endian: LuaStructEndian, is_signed: bool
         return 0.0;
     }
     if bit_count == 64 {
-        return (value as i64) as f64;
+        return value.cast_signed() as f64;
     }
     let sign_bit = 1_u64 << (bit_count - 1);
     if value & sign_bit != 0 {
         let mask = (!0_u64) << bit_count;
-        (value | mask) as i64 as f64
+        (value | mask).cast_signed() as f64
     } else {
         value as f64
     }
@@ -13163,8 +13242,8 @@ fn lua_value_to_json(val: &LuaValue) -> Result<String, String> {
             }
 
             // Tally positive-integer keys vs total keys.
-            let mut max_int_key: i64 = array_len as i64;
-            let mut int_key_count: i64 = array_len as i64;
+            let mut max_int_key: i64 = reply_len(array_len);
+            let mut int_key_count: i64 = reply_len(array_len);
             let mut has_non_int_key = false;
             for (k, _) in &hash_pairs {
                 match k {
@@ -13206,7 +13285,7 @@ fn lua_value_to_json(val: &LuaValue) -> Result<String, String> {
                     std::collections::HashMap::new();
                 let inner = t.inner.borrow();
                 for (i, v) in inner.array.iter().enumerate() {
-                    by_idx.insert((i + 1) as i64, v.clone());
+                    by_idx.insert(reply_len(i + 1), v.clone());
                 }
                 drop(inner);
                 for (k, v) in &hash_pairs {
@@ -13598,12 +13677,11 @@ impl<'a> JsonParser<'a> {
         // the nearest f64, ties-to-even — but skips dec2flt, which a perf-record put at ~13% of
         // cjson.decode. The `i != 0` guard preserves -0.0 for the "-0" token (i64 parse drops the
         // sign), and an i64 overflow on a huge integer simply falls through to the float parser.
-        if is_integer {
-            if let Ok(i) = text.parse::<i64>() {
-                if i != 0 {
-                    return Ok(i as f64);
-                }
-            }
+        if is_integer
+            && let Ok(i) = text.parse::<i64>()
+            && i != 0
+        {
+            return Ok(i as f64);
         }
         text.parse::<f64>().map_err(|_| {
             format!(
@@ -13716,6 +13794,8 @@ pub(crate) fn compile_lua_chunk_cached(script: &[u8]) -> Result<Rc<Block>, Strin
 
 // ── Public entry point ──────────────────────────────────────────────────
 
+/// Run `script` with its effects propagated, as FCALL does regardless of
+/// [`Store::script_verbatim_propagation`].
 pub fn eval_script(
     script: &[u8],
     keys: &[Vec<u8>],
@@ -13724,7 +13804,7 @@ pub fn eval_script(
     now_ms: u64,
 ) -> Result<RespFrame, String> {
     let compiled = compile_lua_chunk_cached(script)?;
-    eval_compiled_script(compiled, keys, argv, store, now_ms)
+    eval_compiled_script_inner(compiled, keys, argv, store, now_ms, false, true)
 }
 
 #[doc(hidden)]
@@ -13736,9 +13816,10 @@ pub fn eval_script_cloned_globals_for_bench(
     now_ms: u64,
 ) -> Result<RespFrame, String> {
     let compiled = compile_lua_chunk_cached(script)?;
-    eval_compiled_script_inner(compiled, keys, argv, store, now_ms, true)
+    eval_compiled_script_inner(compiled, keys, argv, store, now_ms, true, true)
 }
 
+/// The EVAL/EVALSHA entry point: verbatim propagation applies here only.
 pub(crate) fn eval_compiled_script(
     compiled: Rc<Block>,
     keys: &[Vec<u8>],
@@ -13746,7 +13827,16 @@ pub(crate) fn eval_compiled_script(
     store: &mut Store,
     now_ms: u64,
 ) -> Result<RespFrame, String> {
-    eval_compiled_script_inner(compiled, keys, argv, store, now_ms, false)
+    let replicate_effects = !store.script_verbatim_propagation();
+    eval_compiled_script_inner(
+        compiled,
+        keys,
+        argv,
+        store,
+        now_ms,
+        false,
+        replicate_effects,
+    )
 }
 
 fn eval_compiled_script_inner(
@@ -13756,9 +13846,11 @@ fn eval_compiled_script_inner(
     store: &mut Store,
     now_ms: u64,
     clone_globals_template: bool,
+    replicate_effects: bool,
 ) -> Result<RespFrame, String> {
     store.clear_script_propagation_state();
     store.script_propagation_mode = SCRIPT_PROPAGATE_ALL;
+    store.script_replicate_effects = replicate_effects;
     // (frankenredis-qqq17) Break any Rc cycles this script allocates when it
     // returns. Declared before `state` so the LuaState/Env drop first (reverse
     // declaration order), leaving only leaked cycle islands for the sweep.
@@ -13801,55 +13893,11 @@ fn eval_compiled_script_inner(
     let frame = if store.dispatch_client_ctx.resp_protocol_version == 3 {
         frame
     } else {
-        downconvert_lua_reply_to_resp2(frame)
+        downconvert_resp3_reply_to_resp2(frame)
     };
     Ok(frame)
 }
 
-/// Walk a RESP frame tree and rewrite RESP3-only shapes into their
-/// RESP2 equivalents (Map → flat 2N Array). Applied to Lua reply
-/// frames before they leave eval_script when the calling client is on
-/// RESP2. (frankenredis-luaresp2map)
-///
-/// General RESP3→RESP2 downconverter — also reused for SENTINEL replies,
-/// which upstream builds with `addReplyMapLen` (a flat array in RESP2).
-pub(crate) fn downconvert_lua_reply_to_resp2(frame: RespFrame) -> RespFrame {
-    match frame {
-        RespFrame::Map(Some(entries)) => {
-            let mut flat = Vec::with_capacity(entries.len() * 2);
-            for (k, v) in entries {
-                flat.push(downconvert_lua_reply_to_resp2(k));
-                flat.push(downconvert_lua_reply_to_resp2(v));
-            }
-            RespFrame::Array(Some(flat))
-        }
-        RespFrame::Map(None) => RespFrame::Array(None),
-        RespFrame::Array(Some(items)) => RespFrame::Array(Some(
-            items
-                .into_iter()
-                .map(downconvert_lua_reply_to_resp2)
-                .collect(),
-        )),
-        RespFrame::Push(items) => RespFrame::Array(Some(
-            items
-                .into_iter()
-                .map(downconvert_lua_reply_to_resp2)
-                .collect(),
-        )),
-        // RESP2 has no Double type; upstream addReplyDouble emits the
-        // d2string text as a bulk string. The Double frame already carries
-        // that exact text. (frankenredis-aae3d)
-        RespFrame::Double(s) => RespFrame::BulkString(Some(s.into_bytes())),
-        // RESP2 has no Big Number type; upstream emits the digits as a bulk
-        // string. (frankenredis-h2uga)
-        RespFrame::BigNumber(s) => RespFrame::BulkString(Some(s.into_bytes())),
-        // RESP2 has no Boolean type; upstream addReplyBool downgrades to the
-        // integer `:1` / `:0`. (frankenredis-0gz4g)
-        RespFrame::Bool(b) => RespFrame::Integer(i64::from(b)),
-        other => other,
-    }
-}
-
 /// Lex+parse a script body without executing it. Returns the parser's
 /// error message verbatim on failure. Mirrors the shebang-stripping
 /// performed by `eval_script` so SCRIPT LOAD validates the same source
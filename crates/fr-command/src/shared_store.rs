@@ -0,0 +1,437 @@
+//! A sharded [`Store`] handle that tokio tasks dispatch commands through.
+//!
+//! `SharedStore` splits the keyspace into shards. Each shard is a separate
+//! [`Store`] behind its own `tokio::sync::Mutex`, and the handle is a cheap
+//! clone of the shard list. [`SharedStore::dispatch`] locks only the shard
+//! that owns the command's keys, so commands on different shards run in
+//! parallel.
+//!
+//! Routing follows Redis Cluster. A key belongs to shard
+//! `crc16_slot(key) % shards`, so keys sharing a `{hash tag}` share a shard.
+//! With more than one shard:
+//! - a command whose keys span hash slots fails with `CROSSSLOT`, as on a
+//!   cluster node;
+//! - keyless commands run on shard 0, except:
+//!   - DBSIZE, KEYS and INFO read every shard and merge the replies (INFO
+//!     sums each database's Keyspace line);
+//!   - FLUSHALL, FLUSHDB, SWAPDB, CONFIG SET/RESETSTAT, SCRIPT LOAD/FLUSH and
+//!     FUNCTION LOAD/DELETE/FLUSH/RESTORE run on every shard, so config and
+//!     scripts match wherever a later command lands;
+//!   - SCAN and RANDOMKEY are rejected, since their cursors and sampling are
+//!     per shard.
+//! - a script runs on the shard its declared keys route to, or shard 0 with
+//!   `numkeys` 0. A `redis.call` on a key another shard owns fails with the
+//!   cluster "non local key" error rather than touching the wrong shard.
+//!
+//! [`SharedStore::new`] builds a single shard. It accepts every command and
+//! serializes them, as the server's event loop does.
+//!
+//! Locking: the dispatching future never holds a shard lock across an
+//! `.await`.
+//! - A single-shard command awaits its lock, then runs synchronously within
+//!   the same poll.
+//! - A command that needs every shard try-locks them in ascending order. On
+//!   contention it releases everything it holds, waits for the busy shard
+//!   holding nothing, and retries.
+//! - A command whose keys hold [`DEFAULT_BLOCKING_THRESHOLD`] or more bytes or
+//!   elements runs on tokio's blocking pool. Its guards move into that task.
+//!   The threshold also applies to a KEYS or FLUSH over that many keys, and is
+//!   changed with [`SharedStore::with_blocking_threshold`].
+//!
+//! Cancellation: dropping a `dispatch` future never leaves a shard poisoned
+//! or a multi-key write half applied.
+//! - Dropped while waiting for a lock: nothing has run and nothing is held.
+//! - Once the locks are held, the command runs to completion in the same
+//!   poll, before any await point, so it cannot be dropped partway.
+//! - Dropped while a blocking-pool command runs: the command finishes on its
+//!   thread and releases its shards. Its reply is discarded.
+//!
+//! Only a command that panics poisons its shard. The store may be mid-write,
+//! so every later command on that shard panics too.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fr_protocol::RespFrame;
+use fr_store::{Store, crc16_slot};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::{CommandError, command_key_indexes, dispatch_argv, dispatch_argv_with_budget};
+
+/// Value bytes or collection elements (or keys, for a keyspace walk) at which
+/// a command leaves the async worker for `spawn_blocking`.
+pub const DEFAULT_BLOCKING_THRESHOLD: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+struct Shard {
+    store: Store,
+    /// Set while a command runs; still set afterwards only if it panicked.
+    in_command: bool,
+}
+
+type ShardGuard = OwnedMutexGuard<Shard>;
+
+/// Which shards a command locks, and how their replies combine.
+#[derive(Clone, Copy, Debug)]
+enum Route {
+    One(usize),
+    Every(Merge),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Merge {
+    /// Integer replies added up (DBSIZE).
+    Sum,
+    /// Array replies joined (KEYS).
+    Concat,
+    /// Shard 0's reply (FLUSHALL, CONFIG SET, ...).
+    First,
+    /// Shard 0's INFO with the Keyspace section summed over every shard.
+    Info,
+}
+
+#[derive(Clone, Debug)]
+pub struct SharedStore {
+    shards: Arc<[Arc<Mutex<Shard>>]>,
+    blocking_threshold: usize,
+}
+
+impl Default for SharedStore {
+    fn default() -> Self {
+        Self::new(Store::new())
+    }
+}
+
+impl SharedStore {
+    /// One shard holding `store`.
+    #[must_use]
+    pub fn new(store: Store) -> Self {
+        Self::from_stores(vec![store])
+    }
+
+    /// `shards` shards, each a store built by `make`.
+    ///
+    /// # Panics
+    /// If `shards` is 0.
+    #[must_use]
+    pub fn sharded(shards: usize, mut make: impl FnMut() -> Store) -> Self {
+        assert!(shards > 0, "SharedStore needs at least one shard");
+        Self::from_stores((0..shards).map(|_| make()).collect())
+    }
+
+    fn from_stores(stores: Vec<Store>) -> Self {
+        let count = stores.len();
+        Self {
+            shards: stores
+                .into_iter()
+                .enumerate()
+                .map(|(shard, mut store)| {
+                    if count > 1 {
+                        store.script_key_shard = Some((shard, count));
+                    }
+                    Arc::new(Mutex::new(Shard {
+                        store,
+                        in_command: false,
+                    }))
+                })
+                .collect(),
+            blocking_threshold: DEFAULT_BLOCKING_THRESHOLD,
+        }
+    }
+
+    /// Move commands of at least `threshold` bytes or elements to the blocking
+    /// pool; `usize::MAX` keeps every command on the calling task.
+    #[must_use]
+    pub fn with_blocking_threshold(mut self, threshold: usize) -> Self {
+        self.blocking_threshold = threshold;
+        self
+    }
+
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The shard that owns `key`.
+    #[must_use]
+    pub fn shard_of(&self, key: &[u8]) -> usize {
+        usize::from(crc16_slot(key)) % self.shards.len()
+    }
+
+    /// Run one command with [`dispatch_argv`] on the shards it routes to.
+    ///
+    /// Must be polled inside a tokio runtime when the command can reach the
+    /// blocking threshold.
+    pub async fn dispatch(
+        &self,
+        argv: Vec<Vec<u8>>,
+        now_ms: u64,
+    ) -> Result<RespFrame, CommandError> {
+        self.run(argv, now_ms, None).await
+    }
+
+    /// Like [`Self::dispatch`] with [`dispatch_argv_with_budget`], so an
+    /// expensive scan gives its shard back once `budget` has elapsed. A
+    /// command on every shard gets `budget` per shard.
+    pub async fn dispatch_with_budget(
+        &self,
+        argv: Vec<Vec<u8>>,
+        now_ms: u64,
+        budget: Duration,
+    ) -> Result<RespFrame, CommandError> {
+        self.run(argv, now_ms, Some(budget)).await
+    }
+
+    /// Run `f` with exclusive access to the shard that owns `key`, e.g. to
+    /// seed data or read several values consistently. `f` runs on the calling
+    /// task and must not block; a panic in it poisons the shard.
+    pub async fn with_shard<R>(&self, key: &[u8], f: impl FnOnce(&mut Store) -> R) -> R {
+        let mut guard = Arc::clone(&self.shards[self.shard_of(key)])
+            .lock_owned()
+            .await;
+        guarded(&mut guard, f)
+    }
+
+    async fn run(
+        &self,
+        argv: Vec<Vec<u8>>,
+        now_ms: u64,
+        budget: Option<Duration>,
+    ) -> Result<RespFrame, CommandError> {
+        let keys = command_key_indexes(&argv);
+        let route = self.route(&argv, &keys)?;
+        let mut guards = match route {
+            Route::One(shard) => vec![Arc::clone(&self.shards[shard]).lock_owned().await],
+            Route::Every(_) => self.lock_every_shard().await,
+        };
+        if cost(&guards, &argv, &keys, now_ms) < self.blocking_threshold {
+            return execute(&mut guards, route, &argv, now_ms, budget);
+        }
+        let task =
+            tokio::task::spawn_blocking(move || execute(&mut guards, route, &argv, now_ms, budget));
+        match task.await {
+            Ok(reply) => reply,
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            Err(err) => panic!("SharedStore blocking task did not run: {err}"),
+        }
+    }
+
+    fn route(&self, argv: &[Vec<u8>], keys: &[usize]) -> Result<Route, CommandError> {
+        if self.shards.len() == 1 {
+            return Ok(Route::One(0));
+        }
+        let mut slots = keys
+            .iter()
+            .filter_map(|&idx| argv.get(idx))
+            .map(|key| crc16_slot(key));
+        if let Some(slot) = slots.next() {
+            if slots.any(|other| other != slot) {
+                return Err(CommandError::Custom(
+                    "CROSSSLOT Keys in request don't hash to the same slot".to_string(),
+                ));
+            }
+            return Ok(Route::One(usize::from(slot) % self.shards.len()));
+        }
+        let name = argv.first().map_or(&[][..], Vec::as_slice);
+        let is = |command: &str| name.eq_ignore_ascii_case(command.as_bytes());
+        let sub = |subcommands: &[&str]| {
+            argv.get(1).is_some_and(|sub| {
+                subcommands
+                    .iter()
+                    .any(|command| sub.eq_ignore_ascii_case(command.as_bytes()))
+            })
+        };
+        if is("DBSIZE") {
+            Ok(Route::Every(Merge::Sum))
+        } else if is("KEYS") {
+            Ok(Route::Every(Merge::Concat))
+        } else if is("INFO") {
+            Ok(Route::Every(Merge::Info))
+        } else if is("FLUSHALL")
+            || is("FLUSHDB")
+            || is("SWAPDB")
+            || (is("CONFIG") && sub(&["SET", "RESETSTAT"]))
+            || (is("SCRIPT") && sub(&["LOAD", "FLUSH"]))
+            || (is("FUNCTION") && sub(&["LOAD", "DELETE", "FLUSH", "RESTORE"]))
+        {
+            Ok(Route::Every(Merge::First))
+        } else if is("SCAN") || is("RANDOMKEY") {
+            Err(CommandError::Custom(format!(
+                "ERR {} is not supported on a sharded store",
+                String::from_utf8_lossy(name).to_ascii_uppercase()
+            )))
+        } else {
+            Ok(Route::One(0))
+        }
+    }
+
+    /// Lock every shard in ascending order without awaiting while holding a
+    /// guard: on contention drop them all, wait for the busy shard, retry.
+    async fn lock_every_shard(&self) -> Vec<ShardGuard> {
+        loop {
+            let mut guards = Vec::with_capacity(self.shards.len());
+            let mut busy = None;
+            for shard in self.shards.iter() {
+                match Arc::clone(shard).try_lock_owned() {
+                    Ok(guard) => guards.push(guard),
+                    Err(_) => {
+                        busy = Some(shard);
+                        break;
+                    }
+                }
+            }
+            let Some(busy) = busy else {
+                return guards;
+            };
+            drop(guards);
+            drop(busy.lock().await);
+        }
+    }
+}
+
+/// Bytes or elements the command's keys hold, or the keys a keyspace walk
+/// visits, read without touching stats.
+fn cost(guards: &[ShardGuard], argv: &[Vec<u8>], keys: &[usize], now_ms: u64) -> usize {
+    if keys.is_empty() {
+        let walks_keyspace = argv.first().is_some_and(|name| {
+            name.eq_ignore_ascii_case(b"KEYS")
+                || name.eq_ignore_ascii_case(b"FLUSHALL")
+                || name.eq_ignore_ascii_case(b"FLUSHDB")
+        });
+        return if walks_keyspace {
+            guards.iter().map(|shard| shard.store.dbsize(now_ms)).sum()
+        } else {
+            0
+        };
+    }
+    let store = &guards[0].store;
+    keys.iter()
+        .filter_map(|&idx| argv.get(idx))
+        .map(|key| store.value_size_no_stats(key, now_ms))
+        .fold(0, usize::saturating_add)
+}
+
+/// Run the command on the locked shards. An all-shard command runs on shard 0
+/// first, so an argument error (or error reply) is returned before any other
+/// shard changes.
+fn execute(
+    guards: &mut [ShardGuard],
+    route: Route,
+    argv: &[Vec<u8>],
+    now_ms: u64,
+    budget: Option<Duration>,
+) -> Result<RespFrame, CommandError> {
+    let run = |shard: &mut Shard| {
+        guarded(shard, |store| match budget {
+            Some(budget) => dispatch_argv_with_budget(argv, store, now_ms, budget),
+            None => dispatch_argv(argv, store, now_ms),
+        })
+    };
+    let Route::Every(merge) = route else {
+        return run(&mut guards[0]);
+    };
+    let mut replies = Vec::with_capacity(guards.len());
+    for guard in guards.iter_mut() {
+        let reply = run(guard)?;
+        if matches!(reply, RespFrame::Error(_)) {
+            return Ok(reply);
+        }
+        replies.push(reply);
+    }
+    Ok(match merge {
+        Merge::Sum => RespFrame::Integer(
+            replies
+                .iter()
+                .map(|reply| match reply {
+                    RespFrame::Integer(n) => *n,
+                    _ => 0,
+                })
+                .sum(),
+        ),
+        Merge::Concat => RespFrame::Array(Some(
+            replies
+                .into_iter()
+                .flat_map(|reply| match reply {
+                    RespFrame::Array(Some(items)) => items,
+                    _ => Vec::new(),
+                })
+                .collect(),
+        )),
+        Merge::First => replies.swap_remove(0),
+        Merge::Info => merge_info(replies),
+    })
+}
+
+/// Shard 0's INFO text with each `dbN:keys=..,expires=..,avg_ttl=..` line
+/// replaced by the totals over every shard; `avg_ttl` is weighted by each
+/// shard's expires.
+fn merge_info(mut replies: Vec<RespFrame>) -> RespFrame {
+    let mut dbs: BTreeMap<usize, (u64, u64, u128)> = BTreeMap::new();
+    for reply in &replies {
+        let RespFrame::BulkString(Some(text)) = reply else {
+            continue;
+        };
+        for line in String::from_utf8_lossy(text).lines() {
+            let Some((db, keys, expires, avg_ttl)) = parse_keyspace_line(line) else {
+                continue;
+            };
+            let totals = dbs.entry(db).or_default();
+            totals.0 += keys;
+            totals.1 += expires;
+            totals.2 += u128::from(avg_ttl) * u128::from(expires);
+        }
+    }
+    let RespFrame::BulkString(Some(first)) = replies.swap_remove(0) else {
+        return replies.swap_remove(0);
+    };
+    let text = String::from_utf8_lossy(&first);
+    let mut merged = String::with_capacity(text.len());
+    for line in text.split_inclusive("\r\n") {
+        if parse_keyspace_line(line.trim_end()).is_some() {
+            continue;
+        }
+        merged.push_str(line);
+        if line.trim_end() == "# Keyspace" {
+            for (db, (keys, expires, ttl_sum)) in &dbs {
+                let avg_ttl = ttl_sum.checked_div(u128::from(*expires)).unwrap_or(0);
+                let _ = write!(
+                    merged,
+                    "db{db}:keys={keys},expires={expires},avg_ttl={avg_ttl}\r\n"
+                );
+            }
+        }
+    }
+    RespFrame::BulkString(Some(merged.into_bytes()))
+}
+
+/// `(db, keys, expires, avg_ttl)` from an INFO Keyspace line.
+fn parse_keyspace_line(line: &str) -> Option<(usize, u64, u64, u64)> {
+    let (db, fields) = line.strip_prefix("db")?.split_once(':')?;
+    let mut fields = fields.split(',');
+    let mut field = |name: &str| {
+        fields
+            .next()?
+            .strip_prefix(name)?
+            .strip_prefix('=')?
+            .parse::<u64>()
+            .ok()
+    };
+    let keys = field("keys")?;
+    let expires = field("expires")?;
+    let avg_ttl = field("avg_ttl")?;
+    Some((db.parse().ok()?, keys, expires, avg_ttl))
+}
+
+/// Run `f` on the shard's store, leaving the shard poisoned if `f` panics.
+fn guarded<R>(shard: &mut Shard, f: impl FnOnce(&mut Store) -> R) -> R {
+    assert!(
+        !shard.in_command,
+        "SharedStore poisoned: a command panicked mid-dispatch"
+    );
+    shard.in_command = true;
+    let out = f(&mut shard.store);
+    shard.in_command = false;
+    out
+}
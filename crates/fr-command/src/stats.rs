@@ -0,0 +1,251 @@
+//! Per-command call, error and latency counters for embedders.
+//!
+//! A [`StatsHandle`] is a cheaply cloned set of atomic counters, one slot per
+//! [`CommandId`]. Commands run through [`dispatch_argv_with_stats`] bump their
+//! slot with relaxed atomic adds; nothing on the dispatch path takes a lock,
+//! and plain [`dispatch_argv`] never touches a handle. A metrics thread can
+//! call [`StatsHandle::snapshot`] at any time:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use fr_command::dispatch_argv_with_stats;
+//! use fr_command::stats::StatsHandle;
+//! use fr_store::Store;
+//!
+//! let stats = StatsHandle::new();
+//! let mut store = Store::new();
+//! for argv in [&[&b"SET"[..], b"k", b"v"][..], &[b"GET", b"k"], &[b"INCR", b"k"]] {
+//!     let argv: Vec<Vec<u8>> = argv.iter().map(|a| a.to_vec()).collect();
+//!     let _ = dispatch_argv_with_stats(&argv, &mut store, 0, &stats);
+//! }
+//!
+//! let mut calls: HashMap<String, u64> = HashMap::new();
+//! for command in stats.snapshot().commands {
+//!     *calls.entry(command.name.to_owned()).or_default() += command.calls;
+//! }
+//! assert_eq!(calls["set"], 1);
+//! assert_eq!(calls["get"], 1);
+//! assert_eq!(stats.snapshot().get("incr").map(|c| c.errors), Some(1));
+//! ```
+//!
+//! `calls` and `errors` are exact. Reading the clock costs more than a PING,
+//! so only one call in [`TIMING_SAMPLE`] per command is timed: `usec` is an
+//! estimate, the time of those `timed_calls` scaled up to all calls.
+//!
+//! Counters from different slots are read one at a time, so a snapshot taken
+//! while commands run is not a single instant across commands. Unknown
+//! commands are not counted.
+//!
+//! With the `serde` feature, [`Snapshot`] implements `Serialize`.
+
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+use crate::{CommandError, CommandId, classify_command, dispatch_argv};
+
+/// One call in this many per command is timed for `usec`.
+pub const TIMING_SAMPLE: u64 = 64;
+
+const COMMAND_COUNT: usize = CommandId::ALL.len();
+
+#[derive(Debug, Default)]
+struct CommandCounters {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    timed_calls: AtomicU64,
+    timed_nanos: AtomicU64,
+}
+
+/// Shared handle to the counters; clones feed and read the same slots.
+#[derive(Clone, Debug)]
+pub struct StatsHandle {
+    counters: Arc<[CommandCounters; COMMAND_COUNT]>,
+}
+
+impl Default for StatsHandle {
+    fn default() -> Self {
+        Self {
+            counters: Arc::new(std::array::from_fn(|_| CommandCounters::default())),
+        }
+    }
+}
+
+impl StatsHandle {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counters for every command called at least once since creation or the
+    /// last [`reset`](Self::reset), in [`CommandId`] declaration order.
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        let commands = CommandId::ALL
+            .iter()
+            .zip(self.counters.iter())
+            .filter_map(|(&id, slot)| {
+                let calls = slot.calls.load(Ordering::Relaxed);
+                let timed_calls = slot.timed_calls.load(Ordering::Relaxed);
+                (calls > 0).then(|| CommandStats {
+                    id,
+                    name: command_name(id),
+                    calls,
+                    errors: slot.errors.load(Ordering::Relaxed),
+                    usec: estimated_usec(
+                        calls,
+                        timed_calls,
+                        slot.timed_nanos.load(Ordering::Relaxed),
+                    ),
+                    timed_calls,
+                })
+            })
+            .collect();
+        Snapshot { commands }
+    }
+
+    /// Zero every counter. Commands finishing concurrently may land either
+    /// side of the reset.
+    pub fn reset(&self) {
+        for slot in self.counters.iter() {
+            slot.calls.store(0, Ordering::Relaxed);
+            slot.errors.store(0, Ordering::Relaxed);
+            slot.timed_calls.store(0, Ordering::Relaxed);
+            slot.timed_nanos.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, id: CommandId) -> &CommandCounters {
+        &self.counters[id as usize]
+    }
+}
+
+/// Point-in-time copy of a [`StatsHandle`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Snapshot {
+    pub commands: Vec<CommandStats>,
+}
+
+impl Snapshot {
+    /// The entry for `name` (lowercase, as in INFO commandstats), if called.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&CommandStats> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+}
+
+/// Exact call and error totals for one command, with an estimated `usec`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CommandStats {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub id: CommandId,
+    /// Lowercase command name, e.g. `get` or `eval_ro`.
+    pub name: &'static str,
+    pub calls: u64,
+    /// Calls that replied with an error.
+    pub errors: u64,
+    /// Estimated total time spent in dispatch, in microseconds: the time of
+    /// the `timed_calls` scaled up to `calls`.
+    pub usec: u64,
+    /// Calls that were timed, one in [`TIMING_SAMPLE`]; the sample behind
+    /// `usec`.
+    pub timed_calls: u64,
+}
+
+/// Scale the timed sample to all `calls`.
+fn estimated_usec(calls: u64, timed_calls: u64, timed_nanos: u64) -> u64 {
+    if timed_calls == 0 {
+        return 0;
+    }
+    let nanos = u128::from(timed_nanos) * u128::from(calls) / u128::from(timed_calls);
+    u64::try_from(nanos / 1_000).unwrap_or(u64::MAX)
+}
+
+/// Lowercase name of `id`, derived from the variant name (`EvalRo` is `eval_ro`).
+fn command_name(id: CommandId) -> &'static str {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        CommandId::ALL
+            .iter()
+            .map(|id| {
+                let variant = format!("{id:?}");
+                match variant.strip_suffix("Ro") {
+                    Some(base) => format!("{}_ro", base.to_ascii_lowercase()),
+                    None => variant.to_ascii_lowercase(),
+                }
+            })
+            .collect()
+    });
+    &names[id as usize]
+}
+
+/// [`dispatch_argv`] that also counts the call and its outcome in `stats`,
+/// timing every [`TIMING_SAMPLE`]th call of each command. Known commands
+/// only; an unknown name is dispatched uncounted.
+pub fn dispatch_argv_with_stats(
+    argv: &[Vec<u8>],
+    store: &mut Store,
+    now_ms: u64,
+    stats: &StatsHandle,
+) -> Result<RespFrame, CommandError> {
+    let Some(id) = argv.first().and_then(|name| classify_command(name)) else {
+        return dispatch_argv(argv, store, now_ms);
+    };
+    let slot = stats.slot(id);
+    let timed = slot
+        .calls
+        .fetch_add(1, Ordering::Relaxed)
+        .is_multiple_of(TIMING_SAMPLE);
+    let reply = if timed {
+        let started = Instant::now();
+        let reply = dispatch_argv(argv, store, now_ms);
+        let nanos = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        slot.timed_calls.fetch_add(1, Ordering::Relaxed);
+        slot.timed_nanos.fetch_add(nanos, Ordering::Relaxed);
+        reply
+    } else {
+        dispatch_argv(argv, store, now_ms)
+    };
+    if matches!(reply, Err(_) | Ok(RespFrame::Error(_))) {
+        slot.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_name, estimated_usec};
+    use crate::{CommandId, classify_command_name};
+
+    #[test]
+    fn all_commands_is_indexed_by_discriminant() {
+        for (index, &id) in CommandId::ALL.iter().enumerate() {
+            assert_eq!(id as usize, index, "{id:?}");
+        }
+    }
+
+    #[test]
+    fn every_command_name_classifies_back_to_its_id() {
+        for &id in CommandId::ALL {
+            let name = command_name(id);
+            assert_eq!(classify_command_name(name.as_bytes()), Some(id), "{name}");
+        }
+        assert_eq!(command_name(CommandId::EvalshaRo), "evalsha_ro");
+    }
+
+    #[test]
+    fn usec_scales_the_timed_sample() {
+        assert_eq!(estimated_usec(0, 0, 0), 0);
+        assert_eq!(estimated_usec(5, 0, 0), 0);
+        assert_eq!(estimated_usec(1, 1, 2_500), 2);
+        assert_eq!(estimated_usec(128, 2, 4_000), 256);
+        assert_eq!(estimated_usec(u64::MAX, 1, u64::MAX), u64::MAX);
+    }
+}
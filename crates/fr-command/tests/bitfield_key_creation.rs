@@ -0,0 +1,239 @@
+//! Which bit commands create or grow the string they address.
+//!
+//! Reads (BITFIELD GET, BITFIELD_RO, GETBIT) treat a missing key or an offset
+//! past the end as zero bits and never touch the keyspace. Writes (BITFIELD
+//! SET/INCRBY, SETBIT) create the key and zero-extend it only up to the byte
+//! holding the highest written bit. Offsets follow getBitOffsetFromArgument:
+//! `#N` means N times the field width and is only accepted by BITFIELD.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run;
+
+fn ints(values: &[i64]) -> RespFrame {
+    RespFrame::Array(Some(
+        values.iter().copied().map(RespFrame::Integer).collect(),
+    ))
+}
+
+fn strlen(store: &mut Store, key: &[u8]) -> RespFrame {
+    run(store, &[b"STRLEN", key])
+}
+
+fn exists(store: &mut Store, key: &[u8]) -> RespFrame {
+    run(store, &[b"EXISTS", key])
+}
+
+#[test]
+fn reads_never_create_or_extend_the_key() {
+    let mut store = Store::new();
+    let dirty = store.dirty;
+    assert_eq!(
+        run(&mut store, &[b"BITFIELD", b"k", b"GET", b"u16", b"100"]),
+        ints(&[0])
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"GET", b"i8", b"#7", b"OVERFLOW", b"SAT"]
+        ),
+        ints(&[0])
+    );
+    assert_eq!(
+        run(&mut store, &[b"BITFIELD_RO", b"k", b"GET", b"u8", b"#4"]),
+        ints(&[0])
+    );
+    assert_eq!(
+        run(&mut store, &[b"GETBIT", b"k", b"4000"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(exists(&mut store, b"k"), RespFrame::Integer(0));
+    assert_eq!(store.dirty, dirty);
+
+    // An existing string keeps its length when read past the end.
+    run(&mut store, &[b"SET", b"k", b"\xff"]);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"GET", b"u8", b"0", b"GET", b"u8", b"#9"]
+        ),
+        ints(&[255, 0])
+    );
+    assert_eq!(
+        run(&mut store, &[b"GETBIT", b"k", b"800"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(1));
+}
+
+#[test]
+fn writes_extend_only_to_the_highest_written_byte() {
+    let mut store = Store::new();
+    // GET beyond the end, then a SET whose field ends in byte 2.
+    assert_eq!(
+        run(
+            &mut store,
+            &[
+                b"BITFIELD",
+                b"k",
+                b"GET",
+                b"u8",
+                b"#40",
+                b"SET",
+                b"u8",
+                b"#2",
+                b"7"
+            ]
+        ),
+        ints(&[0, 0])
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(3));
+    assert_eq!(
+        run(&mut store, &[b"GET", b"k"]),
+        RespFrame::BulkString(Some(vec![0, 0, 7]))
+    );
+
+    // Reading further out still leaves the length alone.
+    assert_eq!(
+        run(&mut store, &[b"BITFIELD", b"k", b"GET", b"u32", b"#10"]),
+        ints(&[0])
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(3));
+
+    // An unaligned 4-bit INCRBY at bit 38 spans bytes 4 and 5.
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"INCRBY", b"u4", b"38", b"3"]
+        ),
+        ints(&[3])
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(6));
+    // `#N` scales by the field width: u4 #12 is bit 48, byte 6.
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"SET", b"u4", b"#12", b"15"]
+        ),
+        ints(&[0])
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(7));
+    assert_eq!(
+        run(&mut store, &[b"BITFIELD", b"k", b"GET", b"u4", b"48"]),
+        ints(&[15])
+    );
+
+    // A write that fails on OVERFLOW FAIL still grows the key, as upstream
+    // sizes the string before applying any subcommand.
+    assert_eq!(
+        run(
+            &mut store,
+            &[
+                b"BITFIELD",
+                b"k",
+                b"OVERFLOW",
+                b"FAIL",
+                b"SET",
+                b"u8",
+                b"#9",
+                b"300"
+            ]
+        ),
+        RespFrame::Array(Some(vec![RespFrame::BulkString(None)]))
+    );
+    assert_eq!(strlen(&mut store, b"k"), RespFrame::Integer(10));
+
+    // SETBIT creates and grows; GETBIT beyond the end does not.
+    assert_eq!(
+        run(&mut store, &[b"SETBIT", b"b", b"17", b"1"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(strlen(&mut store, b"b"), RespFrame::Integer(3));
+    assert_eq!(
+        run(&mut store, &[b"GETBIT", b"b", b"1000"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(strlen(&mut store, b"b"), RespFrame::Integer(3));
+    assert_eq!(
+        run(&mut store, &[b"SETBIT", b"b", b"1000", b"0"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(strlen(&mut store, b"b"), RespFrame::Integer(126));
+}
+
+#[test]
+fn offsets_follow_get_bit_offset_from_argument() {
+    let mut store = Store::new();
+    let bad_offset =
+        RespFrame::Error("ERR bit offset is not an integer or out of range".to_string());
+    for offset in [
+        &b"+5"[..],
+        b"05",
+        b" 5",
+        b"-1",
+        b"#-1",
+        b"#",
+        b"##1",
+        b"#+1",
+        b"#01",
+        b"4294967296",
+        b"#536870912",
+        b"#9223372036854775807",
+    ] {
+        for command in [&b"BITFIELD"[..], b"BITFIELD_RO"] {
+            assert_eq!(
+                run(&mut store, &[command, b"k", b"GET", b"u8", offset]),
+                bad_offset,
+                "{command:?} {offset:?}"
+            );
+        }
+        assert_eq!(
+            run(
+                &mut store,
+                &[b"BITFIELD", b"k", b"SET", b"u8", offset, b"1"]
+            ),
+            bad_offset,
+            "SET {offset:?}"
+        );
+    }
+    // The hash form is BITFIELD-only.
+    assert_eq!(run(&mut store, &[b"GETBIT", b"k", b"#1"]), bad_offset);
+    assert_eq!(run(&mut store, &[b"SETBIT", b"k", b"#1", b"1"]), bad_offset);
+    assert_eq!(run(&mut store, &[b"GETBIT", b"k", b"+1"]), bad_offset);
+    assert_eq!(exists(&mut store, b"k"), RespFrame::Integer(0));
+
+    // The largest in-range offsets are accepted.
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"GET", b"u8", b"#536870911"]
+        ),
+        ints(&[0])
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"BITFIELD", b"k", b"GET", b"i64", b"4294967295"]
+        ),
+        ints(&[0])
+    );
+    assert_eq!(
+        run(&mut store, &[b"GETBIT", b"k", b"4294967295"]),
+        RespFrame::Integer(0)
+    );
+
+    // The cap follows proto-max-bulk-len.
+    store.proto_max_bulk_len = 1;
+    assert_eq!(
+        run(&mut store, &[b"BITFIELD", b"k", b"GET", b"u8", b"#1"]),
+        bad_offset
+    );
+    assert_eq!(run(&mut store, &[b"GETBIT", b"k", b"8"]), bad_offset);
+    assert_eq!(
+        run(&mut store, &[b"SETBIT", b"k", b"7", b"1"]),
+        RespFrame::Integer(0)
+    );
+}
@@ -0,0 +1,141 @@
+//! BITFIELD overflow control at the edges of every width.
+//!
+//! `OVERFLOW WRAP|SAT|FAIL` applies to every SET / INCRBY after it in the
+//! same command and resets to WRAP for the next command. FAIL answers nil for
+//! just the op that overflowed and leaves its field untouched, so the rest of
+//! the command still runs.
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::NOW;
+
+/// `BITFIELD k <ops>`, with the ops given as one space-separated string.
+fn bitfield(store: &mut Store, ops: &str) -> RespFrame {
+    let argv: Vec<Vec<u8>> = ["BITFIELD", "k"]
+        .into_iter()
+        .chain(ops.split_whitespace())
+        .map(|arg| arg.as_bytes().to_vec())
+        .collect();
+    dispatch_argv(&argv, store, NOW).unwrap_or_else(|err| err.to_resp())
+}
+
+fn replies(values: &[Option<i64>]) -> RespFrame {
+    RespFrame::Array(Some(
+        values
+            .iter()
+            .map(|value| match value {
+                Some(value) => RespFrame::Integer(*value),
+                None => RespFrame::BulkString(None),
+            })
+            .collect(),
+    ))
+}
+
+#[test]
+fn overflow_mode_is_sticky_within_one_command_only() {
+    let mut store = Store::new();
+    assert_eq!(
+        bitfield(
+            &mut store,
+            "OVERFLOW SAT INCRBY u8 100 200 INCRBY u8 100 200"
+        ),
+        replies(&[Some(200), Some(255)])
+    );
+    // A new command starts over in WRAP.
+    assert_eq!(bitfield(&mut store, "INCRBY u8 100 1"), replies(&[Some(0)]));
+    // The last OVERFLOW seen wins for the ops after it.
+    assert_eq!(
+        bitfield(
+            &mut store,
+            "OVERFLOW FAIL INCRBY u8 100 -1 OVERFLOW SAT INCRBY u8 100 -1"
+        ),
+        replies(&[None, Some(0)])
+    );
+}
+
+#[test]
+fn u63_saturates_wraps_and_fails_at_both_ends() {
+    let mut store = Store::new();
+    assert_eq!(
+        bitfield(
+            &mut store,
+            "OVERFLOW SAT INCRBY u63 0 9223372036854775807 INCRBY u63 0 1"
+        ),
+        replies(&[Some(i64::MAX), Some(i64::MAX)])
+    );
+    assert_eq!(
+        bitfield(&mut store, "OVERFLOW FAIL INCRBY u63 0 1"),
+        replies(&[None])
+    );
+    assert_eq!(bitfield(&mut store, "INCRBY u63 0 1"), replies(&[Some(0)]));
+    assert_eq!(
+        bitfield(&mut store, "OVERFLOW SAT INCRBY u63 0 -1"),
+        replies(&[Some(0)])
+    );
+    assert_eq!(
+        bitfield(&mut store, "INCRBY u63 0 -1"),
+        replies(&[Some(i64::MAX)])
+    );
+}
+
+#[test]
+fn i8_negative_overflow_wraps_or_saturates() {
+    let mut store = Store::new();
+    bitfield(&mut store, "SET i8 0 -100");
+    assert_eq!(
+        bitfield(&mut store, "INCRBY i8 0 -100"),
+        replies(&[Some(56)])
+    );
+
+    bitfield(&mut store, "SET i8 0 -100");
+    assert_eq!(
+        bitfield(&mut store, "OVERFLOW SAT INCRBY i8 0 -100"),
+        replies(&[Some(-128)])
+    );
+    assert_eq!(
+        bitfield(&mut store, "OVERFLOW FAIL INCRBY i8 0 -1"),
+        replies(&[None])
+    );
+    assert_eq!(bitfield(&mut store, "GET i8 0"), replies(&[Some(-128)]));
+}
+
+#[test]
+fn i64_overflow_past_the_native_range() {
+    let mut store = Store::new();
+    bitfield(&mut store, "SET i64 0 9223372036854775807");
+    assert_eq!(
+        bitfield(&mut store, "OVERFLOW SAT INCRBY i64 0 1"),
+        replies(&[Some(i64::MAX)])
+    );
+    assert_eq!(
+        bitfield(&mut store, "INCRBY i64 0 1"),
+        replies(&[Some(i64::MIN)])
+    );
+    assert_eq!(
+        bitfield(
+            &mut store,
+            "OVERFLOW SAT INCRBY i64 0 -1 OVERFLOW FAIL INCRBY i64 0 -1"
+        ),
+        replies(&[Some(i64::MIN), None])
+    );
+}
+
+#[test]
+fn only_the_failing_op_in_a_mixed_sequence_replies_nil() {
+    let mut store = Store::new();
+    assert_eq!(
+        bitfield(
+            &mut store,
+            "OVERFLOW FAIL SET u8 0 250 INCRBY u8 0 10 GET u8 0 INCRBY u8 8 10 GET u8 8"
+        ),
+        replies(&[Some(0), None, Some(250), Some(10), Some(10)])
+    );
+    assert_eq!(
+        bitfield(&mut store, "GET u16 0"),
+        replies(&[Some(250 * 256 + 10)])
+    );
+}
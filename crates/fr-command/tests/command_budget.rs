@@ -0,0 +1,254 @@
+//! `dispatch_argv_with_budget`: expensive commands give up once their
+//! wall-clock allowance is spent instead of running to completion.
+//!
+//! A zero budget trips at the first checkpoint, so every scan below aborts
+//! before producing a reply. Reads must leave the data they scanned intact and
+//! writes must not land at all; fast commands and generous budgets behave
+//! exactly like plain `dispatch_argv`.
+
+use std::time::{Duration, Instant};
+
+use fr_command::{CommandError, dispatch_argv, dispatch_argv_with_budget};
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv, run};
+
+const ITEMS: usize = 50_000;
+/// Far above what an aborted command needs, far below an unbudgeted LCS.
+const TIMELY: Duration = Duration::from_secs(2);
+
+fn run_budgeted(
+    store: &mut Store,
+    parts: &[&[u8]],
+    budget: Duration,
+) -> Result<RespFrame, CommandError> {
+    dispatch_argv_with_budget(&argv(parts), store, NOW, budget)
+}
+
+/// Run `parts` with a zero budget and assert it aborts promptly.
+fn assert_aborts(store: &mut Store, parts: &[&[u8]]) {
+    let started = Instant::now();
+    let result = run_budgeted(store, parts, Duration::ZERO);
+    assert_eq!(result, Err(CommandError::BudgetExceeded), "{parts:?}");
+    assert!(
+        started.elapsed() < TIMELY,
+        "{parts:?} took {:?}",
+        started.elapsed()
+    );
+}
+
+fn members(prefix: &str, range: std::ops::Range<usize>) -> Vec<Vec<u8>> {
+    range.map(|i| format!("{prefix}{i}").into_bytes()).collect()
+}
+
+fn push_all(store: &mut Store, head: &[&[u8]], items: &[Vec<u8>]) {
+    let mut full: Vec<Vec<u8>> = argv(head);
+    full.extend(items.iter().cloned());
+    dispatch_argv(&full, store, NOW).expect("populate");
+}
+
+#[test]
+fn budget_exceeded_renders_as_an_error_reply() {
+    assert_eq!(
+        CommandError::BudgetExceeded.to_resp(),
+        RespFrame::Error("ERR command exceeded its time budget".to_string())
+    );
+}
+
+#[test]
+fn fast_commands_ignore_a_spent_budget() {
+    let mut store = Store::new();
+    assert_eq!(
+        run_budgeted(&mut store, &[b"SET", b"k", b"v"], Duration::ZERO),
+        Ok(RespFrame::SimpleString("OK".to_string()))
+    );
+    assert_eq!(
+        run_budgeted(&mut store, &[b"GET", b"k"], Duration::ZERO),
+        Ok(RespFrame::BulkString(Some(b"v".to_vec())))
+    );
+}
+
+#[test]
+fn keys_aborts_and_the_budget_is_lifted_afterwards() {
+    let mut store = Store::new();
+    let mut mset = vec![b"MSET".to_vec()];
+    for key in members("key:", 0..ITEMS) {
+        mset.push(key);
+        mset.push(b"v".to_vec());
+    }
+    dispatch_argv(&mset, &mut store, NOW).expect("MSET");
+
+    assert_aborts(&mut store, &[b"KEYS", b"*"]);
+    assert_aborts(&mut store, &[b"KEYS", b"key:*"]);
+
+    // The next command runs without a budget and sees every key.
+    let RespFrame::Array(Some(keys)) = run(&mut store, &[b"KEYS", b"*"]) else {
+        panic!("KEYS reply");
+    };
+    assert_eq!(keys.len(), ITEMS);
+}
+
+#[test]
+fn set_intersections_abort_and_sinterstore_writes_nothing() {
+    let mut store = Store::new();
+    push_all(&mut store, &[b"SADD", b"a"], &members("m", 0..ITEMS));
+    push_all(
+        &mut store,
+        &[b"SADD", b"b"],
+        &members("m", ITEMS / 2..ITEMS * 2),
+    );
+    run(&mut store, &[b"SET", b"dest", b"untouched"]);
+
+    assert_aborts(&mut store, &[b"SINTER", b"a", b"b"]);
+    assert_aborts(&mut store, &[b"SINTERCARD", b"2", b"a", b"b"]);
+    assert_aborts(&mut store, &[b"SINTERSTORE", b"dest", b"a", b"b"]);
+
+    assert_eq!(
+        run(&mut store, &[b"GET", b"dest"]),
+        RespFrame::BulkString(Some(b"untouched".to_vec()))
+    );
+    let card = i64::try_from(ITEMS / 2).unwrap();
+    assert_eq!(
+        run(&mut store, &[b"SINTERCARD", b"2", b"a", b"b"]),
+        RespFrame::Integer(card)
+    );
+}
+
+#[test]
+fn sort_aborts_and_sort_store_writes_nothing() {
+    let mut store = Store::new();
+    let values: Vec<Vec<u8>> = (0..ITEMS)
+        .map(|i| ((i * 7919) % ITEMS).to_string().into_bytes())
+        .collect();
+    push_all(&mut store, &[b"RPUSH", b"list"], &values);
+
+    assert_aborts(&mut store, &[b"SORT", b"list"]);
+    assert_aborts(&mut store, &[b"SORT", b"list", b"ALPHA"]);
+    assert_aborts(&mut store, &[b"SORT", b"list", b"STORE", b"sorted"]);
+
+    assert_eq!(
+        run(&mut store, &[b"EXISTS", b"sorted"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(
+        run(&mut store, &[b"LLEN", b"list"]),
+        RespFrame::Integer(i64::try_from(ITEMS).unwrap())
+    );
+}
+
+#[test]
+fn zset_score_ranges_abort_and_zremrangebyscore_removes_nothing() {
+    let mut store = Store::new();
+    let mut zadd = vec![b"ZADD".to_vec(), b"z".to_vec()];
+    for i in 0..ITEMS {
+        zadd.push(i.to_string().into_bytes());
+        zadd.push(format!("m{i}").into_bytes());
+    }
+    dispatch_argv(&zadd, &mut store, NOW).expect("ZADD");
+
+    assert_aborts(&mut store, &[b"ZRANGEBYSCORE", b"z", b"-inf", b"+inf"]);
+    assert_aborts(
+        &mut store,
+        &[b"ZREVRANGEBYSCORE", b"z", b"+inf", b"-inf", b"WITHSCORES"],
+    );
+    assert_aborts(&mut store, &[b"ZREMRANGEBYSCORE", b"z", b"-inf", b"+inf"]);
+
+    assert_eq!(
+        run(&mut store, &[b"ZCARD", b"z"]),
+        RespFrame::Integer(i64::try_from(ITEMS).unwrap())
+    );
+}
+
+#[test]
+fn bitcount_aborts_on_a_large_bitmap() {
+    let mut store = Store::new();
+    let bitmap = vec![0xA5_u8; 8 * 1024 * 1024];
+    run(&mut store, &[b"SET", b"bits", &bitmap]);
+
+    assert_aborts(&mut store, &[b"BITCOUNT", b"bits"]);
+    assert_aborts(&mut store, &[b"BITCOUNT", b"bits", b"1", b"-2", b"BIT"]);
+    assert_eq!(
+        run(&mut store, &[b"BITCOUNT", b"bits"]),
+        RespFrame::Integer(i64::try_from(bitmap.len() * 4).unwrap())
+    );
+}
+
+#[test]
+fn lcs_stops_long_before_the_full_dynamic_program() {
+    let mut store = Store::new();
+    // 40k x 40k cells: far longer than TIMELY to finish, so returning at all
+    // shows the DP checks the clock as it goes.
+    let a: Vec<u8> = (0..40_000_u32).map(|i| b"ACGT"[(i % 4) as usize]).collect();
+    let b: Vec<u8> = (0..40_000_u32)
+        .map(|i| b"ACGT"[((i * 7) % 4) as usize])
+        .collect();
+    run(&mut store, &[b"SET", b"a", &a]);
+    run(&mut store, &[b"SET", b"b", &b]);
+
+    let started = Instant::now();
+    let result = run_budgeted(
+        &mut store,
+        &[b"LCS", b"a", b"b", b"LEN"],
+        Duration::from_millis(20),
+    );
+    assert_eq!(result, Err(CommandError::BudgetExceeded));
+    assert!(started.elapsed() < TIMELY, "took {:?}", started.elapsed());
+
+    run(&mut store, &[b"SET", b"short", b"ohmytext"]);
+    run(&mut store, &[b"SET", b"other", b"mynewtext"]);
+    assert_aborts(&mut store, &[b"LCS", b"a", b"short"]);
+    assert_eq!(
+        run_budgeted(
+            &mut store,
+            &[b"LCS", b"short", b"other"],
+            Duration::from_secs(60)
+        ),
+        Ok(RespFrame::BulkString(Some(b"mytext".to_vec())))
+    );
+}
+
+#[test]
+fn a_generous_budget_matches_plain_dispatch() {
+    let mut store = Store::new();
+    let values: Vec<Vec<u8>> = (0..ITEMS)
+        .map(|i| ((i * 7919) % ITEMS).to_string().into_bytes())
+        .collect();
+    push_all(&mut store, &[b"RPUSH", b"list"], &values);
+    push_all(&mut store, &[b"SADD", b"a"], &members("m", 0..ITEMS));
+    push_all(
+        &mut store,
+        &[b"SADD", b"b"],
+        &members("m", ITEMS / 2..ITEMS * 2),
+    );
+
+    let generous = Duration::from_secs(600);
+    for command in [
+        &[&b"SORT"[..], b"list"][..],
+        &[b"SORT", b"list", b"DESC", b"ALPHA"],
+        &[b"SORT", b"list", b"LIMIT", b"10", b"20"],
+        &[b"SINTER", b"a", b"b"],
+        &[b"KEYS", b"*"],
+    ] {
+        let expected = run(&mut store, command);
+        assert_eq!(
+            run_budgeted(&mut store, command, generous),
+            Ok(expected),
+            "{command:?}"
+        );
+    }
+    assert_eq!(
+        run_budgeted(
+            &mut store,
+            &[b"SORT", b"list", b"STORE", b"sorted"],
+            generous
+        ),
+        Ok(RespFrame::Integer(i64::try_from(ITEMS).unwrap()))
+    );
+    assert_eq!(
+        run(&mut store, &[b"LINDEX", b"sorted", b"-1"]),
+        RespFrame::BulkString(Some((ITEMS - 1).to_string().into_bytes()))
+    );
+}
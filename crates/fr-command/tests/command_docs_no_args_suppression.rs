@@ -55,8 +55,25 @@ fn command_docs_containers_omit_arguments_field() {
     // because subcommand handlers route the args themselves) and so
     // emit no `arguments` field at all.
     for cmd in [
-        "DEBUG", "CLUSTER", "CONFIG", "CLIENT", "FUNCTION", "LATENCY", "MEMORY", "MODULE",
-        "OBJECT", "PUBSUB", "SCRIPT", "SLOWLOG", "ACL", "XGROUP", "XINFO",
+        "DEBUG",
+        "CLUSTER",
+        "CONFIG",
+        "CLIENT",
+        #[cfg(feature = "scripting")]
+        "FUNCTION",
+        "LATENCY",
+        "MEMORY",
+        "MODULE",
+        "OBJECT",
+        "PUBSUB",
+        #[cfg(feature = "scripting")]
+        "SCRIPT",
+        "SLOWLOG",
+        "ACL",
+        #[cfg(feature = "streams")]
+        "XGROUP",
+        #[cfg(feature = "streams")]
+        "XINFO",
     ] {
         let out = run(cmd);
         let kv = kv(&out);
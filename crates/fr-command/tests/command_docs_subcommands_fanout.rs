@@ -105,10 +105,14 @@ fn command_docs_subcommand_counts_match_subcommand_table() {
         ("COMMAND", 7),
         ("ACL", 13),
         ("OBJECT", 5),
+        #[cfg(feature = "streams")]
         ("XGROUP", 6),
+        #[cfg(feature = "streams")]
         ("XINFO", 4),
         ("MEMORY", 6),
+        #[cfg(feature = "scripting")]
         ("SCRIPT", 6),
+        #[cfg(feature = "scripting")]
         ("FUNCTION", 9),
         ("LATENCY", 7),
         ("SLOWLOG", 4),
@@ -0,0 +1,127 @@
+//! `dispatch_argv_with_stats` counts every call of a known command and its
+//! error replies exactly, samples latency into `usec`, and shares one set of
+//! counters between clones of a `StatsHandle`.
+
+use std::thread;
+
+use fr_command::dispatch_argv_with_stats;
+use fr_command::stats::{StatsHandle, TIMING_SAMPLE};
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+fn run(store: &mut Store, stats: &StatsHandle, parts: &[&[u8]]) -> RespFrame {
+    let argv: Vec<Vec<u8>> = parts.iter().map(|part| part.to_vec()).collect();
+    dispatch_argv_with_stats(&argv, store, 0, stats).unwrap_or_else(|err| err.to_resp())
+}
+
+fn counts(stats: &StatsHandle, name: &str) -> Option<(u64, u64)> {
+    stats
+        .snapshot()
+        .get(name)
+        .map(|command| (command.calls, command.errors))
+}
+
+#[test]
+fn calls_and_errors_are_counted_per_command() {
+    let stats = StatsHandle::new();
+    let mut store = Store::new();
+    run(&mut store, &stats, &[b"SET", b"k", b"v"]);
+    run(&mut store, &stats, &[b"get", b"k"]);
+    run(&mut store, &stats, &[b"GET", b"k"]);
+    run(&mut store, &stats, &[b"INCR", b"k"]);
+    run(&mut store, &stats, &[b"GET"]);
+
+    assert_eq!(counts(&stats, "set"), Some((1, 0)));
+    assert_eq!(counts(&stats, "get"), Some((3, 1)));
+    assert_eq!(counts(&stats, "incr"), Some((1, 1)));
+    assert_eq!(counts(&stats, "del"), None);
+    let names: Vec<&str> = stats.snapshot().commands.iter().map(|c| c.name).collect();
+    assert_eq!(names, ["set", "get", "incr"]);
+}
+
+#[test]
+fn unknown_commands_are_not_counted() {
+    let stats = StatsHandle::new();
+    let mut store = Store::new();
+    assert!(matches!(
+        run(&mut store, &stats, &[b"NOSUCHCOMMAND"]),
+        RespFrame::Error(_)
+    ));
+    assert!(stats.snapshot().commands.is_empty());
+}
+
+#[test]
+fn reset_zeroes_every_counter_seen_through_any_clone() {
+    let stats = StatsHandle::new();
+    let reader = stats.clone();
+    let mut store = Store::new();
+    run(&mut store, &stats, &[b"PING"]);
+    assert_eq!(counts(&reader, "ping"), Some((1, 0)));
+    reader.reset();
+    assert!(stats.snapshot().commands.is_empty());
+    run(&mut store, &stats, &[b"PING"]);
+    assert_eq!(counts(&reader, "ping"), Some((1, 0)));
+}
+
+#[test]
+fn usec_is_scaled_from_the_timed_calls() {
+    let stats = StatsHandle::new();
+    let mut store = Store::new();
+    for i in 0..2_000 {
+        let member = i.to_string();
+        run(&mut store, &stats, &[b"SADD", b"s", member.as_bytes()]);
+    }
+    let calls = TIMING_SAMPLE * 4;
+    for _ in 0..calls {
+        run(&mut store, &stats, &[b"SMEMBERS", b"s"]);
+    }
+    let snapshot = stats.snapshot();
+    let smembers = snapshot.get("smembers").expect("counted");
+    assert_eq!(smembers.calls, calls);
+    assert_eq!(smembers.timed_calls, 4);
+    // Each SMEMBERS of 2000 members takes well over a microsecond.
+    assert!(smembers.usec >= calls, "{smembers:?}");
+}
+
+#[test]
+fn concurrent_dispatchers_lose_no_counts() {
+    const THREADS: u64 = 4;
+    const CALLS: u64 = 5_000;
+    let stats = StatsHandle::new();
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let stats = stats.clone();
+            thread::spawn(move || {
+                let mut store = Store::new();
+                for _ in 0..CALLS {
+                    run(&mut store, &stats, &[b"INCR", b"n"]);
+                    run(&mut store, &stats, &[b"LPOP", b"n"]);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker");
+    }
+    assert_eq!(counts(&stats, "incr"), Some((THREADS * CALLS, 0)));
+    assert_eq!(
+        counts(&stats, "lpop"),
+        Some((THREADS * CALLS, THREADS * CALLS))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_serializes_by_command_name() {
+    let stats = StatsHandle::new();
+    let mut store = Store::new();
+    run(&mut store, &stats, &[b"PING"]);
+    let json = serde_json::to_value(stats.snapshot()).expect("serialize");
+    let ping = &json["commands"][0];
+    assert_eq!(ping["name"], "ping");
+    assert_eq!(ping["calls"], 1);
+    assert_eq!(ping["errors"], 0);
+    assert!(ping["usec"].is_u64());
+    assert_eq!(ping["timed_calls"], 1);
+    assert_eq!(ping.as_object().map(|fields| fields.len()), Some(5));
+}
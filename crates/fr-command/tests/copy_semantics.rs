@@ -0,0 +1,166 @@
+//! COPY within one database: the copy carries the source's absolute expiry,
+//! REPLACE decides whether an existing destination is overwritten, and the
+//! copy of every aggregate type (stream consumer groups included) is a deep
+//! clone that later writes to the source never reach.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, int, run_str as run};
+
+fn bulks(values: &[&str]) -> RespFrame {
+    RespFrame::Array(Some(
+        values
+            .iter()
+            .map(|v| RespFrame::BulkString(Some(v.as_bytes().to_vec())))
+            .collect(),
+    ))
+}
+
+#[cfg(feature = "streams")]
+fn array_len(frame: RespFrame) -> usize {
+    match frame {
+        RespFrame::Array(Some(items)) => items.len(),
+        other => panic!("expected an array, got {other:?}"),
+    }
+}
+
+#[test]
+fn copy_keeps_the_absolute_expiry() {
+    let mut store = Store::new();
+    let deadline = (NOW + 5_000).to_string();
+    run(&mut store, &["SET", "src", "v"]);
+    assert_eq!(run(&mut store, &["PEXPIREAT", "src", &deadline]), int(1));
+    assert_eq!(run(&mut store, &["COPY", "src", "dst"]), int(1));
+    assert_eq!(run(&mut store, &["PTTL", "dst"]), int(5_000));
+    assert_eq!(
+        run(&mut store, &["PEXPIRETIME", "dst"]),
+        int((NOW + 5_000).cast_signed())
+    );
+
+    // Aggregates carry it too.
+    run(&mut store, &["RPUSH", "list", "a"]);
+    run(&mut store, &["PEXPIREAT", "list", &deadline]);
+    assert_eq!(run(&mut store, &["COPY", "list", "list2"]), int(1));
+    assert_eq!(run(&mut store, &["PTTL", "list2"]), int(5_000));
+}
+
+#[test]
+fn replace_decides_whether_an_existing_destination_is_overwritten() {
+    let mut store = Store::new();
+    run(&mut store, &["SET", "src", "new"]);
+    run(&mut store, &["SADD", "dst", "old"]);
+    run(&mut store, &["EXPIRE", "dst", "100"]);
+
+    assert_eq!(run(&mut store, &["COPY", "src", "dst"]), int(0));
+    assert_eq!(
+        run(&mut store, &["TYPE", "dst"]),
+        RespFrame::SimpleString("set".to_string())
+    );
+
+    assert_eq!(run(&mut store, &["COPY", "src", "dst", "REPLACE"]), int(1));
+    assert_eq!(
+        run(&mut store, &["GET", "dst"]),
+        RespFrame::BulkString(Some(b"new".to_vec()))
+    );
+    // The source had no TTL, so neither does the overwritten destination.
+    assert_eq!(run(&mut store, &["PTTL", "dst"]), int(-1));
+
+    // DB is parsed like REPLACE, in any order.
+    assert_eq!(
+        run(&mut store, &["COPY", "src", "dst", "REPLACE", "DB", "0"]),
+        int(1)
+    );
+    assert_eq!(
+        run(&mut store, &["COPY", "src", "dst", "DB", "0", "REPLACE"]),
+        int(1)
+    );
+}
+
+#[test]
+fn copied_aggregates_are_independent_of_the_source() {
+    let mut store = Store::new();
+    run(&mut store, &["ZADD", "z", "1", "a", "2", "b"]);
+    run(&mut store, &["RPUSH", "l", "a", "b"]);
+    run(&mut store, &["HSET", "h", "f", "1"]);
+    run(&mut store, &["SADD", "s", "a"]);
+    for key in ["z", "l", "h", "s"] {
+        let copy = format!("{key}:copy");
+        assert_eq!(run(&mut store, &["COPY", key, &copy]), int(1));
+    }
+
+    run(&mut store, &["ZADD", "z", "10", "a", "3", "c"]);
+    run(&mut store, &["ZREM", "z", "b"]);
+    run(&mut store, &["LPUSH", "l", "x"]);
+    run(&mut store, &["LSET", "l", "1", "y"]);
+    run(&mut store, &["HSET", "h", "f", "2", "g", "3"]);
+    run(&mut store, &["SADD", "s", "b"]);
+
+    assert_eq!(
+        run(&mut store, &["ZRANGE", "z:copy", "0", "-1", "WITHSCORES"]),
+        bulks(&["a", "1", "b", "2"])
+    );
+    assert_eq!(
+        run(&mut store, &["ZRANGE", "z", "0", "-1", "WITHSCORES"]),
+        bulks(&["c", "3", "a", "10"])
+    );
+    assert_eq!(
+        run(&mut store, &["LRANGE", "l:copy", "0", "-1"]),
+        bulks(&["a", "b"])
+    );
+    assert_eq!(run(&mut store, &["HGETALL", "h:copy"]), bulks(&["f", "1"]));
+    assert_eq!(run(&mut store, &["SMEMBERS", "s:copy"]), bulks(&["a"]));
+}
+
+#[cfg(feature = "streams")]
+#[test]
+fn copied_stream_owns_its_consumer_groups() {
+    let mut store = Store::new();
+    run(&mut store, &["XADD", "st", "1-0", "f", "v"]);
+    run(&mut store, &["XADD", "st", "2-0", "f", "v"]);
+    run(&mut store, &["XGROUP", "CREATE", "st", "g", "0"]);
+    run(
+        &mut store,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "c",
+            "COUNT",
+            "1",
+            "STREAMS",
+            "st",
+            ">",
+        ],
+    );
+    assert_eq!(run(&mut store, &["COPY", "st", "st2"]), int(1));
+
+    // The copy starts with the same pending entry.
+    let pending = |store: &mut Store, key: &str| {
+        array_len(run(store, &["XPENDING", key, "g", "-", "+", "10"]))
+    };
+    assert_eq!(pending(&mut store, "st2"), 1);
+
+    // Acking and reading on the source leaves the copy's group alone.
+    assert_eq!(run(&mut store, &["XACK", "st", "g", "1-0"]), int(1));
+    run(
+        &mut store,
+        &["XREADGROUP", "GROUP", "g", "c", "STREAMS", "st", ">"],
+    );
+    run(&mut store, &["XADD", "st", "3-0", "f", "v"]);
+    assert_eq!(pending(&mut store, "st"), 1);
+    assert_eq!(pending(&mut store, "st2"), 1);
+    assert_eq!(run(&mut store, &["XLEN", "st2"]), int(2));
+
+    // The copy's group still has 2-0 left to deliver.
+    assert_eq!(
+        array_len(run(
+            &mut store,
+            &["XREADGROUP", "GROUP", "g", "c", "STREAMS", "st2", ">"],
+        )),
+        1
+    );
+    assert_eq!(pending(&mut store, "st2"), 2);
+}
@@ -0,0 +1,80 @@
+//! `max-elements-per-call`: a ZADD, SADD or HSET/HMSET adding more elements
+//! than the limit is rejected with an error naming the command, the count and
+//! the limit, and leaves the key untouched. 0 (the default) means no limit.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run_str as run;
+
+fn over_limit(command: &str, elements: usize, limit: usize) -> RespFrame {
+    RespFrame::Error(format!(
+        "ERR {command} would add {elements} elements, over the max-elements-per-call limit of {limit}"
+    ))
+}
+
+#[test]
+fn bulk_adds_over_the_limit_are_rejected_before_any_write() {
+    let mut store = Store::new();
+    store.set_max_elements_per_call(2);
+
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]),
+        over_limit("ZADD", 3, 2)
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["ZADD", "z", "NX", "CH", "1", "a", "2", "b", "3", "c"]
+        ),
+        over_limit("ZADD", 3, 2)
+    );
+    assert_eq!(
+        run(&mut store, &["SADD", "s", "a", "b", "c"]),
+        over_limit("SADD", 3, 2)
+    );
+    assert_eq!(
+        run(&mut store, &["HSET", "h", "f1", "v", "f2", "v", "f3", "v"]),
+        over_limit("HSET", 3, 2)
+    );
+    assert_eq!(
+        run(&mut store, &["HMSET", "h", "f1", "v", "f2", "v", "f3", "v"]),
+        over_limit("HMSET", 3, 2)
+    );
+    assert_eq!(
+        run(&mut store, &["EXISTS", "z", "s", "h"]),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn bulk_adds_at_the_limit_and_without_one_succeed() {
+    let mut store = Store::new();
+    store.set_max_elements_per_call(2);
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "1", "a", "2", "b"]),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(
+        run(&mut store, &["SADD", "s", "a", "b"]),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(
+        run(&mut store, &["HSET", "h", "f1", "v", "f2", "v"]),
+        RespFrame::Integer(2)
+    );
+
+    // Syntax errors keep priority over the limit.
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "1", "a", "2", "b", "3"]),
+        RespFrame::Error("ERR syntax error".to_string())
+    );
+
+    store.set_max_elements_per_call(0);
+    assert_eq!(
+        run(&mut store, &["SADD", "s", "c", "d", "e"]),
+        RespFrame::Integer(3)
+    );
+}
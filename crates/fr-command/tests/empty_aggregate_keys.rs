@@ -0,0 +1,99 @@
+//! The command that removes the last element of a hash, list, set or sorted
+//! set deletes the key; a stream emptied by XDEL/XTRIM stays.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run;
+
+type Argv = &'static [&'static [u8]];
+
+/// (seeding command, command removing the only element of `k`)
+const REMOVALS: &[(Argv, Argv)] = &[
+    (&[b"HSET", b"k", b"f", b"v"], &[b"HDEL", b"k", b"f"]),
+    (&[b"RPUSH", b"k", b"a"], &[b"LPOP", b"k"]),
+    (&[b"RPUSH", b"k", b"a"], &[b"RPOP", b"k", b"5"]),
+    (&[b"RPUSH", b"k", b"a"], &[b"LREM", b"k", b"0", b"a"]),
+    (&[b"RPUSH", b"k", b"a"], &[b"LTRIM", b"k", b"1", b"0"]),
+    (&[b"RPUSH", b"k", b"a"], &[b"LMPOP", b"1", b"k", b"LEFT"]),
+    (
+        &[b"RPUSH", b"k", b"a"],
+        &[b"LMOVE", b"k", b"dst", b"LEFT", b"RIGHT"],
+    ),
+    (&[b"RPUSH", b"k", b"a"], &[b"RPOPLPUSH", b"k", b"dst"]),
+    (&[b"SADD", b"k", b"m"], &[b"SREM", b"k", b"m"]),
+    (&[b"SADD", b"k", b"1"], &[b"SPOP", b"k"]),
+    (&[b"SADD", b"k", b"m"], &[b"SPOP", b"k", b"5"]),
+    (&[b"SADD", b"k", b"m"], &[b"SMOVE", b"k", b"dst", b"m"]),
+    (&[b"ZADD", b"k", b"1", b"m"], &[b"ZREM", b"k", b"m"]),
+    (&[b"ZADD", b"k", b"1", b"m"], &[b"ZPOPMIN", b"k"]),
+    (&[b"ZADD", b"k", b"1", b"m"], &[b"ZPOPMAX", b"k", b"5"]),
+    (
+        &[b"ZADD", b"k", b"1", b"m"],
+        &[b"ZMPOP", b"1", b"k", b"MIN"],
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"m"],
+        &[b"ZREMRANGEBYRANK", b"k", b"0", b"-1"],
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"m"],
+        &[b"ZREMRANGEBYSCORE", b"k", b"-inf", b"+inf"],
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"m"],
+        &[b"ZREMRANGEBYLEX", b"k", b"-", b"+"],
+    ),
+];
+
+fn text(argv: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&argv.join(&b' ')).into_owned()
+}
+
+#[test]
+fn removing_the_last_element_deletes_the_key() {
+    for (seed, remove) in REMOVALS {
+        let mut store = Store::new();
+        run(&mut store, seed);
+        let reply = run(&mut store, remove);
+        assert!(
+            !matches!(reply, RespFrame::Error(_)),
+            "{}: {reply:?}",
+            text(remove)
+        );
+        assert_eq!(
+            run(&mut store, &[b"EXISTS", b"k"]),
+            RespFrame::Integer(0),
+            "{} left an empty key",
+            text(remove)
+        );
+        assert_eq!(
+            run(&mut store, &[b"TYPE", b"k"]),
+            RespFrame::SimpleString("none".to_string()),
+            "{}",
+            text(remove)
+        );
+    }
+}
+
+#[cfg(feature = "streams")]
+#[test]
+fn an_emptied_stream_keeps_its_key() {
+    for remove in [
+        &[&b"XDEL"[..], b"k", b"1-1"][..],
+        &[b"XTRIM", b"k", b"MAXLEN", b"0"],
+    ] {
+        let mut store = Store::new();
+        run(&mut store, &[b"XADD", b"k", b"1-1", b"f", b"v"]);
+        assert_eq!(run(&mut store, remove), RespFrame::Integer(1));
+        assert_eq!(
+            run(&mut store, &[b"EXISTS", b"k"]),
+            RespFrame::Integer(1),
+            "{}",
+            text(remove)
+        );
+        assert_eq!(run(&mut store, &[b"XLEN", b"k"]), RespFrame::Integer(0));
+    }
+}
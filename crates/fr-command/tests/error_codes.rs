@@ -0,0 +1,138 @@
+//! `CommandError` and `StoreError` as library errors.
+//!
+//! Display is the canonical Redis reply text and `to_resp` is built from it,
+//! so embedders matching on `code()` see the same class a client would read
+//! off the wire. Every variant must report a non-empty code that its rendered
+//! message starts with.
+
+use std::error::Error;
+
+use fr_command::{CommandError, dispatch_argv};
+use fr_protocol::RespFrame;
+use fr_store::{Store, StoreError, StreamNameKind};
+
+fn store_errors() -> Vec<StoreError> {
+    vec![
+        StoreError::ValueNotInteger,
+        StoreError::HashValueNotInteger,
+        StoreError::ValueNotFloat,
+        StoreError::HashValueNotFloat,
+        StoreError::IncrFloatNaN,
+        StoreError::IntegerOverflow,
+        StoreError::KeyNotFound,
+        StoreError::WrongType,
+        StoreError::InvalidHllValue,
+        StoreError::CorruptedHllValue,
+        StoreError::IndexOutOfRange,
+        StoreError::InvalidDumpPayload,
+        StoreError::BusyKey,
+        StoreError::BudgetExceeded,
+        StoreError::EmptyStreamName(StreamNameKind::Group),
+        StoreError::EmptyStreamName(StreamNameKind::Consumer),
+        StoreError::GenericError("ERR no such key".to_string()),
+        StoreError::GenericError("BUSYGROUP Consumer Group name already exists".to_string()),
+    ]
+}
+
+fn command_errors() -> Vec<CommandError> {
+    let mut errors = vec![
+        CommandError::InvalidCommandFrame,
+        CommandError::InvalidUtf8Argument,
+        CommandError::UnknownCommand {
+            command: "nope".to_string(),
+            args_preview: Some("'a' ".to_string()),
+        },
+        CommandError::WrongArity("get"),
+        CommandError::WrongSubcommandArity {
+            command: "CONFIG",
+            subcommand: "GET".to_string(),
+        },
+        CommandError::UnknownSubcommand {
+            command: "CONFIG",
+            subcommand: "NOPE".to_string(),
+        },
+        CommandError::InvalidInteger,
+        CommandError::InvalidSlot,
+        CommandError::SyntaxError,
+        CommandError::NoSuchKey,
+        CommandError::BudgetExceeded,
+        CommandError::Custom("ERR custom failure".to_string()),
+        CommandError::Custom("NOGROUP No such key 's' or consumer group 'g'".to_string()),
+        CommandError::Custom("NOSCRIPT No matching script.".to_string()),
+        CommandError::Custom("OOM command not allowed when used memory > 'maxmemory'.".to_string()),
+        CommandError::Custom(
+            "EXECABORT Transaction discarded because of previous errors.".to_string(),
+        ),
+    ];
+    errors.extend(store_errors().into_iter().map(CommandError::Store));
+    errors
+}
+
+#[test]
+fn every_store_error_display_starts_with_its_code() {
+    for err in store_errors() {
+        let code = err.code();
+        let text = err.to_string();
+        assert!(!code.is_empty(), "{err:?}");
+        assert!(text.starts_with(code), "{err:?} rendered {text:?}");
+    }
+}
+
+#[test]
+fn every_command_error_display_starts_with_its_code() {
+    for err in command_errors() {
+        let code = err.code();
+        let text = err.to_string();
+        assert!(!code.is_empty(), "{err:?}");
+        assert!(text.starts_with(code), "{err:?} rendered {text:?}");
+        assert_eq!(err.to_resp(), RespFrame::Error(text), "{err:?}");
+    }
+}
+
+#[test]
+fn codes_name_the_error_class() {
+    assert_eq!(CommandError::SyntaxError.code(), "ERR");
+    assert_eq!(
+        CommandError::Store(StoreError::WrongType).code(),
+        "WRONGTYPE"
+    );
+    assert_eq!(
+        CommandError::Store(StoreError::CorruptedHllValue).code(),
+        "INVALIDOBJ"
+    );
+    assert_eq!(CommandError::Store(StoreError::BusyKey).code(), "BUSYKEY");
+    assert_eq!(
+        CommandError::Custom("NOGROUP No such consumer group".to_string()).code(),
+        "NOGROUP"
+    );
+    assert_eq!(
+        CommandError::Custom("NOSCRIPT No matching script.".to_string()).code(),
+        "NOSCRIPT"
+    );
+    // An unrecognised leading word is not an error class.
+    assert_eq!(
+        CommandError::Custom("bogus failure".to_string()).code(),
+        "ERR"
+    );
+}
+
+#[test]
+fn store_errors_are_exposed_as_the_source() {
+    let err = CommandError::Store(StoreError::WrongType);
+    let source = err.source().expect("store error is the source");
+    assert_eq!(source.to_string(), StoreError::WrongType.to_string());
+    assert!(CommandError::SyntaxError.source().is_none());
+}
+
+#[test]
+fn dispatch_errors_box_into_dyn_error() {
+    let mut store = Store::new();
+    let argv = vec![b"SET".to_vec(), b"k".to_vec(), b"v".to_vec()];
+    dispatch_argv(&argv, &mut store, 0).expect("SET succeeds");
+    let argv = vec![b"LPUSH".to_vec(), b"k".to_vec(), b"x".to_vec()];
+    let err: Box<dyn Error> = match dispatch_argv(&argv, &mut store, 0) {
+        Ok(reply) => panic!("LPUSH on a string replied {reply:?}"),
+        Err(err) => Box::new(err),
+    };
+    assert!(err.to_string().starts_with("WRONGTYPE "));
+}
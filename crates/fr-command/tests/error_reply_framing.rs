@@ -0,0 +1,80 @@
+//! Error replies that echo client-supplied bytes must stay valid RESP.
+//!
+//! A key named `a\r\nb` flows verbatim into the NOGROUP text of every
+//! consumer-group lookup error. Encoded under RESP2 or RESP3 the reply must be a
+//! single `-` simple error terminated by exactly one CRLF (upstream maps CR/LF
+//! to spaces), and must parse back as one frame consuming the whole buffer.
+
+#![cfg(feature = "streams")]
+
+use fr_command::dispatch_argv;
+use fr_protocol::{ParserConfig, RespFrame, parse_frame_with_config};
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::NOW;
+
+const KEY: &[u8] = b"a\r\nb";
+
+fn nogroup_reply(argv: &[&[u8]]) -> RespFrame {
+    let mut store = Store::new();
+    let argv: Vec<Vec<u8>> = argv.iter().map(|a| a.to_vec()).collect();
+    let reply = dispatch_argv(&argv, &mut store, NOW).unwrap_or_else(|err| err.to_resp());
+    let RespFrame::Error(msg) = &reply else {
+        panic!("expected NOGROUP error, got {reply:?}");
+    };
+    assert!(msg.starts_with("NOGROUP "), "{msg:?}");
+    assert!(
+        msg.contains("a\r\nb"),
+        "error must echo the raw key: {msg:?}"
+    );
+    reply
+}
+
+fn assert_single_simple_error(wire: &[u8], allow_resp3: bool) {
+    assert_eq!(wire[0], b'-', "{wire:?}");
+    assert_eq!(
+        wire.windows(2).filter(|w| w == b"\r\n").count(),
+        1,
+        "embedded CRLF leaked into {:?}",
+        String::from_utf8_lossy(wire)
+    );
+    assert!(wire.ends_with(b"\r\n"));
+
+    let config = ParserConfig {
+        allow_resp3,
+        ..ParserConfig::default()
+    };
+    let parsed = parse_frame_with_config(wire, &config).expect("reply must parse");
+    assert_eq!(parsed.consumed, wire.len());
+    let RespFrame::Error(text) = parsed.frame else {
+        panic!("reply must parse back as an error");
+    };
+    assert!(
+        text.contains("'a  b'"),
+        "CR/LF must map to spaces: {text:?}"
+    );
+}
+
+#[test]
+fn nogroup_error_with_crlf_key_is_valid_resp2_and_resp3() {
+    let commands: &[&[&[u8]]] = &[
+        &[b"XREADGROUP", b"GROUP", b"g", b"c", b"STREAMS", KEY, b">"],
+        &[b"XPENDING", KEY, b"g"],
+        &[b"XCLAIM", KEY, b"g", b"c", b"0", b"1-1"],
+        &[b"XAUTOCLAIM", KEY, b"g", b"c", b"0", b"0"],
+    ];
+    for argv in commands {
+        let reply = nogroup_reply(argv);
+
+        let mut resp2 = Vec::new();
+        reply.encode_into(&mut resp2);
+        assert_single_simple_error(&resp2, false);
+
+        let mut resp3 = Vec::new();
+        reply.encode_into_resp3(&mut resp3);
+        assert_single_simple_error(&resp3, true);
+        assert_eq!(resp2, resp3, "error framing must not depend on protocol");
+    }
+}
@@ -0,0 +1,201 @@
+//! EXPIRE-family NX/XX/GT/LT at the top of the deadline range.
+//!
+//! GT and LT compare the absolute deadline the key already has with the new
+//! one, for the relative commands as much as for EXPIREAT/PEXPIREAT. Deadlines
+//! one millisecond apart just under `i64::MAX` must still order correctly, and
+//! a deadline past `i64::MAX` is refused before any option is looked at.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, err, int, run_str as run};
+
+const MAX: i64 = i64::MAX;
+
+const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+
+const OPTIONS: [&str; 4] = ["NX", "XX", "GT", "LT"];
+
+fn invalid_expire(command: &str) -> RespFrame {
+    err(&format!("ERR invalid expire time in '{command}' command"))
+}
+
+/// `k` with an absolute deadline of `at` milliseconds.
+fn store_expiring_at(at: i64) -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["SET", "k", "v"]);
+    assert_eq!(
+        run(&mut store, &["PEXPIREAT", "k", &at.to_string()]),
+        int(1)
+    );
+    store
+}
+
+fn pexpiretime(store: &mut Store) -> RespFrame {
+    run(store, &["PEXPIRETIME", "k"])
+}
+
+#[test]
+fn absolute_deadlines_order_at_the_top_of_the_range() {
+    let (max, below) = (MAX.to_string(), (MAX - 1).to_string());
+
+    // (stored deadline, new deadline, option, applied)
+    let cases = [
+        (MAX - 1, &max, "GT", true),
+        (MAX - 1, &below, "GT", false),
+        (MAX, &max, "GT", false),
+        (MAX, &below, "GT", false),
+        (MAX, &below, "LT", true),
+        (MAX, &max, "LT", false),
+        (MAX - 1, &below, "LT", false),
+        (MAX - 1, &max, "LT", false),
+        (MAX - 1, &max, "NX", false),
+        (MAX, &below, "NX", false),
+        (MAX - 1, &max, "XX", true),
+        (MAX, &below, "XX", true),
+    ];
+    for (stored, new, option, applied) in cases {
+        let mut store = store_expiring_at(stored);
+        assert_eq!(
+            run(&mut store, &["PEXPIREAT", "k", new, option]),
+            int(i64::from(applied)),
+            "PEXPIREAT {new} {option} over {stored}"
+        );
+        let expected: i64 = if applied {
+            new.parse().unwrap()
+        } else {
+            stored
+        };
+        assert_eq!(pexpiretime(&mut store), int(expected));
+    }
+}
+
+#[test]
+fn relative_commands_compare_the_absolute_deadline() {
+    let now = i64::try_from(NOW).unwrap();
+    let to_max = (MAX - now).to_string();
+    let to_below = (MAX - 1 - now).to_string();
+
+    let mut store = store_expiring_at(MAX - 1);
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_below, "GT"]), int(0));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_max, "LT"]), int(0));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_max, "NX"]), int(0));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_max, "GT"]), int(1));
+    assert_eq!(pexpiretime(&mut store), int(MAX));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_max, "GT"]), int(0));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_below, "LT"]), int(1));
+    assert_eq!(pexpiretime(&mut store), int(MAX - 1));
+    assert_eq!(run(&mut store, &["PEXPIRE", "k", &to_max, "XX"]), int(1));
+    assert_eq!(pexpiretime(&mut store), int(MAX));
+    assert_eq!(run(&mut store, &["PTTL", "k"]), int(MAX - now));
+
+    // EXPIRE's largest deadline is whole seconds below i64::MAX, so LT wins.
+    let seconds = ((MAX - now) / 1000).to_string();
+    assert_eq!(run(&mut store, &["EXPIRE", "k", &seconds, "GT"]), int(0));
+    assert_eq!(run(&mut store, &["EXPIRE", "k", &seconds, "LT"]), int(1));
+    assert_eq!(
+        pexpiretime(&mut store),
+        int(now + (MAX - now) / 1000 * 1000)
+    );
+}
+
+#[test]
+fn keys_without_a_ttl_at_the_top_of_the_range() {
+    for (command, when) in [
+        ("PEXPIREAT", MAX.to_string()),
+        ("PEXPIREAT", (MAX - 1).to_string()),
+        ("EXPIREAT", (MAX / 1000).to_string()),
+        ("PEXPIRE", (MAX - i64::try_from(NOW).unwrap()).to_string()),
+    ] {
+        for option in OPTIONS {
+            let mut store = Store::new();
+            run(&mut store, &["SET", "k", "v"]);
+            // No TTL counts as an infinite deadline: NX and LT apply, XX and
+            // GT do not.
+            let applied = matches!(option, "NX" | "LT");
+            assert_eq!(
+                run(&mut store, &[command, "k", &when, option]),
+                int(i64::from(applied)),
+                "{command} {when} {option}"
+            );
+            let ttl = run(&mut store, &["TTL", "k"]);
+            assert_eq!(ttl == int(-1), !applied, "{command} {when} {option}");
+        }
+    }
+}
+
+#[test]
+fn deadlines_past_i64_max_are_refused_for_every_option() {
+    let now = i64::try_from(NOW).unwrap();
+    let beyond = [
+        (
+            "PEXPIREAT",
+            "9223372036854775808".to_string(),
+            err(NOT_AN_INTEGER),
+        ),
+        (
+            "PEXPIRE",
+            "9223372036854775808".to_string(),
+            err(NOT_AN_INTEGER),
+        ),
+        (
+            "EXPIREAT",
+            (MAX / 1000 + 1).to_string(),
+            invalid_expire("expireat"),
+        ),
+        (
+            "PEXPIRE",
+            (MAX - now + 1).to_string(),
+            invalid_expire("pexpire"),
+        ),
+        ("EXPIRE", (MAX / 1000).to_string(), invalid_expire("expire")),
+    ];
+    for stored in [MAX - 1, MAX] {
+        for (command, when, reply) in &beyond {
+            for option in OPTIONS {
+                let mut store = store_expiring_at(stored);
+                assert_eq!(
+                    run(&mut store, &[command, "k", when, option]),
+                    *reply,
+                    "{command} {when} {option}"
+                );
+                assert_eq!(pexpiretime(&mut store), int(stored));
+            }
+        }
+    }
+}
+
+#[test]
+fn past_deadlines_delete_only_when_the_option_lets_them_apply() {
+    let past = (i64::try_from(NOW).unwrap() - 1).to_string();
+    for stored in [Some(MAX - 1), Some(MAX), None] {
+        for option in OPTIONS {
+            let mut store = match stored {
+                Some(at) => store_expiring_at(at),
+                None => {
+                    let mut store = Store::new();
+                    run(&mut store, &["SET", "k", "v"]);
+                    store
+                }
+            };
+            let applied = match option {
+                "NX" => stored.is_none(),
+                "XX" => stored.is_some(),
+                "GT" => false,
+                _ => true,
+            };
+            assert_eq!(
+                run(&mut store, &["PEXPIREAT", "k", &past, option]),
+                int(i64::from(applied)),
+                "{stored:?} {option}"
+            );
+            assert_eq!(
+                run(&mut store, &["EXISTS", "k"]),
+                int(i64::from(!applied)),
+                "{stored:?} {option}"
+            );
+        }
+    }
+}
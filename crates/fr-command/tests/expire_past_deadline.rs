@@ -0,0 +1,138 @@
+//! An EXPIRE-family command whose deadline is already due deletes the key.
+//!
+//! Upstream expireGenericCommand runs `checkAlreadyExpired` once NX/XX/GT/LT
+//! have let the new deadline through and removes the key on the spot, replying
+//! 1 just like DEL — so DBSIZE drops immediately instead of waiting for a
+//! lazy or active expiry pass. A refused option leaves the key untouched.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, int, run_str as run};
+
+/// `k` plus an unrelated `other`, optionally giving `k` a TTL of `ttl` seconds.
+fn two_keys(ttl: Option<&str>) -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["SET", "k", "v"]);
+    run(&mut store, &["SET", "other", "v"]);
+    if let Some(ttl) = ttl {
+        assert_eq!(run(&mut store, &["EXPIRE", "k", ttl]), int(1));
+    }
+    store
+}
+
+#[test]
+fn every_past_deadline_form_deletes_immediately() {
+    let past_ms = (NOW - 1).to_string();
+    let past_secs = (NOW / 1000 - 1).to_string();
+    let cases: [&[&str]; 7] = [
+        &["EXPIRE", "k", "-1"],
+        &["EXPIRE", "k", "0"],
+        &["PEXPIRE", "k", "-100"],
+        &["PEXPIRE", "k", "0"],
+        &["EXPIREAT", "k", &past_secs],
+        &["PEXPIREAT", "k", &past_ms],
+        &["PEXPIREAT", "k", "1"],
+    ];
+    for argv in cases {
+        let mut store = two_keys(None);
+        assert_eq!(run(&mut store, &["DBSIZE"]), int(2));
+        assert_eq!(run(&mut store, argv), int(1), "{argv:?}");
+        assert_eq!(run(&mut store, &["DBSIZE"]), int(1), "{argv:?}");
+        assert_eq!(run(&mut store, &["EXISTS", "k"]), int(0), "{argv:?}");
+        assert_eq!(run(&mut store, &["TTL", "k"]), int(-2), "{argv:?}");
+        // Nothing left to delete the second time round.
+        assert_eq!(run(&mut store, argv), int(0), "{argv:?}");
+    }
+}
+
+#[test]
+fn a_deadline_exactly_now_counts_as_past() {
+    let mut store = two_keys(None);
+    assert_eq!(
+        run(&mut store, &["PEXPIREAT", "k", &NOW.to_string()]),
+        int(1)
+    );
+    assert_eq!(run(&mut store, &["DBSIZE"]), int(1));
+
+    let mut store = two_keys(None);
+    assert_eq!(
+        run(&mut store, &["PEXPIREAT", "k", &(NOW + 1).to_string()]),
+        int(1)
+    );
+    assert_eq!(run(&mut store, &["DBSIZE"]), int(2));
+    assert_eq!(run(&mut store, &["PTTL", "k"]), int(1));
+}
+
+#[test]
+fn options_decide_before_the_past_deadline_deletes() {
+    // (stored TTL, option, applied)
+    let cases = [
+        // No TTL counts as an infinite deadline: nothing is greater.
+        (None, "GT", false),
+        (None, "LT", true),
+        (None, "NX", true),
+        (None, "XX", false),
+        // A live TTL is always later than a past deadline.
+        (Some("100"), "GT", false),
+        (Some("100"), "LT", true),
+        (Some("100"), "NX", false),
+        (Some("100"), "XX", true),
+    ];
+    for (ttl, option, applied) in cases {
+        let mut store = two_keys(ttl);
+        assert_eq!(
+            run(&mut store, &["EXPIRE", "k", "-1", option]),
+            int(i64::from(applied)),
+            "{ttl:?} {option}"
+        );
+        let expected_size = if applied { 1 } else { 2 };
+        assert_eq!(
+            run(&mut store, &["DBSIZE"]),
+            int(expected_size),
+            "{ttl:?} {option}"
+        );
+        let expected_ttl = match (applied, ttl) {
+            (true, _) => -2,
+            (false, None) => -1,
+            (false, Some(_)) => 100,
+        };
+        assert_eq!(
+            run(&mut store, &["TTL", "k"]),
+            int(expected_ttl),
+            "{ttl:?} {option}"
+        );
+    }
+}
+
+#[test]
+fn missing_keys_are_never_created() {
+    let mut store = Store::new();
+    assert_eq!(run(&mut store, &["EXPIRE", "nope", "-1"]), int(0));
+    assert_eq!(run(&mut store, &["PEXPIREAT", "nope", "1", "LT"]), int(0));
+    assert_eq!(run(&mut store, &["DBSIZE"]), int(0));
+}
+
+#[test]
+fn set_with_a_past_absolute_deadline_is_never_visible() {
+    // Redis 7.2 SET stores the deadline as given (no checkAlreadyExpired),
+    // so the key is reclaimed by the next lookup rather than by SET itself;
+    // readers must still never observe it.
+    let past_secs = (NOW / 1000 - 1).to_string();
+    for argv in [
+        ["SET", "k", "v", "PXAT", "1"],
+        ["SET", "k", "v", "EXAT", past_secs.as_str()],
+    ] {
+        let mut store = Store::new();
+        assert_eq!(
+            run(&mut store, &argv),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(run(&mut store, &["EXISTS", "k"]), int(0), "{argv:?}");
+        assert_eq!(run(&mut store, &["GET", "k"]), RespFrame::BulkString(None));
+        assert_eq!(run(&mut store, &["TTL", "k"]), int(-2));
+        assert_eq!(run(&mut store, &["DBSIZE"]), int(0), "{argv:?}");
+    }
+}
@@ -0,0 +1,91 @@
+//! Optional command families and the cargo features that compile them in.
+//!
+//! `scripting`, `streams`, `geo` and `hyperloglog` default on and
+//! `hash-field-ttl` defaults off; a build without one of them must answer that
+//! family's commands exactly like a name that was never a command, while the core surface (strings, hashes, expiry) behaves
+//! identically in every build. Run under both the default feature set and
+//! `--no-default-features --features strings-core`.
+
+use fr_command::{
+    GEO_ENABLED, HASH_FIELD_TTL_ENABLED, HYPERLOGLOG_ENABLED, SCRIPTING_ENABLED, STREAMS_ENABLED,
+    is_known_command, unknown_command_error,
+};
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{argv, run};
+
+/// One command per family, each valid enough to succeed when compiled in.
+const FAMILY_COMMANDS: &[(bool, &[&[u8]])] = &[
+    (SCRIPTING_ENABLED, &[b"EVAL", b"return 1", b"0"]),
+    (SCRIPTING_ENABLED, &[b"SCRIPT", b"FLUSH"]),
+    (SCRIPTING_ENABLED, &[b"FUNCTION", b"LIST"]),
+    (SCRIPTING_ENABLED, &[b"fcall_ro", b"nope", b"0"]),
+    (STREAMS_ENABLED, &[b"XADD", b"s", b"1-1", b"f", b"v"]),
+    (STREAMS_ENABLED, &[b"xlen", b"s"]),
+    (GEO_ENABLED, &[b"GEOADD", b"g", b"13.36", b"38.11", b"a"]),
+    (
+        GEO_ENABLED,
+        &[
+            b"GEOSEARCH",
+            b"g",
+            b"FROMMEMBER",
+            b"a",
+            b"BYRADIUS",
+            b"1",
+            b"km",
+        ],
+    ),
+    (HYPERLOGLOG_ENABLED, &[b"PFADD", b"h", b"a"]),
+    (HYPERLOGLOG_ENABLED, &[b"pfcount", b"h"]),
+    (
+        HASH_FIELD_TTL_ENABLED,
+        &[b"HEXPIRE", b"f", b"10", b"FIELDS", b"1", b"a"],
+    ),
+    (
+        HASH_FIELD_TTL_ENABLED,
+        &[b"httl", b"f", b"FIELDS", b"1", b"a"],
+    ),
+];
+
+#[test]
+fn family_commands_are_known_exactly_when_compiled_in() {
+    let mut store = Store::new();
+    for &(enabled, parts) in FAMILY_COMMANDS {
+        let name = String::from_utf8_lossy(parts[0]);
+        assert_eq!(is_known_command(parts[0]), enabled, "{name}");
+        let reply = run(&mut store, parts);
+        let unknown = unknown_command_error(&argv(parts)).to_resp();
+        if enabled {
+            assert_ne!(reply, unknown, "{name} is compiled in");
+        } else {
+            assert_eq!(reply, unknown, "{name} is compiled out");
+        }
+    }
+}
+
+#[test]
+fn core_commands_work_in_every_build() {
+    let mut store = Store::new();
+    let ok = RespFrame::SimpleString("OK".to_string());
+    assert_eq!(run(&mut store, &[b"SET", b"k", b"v", b"PX", b"5000"]), ok);
+    assert_eq!(
+        run(&mut store, &[b"GET", b"k"]),
+        RespFrame::BulkString(Some(b"v".to_vec()))
+    );
+    assert_eq!(run(&mut store, &[b"PTTL", b"k"]), RespFrame::Integer(5000));
+    assert_eq!(
+        run(&mut store, &[b"HSET", b"h", b"f1", b"a", b"f2", b"b"]),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(run(&mut store, &[b"HLEN", b"h"]), RespFrame::Integer(2));
+    assert_eq!(
+        run(&mut store, &[b"EXPIRE", b"h", b"10"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(run(&mut store, &[b"TTL", b"h"]), RespFrame::Integer(10));
+    assert_eq!(run(&mut store, &[b"PERSIST", b"h"]), RespFrame::Integer(1));
+    assert_eq!(run(&mut store, &[b"TTL", b"h"]), RespFrame::Integer(-1));
+}
@@ -0,0 +1,152 @@
+//! FLUSHALL vs FLUSHDB through dispatch, and SCAN-family cursors across a
+//! flush: a cursor minted before the flush must end the iteration with
+//! `(0, [])` rather than resume into whatever was written afterwards.
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv, ok, run_ok as run};
+
+/// Split a `[cursor, [items]]` reply.
+fn scan_page(reply: RespFrame) -> (Vec<u8>, Vec<RespFrame>) {
+    let RespFrame::Array(Some(mut parts)) = reply else {
+        panic!("scan reply is an array");
+    };
+    let Some(RespFrame::Array(Some(items))) = parts.pop() else {
+        panic!("scan items are an array");
+    };
+    let Some(RespFrame::BulkString(Some(cursor))) = parts.pop() else {
+        panic!("scan cursor is a bulk string");
+    };
+    (cursor, items)
+}
+
+fn fill(store: &mut Store, prefix: &str) {
+    for i in 0..200 {
+        let key = format!("{prefix}:{i}");
+        run(store, &[b"SET", key.as_bytes(), b"v"]);
+    }
+    // Past hash-max-listpack-value, so HSCAN pages instead of returning the
+    // whole hash with cursor 0.
+    let long_value = [b'v'; 100];
+    for i in 0..200 {
+        let member = format!("{prefix}:{i}");
+        run(store, &[b"HSET", b"hash", member.as_bytes(), &long_value]);
+        run(store, &[b"SADD", b"set", member.as_bytes()]);
+        run(store, &[b"ZADD", b"zset", b"1", member.as_bytes()]);
+    }
+}
+
+#[test]
+fn command_info_reports_flushall_as_its_own_command() {
+    let mut store = Store::new();
+    let reply = run(&mut store, &[b"COMMAND", b"INFO", b"FLUSHALL", b"FLUSHDB"]);
+    let RespFrame::Array(Some(rows)) = reply else {
+        panic!("COMMAND INFO is an array");
+    };
+    let names: Vec<RespFrame> = rows
+        .into_iter()
+        .map(|row| match row {
+            RespFrame::Array(Some(mut entry)) => entry.swap_remove(0),
+            other => panic!("unexpected COMMAND INFO row {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            RespFrame::BulkString(Some(b"flushall".to_vec())),
+            RespFrame::BulkString(Some(b"flushdb".to_vec())),
+        ]
+    );
+}
+
+#[test]
+fn flushall_accepts_only_async_or_sync() {
+    let mut store = Store::new();
+    for mode in [&b"ASYNC"[..], b"SYNC", b"async", b"sync"] {
+        run(&mut store, &[b"SET", b"k", b"v"]);
+        assert_eq!(run(&mut store, &[b"FLUSHALL", mode]), ok());
+        assert_eq!(run(&mut store, &[b"DBSIZE"]), RespFrame::Integer(0));
+    }
+    for bad in [
+        argv(&[b"FLUSHALL", b"LAZY"]),
+        argv(&[b"FLUSHALL", b"ASYNC", b"SYNC"]),
+    ] {
+        let err = dispatch_argv(&bad, &mut store, NOW).unwrap_err();
+        assert_eq!(
+            err.to_resp(),
+            RespFrame::Error("ERR syntax error".to_string())
+        );
+    }
+}
+
+#[test]
+fn cursors_minted_before_a_flush_end_the_iteration() {
+    for flush in [&b"FLUSHALL"[..], b"FLUSHDB"] {
+        let mut store = Store::new();
+        fill(&mut store, "old");
+
+        let stale = [
+            argv(&[b"SCAN", b"0", b"COUNT", b"10"]),
+            argv(&[b"HSCAN", b"hash", b"0", b"COUNT", b"10"]),
+            argv(&[b"SSCAN", b"set", b"0", b"COUNT", b"10"]),
+            argv(&[b"ZSCAN", b"zset", b"0", b"COUNT", b"10"]),
+        ]
+        .map(|command| {
+            let (cursor, items) = scan_page(dispatch_argv(&command, &mut store, NOW).unwrap());
+            assert_ne!(cursor, b"0", "{command:?} needs a second page");
+            assert!(!items.is_empty());
+            let mut resume = command;
+            let cursor_slot = if resume[0] == b"SCAN" { 1 } else { 2 };
+            resume[cursor_slot] = cursor;
+            resume
+        });
+
+        assert_eq!(run(&mut store, &[flush]), ok());
+        // Refill so the old positions would land on live (but unrelated) data.
+        fill(&mut store, "new");
+
+        for resume in &stale {
+            let (cursor, items) = scan_page(dispatch_argv(resume, &mut store, NOW).unwrap());
+            assert_eq!(cursor, b"0", "{resume:?} after {flush:?}");
+            assert!(items.is_empty(), "{resume:?} after {flush:?}: {items:?}");
+        }
+
+        // Cursors minted after the flush walk the new data to the end.
+        let mut cursor = b"0".to_vec();
+        let mut seen = 0;
+        loop {
+            let (next, items) = scan_page(run(&mut store, &[b"SCAN", &cursor, b"COUNT", b"10"]));
+            seen += items.len();
+            if next == b"0" {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen, 203, "200 strings plus the three collections");
+    }
+}
+
+#[test]
+fn flushing_an_empty_keyspace_keeps_cursors_valid() {
+    let mut store = Store::new();
+    run(&mut store, &[b"FLUSHALL"]);
+    for i in 0..5 {
+        let key = format!("k{i}");
+        run(&mut store, &[b"SET", key.as_bytes(), b"v"]);
+    }
+    // Before any key-dropping flush the cursor is the plain position.
+    let (cursor, items) = scan_page(run(&mut store, &[b"SCAN", b"0", b"COUNT", b"2"]));
+    assert_eq!(cursor, b"2");
+    assert_eq!(items.len(), 2);
+
+    store.dispatch_client_ctx.db_index = 3;
+    run(&mut store, &[b"FLUSHDB"]);
+    store.dispatch_client_ctx.db_index = 0;
+    let (cursor, items) = scan_page(run(&mut store, &[b"SCAN", b"2", b"COUNT", b"10"]));
+    assert_eq!(cursor, b"0");
+    assert_eq!(items.len(), 3);
+}
@@ -0,0 +1,190 @@
+//! Generic keyspace commands must work on a key of every value type.
+//!
+//! Seeds one key per type (string, integer-encoded string, list, hash, set,
+//! zset, stream) and drives the whole generic-command surface against it:
+//! DEL/UNLINK, EXISTS, TTL/PTTL, EXPIRE/PEXPIRE/PERSIST, TYPE, RENAME, COPY,
+//! DUMP/RESTORE, OBJECT ENCODING, MEMORY USAGE, TOUCH, RANDOMKEY, KEYS and SCAN.
+//! None of these may ever answer WRONGTYPE, and each reply must be the one the
+//! command gives for a plain live key of that type.
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, bulk, int};
+
+/// (type name, seeding command) for every value type the store can hold.
+const TYPES: &[(&str, &[&[u8]])] = &[
+    ("string", &[b"SET", b"k", b"hello"]),
+    ("string", &[b"SET", b"k", b"12345"]),
+    ("list", &[b"RPUSH", b"k", b"a", b"b"]),
+    ("hash", &[b"HSET", b"k", b"f", b"v"]),
+    ("set", &[b"SADD", b"k", b"m1", b"m2"]),
+    ("zset", &[b"ZADD", b"k", b"1", b"m"]),
+    #[cfg(feature = "streams")]
+    ("stream", &[b"XADD", b"k", b"1-1", b"f", b"v"]),
+];
+
+fn run(store: &mut Store, argv: &[&[u8]]) -> RespFrame {
+    let argv: Vec<Vec<u8>> = argv.iter().map(|a| a.to_vec()).collect();
+    let reply = match dispatch_argv(&argv, store, NOW) {
+        Ok(frame) => frame,
+        Err(err) => err.to_resp(),
+    };
+    if let RespFrame::Error(msg) = &reply {
+        assert!(
+            !msg.starts_with("WRONGTYPE"),
+            "{:?} answered WRONGTYPE",
+            String::from_utf8_lossy(&argv.join(&b' '))
+        );
+    }
+    reply
+}
+
+fn seeded(seed: &[&[u8]]) -> Store {
+    let mut store = Store::new();
+    let reply = run(&mut store, seed);
+    assert!(
+        !matches!(reply, RespFrame::Error(_)),
+        "seed failed: {reply:?}"
+    );
+    store
+}
+
+fn type_of(store: &mut Store, key: &[u8]) -> RespFrame {
+    run(store, &[b"TYPE", key])
+}
+
+fn scan_keys(reply: &RespFrame) -> Vec<Vec<u8>> {
+    let RespFrame::Array(Some(parts)) = reply else {
+        panic!("SCAN reply must be an array: {reply:?}");
+    };
+    let RespFrame::Array(Some(keys)) = &parts[1] else {
+        panic!("SCAN keys must be an array: {reply:?}");
+    };
+    keys.iter()
+        .map(|k| match k {
+            RespFrame::BulkString(Some(b)) => b.clone(),
+            other => panic!("SCAN key must be a bulk string: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn presence_and_type_queries_work_for_every_type() {
+    for &(type_name, seed) in TYPES {
+        let mut store = seeded(seed);
+        let type_reply = RespFrame::SimpleString(type_name.to_string());
+        assert_eq!(type_of(&mut store, b"k"), type_reply, "{type_name}");
+        assert_eq!(
+            run(&mut store, &[b"EXISTS", b"k", b"k"]),
+            int(2),
+            "{type_name}"
+        );
+        assert_eq!(
+            run(&mut store, &[b"TOUCH", b"k", b"nope"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(run(&mut store, &[b"RANDOMKEY"]), bulk(b"k"), "{type_name}");
+        assert_eq!(
+            run(&mut store, &[b"KEYS", b"*"]),
+            RespFrame::Array(Some(vec![bulk(b"k")])),
+            "{type_name}"
+        );
+        let scan = run(&mut store, &[b"SCAN", b"0"]);
+        assert_eq!(scan_keys(&scan), vec![b"k".to_vec()], "{type_name}");
+        let scan_typed = run(&mut store, &[b"SCAN", b"0", b"TYPE", type_name.as_bytes()]);
+        assert_eq!(scan_keys(&scan_typed), vec![b"k".to_vec()], "{type_name}");
+
+        match run(&mut store, &[b"OBJECT", b"ENCODING", b"k"]) {
+            RespFrame::BulkString(Some(enc)) => assert!(!enc.is_empty(), "{type_name}"),
+            other => panic!("OBJECT ENCODING on {type_name}: {other:?}"),
+        }
+        match run(&mut store, &[b"MEMORY", b"USAGE", b"k"]) {
+            RespFrame::Integer(n) => assert!(n > 0, "{type_name} MEMORY USAGE = {n}"),
+            other => panic!("MEMORY USAGE on {type_name}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn expiry_commands_work_for_every_type() {
+    for &(type_name, seed) in TYPES {
+        let mut store = seeded(seed);
+        assert_eq!(run(&mut store, &[b"TTL", b"k"]), int(-1), "{type_name}");
+        assert_eq!(run(&mut store, &[b"PTTL", b"k"]), int(-1), "{type_name}");
+        assert_eq!(run(&mut store, &[b"PERSIST", b"k"]), int(0), "{type_name}");
+
+        assert_eq!(
+            run(&mut store, &[b"EXPIRE", b"k", b"100"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(run(&mut store, &[b"TTL", b"k"]), int(100), "{type_name}");
+        assert_eq!(
+            run(&mut store, &[b"PEXPIRE", b"k", b"5000"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(run(&mut store, &[b"PTTL", b"k"]), int(5000), "{type_name}");
+        assert_eq!(run(&mut store, &[b"PERSIST", b"k"]), int(1), "{type_name}");
+        assert_eq!(run(&mut store, &[b"TTL", b"k"]), int(-1), "{type_name}");
+
+        // A non-positive TTL deletes the key outright, whatever its type.
+        assert_eq!(
+            run(&mut store, &[b"EXPIRE", b"k", b"0"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(run(&mut store, &[b"EXISTS", b"k"]), int(0), "{type_name}");
+        assert_eq!(run(&mut store, &[b"TTL", b"k"]), int(-2), "{type_name}");
+    }
+}
+
+#[test]
+fn copy_rename_dump_restore_and_delete_work_for_every_type() {
+    for &(type_name, seed) in TYPES {
+        let mut store = seeded(seed);
+        let type_reply = RespFrame::SimpleString(type_name.to_string());
+
+        assert_eq!(
+            run(&mut store, &[b"COPY", b"k", b"copy"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(type_of(&mut store, b"copy"), type_reply, "{type_name}");
+
+        let RespFrame::BulkString(Some(payload)) = run(&mut store, &[b"DUMP", b"k"]) else {
+            panic!("DUMP on {type_name} must return a payload");
+        };
+        assert_eq!(
+            run(&mut store, &[b"RESTORE", b"restored", b"0", &payload]),
+            RespFrame::SimpleString("OK".to_string()),
+            "{type_name}"
+        );
+        assert_eq!(type_of(&mut store, b"restored"), type_reply, "{type_name}");
+
+        assert_eq!(
+            run(&mut store, &[b"RENAME", b"k", b"renamed"]),
+            RespFrame::SimpleString("OK".to_string()),
+            "{type_name}"
+        );
+        assert_eq!(type_of(&mut store, b"renamed"), type_reply, "{type_name}");
+        assert_eq!(run(&mut store, &[b"EXISTS", b"k"]), int(0), "{type_name}");
+
+        assert_eq!(
+            run(&mut store, &[b"DEL", b"renamed", b"copy", b"missing"]),
+            int(2),
+            "{type_name}"
+        );
+        assert_eq!(
+            run(&mut store, &[b"UNLINK", b"restored"]),
+            int(1),
+            "{type_name}"
+        );
+        assert_eq!(run(&mut store, &[b"DBSIZE"]), int(0), "{type_name}");
+    }
+}
@@ -0,0 +1,79 @@
+//! SET ... GET, GETSET, GETDEL and GETEX against a key of every non-string
+//! type answer WRONGTYPE before writing anything: the key keeps its type,
+//! its serialized value and its TTL.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run_str;
+
+/// (type name, seeding command) for every non-string value type.
+const TYPES: &[(&str, &str)] = &[
+    ("list", "RPUSH k a b"),
+    ("hash", "HSET k f v"),
+    ("set", "SADD k m1 m2"),
+    ("zset", "ZADD k 1 m"),
+    #[cfg(feature = "streams")]
+    ("stream", "XADD k 1-1 f v"),
+];
+
+const COMMANDS: &[&str] = &[
+    "SET k v GET",
+    "SET k v NX GET",
+    "SET k v GET XX PX 100",
+    "SET k v KEEPTTL GET",
+    "GETSET k v",
+    "GETDEL k",
+    "GETEX k",
+    "GETEX k EX 10",
+    "GETEX k PERSIST",
+];
+
+fn run(store: &mut Store, line: &str) -> RespFrame {
+    run_str(store, &line.split(' ').collect::<Vec<_>>())
+}
+
+#[test]
+fn string_read_then_write_commands_reject_other_types() {
+    let wrongtype = RespFrame::Error(
+        "WRONGTYPE Operation against a key holding the wrong kind of value".into(),
+    );
+    for (kind, seed) in TYPES {
+        for command in COMMANDS {
+            let mut store = Store::new();
+            run(&mut store, seed);
+            run(&mut store, "EXPIRE k 100");
+            let dump = run(&mut store, "DUMP k");
+
+            assert_eq!(run(&mut store, command), wrongtype, "{command} on {kind}");
+            assert_eq!(
+                run(&mut store, "TYPE k"),
+                RespFrame::SimpleString((*kind).to_string()),
+                "{command} on {kind}"
+            );
+            assert_eq!(run(&mut store, "DUMP k"), dump, "{command} on {kind}");
+            assert_eq!(
+                run(&mut store, "TTL k"),
+                RespFrame::Integer(100),
+                "{command} on {kind}"
+            );
+        }
+    }
+}
+
+#[test]
+fn missing_and_string_keys_still_behave() {
+    let mut store = Store::new();
+    let nil = RespFrame::BulkString(None);
+    let bulk = |v: &str| RespFrame::BulkString(Some(v.as_bytes().to_vec()));
+    assert_eq!(run(&mut store, "GETDEL k"), nil);
+    assert_eq!(run(&mut store, "GETEX k EX 10"), nil);
+    assert_eq!(run(&mut store, "SET k 1 GET"), nil);
+    assert_eq!(run(&mut store, "GETSET k 2"), bulk("1"));
+    assert_eq!(run(&mut store, "GETEX k EX 10"), bulk("2"));
+    assert_eq!(run(&mut store, "TTL k"), RespFrame::Integer(10));
+    assert_eq!(run(&mut store, "GETDEL k"), bulk("2"));
+    assert_eq!(run(&mut store, "EXISTS k"), RespFrame::Integer(0));
+}
@@ -0,0 +1,276 @@
+//! The Redis 7.4 per-field hash TTL commands, compiled in by the
+//! `hash-field-ttl` feature: HEXPIRE / HPEXPIRE / HEXPIREAT / HPEXPIREAT,
+//! HTTL / HPTTL / HEXPIRETIME / HPEXPIRETIME and HPERSIST, plus how an
+//! expired field disappears from the rest of the hash surface.
+
+#![cfg(feature = "hash-field-ttl")]
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, err, run_str as run, run_str_at as run_at};
+
+fn codes(values: &[i64]) -> RespFrame {
+    RespFrame::Array(Some(
+        values.iter().copied().map(RespFrame::Integer).collect(),
+    ))
+}
+
+fn seeded() -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+    store
+}
+
+#[test]
+fn reply_codes_per_field() {
+    let mut store = seeded();
+    assert_eq!(
+        run(
+            &mut store,
+            &["HEXPIRE", "h", "100", "FIELDS", "2", "a", "nope"]
+        ),
+        codes(&[1, -2])
+    );
+    assert_eq!(
+        run(&mut store, &["HTTL", "h", "FIELDS", "3", "a", "b", "nope"]),
+        codes(&[100, -1, -2])
+    );
+    assert_eq!(
+        run(&mut store, &["HPTTL", "h", "FIELDS", "1", "a"]),
+        codes(&[100_000])
+    );
+    assert_eq!(
+        run(&mut store, &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]),
+        codes(&[1_100_000])
+    );
+    assert_eq!(
+        run(&mut store, &["HEXPIRETIME", "h", "FIELDS", "1", "a"]),
+        codes(&[1_100])
+    );
+
+    // A deadline that is already past deletes the field: reply 2, then -2.
+    assert_eq!(
+        run(
+            &mut store,
+            &["HPEXPIREAT", "h", "5", "FIELDS", "2", "b", "b"]
+        ),
+        codes(&[2, -2])
+    );
+    assert_eq!(
+        run(&mut store, &["HGET", "h", "b"]),
+        RespFrame::BulkString(None)
+    );
+
+    // A missing key answers -2 for every field.
+    assert_eq!(
+        run(
+            &mut store,
+            &["HEXPIRE", "missing", "10", "FIELDS", "2", "x", "y"]
+        ),
+        codes(&[-2, -2])
+    );
+    assert_eq!(
+        run(&mut store, &["HTTL", "missing", "FIELDS", "1", "x"]),
+        codes(&[-2])
+    );
+}
+
+#[test]
+fn nx_xx_gt_lt_conditions() {
+    let mut store = seeded();
+    // `a` has no TTL, `b` expires in 100s.
+    run(&mut store, &["HEXPIRE", "h", "100", "FIELDS", "1", "b"]);
+    let set = |store: &mut Store, secs: &str, cond: &str| {
+        run(
+            store,
+            &["HEXPIRE", "h", secs, cond, "FIELDS", "2", "a", "b"],
+        )
+    };
+
+    assert_eq!(set(&mut store, "50", "XX"), codes(&[0, 1]));
+    assert_eq!(
+        run(&mut store, &["HTTL", "h", "FIELDS", "2", "a", "b"]),
+        codes(&[-1, 50])
+    );
+    // GT never applies to a field without a TTL; LT always does.
+    assert_eq!(set(&mut store, "60", "GT"), codes(&[0, 1]));
+    assert_eq!(set(&mut store, "40", "gt"), codes(&[0, 0]));
+    assert_eq!(set(&mut store, "200", "LT"), codes(&[1, 0]));
+    assert_eq!(set(&mut store, "30", "lt"), codes(&[1, 1]));
+    assert_eq!(set(&mut store, "999", "NX"), codes(&[0, 0]));
+    run(&mut store, &["HPERSIST", "h", "FIELDS", "1", "a"]);
+    assert_eq!(set(&mut store, "999", "nx"), codes(&[1, 0]));
+    assert_eq!(
+        run(&mut store, &["HTTL", "h", "FIELDS", "2", "a", "b"]),
+        codes(&[999, 30])
+    );
+}
+
+#[test]
+fn hpersist_clears_a_ttl() {
+    let mut store = seeded();
+    run(&mut store, &["HPEXPIRE", "h", "500", "FIELDS", "1", "a"]);
+    assert_eq!(
+        run(
+            &mut store,
+            &["HPERSIST", "h", "FIELDS", "3", "a", "b", "nope"]
+        ),
+        codes(&[1, -1, -2])
+    );
+    assert_eq!(
+        run(&mut store, &["HTTL", "h", "FIELDS", "1", "a"]),
+        codes(&[-1])
+    );
+    assert_eq!(
+        run_at(&mut store, NOW + 1_000, &["HGET", "h", "a"]),
+        RespFrame::BulkString(Some(b"1".to_vec()))
+    );
+}
+
+#[test]
+fn expired_fields_vanish_from_reads() {
+    let mut store = seeded();
+    run(
+        &mut store,
+        &["HPEXPIRE", "h", "10", "FIELDS", "2", "a", "b"],
+    );
+    let later = NOW + 10;
+
+    assert_eq!(
+        run_at(&mut store, later, &["HGET", "h", "a"]),
+        RespFrame::BulkString(None)
+    );
+    assert_eq!(
+        run_at(&mut store, later, &["HLEN", "h"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        run_at(&mut store, later, &["HGETALL", "h"]),
+        RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"c".to_vec())),
+            RespFrame::BulkString(Some(b"3".to_vec())),
+        ]))
+    );
+    assert_eq!(
+        run_at(&mut store, later, &["HTTL", "h", "FIELDS", "1", "a"]),
+        codes(&[-2])
+    );
+}
+
+#[test]
+fn the_last_field_expiring_deletes_the_key() {
+    let mut store = seeded();
+    run(
+        &mut store,
+        &["HPEXPIRE", "h", "10", "FIELDS", "3", "a", "b", "c"],
+    );
+    assert_eq!(
+        run_at(&mut store, NOW + 10, &["HLEN", "h"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(
+        run_at(&mut store, NOW + 10, &["EXISTS", "h"]),
+        RespFrame::Integer(0)
+    );
+
+    // Expiring the last field immediately deletes it on the spot.
+    let mut store = Store::new();
+    run(&mut store, &["HSET", "k", "f", "v"]);
+    assert_eq!(
+        run(&mut store, &["HEXPIRE", "k", "0", "FIELDS", "1", "f"]),
+        codes(&[2])
+    );
+    assert_eq!(run(&mut store, &["EXISTS", "k"]), RespFrame::Integer(0));
+}
+
+#[test]
+fn hrandfield_never_returns_an_expired_field() {
+    let mut store = seeded();
+    run(
+        &mut store,
+        &["HPEXPIRE", "h", "10", "FIELDS", "2", "a", "b"],
+    );
+    let later = NOW + 10;
+    for _ in 0..20 {
+        assert_eq!(
+            run_at(&mut store, later, &["HRANDFIELD", "h"]),
+            RespFrame::BulkString(Some(b"c".to_vec()))
+        );
+    }
+    for count in ["3", "-5"] {
+        let RespFrame::Array(Some(fields)) =
+            run_at(&mut store, later, &["HRANDFIELD", "h", count, "WITHVALUES"])
+        else {
+            panic!("HRANDFIELD with a count replies with an array");
+        };
+        assert!(!fields.is_empty());
+        for pair in fields.chunks(2) {
+            assert_eq!(pair[0], RespFrame::BulkString(Some(b"c".to_vec())));
+            assert_eq!(pair[1], RespFrame::BulkString(Some(b"3".to_vec())));
+        }
+    }
+
+    run(&mut store, &["HPEXPIRE", "h", "10", "FIELDS", "1", "c"]);
+    assert_eq!(
+        run_at(&mut store, later, &["HRANDFIELD", "h"]),
+        RespFrame::BulkString(None)
+    );
+}
+
+#[test]
+fn argument_errors() {
+    let mut store = seeded();
+    assert_eq!(
+        run(
+            &mut store,
+            &["HEXPIRE", "h", "10", "NX", "XX", "FIELDS", "1", "a"]
+        ),
+        err("ERR Mandatory argument FIELDS is missing or not at the right position")
+    );
+    assert_eq!(
+        run(&mut store, &["HTTL", "h", "FIELD", "1", "a"]),
+        err("ERR Mandatory argument FIELDS is missing or not at the right position")
+    );
+    assert_eq!(
+        run(&mut store, &["HEXPIRE", "h", "10", "FIELDS", "0", "a"]),
+        err("ERR Number of fields must be a positive integer")
+    );
+    assert_eq!(
+        run(&mut store, &["HPERSIST", "h", "FIELDS", "2", "a"]),
+        err("ERR The `numfields` parameter must match the number of arguments")
+    );
+    assert_eq!(
+        run(&mut store, &["HEXPIRE", "h", "-1", "FIELDS", "1", "a"]),
+        err("ERR invalid expire time, must be >= 0")
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["HPEXPIREAT", "h", "281474976710656", "FIELDS", "1", "a"]
+        ),
+        err("ERR invalid expire time in 'hpexpireat' command")
+    );
+    assert_eq!(
+        run(&mut store, &["HEXPIRE", "h", "10", "FIELDS", "1"]),
+        err("ERR wrong number of arguments for 'hexpire' command")
+    );
+
+    run(&mut store, &["SET", "s", "v"]);
+    let wrongtype = err("WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(
+        run(&mut store, &["HEXPIRE", "s", "soon", "FIELDS", "1", "a"]),
+        wrongtype
+    );
+    assert_eq!(
+        run(&mut store, &["HTTL", "s", "FIELDS", "1", "a"]),
+        wrongtype
+    );
+    assert_eq!(
+        run(&mut store, &["HPERSIST", "s", "FIELDS", "1", "a"]),
+        wrongtype
+    );
+}
@@ -0,0 +1,145 @@
+//! HINCRBY / HINCRBYFLOAT against a field that already holds bytes.
+//!
+//! The stored value is parsed strictly (t_hash.c uses string2ll / string2ld):
+//! no surrounding whitespace, no trailing bytes, and for integers no `+` sign,
+//! leading zero or `-0`. A field that fails to parse is reported with the
+//! hash-specific wording and keeps its original bytes, as does an integer
+//! increment that would overflow.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, err, run};
+
+type Case<'a> = (&'a [u8], &'a [u8]);
+
+fn field_after(store: &mut Store, command: &[u8], stored: &[u8], increment: &[u8]) -> RespFrame {
+    run(store, &[b"DEL", b"h"]);
+    run(store, &[b"HSET", b"h", b"f", stored]);
+    run(store, &[command, b"h", b"f", increment])
+}
+
+#[test]
+fn hincrby_rejects_fields_that_are_not_strict_integers() {
+    let mut store = Store::new();
+    let not_integer = err("ERR hash value is not an integer");
+    for stored in [
+        &b"abc"[..],
+        b"",
+        b"+5",
+        b"-0",
+        b"05",
+        b" 5",
+        b"5 ",
+        b"5abc",
+        b"1.0",
+        b"1e3",
+        b"0x10",
+        b"-",
+        b"9223372036854775808",
+        b"-9223372036854775809",
+    ] {
+        assert_eq!(
+            field_after(&mut store, b"HINCRBY", stored, b"1"),
+            not_integer,
+            "{stored:?}"
+        );
+        assert_eq!(
+            run(&mut store, &[b"HGET", b"h", b"f"]),
+            bulk(stored),
+            "{stored:?} must be left untouched"
+        );
+    }
+}
+
+#[test]
+fn hincrby_accepts_canonical_integers_and_reports_overflow() {
+    let mut store = Store::new();
+    let cases: &[(Case, i64)] = &[
+        ((b"5", b"1"), 6),
+        ((b"-5", b"-1"), -6),
+        ((b"0", b"0"), 0),
+        ((b"9223372036854775806", b"1"), i64::MAX),
+        ((b"-9223372036854775807", b"-1"), i64::MIN),
+    ];
+    for ((stored, increment), expected) in cases {
+        assert_eq!(
+            field_after(&mut store, b"HINCRBY", stored, increment),
+            RespFrame::Integer(*expected),
+            "{stored:?} + {increment:?}"
+        );
+    }
+
+    let overflow = err("ERR increment or decrement would overflow");
+    let overflows: &[Case] = &[
+        (b"9223372036854775807", b"1"),
+        (b"-9223372036854775808", b"-1"),
+        (b"1", b"9223372036854775807"),
+        (b"-2", b"-9223372036854775807"),
+    ];
+    for (stored, increment) in overflows {
+        assert_eq!(
+            field_after(&mut store, b"HINCRBY", stored, increment),
+            overflow,
+            "{stored:?} + {increment:?}"
+        );
+        assert_eq!(run(&mut store, &[b"HGET", b"h", b"f"]), bulk(stored));
+    }
+
+    // A missing field or key starts from zero.
+    run(&mut store, &[b"DEL", b"h"]);
+    assert_eq!(
+        run(&mut store, &[b"HINCRBY", b"h", b"f", b"-3"]),
+        RespFrame::Integer(-3)
+    );
+}
+
+#[test]
+fn hincrbyfloat_rejects_fields_that_are_not_strict_floats() {
+    let mut store = Store::new();
+    let not_float = err("ERR hash value is not a float");
+    for stored in [
+        &b"abc"[..],
+        b"",
+        b" 1.5",
+        b"1.5 ",
+        b"1.5abc",
+        b"nan",
+        b"1e",
+        b".",
+    ] {
+        assert_eq!(
+            field_after(&mut store, b"HINCRBYFLOAT", stored, b"1"),
+            not_float,
+            "{stored:?}"
+        );
+        assert_eq!(
+            run(&mut store, &[b"HGET", b"h", b"f"]),
+            bulk(stored),
+            "{stored:?} must be left untouched"
+        );
+    }
+}
+
+#[test]
+fn hincrbyfloat_accepts_exponents_and_signs_in_the_stored_field() {
+    let mut store = Store::new();
+    let cases: &[(Case, &[u8])] = &[
+        ((b"1e3", b"1"), b"1001"),
+        ((b"1.5E2", b"0.5"), b"150.5"),
+        ((b"2.5e-1", b"0.25"), b"0.5"),
+        ((b"+5", b"1"), b"6"),
+        ((b"-1e1", b"1"), b"-9"),
+        ((b"10", b"-0.5"), b"9.5"),
+    ];
+    for ((stored, increment), expected) in cases {
+        assert_eq!(
+            field_after(&mut store, b"HINCRBYFLOAT", stored, increment),
+            bulk(expected),
+            "{stored:?} + {increment:?}"
+        );
+        assert_eq!(run(&mut store, &[b"HGET", b"h", b"f"]), bulk(expected));
+    }
+}
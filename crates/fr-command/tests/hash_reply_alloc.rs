@@ -0,0 +1,209 @@
+//! Allocation gate for the generic collection reply builders.
+//!
+//! HGETALL, HKEYS, HVALS, HMGET, SMEMBERS and ZRANGE WITHSCORES used to clone
+//! every element out of the store into an owned `Vec`, then move those into
+//! reply frames. The handlers now build each frame straight from the store's
+//! borrowed scan, so a reply costs one allocation per bulk string plus a
+//! constant. The shared `alloc_meter` counts allocations on the measuring
+//! thread only.
+
+#[path = "support/alloc_meter.rs"]
+mod alloc_meter;
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::{Store, redis_score_to_string};
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv, run_ok as run};
+
+const FIELDS: usize = 10_000;
+/// Allocations a reply may make on top of one per returned bulk string: the
+/// reply `Vec` itself plus fixed dispatch bookkeeping.
+const SLACK: usize = 64;
+
+/// Run `op` metered on this thread and count its allocations.
+fn count_allocations<T>(op: impl FnOnce() -> T) -> (T, usize) {
+    let (out, usage) = alloc_meter::measure(op);
+    (out, usage.allocations)
+}
+
+fn loaded_store() -> Store {
+    let mut store = Store::new();
+    let mut hset = argv(&[b"HSET", b"hash"]);
+    let mut sadd = argv(&[b"SADD", b"set"]);
+    let mut zadd = argv(&[b"ZADD", b"zset"]);
+    for i in 0..FIELDS {
+        hset.push(format!("field:{i}").into_bytes());
+        hset.push(format!("value:{i}").into_bytes());
+        sadd.push(format!("member:{i}").into_bytes());
+        zadd.push(format!("{i}.5").into_bytes());
+        zadd.push(format!("member:{i}").into_bytes());
+    }
+    for command in [hset, sadd, zadd] {
+        dispatch_argv(&command, &mut store, NOW).expect("load");
+    }
+    store
+}
+
+fn bulks(items: impl IntoIterator<Item = Vec<u8>>) -> RespFrame {
+    RespFrame::Array(Some(
+        items
+            .into_iter()
+            .map(|item| RespFrame::BulkString(Some(item)))
+            .collect(),
+    ))
+}
+
+/// Dispatch `parts`, check the reply against `expected` and that building it
+/// took at most `copies` allocations (one per owned bulk string) plus `SLACK`.
+fn assert_single_copy(store: &mut Store, parts: &[&[u8]], copies: usize, expected: RespFrame) {
+    let argv = argv(parts);
+    let (reply, allocations) = count_allocations(|| dispatch_argv(&argv, store, NOW));
+    let name = String::from_utf8_lossy(parts[0]).into_owned();
+    assert_eq!(
+        reply.expect("command succeeds").to_bytes(),
+        expected.to_bytes(),
+        "{name} reply changed"
+    );
+    assert!(
+        allocations <= copies + SLACK,
+        "{name}: {allocations} allocations for {copies} element copies"
+    );
+}
+
+#[test]
+fn hash_replies_copy_each_element_once() {
+    let mut store = loaded_store();
+
+    let pairs = store.hgetall(b"hash", NOW).expect("hgetall");
+    assert_eq!(pairs.len(), FIELDS);
+    // Guard against a vacuous pass: the old shape cloned every pair and then
+    // built a frame per element, so it sat well above the new bound.
+    let (_, cloned) = count_allocations(|| store.hgetall(b"hash", NOW));
+    assert!(
+        cloned >= FIELDS * 2,
+        "hgetall clone made {cloned} allocations"
+    );
+
+    let flat = pairs
+        .iter()
+        .flat_map(|(field, value)| [field.clone(), value.clone()]);
+    assert_single_copy(&mut store, &[b"HGETALL", b"hash"], FIELDS * 2, bulks(flat));
+    assert_single_copy(
+        &mut store,
+        &[b"HKEYS", b"hash"],
+        FIELDS,
+        bulks(pairs.iter().map(|(field, _)| field.clone())),
+    );
+    assert_single_copy(
+        &mut store,
+        &[b"HVALS", b"hash"],
+        FIELDS,
+        bulks(pairs.iter().map(|(_, value)| value.clone())),
+    );
+
+    let mut hmget: Vec<&[u8]> = vec![b"HMGET", b"hash"];
+    hmget.extend(pairs.iter().map(|(field, _)| field.as_slice()));
+    hmget.push(b"missing");
+    let mut values: Vec<RespFrame> = pairs
+        .iter()
+        .map(|(_, value)| RespFrame::BulkString(Some(value.clone())))
+        .collect();
+    values.push(RespFrame::BulkString(None));
+    assert_single_copy(&mut store, &hmget, FIELDS, RespFrame::Array(Some(values)));
+}
+
+#[test]
+fn set_and_scored_replies_copy_each_element_once() {
+    let mut store = loaded_store();
+
+    let members = store.smembers(b"set", NOW).expect("smembers");
+    assert_single_copy(&mut store, &[b"SMEMBERS", b"set"], FIELDS, bulks(members));
+
+    let pairs = store
+        .zrange_withscores(b"zset", 0, -1, NOW)
+        .expect("zrange_withscores");
+    // Score formatting is measured on its own; the bound only adds one copy
+    // per member on top of it.
+    let (scores, formatting) = count_allocations(|| {
+        pairs
+            .iter()
+            .map(|(_, score)| redis_score_to_string(*score).into_bytes())
+            .collect::<Vec<_>>()
+    });
+    let flat = pairs
+        .into_iter()
+        .zip(scores)
+        .flat_map(|((member, _), score)| [member, score]);
+    assert_single_copy(
+        &mut store,
+        &[b"ZRANGE", b"zset", b"0", b"-1", b"WITHSCORES"],
+        FIELDS + formatting,
+        bulks(flat),
+    );
+}
+
+#[test]
+fn resp3_and_deterministic_replies_are_unchanged() {
+    let mut store = loaded_store();
+    run(&mut store, &[b"HSET", b"small", b"b", b"2", b"a", b"1"]);
+    run(&mut store, &[b"ZADD", b"z", b"1", b"a", b"2.5", b"b"]);
+
+    store.dispatch_client_ctx.resp_protocol_version = 3;
+    let bulk = |bytes: &[u8]| RespFrame::BulkString(Some(bytes.to_vec()));
+    let RespFrame::Map(Some(mut entries)) = run(&mut store, &[b"HGETALL", b"small"]) else {
+        panic!("RESP3 HGETALL is a map");
+    };
+    entries.sort_by_key(|(field, _)| field.to_bytes());
+    assert_eq!(
+        entries,
+        vec![(bulk(b"a"), bulk(b"1")), (bulk(b"b"), bulk(b"2"))]
+    );
+    assert_eq!(
+        run(&mut store, &[b"ZRANGE", b"z", b"0", b"-1", b"WITHSCORES"]),
+        RespFrame::Array(Some(vec![
+            RespFrame::Array(Some(vec![bulk(b"a"), RespFrame::double_from_f64(1.0)])),
+            RespFrame::Array(Some(vec![bulk(b"b"), RespFrame::double_from_f64(2.5)])),
+        ]))
+    );
+    assert_eq!(
+        run(&mut store, &[b"HGETALL", b"missing"]),
+        RespFrame::Map(Some(Vec::new()))
+    );
+    store.dispatch_client_ctx.resp_protocol_version = 2;
+
+    // Deterministic iteration still sorts every unordered reply.
+    store.set_deterministic_iteration(true);
+    assert_eq!(
+        run(&mut store, &[b"HGETALL", b"small"]),
+        bulks([b"a".to_vec(), b"1".to_vec(), b"b".to_vec(), b"2".to_vec()])
+    );
+    assert_eq!(
+        run(&mut store, &[b"HKEYS", b"small"]),
+        bulks([b"a".to_vec(), b"b".to_vec()])
+    );
+    assert_eq!(
+        run(&mut store, &[b"HVALS", b"small"]),
+        bulks([b"1".to_vec(), b"2".to_vec()])
+    );
+    let RespFrame::Array(Some(members)) = run(&mut store, &[b"SMEMBERS", b"set"]) else {
+        panic!("SMEMBERS is an array");
+    };
+    let members: Vec<Vec<u8>> = members
+        .into_iter()
+        .map(|member| match member {
+            RespFrame::BulkString(Some(member)) => member,
+            other => panic!("unexpected SMEMBERS element {other:?}"),
+        })
+        .collect();
+    assert_eq!(members.len(), FIELDS);
+    assert!(members.is_sorted());
+
+    run(&mut store, &[b"SET", b"str", b"v"]);
+    for read in [&b"HGETALL"[..], b"HKEYS", b"HVALS", b"SMEMBERS"] {
+        let err = dispatch_argv(&argv(&[read, b"str"]), &mut store, NOW).unwrap_err();
+        assert_eq!(err.code(), "WRONGTYPE");
+    }
+}
@@ -0,0 +1,94 @@
+//! String mutations on INCR-created (int-encoded) values.
+//!
+//! INCR leaves an integer-encoded string; APPEND/SETRANGE/SETBIT must rewrite
+//! it as raw bytes, GETRANGE must read it without changing it, and the next
+//! INCR must re-parse whatever bytes are there. Interleaving all of them on
+//! one key checks that there is a single source of truth for the value.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, run};
+
+fn encoding(store: &mut Store) -> RespFrame {
+    run(store, &[b"OBJECT", b"ENCODING", b"counter"])
+}
+
+#[test]
+fn incr_append_getrange_setrange_setbit_interleave() {
+    let mut store = Store::new();
+
+    assert_eq!(
+        run(&mut store, &[b"INCR", b"counter"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(encoding(&mut store), bulk(b"int"));
+
+    assert_eq!(
+        run(&mut store, &[b"APPEND", b"counter", b"00"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"counter"]), bulk(b"100"));
+    assert_eq!(encoding(&mut store), bulk(b"raw"));
+    assert_eq!(
+        run(&mut store, &[b"GETRANGE", b"counter", b"0", b"1"]),
+        bulk(b"10")
+    );
+
+    assert_eq!(
+        run(&mut store, &[b"INCR", b"counter"]),
+        RespFrame::Integer(101)
+    );
+    assert_eq!(
+        run(&mut store, &[b"STRLEN", b"counter"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(encoding(&mut store), bulk(b"int"));
+    // Reading a range must not disturb the int encoding.
+    assert_eq!(
+        run(&mut store, &[b"GETRANGE", b"counter", b"-1", b"-1"]),
+        bulk(b"1")
+    );
+    assert_eq!(encoding(&mut store), bulk(b"int"));
+
+    assert_eq!(
+        run(&mut store, &[b"SETRANGE", b"counter", b"0", b"9"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"counter"]), bulk(b"901"));
+    assert_eq!(encoding(&mut store), bulk(b"raw"));
+    assert_eq!(
+        run(&mut store, &[b"INCRBY", b"counter", b"1"]),
+        RespFrame::Integer(902)
+    );
+
+    // '2' (0x32) -> '3' (0x33): set the low bit of the last byte.
+    assert_eq!(
+        run(&mut store, &[b"SETBIT", b"counter", b"23", b"1"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"counter"]), bulk(b"903"));
+    assert_eq!(encoding(&mut store), bulk(b"raw"));
+    assert_eq!(
+        run(&mut store, &[b"DECR", b"counter"]),
+        RespFrame::Integer(902)
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"counter"]), bulk(b"902"));
+}
+
+#[test]
+fn append_making_value_non_numeric_fails_next_incr() {
+    let mut store = Store::new();
+    run(&mut store, &[b"INCRBY", b"counter", b"41"]);
+    assert_eq!(
+        run(&mut store, &[b"APPEND", b"counter", b"x"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(
+        run(&mut store, &[b"INCR", b"counter"]),
+        RespFrame::Error("ERR value is not an integer or out of range".to_string())
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"counter"]), bulk(b"41x"));
+}
@@ -0,0 +1,134 @@
+//! Peak-memory gate for LCS.
+//!
+//! LCS used to fill the full `(m+1) x (n+1)` u32 table for every mode, so a
+//! LEN over two large values could abort the process on allocation. LEN now
+//! keeps one rolling bit vector the size of the shorter string, and the
+//! backtracking modes record one bit per cell up to the proto-max-bulk-len
+//! threshold. The shared `alloc_meter` tracks live/peak heap bytes on the
+//! measuring thread only.
+
+#[path = "support/alloc_meter.rs"]
+mod alloc_meter;
+
+use std::time::{Duration, Instant};
+
+use fr_command::{CommandError, dispatch_argv};
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::NOW;
+
+const MIB: isize = 1024 * 1024;
+
+/// Run `op` metered on this thread and report the most heap bytes it held
+/// at once.
+fn peak_of<T>(op: impl FnOnce() -> T) -> (T, isize) {
+    let (out, usage) = alloc_meter::measure(op);
+    (out, usage.peak)
+}
+
+fn lcs(store: &mut Store, mode: Option<&[u8]>) -> Result<RespFrame, CommandError> {
+    let mut argv = vec![b"LCS".to_vec(), b"a".to_vec(), b"b".to_vec()];
+    argv.extend(mode.map(<[u8]>::to_vec));
+    dispatch_argv(&argv, store, NOW)
+}
+
+/// `len` pseudo-random lowercase bytes.
+fn random_text(len: usize, mut state: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            b'a' + (state % 26) as u8
+        })
+        .collect()
+}
+
+/// `a` holding `long` and `b` holding every `stride`-th byte of it, so the
+/// LCS is exactly `b`.
+fn store_with_subsequence(long: usize, stride: usize) -> (Store, Vec<u8>) {
+    let text = random_text(long, 0x9E37_79B9_7F4A_7C15);
+    let sub: Vec<u8> = text.iter().copied().step_by(stride).collect();
+    let mut store = Store::new();
+    store.set(b"a".to_vec(), text, None, NOW);
+    store.set(b"b".to_vec(), sub.clone(), None, NOW);
+    (store, sub)
+}
+
+#[test]
+fn len_over_a_megabyte_runs_in_linear_memory() {
+    let (mut store, sub) = store_with_subsequence(1024 * 1024, 256);
+    let started = Instant::now();
+    let (reply, peak) = peak_of(|| lcs(&mut store, Some(b"LEN")));
+    assert_eq!(
+        reply,
+        Ok(RespFrame::Integer(i64::try_from(sub.len()).unwrap()))
+    );
+    // The values are copied out of the store (~1 MiB); the quadratic table
+    // would have been 16 GiB.
+    assert!(peak < 4 * MIB, "LCS LEN peaked at {peak} bytes");
+    assert!(
+        started.elapsed() < Duration::from_secs(60),
+        "LCS LEN took {:?}",
+        started.elapsed()
+    );
+}
+
+#[test]
+fn len_between_unrelated_megabyte_strings_completes() {
+    let mut store = Store::new();
+    store.set(b"a".to_vec(), random_text(1024 * 1024, 1), None, NOW);
+    store.set(b"b".to_vec(), random_text(2048, 2), None, NOW);
+    let (reply, peak) = peak_of(|| lcs(&mut store, Some(b"LEN")));
+    let Ok(RespFrame::Integer(len)) = reply else {
+        panic!("LCS LEN reply: {reply:?}");
+    };
+    // Over a 26-letter alphabet the shorter string almost entirely embeds.
+    assert!((1..=2048).contains(&len), "{len}");
+    assert!(peak < 4 * MIB, "LCS LEN peaked at {peak} bytes");
+}
+
+#[test]
+fn plain_and_idx_stay_far_below_the_quadratic_table() {
+    // 20k x 2k: the u32 table alone would be 160 MB.
+    let (mut store, sub) = store_with_subsequence(20_000, 10);
+    assert_eq!(sub.len(), 2_000);
+
+    let (reply, peak) = peak_of(|| lcs(&mut store, None));
+    assert_eq!(reply, Ok(RespFrame::BulkString(Some(sub.clone()))));
+    assert!(peak < 16 * MIB, "LCS peaked at {peak} bytes");
+
+    let (reply, peak) = peak_of(|| lcs(&mut store, Some(b"IDX")));
+    let Ok(RespFrame::Array(Some(reply))) = reply else {
+        panic!("LCS IDX reply: {reply:?}");
+    };
+    assert_eq!(
+        reply.last(),
+        Some(&RespFrame::Integer(i64::try_from(sub.len()).unwrap()))
+    );
+    assert!(peak < 16 * MIB, "LCS IDX peaked at {peak} bytes");
+}
+
+#[test]
+fn backtracking_modes_keep_the_proto_max_bulk_len_guard() {
+    let insufficient = Err(CommandError::Custom(
+        "ERR Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len".to_string(),
+    ));
+    let (mut store, _) = store_with_subsequence(1024 * 1024, 256);
+    let (reply, peak) = peak_of(|| lcs(&mut store, None));
+    assert_eq!(reply, insufficient);
+    assert!(peak < 4 * MIB, "rejected LCS peaked at {peak} bytes");
+    assert_eq!(lcs(&mut store, Some(b"IDX")), insufficient);
+
+    // 100k x 2k: upstream's 800 MB table exceeds the default 512 MB limit;
+    // raising the limit admits the pair at one bit per cell.
+    let (mut store, sub) = store_with_subsequence(100_000, 50);
+    assert_eq!(lcs(&mut store, None), insufficient);
+    store.proto_max_bulk_len = 1024 * 1024 * 1024;
+    let (reply, peak) = peak_of(|| lcs(&mut store, None));
+    assert_eq!(reply, Ok(RespFrame::BulkString(Some(sub))));
+    assert!(peak < 64 * MIB, "LCS peaked at {peak} bytes");
+}
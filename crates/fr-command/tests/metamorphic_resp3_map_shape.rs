@@ -172,6 +172,7 @@ fn mr_memory_stats_resp2_array_matches_resp3_map_shape() {
     );
 }
 
+#[cfg(feature = "scripting")]
 #[test]
 fn mr_function_stats_resp3_outer_keys_match_resp2_array_keys() {
     // asvh1 contract is doubly-nested: outer 2-entry Map, inner
@@ -451,6 +452,74 @@ fn mr_latency_histogram_resp3_map_outer_matches_resp2_flat_alternating() {
     }
 }
 
+/// Split a space-separated command line into argv.
+fn argv(line: &str) -> Vec<Vec<u8>> {
+    line.split_whitespace()
+        .map(|a| a.as_bytes().to_vec())
+        .collect()
+}
+
+#[test]
+fn mr_hgetall_and_config_get_resp3_map_matches_resp2_flat_alternating() {
+    let seed = |store: &mut Store| {
+        dispatch_argv(&argv("HSET h a 1 b 2"), store, 0).expect("HSET");
+    };
+    let (resp2, resp3) = run_both_protocols_with_seed(seed, &argv("HGETALL h"));
+    assert_shape_contract("HGETALL", &resp2, &resp3);
+    let RespFrame::Map(Some(entries)) = &resp3 else {
+        unreachable!("guarded above"); // ubs:ignore — AI triage
+    };
+    assert_eq!(entries.len(), 2);
+
+    // A missing hash is an empty aggregate of the protocol's kind.
+    let (resp2, resp3) = run_both_protocols(&argv("HGETALL missing"));
+    assert_eq!(resp2, RespFrame::Array(Some(Vec::new())));
+    assert_eq!(resp3, RespFrame::Map(Some(Vec::new())));
+
+    let (resp2, resp3) = run_both_protocols(&argv("CONFIG GET *-max-listpack-entries timeout"));
+    assert_shape_contract("CONFIG GET", &resp2, &resp3);
+    let RespFrame::Map(Some(entries)) = &resp3 else {
+        unreachable!("guarded above"); // ubs:ignore — AI triage
+    };
+    assert!(entries.len() > 2, "the glob matches several parameters");
+    assert!(
+        entries
+            .iter()
+            .any(|(k, _)| *k == RespFrame::BulkString(Some(b"timeout".to_vec())))
+    );
+}
+
+#[cfg(feature = "streams")]
+#[test]
+fn mr_xinfo_resp3_maps_match_resp2_flat_alternating() {
+    let seed = |store: &mut Store| {
+        for line in [
+            "XADD s 1-0 f v",
+            "XADD s 2-0 f v",
+            "XGROUP CREATE s g 0",
+            "XREADGROUP GROUP g c COUNT 1 STREAMS s >",
+        ] {
+            dispatch_argv(&argv(line), store, 0).expect("seed");
+        }
+    };
+    let (resp2, resp3) = run_both_protocols_with_seed(seed, &argv("XINFO STREAM s"));
+    assert_shape_contract("XINFO STREAM", &resp2, &resp3);
+
+    for (label, line) in [
+        ("XINFO GROUPS", "XINFO GROUPS s"),
+        ("XINFO CONSUMERS", "XINFO CONSUMERS s g"),
+    ] {
+        let (resp2, resp3) = run_both_protocols_with_seed(seed, &argv(line));
+        let (RespFrame::Array(Some(rows2)), RespFrame::Array(Some(rows3))) = (&resp2, &resp3)
+        else {
+            panic!("{label}: both protocols reply with an outer array"); // ubs:ignore — AI triage
+        };
+        assert_eq!(rows2.len(), 1, "{label}");
+        assert_eq!(rows3.len(), 1, "{label}");
+        assert_shape_contract(label, &rows2[0], &rows3[0]);
+    }
+}
+
 /// Recursively assert that every COMMAND DOCS argument entry reachable
 /// from `frame` is a RESP3 Map (not a flat Array), descending through
 /// the nested `arguments` sub-array of oneof/block args. Returns the
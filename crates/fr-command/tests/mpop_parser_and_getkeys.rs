@@ -0,0 +1,204 @@
+//! Option parsing for the numkeys-prefixed pop commands and COMMAND GETKEYS
+//! extraction for every numkeys-style key spec.
+//!
+//! LMPOP/ZMPOP/BLMPOP/BZMPOP take exactly one direction token right after the
+//! keys, then at most one `COUNT n` with n >= 1, and nothing else. The same
+//! numkeys prefix decides which arguments GETKEYS reports as keys.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{err, run};
+
+type Argv<'a> = &'a [&'a [u8]];
+type Case<'a> = (Argv<'a>, &'a RespFrame);
+
+fn keys(names: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        names
+            .iter()
+            .map(|name| RespFrame::BulkString(Some(name.to_vec())))
+            .collect(),
+    ))
+}
+
+fn getkeys(store: &mut Store, argv: &[&[u8]]) -> RespFrame {
+    let mut full: Vec<&[u8]> = vec![b"COMMAND", b"GETKEYS"];
+    full.extend_from_slice(argv);
+    run(store, &full)
+}
+
+#[test]
+fn mpop_rejects_malformed_option_tails() {
+    let mut store = Store::new();
+    assert_eq!(
+        run(&mut store, &[b"RPUSH", b"k1", b"a", b"b", b"c"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(
+        run(&mut store, &[b"ZADD", b"z", b"1", b"a", b"2", b"b"]),
+        RespFrame::Integer(2)
+    );
+
+    let syntax = err("ERR syntax error");
+    let bad_count = err("ERR count should be greater than 0");
+    let cases: &[Case] = &[
+        // Trailing duplicate direction.
+        (
+            &[
+                b"LMPOP", b"2", b"k1", b"k2", b"LEFT", b"COUNT", b"5", b"LEFT",
+            ],
+            &syntax,
+        ),
+        (&[b"LMPOP", b"1", b"k1", b"LEFT", b"RIGHT"], &syntax),
+        (&[b"ZMPOP", b"1", b"z", b"MIN", b"MAX"], &syntax),
+        (
+            &[
+                b"BLMPOP", b"0", b"1", b"k1", b"LEFT", b"COUNT", b"1", b"LEFT",
+            ],
+            &syntax,
+        ),
+        (
+            &[b"BZMPOP", b"0", b"1", b"z", b"MIN", b"COUNT", b"1", b"MAX"],
+            &syntax,
+        ),
+        // COUNT before the direction.
+        (&[b"LMPOP", b"1", b"k1", b"COUNT", b"1", b"LEFT"], &syntax),
+        (&[b"ZMPOP", b"1", b"z", b"COUNT", b"1", b"MIN"], &syntax),
+        (
+            &[b"BLMPOP", b"0", b"1", b"k1", b"COUNT", b"1", b"LEFT"],
+            &syntax,
+        ),
+        (
+            &[b"BZMPOP", b"0", b"1", b"z", b"COUNT", b"1", b"MIN"],
+            &syntax,
+        ),
+        // COUNT given twice.
+        (
+            &[
+                b"LMPOP", b"1", b"k1", b"LEFT", b"COUNT", b"1", b"COUNT", b"2",
+            ],
+            &syntax,
+        ),
+        (
+            &[b"ZMPOP", b"1", b"z", b"MIN", b"COUNT", b"1", b"COUNT", b"2"],
+            &syntax,
+        ),
+        (
+            &[
+                b"BLMPOP", b"0", b"1", b"k1", b"LEFT", b"COUNT", b"1", b"COUNT", b"2",
+            ],
+            &syntax,
+        ),
+        (
+            &[
+                b"BZMPOP", b"0", b"1", b"z", b"MIN", b"COUNT", b"1", b"COUNT", b"2",
+            ],
+            &syntax,
+        ),
+        // COUNT without a value.
+        (&[b"LMPOP", b"1", b"k1", b"LEFT", b"COUNT"], &syntax),
+        (&[b"ZMPOP", b"1", b"z", b"MIN", b"COUNT"], &syntax),
+        // COUNT zero or negative.
+        (
+            &[b"LMPOP", b"1", b"k1", b"LEFT", b"COUNT", b"0"],
+            &bad_count,
+        ),
+        (&[b"ZMPOP", b"1", b"z", b"MIN", b"COUNT", b"0"], &bad_count),
+        (
+            &[b"BLMPOP", b"0", b"1", b"k1", b"LEFT", b"COUNT", b"0"],
+            &bad_count,
+        ),
+        (
+            &[b"BZMPOP", b"0", b"1", b"z", b"MAX", b"COUNT", b"-1"],
+            &bad_count,
+        ),
+    ];
+    for (argv, expected) in cases {
+        assert_eq!(&run(&mut store, argv), *expected, "{argv:?}");
+    }
+
+    // Every rejection happened before popping anything.
+    assert_eq!(run(&mut store, &[b"LLEN", b"k1"]), RespFrame::Integer(3));
+    assert_eq!(run(&mut store, &[b"ZCARD", b"z"]), RespFrame::Integer(2));
+
+    // The well-formed shapes still pop.
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"LMPOP", b"2", b"k1", b"k2", b"LEFT", b"COUNT", b"2"]
+        ),
+        RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"k1".to_vec())),
+            keys(&[b"a", b"b"]),
+        ]))
+    );
+}
+
+#[test]
+fn getkeys_follows_numkeys_prefix() {
+    let mut store = Store::new();
+    let cases: &[(Argv, Argv)] = &[
+        (
+            &[b"LMPOP", b"2", b"k1", b"k2", b"LEFT", b"COUNT", b"5"],
+            &[b"k1", b"k2"],
+        ),
+        (&[b"ZMPOP", b"1", b"z", b"MIN"], &[b"z"]),
+        (
+            &[b"BLMPOP", b"0", b"2", b"k1", b"k2", b"RIGHT"],
+            &[b"k1", b"k2"],
+        ),
+        (&[b"BZMPOP", b"0", b"1", b"z", b"MAX"], &[b"z"]),
+        (
+            &[b"SINTERCARD", b"2", b"s1", b"s2", b"LIMIT", b"1"],
+            &[b"s1", b"s2"],
+        ),
+        (&[b"ZINTERCARD", b"2", b"z1", b"z2"], &[b"z1", b"z2"]),
+        (
+            &[b"ZDIFF", b"2", b"z1", b"z2", b"WITHSCORES"],
+            &[b"z1", b"z2"],
+        ),
+        (
+            &[b"ZUNION", b"2", b"z1", b"z2", b"WEIGHTS", b"1", b"2"],
+            &[b"z1", b"z2"],
+        ),
+        (
+            &[b"ZINTER", b"2", b"z1", b"z2", b"AGGREGATE", b"MAX"],
+            &[b"z1", b"z2"],
+        ),
+        (
+            &[b"ZUNIONSTORE", b"dst", b"2", b"z1", b"z2"],
+            &[b"dst", b"z1", b"z2"],
+        ),
+        (
+            &[b"EVAL", b"return 1", b"2", b"k1", b"k2", b"arg"],
+            &[b"k1", b"k2"],
+        ),
+    ];
+    for (argv, expected) in cases {
+        assert_eq!(getkeys(&mut store, argv), keys(expected), "{argv:?}");
+    }
+}
+
+#[test]
+fn getkeys_rejects_bad_numkeys() {
+    let mut store = Store::new();
+    let invalid = err("ERR Invalid arguments specified for command");
+    let cases: &[&[&[u8]]] = &[
+        &[b"LMPOP", b"0", b"k1", b"LEFT"],
+        &[b"LMPOP", b"x", b"k1", b"LEFT"],
+        &[b"ZMPOP", b"3", b"z", b"MIN"],
+        &[b"BLMPOP", b"0", b"-1", b"k1", b"LEFT"],
+        &[b"BZMPOP", b"0", b"5", b"z", b"MIN"],
+        &[b"SINTERCARD", b"0", b"s1"],
+        &[b"ZINTERCARD", b"3", b"z1", b"z2"],
+        &[b"ZDIFF", b"abc", b"z1"],
+        &[b"ZUNION", b"-2", b"z1", b"z2"],
+        &[b"ZINTER", b"4", b"z1", b"z2"],
+    ];
+    for argv in cases {
+        assert_eq!(getkeys(&mut store, argv), invalid, "{argv:?}");
+    }
+}
@@ -0,0 +1,122 @@
+//! OBJECT IDLETIME / OBJECT FREQ over the per-key access metadata.
+//!
+//! Under an LRU (or non-evicting) policy every reader and TOUCH restamps
+//! the key's last access, and IDLETIME reports whole seconds since then;
+//! FREQ is refused. Under an LFU policy it is the other way round: FREQ
+//! reports the logarithmic counter (decayed by elapsed minutes) and
+//! IDLETIME is refused. OBJECT itself never counts as an access.
+
+use fr_protocol::RespFrame;
+use fr_store::{MaxmemoryPolicy, Store};
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, run_str_at as run_at};
+
+const SEC: u64 = 1_000;
+
+fn idletime(store: &mut Store, now_ms: u64, key: &str) -> RespFrame {
+    run_at(store, now_ms, &["OBJECT", "IDLETIME", key])
+}
+
+fn freq(store: &mut Store, now_ms: u64, key: &str) -> RespFrame {
+    run_at(store, now_ms, &["OBJECT", "FREQ", key])
+}
+
+#[test]
+fn idletime_grows_until_the_next_read() {
+    let mut store = Store::new();
+    run_at(&mut store, NOW, &["SET", "k", "v"]);
+    assert_eq!(
+        idletime(&mut store, NOW + 10 * SEC, "k"),
+        RespFrame::Integer(10)
+    );
+    // OBJECT IDLETIME is not itself an access.
+    assert_eq!(
+        idletime(&mut store, NOW + 12 * SEC + 999, "k"),
+        RespFrame::Integer(12)
+    );
+
+    run_at(&mut store, NOW + 12 * SEC, &["GET", "k"]);
+    assert_eq!(
+        idletime(&mut store, NOW + 15 * SEC, "k"),
+        RespFrame::Integer(3)
+    );
+
+    assert_eq!(
+        run_at(&mut store, NOW + 20 * SEC, &["TOUCH", "k"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        idletime(&mut store, NOW + 20 * SEC, "k"),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(
+        idletime(&mut store, NOW, "missing"),
+        RespFrame::BulkString(None)
+    );
+}
+
+#[test]
+fn aggregate_readers_restamp_the_access_time() {
+    let mut store = Store::new();
+    run_at(&mut store, NOW, &["HSET", "h", "f", "v"]);
+    run_at(&mut store, NOW, &["RPUSH", "l", "a", "b"]);
+    run_at(&mut store, NOW, &["SADD", "s", "m"]);
+    run_at(&mut store, NOW, &["ZADD", "z", "1", "m"]);
+
+    let later = NOW + 60 * SEC;
+    run_at(&mut store, later, &["HGET", "h", "f"]);
+    run_at(&mut store, later, &["LRANGE", "l", "0", "-1"]);
+    run_at(&mut store, later, &["SISMEMBER", "s", "m"]);
+    run_at(&mut store, later, &["ZSCORE", "z", "m"]);
+    for key in ["h", "l", "s", "z"] {
+        assert_eq!(
+            idletime(&mut store, later + 5 * SEC, key),
+            RespFrame::Integer(5),
+            "{key}"
+        );
+    }
+}
+
+#[test]
+fn freq_is_refused_outside_lfu_and_idletime_inside_it() {
+    let mut store = Store::new();
+    run_at(&mut store, NOW, &["SET", "k", "v"]);
+    let RespFrame::Error(message) = freq(&mut store, NOW, "k") else {
+        panic!("OBJECT FREQ under noeviction must be an error");
+    };
+    assert!(
+        message.starts_with("ERR An LFU maxmemory policy is not selected"),
+        "{message}"
+    );
+    assert_eq!(
+        freq(&mut store, NOW, "missing"),
+        RespFrame::BulkString(None)
+    );
+
+    store.maxmemory_policy = MaxmemoryPolicy::AllkeysLfu;
+    let RespFrame::Error(message) = idletime(&mut store, NOW, "k") else {
+        panic!("OBJECT IDLETIME under an LFU policy must be an error");
+    };
+    assert!(
+        message.starts_with("ERR An LFU maxmemory policy is selected"),
+        "{message}"
+    );
+}
+
+#[test]
+fn lfu_counter_starts_at_five_bumps_on_read_and_decays_per_minute() {
+    let mut store = Store::new();
+    store.maxmemory_policy = MaxmemoryPolicy::AllkeysLfu;
+    run_at(&mut store, NOW, &["SET", "k", "v"]);
+    assert_eq!(freq(&mut store, NOW, "k"), RespFrame::Integer(5));
+    // From the initial value the first hit always increments.
+    run_at(&mut store, NOW, &["GET", "k"]);
+    assert_eq!(freq(&mut store, NOW, "k"), RespFrame::Integer(6));
+    // lfu-decay-time 1: one point per elapsed minute.
+    assert_eq!(
+        freq(&mut store, NOW + 180 * SEC, "k"),
+        RespFrame::Integer(3)
+    );
+}
@@ -0,0 +1,81 @@
+//! Every RESP2 score reply is `format_redis_double` text.
+//!
+//! ZSCORE, ZMSCORE, ZINCRBY, ZADD INCR and ZRANGE WITHSCORES all render a
+//! stored score through the same d2string port, so one member reads back
+//! byte-identically whichever command fetched it.
+
+use fr_protocol::{RespFrame, format_redis_double};
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, run_str as run};
+
+/// (score argument, stored value)
+const SCORES: [(&str, f64); 8] = [
+    ("0.1", 0.1),
+    ("3.0", 3.0),
+    ("1e21", 1e21),
+    ("9007199254740992", 9_007_199_254_740_992.0),
+    ("1.7976931348623157e308", f64::MAX),
+    ("-2.5e-10", -2.5e-10),
+    ("inf", f64::INFINITY),
+    ("-inf", f64::NEG_INFINITY),
+];
+
+#[test]
+fn every_score_reply_uses_the_same_text() {
+    let mut store = Store::new();
+    for (idx, (arg, value)) in SCORES.iter().enumerate() {
+        let member = format!("m{idx}");
+        let want = bulk(format_redis_double(*value));
+        assert_eq!(
+            run(&mut store, &["ZADD", "z", arg, &member]),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(run(&mut store, &["ZSCORE", "z", &member]), want, "{arg}");
+        assert_eq!(
+            run(&mut store, &["ZMSCORE", "z", &member]),
+            RespFrame::Array(Some(vec![want.clone()])),
+            "{arg}"
+        );
+        assert_eq!(
+            run(&mut store, &["ZADD", "fresh", "INCR", arg, &member]),
+            want,
+            "{arg}"
+        );
+        assert_eq!(
+            run(&mut store, &["ZINCRBY", "other", arg, &member]),
+            want,
+            "{arg}"
+        );
+        let by_score = run(&mut store, &["ZRANGEBYSCORE", "z", arg, arg, "WITHSCORES"]);
+        assert_eq!(
+            by_score,
+            RespFrame::Array(Some(vec![bulk(&member), want])),
+            "{arg}"
+        );
+    }
+}
+
+#[test]
+fn arithmetic_results_use_the_shortest_round_trip_text() {
+    let mut store = Store::new();
+    run(&mut store, &["ZADD", "z", "0.1", "m"]);
+    assert_eq!(
+        run(&mut store, &["ZINCRBY", "z", "0.2", "m"]),
+        bulk("0.30000000000000004")
+    );
+    // `-0` is stored as `+0`, so it reads back without the sign.
+    run(&mut store, &["ZADD", "z", "-0", "zero"]);
+    assert_eq!(run(&mut store, &["ZSCORE", "z", "zero"]), bulk("0"));
+    assert_eq!(
+        run(&mut store, &["ZRANGE", "z", "0", "-1", "WITHSCORES"]),
+        RespFrame::Array(Some(vec![
+            bulk("zero"),
+            bulk("0"),
+            bulk("m"),
+            bulk("0.30000000000000004"),
+        ]))
+    );
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "scripting")]
+
 use fr_command::CommandError;
 use fr_command::dispatch_argv;
 use fr_protocol::RespFrame;
@@ -0,0 +1,176 @@
+//! The SET option grammar, row by row against captured Redis 7.2 replies.
+//!
+//! Upstream scans every option before validating the chosen expire value:
+//! repeating a flag or the same expire kind is accepted (the last value wins),
+//! different expire kinds, NX with XX, a missing value and unknown tokens are
+//! syntax errors, and only then is the expire value parsed. GET reads the old
+//! value before the NX/XX gate, so a wrong-typed key fails without writing.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, err, run};
+
+#[derive(Clone, Copy, Debug)]
+enum Setup {
+    Absent,
+    Str,
+    List,
+}
+
+/// `(setup, options after SET k new, reply, string value of k afterwards)`.
+type Case<'a> = (Setup, &'a [&'a [u8]], &'a RespFrame, Option<&'a [u8]>);
+
+fn prepared(setup: Setup) -> Store {
+    let mut store = Store::new();
+    match setup {
+        Setup::Absent => {}
+        Setup::Str => {
+            run(&mut store, &[b"SET", b"k", b"old", b"PX", b"5000"]);
+        }
+        Setup::List => {
+            run(&mut store, &[b"RPUSH", b"k", b"item"]);
+        }
+    }
+    store
+}
+
+#[test]
+fn set_option_grammar_matches_redis() {
+    let ok = RespFrame::SimpleString("OK".to_string());
+    let nil = RespFrame::BulkString(None);
+    let old = bulk(b"old");
+    let syntax = err("ERR syntax error");
+    let not_int = err("ERR value is not an integer or out of range");
+    let bad_expire = err("ERR invalid expire time in 'set' command");
+    let wrongtype = err("WRONGTYPE Operation against a key holding the wrong kind of value");
+    let new = Some(&b"new"[..]);
+    let kept = Some(&b"old"[..]);
+
+    // A `None` value afterwards means k is absent or not a string.
+    let cases: &[Case] = &[
+        // Plain and conditional writes.
+        (Setup::Absent, &[], &ok, new),
+        (Setup::Str, &[], &ok, new),
+        (Setup::Absent, &[b"NX"], &ok, new),
+        (Setup::Str, &[b"NX"], &nil, kept),
+        (Setup::Absent, &[b"XX"], &nil, None),
+        (Setup::Str, &[b"xx"], &ok, new),
+        // Repeated flags are tolerated.
+        (Setup::Absent, &[b"NX", b"NX"], &ok, new),
+        (Setup::Str, &[b"XX", b"xx"], &ok, new),
+        (Setup::Str, &[b"KEEPTTL", b"KEEPTTL"], &ok, new),
+        (Setup::Str, &[b"GET", b"GET"], &old, new),
+        (Setup::Str, &[b"EX", b"10", b"EX", b"20"], &ok, new),
+        // NX and XX exclude each other in either order, even when repeated.
+        (Setup::Absent, &[b"NX", b"XX"], &syntax, None),
+        (Setup::Str, &[b"XX", b"NX"], &syntax, kept),
+        (Setup::Str, &[b"NX", b"NX", b"XX"], &syntax, kept),
+        // Different expire kinds conflict; KEEPTTL counts as a kind.
+        (Setup::Str, &[b"EX", b"10", b"PX", b"100"], &syntax, kept),
+        (
+            Setup::Str,
+            &[b"PX", b"100", b"EXAT", b"2000"],
+            &syntax,
+            kept,
+        ),
+        (
+            Setup::Str,
+            &[b"EXAT", b"2000", b"PXAT", b"2000000"],
+            &syntax,
+            kept,
+        ),
+        (Setup::Str, &[b"EX", b"10", b"KEEPTTL"], &syntax, kept),
+        (Setup::Str, &[b"KEEPTTL", b"PX", b"100"], &syntax, kept),
+        // Missing values and unknown tokens.
+        (Setup::Str, &[b"EX"], &syntax, kept),
+        (Setup::Str, &[b"NX", b"PX"], &syntax, kept),
+        (Setup::Str, &[b"IDLE", b"10"], &syntax, kept),
+        (Setup::Str, &[b"PERSIST"], &syntax, kept),
+        (Setup::Str, &[b"NX", b"10"], &syntax, kept),
+        (Setup::Str, &[b"GETT"], &syntax, kept),
+        // A syntax error anywhere beats a bad expire value.
+        (Setup::Str, &[b"EX", b"abc", b"NX", b"XX"], &syntax, kept),
+        (Setup::Str, &[b"EX", b"0", b"PX", b"100"], &syntax, kept),
+        (Setup::Str, &[b"EX", b"-1", b"BOGUS"], &syntax, kept),
+        // Expire values: only the last one of a repeated kind is validated.
+        (Setup::Str, &[b"EX", b"abc"], &not_int, kept),
+        (Setup::Str, &[b"EX", b"1.5"], &not_int, kept),
+        (Setup::Str, &[b"PX", b" 10"], &not_int, kept),
+        (Setup::Str, &[b"EX", b"9223372036854775808"], &not_int, kept),
+        (Setup::Str, &[b"EX", b"-1"], &bad_expire, kept),
+        (Setup::Str, &[b"EX", b"0"], &bad_expire, kept),
+        (Setup::Str, &[b"PX", b"0"], &bad_expire, kept),
+        (Setup::Str, &[b"EXAT", b"0"], &bad_expire, kept),
+        (Setup::Str, &[b"PXAT", b"-5"], &bad_expire, kept),
+        (Setup::Str, &[b"EX", b"9223372036854776"], &bad_expire, kept),
+        (
+            Setup::Str,
+            &[b"PX", b"9223372036854775807"],
+            &bad_expire,
+            kept,
+        ),
+        (Setup::Str, &[b"EX", b"abc", b"EX", b"10"], &ok, new),
+        (Setup::Str, &[b"EX", b"10", b"EX", b"abc"], &not_int, kept),
+        (Setup::Str, &[b"EX", b"0", b"EX", b"10"], &ok, new),
+        // GET reads before the NX/XX gate and before writing.
+        (Setup::Absent, &[b"GET"], &nil, new),
+        (Setup::Str, &[b"NX", b"GET"], &old, kept),
+        (Setup::Absent, &[b"GET", b"NX"], &nil, new),
+        (Setup::Absent, &[b"XX", b"GET"], &nil, None),
+        (Setup::Str, &[b"GET", b"XX", b"PX", b"100"], &old, new),
+        (Setup::List, &[b"GET"], &wrongtype, None),
+        (Setup::List, &[b"NX", b"GET"], &wrongtype, None),
+        (Setup::List, &[b"GET", b"EX", b"0"], &bad_expire, None),
+        (Setup::List, &[], &ok, new),
+    ];
+    for &(setup, options, expected, value_after) in cases {
+        let mut store = prepared(setup);
+        let mut argv: Vec<&[u8]> = vec![b"SET", b"k", b"new"];
+        argv.extend_from_slice(options);
+        assert_eq!(&run(&mut store, &argv), expected, "{setup:?} {argv:?}");
+        let value = match run(&mut store, &[b"GET", b"k"]) {
+            RespFrame::BulkString(value) => value,
+            _ => None,
+        };
+        assert_eq!(value.as_deref(), value_after, "{setup:?} {argv:?}");
+    }
+}
+
+#[test]
+fn set_expiry_options_resolve_to_the_last_value() {
+    let pttl = |options: &[&[u8]]| {
+        let mut store = prepared(Setup::Str);
+        let mut argv: Vec<&[u8]> = vec![b"SET", b"k", b"new"];
+        argv.extend_from_slice(options);
+        assert_eq!(
+            run(&mut store, &argv),
+            RespFrame::SimpleString("OK".to_string()),
+            "{argv:?}"
+        );
+        run(&mut store, &[b"PTTL", b"k"])
+    };
+    assert_eq!(pttl(&[]), RespFrame::Integer(-1));
+    assert_eq!(
+        pttl(&[b"EX", b"10", b"EX", b"20"]),
+        RespFrame::Integer(20_000)
+    );
+    assert_eq!(
+        pttl(&[b"PX", b"abc", b"PX", b"300"]),
+        RespFrame::Integer(300)
+    );
+    assert_eq!(pttl(&[b"KEEPTTL", b"KEEPTTL"]), RespFrame::Integer(5_000));
+    assert_eq!(
+        pttl(&[b"PXAT", b"1000100", b"PXAT", b"1000200"]),
+        RespFrame::Integer(200)
+    );
+    assert_eq!(pttl(&[b"EXAT", b"1100"]), RespFrame::Integer(100_000));
+
+    let mut store = Store::new();
+    assert_eq!(
+        run(&mut store, &[b"SET", b"k"]),
+        err("ERR wrong number of arguments for 'set' command")
+    );
+}
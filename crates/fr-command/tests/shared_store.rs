@@ -0,0 +1,474 @@
+//! `SharedStore`: the sharded async handle.
+//!
+//! Writers hammer INCR on counters spread over every shard, MSET of a
+//! hash-tagged key pair to one value, and SMOVE between two hash-tagged sets.
+//! Readers check the invariants those writes preserve: the pair is equal, and
+//! the union of the sets keeps its size. A walker runs DBSIZE and KEYS, which
+//! lock every shard, at the same time. The final counters prove no increment
+//! was lost. The other tests cover:
+//! - routing: CROSSSLOT and the keyspace-wide fan-out;
+//! - global commands (CONFIG SET, INFO, SCRIPT LOAD) and script key locality;
+//! - the blocking-pool threshold;
+//! - futures dropped mid-wait and mid-command;
+//! - per-shard poisoning;
+//! - the budgeted scan.
+
+#![cfg(feature = "shared-store")]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use fr_command::{CommandError, SharedStore};
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv, bulk, err};
+
+const SHARDS: usize = 8;
+const WRITERS: usize = 4;
+const READERS: usize = 2;
+const ROUNDS: usize = 1_024;
+const COUNTERS: usize = 16;
+const MEMBERS: usize = 32;
+/// Members of each big set; their union is over the blocking threshold.
+const BIG: usize = 100_000;
+
+async fn run(store: &SharedStore, parts: &[&[u8]]) -> RespFrame {
+    store
+        .dispatch(argv(parts), NOW)
+        .await
+        .unwrap_or_else(|err| err.to_resp())
+}
+
+fn int(n: usize) -> RespFrame {
+    RespFrame::Integer(i64::try_from(n).expect("count fits i64"))
+}
+
+/// A key that does not live on `shard`.
+fn key_off_shard(store: &SharedStore, shard: usize) -> Vec<u8> {
+    (0..)
+        .map(|n| format!("other:{n}").into_bytes())
+        .find(|key| store.shard_of(key) != shard)
+        .expect("more than one shard")
+}
+
+async fn seeded() -> SharedStore {
+    let store = SharedStore::sharded(SHARDS, Store::new);
+    run(&store, &[b"MSET", b"{pair}:a", b"0", b"{pair}:b", b"0"]).await;
+    // `anchor` never moves, so neither set empties and DBSIZE stays fixed.
+    run(&store, &[b"SADD", b"{sets}left", b"anchor"]).await;
+    for member in 0..MEMBERS {
+        run(
+            &store,
+            &[b"SADD", b"{sets}left", format!("a{member}").as_bytes()],
+        )
+        .await;
+        run(
+            &store,
+            &[b"SADD", b"{sets}right", format!("b{member}").as_bytes()],
+        )
+        .await;
+    }
+    for counter in 0..COUNTERS {
+        run(
+            &store,
+            &[b"SET", format!("counter:{counter}").as_bytes(), b"0"],
+        )
+        .await;
+    }
+    store
+}
+
+async fn seed_big_sets(store: &SharedStore) {
+    for key in [&b"{big}a"[..], b"{big}b"] {
+        let mut sadd = vec![b"SADD".to_vec(), key.to_vec()];
+        sadd.extend((0..BIG).map(|n| format!("{}{n}", key[5] as char).into_bytes()));
+        assert_eq!(store.dispatch(sadd, NOW).await, Ok(int(BIG)));
+    }
+}
+
+fn sunionstore() -> Vec<Vec<u8>> {
+    argv(&[b"SUNIONSTORE", b"{big}dest", b"{big}a", b"{big}b"])
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_dispatch_applies_each_command_whole() {
+    let store = seeded().await;
+    let keys = 2 + 2 + COUNTERS;
+    let done = Arc::new(AtomicBool::new(false));
+    let mut checkers = Vec::new();
+
+    for _ in 0..READERS {
+        let store = store.clone();
+        let done = Arc::clone(&done);
+        checkers.push(tokio::spawn(async move {
+            let mut checks = 0_usize;
+            while !done.load(Ordering::Relaxed) || checks == 0 {
+                match run(&store, &[b"MGET", b"{pair}:a", b"{pair}:b"]).await {
+                    RespFrame::Array(Some(values)) => {
+                        assert_eq!(values[0], values[1], "torn MSET");
+                    }
+                    other => panic!("MGET: {other:?}"),
+                }
+                match run(&store, &[b"SUNION", b"{sets}left", b"{sets}right"]).await {
+                    RespFrame::Array(Some(members)) => {
+                        assert_eq!(members.len(), 2 * MEMBERS + 1, "torn SMOVE");
+                    }
+                    other => panic!("SUNION: {other:?}"),
+                }
+                checks += 1;
+                tokio::task::yield_now().await;
+            }
+            checks
+        }));
+    }
+    {
+        let store = store.clone();
+        let done = Arc::clone(&done);
+        checkers.push(tokio::spawn(async move {
+            let mut checks = 0_usize;
+            while !done.load(Ordering::Relaxed) || checks == 0 {
+                assert_eq!(run(&store, &[b"DBSIZE"]).await, int(keys));
+                match run(&store, &[b"KEYS", b"*"]).await {
+                    RespFrame::Array(Some(found)) => assert_eq!(found.len(), keys),
+                    other => panic!("KEYS: {other:?}"),
+                }
+                checks += 1;
+                tokio::task::yield_now().await;
+            }
+            checks
+        }));
+    }
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|writer| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                for round in 0..ROUNDS {
+                    let counter = format!("counter:{}", round % COUNTERS);
+                    let incr = run(&store, &[b"INCR", counter.as_bytes()]).await;
+                    assert!(matches!(incr, RespFrame::Integer(_)), "{incr:?}");
+                    let value = format!("{writer}:{round}");
+                    let value = value.as_bytes();
+                    run(&store, &[b"MSET", b"{pair}:a", value, b"{pair}:b", value]).await;
+                    let member = format!("a{}", (writer + round) % MEMBERS);
+                    let (from, to): (&[u8], &[u8]) = if round % 2 == 0 {
+                        (b"{sets}left", b"{sets}right")
+                    } else {
+                        (b"{sets}right", b"{sets}left")
+                    };
+                    run(&store, &[b"SMOVE", from, to, member.as_bytes()]).await;
+                }
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.await.expect("writer");
+    }
+    done.store(true, Ordering::Relaxed);
+    for checker in checkers {
+        assert!(checker.await.expect("checker") > 0);
+    }
+
+    let per_counter = (WRITERS * ROUNDS / COUNTERS).to_string();
+    for counter in 0..COUNTERS {
+        let key = format!("counter:{counter}");
+        assert_eq!(
+            run(&store, &[b"GET", key.as_bytes()]).await,
+            bulk(per_counter.as_bytes()),
+            "{key}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn sharded_routing_follows_cluster_rules() {
+    let store = SharedStore::sharded(SHARDS, Store::new);
+    assert_eq!(store.shard_count(), SHARDS);
+    assert_eq!(
+        store.shard_of(b"{user1}:name"),
+        store.shard_of(b"{user1}:age")
+    );
+
+    assert_eq!(
+        run(&store, &[b"MSET", b"a", b"1", b"b", b"2"]).await,
+        err("CROSSSLOT Keys in request don't hash to the same slot")
+    );
+    assert_eq!(
+        run(&store, &[b"GET", b"a"]).await,
+        RespFrame::BulkString(None)
+    );
+    assert_eq!(
+        run(&store, &[b"MSET", b"{t}a", b"1", b"{t}b", b"2"]).await,
+        RespFrame::SimpleString("OK".to_string())
+    );
+    for n in 0..40 {
+        run(&store, &[b"SET", format!("k{n}").as_bytes(), b"v"]).await;
+    }
+    let spread: std::collections::HashSet<_> = (0..40)
+        .map(|n| store.shard_of(format!("k{n}").as_bytes()))
+        .collect();
+    assert!(spread.len() > 1, "keys all landed on one shard");
+
+    assert_eq!(run(&store, &[b"DBSIZE"]).await, RespFrame::Integer(42));
+    match run(&store, &[b"KEYS", b"k*"]).await {
+        RespFrame::Array(Some(keys)) => assert_eq!(keys.len(), 40),
+        other => panic!("KEYS: {other:?}"),
+    }
+    assert_eq!(
+        run(&store, &[b"SCAN", b"0"]).await,
+        err("ERR SCAN is not supported on a sharded store")
+    );
+    assert_eq!(
+        run(&store, &[b"RANDOMKEY"]).await,
+        err("ERR RANDOMKEY is not supported on a sharded store")
+    );
+    assert_eq!(
+        run(&store, &[b"PING"]).await,
+        RespFrame::SimpleString("PONG".to_string())
+    );
+
+    // A bad argument fails on shard 0 before any shard is flushed.
+    assert!(matches!(
+        run(&store, &[b"FLUSHALL", b"BOGUS"]).await,
+        RespFrame::Error(_)
+    ));
+    assert_eq!(run(&store, &[b"DBSIZE"]).await, RespFrame::Integer(42));
+    assert_eq!(
+        run(&store, &[b"FLUSHALL"]).await,
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(run(&store, &[b"DBSIZE"]).await, RespFrame::Integer(0));
+
+    // One shard is the whole keyspace: nothing is cross-slot.
+    let single = SharedStore::new(Store::new());
+    assert_eq!(
+        run(&single, &[b"MSET", b"a", b"1", b"b", b"2"]).await,
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(run(&single, &[b"SCAN", b"0"]).await.to_bytes()[0], b'*');
+}
+
+#[tokio::test]
+async fn global_commands_reach_every_shard_and_scripts_stay_local() {
+    let store = SharedStore::sharded(SHARDS, Store::new);
+    let home = b"{home}".as_slice();
+    let other = key_off_shard(&store, store.shard_of(b"{home}"));
+    let off_shard = key_off_shard(&store, 0);
+
+    // CONFIG SET lands on the shard that later owns `other`.
+    assert_eq!(
+        run(
+            &store,
+            &[b"CONFIG", b"SET", b"set-max-intset-entries", b"1"]
+        )
+        .await,
+        RespFrame::SimpleString("OK".to_string())
+    );
+    run(&store, &[b"SADD", &other, b"1", b"2"]).await;
+    assert_ne!(
+        run(&store, &[b"OBJECT", b"ENCODING", &other]).await,
+        bulk(b"intset")
+    );
+    // A bad value fails on shard 0 before any other shard changes.
+    assert!(matches!(
+        run(&store, &[b"CONFIG", b"SET", b"maxmemory-policy", b"bogus"]).await,
+        RespFrame::Error(_)
+    ));
+
+    // INFO sums the Keyspace section over every shard.
+    run(&store, &[b"SET", home, b"v"]).await;
+    run(&store, &[b"SET", b"plain", b"v", b"PX", b"5000"]).await;
+    let RespFrame::BulkString(Some(info)) = run(&store, &[b"INFO", b"keyspace"]).await else {
+        panic!("INFO is a bulk string");
+    };
+    let info = String::from_utf8(info).expect("INFO is text");
+    assert!(
+        info.contains("db0:keys=3,expires=1,avg_ttl=5000\r\n"),
+        "{info}"
+    );
+
+    // A loaded script is known to every shard.
+    let RespFrame::BulkString(Some(sha)) = run(
+        &store,
+        &[b"SCRIPT", b"LOAD", b"return redis.call('GET', KEYS[1])"],
+    )
+    .await
+    else {
+        panic!("SCRIPT LOAD returns the sha");
+    };
+    assert_eq!(
+        run(&store, &[b"EVALSHA", &sha, b"1", home]).await,
+        bulk(b"v")
+    );
+
+    // A script may only touch keys on the shard it runs on.
+    const NON_LOCAL: &str = "ERR Script attempted to access a non local key";
+    let reply = run(
+        &store,
+        &[
+            b"EVAL",
+            b"return redis.call('GET', ARGV[1])",
+            b"1",
+            home,
+            &other,
+        ],
+    )
+    .await;
+    assert!(
+        matches!(&reply, RespFrame::Error(message) if message.starts_with(NON_LOCAL)),
+        "{reply:?}"
+    );
+    let reply = run(
+        &store,
+        &[
+            b"EVAL",
+            b"return redis.call('GET', ARGV[1])",
+            b"0",
+            &off_shard,
+        ],
+    )
+    .await;
+    assert!(
+        matches!(&reply, RespFrame::Error(message) if message.starts_with(NON_LOCAL)),
+        "{reply:?}"
+    );
+    assert_eq!(
+        run(
+            &store,
+            &[
+                b"EVAL",
+                b"return redis.call('GET', ARGV[1])",
+                b"1",
+                home,
+                home
+            ],
+        )
+        .await,
+        bulk(b"v")
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn commands_over_the_threshold_leave_the_async_worker() {
+    // On a single-threaded runtime an inline command holds the only worker, so
+    // the heavy SUNIONSTORE is already finished by the time the light GET on
+    // another shard runs. On the blocking pool it is still running.
+    for (threshold, heavy_finished_first) in [(usize::MAX, true), (1_000, false)] {
+        let store = SharedStore::sharded(2, Store::new).with_blocking_threshold(threshold);
+        seed_big_sets(&store).await;
+        let other = key_off_shard(&store, store.shard_of(b"{big}a"));
+
+        let heavy = {
+            let store = store.clone();
+            tokio::spawn(async move { store.dispatch(sunionstore(), NOW).await })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(
+            run(&store, &[b"GET", &other]).await,
+            RespFrame::BulkString(None)
+        );
+        assert_eq!(
+            heavy.is_finished(),
+            heavy_finished_first,
+            "threshold {threshold}"
+        );
+        assert_eq!(heavy.await.expect("heavy task"), Ok(int(2 * BIG)));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dropped_dispatches_never_tear_or_poison_a_shard() {
+    let store = SharedStore::sharded(2, Store::new).with_blocking_threshold(1_000);
+    seed_big_sets(&store).await;
+
+    // Dropped while its command runs on the blocking pool: the command still
+    // completes whole and the shard stays usable.
+    let dropped =
+        tokio::time::timeout(Duration::from_micros(1), store.dispatch(sunionstore(), NOW));
+    assert!(dropped.await.is_err(), "SUNIONSTORE finished within 1us");
+    assert_eq!(run(&store, &[b"SCARD", b"{big}dest"]).await, int(2 * BIG));
+
+    // Dropped while waiting for a shard that a long command holds: the
+    // single-shard DEL and the all-shard FLUSHALL never run.
+    let busy = {
+        let store = store.clone();
+        tokio::spawn(async move { store.dispatch(sunionstore(), NOW).await })
+    };
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    for waiting in [argv(&[b"DEL", b"{big}a"]), argv(&[b"FLUSHALL"])] {
+        let waiting = tokio::time::timeout(Duration::from_millis(1), store.dispatch(waiting, NOW));
+        assert!(waiting.await.is_err(), "did not wait for the busy shard");
+    }
+    assert_eq!(busy.await.expect("busy task"), Ok(int(2 * BIG)));
+    assert_eq!(
+        run(&store, &[b"EXISTS", b"{big}a"]).await,
+        RespFrame::Integer(1)
+    );
+    assert_eq!(run(&store, &[b"DBSIZE"]).await, RespFrame::Integer(3));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_panicking_command_poisons_only_its_shard() {
+    let store = SharedStore::sharded(2, Store::new);
+    let poisoner = store.clone();
+    let panicked = tokio::spawn(async move {
+        poisoner
+            .with_shard(b"half", |store| {
+                store.set(b"half".to_vec(), b"written".to_vec(), None, NOW);
+                panic!("command bug");
+            })
+            .await
+    })
+    .await;
+    assert!(panicked.expect_err("with_shard panics").is_panic());
+
+    let after = {
+        let store = store.clone();
+        tokio::spawn(async move { run(&store, &[b"GET", b"half"]).await }).await
+    };
+    let payload = after
+        .expect_err("dispatch on a poisoned shard must panic")
+        .into_panic();
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or_default();
+    assert!(message.contains("SharedStore poisoned"), "{message}");
+
+    let other = key_off_shard(&store, store.shard_of(b"half"));
+    assert_eq!(
+        run(&store, &[b"SET", &other, b"v"]).await,
+        RespFrame::SimpleString("OK".to_string())
+    );
+}
+
+#[tokio::test]
+async fn a_budgeted_scan_releases_the_lock() {
+    let store = SharedStore::new(Store::new());
+    store
+        .with_shard(b"", |store| {
+            for i in 0..50_000 {
+                store.set(format!("k:{i}").into_bytes(), b"v".to_vec(), None, NOW);
+            }
+        })
+        .await;
+    assert_eq!(
+        store
+            .dispatch_with_budget(argv(&[b"KEYS", b"*"]), NOW, Duration::ZERO)
+            .await,
+        Err(CommandError::BudgetExceeded)
+    );
+    assert_eq!(run(&store, &[b"GET", b"k:7"]).await, bulk(b"v"));
+    assert_eq!(
+        store
+            .dispatch_with_budget(argv(&[b"DBSIZE"]), NOW, Duration::ZERO)
+            .await,
+        Ok(RespFrame::Integer(50_000))
+    );
+}
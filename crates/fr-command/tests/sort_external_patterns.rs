@@ -0,0 +1,183 @@
+//! SORT with external keys: `BY` weights read from `weight_*` strings or
+//! `obj_*->field` hash fields (a pattern without `*` skips sorting), `GET`
+//! patterns substituted per element with `#` for the element itself, plus
+//! LIMIT / ASC / DESC / ALPHA and STORE into a list.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run_str as run;
+
+/// SORT reply where `None` is a nil bulk.
+fn reply(values: &[Option<&str>]) -> RespFrame {
+    RespFrame::Array(Some(
+        values
+            .iter()
+            .map(|v| RespFrame::BulkString(v.map(|v| v.as_bytes().to_vec())))
+            .collect(),
+    ))
+}
+
+fn bulks(values: &[&str]) -> RespFrame {
+    let values: Vec<Option<&str>> = values.iter().copied().map(Some).collect();
+    reply(&values)
+}
+
+/// `l` = [1, 2, 3] with hash weights 30 / 10 / 20, names for 1 and 2 and
+/// an `obj_*` hash for 1 and 2.
+fn seeded() -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["RPUSH", "l", "1", "2", "3"]);
+    run(&mut store, &["HSET", "w_1", "weight", "30"]);
+    run(&mut store, &["HSET", "w_2", "weight", "10"]);
+    run(&mut store, &["HSET", "w_3", "weight", "20"]);
+    run(&mut store, &["SET", "name_1", "one"]);
+    run(&mut store, &["SET", "name_2", "two"]);
+    run(&mut store, &["HSET", "obj_1", "field", "a"]);
+    run(&mut store, &["HSET", "obj_2", "field", "b"]);
+    store
+}
+
+#[test]
+fn by_hash_field_pattern() {
+    let mut store = seeded();
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "BY", "w_*->weight"]),
+        bulks(&["2", "3", "1"])
+    );
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "BY", "w_*->weight", "DESC"]),
+        bulks(&["1", "3", "2"])
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["SORT", "l", "BY", "w_*->weight", "LIMIT", "1", "5"]
+        ),
+        bulks(&["3", "1"])
+    );
+    // Plain string weights; a missing weight key sorts as 0.
+    run(&mut store, &["SET", "s_1", "5"]);
+    run(&mut store, &["SET", "s_3", "-1"]);
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "BY", "s_*"]),
+        bulks(&["3", "2", "1"])
+    );
+}
+
+#[test]
+fn by_without_a_star_keeps_the_natural_order() {
+    let mut store = seeded();
+    run(&mut store, &["LSET", "l", "0", "9"]);
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "BY", "nosort"]),
+        bulks(&["9", "2", "3"])
+    );
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "BY", "nosort", "DESC"]),
+        bulks(&["3", "2", "9"])
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["SORT", "l", "BY", "nosort", "LIMIT", "1", "1"]
+        ),
+        bulks(&["2"])
+    );
+}
+
+#[test]
+fn get_element_mixed_with_string_and_hash_patterns() {
+    let mut store = seeded();
+    assert_eq!(
+        run(
+            &mut store,
+            &[
+                "SORT",
+                "l",
+                "BY",
+                "w_*->weight",
+                "GET",
+                "#",
+                "GET",
+                "name_*",
+                "GET",
+                "obj_*->field",
+            ]
+        ),
+        reply(&[
+            Some("2"),
+            Some("two"),
+            Some("b"),
+            Some("3"),
+            None,
+            None,
+            Some("1"),
+            Some("one"),
+            Some("a"),
+        ])
+    );
+}
+
+#[test]
+fn store_writes_a_list_and_replies_its_length() {
+    let mut store = seeded();
+    assert_eq!(
+        run(
+            &mut store,
+            &["SORT", "l", "BY", "w_*->weight", "STORE", "dst"]
+        ),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(
+        run(&mut store, &["LRANGE", "dst", "0", "-1"]),
+        bulks(&["2", "3", "1"])
+    );
+
+    // A missing GET lookup is stored as an empty string.
+    assert_eq!(
+        run(&mut store, &["SORT", "l", "GET", "name_*", "STORE", "dst"]),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(
+        run(&mut store, &["LRANGE", "dst", "0", "-1"]),
+        bulks(&["one", "two", ""])
+    );
+
+    // An empty result deletes the destination.
+    assert_eq!(
+        run(&mut store, &["SORT", "missing", "STORE", "dst"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(run(&mut store, &["EXISTS", "dst"]), RespFrame::Integer(0));
+}
+
+#[test]
+fn alpha_and_numeric_conversion_errors() {
+    let mut store = Store::new();
+    run(&mut store, &["RPUSH", "words", "b", "10", "a", "9"]);
+    let not_a_double =
+        RespFrame::Error("ERR One or more scores can't be converted into double".to_string());
+    assert_eq!(run(&mut store, &["SORT", "words"]), not_a_double);
+    assert_eq!(
+        run(&mut store, &["SORT", "words", "ALPHA"]),
+        bulks(&["10", "9", "a", "b"])
+    );
+    assert_eq!(
+        run(
+            &mut store,
+            &["SORT", "words", "ALPHA", "DESC", "LIMIT", "0", "2"]
+        ),
+        bulks(&["b", "a"])
+    );
+
+    // A weight that is not a number fails the same way.
+    run(&mut store, &["RPUSH", "nums", "1", "2"]);
+    run(&mut store, &["SET", "weight_1", "heavy"]);
+    assert_eq!(
+        run(&mut store, &["SORT", "nums", "BY", "weight_*"]),
+        not_a_double
+    );
+}
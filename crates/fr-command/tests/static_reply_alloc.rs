@@ -0,0 +1,88 @@
+//! Allocation gate for the static replies.
+//!
+//! PING answers with a `StaticSimple` frame and the boolean-integer probes
+//! (EXISTS, SISMEMBER, HEXISTS, SETNX on an existing key) with `:0` / `:1`,
+//! which the encoder writes from pre-encoded bytes. Dispatching one of them
+//! and encoding its reply into a warmed buffer must not touch the heap. The
+//! shared `alloc_meter` counts allocations on the measuring thread only.
+
+#[path = "support/alloc_meter.rs"]
+mod alloc_meter;
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv};
+
+/// Dispatch `argv` and encode its reply into `out`, replacing what was there.
+fn answer(store: &mut Store, argv: &[Vec<u8>], out: &mut Vec<u8>) {
+    out.clear();
+    dispatch_argv(argv, store, NOW)
+        .expect("command succeeds")
+        .encode_into(out);
+}
+
+#[test]
+fn owned_simple_strings_are_counted() {
+    // Guard against a vacuous pass: the owned form allocates its text.
+    let (frame, usage) = alloc_meter::measure(|| RespFrame::SimpleString("PONG".to_string()));
+    assert!(usage.allocations > 0, "the meter saw no allocations");
+    assert_eq!(frame, RespFrame::StaticSimple("PONG"));
+    assert_eq!(frame.to_bytes(), RespFrame::StaticSimple("PONG").to_bytes());
+}
+
+#[test]
+fn one_million_pings_allocate_nothing() {
+    let mut store = Store::new();
+    let ping = argv(&[b"PING"]);
+    let mut out = Vec::with_capacity(64);
+    // Warm-up: builds the static command index and any lazily built state.
+    answer(&mut store, &ping, &mut out);
+
+    let ((), usage) = alloc_meter::measure(|| {
+        for _ in 0..1_000_000 {
+            answer(&mut store, &ping, &mut out);
+        }
+    });
+    assert_eq!(out, b"+PONG\r\n");
+    assert_eq!(usage.allocations, 0, "PING must not allocate per op");
+}
+
+#[test]
+fn boolean_integer_probes_allocate_nothing() {
+    let mut store = Store::new();
+    for setup in [
+        argv(&[b"SET", b"present", b"v"]),
+        argv(&[b"SADD", b"set", b"m"]),
+        argv(&[b"HSET", b"hash", b"f", b"v"]),
+    ] {
+        dispatch_argv(&setup, &mut store, NOW).expect("setup");
+    }
+    let probes = [
+        (argv(&[b"EXISTS", b"present"]), &b":1\r\n"[..]),
+        (argv(&[b"EXISTS", b"missing"]), b":0\r\n"),
+        (argv(&[b"SISMEMBER", b"set", b"m"]), b":1\r\n"),
+        (argv(&[b"HEXISTS", b"hash", b"nope"]), b":0\r\n"),
+        (argv(&[b"SETNX", b"present", b"other"]), b":0\r\n"),
+    ];
+    let mut out = Vec::with_capacity(64);
+    for (probe, expected) in &probes {
+        answer(&mut store, probe, &mut out);
+        assert_eq!(&out, expected, "{probe:?}");
+    }
+
+    let ((), usage) = alloc_meter::measure(|| {
+        for _ in 0..100_000 {
+            for (probe, _) in &probes {
+                answer(&mut store, probe, &mut out);
+            }
+        }
+    });
+    assert_eq!(
+        usage.allocations, 0,
+        "EXISTS/SISMEMBER/HEXISTS/SETNX replies must not allocate"
+    );
+}
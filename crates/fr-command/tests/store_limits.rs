@@ -0,0 +1,321 @@
+//! Tenant `StoreLimits` seen through commands: every quota kind surfaces as
+//! an OOM error reply from the write that would cross it, the key is left as
+//! it was, and reads and deletes keep working on data over a lowered limit.
+//! A write to a key of another type still fails with WRONGTYPE first.
+
+use fr_command::{CommandError, dispatch_argv};
+use fr_protocol::RespFrame;
+use fr_store::{QuotaKind, Store, StoreError, StoreLimits};
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, argv, bulk, run_ok as run};
+
+fn assert_quota(store: &mut Store, parts: &[&[u8]], kind: QuotaKind) {
+    let err = dispatch_argv(&argv(parts), store, NOW).expect_err("quota rejects the write");
+    assert_eq!(
+        err,
+        CommandError::Store(StoreError::QuotaExceeded(kind)),
+        "{parts:?}"
+    );
+    assert_eq!(err.code(), "OOM");
+}
+
+fn limited(limits: StoreLimits) -> Store {
+    Store::with_limits(limits)
+}
+
+#[test]
+fn quota_error_renders_as_an_oom_reply() {
+    let mut store = limited(StoreLimits {
+        max_keys: Some(0),
+        ..StoreLimits::default()
+    });
+    let err = dispatch_argv(&argv(&[b"SET", b"k", b"v"]), &mut store, NOW).unwrap_err();
+    let RespFrame::Error(text) = err.to_resp() else {
+        panic!("expected an error reply");
+    };
+    assert!(text.starts_with("OOM "), "{text}");
+}
+
+#[test]
+fn value_size_quota() {
+    let mut store = limited(StoreLimits {
+        max_value_bytes: Some(8),
+        ..StoreLimits::default()
+    });
+    let big: &[u8] = b"123456789";
+
+    assert_quota(&mut store, &[b"SET", b"s", big], QuotaKind::ValueSize);
+    assert_quota(
+        &mut store,
+        &[b"SETEX", b"s", b"10", big],
+        QuotaKind::ValueSize,
+    );
+    assert_quota(
+        &mut store,
+        &[b"MSET", b"a", b"1", b"s", big],
+        QuotaKind::ValueSize,
+    );
+    assert_eq!(
+        run(&mut store, &[b"EXISTS", b"a", b"s"]),
+        RespFrame::Integer(0)
+    );
+
+    run(&mut store, &[b"SET", b"s", b"12345"]);
+    assert_quota(
+        &mut store,
+        &[b"APPEND", b"s", b"6789"],
+        QuotaKind::ValueSize,
+    );
+    assert_quota(
+        &mut store,
+        &[b"SETRANGE", b"s", b"8", b"x"],
+        QuotaKind::ValueSize,
+    );
+    assert_quota(
+        &mut store,
+        &[b"SETBIT", b"s", b"64", b"1"],
+        QuotaKind::ValueSize,
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"s"]), bulk(b"12345"));
+
+    assert_quota(
+        &mut store,
+        &[b"RPUSH", b"l", b"ok", big],
+        QuotaKind::ValueSize,
+    );
+    assert_quota(
+        &mut store,
+        &[b"HSET", b"h", b"f", big],
+        QuotaKind::ValueSize,
+    );
+    assert_quota(&mut store, &[b"SADD", b"set", big], QuotaKind::ValueSize);
+    assert_quota(
+        &mut store,
+        &[b"ZADD", b"z", b"1", big],
+        QuotaKind::ValueSize,
+    );
+    #[cfg(feature = "streams")]
+    assert_quota(
+        &mut store,
+        &[b"XADD", b"x", b"*", b"f", big],
+        QuotaKind::ValueSize,
+    );
+    assert_eq!(
+        run(&mut store, &[b"EXISTS", b"l", b"h", b"set", b"z", b"x"]),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn element_quota() {
+    let mut store = limited(StoreLimits {
+        max_elements: Some(3),
+        ..StoreLimits::default()
+    });
+
+    run(&mut store, &[b"RPUSH", b"l", b"a", b"b", b"c"]);
+    assert_quota(&mut store, &[b"LPUSH", b"l", b"d"], QuotaKind::Elements);
+
+    run(&mut store, &[b"SADD", b"s", b"a", b"b", b"c"]);
+    assert_eq!(
+        run(&mut store, &[b"SADD", b"s", b"a"]),
+        RespFrame::Integer(0)
+    );
+    assert_quota(&mut store, &[b"SADD", b"s", b"d"], QuotaKind::Elements);
+
+    run(
+        &mut store,
+        &[b"HSET", b"h", b"f1", b"v", b"f2", b"v", b"f3", b"v"],
+    );
+    assert_eq!(
+        run(&mut store, &[b"HSET", b"h", b"f1", b"updated"]),
+        RespFrame::Integer(0)
+    );
+    // A multi-field HSET is rejected as a whole: f1 keeps its value.
+    assert_quota(
+        &mut store,
+        &[b"HSET", b"h", b"f1", b"again", b"f4", b"v"],
+        QuotaKind::Elements,
+    );
+    assert_eq!(run(&mut store, &[b"HGET", b"h", b"f1"]), bulk(b"updated"));
+
+    run(
+        &mut store,
+        &[b"ZADD", b"z", b"1", b"a", b"2", b"b", b"3", b"c"],
+    );
+    assert_eq!(
+        run(&mut store, &[b"ZADD", b"z", b"XX", b"9", b"a"]),
+        RespFrame::Integer(0)
+    );
+    assert_quota(
+        &mut store,
+        &[b"ZADD", b"z", b"4", b"d"],
+        QuotaKind::Elements,
+    );
+
+    #[cfg(feature = "streams")]
+    {
+        for id in [&b"1-1"[..], b"1-2", b"1-3"] {
+            run(&mut store, &[b"XADD", b"x", id, b"f", b"v"]);
+        }
+        assert_quota(
+            &mut store,
+            &[b"XADD", b"x", b"1-4", b"f", b"v"],
+            QuotaKind::Elements,
+        );
+        assert_eq!(run(&mut store, &[b"XLEN", b"x"]), RespFrame::Integer(3));
+    }
+}
+
+#[test]
+fn wrong_type_is_reported_before_any_quota() {
+    let mut store = limited(StoreLimits {
+        max_elements: Some(1),
+        ..StoreLimits::default()
+    });
+    run(&mut store, &[b"SET", b"s", b"v"]);
+    let mut writes: Vec<&[&[u8]]> = vec![
+        &[b"HSET", b"s", b"f1", b"v", b"f2", b"v"],
+        &[b"SADD", b"s", b"a", b"b"],
+        &[b"ZADD", b"s", b"1", b"a", b"2", b"b"],
+        &[b"RPUSH", b"s", b"a", b"b"],
+    ];
+    if cfg!(feature = "streams") {
+        writes.push(&[b"XADD", b"s", b"1-1", b"f", b"v"]);
+    }
+    for parts in writes {
+        let err = dispatch_argv(&argv(parts), &mut store, NOW).expect_err("string key");
+        assert_eq!(err.code(), "WRONGTYPE", "{parts:?}");
+    }
+}
+
+#[test]
+fn key_quota() {
+    let mut store = limited(StoreLimits {
+        max_keys: Some(2),
+        ..StoreLimits::default()
+    });
+    run(&mut store, &[b"SET", b"a", b"1"]);
+    run(&mut store, &[b"SET", b"b", b"1"]);
+
+    assert_quota(&mut store, &[b"SET", b"c", b"1"], QuotaKind::Keys);
+    assert_quota(&mut store, &[b"RPUSH", b"c", b"1"], QuotaKind::Keys);
+    assert_quota(&mut store, &[b"HSET", b"c", b"f", b"v"], QuotaKind::Keys);
+    // Overwriting existing keys is fine.
+    run(&mut store, &[b"SET", b"a", b"2"]);
+    run(&mut store, &[b"MSET", b"a", b"3", b"b", b"3"]);
+
+    run(&mut store, &[b"DEL", b"a"]);
+    // One slot is free, but MSET needs two.
+    assert_quota(
+        &mut store,
+        &[b"MSET", b"c", b"1", b"d", b"1"],
+        QuotaKind::Keys,
+    );
+    assert_eq!(
+        run(&mut store, &[b"EXISTS", b"c", b"d"]),
+        RespFrame::Integer(0)
+    );
+    run(&mut store, &[b"SET", b"c", b"1"]);
+    assert_eq!(run(&mut store, &[b"DBSIZE"]), RespFrame::Integer(2));
+}
+
+#[test]
+fn memory_quota() {
+    let mut store = Store::new();
+    run(&mut store, &[b"SET", b"big", &[b'x'; 4096]]);
+    let used = store.estimate_memory_usage_bytes();
+    store.set_limits(StoreLimits {
+        max_memory_bytes: Some(used),
+        ..StoreLimits::default()
+    });
+
+    assert_quota(&mut store, &[b"SET", b"k", b"v"], QuotaKind::Memory);
+    assert_quota(&mut store, &[b"SADD", b"s", b"m"], QuotaKind::Memory);
+    assert_eq!(
+        run(&mut store, &[b"STRLEN", b"big"]),
+        RespFrame::Integer(4096)
+    );
+    assert_eq!(run(&mut store, &[b"DEL", b"big"]), RespFrame::Integer(1));
+    assert_eq!(
+        run(&mut store, &[b"SET", b"k", b"v"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+}
+
+#[test]
+fn restore_is_checked_against_the_decoded_value() {
+    let mut store = Store::new();
+    run(&mut store, &[b"RPUSH", b"l", b"a", b"b", b"c", b"d"]);
+    run(&mut store, &[b"SET", b"s", b"123456789"]);
+    let RespFrame::BulkString(Some(list_dump)) = run(&mut store, &[b"DUMP", b"l"]) else {
+        panic!("DUMP l");
+    };
+    let RespFrame::BulkString(Some(string_dump)) = run(&mut store, &[b"DUMP", b"s"]) else {
+        panic!("DUMP s");
+    };
+    store.set_limits(StoreLimits {
+        max_elements: Some(3),
+        max_value_bytes: Some(8),
+        ..StoreLimits::default()
+    });
+
+    assert_quota(
+        &mut store,
+        &[b"RESTORE", b"l2", b"0", &list_dump],
+        QuotaKind::Elements,
+    );
+    assert_quota(
+        &mut store,
+        &[b"RESTORE", b"s2", b"0", &string_dump],
+        QuotaKind::ValueSize,
+    );
+    assert_eq!(
+        run(&mut store, &[b"EXISTS", b"l2", b"s2"]),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn reads_and_deletes_work_over_a_lowered_quota() {
+    let mut store = Store::new();
+    run(&mut store, &[b"RPUSH", b"l", b"a", b"b", b"c"]);
+    run(&mut store, &[b"SADD", b"s", b"a", b"b", b"c"]);
+    run(&mut store, &[b"HSET", b"h", b"f1", b"v", b"f2", b"v"]);
+    run(&mut store, &[b"ZADD", b"z", b"1", b"a", b"2", b"b"]);
+    store.set_limits(StoreLimits {
+        max_keys: Some(1),
+        max_elements: Some(1),
+        max_value_bytes: Some(0),
+        max_memory_bytes: Some(0),
+    });
+
+    assert_eq!(run(&mut store, &[b"LLEN", b"l"]), RespFrame::Integer(3));
+    assert_eq!(run(&mut store, &[b"SCARD", b"s"]), RespFrame::Integer(3));
+    assert_eq!(run(&mut store, &[b"HGET", b"h", b"f1"]), bulk(b"v"));
+    assert_eq!(run(&mut store, &[b"ZSCORE", b"z", b"b"]), bulk(b"2"));
+    assert_eq!(run(&mut store, &[b"LPOP", b"l"]), bulk(b"a"));
+    assert_eq!(
+        run(&mut store, &[b"SREM", b"s", b"a"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        run(&mut store, &[b"HDEL", b"h", b"f1"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        run(&mut store, &[b"ZREM", b"z", b"a"]),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        run(&mut store, &[b"LPUSHX", b"missing", b"x"]),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(
+        run(&mut store, &[b"DEL", b"l", b"s", b"h", b"z"]),
+        RespFrame::Integer(4)
+    );
+    assert_eq!(run(&mut store, &[b"DBSIZE"]), RespFrame::Integer(0));
+}
@@ -0,0 +1,135 @@
+//! Exclusive `(` stream-ID bounds used as pagination cursors.
+//!
+//! Clients page through a stream by re-issuing XRANGE/XREVRANGE (and the
+//! extended XPENDING form) with the last-seen ID as an exclusive bound, so the
+//! boundary entry is never returned twice. Walking a 10-entry stream three at
+//! a time in either direction must visit every entry exactly once.
+
+#![cfg(feature = "streams")]
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run;
+
+const PAGE: &[u8] = b"3";
+
+fn seeded() -> (Store, Vec<Vec<u8>>) {
+    let mut store = Store::new();
+    let ids: Vec<Vec<u8>> = (1..=10).map(|i| format!("{i}-0").into_bytes()).collect();
+    for id in &ids {
+        run(&mut store, &[b"XADD", b"s", id, b"f", b"v"]);
+    }
+    (store, ids)
+}
+
+fn exclusive(id: &[u8]) -> Vec<u8> {
+    let mut bound = b"(".to_vec();
+    bound.extend_from_slice(id);
+    bound
+}
+
+/// IDs of an XRANGE-shaped reply, or the first field of each XPENDING row.
+fn ids(reply: &RespFrame) -> Vec<Vec<u8>> {
+    let RespFrame::Array(Some(rows)) = reply else {
+        panic!("expected an array reply, got {reply:?}");
+    };
+    rows.iter()
+        .map(|row| match row {
+            RespFrame::Array(Some(parts)) => match &parts[0] {
+                RespFrame::BulkString(Some(id)) => id.clone(),
+                other => panic!("row id must be a bulk string: {other:?}"),
+            },
+            other => panic!("row must be an array: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn xrange_pages_forward_with_exclusive_start() {
+    let (mut store, all) = seeded();
+    let mut seen = Vec::new();
+    let mut start = b"-".to_vec();
+    loop {
+        let page = ids(&run(
+            &mut store,
+            &[b"XRANGE", b"s", &start, b"+", b"COUNT", PAGE],
+        ));
+        let Some(last) = page.last() else { break };
+        start = exclusive(last);
+        seen.extend(page);
+    }
+    assert_eq!(seen, all);
+}
+
+#[test]
+fn xrevrange_pages_backward_with_exclusive_end() {
+    let (mut store, all) = seeded();
+    let mut seen = Vec::new();
+    let mut end = b"+".to_vec();
+    loop {
+        let page = ids(&run(
+            &mut store,
+            &[b"XREVRANGE", b"s", &end, b"-", b"COUNT", PAGE],
+        ));
+        let Some(last) = page.last() else { break };
+        end = exclusive(last);
+        seen.extend(page);
+    }
+    let mut expected = all;
+    expected.reverse();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn exclusive_bounds_equal_to_existing_ids_drop_exactly_those_entries() {
+    let (mut store, all) = seeded();
+    assert_eq!(
+        ids(&run(&mut store, &[b"XRANGE", b"s", b"(1-0", b"(10-0"])),
+        all[1..9].to_vec()
+    );
+    let mut reversed = all[1..9].to_vec();
+    reversed.reverse();
+    assert_eq!(
+        ids(&run(&mut store, &[b"XREVRANGE", b"s", b"(10-0", b"(1-0"])),
+        reversed
+    );
+    // An exclusive range that excludes both ends of a single entry is empty.
+    assert_eq!(
+        run(&mut store, &[b"XRANGE", b"s", b"(5-0", b"(5-0"]),
+        RespFrame::Array(Some(Vec::new()))
+    );
+    for bad in [b"(+".as_slice(), b"(-"] {
+        assert_eq!(
+            run(&mut store, &[b"XRANGE", b"s", bad, b"+"]),
+            RespFrame::Error(
+                "ERR Invalid stream ID specified as stream command argument".to_string()
+            )
+        );
+    }
+}
+
+#[test]
+fn xpending_extended_form_pages_with_exclusive_start() {
+    let (mut store, all) = seeded();
+    run(&mut store, &[b"XGROUP", b"CREATE", b"s", b"g", b"0"]);
+    run(
+        &mut store,
+        &[b"XREADGROUP", b"GROUP", b"g", b"c", b"STREAMS", b"s", b">"],
+    );
+
+    let mut seen = Vec::new();
+    let mut start = b"-".to_vec();
+    loop {
+        let page = ids(&run(
+            &mut store,
+            &[b"XPENDING", b"s", b"g", &start, b"+", PAGE],
+        ));
+        let Some(last) = page.last() else { break };
+        start = exclusive(last);
+        seen.extend(page);
+    }
+    assert_eq!(seen, all);
+}
@@ -0,0 +1,107 @@
+//! Deleting and recreating a stream under `stream-preserve-last-id-on-delete`.
+//!
+//! A consumer polls with XREAD from the last id it saw. If the stream is
+//! deleted and recreated while the clock sits at or behind that id, plain
+//! auto ids restart below the cursor and the consumer silently skips the
+//! new entries. With the flag on, XADD `*` continues above the old stream.
+
+#![cfg(feature = "streams")]
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, run_str_at};
+
+/// Behind the ids the first stream used, as after a failover to a replica
+/// with a slower clock.
+const NOW: u64 = 1_000;
+
+fn run(store: &mut Store, argv: &[&str]) -> RespFrame {
+    run_str_at(store, NOW, argv)
+}
+
+/// Entry ids XREAD hands a consumer whose cursor is `cursor`.
+fn read_after(store: &mut Store, cursor: &str) -> Vec<Vec<u8>> {
+    let reply = run(store, &["XREAD", "STREAMS", "s", cursor]);
+    let RespFrame::Array(Some(streams)) = reply else {
+        return Vec::new();
+    };
+    let RespFrame::Array(Some(stream)) = &streams[0] else {
+        panic!("expected [key, entries]: {streams:?}");
+    };
+    let RespFrame::Array(Some(entries)) = &stream[1] else {
+        panic!("expected entries: {stream:?}");
+    };
+    entries
+        .iter()
+        .map(|entry| match entry {
+            RespFrame::Array(Some(parts)) => match &parts[0] {
+                RespFrame::BulkString(Some(id)) => id.clone(),
+                other => panic!("entry id must be a bulk string: {other:?}"),
+            },
+            other => panic!("entry must be an array: {other:?}"),
+        })
+        .collect()
+}
+
+/// Fill `s`, leave a consumer at its last id, then delete and recreate it.
+fn recreate(store: &mut Store) -> (&'static str, Vec<RespFrame>) {
+    run(store, &["XADD", "s", "5000-0", "f", "v"]);
+    run(store, &["XADD", "s", "5000-1", "f", "v"]);
+    let cursor = "5000-1";
+    assert_eq!(run(store, &["DEL", "s"]), RespFrame::Integer(1));
+    let added = (0..2)
+        .map(|_| run(store, &["XADD", "s", "*", "f", "v"]))
+        .collect();
+    (cursor, added)
+}
+
+#[test]
+fn a_stale_cursor_misses_a_recreated_stream_by_default() {
+    let mut store = Store::new();
+    let (cursor, added) = recreate(&mut store);
+    assert_eq!(added, vec![bulk("1000-0"), bulk("1000-1")]);
+    assert!(read_after(&mut store, cursor).is_empty());
+}
+
+#[test]
+fn a_stale_cursor_sees_every_entry_when_last_ids_are_preserved() {
+    let mut store = Store::new();
+    store.set_stream_preserve_last_id_on_delete(true);
+    let (cursor, added) = recreate(&mut store);
+    assert_eq!(added, vec![bulk("5000-2"), bulk("5000-3")]);
+    assert_eq!(
+        read_after(&mut store, cursor),
+        vec![b"5000-2".to_vec(), b"5000-3".to_vec()]
+    );
+
+    // Explicit ids are the caller's business and may go lower.
+    run(&mut store, &["DEL", "s"]);
+    assert_eq!(
+        run(&mut store, &["XADD", "s", "10-0", "f", "v"]),
+        bulk("10-0")
+    );
+}
+
+#[test]
+fn xsetid_resets_a_recreated_stream() {
+    let mut store = Store::new();
+    store.set_stream_preserve_last_id_on_delete(true);
+    run(&mut store, &["XADD", "s", "5000-0", "f", "v"]);
+    run(&mut store, &["DEL", "s"]);
+
+    assert_eq!(
+        run(&mut store, &["XGROUP", "CREATE", "s", "g", "$", "MKSTREAM"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        run(&mut store, &["XSETID", "s", "0-1"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        run(&mut store, &["XADD", "s", "*", "f", "v"]),
+        bulk("1000-0")
+    );
+}
@@ -0,0 +1,164 @@
+//! Stream commands on keys of every other type.
+//!
+//! A key holds one value. Every stream command run against a string, list,
+//! hash, set or zset must answer WRONGTYPE and leave the value as it was; in
+//! particular `XGROUP CREATE ... MKSTREAM` must not plant a stream next to a
+//! string. Consumer group and consumer names must be non-empty.
+
+#![cfg(feature = "streams")]
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{bulk, err, ok, run};
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+/// (type name, seeding command) for every non-stream value type.
+const TYPES: &[(&str, &[&[u8]])] = &[
+    ("string", &[b"SET", b"k", b"hello"]),
+    ("list", &[b"RPUSH", b"k", b"a", b"b"]),
+    ("hash", &[b"HSET", b"k", b"f", b"v"]),
+    ("set", &[b"SADD", b"k", b"m1", b"m2"]),
+    ("zset", &[b"ZADD", b"k", b"1", b"m"]),
+];
+
+/// Every stream command, each naming `k` and otherwise well-formed.
+const STREAM_COMMANDS: &[&[&[u8]]] = &[
+    &[b"XADD", b"k", b"*", b"f", b"v"],
+    &[b"XADD", b"k", b"NOMKSTREAM", b"1-1", b"f", b"v"],
+    &[b"XLEN", b"k"],
+    &[b"XRANGE", b"k", b"-", b"+"],
+    &[b"XREVRANGE", b"k", b"+", b"-"],
+    &[b"XDEL", b"k", b"1-1"],
+    &[b"XTRIM", b"k", b"MAXLEN", b"0"],
+    &[b"XREAD", b"STREAMS", b"k", b"0"],
+    &[b"XREADGROUP", b"GROUP", b"g", b"c", b"STREAMS", b"k", b">"],
+    &[b"XREADGROUP", b"GROUP", b"g", b"c", b"STREAMS", b"k", b"0"],
+    &[b"XGROUP", b"CREATE", b"k", b"g", b"$"],
+    &[b"XGROUP", b"CREATE", b"k", b"g", b"0", b"MKSTREAM"],
+    &[b"XGROUP", b"SETID", b"k", b"g", b"0"],
+    &[b"XGROUP", b"DESTROY", b"k", b"g"],
+    &[b"XGROUP", b"CREATECONSUMER", b"k", b"g", b"c"],
+    &[b"XGROUP", b"DELCONSUMER", b"k", b"g", b"c"],
+    &[b"XACK", b"k", b"g", b"1-1"],
+    &[b"XPENDING", b"k", b"g"],
+    &[b"XPENDING", b"k", b"g", b"-", b"+", b"10"],
+    &[b"XCLAIM", b"k", b"g", b"c", b"0", b"1-1"],
+    &[b"XAUTOCLAIM", b"k", b"g", b"c", b"0", b"0"],
+    &[b"XINFO", b"STREAM", b"k"],
+    &[b"XINFO", b"GROUPS", b"k"],
+    &[b"XINFO", b"CONSUMERS", b"k", b"g"],
+    &[b"XSETID", b"k", b"1-1"],
+];
+
+#[test]
+fn every_stream_command_refuses_other_types() {
+    for (type_name, seed) in TYPES {
+        let mut store = Store::new();
+        run(&mut store, seed);
+        let dump = run(&mut store, &[b"DUMP", b"k"]);
+        for command in STREAM_COMMANDS {
+            assert_eq!(
+                run(&mut store, command),
+                err(WRONGTYPE),
+                "{} on a {type_name}",
+                String::from_utf8_lossy(&command.join(&b' '))
+            );
+        }
+        assert_eq!(
+            run(&mut store, &[b"TYPE", b"k"]),
+            RespFrame::SimpleString((*type_name).to_string())
+        );
+        assert_eq!(
+            run(&mut store, &[b"DUMP", b"k"]),
+            dump,
+            "{type_name} changed"
+        );
+        assert_eq!(run(&mut store, &[b"DBSIZE"]), RespFrame::Integer(1));
+    }
+}
+
+#[test]
+fn mkstream_on_a_string_keeps_the_string() {
+    let mut store = Store::new();
+    run(&mut store, &[b"SET", b"stringkey", b"hello"]);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"XGROUP", b"CREATE", b"stringkey", b"g", b"$", b"MKSTREAM"]
+        ),
+        err(WRONGTYPE)
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"stringkey"]), bulk(b"hello"));
+    assert_eq!(run(&mut store, &[b"XLEN", b"stringkey"]), err(WRONGTYPE));
+    assert_eq!(
+        run(&mut store, &[b"XINFO", b"GROUPS", b"stringkey"]),
+        err(WRONGTYPE)
+    );
+
+    // The same key becomes a stream only after the string is gone.
+    run(&mut store, &[b"DEL", b"stringkey"]);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"XGROUP", b"CREATE", b"stringkey", b"g", b"$", b"MKSTREAM"]
+        ),
+        ok()
+    );
+    assert_eq!(run(&mut store, &[b"GET", b"stringkey"]), err(WRONGTYPE));
+    assert_eq!(
+        run(&mut store, &[b"XLEN", b"stringkey"]),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn group_and_consumer_names_must_be_non_empty() {
+    let empty_group = err("ERR consumer group name can't be empty");
+    let empty_consumer = err("ERR consumer name can't be empty");
+    let mut store = Store::new();
+
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"XGROUP", b"CREATE", b"s", b"", b"$", b"MKSTREAM"]
+        ),
+        empty_group
+    );
+    assert_eq!(run(&mut store, &[b"EXISTS", b"s"]), RespFrame::Integer(0));
+
+    run(&mut store, &[b"XADD", b"s", b"1-1", b"f", b"v"]);
+    assert_eq!(
+        run(&mut store, &[b"XGROUP", b"CREATE", b"s", b"", b"0"]),
+        empty_group
+    );
+    assert_eq!(
+        run(&mut store, &[b"XGROUP", b"CREATE", b"s", b"g", b"0"]),
+        ok()
+    );
+    for command in [
+        &[&b"XGROUP"[..], b"CREATECONSUMER", b"s", b"g", b""][..],
+        &[b"XREADGROUP", b"GROUP", b"g", b"", b"STREAMS", b"s", b">"],
+        &[b"XCLAIM", b"s", b"g", b"", b"0", b"1-1"],
+        &[b"XAUTOCLAIM", b"s", b"g", b"", b"0", b"0"],
+    ] {
+        assert_eq!(
+            run(&mut store, command),
+            empty_consumer,
+            "{}",
+            String::from_utf8_lossy(&command.join(&b' '))
+        );
+    }
+    // Nothing was created or delivered by the refused calls.
+    assert_eq!(
+        run(&mut store, &[b"XINFO", b"CONSUMERS", b"s", b"g"]),
+        RespFrame::Array(Some(Vec::new()))
+    );
+    assert_eq!(
+        run(&mut store, &[b"XPENDING", b"s", b"g", b"-", b"+", b"10"]),
+        RespFrame::Array(Some(Vec::new()))
+    );
+}
@@ -0,0 +1,107 @@
+//! Thread-local heap meter shared by the allocation-gate tests.
+//!
+//! Including this module installs a global allocator that forwards to
+//! `System` and, while `measure` runs on a thread, tallies that thread's
+//! allocation calls and live/peak heap bytes. The tallies are thread-local,
+//! so the other tests in the same binary running in parallel cannot skew
+//! them. fr-runtime's allocation tests include this file by path.
+
+// Each test binary reads only the `Usage` fields its gate needs.
+#![allow(dead_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct MeteredAllocator;
+
+thread_local! {
+    static TRACKING: Cell<bool> = const { Cell::new(false) };
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+    static REQUESTED: Cell<usize> = const { Cell::new(0) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn note(delta: isize) {
+    // try_with: the allocator can run during thread teardown after the TLS
+    // slots are gone.
+    let _ = TRACKING.try_with(|tracking| {
+        if tracking.get() {
+            let _ = LIVE.try_with(|live| {
+                let now = live.get() + delta;
+                live.set(now);
+                let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+            });
+            if delta > 0 {
+                let _ = REQUESTED.try_with(|total| total.set(total.get() + delta.unsigned_abs()));
+            }
+        }
+    });
+}
+
+fn note_call() {
+    let _ = TRACKING.try_with(|tracking| {
+        if tracking.get() {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for MeteredAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        note_call();
+        note(layout.size().cast_signed());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        note(-layout.size().cast_signed());
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        note_call();
+        note(layout.size().cast_signed());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        note_call();
+        note(new_size.cast_signed() - layout.size().cast_signed());
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: MeteredAllocator = MeteredAllocator;
+
+/// Heap traffic observed while one `measure` call ran.
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    /// `alloc`, `alloc_zeroed` and `realloc` calls made.
+    pub allocations: usize,
+    /// Every byte requested, freed or not (growing reallocs count the growth).
+    pub requested: usize,
+    /// Most bytes held at once above the starting point.
+    pub peak: isize,
+    /// Bytes still held when the op returned.
+    pub retained: isize,
+}
+
+/// Run `op` with metering enabled on this thread and report its heap usage.
+pub fn measure<T>(op: impl FnOnce() -> T) -> (T, Usage) {
+    LIVE.with(|live| live.set(0));
+    PEAK.with(|peak| peak.set(0));
+    REQUESTED.with(|total| total.set(0));
+    ALLOCATIONS.with(|n| n.set(0));
+    TRACKING.with(|t| t.set(true));
+    let out = op();
+    TRACKING.with(|t| t.set(false));
+    let usage = Usage {
+        allocations: ALLOCATIONS.with(Cell::get),
+        requested: REQUESTED.with(Cell::get),
+        peak: PEAK.with(Cell::get),
+        retained: LIVE.with(Cell::get),
+    };
+    (out, usage)
+}
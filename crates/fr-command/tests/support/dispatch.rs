@@ -0,0 +1,60 @@
+//! Dispatch helpers shared by the command integration tests.
+//!
+//! Each test binary includes this file by path and imports the helpers it
+//! needs. `run` and `run_str` render a command error as its RESP error reply,
+//! so one assertion compares success and failure replies alike; `run_ok` is
+//! for setup that must succeed.
+
+// Each test binary uses only some of the helpers.
+#![allow(dead_code)]
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+/// The clock the helpers dispatch at unless a test passes its own.
+pub const NOW: u64 = 1_000_000;
+
+pub fn argv(parts: &[&[u8]]) -> Vec<Vec<u8>> {
+    parts.iter().map(|part| part.to_vec()).collect()
+}
+
+pub fn argv_str(parts: &[&str]) -> Vec<Vec<u8>> {
+    parts.iter().map(|part| part.as_bytes().to_vec()).collect()
+}
+
+pub fn run_at(store: &mut Store, now_ms: u64, parts: &[&[u8]]) -> RespFrame {
+    dispatch_argv(&argv(parts), store, now_ms).unwrap_or_else(|err| err.to_resp())
+}
+
+pub fn run(store: &mut Store, parts: &[&[u8]]) -> RespFrame {
+    run_at(store, NOW, parts)
+}
+
+pub fn run_str_at(store: &mut Store, now_ms: u64, parts: &[&str]) -> RespFrame {
+    dispatch_argv(&argv_str(parts), store, now_ms).unwrap_or_else(|err| err.to_resp())
+}
+
+pub fn run_str(store: &mut Store, parts: &[&str]) -> RespFrame {
+    run_str_at(store, NOW, parts)
+}
+
+pub fn run_ok(store: &mut Store, parts: &[&[u8]]) -> RespFrame {
+    dispatch_argv(&argv(parts), store, NOW).expect("command succeeds")
+}
+
+pub fn bulk(value: impl AsRef<[u8]>) -> RespFrame {
+    RespFrame::BulkString(Some(value.as_ref().to_vec()))
+}
+
+pub fn int(value: i64) -> RespFrame {
+    RespFrame::Integer(value)
+}
+
+pub fn err(message: &str) -> RespFrame {
+    RespFrame::Error(message.to_string())
+}
+
+pub fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
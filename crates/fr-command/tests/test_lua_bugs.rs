@@ -1,3 +1,5 @@
+#![cfg(feature = "scripting")]
+
 use fr_command::lua_eval::eval_script;
 use fr_store::Store;
 
@@ -0,0 +1,102 @@
+//! Binary-safe handling of command names that are not commands.
+//!
+//! argv[0] is arbitrary bytes. Whatever arrives — invalid UTF-8, embedded
+//! NULs, an empty name, megabytes of garbage — must come back as a
+//! well-formed `unknown command` error rendered like upstream's
+//! `%.128s`, and building that error must only ever look at the capped
+//! prefix. The shared `alloc_meter` tracks peak heap use while the command
+//! is dispatched so an allocation proportional to the input shows up.
+
+#[path = "support/alloc_meter.rs"]
+mod alloc_meter;
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::NOW;
+
+const GARBAGE: usize = 1024 * 1024;
+/// Far above the 128-byte name cap plus the 128-byte preview, far below a
+/// copy of the garbage.
+const SMALL: isize = 16 * 1024;
+
+/// Dispatch `argv` with tracking enabled and return the rendered reply and
+/// the most heap bytes held at once.
+fn dispatch_measured(argv: &[Vec<u8>]) -> (RespFrame, isize) {
+    let mut store = Store::new();
+    let (reply, usage) = alloc_meter::measure(|| {
+        dispatch_argv(argv, &mut store, NOW).unwrap_or_else(|err| err.to_resp())
+    });
+    (reply, usage.peak)
+}
+
+fn unknown(command: &str, args: &str) -> RespFrame {
+    RespFrame::Error(format!(
+        "ERR unknown command '{command}', with args beginning with: {args}"
+    ))
+}
+
+#[test]
+fn invalid_utf8_names_are_unknown_commands() {
+    let (reply, _) = dispatch_measured(&[vec![0xFF], b"k".to_vec()]);
+    assert_eq!(reply, unknown("\u{FFFD}", "'k' "));
+
+    let (reply, _) = dispatch_measured(&[b"G\xC3T".to_vec()]);
+    assert_eq!(reply, unknown("G\u{FFFD}T", ""));
+
+    // Binary arguments are rendered the same way.
+    let (reply, _) = dispatch_measured(&[b"nope".to_vec(), vec![0xFE, b'a'], b"b\r\nc".to_vec()]);
+    assert_eq!(reply, unknown("nope", "'\u{FFFD}a' 'b  c' "));
+}
+
+#[test]
+fn names_stop_at_the_first_nul() {
+    // A real command name followed by a NUL is still not that command.
+    let (reply, _) = dispatch_measured(&[b"GET\0".to_vec(), b"k".to_vec()]);
+    assert_eq!(reply, unknown("GET", "'k' "));
+    let (reply, _) = dispatch_measured(&[b"P\0ING".to_vec()]);
+    assert_eq!(reply, unknown("P", ""));
+    let (reply, _) = dispatch_measured(&[b"\0".to_vec(), b"a\0b".to_vec()]);
+    assert_eq!(reply, unknown("", "'a' "));
+}
+
+#[test]
+fn empty_and_overlong_names_are_unknown_commands() {
+    let (reply, _) = dispatch_measured(&[Vec::new()]);
+    assert_eq!(reply, unknown("", ""));
+
+    // One byte past the longest possible name, built from a real command.
+    let mut name = b"GET".to_vec();
+    name.resize(65, b'X');
+    let (reply, _) = dispatch_measured(&[name.clone()]);
+    assert_eq!(reply, unknown(std::str::from_utf8(&name).unwrap(), ""));
+}
+
+#[test]
+fn megabyte_garbage_costs_only_the_capped_preview() {
+    let capped_x = "x".repeat(128);
+
+    let (reply, peak) = dispatch_measured(&[vec![b'x'; GARBAGE]]);
+    assert_eq!(reply, unknown(&capped_x, ""));
+    assert!(peak < SMALL, "ascii name peaked at {peak} bytes");
+
+    // Every 0xFF is its own invalid sequence: 42 replacement characters fit
+    // the 128-byte cap.
+    let (reply, peak) = dispatch_measured(&[vec![0xFF; GARBAGE]]);
+    assert_eq!(reply, unknown(&"\u{FFFD}".repeat(42), ""));
+    assert!(peak < SMALL, "binary name peaked at {peak} bytes");
+
+    let (reply, peak) =
+        dispatch_measured(&[b"nope".to_vec(), vec![0xFF; GARBAGE], vec![b'y'; GARBAGE]]);
+    // The first argument fills the preview; the second is never read.
+    let preview = format!("'{}' ", "\u{FFFD}".repeat(41));
+    assert_eq!(reply, unknown("nope", &preview));
+    assert!(peak < SMALL, "binary arguments peaked at {peak} bytes");
+
+    let (reply, peak) = dispatch_measured(&[b"nope".to_vec(), vec![b'y'; GARBAGE]]);
+    assert_eq!(reply, unknown("nope", &format!("'{}' ", "y".repeat(125))));
+    assert!(peak < SMALL, "ascii argument peaked at {peak} bytes");
+}
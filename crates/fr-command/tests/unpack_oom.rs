@@ -1,3 +1,5 @@
+#![cfg(feature = "scripting")]
+
 #[test]
 fn test_unpack_oom() {
     let mut store = fr_store::Store::new();
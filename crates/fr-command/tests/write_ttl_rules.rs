@@ -0,0 +1,315 @@
+//! TTL survival rules for write commands against a volatile key.
+//!
+//! Redis clears a key's TTL only when a command replaces the whole value
+//! (SET without KEEPTTL, GETSET, MSET, RESTORE REPLACE); every command that
+//! modifies the value in place (APPEND, INCR*, SETRANGE, SETBIT, LPUSH, HSET,
+//! SADD, ZADD, XADD, ...) must leave it untouched. Each row seeds a key, gives
+//! it a TTL, applies one write, and checks PTTL against the rule.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run;
+
+const TTL_MS: i64 = 50_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ttl {
+    Preserved,
+    Cleared,
+}
+
+/// (seed, write, expected TTL outcome)
+type Row = (&'static [&'static [u8]], &'static [&'static [u8]], Ttl);
+
+const ROWS: &[Row] = &[
+    // Whole-value replacement clears the TTL.
+    (&[b"SET", b"k", b"v"], &[b"SET", b"k", b"w"], Ttl::Cleared),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SET", b"k", b"w", b"XX"],
+        Ttl::Cleared,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SET", b"k", b"w", b"GET"],
+        Ttl::Cleared,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"GETSET", b"k", b"w"],
+        Ttl::Cleared,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"MSET", b"k", b"w", b"other", b"x"],
+        Ttl::Cleared,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"GETEX", b"k", b"PERSIST"],
+        Ttl::Cleared,
+    ),
+    // In-place string modification preserves it.
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SET", b"k", b"w", b"KEEPTTL"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SET", b"k", b"w", b"NX"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SETNX", b"k", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"MSETNX", b"k", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"APPEND", b"k", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SETRANGE", b"k", b"3", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"SETBIT", b"k", b"100", b"1"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"v"],
+        &[b"BITFIELD", b"k", b"SET", b"u8", b"0", b"7"],
+        Ttl::Preserved,
+    ),
+    (&[b"SET", b"k", b"v"], &[b"GETEX", b"k"], Ttl::Preserved),
+    (&[b"SET", b"k", b"v"], &[b"GET", b"k"], Ttl::Preserved),
+    (&[b"SET", b"k", b"10"], &[b"INCR", b"k"], Ttl::Preserved),
+    (&[b"SET", b"k", b"10"], &[b"DECR", b"k"], Ttl::Preserved),
+    (
+        &[b"SET", b"k", b"10"],
+        &[b"INCRBY", b"k", b"5"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"10"],
+        &[b"DECRBY", b"k", b"5"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SET", b"k", b"10"],
+        &[b"INCRBYFLOAT", b"k", b"1.5"],
+        Ttl::Preserved,
+    ),
+    #[cfg(feature = "hyperloglog")]
+    (
+        &[b"PFADD", b"k", b"a"],
+        &[b"PFADD", b"k", b"b"],
+        Ttl::Preserved,
+    ),
+    // Aggregates modified in place preserve it.
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LPUSH", b"k", b"x"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"RPUSHX", b"k", b"x"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LSET", b"k", b"0", b"x"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LINSERT", b"k", b"BEFORE", b"a", b"x"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LPOP", b"k"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LTRIM", b"k", b"0", b"0"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"RPUSH", b"k", b"a", b"b"],
+        &[b"LREM", b"k", b"0", b"a"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"v"],
+        &[b"HSET", b"k", b"f", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"v"],
+        &[b"HSET", b"k", b"g", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"v"],
+        &[b"HMSET", b"k", b"g", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"v"],
+        &[b"HSETNX", b"k", b"g", b"w"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"1"],
+        &[b"HINCRBY", b"k", b"f", b"2"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"1"],
+        &[b"HINCRBYFLOAT", b"k", b"f", b"2.5"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"HSET", b"k", b"f", b"v", b"g", b"w"],
+        &[b"HDEL", b"k", b"f"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SADD", b"k", b"a"],
+        &[b"SADD", b"k", b"b"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SADD", b"k", b"a", b"b"],
+        &[b"SREM", b"k", b"a"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"SADD", b"k", b"a", b"b"],
+        &[b"SPOP", b"k"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"a"],
+        &[b"ZADD", b"k", b"2", b"b"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"a"],
+        &[b"ZADD", b"k", b"5", b"a"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"a"],
+        &[b"ZINCRBY", b"k", b"2", b"a"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"a", b"2", b"b"],
+        &[b"ZREM", b"k", b"a"],
+        Ttl::Preserved,
+    ),
+    (
+        &[b"ZADD", b"k", b"1", b"a", b"2", b"b"],
+        &[b"ZPOPMIN", b"k"],
+        Ttl::Preserved,
+    ),
+    #[cfg(feature = "geo")]
+    (
+        &[b"GEOADD", b"k", b"13.36", b"38.11", b"a"],
+        &[b"GEOADD", b"k", b"15.08", b"37.50", b"b"],
+        Ttl::Preserved,
+    ),
+    #[cfg(feature = "streams")]
+    (
+        &[b"XADD", b"k", b"1-1", b"f", b"v"],
+        &[b"XADD", b"k", b"2-1", b"f", b"v"],
+        Ttl::Preserved,
+    ),
+    #[cfg(feature = "streams")]
+    (
+        &[b"XADD", b"k", b"1-1", b"f", b"v"],
+        &[b"XDEL", b"k", b"1-1"],
+        Ttl::Preserved,
+    ),
+    #[cfg(feature = "streams")]
+    (
+        &[b"XADD", b"k", b"1-1", b"f", b"v"],
+        &[b"XTRIM", b"k", b"MAXLEN", b"0"],
+        Ttl::Preserved,
+    ),
+];
+
+fn describe(argv: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&argv.join(&b' ')).into_owned()
+}
+
+#[test]
+fn write_commands_preserve_or_clear_ttl_per_redis_rule() {
+    for &(seed, write, expected) in ROWS {
+        let mut store = Store::new();
+        let seeded = run(&mut store, seed);
+        assert!(
+            !matches!(seeded, RespFrame::Error(_)),
+            "seed {} failed: {seeded:?}",
+            describe(seed)
+        );
+        assert_eq!(
+            run(
+                &mut store,
+                &[b"PEXPIRE", b"k", TTL_MS.to_string().as_bytes()]
+            ),
+            RespFrame::Integer(1)
+        );
+
+        let reply = run(&mut store, write);
+        assert!(
+            !matches!(reply, RespFrame::Error(_)),
+            "{} failed: {reply:?}",
+            describe(write)
+        );
+
+        let expected_pttl = match expected {
+            Ttl::Preserved => TTL_MS,
+            Ttl::Cleared => -1,
+        };
+        assert_eq!(
+            run(&mut store, &[b"PTTL", b"k"]),
+            RespFrame::Integer(expected_pttl),
+            "{} after seeding with {} should leave the TTL {expected:?}",
+            describe(write),
+            describe(seed)
+        );
+    }
+}
+
+#[test]
+fn restore_replace_clears_ttl_unless_given_one() {
+    let mut store = Store::new();
+    run(&mut store, &[b"SET", b"src", b"payload"]);
+    let RespFrame::BulkString(Some(dump)) = run(&mut store, &[b"DUMP", b"src"]) else {
+        panic!("DUMP must return a payload");
+    };
+    run(&mut store, &[b"SET", b"k", b"v", b"PX", b"50000"]);
+
+    assert_eq!(
+        run(&mut store, &[b"RESTORE", b"k", b"0", &dump, b"REPLACE"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(run(&mut store, &[b"PTTL", b"k"]), RespFrame::Integer(-1));
+}
@@ -0,0 +1,225 @@
+//! Peak-memory gate for bulk ZADD.
+//!
+//! ZADD used to copy every `(score, member)` pair out of argv into a
+//! `Vec<(f64, Vec<u8>)>` before touching the store, so a bulk load briefly
+//! held the whole payload twice. The handler now parses the scores once into
+//! a `Vec<f64>` and streams them, zipped with the argv members, into
+//! `Store::zadd_iter`. HSET and XADD hand their argv tail to the store the
+//! same way. The shared `alloc_meter` tracks live/peak heap bytes on the
+//! measuring thread only.
+
+#[path = "support/alloc_meter.rs"]
+mod alloc_meter;
+
+use alloc_meter::Usage;
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::{Store, ZaddOptions};
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::NOW;
+
+const PAIRS: usize = 100_000;
+
+/// Run `op` metered on this thread and report its heap usage.
+fn measure<T>(op: impl FnOnce() -> T) -> (T, Usage) {
+    // The first dispatch in the process builds the static command lookup
+    // index; build it unmetered so it is not charged to whichever op runs
+    // first.
+    let _ = dispatch_argv(&[b"ZCARD".to_vec(), b"z".to_vec()], &mut Store::new(), NOW);
+    alloc_meter::measure(op)
+}
+
+fn zadd_argv(score: &str) -> Vec<Vec<u8>> {
+    let mut argv = Vec::with_capacity(2 + PAIRS * 2);
+    argv.push(b"ZADD".to_vec());
+    argv.push(b"z".to_vec());
+    for idx in 0..PAIRS {
+        argv.push(format!("{score}{idx}").into_bytes());
+        argv.push(format!("member:{idx:06}").into_bytes());
+    }
+    argv
+}
+
+#[test]
+fn bulk_zadd_does_not_materialize_pair_vec() {
+    let argv = zadd_argv("");
+
+    let mut streamed_store = Store::new();
+    let (reply, streamed) = measure(|| dispatch_argv(&argv, &mut streamed_store, NOW));
+    assert_eq!(reply, Ok(RespFrame::Integer(i64::try_from(PAIRS).unwrap())));
+
+    // The pre-change handler shape: clone every pair into an owned Vec, then
+    // hand it to the store.
+    let mut legacy_store = Store::new();
+    let ((added, _), legacy) = measure(|| {
+        let pairs: Vec<(f64, Vec<u8>)> = argv[2..]
+            .chunks_exact(2)
+            .map(|pair| {
+                let score = std::str::from_utf8(&pair[0]).unwrap().parse().unwrap();
+                (score, pair[1].clone())
+            })
+            .collect();
+        legacy_store
+            .zadd_with_options(b"z", pairs, ZaddOptions::default(), NOW)
+            .unwrap()
+    });
+    assert_eq!(added, PAIRS);
+
+    assert_eq!(streamed.retained, legacy.retained);
+
+    // The streamed path trades the pair Vec for a Vec of parsed scores, so
+    // it must request at least the difference less.
+    let pair_vec_bytes = PAIRS * size_of::<(f64, Vec<u8>)>();
+    let score_vec_bytes = PAIRS * size_of::<f64>();
+    assert!(
+        streamed.requested + pair_vec_bytes <= legacy.requested + score_vec_bytes,
+        "streamed path requested {} B, pair-Vec path {} B; expected a gap of at least {} B",
+        streamed.requested,
+        legacy.requested,
+        pair_vec_bytes - score_vec_bytes
+    );
+    // And the bulk load never holds the payload twice: the transient
+    // overhead on top of what the store keeps stays well under the store's
+    // own footprint.
+    assert!(
+        streamed.peak < streamed.retained * 3 / 2,
+        "peak {} B vs retained {} B",
+        streamed.peak,
+        streamed.retained
+    );
+    // The parsed scores stay live until the store returns; the legacy pair
+    // Vec is already gone by the time the sorted set is built.
+    assert!(streamed.peak <= legacy.peak + isize::try_from(score_vec_bytes).unwrap());
+}
+
+#[test]
+fn bulk_hset_and_xadd_do_not_copy_field_lists() {
+    let mut hset = Vec::with_capacity(2 + PAIRS * 2);
+    hset.extend([b"HSET".to_vec(), b"h".to_vec()]);
+    let mut xadd = Vec::with_capacity(3 + PAIRS * 2);
+    xadd.extend([b"XADD".to_vec(), b"s".to_vec(), b"1-1".to_vec()]);
+    for idx in 0..PAIRS {
+        let field = format!("field:{idx:06}").into_bytes();
+        let value = format!("value:{idx:06}").into_bytes();
+        hset.extend([field.clone(), value.clone()]);
+        xadd.extend([field, value]);
+    }
+
+    let mut store = Store::new();
+    let (reply, streamed) = measure(|| dispatch_argv(&hset, &mut store, NOW));
+    assert_eq!(reply, Ok(RespFrame::Integer(i64::try_from(PAIRS).unwrap())));
+    // The pre-change handler collected a `Vec<&[u8]>` of every argument.
+    let mut legacy_store = Store::new();
+    let (added, legacy) = measure(|| {
+        let pairs: Vec<&[u8]> = hset[2..].iter().map(Vec::as_slice).collect();
+        legacy_store.hset_borrowed_many(b"h", &pairs, NOW).unwrap()
+    });
+    assert_eq!(added, PAIRS);
+    let slice_vec_bytes = PAIRS * 2 * size_of::<&[u8]>();
+    assert!(
+        streamed.requested + slice_vec_bytes <= legacy.requested,
+        "HSET requested {} B, slice-Vec path {} B",
+        streamed.requested,
+        legacy.requested
+    );
+
+    let mut store = Store::new();
+    let (reply, streamed) = measure(|| dispatch_argv(&xadd, &mut store, NOW));
+    assert_eq!(reply, Ok(RespFrame::BulkString(Some(b"1-1".to_vec()))));
+    // The pre-change handler cloned every field and value into owned pairs.
+    let mut legacy_store = Store::new();
+    let ((), legacy) = measure(|| {
+        let fields: Vec<(Vec<u8>, Vec<u8>)> = xadd[3..]
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+        legacy_store.xadd(b"s", (1, 1), &fields, NOW).unwrap();
+    });
+    let field_vec_bytes = PAIRS * size_of::<(Vec<u8>, Vec<u8>)>();
+    assert!(
+        streamed.requested + field_vec_bytes <= legacy.requested,
+        "XADD requested {} B, owned-pair path {} B",
+        streamed.requested,
+        legacy.requested
+    );
+}
+
+#[test]
+fn bulk_zadd_reply_counts_are_unchanged() {
+    let mut store = Store::new();
+    let argv = zadd_argv("");
+    assert_eq!(
+        dispatch_argv(&argv, &mut store, NOW),
+        Ok(RespFrame::Integer(i64::try_from(PAIRS).unwrap()))
+    );
+    // Re-adding the same pairs adds nothing and changes nothing.
+    assert_eq!(
+        dispatch_argv(&argv, &mut store, NOW),
+        Ok(RespFrame::Integer(0))
+    );
+
+    // CH counts every rescored member.
+    let mut rescored = zadd_argv("1");
+    rescored.insert(2, b"CH".to_vec());
+    assert_eq!(
+        dispatch_argv(&rescored, &mut store, NOW),
+        Ok(RespFrame::Integer(i64::try_from(PAIRS).unwrap()))
+    );
+    assert_eq!(
+        dispatch_argv(&[b"ZCARD".to_vec(), b"z".to_vec()], &mut store, NOW),
+        Ok(RespFrame::Integer(i64::try_from(PAIRS).unwrap()))
+    );
+
+    // A bad score anywhere in the batch rejects the whole call untouched.
+    let mut bad = zadd_argv("");
+    bad[2 + 2 * (PAIRS - 1)] = b"nope".to_vec();
+    bad[1] = b"fresh".to_vec();
+    assert!(dispatch_argv(&bad, &mut store, NOW).is_err());
+    assert_eq!(
+        dispatch_argv(&[b"EXISTS".to_vec(), b"fresh".to_vec()], &mut store, NOW),
+        Ok(RespFrame::Integer(0))
+    );
+}
+
+#[test]
+fn fresh_key_repeats_follow_flag_order() {
+    let run = |store: &mut Store, argv: &[&[u8]]| {
+        let argv: Vec<Vec<u8>> = argv.iter().map(|a| a.to_vec()).collect();
+        dispatch_argv(&argv, store, NOW).unwrap_or_else(|err| err.to_resp())
+    };
+    let score = |store: &mut Store, key: &[u8]| run(store, &[b"ZSCORE", key, b"a"]);
+    let bulk = |bytes: &[u8]| RespFrame::BulkString(Some(bytes.to_vec()));
+
+    let mut store = Store::new();
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZADD", b"gt", b"GT", b"CH", b"20", b"a", b"10", b"a"]
+        ),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(score(&mut store, b"gt"), bulk(b"20"));
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZADD", b"lt", b"LT", b"CH", b"20", b"a", b"10", b"a"]
+        ),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(score(&mut store, b"lt"), bulk(b"10"));
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZADD", b"nx", b"NX", b"CH", b"1", b"a", b"2", b"a"]
+        ),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(score(&mut store, b"nx"), bulk(b"1"));
+    assert_eq!(
+        run(&mut store, &[b"ZADD", b"ch", b"CH", b"1", b"a", b"2", b"a"]),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(score(&mut store, b"ch"), bulk(b"2"));
+}
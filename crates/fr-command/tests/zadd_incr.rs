@@ -0,0 +1,156 @@
+//! ZADD ... INCR: ZINCRBY gated by the NX/XX/GT/LT conditions.
+//!
+//! The reply is the member's new score as a bulk string, or nil when a
+//! condition refused the update — in which case the stored score is left
+//! alone. GT/LT compare the RESULTING score with the current one and never
+//! stop a new member from being added; CH has no effect on the reply.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{err, run_str as run};
+
+fn bulk(value: Option<&str>) -> RespFrame {
+    RespFrame::BulkString(value.map(|v| v.as_bytes().to_vec()))
+}
+
+/// `z` holding `m` at 10 and an unrelated `other` at 0.
+fn seeded() -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["ZADD", "z", "10", "m", "0", "other"]);
+    store
+}
+
+fn zadd_incr(store: &mut Store, flags: &[&str], delta: &str, member: &str) -> RespFrame {
+    let mut argv = vec!["ZADD", "z"];
+    argv.extend_from_slice(flags);
+    argv.extend_from_slice(&["INCR", delta, member]);
+    run(store, &argv)
+}
+
+#[test]
+fn every_flag_combination_on_an_existing_member() {
+    // (flags, reply for +5, reply for -5); `m` starts at 10.
+    let cases: [(&[&str], Option<&str>, Option<&str>); 10] = [
+        (&[], Some("15"), Some("5")),
+        (&["CH"], Some("15"), Some("5")),
+        (&["NX"], None, None),
+        (&["XX"], Some("15"), Some("5")),
+        (&["GT"], Some("15"), None),
+        (&["LT"], None, Some("5")),
+        (&["XX", "GT"], Some("15"), None),
+        (&["XX", "LT"], None, Some("5")),
+        (&["GT", "CH"], Some("15"), None),
+        (&["XX", "LT", "CH"], None, Some("5")),
+    ];
+    for (flags, up, down) in cases {
+        for (delta, expected) in [("5", up), ("-5", down)] {
+            let mut store = seeded();
+            assert_eq!(
+                zadd_incr(&mut store, flags, delta, "m"),
+                bulk(expected),
+                "{flags:?} {delta}"
+            );
+            assert_eq!(
+                run(&mut store, &["ZSCORE", "z", "m"]),
+                bulk(Some(expected.unwrap_or("10"))),
+                "{flags:?} {delta}"
+            );
+        }
+    }
+}
+
+#[test]
+fn every_flag_combination_on_a_new_member() {
+    // GT/LT only restrict updates; a new member takes the increment as its
+    // score. XX refuses it outright.
+    let cases: [(&[&str], bool); 8] = [
+        (&[], true),
+        (&["CH"], true),
+        (&["NX"], true),
+        (&["XX"], false),
+        (&["GT"], true),
+        (&["LT"], true),
+        (&["XX", "GT"], false),
+        (&["XX", "LT"], false),
+    ];
+    for (flags, added) in cases {
+        for delta in ["2.5", "-2.5"] {
+            let mut store = seeded();
+            let expected = added.then_some(delta);
+            assert_eq!(
+                zadd_incr(&mut store, flags, delta, "new"),
+                bulk(expected),
+                "{flags:?} {delta}"
+            );
+            assert_eq!(
+                run(&mut store, &["ZSCORE", "z", "new"]),
+                bulk(expected),
+                "{flags:?} {delta}"
+            );
+            let card = if added { 3 } else { 2 };
+            assert_eq!(run(&mut store, &["ZCARD", "z"]), RespFrame::Integer(card));
+        }
+    }
+}
+
+#[test]
+fn a_missing_key_is_created_only_when_the_update_applies() {
+    let mut store = Store::new();
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "XX", "INCR", "1", "m"]),
+        bulk(None)
+    );
+    assert_eq!(run(&mut store, &["EXISTS", "z"]), RespFrame::Integer(0));
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "NX", "INCR", "1", "m"]),
+        bulk(Some("1"))
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "NX", "INCR", "1", "m"]),
+        bulk(None)
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "0.5", "m"]),
+        bulk(Some("1.5"))
+    );
+}
+
+#[test]
+fn incr_takes_exactly_one_pair_and_rejects_nan() {
+    let mut store = seeded();
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "1", "a", "2", "b"]),
+        err("ERR INCR option supports a single increment-element pair")
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "1"]),
+        err("ERR syntax error")
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "NX", "XX", "INCR", "1", "m"]),
+        err("ERR XX and NX options at the same time are not compatible")
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "NX", "GT", "INCR", "1", "m"]),
+        err("ERR GT, LT, and/or NX options at the same time are not compatible")
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "abc", "m"]),
+        err("ERR value is not a valid float")
+    );
+
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "inf", "m"]),
+        bulk(Some("inf"))
+    );
+    assert_eq!(
+        run(&mut store, &["ZADD", "z", "INCR", "-inf", "m"]),
+        err("ERR resulting score is not a number (NaN)")
+    );
+    assert_eq!(run(&mut store, &["ZSCORE", "z", "m"]), bulk(Some("inf")));
+    // Nothing above touched the other member or added any.
+    assert_eq!(run(&mut store, &["ZCARD", "z"]), RespFrame::Integer(2));
+}
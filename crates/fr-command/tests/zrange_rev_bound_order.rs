@@ -0,0 +1,172 @@
+//! ZRANGE bound order for every {by-rank, BYSCORE, BYLEX} x {forward, REV}.
+//!
+//! With REV, BYSCORE and BYLEX take the maximum first (`ZRANGE k 5 3 BYSCORE
+//! REV`, `ZRANGE k + - BYLEX REV`); bounds given in ascending order select
+//! nothing. By-rank REV does not reinterpret the indices: it counts them from
+//! the highest-scored member, exactly like ZREVRANGE. Expected replies are the
+//! ones Redis 7.2 gives on the same ten-member sets.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::run;
+
+fn bulk_strings(reply: RespFrame) -> Vec<String> {
+    let RespFrame::Array(Some(items)) = reply else {
+        panic!("expected an array, got {reply:?}");
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("utf8"),
+            other => panic!("expected a bulk string, got {other:?}"),
+        })
+        .collect()
+}
+
+/// `scored`: a..j with scores 1..10. `lex`: a..j all at score 0.
+fn seeded() -> Store {
+    let mut store = Store::new();
+    for (i, member) in ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]
+        .iter()
+        .enumerate()
+    {
+        let score = (i + 1).to_string();
+        run(
+            &mut store,
+            &[b"ZADD", b"scored", score.as_bytes(), member.as_bytes()],
+        );
+        run(&mut store, &[b"ZADD", b"lex", b"0", member.as_bytes()]);
+    }
+    store
+}
+
+fn check(cases: &[(&[&str], &[&str])]) {
+    let mut store = seeded();
+    for (args, expected) in cases {
+        let mut argv: Vec<&[u8]> = vec![b"ZRANGE"];
+        argv.extend(args.iter().map(|arg| arg.as_bytes()));
+        assert_eq!(
+            bulk_strings(run(&mut store, &argv)),
+            *expected,
+            "ZRANGE {args:?}"
+        );
+    }
+}
+
+#[test]
+fn by_rank_forward() {
+    check(&[
+        (&["scored", "0", "2"], &["a", "b", "c"]),
+        (&["scored", "-3", "-1"], &["h", "i", "j"]),
+        (&["scored", "2", "0"], &[]),
+        (&["scored", "8", "100"], &["i", "j"]),
+        (&["scored", "0", "1", "WITHSCORES"], &["a", "1", "b", "2"]),
+    ]);
+}
+
+#[test]
+fn by_rank_rev_counts_indices_from_the_top() {
+    check(&[
+        (&["scored", "0", "2", "REV"], &["j", "i", "h"]),
+        (&["scored", "-3", "-1", "REV"], &["c", "b", "a"]),
+        (&["scored", "2", "0", "REV"], &[]),
+        (&["scored", "8", "100", "REV"], &["b", "a"]),
+        (
+            &["scored", "0", "1", "REV", "WITHSCORES"],
+            &["j", "10", "i", "9"],
+        ),
+    ]);
+}
+
+#[test]
+fn byscore_forward_takes_min_first() {
+    check(&[
+        (&["scored", "3", "5", "BYSCORE"], &["c", "d", "e"]),
+        (&["scored", "(3", "5", "BYSCORE"], &["d", "e"]),
+        (&["scored", "3", "(5", "BYSCORE"], &["c", "d"]),
+        (&["scored", "5", "3", "BYSCORE"], &[]),
+        (&["scored", "(5", "(1", "BYSCORE"], &[]),
+        (
+            &["scored", "-inf", "+inf", "BYSCORE", "LIMIT", "2", "3"],
+            &["c", "d", "e"],
+        ),
+    ]);
+}
+
+#[test]
+fn byscore_rev_takes_max_first() {
+    check(&[
+        (&["scored", "5", "3", "BYSCORE", "REV"], &["e", "d", "c"]),
+        (&["scored", "(5", "(1", "BYSCORE", "REV"], &["d", "c", "b"]),
+        (&["scored", "5", "5", "BYSCORE", "REV"], &["e"]),
+        (&["scored", "3", "5", "BYSCORE", "REV"], &[]),
+        (&["scored", "1", "5", "BYSCORE", "REV"], &[]),
+        (&["scored", "-inf", "+inf", "BYSCORE", "REV"], &[]),
+        (
+            &[
+                "scored", "+inf", "-inf", "BYSCORE", "REV", "LIMIT", "0", "2",
+            ],
+            &["j", "i"],
+        ),
+    ]);
+}
+
+#[test]
+fn bylex_forward_takes_min_first() {
+    check(&[
+        (&["lex", "[c", "[e", "BYLEX"], &["c", "d", "e"]),
+        (&["lex", "(c", "[e", "BYLEX"], &["d", "e"]),
+        (&["lex", "-", "(c", "BYLEX"], &["a", "b"]),
+        (&["lex", "[e", "[c", "BYLEX"], &[]),
+        (&["lex", "+", "-", "BYLEX"], &[]),
+        (
+            &["lex", "-", "+", "BYLEX", "LIMIT", "7", "5"],
+            &["h", "i", "j"],
+        ),
+    ]);
+}
+
+#[test]
+fn bylex_rev_takes_max_first() {
+    check(&[
+        (&["lex", "[e", "[c", "BYLEX", "REV"], &["e", "d", "c"]),
+        (
+            &["lex", "(e", "-", "BYLEX", "REV", "LIMIT", "1", "2"],
+            &["c", "b"],
+        ),
+        (&["lex", "+", "(h", "BYLEX", "REV"], &["j", "i"]),
+        (&["lex", "[c", "[e", "BYLEX", "REV"], &[]),
+        (&["lex", "-", "+", "BYLEX", "REV"], &[]),
+    ]);
+}
+
+#[test]
+fn rev_forms_agree_with_the_dedicated_reverse_commands() {
+    let mut store = seeded();
+    let pairs: [(&[&str], &[&str]); 3] = [
+        (
+            &["ZRANGE", "scored", "(8", "2", "BYSCORE", "REV"],
+            &["ZREVRANGEBYSCORE", "scored", "(8", "2"],
+        ),
+        (
+            &["ZRANGE", "lex", "[g", "(b", "BYLEX", "REV"],
+            &["ZREVRANGEBYLEX", "lex", "[g", "(b"],
+        ),
+        (
+            &["ZRANGE", "scored", "1", "-2", "REV"],
+            &["ZREVRANGE", "scored", "1", "-2"],
+        ),
+    ];
+    for (zrange, legacy) in pairs {
+        let zrange_argv: Vec<&[u8]> = zrange.iter().map(|arg| arg.as_bytes()).collect();
+        let legacy_argv: Vec<&[u8]> = legacy.iter().map(|arg| arg.as_bytes()).collect();
+        assert_eq!(
+            run(&mut store, &zrange_argv),
+            run(&mut store, &legacy_argv),
+            "{zrange:?}"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! `numkeys key [key ...] [options]` parsing shared by ZUNION, ZINTER, ZDIFF,
+//! their STORE variants and ZINTERCARD.
+//!
+//! Every command sees the same numkeys checks and error strings; the options
+//! differ only in which ones are accepted, as in upstream
+//! t_zset.c::zunionInterDiffGenericCommand.
+
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{err, run_str as run};
+
+const SYNTAX: &str = "ERR syntax error";
+
+/// (command, arguments before numkeys, accepts WEIGHTS/AGGREGATE,
+/// WITHSCORES, LIMIT)
+const COMMANDS: &[(&str, &[&str], bool, bool, bool)] = &[
+    ("ZUNION", &[], true, true, false),
+    ("ZINTER", &[], true, true, false),
+    ("ZDIFF", &[], false, true, false),
+    ("ZUNIONSTORE", &["dst"], true, false, false),
+    ("ZINTERSTORE", &["dst"], true, false, false),
+    ("ZDIFFSTORE", &["dst"], false, false, false),
+    ("ZINTERCARD", &[], false, false, true),
+];
+
+fn seeded() -> Store {
+    let mut store = Store::new();
+    run(&mut store, &["ZADD", "a", "1", "x", "2", "y"]);
+    run(&mut store, &["ZADD", "b", "3", "x"]);
+    store
+}
+
+/// Run `command [prefix...] tail...` on a fresh seeded store.
+fn call(command: &str, prefix: &[&str], tail: &[&str]) -> RespFrame {
+    let argv: Vec<&str> = [command]
+        .into_iter()
+        .chain(prefix.iter().copied())
+        .chain(tail.iter().copied())
+        .collect();
+    run(&mut seeded(), &argv)
+}
+
+fn is_error(frame: &RespFrame) -> bool {
+    matches!(frame, RespFrame::Error(_))
+}
+
+#[test]
+fn numkeys_is_validated_the_same_way_everywhere() {
+    for &(command, prefix, ..) in COMMANDS {
+        let needed = format!(
+            "ERR at least 1 input key is needed for '{}' command",
+            command.to_ascii_lowercase()
+        );
+        for numkeys in ["0", "-1", "-9223372036854775808"] {
+            assert_eq!(
+                call(command, prefix, &[numkeys, "a"]),
+                err(&needed),
+                "{command} {numkeys}"
+            );
+        }
+        assert_eq!(
+            call(command, prefix, &["abc", "a"]),
+            err("ERR value is not an integer or out of range"),
+            "{command}"
+        );
+        // More keys than arguments, however large, is a syntax error.
+        for numkeys in ["3", "9223372036854775807"] {
+            assert_eq!(
+                call(command, prefix, &[numkeys, "a", "b"]),
+                err(SYNTAX),
+                "{command} {numkeys}"
+            );
+        }
+        assert!(
+            !is_error(&call(command, prefix, &["2", "a", "b"])),
+            "{command}"
+        );
+    }
+}
+
+#[test]
+fn weights_and_aggregate() {
+    for &(command, prefix, weights, ..) in COMMANDS {
+        let with = |tail: &[&str]| {
+            let mut args = vec!["2", "a", "b"];
+            args.extend_from_slice(tail);
+            call(command, prefix, &args)
+        };
+        let accepted = [
+            &["WEIGHTS", "2", "3"][..],
+            &["WEIGHTS", "1", "inf", "AGGREGATE", "max"],
+            &["AGGREGATE", "MIN", "WEIGHTS", "1", "1"],
+            &["AGGREGATE", "sum", "AGGREGATE", "MAX"],
+        ];
+        for tail in accepted {
+            let reply = with(tail);
+            if weights {
+                assert!(!is_error(&reply), "{command} {tail:?}: {reply:?}");
+            } else {
+                assert_eq!(reply, err(SYNTAX), "{command} {tail:?}");
+            }
+        }
+
+        // WEIGHTS needs one weight per key.
+        for tail in [&["WEIGHTS", "1"][..], &["WEIGHTS"], &["AGGREGATE"]] {
+            assert_eq!(with(tail), err(SYNTAX), "{command} {tail:?}");
+        }
+        assert_eq!(with(&["AGGREGATE", "AVG"]), err(SYNTAX), "{command}");
+        let bad_weight = if weights {
+            err("ERR weight value is not a float")
+        } else {
+            err(SYNTAX)
+        };
+        for weight in ["x", "nan"] {
+            assert_eq!(
+                with(&["WEIGHTS", "1", weight]),
+                bad_weight,
+                "{command} {weight}"
+            );
+        }
+    }
+}
+
+#[test]
+fn withscores_and_limit() {
+    for &(command, prefix, _, withscores, limit) in COMMANDS {
+        let with = |tail: &[&str]| {
+            let mut args = vec!["2", "a", "b"];
+            args.extend_from_slice(tail);
+            call(command, prefix, &args)
+        };
+
+        let reply = with(&["WITHSCORES"]);
+        if withscores {
+            assert!(!is_error(&reply), "{command}: {reply:?}");
+        } else {
+            assert_eq!(reply, err(SYNTAX), "{command}");
+        }
+
+        for tail in [&["LIMIT", "0"][..], &["LIMIT", "1"]] {
+            let reply = with(tail);
+            if limit {
+                assert_eq!(reply, RespFrame::Integer(1), "{command} {tail:?}");
+            } else {
+                assert_eq!(reply, err(SYNTAX), "{command} {tail:?}");
+            }
+        }
+        let negative = if limit {
+            err("ERR LIMIT can't be negative")
+        } else {
+            err(SYNTAX)
+        };
+        for value in ["-1", "x"] {
+            assert_eq!(with(&["LIMIT", value]), negative, "{command} {value}");
+        }
+        assert_eq!(with(&["LIMIT"]), err(SYNTAX), "{command}");
+        assert_eq!(with(&["BOGUS"]), err(SYNTAX), "{command}");
+    }
+}
+
+#[test]
+fn a_wrong_type_key_beats_a_bad_option() {
+    for &(command, prefix, ..) in COMMANDS {
+        let mut store = seeded();
+        run(&mut store, &["SET", "s", "v"]);
+        let mut argv = vec![command];
+        argv.extend_from_slice(prefix);
+        argv.extend_from_slice(&["2", "a", "s", "BOGUS"]);
+        assert_eq!(
+            run(&mut store, &argv),
+            err("WRONGTYPE Operation against a key holding the wrong kind of value"),
+            "{command}"
+        );
+    }
+}
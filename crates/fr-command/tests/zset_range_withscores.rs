@@ -0,0 +1,183 @@
+//! WITHSCORES range replies take each score from the range walk itself.
+//!
+//! ZREVRANGE WITHSCORES used to collect members and then look every one up
+//! again with ZSCORE. The replies must be exactly what that two-pass shape
+//! produced, in both protocols, and the read must still count as a single
+//! keyspace lookup. ZRANGESTORE BYLEX likewise stores the scores its lex walk
+//! carries.
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_store::Store;
+
+#[path = "support/dispatch.rs"]
+mod dispatch;
+use dispatch::{NOW, run};
+
+fn members(reply: RespFrame) -> Vec<Vec<u8>> {
+    let RespFrame::Array(Some(items)) = reply else {
+        panic!("expected an array, got {reply:?}");
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            RespFrame::BulkString(Some(member)) => member,
+            other => panic!("expected a member, got {other:?}"),
+        })
+        .collect()
+}
+
+/// The pre-change reply: each member followed by its own ZSCORE, flat under
+/// RESP2 and `[member, score]` pairs under RESP3.
+fn with_scores_by_lookup(store: &mut Store, key: &[u8], members: Vec<Vec<u8>>) -> RespFrame {
+    let resp3 = store.dispatch_client_ctx.resp_protocol_version == 3;
+    let mut frames = Vec::new();
+    for member in members {
+        let score = run(store, &[b"ZSCORE", key, &member]);
+        let member = RespFrame::BulkString(Some(member));
+        if resp3 {
+            frames.push(RespFrame::Array(Some(vec![member, score])));
+        } else {
+            frames.push(member);
+            frames.push(score);
+        }
+    }
+    RespFrame::Array(Some(frames))
+}
+
+fn seeded(resp: i64) -> Store {
+    let mut store = Store::new();
+    store.dispatch_client_ctx.resp_protocol_version = resp;
+    // Listpack-sized and skiplist-sized sets, with ties and fractional scores,
+    // and an equal-score set for the lex ranges.
+    let mut small: Vec<Vec<u8>> = vec![b"ZADD".to_vec(), b"small".to_vec()];
+    for (i, member) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+        small.push(format!("{}", (i % 3) as f64 * 1.5).into_bytes());
+        small.push(member.as_bytes().to_vec());
+    }
+    dispatch_argv(&small, &mut store, NOW).expect("ZADD small");
+    let mut big: Vec<Vec<u8>> = vec![b"ZADD".to_vec(), b"big".to_vec()];
+    for i in 0..500 {
+        big.push(format!("{}", (i * 37) % 101).into_bytes());
+        big.push(format!("m{i:03}").into_bytes());
+    }
+    dispatch_argv(&big, &mut store, NOW).expect("ZADD big");
+    let mut lex: Vec<Vec<u8>> = vec![b"ZADD".to_vec(), b"lex".to_vec()];
+    for i in 0..500 {
+        lex.push(b"2.5".to_vec());
+        lex.push(format!("k{i:03}").into_bytes());
+    }
+    dispatch_argv(&lex, &mut store, NOW).expect("ZADD lex");
+    store
+}
+
+#[test]
+fn zrevrange_withscores_matches_per_member_lookups() {
+    for resp in [2, 3] {
+        let mut store = seeded(resp);
+        for key in [&b"small"[..], b"big", b"missing"] {
+            for (start, stop) in [
+                (&b"0"[..], &b"-1"[..]),
+                (b"1", b"3"),
+                (b"-4", b"-2"),
+                (b"5", b"2"),
+                (b"100", b"600"),
+            ] {
+                let plain = members(run(&mut store, &[b"ZREVRANGE", key, start, stop]));
+                let expected = with_scores_by_lookup(&mut store, key, plain);
+                assert_eq!(
+                    run(&mut store, &[b"ZREVRANGE", key, start, stop, b"WITHSCORES"]),
+                    expected,
+                    "RESP{resp} {key:?} {start:?} {stop:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn zrangestore_bylex_matches_per_member_lookups() {
+    let mut store = seeded(2);
+    for (min, max) in [
+        (&b"-"[..], &b"+"[..]),
+        (b"[k100", b"(k200"),
+        (b"(k499", b"+"),
+        (b"[z", b"[a"),
+    ] {
+        for limit in [None, Some((&b"0"[..], &b"-1"[..])), Some((b"5", b"10"))] {
+            let mut forward: Vec<&[u8]> = vec![b"ZRANGEBYLEX", b"lex", min, max];
+            let mut reverse: Vec<&[u8]> = vec![b"ZREVRANGEBYLEX", b"lex", max, min];
+            let mut store_fwd: Vec<&[u8]> =
+                vec![b"ZRANGESTORE", b"dst", b"lex", min, max, b"BYLEX"];
+            let mut store_rev: Vec<&[u8]> =
+                vec![b"ZRANGESTORE", b"dst", b"lex", max, min, b"BYLEX", b"REV"];
+            if let Some((offset, count)) = limit {
+                for argv in [&mut forward, &mut reverse, &mut store_fwd, &mut store_rev] {
+                    argv.extend([&b"LIMIT"[..], offset, count]);
+                }
+            }
+
+            for (range, store_cmd) in [(forward, store_fwd), (reverse, store_rev)] {
+                let mut plain = members(run(&mut store, &range));
+                // The destination orders its (equal-score) members ascending.
+                plain.sort();
+                let expected = with_scores_by_lookup(&mut store, b"lex", plain);
+                run(&mut store, &store_cmd);
+                assert_eq!(
+                    run(&mut store, &[b"ZRANGE", b"dst", b"0", b"-1", b"WITHSCORES"]),
+                    expected,
+                    "{store_cmd:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn zrange_bylex_still_rejects_withscores() {
+    let mut store = seeded(2);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZRANGE", b"lex", b"-", b"+", b"BYLEX", b"WITHSCORES"]
+        ),
+        RespFrame::Error(
+            "ERR syntax error, WITHSCORES not supported in combination with BYLEX".to_string()
+        )
+    );
+}
+
+#[test]
+fn zrevrange_withscores_counts_one_keyspace_lookup() {
+    let mut store = seeded(2);
+    let hits = store.stat_keyspace_hits;
+    let misses = store.stat_keyspace_misses;
+    run(
+        &mut store,
+        &[b"ZREVRANGE", b"big", b"0", b"-1", b"WITHSCORES"],
+    );
+    assert_eq!(store.stat_keyspace_hits, hits + 1);
+    run(
+        &mut store,
+        &[b"ZREVRANGE", b"nokey", b"0", b"-1", b"WITHSCORES"],
+    );
+    assert_eq!(store.stat_keyspace_misses, misses + 1);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZREVRANGE", b"nokey", b"0", b"-1", b"WITHSCORES"]
+        ),
+        RespFrame::Array(Some(Vec::new()))
+    );
+
+    run(&mut store, &[b"SET", b"str", b"v"]);
+    assert_eq!(
+        run(
+            &mut store,
+            &[b"ZREVRANGE", b"str", b"0", b"-1", b"WITHSCORES"]
+        ),
+        RespFrame::Error(
+            "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+        )
+    );
+}
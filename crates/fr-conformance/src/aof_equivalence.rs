@@ -0,0 +1,282 @@
+//! AOF replay equivalence: record a workload's AOF, replay it into a fresh
+//! runtime, and check that the two keyspaces agree.
+//!
+//! The recorder runs the workload one command per millisecond on a runtime
+//! with AOF capture on (records stay in memory, nothing is written to disk).
+//! The captured records are encoded, then replayed `REPLAY_LAG_MS` later into a
+//! fresh runtime, the way a restart would load them. Both runtimes are then
+//! observed at the same instant: `DEBUG DIGEST` plus the `PEXPIRETIME` of every
+//! db-0 key. The digest only records *that* a key has a TTL, not when it fires,
+//! so without the expiry snapshot a relative EXPIRE propagated verbatim would
+//! still replay as equivalent.
+//!
+//! [`check_equivalence`] shrinks a divergence to a minimal command window: the
+//! shortest diverging prefix, minus every earlier command the divergence does
+//! not depend on.
+//!
+//! Behind the `aof-equivalence` feature; `tests/aof_replay_equivalence.rs`
+//! drives it with the differential-fuzz grammar.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use fr_persist::{AofRecord, encode_aof_stream};
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+/// Clock reading of the first recorded command.
+pub const RECORD_START_MS: u64 = 1_700_000_000_000;
+/// Gap between the last recorded command and the start of the replay.
+pub const REPLAY_LAG_MS: u64 = 60_000;
+
+/// Which AOF the harness replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Propagation {
+    /// The records the runtime captured: effects, with relative expiries,
+    /// random pops, float increments and auto stream IDs already rewritten
+    /// into deterministic commands.
+    #[default]
+    Effects,
+    /// Every write logged as the client sent it, as propagation worked before
+    /// the effect rewriter. Known to diverge; kept so the harness can show it
+    /// catches what the rewriter fixed.
+    Verbatim,
+}
+
+/// One runtime's keyspace at the observation instant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyspaceSnapshot {
+    /// `DEBUG DIGEST` reply.
+    pub digest: String,
+    /// `PEXPIRETIME` of every key in db 0; -1 for keys without a TTL.
+    pub expiries: BTreeMap<Vec<u8>, i64>,
+}
+
+/// Result of recording a workload and replaying its AOF.
+#[derive(Debug, Clone)]
+pub struct RoundTrip {
+    /// The records that were replayed.
+    pub aof: Vec<AofRecord>,
+    /// Error replies the replay produced, as `(record index, error)`.
+    pub replay_errors: Vec<(usize, String)>,
+    pub recorded: KeyspaceSnapshot,
+    pub replayed: KeyspaceSnapshot,
+}
+
+impl RoundTrip {
+    #[must_use]
+    pub fn diverged(&self) -> bool {
+        !self.replay_errors.is_empty() || self.recorded != self.replayed
+    }
+}
+
+/// A workload whose replayed AOF does not reproduce the recorded keyspace.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// Index in the original workload of the command after which the
+    /// keyspaces first differ.
+    pub step: usize,
+    /// Minimal command window that still diverges; ends with `step`.
+    pub window: Vec<Vec<String>>,
+    /// The window's own round trip.
+    pub round_trip: RoundTrip,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let RoundTrip {
+            aof,
+            replay_errors,
+            recorded,
+            replayed,
+        } = &self.round_trip;
+        writeln!(
+            f,
+            "AOF replay diverged at workload step {} ({}-command window):",
+            self.step,
+            self.window.len()
+        )?;
+        for argv in &self.window {
+            writeln!(f, "  {}", render_argv(argv.iter().map(String::as_bytes)))?;
+        }
+        writeln!(f, "propagated AOF:")?;
+        for record in aof {
+            writeln!(
+                f,
+                "  {}",
+                render_argv(record.argv.iter().map(Vec::as_slice))
+            )?;
+        }
+        for (index, error) in replay_errors {
+            writeln!(f, "replaying record {index} failed: {error}")?;
+        }
+        if recorded.digest != replayed.digest {
+            writeln!(
+                f,
+                "digest: recorded {} replayed {}",
+                recorded.digest, replayed.digest
+            )?;
+        }
+        let keys: BTreeSet<&Vec<u8>> = recorded
+            .expiries
+            .keys()
+            .chain(replayed.expiries.keys())
+            .collect();
+        for key in keys {
+            let (before, after) = (recorded.expiries.get(key), replayed.expiries.get(key));
+            if before != after {
+                writeln!(
+                    f,
+                    "{:?}: recorded {} replayed {}",
+                    String::from_utf8_lossy(key),
+                    describe_expiry(before),
+                    describe_expiry(after)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Record `workload`, replay its AOF and snapshot both runtimes.
+///
+/// # Errors
+///
+/// Returns an error if the AOF stream does not decode or a runtime answers an
+/// observation command with an unexpected reply.
+pub fn record_and_replay(
+    workload: &[Vec<String>],
+    propagation: Propagation,
+) -> Result<RoundTrip, String> {
+    let mut recorder = harness_runtime();
+    // Enables capture only; the records are never flushed to this path.
+    recorder.set_aof_path(std::env::temp_dir().join("fr-aof-equivalence.aof"));
+    let mut aof = Vec::new();
+    let mut now_ms = RECORD_START_MS;
+    for argv in workload {
+        let captured_before = recorder.aof_records().len();
+        let _ = recorder.execute_frame(command_frame(argv), now_ms);
+        let captured = recorder.aof_records().get(captured_before..).unwrap_or(&[]);
+        match propagation {
+            Propagation::Effects => aof.extend_from_slice(captured),
+            Propagation::Verbatim if !captured.is_empty() => aof.push(AofRecord {
+                argv: argv.iter().map(|arg| arg.as_bytes().to_vec()).collect(),
+            }),
+            Propagation::Verbatim => {}
+        }
+        now_ms += 1;
+    }
+
+    let replay_ms = now_ms + REPLAY_LAG_MS;
+    let mut replica = harness_runtime();
+    let replies = replica
+        .replay_aof_stream(&encode_aof_stream(&aof), replay_ms)
+        .map_err(|err| format!("recorded AOF does not decode: {err:?}"))?;
+    let replay_errors = replies
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, reply)| match reply {
+            RespFrame::Error(error) => Some((index, error)),
+            _ => None,
+        })
+        .collect();
+
+    let observe_ms = replay_ms + aof.len() as u64 + 1;
+    Ok(RoundTrip {
+        recorded: snapshot(&mut recorder, observe_ms)?,
+        replayed: snapshot(&mut replica, observe_ms)?,
+        aof,
+        replay_errors,
+    })
+}
+
+/// Check that replaying `workload`'s AOF reproduces its keyspace, and shrink
+/// the workload to a minimal [`Divergence`] when it does not.
+///
+/// # Errors
+///
+/// Propagates [`record_and_replay`] errors.
+pub fn check_equivalence(
+    workload: &[Vec<String>],
+    propagation: Propagation,
+) -> Result<Option<Divergence>, String> {
+    if !record_and_replay(workload, propagation)?.diverged() {
+        return Ok(None);
+    }
+    let mut step = workload.len() - 1;
+    for len in 1..workload.len() {
+        if record_and_replay(&workload[..len], propagation)?.diverged() {
+            step = len - 1;
+            break;
+        }
+    }
+    let mut window = workload[..=step].to_vec();
+    // The last command stays: it is where the divergence shows up.
+    for index in (0..window.len() - 1).rev() {
+        let mut candidate = window.clone();
+        candidate.remove(index);
+        if record_and_replay(&candidate, propagation)?.diverged() {
+            window = candidate;
+        }
+    }
+    let round_trip = record_and_replay(&window, propagation)?;
+    Ok(Some(Divergence {
+        step,
+        window,
+        round_trip,
+    }))
+}
+
+fn harness_runtime() -> Runtime {
+    let mut runtime = Runtime::default_strict();
+    runtime.set_enable_debug_command("yes");
+    runtime
+}
+
+fn command_frame<A: AsRef<[u8]>>(argv: &[A]) -> RespFrame {
+    RespFrame::Array(Some(
+        argv.iter()
+            .map(|arg| RespFrame::BulkString(Some(arg.as_ref().to_vec())))
+            .collect(),
+    ))
+}
+
+fn snapshot(runtime: &mut Runtime, now_ms: u64) -> Result<KeyspaceSnapshot, String> {
+    let digest = match runtime.execute_frame(command_frame(&["DEBUG", "DIGEST"]), now_ms) {
+        RespFrame::SimpleString(digest) => digest,
+        other => return Err(format!("DEBUG DIGEST replied {other:?}")),
+    };
+    let keys = match runtime.execute_frame(command_frame(&["KEYS", "*"]), now_ms) {
+        RespFrame::Array(Some(keys)) => keys,
+        other => return Err(format!("KEYS * replied {other:?}")),
+    };
+    let mut expiries = BTreeMap::new();
+    for key in keys {
+        let RespFrame::BulkString(Some(key)) = key else {
+            return Err(format!("KEYS * returned {key:?}"));
+        };
+        let reply = runtime.execute_frame(
+            command_frame(&[b"PEXPIRETIME".as_slice(), key.as_slice()]),
+            now_ms,
+        );
+        let RespFrame::Integer(at) = reply else {
+            return Err(format!("PEXPIRETIME replied {reply:?}"));
+        };
+        expiries.insert(key, at);
+    }
+    Ok(KeyspaceSnapshot { digest, expiries })
+}
+
+fn render_argv<'a>(argv: impl Iterator<Item = &'a [u8]>) -> String {
+    argv.map(|arg| format!("{:?}", String::from_utf8_lossy(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn describe_expiry(expiry: Option<&i64>) -> String {
+    match expiry {
+        None => "missing".to_string(),
+        Some(-1) => "no TTL".to_string(),
+        Some(at) => format!("PEXPIRETIME {at}"),
+    }
+}
@@ -33,6 +33,8 @@ use crate::log_contract::{
     append_structured_log_jsonl, live_log_output_path,
 };
 
+#[cfg(feature = "aof-equivalence")]
+pub mod aof_equivalence;
 pub mod log_contract;
 pub mod phase2c_schema;
 
@@ -3200,7 +3202,7 @@ fn frame_matches_expected(actual: &RespFrame, expected: &ExpectedFrame) -> bool
     match expected {
         ExpectedFrame::AnyInteger => matches!(actual, RespFrame::Integer(_)),
         ExpectedFrame::AnyBulk => matches!(actual, RespFrame::BulkString(Some(_))),
-        ExpectedFrame::AnySimple => matches!(actual, RespFrame::SimpleString(_)),
+        ExpectedFrame::AnySimple => actual.as_simple_str().is_some(),
         ExpectedFrame::AnyArray => {
             matches!(
                 actual,
@@ -3221,14 +3223,12 @@ fn frame_matches_expected(actual: &RespFrame, expected: &ExpectedFrame) -> bool
             }
             _ => false,
         },
-        ExpectedFrame::SimpleContainsAll { value } => match actual {
-            RespFrame::SimpleString(text) => value.iter().all(|needle| text.contains(needle)),
-            _ => false,
-        },
-        ExpectedFrame::SimplePattern { value } => match actual {
-            RespFrame::SimpleString(text) => simple_pattern_matches(value, text),
-            _ => false,
-        },
+        ExpectedFrame::SimpleContainsAll { value } => actual
+            .as_simple_str()
+            .is_some_and(|text| value.iter().all(|needle| text.contains(needle))),
+        ExpectedFrame::SimplePattern { value } => actual
+            .as_simple_str()
+            .is_some_and(|text| simple_pattern_matches(value, text)),
         ExpectedFrame::Array { value } => match actual {
             RespFrame::Array(Some(items)) => {
                 items.len() == value.len()
@@ -3263,9 +3263,7 @@ fn frame_matches_expected(actual: &RespFrame, expected: &ExpectedFrame) -> bool
             }
             _ => false,
         },
-        ExpectedFrame::Simple { value } => {
-            matches!(actual, RespFrame::SimpleString(actual) if actual == value)
-        }
+        ExpectedFrame::Simple { value } => actual.as_simple_str() == Some(value.as_str()),
         ExpectedFrame::Error { value } => {
             matches!(actual, RespFrame::Error(actual) if actual == value)
         }
@@ -11686,7 +11684,7 @@ mod tests {
             let now_ms = 100u64;
             let runtime_set = runtime.execute_frame(set_frame.clone(), now_ms);
             assert!(
-                matches!(&runtime_set, RespFrame::SimpleString(s) if s == "OK"),
+                runtime_set.as_simple_str() == Some("OK"),
                 "runtime SET failed for key {key}: {runtime_set:?}"
             );
             send_frame(&mut stream, &set_frame).expect("send SET to oracle");
@@ -0,0 +1,158 @@
+//! AOF replay equivalence over random workloads.
+//!
+//! `aof_replay_reproduces_the_recorded_keyspace` draws command sequences from
+//! the differential-fuzz grammar plus commands whose propagated form differs
+//! from what the client sent (relative expiries, partial SPOP, float
+//! increments, pops, XADD `*`). Each sequence is recorded with AOF capture on,
+//! replayed into a fresh runtime, and the two keyspaces are compared by
+//! `DEBUG DIGEST` and per-key expiry. A failure is reported as a minimal
+//! command window with the AOF it propagated.
+//!
+//!   cargo test -p fr-conformance --features aof-equivalence \
+//!       --test aof_replay_equivalence
+//!
+//! Knobs: `FR_AOF_EQUIV_CASES` (default 64) sequences per run.
+
+use fr_conformance::aof_equivalence::{Propagation, check_equivalence, record_and_replay};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use workload_grammar::{FIELDS, MEMBERS, cmd, command, key, pick, value};
+
+mod workload_grammar;
+
+/// Commands the runtime must rewrite before propagating them. Expiries are
+/// long enough that nothing expires before the replayed keyspace is observed.
+fn effect_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        2 => (key(), 100u32..10_000)
+            .prop_map(|(k, secs)| cmd(&["EXPIRE"], vec![k, secs.to_string()])),
+        1 => (key(), 100_000u32..10_000_000)
+            .prop_map(|(k, ms)| cmd(&["PEXPIRE"], vec![k, ms.to_string()])),
+        1 => (key(), value()).prop_map(|(k, v)| cmd(&["SETEX"], vec![k, "500".to_string(), v])),
+        1 => (key(), value())
+            .prop_map(|(k, v)| cmd(&["SET"], vec![k, v, "EX".to_string(), "700".to_string()])),
+        1 => key().prop_map(|k| cmd(&["GETEX"], vec![k, "EX".to_string(), "900".to_string()])),
+        2 => (key(), value()).prop_map(|(k, v)| cmd(&["INCRBYFLOAT"], vec![k, v])),
+        1 => (key(), pick(FIELDS), value())
+            .prop_map(|(k, f, v)| cmd(&["HINCRBYFLOAT"], vec![k, f, v])),
+        2 => (key(), 1u8..3).prop_map(|(k, n)| cmd(&["SPOP"], vec![k, n.to_string()])),
+        1 => (key(), pick(MEMBERS), pick(MEMBERS))
+            .prop_map(|(k, a, b)| cmd(&["SADD"], vec![k, a, b])),
+        1 => key().prop_map(|k| cmd(&["ZPOPMIN"], vec![k])),
+        1 => (key(), key()).prop_map(|(a, b)| {
+            cmd(&["LMPOP", "2"], vec![a, b, "LEFT".to_string()])
+        }),
+        1 => (key(), value())
+            .prop_map(|(k, v)| cmd(&["XADD"], vec![k, "*".to_string(), "f".to_string(), v])),
+    ]
+    .boxed()
+}
+
+fn workload_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        3 => command(),
+        1 => effect_command(),
+    ]
+    .boxed()
+}
+
+fn equivalence_cases() -> u32 {
+    std::env::var("FR_AOF_EQUIV_CASES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(64)
+}
+
+fn workload(steps: &[&[&str]]) -> Vec<Vec<String>> {
+    steps
+        .iter()
+        .map(|step| step.iter().map(|arg| (*arg).to_string()).collect())
+        .collect()
+}
+
+#[test]
+fn aof_replay_reproduces_the_recorded_keyspace() {
+    let config = ProptestConfig {
+        cases: equivalence_cases(),
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    };
+    let mut runner = proptest::test_runner::TestRunner::new(config);
+    let result = runner.run(
+        &proptest::collection::vec(workload_command(), 1..24),
+        |sequence| match check_equivalence(&sequence, Propagation::Effects) {
+            Ok(None) => Ok(()),
+            Ok(Some(divergence)) => Err(TestCaseError::fail(divergence.to_string())),
+            Err(err) => Err(TestCaseError::fail(format!("harness error: {err}"))),
+        },
+    );
+    if let Err(failure) = result {
+        panic!("{failure}");
+    }
+}
+
+#[test]
+fn verbatim_relative_expire_is_caught_and_minimized() {
+    // Propagating EXPIRE as sent re-arms the TTL relative to load time, so the
+    // replayed key outlives the recorded one by the replay lag. The digest
+    // alone cannot see this; the expiry snapshot does.
+    let steps = workload(&[
+        &["SET", "k0", "a"],
+        &["RPUSH", "k1", "x", "y"],
+        &["SET", "k2", "b"],
+        &["EXPIRE", "k0", "100"],
+        &["APPEND", "k2", "c"],
+        &["INCR", "k3"],
+    ]);
+
+    let divergence = check_equivalence(&steps, Propagation::Verbatim)
+        .expect("harness runs")
+        .expect("verbatim EXPIRE diverges");
+    assert_eq!(divergence.step, 3);
+    assert_eq!(
+        divergence.window,
+        workload(&[&["SET", "k0", "a"], &["EXPIRE", "k0", "100"]])
+    );
+    let report = divergence.to_string();
+    assert!(report.contains("\"EXPIRE\" \"k0\" \"100\""), "{report}");
+    assert!(report.contains("\"k0\": recorded PEXPIRETIME"), "{report}");
+    assert_eq!(
+        divergence.round_trip.recorded.digest,
+        divergence.round_trip.replayed.digest
+    );
+
+    // The runtime's own AOF carries the absolute deadline instead.
+    assert!(
+        check_equivalence(&steps, Propagation::Effects)
+            .expect("harness runs")
+            .is_none()
+    );
+    let round_trip = record_and_replay(&steps, Propagation::Effects).expect("harness runs");
+    assert!(
+        round_trip
+            .aof
+            .iter()
+            .any(|record| record.argv[0].eq_ignore_ascii_case(b"PEXPIREAT")),
+        "{:?}",
+        round_trip.aof
+    );
+}
+
+#[test]
+fn append_and_incr_replay_equivalently() {
+    let steps = workload(&[
+        &["APPEND", "k0", "a"],
+        &["APPEND", "k0", "bb"],
+        &["INCR", "k1"],
+        &["INCRBY", "k1", "-3"],
+        &["SET", "k2", "10"],
+        &["INCR", "k2"],
+        &["APPEND", "k2", "0"],
+        &["INCRBYFLOAT", "k2", "2.5"],
+    ]);
+    for propagation in [Propagation::Effects, Propagation::Verbatim] {
+        let round_trip = record_and_replay(&steps, propagation).expect("harness runs");
+        assert!(!round_trip.diverged(), "{propagation:?}: {round_trip:?}");
+        assert_eq!(round_trip.recorded.expiries.len(), 3);
+    }
+}
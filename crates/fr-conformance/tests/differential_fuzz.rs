@@ -0,0 +1,415 @@
+//! Property-based differential testing against a real Redis.
+//!
+//! `differential_fuzz_against_live_redis` draws random command sequences from
+//! a weighted grammar over implemented string/list/hash/set/zset/stream/keyspace
+//! commands, runs each sequence on a fresh in-process `Runtime` and on the
+//! Redis at `REDIS_URL` (after `FLUSHDB`), and compares the replies
+//! frame-by-frame through a normalizer table for the legitimately
+//! nondeterministic commands. proptest shrinks a divergence to a minimal
+//! sequence and the failure message is a ready-to-paste `RegressionCase`.
+//!
+//! The live test is `#[ignore]`d and skips unless `REDIS_URL` is set; point it
+//! at a scratch instance, it flushes the selected db before every case:
+//!
+//!   REDIS_URL=redis://127.0.0.1:6379/9 cargo test -p fr-conformance \
+//!       --test differential_fuzz -- --ignored
+//!
+//! Knobs: `FR_DIFF_FUZZ_CASES` (default 256) sequences per run.
+//!
+//! `regression_corpus_replays_without_divergence` is always on: it replays
+//! `REGRESSION_CORPUS` (divergences found earlier, expected replies captured
+//! from Redis 7.2) against the runtime alone.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fr_protocol::{RespFrame, RespParseError, parse_frame};
+use fr_runtime::Runtime;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use workload_grammar::command;
+
+mod workload_grammar;
+
+// ── normalizers ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Normalizer {
+    /// Replies must match byte-for-byte.
+    Exact,
+    /// Array whose element order is unspecified (SMEMBERS, KEYS, ...).
+    Unordered,
+    /// Flat field/value array whose pair order is unspecified (HGETALL).
+    UnorderedPairs,
+    /// Random or clock-derived content: keep only the reply's shape.
+    Shape,
+    /// TTL: exact for -2/-1, any non-negative value otherwise.
+    TtlClass,
+    /// Stream auto-IDs: mask the ID of XADD replies and XRANGE entries.
+    StreamIds,
+}
+
+fn normalizer_for(argv: &[String]) -> Normalizer {
+    match argv[0].to_ascii_uppercase().as_str() {
+        "SMEMBERS" | "SINTER" | "SUNION" | "SDIFF" | "HKEYS" | "HVALS" | "KEYS" | "SPOP" => {
+            Normalizer::Unordered
+        }
+        "HGETALL" => Normalizer::UnorderedPairs,
+        "RANDOMKEY" | "SRANDMEMBER" | "HRANDFIELD" | "ZRANDMEMBER" | "TIME" | "INFO" | "MEMORY" => {
+            Normalizer::Shape
+        }
+        "TTL" | "PTTL" => Normalizer::TtlClass,
+        "XADD" | "XRANGE" | "XREVRANGE" => Normalizer::StreamIds,
+        _ => Normalizer::Exact,
+    }
+}
+
+fn sort_frames(items: &mut [RespFrame]) {
+    items.sort_by_cached_key(RespFrame::to_bytes);
+}
+
+fn shape(frame: &RespFrame) -> RespFrame {
+    match frame {
+        RespFrame::BulkString(Some(_)) => RespFrame::BulkString(Some(b"<any>".to_vec())),
+        RespFrame::Integer(_) => RespFrame::Integer(0),
+        RespFrame::Array(Some(items)) => RespFrame::Array(Some(items.iter().map(shape).collect())),
+        other => other.clone(),
+    }
+}
+
+fn mask_stream_id(frame: &RespFrame) -> RespFrame {
+    match frame {
+        RespFrame::BulkString(Some(_)) => RespFrame::BulkString(Some(b"<id>".to_vec())),
+        other => other.clone(),
+    }
+}
+
+fn normalize(normalizer: Normalizer, frame: RespFrame) -> RespFrame {
+    match (normalizer, frame) {
+        (Normalizer::Unordered, RespFrame::Array(Some(mut items))) => {
+            sort_frames(&mut items);
+            RespFrame::Array(Some(items))
+        }
+        (Normalizer::UnorderedPairs, RespFrame::Array(Some(items))) => {
+            let mut pairs: Vec<RespFrame> = items
+                .chunks(2)
+                .map(|pair| RespFrame::Array(Some(pair.to_vec())))
+                .collect();
+            sort_frames(&mut pairs);
+            RespFrame::Array(Some(pairs))
+        }
+        (Normalizer::Shape, frame @ RespFrame::Error(_)) => frame,
+        (Normalizer::Shape, frame) => shape(&frame),
+        (Normalizer::TtlClass, RespFrame::Integer(n)) if n >= 0 => RespFrame::Integer(0),
+        (Normalizer::StreamIds, RespFrame::Array(Some(entries))) => RespFrame::Array(Some(
+            entries
+                .iter()
+                .map(|entry| match entry {
+                    RespFrame::Array(Some(parts)) if !parts.is_empty() => {
+                        let mut parts = parts.clone();
+                        parts[0] = mask_stream_id(&parts[0]);
+                        RespFrame::Array(Some(parts))
+                    }
+                    other => other.clone(),
+                })
+                .collect(),
+        )),
+        (Normalizer::StreamIds, frame) => mask_stream_id(&frame),
+        (_, frame) => frame,
+    }
+}
+
+// ── execution ───────────────────────────────────────────────────────────
+
+fn argv_frame(argv: &[String]) -> RespFrame {
+    RespFrame::Array(Some(
+        argv.iter()
+            .map(|arg| RespFrame::BulkString(Some(arg.as_bytes().to_vec())))
+            .collect(),
+    ))
+}
+
+fn wall_clock_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+struct LiveRedis {
+    stream: TcpStream,
+    buf: Vec<u8>,
+}
+
+impl LiveRedis {
+    /// Accepts `redis://host:port[/db]` or a bare `host:port`.
+    fn connect(url: &str) -> Result<Self, String> {
+        let rest = url.strip_prefix("redis://").unwrap_or(url);
+        let (addr, db) = match rest.split_once('/') {
+            Some((addr, db)) if !db.is_empty() => (addr, Some(db)),
+            Some((addr, _)) => (addr, None),
+            None => (rest, None),
+        };
+        let stream = TcpStream::connect(addr).map_err(|err| format!("connect {addr}: {err}"))?;
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .map_err(|err| err.to_string())?;
+        let mut redis = Self {
+            stream,
+            buf: Vec::new(),
+        };
+        if let Some(db) = db {
+            redis.expect_ok(&["SELECT".to_string(), db.to_string()])?;
+        }
+        Ok(redis)
+    }
+
+    fn execute(&mut self, argv: &[String]) -> Result<RespFrame, String> {
+        self.stream
+            .write_all(&argv_frame(argv).to_bytes())
+            .map_err(|err| err.to_string())?;
+        let mut chunk = [0u8; 4096];
+        loop {
+            match parse_frame(&self.buf) {
+                Ok(parsed) => {
+                    self.buf.drain(..parsed.consumed);
+                    return Ok(parsed.frame);
+                }
+                Err(RespParseError::Incomplete) => {}
+                Err(err) => return Err(format!("bad reply from Redis: {err}")),
+            }
+            let n = self
+                .stream
+                .read(&mut chunk)
+                .map_err(|err| err.to_string())?;
+            if n == 0 {
+                return Err("Redis closed the connection".to_string());
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    fn expect_ok(&mut self, argv: &[String]) -> Result<(), String> {
+        match self.execute(argv)? {
+            RespFrame::SimpleString(ok) if ok == "OK" => Ok(()),
+            other => Err(format!("{argv:?} replied {other:?}")),
+        }
+    }
+}
+
+struct Divergence {
+    step: usize,
+    expected: RespFrame,
+    actual: RespFrame,
+}
+
+fn run_differential(
+    redis: &mut LiveRedis,
+    sequence: &[Vec<String>],
+) -> Result<Option<Divergence>, String> {
+    redis.expect_ok(&["FLUSHDB".to_string()])?;
+    let mut runtime = Runtime::default_strict();
+    for (step, argv) in sequence.iter().enumerate() {
+        let expected = redis.execute(argv)?;
+        let actual = runtime.execute_frame(argv_frame(argv), wall_clock_ms());
+        let normalizer = normalizer_for(argv);
+        if normalize(normalizer, expected.clone()) != normalize(normalizer, actual.clone()) {
+            return Ok(Some(Divergence {
+                step,
+                expected,
+                actual,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Render a shrunk divergence as a `REGRESSION_CORPUS` entry.
+fn reproducer(sequence: &[Vec<String>], divergence: &Divergence) -> String {
+    let steps: Vec<String> = sequence[..=divergence.step]
+        .iter()
+        .map(|argv| {
+            let args: Vec<String> = argv.iter().map(|arg| format!("{arg:?}")).collect();
+            format!("        &[{}],", args.join(", "))
+        })
+        .collect();
+    format!(
+        "divergence at step {} (frankenredis replied {:?})\n\
+         paste into REGRESSION_CORPUS:\n\
+         RegressionCase {{\n    name: \"TODO\",\n    steps: &[\n{}\n    ],\n    expected_last: {:?},\n}},",
+        divergence.step,
+        divergence.actual,
+        steps.join("\n"),
+        String::from_utf8_lossy(&divergence.expected.to_bytes()),
+    )
+}
+
+fn fuzz_cases() -> u32 {
+    std::env::var("FR_DIFF_FUZZ_CASES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(256)
+}
+
+#[test]
+#[ignore = "needs a scratch Redis at REDIS_URL"]
+fn differential_fuzz_against_live_redis() {
+    let Ok(url) = std::env::var("REDIS_URL") else {
+        eprintln!("[SKIP] differential_fuzz: REDIS_URL is not set");
+        return;
+    };
+    let redis = std::cell::RefCell::new(LiveRedis::connect(&url).expect("connect to REDIS_URL"));
+    let config = ProptestConfig {
+        cases: fuzz_cases(),
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    };
+    let mut runner = proptest::test_runner::TestRunner::new(config);
+    let result =
+        runner.run(
+            &proptest::collection::vec(command(), 1..24),
+            |sequence| match run_differential(&mut redis.borrow_mut(), &sequence) {
+                Ok(None) => Ok(()),
+                Ok(Some(divergence)) => {
+                    Err(TestCaseError::fail(reproducer(&sequence, &divergence)))
+                }
+                Err(err) => Err(TestCaseError::fail(format!("live Redis error: {err}"))),
+            },
+        );
+    if let Err(failure) = result {
+        panic!("{failure}");
+    }
+}
+
+// ── regression corpus ───────────────────────────────────────────────────
+
+struct RegressionCase {
+    name: &'static str,
+    steps: &'static [&'static [&'static str]],
+    /// Redis 7.2's RESP2 reply to the last step.
+    expected_last: &'static str,
+}
+
+const REGRESSION_CORPUS: &[RegressionCase] = &[
+    RegressionCase {
+        name: "getrange_too_negative_end_clamps_to_zero",
+        steps: &[&["SET", "k0", "hello"], &["GETRANGE", "k0", "0", "-100"]],
+        expected_last: "$1\r\nh\r\n",
+    },
+    RegressionCase {
+        name: "ltrim_to_empty_window_deletes_key",
+        steps: &[
+            &["RPUSH", "k0", "a"],
+            &["LTRIM", "k0", "1", "-1"],
+            &["EXISTS", "k0"],
+        ],
+        expected_last: ":0\r\n",
+    },
+    RegressionCase {
+        name: "incrbyfloat_formats_shortest_decimal",
+        steps: &[&["SET", "k0", "10"], &["INCRBYFLOAT", "k0", "0.1"]],
+        expected_last: "$4\r\n10.1\r\n",
+    },
+    RegressionCase {
+        name: "hincrby_non_integer_field",
+        steps: &[&["HSET", "k0", "f0", "a"], &["HINCRBY", "k0", "f0", "1"]],
+        expected_last: "-ERR hash value is not an integer\r\n",
+    },
+    RegressionCase {
+        name: "wrongtype_list_push_on_set",
+        steps: &[&["SADD", "k0", "m0"], &["LPUSH", "k0", "a"]],
+        expected_last: "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+    },
+    RegressionCase {
+        name: "zrange_withscores_flat_pairs",
+        steps: &[
+            &["ZADD", "k0", "2", "m1", "1", "m0"],
+            &["ZRANGE", "k0", "0", "-1", "WITHSCORES"],
+        ],
+        expected_last: "*4\r\n$2\r\nm0\r\n$1\r\n1\r\n$2\r\nm1\r\n$1\r\n2\r\n",
+    },
+    RegressionCase {
+        name: "xadd_rejects_non_increasing_id",
+        steps: &[
+            &["XADD", "k0", "1-1", "f", "v"],
+            &["XADD", "k0", "1-1", "f", "v"],
+        ],
+        expected_last: "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n",
+    },
+];
+
+#[test]
+fn regression_corpus_replays_without_divergence() {
+    for case in REGRESSION_CORPUS {
+        let mut runtime = Runtime::default_strict();
+        let mut last = None;
+        for step in case.steps {
+            let argv: Vec<String> = step.iter().map(|arg| (*arg).to_string()).collect();
+            last = Some((
+                normalizer_for(&argv),
+                runtime.execute_frame(argv_frame(&argv), wall_clock_ms()),
+            ));
+        }
+        let (normalizer, actual) = last.expect("regression case has steps");
+        let expected = parse_frame(case.expected_last.as_bytes())
+            .expect("expected_last is a RESP2 reply")
+            .frame;
+        assert_eq!(
+            normalize(normalizer, actual),
+            normalize(normalizer, expected),
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn normalizers_mask_only_nondeterministic_content() {
+    let bulk = |bytes: &[u8]| RespFrame::BulkString(Some(bytes.to_vec()));
+    let argv = |parts: &[&str]| -> Vec<String> { parts.iter().map(|p| (*p).to_string()).collect() };
+
+    let members = RespFrame::Array(Some(vec![bulk(b"b"), bulk(b"a")]));
+    let smembers = normalizer_for(&argv(&["smembers", "k"]));
+    assert_eq!(
+        normalize(smembers, members),
+        RespFrame::Array(Some(vec![bulk(b"a"), bulk(b"b")]))
+    );
+
+    let hgetall = normalizer_for(&argv(&["HGETALL", "k"]));
+    assert_eq!(
+        normalize(
+            hgetall,
+            RespFrame::Array(Some(vec![bulk(b"f2"), bulk(b"1"), bulk(b"f1"), bulk(b"2")]))
+        ),
+        normalize(
+            hgetall,
+            RespFrame::Array(Some(vec![bulk(b"f1"), bulk(b"2"), bulk(b"f2"), bulk(b"1")]))
+        )
+    );
+
+    let ttl = normalizer_for(&argv(&["TTL", "k"]));
+    assert_eq!(
+        normalize(ttl, RespFrame::Integer(999)),
+        normalize(ttl, RespFrame::Integer(1000))
+    );
+    assert_ne!(
+        normalize(ttl, RespFrame::Integer(-2)),
+        normalize(ttl, RespFrame::Integer(-1))
+    );
+
+    let randomkey = normalizer_for(&argv(&["RANDOMKEY"]));
+    assert_eq!(
+        normalize(randomkey, bulk(b"k1")),
+        normalize(randomkey, bulk(b"k2"))
+    );
+    assert_ne!(
+        normalize(randomkey, bulk(b"k1")),
+        normalize(randomkey, RespFrame::BulkString(None))
+    );
+
+    let xadd = normalizer_for(&argv(&["XADD", "k", "*", "f", "v"]));
+    assert_eq!(normalize(xadd, bulk(b"1-0")), normalize(xadd, bulk(b"2-5")));
+
+    let get = normalizer_for(&argv(&["GET", "k"]));
+    assert_ne!(normalize(get, bulk(b"a")), normalize(get, bulk(b"b")));
+}
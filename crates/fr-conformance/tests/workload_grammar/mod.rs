@@ -0,0 +1,159 @@
+//! Weighted command grammar shared by the property-based harnesses.
+//!
+//! Draws commands over implemented string/list/hash/set/zset/stream/keyspace
+//! commands against a small key, value, field and member pool, so random
+//! sequences keep hitting the same keys. `differential_fuzz` runs the
+//! sequences against a live Redis; `aof_replay_equivalence` records and
+//! replays their AOF.
+
+// Each test binary that includes this module uses a different subset.
+#![allow(dead_code)]
+
+use proptest::prelude::*;
+
+pub const KEYS: &[&str] = &["k0", "k1", "k2", "k3"];
+pub const VALUES: &[&str] = &["a", "bb", "0", "1", "-3", "10", "2.5", ""];
+pub const FIELDS: &[&str] = &["f0", "f1", "f2"];
+pub const MEMBERS: &[&str] = &["m0", "m1", "m2", "7", "-1"];
+
+pub fn pick(pool: &'static [&'static str]) -> impl Strategy<Value = String> {
+    proptest::sample::select(pool).prop_map(str::to_string)
+}
+
+pub fn key() -> impl Strategy<Value = String> {
+    pick(KEYS)
+}
+
+pub fn value() -> impl Strategy<Value = String> {
+    pick(VALUES)
+}
+
+pub fn index() -> impl Strategy<Value = String> {
+    (-6i64..6).prop_map(|n| n.to_string())
+}
+
+pub fn cmd(parts: &[&str], rest: Vec<String>) -> Vec<String> {
+    parts
+        .iter()
+        .map(|part| (*part).to_string())
+        .chain(rest)
+        .collect()
+}
+
+pub fn string_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        4 => (key(), value()).prop_map(|(k, v)| cmd(&["SET"], vec![k, v])),
+        4 => key().prop_map(|k| cmd(&["GET"], vec![k])),
+        2 => (key(), value()).prop_map(|(k, v)| cmd(&["APPEND"], vec![k, v])),
+        2 => key().prop_map(|k| cmd(&["INCR"], vec![k])),
+        1 => (key(), index()).prop_map(|(k, n)| cmd(&["INCRBY"], vec![k, n])),
+        1 => (key(), value()).prop_map(|(k, v)| cmd(&["INCRBYFLOAT"], vec![k, v])),
+        1 => (key(), index(), index()).prop_map(|(k, s, e)| cmd(&["GETRANGE"], vec![k, s, e])),
+        1 => (key(), 0u8..4, value())
+            .prop_map(|(k, off, v)| cmd(&["SETRANGE"], vec![k, off.to_string(), v])),
+        1 => key().prop_map(|k| cmd(&["STRLEN"], vec![k])),
+        1 => (key(), value(), key(), value())
+            .prop_map(|(k1, v1, k2, v2)| cmd(&["MSET"], vec![k1, v1, k2, v2])),
+        1 => (key(), key()).prop_map(|(k1, k2)| cmd(&["MGET"], vec![k1, k2])),
+    ]
+    .boxed()
+}
+
+pub fn list_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        3 => (key(), value()).prop_map(|(k, v)| cmd(&["LPUSH"], vec![k, v])),
+        3 => (key(), value(), value()).prop_map(|(k, a, b)| cmd(&["RPUSH"], vec![k, a, b])),
+        1 => key().prop_map(|k| cmd(&["LPOP"], vec![k])),
+        1 => (key(), 0u8..4).prop_map(|(k, n)| cmd(&["RPOP"], vec![k, n.to_string()])),
+        2 => (key(), index(), index()).prop_map(|(k, s, e)| cmd(&["LRANGE"], vec![k, s, e])),
+        1 => key().prop_map(|k| cmd(&["LLEN"], vec![k])),
+        1 => (key(), index()).prop_map(|(k, i)| cmd(&["LINDEX"], vec![k, i])),
+        1 => (key(), index(), index()).prop_map(|(k, s, e)| cmd(&["LTRIM"], vec![k, s, e])),
+        1 => (key(), index(), value()).prop_map(|(k, n, v)| cmd(&["LREM"], vec![k, n, v])),
+    ]
+    .boxed()
+}
+
+pub fn hash_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        3 => (key(), pick(FIELDS), value()).prop_map(|(k, f, v)| cmd(&["HSET"], vec![k, f, v])),
+        2 => (key(), pick(FIELDS)).prop_map(|(k, f)| cmd(&["HGET"], vec![k, f])),
+        1 => (key(), pick(FIELDS)).prop_map(|(k, f)| cmd(&["HDEL"], vec![k, f])),
+        1 => key().prop_map(|k| cmd(&["HLEN"], vec![k])),
+        2 => key().prop_map(|k| cmd(&["HGETALL"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["HKEYS"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["HVALS"], vec![k])),
+        1 => (key(), pick(FIELDS), index())
+            .prop_map(|(k, f, n)| cmd(&["HINCRBY"], vec![k, f, n])),
+        1 => (key(), 0u8..4).prop_map(|(k, n)| cmd(&["HRANDFIELD"], vec![k, n.to_string()])),
+    ]
+    .boxed()
+}
+
+pub fn set_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        3 => (key(), pick(MEMBERS), pick(MEMBERS))
+            .prop_map(|(k, a, b)| cmd(&["SADD"], vec![k, a, b])),
+        1 => (key(), pick(MEMBERS)).prop_map(|(k, m)| cmd(&["SREM"], vec![k, m])),
+        2 => key().prop_map(|k| cmd(&["SMEMBERS"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["SCARD"], vec![k])),
+        1 => (key(), pick(MEMBERS)).prop_map(|(k, m)| cmd(&["SISMEMBER"], vec![k, m])),
+        1 => (key(), key()).prop_map(|(a, b)| cmd(&["SINTER"], vec![a, b])),
+        1 => (key(), key()).prop_map(|(a, b)| cmd(&["SUNION"], vec![a, b])),
+        1 => (key(), key()).prop_map(|(a, b)| cmd(&["SDIFF"], vec![a, b])),
+        1 => (key(), 0u8..4).prop_map(|(k, n)| cmd(&["SRANDMEMBER"], vec![k, n.to_string()])),
+        // Only a count covering the whole member pool: a partial SPOP removes
+        // a random subset and the two keyspaces would drift apart.
+        1 => key().prop_map(|k| cmd(&["SPOP"], vec![k, MEMBERS.len().to_string()])),
+    ]
+    .boxed()
+}
+
+pub fn zset_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        3 => (key(), index(), pick(MEMBERS))
+            .prop_map(|(k, s, m)| cmd(&["ZADD"], vec![k, s, m])),
+        1 => (key(), pick(MEMBERS)).prop_map(|(k, m)| cmd(&["ZREM"], vec![k, m])),
+        2 => (key(), index(), index())
+            .prop_map(|(k, s, e)| cmd(&["ZRANGE"], vec![k, s, e, "WITHSCORES".to_string()])),
+        1 => (key(), pick(MEMBERS)).prop_map(|(k, m)| cmd(&["ZSCORE"], vec![k, m])),
+        1 => (key(), index(), pick(MEMBERS))
+            .prop_map(|(k, n, m)| cmd(&["ZINCRBY"], vec![k, n, m])),
+        1 => (key(), pick(MEMBERS)).prop_map(|(k, m)| cmd(&["ZRANK"], vec![k, m])),
+        1 => key().prop_map(|k| cmd(&["ZCARD"], vec![k])),
+        1 => (key(), 0u8..4).prop_map(|(k, n)| cmd(&["ZRANDMEMBER"], vec![k, n.to_string()])),
+    ]
+    .boxed()
+}
+
+pub fn keyspace_command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        2 => key().prop_map(|k| cmd(&["DEL"], vec![k])),
+        1 => (key(), key()).prop_map(|(a, b)| cmd(&["EXISTS"], vec![a, b])),
+        2 => key().prop_map(|k| cmd(&["TYPE"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["EXPIRE"], vec![k, "1000".to_string()])),
+        1 => key().prop_map(|k| cmd(&["TTL"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["PERSIST"], vec![k])),
+        1 => (key(), key()).prop_map(|(a, b)| cmd(&["RENAME"], vec![a, b])),
+        1 => Just(cmd(&["DBSIZE"], Vec::new())),
+        1 => Just(cmd(&["KEYS", "*"], Vec::new())),
+        1 => Just(cmd(&["RANDOMKEY"], Vec::new())),
+        1 => Just(cmd(&["TIME"], Vec::new())),
+        1 => (key(), value()).prop_map(|(k, v)| cmd(&["XADD"], vec![k, "*".to_string(), "f".to_string(), v])),
+        1 => key().prop_map(|k| cmd(&["XLEN"], vec![k])),
+        1 => key().prop_map(|k| cmd(&["XRANGE"], vec![k, "-".to_string(), "+".to_string()])),
+    ]
+    .boxed()
+}
+
+pub fn command() -> BoxedStrategy<Vec<String>> {
+    prop_oneof![
+        4 => string_command(),
+        3 => list_command(),
+        3 => hash_command(),
+        3 => set_command(),
+        3 => zset_command(),
+        3 => keyspace_command(),
+    ]
+    .boxed()
+}
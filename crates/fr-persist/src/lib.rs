@@ -4,7 +4,7 @@ use std::cell::RefCell;
 use std::collections::BTreeMap;
 #[cfg(feature = "upstream-stream-rdb")]
 use std::collections::BTreeSet;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::Path;
 
 use fr_protocol::{RespFrame, RespParseError};
@@ -758,6 +758,7 @@ impl AofRecord {
             match item {
                 RespFrame::BulkString(Some(bytes)) => argv.push(bytes.clone()),
                 RespFrame::SimpleString(text) => argv.push(text.as_bytes().to_vec()),
+                RespFrame::StaticSimple(text) => argv.push(text.as_bytes().to_vec()),
                 // `into_bytes()` reuses the `String`'s buffer (one alloc), matching
                 // the owned twin's Integer arm; `as_bytes().to_vec()` allocated twice.
                 RespFrame::Integer(n) => argv.push(n.to_string().into_bytes()),
@@ -786,6 +787,7 @@ impl AofRecord {
             match item {
                 RespFrame::BulkString(Some(bytes)) => argv.push(bytes),
                 RespFrame::SimpleString(text) => argv.push(text.into_bytes()),
+                RespFrame::StaticSimple(text) => argv.push(text.as_bytes().to_vec()),
                 RespFrame::Integer(n) => argv.push(n.to_string().into_bytes()),
                 _ => return Err(PersistError::InvalidFrame),
             }
@@ -1024,6 +1026,116 @@ fn classify_aof_tail_failure(
     }
 }
 
+/// Why [`AofStreamReader::next_record`] stopped early.
+#[derive(Debug)]
+pub enum AofStreamError {
+    Io(std::io::Error),
+    /// The record at `offset` does not parse — cut short by the end of the
+    /// input, or malformed. Nothing past it is read.
+    Unreadable {
+        offset: usize,
+        failure: AofReplayTailFailure,
+    },
+}
+
+impl From<std::io::Error> for AofStreamError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Pulls AOF records off a reader one at a time, holding only the bytes of
+/// the record being parsed. Offsets count from the reader's first byte.
+pub struct AofStreamReader<R> {
+    reader: R,
+    window: Vec<u8>,
+    cursor: usize,
+    /// Stream offset of `window[0]`.
+    base: usize,
+    eof: bool,
+    config: fr_protocol::ParserConfig,
+}
+
+impl<R: Read> AofStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            window: Vec::new(),
+            cursor: 0,
+            base: 0,
+            eof: false,
+            config: aof_parser_config(),
+        }
+    }
+
+    /// The next record, or `None` once the input ends cleanly.
+    pub fn next_record(&mut self) -> Result<Option<AofReplayRecord>, AofStreamError> {
+        let mut want = STREAM_READ_CHUNK;
+        loop {
+            if self.cursor == self.window.len() {
+                self.compact();
+                if self.eof {
+                    return Ok(None);
+                }
+            }
+            let parsed =
+                fr_protocol::parse_frame_with_config(&self.window[self.cursor..], &self.config);
+            let failure = match parsed {
+                Ok(parsed) => {
+                    let start_offset = self.base + self.cursor;
+                    let record = AofRecord::from_resp_frame_owned(parsed.frame).map_err(|error| {
+                        AofStreamError::Unreadable {
+                            offset: start_offset,
+                            failure: aof_tail_failure_from_persist_error(error),
+                        }
+                    })?;
+                    self.cursor += parsed.consumed;
+                    return Ok(Some(AofReplayRecord {
+                        record,
+                        start_offset,
+                        end_offset: self.base + self.cursor,
+                    }));
+                }
+                Err(RespParseError::Incomplete) if !self.eof => None,
+                Err(error) => Some(AofReplayTailFailure::Parse(error)),
+            };
+            if let Some(failure) = failure {
+                return Err(AofStreamError::Unreadable {
+                    offset: self.base + self.cursor,
+                    failure,
+                });
+            }
+            // Incomplete: read more, doubling so a huge record parses in
+            // a bounded number of passes.
+            self.compact();
+            let read = self
+                .reader
+                .by_ref()
+                .take(want as u64)
+                .read_to_end(&mut self.window)?;
+            self.eof = read < want;
+            want = want.saturating_mul(2);
+        }
+    }
+
+    /// Drop the unread input and report how many bytes it held, counting
+    /// from the record the last error pointed at.
+    pub fn discard_rest(&mut self) -> std::io::Result<usize> {
+        let buffered = self.window.len() - self.cursor;
+        let unread = std::io::copy(&mut self.reader, &mut std::io::sink())?;
+        self.window.clear();
+        self.cursor = 0;
+        self.eof = true;
+        Ok(buffered.saturating_add(usize::try_from(unread).unwrap_or(usize::MAX)))
+    }
+
+    fn compact(&mut self) {
+        self.base += self.cursor;
+        self.window.drain(..self.cursor);
+        self.cursor = 0;
+    }
+}
+
 /// Decode a Redis replay stream that is either RESP-only or RDB preamble + RESP tail.
 pub fn decode_aof_replay_stream(input: &[u8]) -> Result<AofReplayStream, PersistError> {
     if input.starts_with(b"REDIS") {
@@ -1140,6 +1252,11 @@ const RDB_OPCODE_EOF: u8 = 0xFF;
 /// functions was lost) whenever `FUNCTION LOAD` had been used.
 /// (br-frankenredis-rdb-function2)
 const RDB_OPCODE_FUNCTION2: u8 = 0xF5;
+/// RDB_OPCODE_MODULE_AUX (247) and RDB_TYPE_MODULE_2 (7): data owned by a
+/// module the writing server had loaded. We have no module API, so only
+/// [`decode_rdb_prefix_lenient`] walks past them; startup loads fail closed.
+const RDB_OPCODE_MODULE_AUX: u8 = 0xF7;
+const RDB_TYPE_MODULE_2: u8 = 7;
 
 /// Pre-size cap for collection element vectors during RDB decode. `count` comes
 /// from an untrusted RDB header, so the speculative reservation is bounded to
@@ -1236,6 +1353,23 @@ pub struct RdbDecodeResult {
     pub functions: Vec<Vec<u8>>,
 }
 
+/// Something [`decode_rdb_prefix_lenient`] walked past instead of failing the
+/// whole load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RdbSkipped {
+    /// The header declares an RDB version newer than this build writes; the
+    /// load continues as long as every record is one we know.
+    NewerVersion(u32),
+    /// A module-typed key, dropped. `module` is the 9-character type name.
+    ModuleValue {
+        db: usize,
+        key: Vec<u8>,
+        module: String,
+    },
+    /// Module auxiliary data (`RDB_OPCODE_MODULE_AUX`), dropped.
+    ModuleAux { module: String },
+}
+
 /// Stream entry: (ms, seq, fields).
 pub type StreamEntry = EncodableStreamEntry<Vec<u8>, Vec<u8>>;
 pub type EncodableStreamEntry<F, V> = (u64, u64, Vec<(F, V)>);
@@ -1555,7 +1689,21 @@ pub fn crc64_redis(data: &[u8]) -> u64 {
 #[doc(hidden)]
 #[must_use]
 pub fn crc64_redis_slice_table(data: &[u8]) -> u64 {
-    let mut crc = 0_u64;
+    crc64_redis_slice_table_from(0, data)
+}
+
+/// Extend a [`crc64_redis`] of earlier bytes over `data`: the CRC has no
+/// initial or final XOR, so `crc64_redis_continue(crc64_redis(a), b)` equals
+/// `crc64_redis` of `a` followed by `b`.
+fn crc64_redis_continue(crc: u64, data: &[u8]) -> u64 {
+    if crc == 0 {
+        crc64_redis(data)
+    } else {
+        crc64_redis_slice_table_from(crc, data)
+    }
+}
+
+fn crc64_redis_slice_table_from(mut crc: u64, data: &[u8]) -> u64 {
     let (chunks, remainder) = data.as_chunks::<16>();
     for chunk in chunks {
         let one = u64::from_le_bytes(chunk[0..8].try_into().unwrap()) ^ crc;
@@ -3528,18 +3676,152 @@ fn rdb_decode_string(data: &[u8]) -> Option<(Vec<u8>, usize)> {
 /// Decode redis's legacy ASCII double encoding (`rdbLoadDoubleValue`): a length
 /// byte where 253/254/255 mean NaN/+Inf/-Inf, otherwise that many ASCII bytes
 /// holding the textual score (e.g. "3.14"). Returns the value and bytes consumed.
-fn rdb_load_legacy_double(data: &[u8]) -> Option<(f64, usize)> {
-    let len = *data.first()?;
+fn rdb_load_legacy_double(data: &[u8]) -> Result<(f64, usize), PersistError> {
+    let len = *data.first().ok_or(RDB_TRUNCATED)?;
     match len {
-        255 => Some((f64::NEG_INFINITY, 1)),
-        254 => Some((f64::INFINITY, 1)),
-        253 => Some((f64::NAN, 1)),
+        255 => Ok((f64::NEG_INFINITY, 1)),
+        254 => Ok((f64::INFINITY, 1)),
+        253 => Ok((f64::NAN, 1)),
         n => {
-            let end = 1usize.checked_add(n as usize)?;
-            let s = std::str::from_utf8(data.get(1..end)?).ok()?;
-            let v: f64 = s.trim().parse().ok()?;
-            Some((v, end))
+            let end = 1 + usize::from(n);
+            rdb_need(data, end)?;
+            std::str::from_utf8(&data[1..end])
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .map(|v| (v, end))
+                .ok_or(PersistError::InvalidFrame)
+        }
+    }
+}
+
+/// The error a record decode reports when `data` ends before the record
+/// does. It is distinct from `InvalidFrame` so the streaming reader refills
+/// its window only for input that is merely short, never for a corrupt
+/// record; the slice decoders, which hold the whole file, turn it back into
+/// `InvalidFrame`.
+const RDB_TRUNCATED: PersistError = PersistError::Parse(RespParseError::Incomplete);
+
+fn rdb_is_truncated(err: &PersistError) -> bool {
+    matches!(err, PersistError::Parse(RespParseError::Incomplete))
+}
+
+/// Fail with [`RDB_TRUNCATED`] unless `data` holds at least `end` bytes.
+fn rdb_need(data: &[u8], end: usize) -> Result<(), PersistError> {
+    if end > data.len() {
+        return Err(RDB_TRUNCATED);
+    }
+    Ok(())
+}
+
+/// Bytes a length header at the start of `data` spans, or `None` for an
+/// encoding `rdb_decode_length` rejects whatever follows.
+fn rdb_length_width(data: &[u8]) -> Option<usize> {
+    let Some(&first) = data.first() else {
+        return Some(1);
+    };
+    match first >> 6 {
+        0 => Some(1),
+        1 => Some(2),
+        2 if first == 0x80 => Some(5),
+        2 if first == 0x81 => Some(9),
+        _ => None,
+    }
+}
+
+/// Whether `rdb_decode_length` failed on `data` only because it is short.
+pub(crate) fn rdb_length_truncated(data: &[u8]) -> bool {
+    rdb_length_width(data).is_some_and(|width| width > data.len())
+}
+
+/// Whether `rdb_decode_string` failed on `data` only because it is short:
+/// its header is cut off, or it declares more payload than `data` holds.
+pub(crate) fn rdb_string_truncated(data: &[u8]) -> bool {
+    let Some(&first) = data.first() else {
+        return true;
+    };
+    if first >> 6 != 3 {
+        return match rdb_decode_length(data) {
+            Some((len, width)) => width.checked_add(len).is_some_and(|end| end > data.len()),
+            None => rdb_length_truncated(data),
+        };
+    }
+    match first & 0x3F {
+        0 => data.len() < 2,
+        1 => data.len() < 3,
+        2 => data.len() < 5,
+        3 => {
+            let Some((compressed_len, compressed_hdr)) = rdb_decode_length(&data[1..]) else {
+                return rdb_length_truncated(&data[1..]);
+            };
+            let rest = &data[1 + compressed_hdr..];
+            let Some((_, uncompressed_hdr)) = rdb_decode_length(rest) else {
+                return rdb_length_truncated(rest);
+            };
+            (1 + compressed_hdr + uncompressed_hdr)
+                .checked_add(compressed_len)
+                .is_some_and(|end| end > data.len())
+        }
+        _ => false,
+    }
+}
+
+/// `rdb_decode_length` for a record field.
+fn rdb_read_length(data: &[u8]) -> Result<(usize, usize), PersistError> {
+    rdb_decode_length(data).ok_or_else(|| {
+        if rdb_length_truncated(data) {
+            RDB_TRUNCATED
+        } else {
+            PersistError::InvalidFrame
         }
+    })
+}
+
+/// `rdb_decode_string` for a record field.
+fn rdb_read_string(data: &[u8]) -> Result<(Vec<u8>, usize), PersistError> {
+    rdb_decode_string(data).ok_or_else(|| {
+        if rdb_string_truncated(data) {
+            RDB_TRUNCATED
+        } else {
+            PersistError::InvalidFrame
+        }
+    })
+}
+
+/// Module type name for a 64-bit module type id (module.c
+/// `moduleTypeNameByID`): nine 6-bit characters above a 10-bit encoding
+/// version.
+fn rdb_module_type_name(module_id: u64) -> String {
+    const CHARSET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut id = module_id >> 10;
+    let mut name = [0u8; 9];
+    for slot in name.iter_mut().rev() {
+        *slot = CHARSET[(id & 63) as usize];
+        id >>= 6;
+    }
+    String::from_utf8_lossy(&name).into_owned()
+}
+
+/// Walk the self-describing opcode stream a module saves its values with
+/// (rdb.c `rdbLoadCheckModuleValue`) and return how many bytes it spans.
+fn rdb_skip_module_value(data: &[u8]) -> Result<usize, PersistError> {
+    let mut cursor = 0;
+    loop {
+        let (opcode, consumed) = rdb_read_length(&data[cursor..])?;
+        cursor += consumed;
+        let width = match opcode {
+            // RDB_MODULE_OPCODE_EOF
+            0 => return Ok(cursor),
+            // SINT / UINT
+            1 | 2 => rdb_read_length(&data[cursor..])?.1,
+            // FLOAT / DOUBLE, stored as raw little-endian IEEE values
+            3 => 4,
+            4 => 8,
+            // STRING
+            5 => rdb_read_string(&data[cursor..])?.1,
+            _ => return Err(PersistError::InvalidFrame),
+        };
+        rdb_need(data, cursor + width)?;
+        cursor += width;
     }
 }
 
@@ -3549,7 +3831,114 @@ fn rdb_load_legacy_double(data: &[u8]) -> Option<(f64, usize)> {
 /// This API decodes only the RDB prefix and leaves any tail bytes to the caller.
 #[cfg_attr(feature = "bench-reference", inline(never))]
 pub fn decode_rdb_prefix(data: &[u8]) -> Result<RdbDecodeResult, PersistError> {
-    decode_rdb_prefix_impl::<true>(data)
+    decode_rdb_prefix_impl::<true>(data, None)
+}
+
+/// Like [`decode_rdb_prefix`], but skips what this build cannot represent —
+/// module values, module aux data, a newer header version — and reports each
+/// skip instead of failing. Offline import tooling uses this; the server's own
+/// startup load stays strict.
+pub fn decode_rdb_prefix_lenient(
+    data: &[u8],
+) -> Result<(RdbDecodeResult, Vec<RdbSkipped>), PersistError> {
+    let mut skipped = Vec::new();
+    let decoded = decode_rdb_prefix_impl::<true>(data, Some(&mut skipped))?;
+    Ok((decoded, skipped))
+}
+
+/// How much [`decode_rdb_reader_lenient`] and [`AofStreamReader`] read at a
+/// time; a record longer than this grows the window by doubling.
+const STREAM_READ_CHUNK: usize = 64 * 1024;
+
+/// What [`decode_rdb_reader_lenient`] found besides the keys it handed out.
+#[derive(Debug, Default)]
+pub struct RdbStreamSummary {
+    pub aux: BTreeMap<String, String>,
+    /// Function-library source payloads, in file order.
+    pub functions: Vec<Vec<u8>>,
+    pub skipped: Vec<RdbSkipped>,
+    /// Bytes the RDB spanned, checksum included.
+    pub consumed: u64,
+    /// Bytes already read past the checksum; the reader resumes after them.
+    pub tail: Vec<u8>,
+}
+
+/// [`decode_rdb_prefix_lenient`] over a reader: each key goes to `on_entry`
+/// as soon as its record decodes, and only a window around the current
+/// record is held in memory, so the raw dump never has to fit in RAM. What
+/// `on_entry` keeps is up to the caller. The first error `on_entry` returns
+/// stops the decode.
+pub fn decode_rdb_reader_lenient<R: Read>(
+    reader: &mut R,
+    mut on_entry: impl FnMut(RdbEntry) -> Result<(), PersistError>,
+) -> Result<RdbStreamSummary, PersistError> {
+    let mut window = Vec::new();
+    let mut eof = read_window(reader, &mut window, STREAM_READ_CHUNK)?;
+    let mut skipped = Vec::new();
+    let version = decode_rdb_header(&window, Some(&mut skipped))?;
+    let mut state = RdbRecordState::new(version);
+    let mut cursor = 9;
+    // CRC-64 and length of the bytes already dropped from the window.
+    let mut crc_seed = 0;
+    let mut compacted = 0u64;
+    let mut want = STREAM_READ_CHUNK;
+
+    loop {
+        if !eof && window.len() - cursor < STREAM_READ_CHUNK / 2 {
+            eof = read_window(reader, &mut window, STREAM_READ_CHUNK)?;
+        }
+        if cursor >= window.len() {
+            // Ran out of input before the EOF opcode.
+            return Err(PersistError::InvalidFrame);
+        }
+        let step =
+            decode_rdb_record::<true>(&window, cursor, crc_seed, &mut state, Some(&mut skipped));
+        let (record, next) = match step {
+            Ok(step) => step,
+            // The record runs past the window: read more and decode it again.
+            // A failed step leaves `state` untouched. Any other failure is a
+            // corrupt record, which no amount of extra input would fix.
+            Err(err) if rdb_is_truncated(&err) && !eof => {
+                eof = read_window(reader, &mut window, want)?;
+                want = want.saturating_mul(2);
+                continue;
+            }
+            Err(err) if rdb_is_truncated(&err) => return Err(PersistError::InvalidFrame),
+            Err(err) => return Err(err),
+        };
+        cursor = next;
+        want = STREAM_READ_CHUNK;
+        match record {
+            RdbRecord::Entry(entry) => on_entry(entry)?,
+            RdbRecord::ResizeHint(_) | RdbRecord::Other => {}
+            RdbRecord::Eof => break,
+        }
+        if cursor >= STREAM_READ_CHUNK && cursor > window.len() / 2 {
+            crc_seed = crc64_redis_continue(crc_seed, &window[..cursor]);
+            compacted += cursor as u64;
+            window.drain(..cursor);
+            cursor = 0;
+        }
+    }
+
+    Ok(RdbStreamSummary {
+        aux: state.aux,
+        functions: state.functions,
+        skipped,
+        consumed: compacted + cursor as u64,
+        tail: window.split_off(cursor),
+    })
+}
+
+/// Append up to `want` bytes from `reader` to `window`; true once the reader
+/// is exhausted.
+fn read_window<R: Read>(
+    reader: &mut R,
+    window: &mut Vec<u8>,
+    want: usize,
+) -> Result<bool, PersistError> {
+    let read = reader.by_ref().take(want as u64).read_to_end(window)?;
+    Ok(read < want)
 }
 
 /// Frozen pre-optimization decoder for same-binary performance proof.
@@ -3557,13 +3946,69 @@ pub fn decode_rdb_prefix(data: &[u8]) -> Result<RdbDecodeResult, PersistError> {
 #[doc(hidden)]
 #[inline(never)]
 pub fn bench_decode_rdb_prefix_reference(data: &[u8]) -> Result<RdbDecodeResult, PersistError> {
-    decode_rdb_prefix_impl::<false>(data)
+    decode_rdb_prefix_impl::<false>(data, None)
 }
 
 #[cfg_attr(feature = "bench-reference", inline(never))]
 fn decode_rdb_prefix_impl<const MOVE_LEGACY_HASH_ZIPLIST_FIELDS: bool>(
     data: &[u8],
+    mut skipped: Option<&mut Vec<RdbSkipped>>,
 ) -> Result<RdbDecodeResult, PersistError> {
+    let version = decode_rdb_header(data, skipped.as_deref_mut())?;
+    let mut cursor = 9; // Skip "REDIS" + 4-digit version
+    let mut state = RdbRecordState::new(version);
+    let mut entries = Vec::new();
+    let mut saw_eof = false;
+
+    while cursor < data.len() {
+        let (record, next) = decode_rdb_record::<MOVE_LEGACY_HASH_ZIPLIST_FIELDS>(
+            data,
+            cursor,
+            0,
+            &mut state,
+            skipped.as_deref_mut(),
+        )
+        // `data` is the whole dump, so a record cut short means a truncated file.
+        .map_err(|err| {
+            if rdb_is_truncated(&err) {
+                PersistError::InvalidFrame
+            } else {
+                err
+            }
+        })?;
+        cursor = next;
+        match record {
+            RdbRecord::Entry(entry) => entries.push(entry),
+            // The counts are only a hint from the file: every entry takes at
+            // least a type byte plus two length bytes, so never reserve more
+            // entries than the remaining bytes could hold. Loaders presize
+            // the store from the decoded entries, whose count is exact.
+            RdbRecord::ResizeHint(keys) => entries.reserve(keys.min((data.len() - cursor) / 3)),
+            RdbRecord::Other => {}
+            RdbRecord::Eof => {
+                saw_eof = true;
+                break;
+            }
+        }
+    }
+
+    if !saw_eof {
+        return Err(PersistError::InvalidFrame);
+    }
+
+    Ok(RdbDecodeResult {
+        entries,
+        aux: state.aux,
+        consumed: cursor,
+        functions: state.functions,
+    })
+}
+
+/// Check the `REDISnnnn` header and return its version.
+fn decode_rdb_header(
+    data: &[u8],
+    skipped: Option<&mut Vec<RdbSkipped>>,
+) -> Result<u32, PersistError> {
     if data.len() < 9 + RDB_CHECKSUM_LEN || &data[..5] != b"REDIS" {
         return Err(PersistError::InvalidFrame);
     }
@@ -3572,166 +4017,79 @@ fn decode_rdb_prefix_impl<const MOVE_LEGACY_HASH_ZIPLIST_FIELDS: bool>(
     // rdbLoadRioWithLoadingCtx rejects only `rdbver < 1 || rdbver > RDB_VERSION`).
     // Match that range so dumps written by older redis releases (6.x = v9,
     // 7.0/7.1 = v10) still load — RDB type tags are version-stable, and any
-    // encoding we don't recognise still fails closed at the per-type arm below.
+    // encoding we don't recognise still fails closed at the per-type arm of
+    // `decode_rdb_record`.
     // A newer version (> RDB_VERSION) is rejected: its format is unknown to us.
+    // The lenient decoder notes it and carries on record by record instead.
     let version_str = std::str::from_utf8(&data[5..9]).map_err(|_| PersistError::InvalidFrame)?;
     let version: u32 = version_str
         .parse()
         .map_err(|_| PersistError::InvalidFrame)?;
-    if !(1..=RDB_VERSION).contains(&version) {
+    if version < 1 {
         return Err(PersistError::InvalidFrame);
     }
-    let mut cursor = 9; // Skip "REDIS" + 4-digit version
-    let mut entries = Vec::new();
-    let mut aux = BTreeMap::new();
-    let mut functions: Vec<Vec<u8>> = Vec::new();
-    let mut pending_expire_ms: Option<u64> = None;
-    let mut current_db = 0usize;
-    let mut saw_eof = false;
-
-    while cursor < data.len() {
-        let opcode = data[cursor];
-        cursor += 1;
+    if version > RDB_VERSION {
+        match skipped {
+            Some(skipped) => skipped.push(RdbSkipped::NewerVersion(version)),
+            None => return Err(PersistError::InvalidFrame),
+        }
+    }
+    Ok(version)
+}
 
-        // Reset expiry if this opcode is not a type byte and not a known expiry opcode.
-        // This prevents 'leaking' an expiry to the next key if something unexpected happens.
-        let is_type_byte = matches!(
-            opcode,
-            RDB_TYPE_STRING
-                | RDB_TYPE_LIST
-                | RDB_TYPE_SET
-                | RDB_TYPE_HASH
-                | RDB_TYPE_HASH_WITH_TTLS
-                | RDB_TYPE_ZSET
-                | RDB_TYPE_ZSET_2
-                | RDB_TYPE_STREAM
-                | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_2
-                | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3
-                | RDB_TYPE_SET_INTSET
-                | RDB_TYPE_HASH_ZIPMAP
-                | RDB_TYPE_LIST_ZIPLIST
-                | RDB_TYPE_ZSET_ZIPLIST
-                | RDB_TYPE_HASH_ZIPLIST
-                | RDB_TYPE_LIST_QUICKLIST
-                | RDB_TYPE_HASH_LISTPACK
-                | RDB_TYPE_ZSET_LISTPACK
-                | RDB_TYPE_LIST_QUICKLIST_2
-                | RDB_TYPE_SET_LISTPACK
-        );
-        let is_expiry_opcode = matches!(opcode, RDB_OPCODE_EXPIRETIME_MS | 0xFD);
-        let is_eviction_opcode = matches!(opcode, 0xF8 | 0xF9);
+/// Decoder state one RDB record hands to the next.
+struct RdbRecordState {
+    version: u32,
+    pending_expire_ms: Option<u64>,
+    current_db: usize,
+    aux: BTreeMap<String, String>,
+    functions: Vec<Vec<u8>>,
+}
 
-        if !is_type_byte && !is_expiry_opcode && !is_eviction_opcode && pending_expire_ms.is_some()
-        {
-            // In a well-formed RDB, expiry/eviction data must be followed by a type byte.
-            // If we see SELECTDB or something else here, the file is malformed.
-            return Err(PersistError::InvalidFrame);
+impl RdbRecordState {
+    fn new(version: u32) -> Self {
+        Self {
+            version,
+            pending_expire_ms: None,
+            current_db: 0,
+            aux: BTreeMap::new(),
+            functions: Vec::new(),
         }
+    }
+}
 
-        match opcode {
-            RDB_OPCODE_EOF => {
-                if pending_expire_ms.is_some() {
-                    return Err(PersistError::InvalidFrame);
-                }
-                // RDB versions < 5 predate the CRC64 trailer entirely — EOF is
-                // the final byte. Versions >= 5 always append 8 bytes, but a
-                // stored checksum of 0 means "checksum disabled" and redis skips
-                // verification (rdb.c: `if (server.rdb_checksum && cksum) ...`).
-                if version >= 5 {
-                    if cursor + RDB_CHECKSUM_LEN > data.len() {
-                        return Err(PersistError::InvalidFrame);
-                    }
-                    let expected_checksum = u64::from_le_bytes(
-                        data[cursor..cursor + RDB_CHECKSUM_LEN]
-                            .try_into()
-                            .map_err(|_| PersistError::InvalidFrame)?,
-                    );
-                    let actual_checksum = crc64_redis(&data[..cursor]);
-                    if expected_checksum != 0 && expected_checksum != actual_checksum {
-                        return Err(PersistError::InvalidFrame);
-                    }
-                    cursor += RDB_CHECKSUM_LEN;
-                }
-                saw_eof = true;
-                break;
-            }
-            RDB_OPCODE_AUX => {
-                let (key, consumed) =
-                    rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-                let (value, consumed) =
-                    rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-                // Use lossy conversion to preserve AUX metadata even with
-                // non-UTF8 bytes rather than silently discarding fields.
-                let k = String::from_utf8_lossy(&key).into_owned();
-                let v = String::from_utf8_lossy(&value).into_owned();
-                aux.insert(k, v);
-            }
-            RDB_OPCODE_SELECTDB => {
-                let (db, consumed) =
-                    rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-                current_db = db;
-            }
-            RDB_OPCODE_RESIZEDB => {
-                let (_, consumed) =
-                    rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-                let (_, consumed2) =
-                    rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed2;
-            }
-            RDB_OPCODE_EXPIRETIME_MS => {
-                if cursor + 8 > data.len() {
-                    return Err(PersistError::InvalidFrame);
-                }
-                let ms = u64::from_le_bytes(
-                    data[cursor..cursor + 8]
-                        .try_into()
-                        .map_err(|_| PersistError::InvalidFrame)?,
-                );
-                cursor += 8;
-                pending_expire_ms = Some(ms);
-            }
-            0xFD => {
-                // EXPIRETIME (seconds) — skip 4 bytes, convert to ms
-                if cursor + 4 > data.len() {
-                    return Err(PersistError::InvalidFrame);
-                }
-                let secs = u32::from_le_bytes(
-                    data[cursor..cursor + 4]
-                        .try_into()
-                        .map_err(|_| PersistError::InvalidFrame)?,
-                );
-                cursor += 4;
-                pending_expire_ms = Some(u64::from(secs) * 1000);
-            }
-            0xF8 => {
-                // RDB_OPCODE_IDLE
-                let (_, consumed) =
-                    rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-            }
-            0xF9 => {
-                // RDB_OPCODE_FREQ
-                if cursor >= data.len() {
-                    return Err(PersistError::InvalidFrame);
-                }
-                cursor += 1;
-            }
-            RDB_OPCODE_FUNCTION2 => {
-                // Function-library payload: a single raw string (the library
-                // source). Capture it and continue so the keyspace that
-                // follows still loads. Re-registering the library into the
-                // function engine is the runtime's responsibility; dropping
-                // the whole RDB here was a data-loss bug.
-                let (code, consumed) =
-                    rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
-                functions.push(code);
-            }
-            type_byte @ (RDB_TYPE_STRING
+/// What one step of [`decode_rdb_record`] produced.
+enum RdbRecord {
+    Entry(RdbEntry),
+    /// A RESIZEDB key count, straight from the file.
+    ResizeHint(usize),
+    /// Metadata folded into [`RdbRecordState`], or a skipped record.
+    Other,
+    /// The EOF opcode and its checksum; nothing after it belongs to the RDB.
+    Eof,
+}
+
+/// Decode the record whose opcode sits at `data[cursor]` and return it with
+/// the offset just past it. `crc_seed` is the CRC-64 of whatever preceded
+/// `data` in the file, so the EOF checksum still covers the whole dump when
+/// the caller holds only a window of it. A record cut short by the end of
+/// `data` fails without touching `state`, so a reader can refill and retry.
+#[inline]
+fn decode_rdb_record<const MOVE_LEGACY_HASH_ZIPLIST_FIELDS: bool>(
+    data: &[u8],
+    mut cursor: usize,
+    crc_seed: u64,
+    state: &mut RdbRecordState,
+    skipped: Option<&mut Vec<RdbSkipped>>,
+) -> Result<(RdbRecord, usize), PersistError> {
+    let opcode = data[cursor];
+    cursor += 1;
+
+    // Reset expiry if this opcode is not a type byte and not a known expiry opcode.
+    // This prevents 'leaking' an expiry to the next key if something unexpected happens.
+    let is_type_byte = matches!(
+        opcode,
+        RDB_TYPE_STRING
             | RDB_TYPE_LIST
             | RDB_TYPE_SET
             | RDB_TYPE_HASH
@@ -3750,580 +4108,667 @@ fn decode_rdb_prefix_impl<const MOVE_LEGACY_HASH_ZIPLIST_FIELDS: bool>(
             | RDB_TYPE_HASH_LISTPACK
             | RDB_TYPE_ZSET_LISTPACK
             | RDB_TYPE_LIST_QUICKLIST_2
-            | RDB_TYPE_SET_LISTPACK) => {
-                let (key, consumed) =
-                    rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                cursor += consumed;
+            | RDB_TYPE_SET_LISTPACK
+            | RDB_TYPE_MODULE_2
+    );
+    let is_expiry_opcode = matches!(opcode, RDB_OPCODE_EXPIRETIME_MS | 0xFD);
+    let is_eviction_opcode = matches!(opcode, 0xF8 | 0xF9);
 
-                let value = match type_byte {
-                    RDB_TYPE_STRING => {
-                        let (v, c) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+    if !is_type_byte
+        && !is_expiry_opcode
+        && !is_eviction_opcode
+        && state.pending_expire_ms.is_some()
+    {
+        // In a well-formed RDB, expiry/eviction data must be followed by a type byte.
+        // If we see SELECTDB or something else here, the file is malformed.
+        return Err(PersistError::InvalidFrame);
+    }
+
+    match opcode {
+        RDB_OPCODE_EOF => {
+            if state.pending_expire_ms.is_some() {
+                return Err(PersistError::InvalidFrame);
+            }
+            // RDB versions < 5 predate the CRC64 trailer entirely — EOF is
+            // the final byte. Versions >= 5 always append 8 bytes, but a
+            // stored checksum of 0 means "checksum disabled" and redis skips
+            // verification (rdb.c: `if (server.rdb_checksum && cksum) ...`).
+            if state.version >= 5 {
+                rdb_need(data, cursor + RDB_CHECKSUM_LEN)?;
+                let expected_checksum = u64::from_le_bytes(
+                    data[cursor..cursor + RDB_CHECKSUM_LEN]
+                        .try_into()
+                        .map_err(|_| PersistError::InvalidFrame)?,
+                );
+                let actual_checksum = crc64_redis_continue(crc_seed, &data[..cursor]);
+                if expected_checksum != 0 && expected_checksum != actual_checksum {
+                    return Err(PersistError::InvalidFrame);
+                }
+                cursor += RDB_CHECKSUM_LEN;
+            }
+            return Ok((RdbRecord::Eof, cursor));
+        }
+        RDB_OPCODE_AUX => {
+            let (key, consumed) = rdb_read_string(&data[cursor..])?;
+            cursor += consumed;
+            let (value, consumed) = rdb_read_string(&data[cursor..])?;
+            cursor += consumed;
+            // Use lossy conversion to preserve AUX metadata even with
+            // non-UTF8 bytes rather than silently discarding fields.
+            let k = String::from_utf8_lossy(&key).into_owned();
+            let v = String::from_utf8_lossy(&value).into_owned();
+            state.aux.insert(k, v);
+        }
+        RDB_OPCODE_SELECTDB => {
+            let (db, consumed) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed;
+            state.current_db = db;
+        }
+        RDB_OPCODE_RESIZEDB => {
+            let (keys, consumed) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed;
+            let (_, consumed2) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed2;
+            return Ok((RdbRecord::ResizeHint(keys), cursor));
+        }
+        RDB_OPCODE_EXPIRETIME_MS => {
+            rdb_need(data, cursor + 8)?;
+            let ms = u64::from_le_bytes(
+                data[cursor..cursor + 8]
+                    .try_into()
+                    .map_err(|_| PersistError::InvalidFrame)?,
+            );
+            cursor += 8;
+            state.pending_expire_ms = Some(ms);
+        }
+        0xFD => {
+            // EXPIRETIME (seconds) — skip 4 bytes, convert to ms
+            rdb_need(data, cursor + 4)?;
+            let secs = u32::from_le_bytes(
+                data[cursor..cursor + 4]
+                    .try_into()
+                    .map_err(|_| PersistError::InvalidFrame)?,
+            );
+            cursor += 4;
+            state.pending_expire_ms = Some(u64::from(secs) * 1000);
+        }
+        0xF8 => {
+            // RDB_OPCODE_IDLE
+            let (_, consumed) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed;
+        }
+        0xF9 => {
+            // RDB_OPCODE_FREQ
+            rdb_need(data, cursor + 1)?;
+            cursor += 1;
+        }
+        RDB_OPCODE_FUNCTION2 => {
+            // Function-library payload: a single raw string (the library
+            // source). Capture it and continue so the keyspace that
+            // follows still loads. Re-registering the library into the
+            // function engine is the runtime's responsibility; dropping
+            // the whole RDB here was a data-loss bug.
+            let (code, consumed) = rdb_read_string(&data[cursor..])?;
+            cursor += consumed;
+            state.functions.push(code);
+        }
+        RDB_OPCODE_MODULE_AUX if skipped.is_some() => {
+            // Module id, then the `when` opcode and value, then the
+            // module's own opcode stream (rdb.c RDB_OPCODE_MODULE_AUX).
+            let (module_id, consumed) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed;
+            for _ in 0..2 {
+                let (_, consumed) = rdb_read_length(&data[cursor..])?;
+                cursor += consumed;
+            }
+            cursor += rdb_skip_module_value(&data[cursor..])?;
+            if let Some(skipped) = skipped {
+                skipped.push(RdbSkipped::ModuleAux {
+                    module: rdb_module_type_name(module_id as u64),
+                });
+            }
+        }
+        RDB_TYPE_MODULE_2 if skipped.is_some() => {
+            let (key, consumed) = rdb_read_string(&data[cursor..])?;
+            cursor += consumed;
+            let (module_id, consumed) = rdb_read_length(&data[cursor..])?;
+            cursor += consumed;
+            cursor += rdb_skip_module_value(&data[cursor..])?;
+            state.pending_expire_ms = None;
+            if let Some(skipped) = skipped {
+                skipped.push(RdbSkipped::ModuleValue {
+                    db: state.current_db,
+                    key,
+                    module: rdb_module_type_name(module_id as u64),
+                });
+            }
+        }
+        type_byte @ (RDB_TYPE_STRING
+        | RDB_TYPE_LIST
+        | RDB_TYPE_SET
+        | RDB_TYPE_HASH
+        | RDB_TYPE_HASH_WITH_TTLS
+        | RDB_TYPE_ZSET
+        | RDB_TYPE_ZSET_2
+        | RDB_TYPE_STREAM
+        | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_2
+        | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3
+        | RDB_TYPE_SET_INTSET
+        | RDB_TYPE_HASH_ZIPMAP
+        | RDB_TYPE_LIST_ZIPLIST
+        | RDB_TYPE_ZSET_ZIPLIST
+        | RDB_TYPE_HASH_ZIPLIST
+        | RDB_TYPE_LIST_QUICKLIST
+        | RDB_TYPE_HASH_LISTPACK
+        | RDB_TYPE_ZSET_LISTPACK
+        | RDB_TYPE_LIST_QUICKLIST_2
+        | RDB_TYPE_SET_LISTPACK) => {
+            let (key, consumed) = rdb_read_string(&data[cursor..])?;
+            cursor += consumed;
+
+            let value = match type_byte {
+                RDB_TYPE_STRING => {
+                    let (v, c) = rdb_read_string(&data[cursor..])?;
+                    cursor += c;
+                    RdbValue::String(v)
+                }
+                RDB_TYPE_LIST => {
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut items = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (item, c) = rdb_read_string(&data[cursor..])?;
                         cursor += c;
-                        RdbValue::String(v)
+                        items.push(item);
                     }
-                    RDB_TYPE_LIST => {
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::List(items)
+                }
+                RDB_TYPE_SET => {
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (m, c) = rdb_read_string(&data[cursor..])?;
                         cursor += c;
-                        let mut items = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (item, c) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c;
-                            items.push(item);
-                        }
-                        RdbValue::List(items)
+                        members.push(m);
                     }
-                    RDB_TYPE_SET => {
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += c;
-                        let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (m, c) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c;
-                            members.push(m);
-                        }
-                        // (frankenredis-39is8) Plain RDB_TYPE_SET is the hashtable
-                        // encoding; preserve it so the load doesn't re-derive a
-                        // smaller encoding from content. INTSET/LISTPACK arms keep
-                        // the plain `Set` (re-derive intset/listpack).
-                        RdbValue::SetHashtable(members)
+                    // (frankenredis-39is8) Plain RDB_TYPE_SET is the hashtable
+                    // encoding; preserve it so the load doesn't re-derive a
+                    // smaller encoding from content. INTSET/LISTPACK arms keep
+                    // the plain `Set` (re-derive intset/listpack).
+                    RdbValue::SetHashtable(members)
+                }
+                RDB_TYPE_HASH => {
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut fields = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (f, c1) = rdb_read_string(&data[cursor..])?;
+                        cursor += c1;
+                        let (v, c2) = rdb_read_string(&data[cursor..])?;
+                        cursor += c2;
+                        fields.push((f, v));
                     }
-                    RDB_TYPE_HASH => {
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += c;
-                        let mut fields = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (f, c1) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c1;
-                            let (v, c2) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c2;
-                            fields.push((f, v));
-                        }
-                        RdbValue::Hash(fields)
+                    RdbValue::Hash(fields)
+                }
+                RDB_TYPE_HASH_WITH_TTLS => {
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut fields = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (f, c1) = rdb_read_string(&data[cursor..])?;
+                        cursor += c1;
+                        let (v, c2) = rdb_read_string(&data[cursor..])?;
+                        cursor += c2;
+                        rdb_need(data, cursor + 8)?;
+                        let mut deadline_buf = [0u8; 8];
+                        deadline_buf.copy_from_slice(&data[cursor..cursor + 8]);
+                        cursor += 8;
+                        let raw = u64::from_le_bytes(deadline_buf);
+                        let expires = if raw == u64::MAX { None } else { Some(raw) };
+                        fields.push((f, v, expires));
                     }
-                    RDB_TYPE_HASH_WITH_TTLS => {
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::HashWithTtls(fields)
+                }
+                RDB_TYPE_ZSET_2 => {
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (m, c) = rdb_read_string(&data[cursor..])?;
                         cursor += c;
-                        let mut fields = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (f, c1) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c1;
-                            let (v, c2) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c2;
-                            if cursor + 8 > data.len() {
-                                return Err(PersistError::InvalidFrame);
-                            }
-                            let mut deadline_buf = [0u8; 8];
-                            deadline_buf.copy_from_slice(&data[cursor..cursor + 8]);
-                            cursor += 8;
-                            let raw = u64::from_le_bytes(deadline_buf);
-                            let expires = if raw == u64::MAX { None } else { Some(raw) };
-                            fields.push((f, v, expires));
-                        }
-                        RdbValue::HashWithTtls(fields)
+                        rdb_need(data, cursor + 8)?;
+                        let score = f64::from_le_bytes(
+                            data[cursor..cursor + 8]
+                                .try_into()
+                                .map_err(|_| PersistError::InvalidFrame)?,
+                        );
+                        cursor += 8;
+                        members.push((m, score));
                     }
-                    RDB_TYPE_ZSET_2 => {
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::SortedSet(members)
+                }
+                RDB_TYPE_ZSET => {
+                    // Legacy zset (redis ≤ 6.2): count, then (member:string,
+                    // score:legacy-ASCII-double) pairs.
+                    let (count, c) = rdb_read_length(&data[cursor..])?;
+                    cursor += c;
+                    let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        let (m, c) = rdb_read_string(&data[cursor..])?;
                         cursor += c;
-                        let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (m, c) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c;
-                            if cursor + 8 > data.len() {
-                                return Err(PersistError::InvalidFrame);
-                            }
-                            let score = f64::from_le_bytes(
-                                data[cursor..cursor + 8]
-                                    .try_into()
-                                    .map_err(|_| PersistError::InvalidFrame)?,
-                            );
-                            cursor += 8;
-                            members.push((m, score));
-                        }
-                        RdbValue::SortedSet(members)
-                    }
-                    RDB_TYPE_ZSET => {
-                        // Legacy zset (redis ≤ 6.2): count, then (member:string,
-                        // score:legacy-ASCII-double) pairs.
-                        let (count, c) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                        let (score, c) = rdb_load_legacy_double(&data[cursor..])?;
                         cursor += c;
-                        let mut members = Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            let (m, c) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c;
-                            let (score, c) = rdb_load_legacy_double(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += c;
-                            members.push((m, score));
-                        }
-                        RdbValue::SortedSet(members)
+                        members.push((m, score));
                     }
-                    RDB_TYPE_STREAM => {
-                        // Decode watermark, private entries-added counter, and the
-                        // max-deleted-entry-id watermark (frankenredis-fplrm).
-                        if cursor + 40 > data.len() {
-                            return Err(PersistError::InvalidFrame);
-                        }
-                        let wm_ms = u64::from_le_bytes(
+                    RdbValue::SortedSet(members)
+                }
+                RDB_TYPE_STREAM => {
+                    // Decode watermark, private entries-added counter, and the
+                    // max-deleted-entry-id watermark (frankenredis-fplrm).
+                    rdb_need(data, cursor + 40)?;
+                    let wm_ms = u64::from_le_bytes(
+                        data[cursor..cursor + 8]
+                            .try_into()
+                            .map_err(|_| PersistError::InvalidFrame)?,
+                    );
+                    cursor += 8;
+                    let wm_seq = u64::from_le_bytes(
+                        data[cursor..cursor + 8]
+                            .try_into()
+                            .map_err(|_| PersistError::InvalidFrame)?,
+                    );
+                    cursor += 8;
+                    let watermark = if wm_ms == 0 && wm_seq == 0 {
+                        None
+                    } else {
+                        Some((wm_ms, wm_seq))
+                    };
+                    let entries_added = u64::from_le_bytes(
+                        data[cursor..cursor + 8]
+                            .try_into()
+                            .map_err(|_| PersistError::InvalidFrame)?,
+                    );
+                    cursor += 8;
+                    let md_ms = u64::from_le_bytes(
+                        data[cursor..cursor + 8]
+                            .try_into()
+                            .map_err(|_| PersistError::InvalidFrame)?,
+                    );
+                    cursor += 8;
+                    let md_seq = u64::from_le_bytes(
+                        data[cursor..cursor + 8]
+                            .try_into()
+                            .map_err(|_| PersistError::InvalidFrame)?,
+                    );
+                    cursor += 8;
+                    let max_deleted = if md_ms == 0 && md_seq == 0 {
+                        None
+                    } else {
+                        Some((md_ms, md_seq))
+                    };
+                    let (count, consumed) = rdb_read_length(&data[cursor..])?;
+                    cursor += consumed;
+                    let mut stream_entries =
+                        Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..count {
+                        rdb_need(data, cursor + 16)?;
+                        let ms = u64::from_le_bytes(
                             data[cursor..cursor + 8]
                                 .try_into()
                                 .map_err(|_| PersistError::InvalidFrame)?,
                         );
                         cursor += 8;
-                        let wm_seq = u64::from_le_bytes(
+                        let seq = u64::from_le_bytes(
                             data[cursor..cursor + 8]
                                 .try_into()
                                 .map_err(|_| PersistError::InvalidFrame)?,
                         );
                         cursor += 8;
-                        let watermark = if wm_ms == 0 && wm_seq == 0 {
-                            None
-                        } else {
-                            Some((wm_ms, wm_seq))
-                        };
-                        let entries_added = u64::from_le_bytes(
+                        let (field_count, fc) = rdb_read_length(&data[cursor..])?;
+                        cursor += fc;
+                        let mut fields =
+                            Vec::with_capacity(field_count.min(RDB_COLLECTION_PRESIZE_CAP));
+                        for _ in 0..field_count {
+                            let (fname, c1) = rdb_read_string(&data[cursor..])?;
+                            cursor += c1;
+                            let (fval, c2) = rdb_read_string(&data[cursor..])?;
+                            cursor += c2;
+                            fields.push((fname, fval));
+                        }
+                        stream_entries.push((ms, seq, fields));
+                    }
+                    // Decode consumer groups (always present in stream encoding).
+                    let (group_count, gc) = rdb_read_length(&data[cursor..])?;
+                    cursor += gc;
+                    let mut groups = Vec::with_capacity(group_count.min(256));
+                    for _ in 0..group_count {
+                        let (name, nc) = rdb_read_string(&data[cursor..])?;
+                        cursor += nc;
+                        rdb_need(data, cursor + 24)?;
+                        let ld_ms = u64::from_le_bytes(
                             data[cursor..cursor + 8]
                                 .try_into()
                                 .map_err(|_| PersistError::InvalidFrame)?,
                         );
                         cursor += 8;
-                        let md_ms = u64::from_le_bytes(
+                        let ld_seq = u64::from_le_bytes(
                             data[cursor..cursor + 8]
                                 .try_into()
                                 .map_err(|_| PersistError::InvalidFrame)?,
                         );
                         cursor += 8;
-                        let md_seq = u64::from_le_bytes(
+                        let entries_read = u64::from_le_bytes(
                             data[cursor..cursor + 8]
                                 .try_into()
                                 .map_err(|_| PersistError::InvalidFrame)?,
                         );
                         cursor += 8;
-                        let max_deleted = if md_ms == 0 && md_seq == 0 {
-                            None
-                        } else {
-                            Some((md_ms, md_seq))
-                        };
-                        let (count, consumed) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let mut stream_entries =
-                            Vec::with_capacity(count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..count {
-                            if cursor + 16 > data.len() {
-                                return Err(PersistError::InvalidFrame);
-                            }
-                            let ms = u64::from_le_bytes(
+                        // Consumers list
+                        let (consumer_count, cc) = rdb_read_length(&data[cursor..])?;
+                        cursor += cc;
+                        let mut consumers = Vec::with_capacity(consumer_count.min(256));
+                        for _ in 0..consumer_count {
+                            let (cname, cnc) = rdb_read_string(&data[cursor..])?;
+                            cursor += cnc;
+                            rdb_need(data, cursor + 16)?;
+                            let seen_time_ms = u64::from_le_bytes(
                                 data[cursor..cursor + 8]
                                     .try_into()
                                     .map_err(|_| PersistError::InvalidFrame)?,
                             );
                             cursor += 8;
-                            let seq = u64::from_le_bytes(
+                            let active_raw = u64::from_le_bytes(
                                 data[cursor..cursor + 8]
                                     .try_into()
                                     .map_err(|_| PersistError::InvalidFrame)?,
                             );
                             cursor += 8;
-                            let (field_count, fc) = rdb_decode_length(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += fc;
-                            let mut fields =
-                                Vec::with_capacity(field_count.min(RDB_COLLECTION_PRESIZE_CAP));
-                            for _ in 0..field_count {
-                                let (fname, c1) = rdb_decode_string(&data[cursor..])
-                                    .ok_or(PersistError::InvalidFrame)?;
-                                cursor += c1;
-                                let (fval, c2) = rdb_decode_string(&data[cursor..])
-                                    .ok_or(PersistError::InvalidFrame)?;
-                                cursor += c2;
-                                fields.push((fname, fval));
-                            }
-                            stream_entries.push((ms, seq, fields));
+                            consumers.push(RdbStreamConsumer {
+                                name: cname,
+                                seen_time_ms,
+                                active_time_ms: if active_raw as i64 == -1 {
+                                    None
+                                } else {
+                                    Some(active_raw)
+                                },
+                            });
                         }
-                        // Decode consumer groups (always present in stream encoding).
-                        let (group_count, gc) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += gc;
-                        let mut groups = Vec::with_capacity(group_count.min(256));
-                        for _ in 0..group_count {
-                            let (name, nc) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += nc;
-                            if cursor + 24 > data.len() {
-                                return Err(PersistError::InvalidFrame);
-                            }
-                            let ld_ms = u64::from_le_bytes(
+                        // Pending entries
+                        let (pel_count, pc) = rdb_read_length(&data[cursor..])?;
+                        cursor += pc;
+                        let mut pending = Vec::with_capacity(pel_count.min(4096));
+                        for _ in 0..pel_count {
+                            rdb_need(data, cursor + 16)?;
+                            let eid_ms = u64::from_le_bytes(
                                 data[cursor..cursor + 8]
                                     .try_into()
                                     .map_err(|_| PersistError::InvalidFrame)?,
                             );
                             cursor += 8;
-                            let ld_seq = u64::from_le_bytes(
+                            let eid_seq = u64::from_le_bytes(
                                 data[cursor..cursor + 8]
                                     .try_into()
                                     .map_err(|_| PersistError::InvalidFrame)?,
                             );
                             cursor += 8;
-                            let entries_read = u64::from_le_bytes(
+                            let (pe_consumer, pec) = rdb_read_string(&data[cursor..])?;
+                            cursor += pec;
+                            rdb_need(data, cursor + 16)?;
+                            let deliveries = u64::from_le_bytes(
                                 data[cursor..cursor + 8]
                                     .try_into()
                                     .map_err(|_| PersistError::InvalidFrame)?,
                             );
                             cursor += 8;
-                            // Consumers list
-                            let (consumer_count, cc) = rdb_decode_length(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += cc;
-                            let mut consumers = Vec::with_capacity(consumer_count.min(256));
-                            for _ in 0..consumer_count {
-                                let (cname, cnc) = rdb_decode_string(&data[cursor..])
-                                    .ok_or(PersistError::InvalidFrame)?;
-                                cursor += cnc;
-                                if cursor + 16 > data.len() {
-                                    return Err(PersistError::InvalidFrame);
-                                }
-                                let seen_time_ms = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                let active_raw = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                consumers.push(RdbStreamConsumer {
-                                    name: cname,
-                                    seen_time_ms,
-                                    active_time_ms: if active_raw as i64 == -1 {
-                                        None
-                                    } else {
-                                        Some(active_raw)
-                                    },
-                                });
-                            }
-                            // Pending entries
-                            let (pel_count, pc) = rdb_decode_length(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += pc;
-                            let mut pending = Vec::with_capacity(pel_count.min(4096));
-                            for _ in 0..pel_count {
-                                if cursor + 16 > data.len() {
-                                    return Err(PersistError::InvalidFrame);
-                                }
-                                let eid_ms = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                let eid_seq = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                let (pe_consumer, pec) = rdb_decode_string(&data[cursor..])
-                                    .ok_or(PersistError::InvalidFrame)?;
-                                cursor += pec;
-                                if cursor + 16 > data.len() {
-                                    return Err(PersistError::InvalidFrame);
-                                }
-                                let deliveries = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                let last_del_ms = u64::from_le_bytes(
-                                    data[cursor..cursor + 8]
-                                        .try_into()
-                                        .map_err(|_| PersistError::InvalidFrame)?,
-                                );
-                                cursor += 8;
-                                pending.push(RdbStreamPendingEntry {
-                                    entry_id_ms: eid_ms,
-                                    entry_id_seq: eid_seq,
-                                    consumer: pe_consumer,
-                                    deliveries,
-                                    last_delivered_ms: last_del_ms,
-                                });
-                            }
-                            groups.push(RdbStreamConsumerGroup {
-                                name,
-                                last_delivered_id_ms: ld_ms,
-                                last_delivered_id_seq: ld_seq,
-                                entries_read: if entries_read == u64::MAX {
-                                    None
-                                } else {
-                                    Some(entries_read)
-                                },
-                                consumers,
-                                pending,
+                            let last_del_ms = u64::from_le_bytes(
+                                data[cursor..cursor + 8]
+                                    .try_into()
+                                    .map_err(|_| PersistError::InvalidFrame)?,
+                            );
+                            cursor += 8;
+                            pending.push(RdbStreamPendingEntry {
+                                entry_id_ms: eid_ms,
+                                entry_id_seq: eid_seq,
+                                consumer: pe_consumer,
+                                deliveries,
+                                last_delivered_ms: last_del_ms,
                             });
                         }
-                        RdbValue::Stream(
-                            stream_entries,
-                            watermark,
-                            groups,
-                            None,
-                            Some(entries_added),
-                            max_deleted,
-                        )
-                    }
-                    UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_2 | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3 => {
-                        let (value, consumed) =
-                            rdb_stream::decode_upstream_stream_skeleton(type_byte, &data[cursor..])
-                                .map_err(|_| PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        value
-                    }
-                    RDB_TYPE_SET_INTSET => {
-                        // Payload is a string-wrapped binary intset blob.
-                        let (intset, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let members =
-                            decode_intset_members(&intset).ok_or(PersistError::InvalidFrame)?;
-                        RdbValue::Set(members)
+                        groups.push(RdbStreamConsumerGroup {
+                            name,
+                            last_delivered_id_ms: ld_ms,
+                            last_delivered_id_seq: ld_seq,
+                            entries_read: if entries_read == u64::MAX {
+                                None
+                            } else {
+                                Some(entries_read)
+                            },
+                            consumers,
+                            pending,
+                        });
                     }
-                    RDB_TYPE_SET_LISTPACK => {
-                        let (listpack, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let members = listpack::decode_listpack(&listpack)
-                            .map_err(|_| PersistError::InvalidFrame)?
-                            .into_iter()
-                            .map(listpack::ListpackEntry::into_bytes)
-                            .collect();
-                        RdbValue::Set(members)
+                    RdbValue::Stream(
+                        stream_entries,
+                        watermark,
+                        groups,
+                        None,
+                        Some(entries_added),
+                        max_deleted,
+                    )
+                }
+                UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_2 | UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3 => {
+                    let (value, consumed) =
+                        rdb_stream::decode_upstream_stream_skeleton(type_byte, &data[cursor..])
+                            .map_err(|err| match err {
+                                rdb_stream::UpstreamStreamError::Truncated => RDB_TRUNCATED,
+                                _ => PersistError::InvalidFrame,
+                            })?;
+                    cursor += consumed;
+                    value
+                }
+                RDB_TYPE_SET_INTSET => {
+                    // Payload is a string-wrapped binary intset blob.
+                    let (intset, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let members =
+                        decode_intset_members(&intset).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::Set(members)
+                }
+                RDB_TYPE_SET_LISTPACK => {
+                    let (listpack, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let members = listpack::decode_listpack(&listpack)
+                        .map_err(|_| PersistError::InvalidFrame)?
+                        .into_iter()
+                        .map(listpack::ListpackEntry::into_bytes)
+                        .collect();
+                    RdbValue::Set(members)
+                }
+                RDB_TYPE_HASH_LISTPACK => {
+                    // Listpack of f1, v1, f2, v2, ... pairs.
+                    let (listpack, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    // Pair owned decoded entries straight into `fields`.
+                    // Moving string payloads avoids a clone+drop allocation;
+                    // integer entries still render to canonical decimal bytes.
+                    let decoded = listpack::decode_listpack(&listpack)
+                        .map_err(|_| PersistError::InvalidFrame)?;
+                    if !decoded.len().is_multiple_of(2) {
+                        return Err(PersistError::InvalidFrame);
                     }
-                    RDB_TYPE_HASH_LISTPACK => {
-                        // Listpack of f1, v1, f2, v2, ... pairs.
-                        let (listpack, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        // Pair owned decoded entries straight into `fields`.
-                        // Moving string payloads avoids a clone+drop allocation;
-                        // integer entries still render to canonical decimal bytes.
-                        let decoded = listpack::decode_listpack(&listpack)
-                            .map_err(|_| PersistError::InvalidFrame)?;
-                        if !decoded.len().is_multiple_of(2) {
-                            return Err(PersistError::InvalidFrame);
-                        }
-                        let mut fields = Vec::with_capacity(decoded.len() / 2);
-                        let mut it = decoded.into_iter();
-                        while let Some(field) = it.next() {
-                            let value = it.next().ok_or(PersistError::InvalidFrame)?;
-                            fields.push((field.into_bytes(), value.into_bytes()));
-                        }
-                        RdbValue::Hash(fields)
+                    let mut fields = Vec::with_capacity(decoded.len() / 2);
+                    let mut it = decoded.into_iter();
+                    while let Some(field) = it.next() {
+                        let value = it.next().ok_or(PersistError::InvalidFrame)?;
+                        fields.push((field.into_bytes(), value.into_bytes()));
                     }
-                    RDB_TYPE_ZSET_LISTPACK => {
-                        // Listpack of m1, score1, m2, score2, ... where each
-                        // score is encoded as a decimal string (upstream
-                        // calls listpackAppend with the textual score).
-                        let (listpack, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::Hash(fields)
+                }
+                RDB_TYPE_ZSET_LISTPACK => {
+                    // Listpack of m1, score1, m2, score2, ... where each
+                    // score is encoded as a decimal string (upstream
+                    // calls listpackAppend with the textual score).
+                    let (listpack, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    // Decode straight to (member, score) pairs. Members
+                    // materialize owned bytes; each score's f64 is read
+                    // allocation-free via the shared raw-entry core —
+                    // integer scores as `n as f64` (CrimsonHawk 788bbfd00's
+                    // -24.7% shortcut: `n as f64` == `parse(decimal(n))`),
+                    // string scores (1.5, inf, ...) by parsing a BORROWED
+                    // slice rather than a decode_listpack-allocated `Vec<u8>`
+                    // that was parsed then dropped. Byte-/bit-identical
+                    // members and scores; structural validation and the odd
+                    // element-count rejection mirror the old path exactly.
+                    let members = listpack::decode_zset_listpack_pairs(&listpack)
+                        .map_err(|_| PersistError::InvalidFrame)?;
+                    RdbValue::SortedSet(members)
+                }
+                RDB_TYPE_LIST_QUICKLIST_2 => {
+                    // node_count nodes, each: (container:length,
+                    // listpack:string). Upstream's container is 1 for
+                    // PLAIN nodes (raw string elements) and 2 for
+                    // PACKED nodes (listpack-of-elements). We accept
+                    // both; a PLAIN node carries exactly one element.
+                    let (node_count, consumed) = rdb_read_length(&data[cursor..])?;
+                    cursor += consumed;
+                    let mut items = Vec::with_capacity(node_count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..node_count {
+                        let (container, consumed) = rdb_read_length(&data[cursor..])?;
                         cursor += consumed;
-                        // Decode straight to (member, score) pairs. Members
-                        // materialize owned bytes; each score's f64 is read
-                        // allocation-free via the shared raw-entry core —
-                        // integer scores as `n as f64` (CrimsonHawk 788bbfd00's
-                        // -24.7% shortcut: `n as f64` == `parse(decimal(n))`),
-                        // string scores (1.5, inf, ...) by parsing a BORROWED
-                        // slice rather than a decode_listpack-allocated `Vec<u8>`
-                        // that was parsed then dropped. Byte-/bit-identical
-                        // members and scores; structural validation and the odd
-                        // element-count rejection mirror the old path exactly.
-                        let members = listpack::decode_zset_listpack_pairs(&listpack)
-                            .map_err(|_| PersistError::InvalidFrame)?;
-                        RdbValue::SortedSet(members)
-                    }
-                    RDB_TYPE_LIST_QUICKLIST_2 => {
-                        // node_count nodes, each: (container:length,
-                        // listpack:string). Upstream's container is 1 for
-                        // PLAIN nodes (raw string elements) and 2 for
-                        // PACKED nodes (listpack-of-elements). We accept
-                        // both; a PLAIN node carries exactly one element.
-                        let (node_count, consumed) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                        let (node_blob, consumed) = rdb_read_string(&data[cursor..])?;
                         cursor += consumed;
-                        let mut items =
-                            Vec::with_capacity(node_count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..node_count {
-                            let (container, consumed) = rdb_decode_length(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += consumed;
-                            let (node_blob, consumed) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += consumed;
-                            match container {
-                                1 => {
-                                    // PLAIN: the blob is the element itself.
-                                    items.push(node_blob);
-                                }
-                                2 => {
-                                    // PACKED: the blob is a listpack. Move each
-                                    // decoded entry's payload out with `into_bytes`
-                                    // (the iterator yields owned entries) rather than
-                                    // `to_bytes`, which cloned the string payload and
-                                    // dropped the original — one wasted alloc+copy+free
-                                    // per packed list element on the quicklist2 decode
-                                    // (RESTORE / DEBUG RELOAD) path. Byte-identical.
-                                    for entry in listpack::decode_listpack(&node_blob)
-                                        .map_err(|_| PersistError::InvalidFrame)?
-                                    {
-                                        items.push(entry.into_bytes());
-                                    }
+                        match container {
+                            1 => {
+                                // PLAIN: the blob is the element itself.
+                                items.push(node_blob);
+                            }
+                            2 => {
+                                // PACKED: the blob is a listpack. Move each
+                                // decoded entry's payload out with `into_bytes`
+                                // (the iterator yields owned entries) rather than
+                                // `to_bytes`, which cloned the string payload and
+                                // dropped the original — one wasted alloc+copy+free
+                                // per packed list element on the quicklist2 decode
+                                // (RESTORE / DEBUG RELOAD) path. Byte-identical.
+                                for entry in listpack::decode_listpack(&node_blob)
+                                    .map_err(|_| PersistError::InvalidFrame)?
+                                {
+                                    items.push(entry.into_bytes());
                                 }
-                                _ => return Err(PersistError::InvalidFrame),
                             }
+                            _ => return Err(PersistError::InvalidFrame),
                         }
-                        RdbValue::List(items)
                     }
-                    RDB_TYPE_LIST_ZIPLIST => {
-                        // Legacy single-ziplist list (redis ≤ 6.2).
-                        let (zl, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::List(items)
+                }
+                RDB_TYPE_LIST_ZIPLIST => {
+                    // Legacy single-ziplist list (redis ≤ 6.2).
+                    let (zl, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let items = ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
+                    RdbValue::List(items)
+                }
+                RDB_TYPE_LIST_QUICKLIST => {
+                    // Legacy quicklist: node_count plain ziplist nodes (no
+                    // per-node container byte, unlike QUICKLIST_2).
+                    let (node_count, consumed) = rdb_read_length(&data[cursor..])?;
+                    cursor += consumed;
+                    let mut items = Vec::with_capacity(node_count.min(RDB_COLLECTION_PRESIZE_CAP));
+                    for _ in 0..node_count {
+                        let (node_blob, consumed) = rdb_read_string(&data[cursor..])?;
                         cursor += consumed;
-                        let items =
-                            ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
-                        RdbValue::List(items)
+                        items.extend(
+                            ziplist::decode_ziplist(&node_blob)
+                                .ok_or(PersistError::InvalidFrame)?,
+                        );
                     }
-                    RDB_TYPE_LIST_QUICKLIST => {
-                        // Legacy quicklist: node_count plain ziplist nodes (no
-                        // per-node container byte, unlike QUICKLIST_2).
-                        let (node_count, consumed) =
-                            rdb_decode_length(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let mut items =
-                            Vec::with_capacity(node_count.min(RDB_COLLECTION_PRESIZE_CAP));
-                        for _ in 0..node_count {
-                            let (node_blob, consumed) = rdb_decode_string(&data[cursor..])
-                                .ok_or(PersistError::InvalidFrame)?;
-                            cursor += consumed;
-                            items.extend(
-                                ziplist::decode_ziplist(&node_blob)
-                                    .ok_or(PersistError::InvalidFrame)?,
-                            );
-                        }
-                        RdbValue::List(items)
+                    RdbValue::List(items)
+                }
+                RDB_TYPE_HASH_ZIPLIST => {
+                    // Ziplist of f1, v1, f2, v2, ... pairs.
+                    let (zl, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let entries = ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
+                    if !entries.len().is_multiple_of(2) {
+                        return Err(PersistError::InvalidFrame);
                     }
-                    RDB_TYPE_HASH_ZIPLIST => {
-                        // Ziplist of f1, v1, f2, v2, ... pairs.
-                        let (zl, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let entries =
-                            ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
-                        if !entries.len().is_multiple_of(2) {
-                            return Err(PersistError::InvalidFrame);
-                        }
-                        let fields = if MOVE_LEGACY_HASH_ZIPLIST_FIELDS {
-                            let mut fields = Vec::with_capacity(entries.len() / 2);
-                            let mut entries = entries.into_iter();
-                            loop {
-                                match (entries.next(), entries.next()) {
-                                    (Some(field), Some(value)) => fields.push((field, value)),
-                                    (None, None) => break,
-                                    _ => return Err(PersistError::InvalidFrame),
-                                }
+                    let fields = if MOVE_LEGACY_HASH_ZIPLIST_FIELDS {
+                        let mut fields = Vec::with_capacity(entries.len() / 2);
+                        let mut entries = entries.into_iter();
+                        loop {
+                            match (entries.next(), entries.next()) {
+                                (Some(field), Some(value)) => fields.push((field, value)),
+                                (None, None) => break,
+                                _ => return Err(PersistError::InvalidFrame),
                             }
-                            fields
-                        } else {
-                            entries
-                                .as_chunks::<2>()
-                                .0
-                                .iter()
-                                .map(|pair| (pair[0].clone(), pair[1].clone()))
-                                .collect()
-                        };
-                        RdbValue::Hash(fields)
-                    }
-                    RDB_TYPE_HASH_ZIPMAP => {
-                        // Even-older zipmap small-hash encoding (redis ≤ 2.4).
-                        let (zm, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let entries =
-                            ziplist::decode_zipmap(&zm).ok_or(PersistError::InvalidFrame)?;
-                        if !entries.len().is_multiple_of(2) {
-                            return Err(PersistError::InvalidFrame);
                         }
-                        let fields = entries
+                        fields
+                    } else {
+                        entries
                             .as_chunks::<2>()
                             .0
                             .iter()
                             .map(|pair| (pair[0].clone(), pair[1].clone()))
-                            .collect();
-                        RdbValue::Hash(fields)
+                            .collect()
+                    };
+                    RdbValue::Hash(fields)
+                }
+                RDB_TYPE_HASH_ZIPMAP => {
+                    // Even-older zipmap small-hash encoding (redis ≤ 2.4).
+                    let (zm, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let entries = ziplist::decode_zipmap(&zm).ok_or(PersistError::InvalidFrame)?;
+                    if !entries.len().is_multiple_of(2) {
+                        return Err(PersistError::InvalidFrame);
                     }
-                    RDB_TYPE_ZSET_ZIPLIST => {
-                        // Ziplist of m1, score1, m2, score2, ... with the score
-                        // as its decimal-string form.
-                        let (zl, consumed) =
-                            rdb_decode_string(&data[cursor..]).ok_or(PersistError::InvalidFrame)?;
-                        cursor += consumed;
-                        let entries =
-                            ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
-                        if !entries.len().is_multiple_of(2) {
-                            return Err(PersistError::InvalidFrame);
-                        }
-                        let mut members = Vec::with_capacity(entries.len() / 2);
-                        for pair in entries.as_chunks::<2>().0 {
-                            let score = std::str::from_utf8(&pair[1])
-                                .ok()
-                                .and_then(|s| s.parse::<f64>().ok())
-                                .ok_or(PersistError::InvalidFrame)?;
-                            members.push((pair[0].clone(), score));
-                        }
-                        RdbValue::SortedSet(members)
+                    let fields = entries
+                        .as_chunks::<2>()
+                        .0
+                        .iter()
+                        .map(|pair| (pair[0].clone(), pair[1].clone()))
+                        .collect();
+                    RdbValue::Hash(fields)
+                }
+                RDB_TYPE_ZSET_ZIPLIST => {
+                    // Ziplist of m1, score1, m2, score2, ... with the score
+                    // as its decimal-string form.
+                    let (zl, consumed) = rdb_read_string(&data[cursor..])?;
+                    cursor += consumed;
+                    let entries = ziplist::decode_ziplist(&zl).ok_or(PersistError::InvalidFrame)?;
+                    if !entries.len().is_multiple_of(2) {
+                        return Err(PersistError::InvalidFrame);
                     }
-                    _ => return Err(PersistError::InvalidFrame),
-                };
+                    let mut members = Vec::with_capacity(entries.len() / 2);
+                    for pair in entries.as_chunks::<2>().0 {
+                        let score = std::str::from_utf8(&pair[1])
+                            .ok()
+                            .and_then(|s| s.parse::<f64>().ok())
+                            .ok_or(PersistError::InvalidFrame)?;
+                        members.push((pair[0].clone(), score));
+                    }
+                    RdbValue::SortedSet(members)
+                }
+                _ => return Err(PersistError::InvalidFrame),
+            };
 
-                entries.push(RdbEntry {
-                    db: current_db,
+            return Ok((
+                RdbRecord::Entry(RdbEntry {
+                    db: state.current_db,
                     key,
                     value,
-                    expire_ms: pending_expire_ms.take(),
-                });
-            }
-            _ => {
-                // Unknown type — skip this entry (fail-closed for safety)
-                return Err(PersistError::InvalidFrame);
-            }
+                    expire_ms: state.pending_expire_ms.take(),
+                }),
+                cursor,
+            ));
+        }
+        _ => {
+            // Unknown type — skip this entry (fail-closed for safety)
+            return Err(PersistError::InvalidFrame);
         }
     }
-
-    if !saw_eof {
-        return Err(PersistError::InvalidFrame);
-    }
-
-    Ok(RdbDecodeResult {
-        entries,
-        aux,
-        consumed: cursor,
-        functions,
-    })
+    Ok((RdbRecord::Other, cursor))
 }
 
 /// Decode an RDB file into entries. Returns entries and auxiliary metadata.
@@ -5494,14 +5939,16 @@ mod tests {
     // ── RDB tests ────────────────────────────────────────────────────
 
     use super::{
-        CompactRdbThresholds, RDB_CHECKSUM_LEN, RDB_OPCODE_AUX, RDB_OPCODE_EOF,
-        RDB_OPCODE_EXPIRETIME_MS, RDB_OPCODE_FUNCTION2, RDB_OPCODE_RESIZEDB, RDB_OPCODE_SELECTDB,
-        RDB_TYPE_HASH, RDB_TYPE_HASH_LISTPACK, RDB_TYPE_HASH_WITH_TTLS, RDB_TYPE_LIST,
-        RDB_TYPE_LIST_QUICKLIST_2, RDB_TYPE_SET, RDB_TYPE_SET_INTSET, RDB_TYPE_SET_LISTPACK,
-        RDB_TYPE_STRING, RDB_TYPE_ZSET_2, RDB_TYPE_ZSET_LISTPACK, RdbEncodeOptions, RdbEntry,
-        RdbStreamConsumer, RdbStreamConsumerGroup, RdbStreamMetadata, RdbStreamPendingEntry,
-        RdbValue, UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3, crc64_redis, decode_intset_members,
-        decode_rdb, decode_rdb_prefix, encode_compact_set_intset, encode_hash_listpack_blob,
+        AofStreamError, AofStreamReader, CompactRdbThresholds, RDB_CHECKSUM_LEN, RDB_OPCODE_AUX,
+        RDB_OPCODE_EOF, RDB_OPCODE_EXPIRETIME_MS, RDB_OPCODE_FUNCTION2, RDB_OPCODE_MODULE_AUX,
+        RDB_OPCODE_RESIZEDB, RDB_OPCODE_SELECTDB, RDB_TYPE_HASH, RDB_TYPE_HASH_LISTPACK,
+        RDB_TYPE_HASH_WITH_TTLS, RDB_TYPE_LIST, RDB_TYPE_LIST_QUICKLIST_2, RDB_TYPE_MODULE_2,
+        RDB_TYPE_SET, RDB_TYPE_SET_INTSET, RDB_TYPE_SET_LISTPACK, RDB_TYPE_STRING, RDB_TYPE_ZSET_2,
+        RDB_TYPE_ZSET_LISTPACK, RdbEncodeOptions, RdbEntry, RdbSkipped, RdbStreamConsumer,
+        RdbStreamConsumerGroup, RdbStreamMetadata, RdbStreamPendingEntry, RdbValue,
+        STREAM_READ_CHUNK, UPSTREAM_RDB_TYPE_STREAM_LISTPACKS_3, crc64_redis,
+        decode_intset_members, decode_rdb, decode_rdb_prefix, decode_rdb_prefix_lenient,
+        decode_rdb_reader_lenient, encode_compact_set_intset, encode_hash_listpack_blob,
         encode_listpack_strings_blob, encode_rdb, encode_rdb_with_functions,
         encode_rdb_with_options, encode_set_listpack_blob, lzf_compress, lzf_decompress,
         rdb_decode_string, rdb_encode_length, rdb_encode_string,
@@ -6093,6 +6540,232 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lenient_rdb_decode_skips_module_data_and_newer_versions() {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        let module_id = b"ReJSON-RL".iter().fold(0usize, |id, byte| {
+            (id << 6) | CHARSET.iter().position(|c| c == byte).unwrap()
+        }) << 10
+            | 3;
+        // One value in every module opcode shape, then RDB_MODULE_OPCODE_EOF.
+        let module_value = |buf: &mut Vec<u8>| {
+            rdb_encode_length(buf, 1);
+            rdb_encode_length(buf, 7);
+            rdb_encode_length(buf, 2);
+            rdb_encode_length(buf, 1 << 40);
+            rdb_encode_length(buf, 3);
+            buf.extend_from_slice(&1.5f32.to_le_bytes());
+            rdb_encode_length(buf, 4);
+            buf.extend_from_slice(&2.5f64.to_le_bytes());
+            rdb_encode_length(buf, 5);
+            rdb_encode_string(buf, b"{\"a\":1}");
+            rdb_encode_length(buf, 0);
+        };
+
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(b"REDIS0012");
+        encoded.push(RDB_OPCODE_MODULE_AUX);
+        rdb_encode_length(&mut encoded, module_id);
+        rdb_encode_length(&mut encoded, 2);
+        rdb_encode_length(&mut encoded, 2);
+        module_value(&mut encoded);
+        encoded.push(RDB_OPCODE_SELECTDB);
+        rdb_encode_length(&mut encoded, 3);
+        encoded.push(RDB_OPCODE_EXPIRETIME_MS);
+        encoded.extend_from_slice(&1_700_000_000_000_u64.to_le_bytes());
+        encoded.push(RDB_TYPE_MODULE_2);
+        rdb_encode_string(&mut encoded, b"doc");
+        rdb_encode_length(&mut encoded, module_id);
+        module_value(&mut encoded);
+        encoded.push(RDB_TYPE_STRING);
+        rdb_encode_string(&mut encoded, b"plain");
+        rdb_encode_string(&mut encoded, b"value");
+        encoded.push(RDB_OPCODE_EOF);
+        append_rdb_checksum(&mut encoded);
+
+        assert_eq!(decode_rdb_prefix(&encoded), Err(PersistError::InvalidFrame));
+        let mut current = encoded.clone();
+        current[5..9].copy_from_slice(b"0011");
+        assert_eq!(decode_rdb_prefix(&current), Err(PersistError::InvalidFrame));
+
+        let (decoded, skipped) = decode_rdb_prefix_lenient(&encoded).expect("lenient decode");
+        assert_eq!(decoded.consumed, encoded.len());
+        assert_eq!(
+            decoded.entries,
+            vec![RdbEntry {
+                db: 3,
+                key: b"plain".to_vec(),
+                value: RdbValue::String(b"value".to_vec()),
+                expire_ms: None,
+            }],
+            "the module key's expiry must not leak onto the next key"
+        );
+        assert_eq!(
+            skipped,
+            vec![
+                RdbSkipped::NewerVersion(12),
+                RdbSkipped::ModuleAux {
+                    module: "ReJSON-RL".to_string(),
+                },
+                RdbSkipped::ModuleValue {
+                    db: 3,
+                    key: b"doc".to_vec(),
+                    module: "ReJSON-RL".to_string(),
+                },
+            ]
+        );
+
+        // Records we cannot walk past still fail the lenient load.
+        let mut unknown = b"REDIS0011".to_vec();
+        unknown.push(0xF4);
+        unknown.push(RDB_OPCODE_EOF);
+        append_rdb_checksum(&mut unknown);
+        assert_eq!(
+            decode_rdb_prefix_lenient(&unknown),
+            Err(PersistError::InvalidFrame)
+        );
+    }
+
+    /// Hands out at most seven bytes per read, so every record straddles reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn rdb_reader_decode_matches_the_slice_decoder() {
+        let mut entries: Vec<RdbEntry> = (0..300)
+            .map(|i| RdbEntry {
+                db: i % 3,
+                key: format!("key:{i}").into_bytes(),
+                value: RdbValue::String(vec![b'a' + (i % 26) as u8; 1000 + i]),
+                expire_ms: (i % 5 == 0).then_some(1_700_000_000_000 + i as u64),
+            })
+            .collect();
+        // Larger than a few read chunks, so the window has to grow.
+        entries.push(RdbEntry {
+            db: 0,
+            key: b"big".to_vec(),
+            value: RdbValue::List((0..4000).map(|i| vec![(i % 251) as u8; 97]).collect()),
+            expire_ms: None,
+        });
+        let mut encoded = encode_rdb(&entries, &[("redis-ver", "7.2.4")]);
+        let rdb_len = encoded.len();
+        encoded.extend_from_slice(b"*1\r\n$4\r\nPING\r\n");
+
+        let (expected, _) = decode_rdb_prefix_lenient(&encoded).expect("slice decode");
+        let mut decoded = Vec::new();
+        let mut reader = Trickle(&encoded);
+        let summary = decode_rdb_reader_lenient(&mut reader, |entry| {
+            decoded.push(entry);
+            Ok(())
+        })
+        .expect("reader decode");
+        assert_eq!(decoded, expected.entries);
+        assert_eq!(summary.aux, expected.aux);
+        assert_eq!(summary.consumed, rdb_len as u64);
+        assert!(summary.skipped.is_empty());
+        let mut rest = summary.tail;
+        std::io::Read::read_to_end(&mut reader, &mut rest).expect("rest");
+        assert_eq!(rest, b"*1\r\n$4\r\nPING\r\n");
+
+        // The checksum still covers the bytes the reader let go of.
+        let mut corrupt = encoded[..rdb_len].to_vec();
+        corrupt[rdb_len - 1] ^= 0xFF;
+        assert_eq!(
+            decode_rdb_reader_lenient(&mut Trickle(&corrupt), |_| Ok(())).map(|s| s.consumed),
+            Err(PersistError::InvalidFrame)
+        );
+        // As does running out of input before the EOF opcode.
+        assert_eq!(
+            decode_rdb_reader_lenient(&mut Trickle(&encoded[..rdb_len / 2]), |_| Ok(()))
+                .map(|s| s.consumed),
+            Err(PersistError::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn rdb_reader_stops_at_a_corrupt_record_without_reading_ahead() {
+        struct Counting<'a> {
+            data: &'a [u8],
+            read: usize,
+        }
+        impl std::io::Read for Counting<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(self.data.len());
+                buf[..n].copy_from_slice(&self.data[..n]);
+                self.data = &self.data[n..];
+                self.read += n;
+                Ok(n)
+            }
+        }
+
+        // A string key whose header uses an encoding no RDB defines,
+        // followed by far more input than a window holds.
+        let mut corrupt = b"REDIS0011".to_vec();
+        corrupt.extend_from_slice(&[RDB_TYPE_STRING, 0xC5]);
+        corrupt.resize(64 * STREAM_READ_CHUNK, 0);
+        let mut reader = Counting {
+            data: &corrupt,
+            read: 0,
+        };
+        assert_eq!(
+            decode_rdb_reader_lenient(&mut reader, |_| Ok(())).map(|s| s.consumed),
+            Err(PersistError::InvalidFrame)
+        );
+        assert!(
+            reader.read <= 2 * STREAM_READ_CHUNK,
+            "read {} bytes past a corrupt record",
+            reader.read
+        );
+    }
+
+    #[test]
+    fn aof_stream_reader_yields_records_then_reports_the_unreadable_tail() {
+        let records = vec![
+            AofRecord {
+                argv: vec![b"SET".to_vec(), b"k".to_vec(), vec![b'v'; 300_000]],
+            },
+            AofRecord {
+                argv: vec![b"INCR".to_vec(), b"n".to_vec()],
+            },
+        ];
+        let mut aof = encode_aof_stream(&records);
+        let clean_len = aof.len();
+        aof.extend_from_slice(b"*2\r\n$4\r\nINCR");
+
+        let expected = decode_aof_stream_with_offsets(&aof[..clean_len]).expect("slice decode");
+        let mut reader = AofStreamReader::new(Trickle(&aof));
+        for record in &expected {
+            assert_eq!(reader.next_record().expect("record").as_ref(), Some(record));
+        }
+        match reader.next_record() {
+            Err(AofStreamError::Unreadable { offset, failure }) => {
+                assert_eq!(offset, clean_len);
+                assert_eq!(
+                    failure,
+                    AofReplayTailFailure::Parse(RespParseError::Incomplete)
+                );
+            }
+            other => panic!("expected the truncated tail, got {other:?}"),
+        }
+        assert_eq!(
+            reader.discard_rest().expect("discard"),
+            aof.len() - clean_len
+        );
+
+        let mut clean = AofStreamReader::new(Trickle(&aof[..clean_len]));
+        assert!(clean.next_record().expect("first").is_some());
+        assert!(clean.next_record().expect("second").is_some());
+        assert!(clean.next_record().expect("end").is_none());
+    }
+
     #[test]
     fn encode_rdb_with_functions_round_trips_through_decode() {
         // The FUNCTION2 records the encoder emits must decode back to the same
@@ -9044,6 +9717,35 @@ mod tests {
             );
         }
 
+        #[test]
+        fn decode_rdb_bounds_the_resizedb_reservation_by_the_input() {
+            let mut rdb = encode_rdb(
+                &[RdbEntry {
+                    db: 0,
+                    key: b"k".to_vec(),
+                    value: RdbValue::String(b"v".to_vec()),
+                    expire_ms: None,
+                }],
+                &[],
+            );
+            // Claim 2^32 keys in the RESIZEDB record ahead of the single entry.
+            let at = rdb
+                .windows(2)
+                .position(|w| w == [RDB_OPCODE_RESIZEDB, 1])
+                .expect("resizedb record");
+            rdb.splice(
+                at + 1..at + 2,
+                [0x81, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+            );
+            let crc_at = rdb.len() - 8;
+            let crc = crc64_redis(&rdb[..crc_at]);
+            rdb[crc_at..].copy_from_slice(&crc.to_le_bytes());
+
+            let decoded = decode_rdb_prefix(&rdb).expect("decode");
+            assert_eq!(decoded.entries.len(), 1);
+            assert!(decoded.entries.capacity() <= rdb.len());
+        }
+
         /// Golden test: RDB list type encoding uses the upstream compact type byte.
         #[test]
         fn golden_rdb_list_type() {
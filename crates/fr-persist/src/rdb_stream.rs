@@ -27,7 +27,7 @@ use crate::{
     RdbStreamPendingEntry, RdbValue, StreamEntry,
 };
 
-use super::{rdb_decode_length, rdb_decode_string};
+use super::{rdb_decode_length, rdb_decode_string, rdb_length_truncated, rdb_string_truncated};
 
 /// Upstream stream entry flags (matches upstream's `streamFlags`).
 const STREAM_ITEM_FLAG_NONE: i64 = 0;
@@ -74,6 +74,28 @@ pub enum UpstreamStreamError {
     InconsistentEntryTrailer,
     /// A consumer-local PEL referenced an ID absent from the group's global PEL.
     MissingGlobalPelEntry,
+    /// The payload ended before the stream did.
+    Truncated,
+}
+
+fn read_length(data: &[u8]) -> Result<(usize, usize), UpstreamStreamError> {
+    rdb_decode_length(data).ok_or_else(|| {
+        if rdb_length_truncated(data) {
+            UpstreamStreamError::Truncated
+        } else {
+            UpstreamStreamError::InvalidLength
+        }
+    })
+}
+
+fn read_string(data: &[u8]) -> Result<(Vec<u8>, usize), UpstreamStreamError> {
+    rdb_decode_string(data).ok_or_else(|| {
+        if rdb_string_truncated(data) {
+            UpstreamStreamError::Truncated
+        } else {
+            UpstreamStreamError::InvalidString
+        }
+    })
 }
 
 impl From<ListpackError> for UpstreamStreamError {
@@ -643,14 +665,12 @@ pub(crate) fn decode_upstream_stream_skeleton(
     let mut entries: Vec<StreamEntry> = Vec::new();
 
     // (1) Listpacks count.
-    let (listpacks_count, c) =
-        rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+    let (listpacks_count, c) = read_length(&data[cursor..])?;
     cursor += c;
 
     // (2) For each radix-tree pair: nodekey (16-byte streamID) + listpack blob.
     for _ in 0..listpacks_count {
-        let (nodekey, c1) =
-            rdb_decode_string(&data[cursor..]).ok_or(UpstreamStreamError::InvalidString)?;
+        let (nodekey, c1) = read_string(&data[cursor..])?;
         if nodekey.len() != 16 {
             return Err(UpstreamStreamError::InvalidNodekeyLength);
         }
@@ -665,24 +685,20 @@ pub(crate) fn decode_upstream_stream_skeleton(
                 .map_err(|_| UpstreamStreamError::InvalidNodekeyLength)?,
         );
         cursor += c1;
-        let (lp_bytes, c2) =
-            rdb_decode_string(&data[cursor..]).ok_or(UpstreamStreamError::InvalidString)?;
+        let (lp_bytes, c2) = read_string(&data[cursor..])?;
         cursor += c2;
         let lp = decode_listpack(&lp_bytes)?;
         decode_stream_listpack(&lp, master_ms, master_seq, &mut entries)?;
     }
 
     // (3) Stream length (total entry count).
-    let (stream_length, c) =
-        rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+    let (stream_length, c) = read_length(&data[cursor..])?;
     cursor += c;
 
     // (4) last_id.ms, last_id.seq (always present).
-    let (last_id_ms, c) =
-        rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+    let (last_id_ms, c) = read_length(&data[cursor..])?;
     cursor += c;
-    let (last_id_seq, c) =
-        rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+    let (last_id_seq, c) = read_length(&data[cursor..])?;
     cursor += c;
 
     // (5) v2/v3 extras: first_id.ms, first_id.seq, max_deleted_id.ms,
@@ -692,8 +708,7 @@ pub(crate) fn decode_upstream_stream_skeleton(
     let mut max_deleted_seq = 0u64;
     if is_v2_or_later {
         for index in 0..5 {
-            let (_v, c) =
-                rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+            let (_v, c) = read_length(&data[cursor..])?;
             match index {
                 2 => max_deleted_ms = u64::try_from(_v).unwrap_or(0),
                 3 => max_deleted_seq = u64::try_from(_v).unwrap_or(0),
@@ -705,26 +720,21 @@ pub(crate) fn decode_upstream_stream_skeleton(
     }
 
     // (6) Number of consumer groups.
-    let (groups_count, c) =
-        rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+    let (groups_count, c) = read_length(&data[cursor..])?;
     cursor += c;
 
     // (7) For each group: name, last-delivered-id (ms,seq), entries_read (v2+),
     //     PEL count + entries, consumer count + per-consumer fields.
     let mut groups = Vec::with_capacity(groups_count.min(256));
     for _ in 0..groups_count {
-        let (name, c) =
-            rdb_decode_string(&data[cursor..]).ok_or(UpstreamStreamError::InvalidString)?;
+        let (name, c) = read_string(&data[cursor..])?;
         cursor += c;
-        let (last_delivered_id_ms, c) =
-            rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+        let (last_delivered_id_ms, c) = read_length(&data[cursor..])?;
         cursor += c;
-        let (last_delivered_id_seq, c) =
-            rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+        let (last_delivered_id_seq, c) = read_length(&data[cursor..])?;
         cursor += c;
         let entries_read = if is_v2_or_later {
-            let (v, c) =
-                rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+            let (v, c) = read_length(&data[cursor..])?;
             cursor += c;
             if v == usize::MAX {
                 None
@@ -734,8 +744,7 @@ pub(crate) fn decode_upstream_stream_skeleton(
         } else {
             None
         };
-        let (pel_count, c) =
-            rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+        let (pel_count, c) = read_length(&data[cursor..])?;
         cursor += c;
         let mut global_pel: BTreeMap<(u64, u64), (u64, u64)> = BTreeMap::new();
         for _ in 0..pel_count {
@@ -743,19 +752,16 @@ pub(crate) fn decode_upstream_stream_skeleton(
             cursor += c;
             let delivery_time_ms = take_millisecond_time(data, cursor)?;
             cursor += 8;
-            let (delivery_count, c) =
-                rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+            let (delivery_count, c) = read_length(&data[cursor..])?;
             cursor += c;
             global_pel.insert(entry_id, (delivery_time_ms, delivery_count as u64));
         }
-        let (consumers_count, c) =
-            rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+        let (consumers_count, c) = read_length(&data[cursor..])?;
         cursor += c;
         let mut consumers = Vec::with_capacity(consumers_count.min(256));
         let mut pending = Vec::with_capacity(pel_count.min(4096));
         for _ in 0..consumers_count {
-            let (consumer_name, c) =
-                rdb_decode_string(&data[cursor..]).ok_or(UpstreamStreamError::InvalidString)?;
+            let (consumer_name, c) = read_string(&data[cursor..])?;
             cursor += c;
             // seen_time (type 19+), then active_time (type 21+); both mstime_t.
             // An active_time of -1 is upstream's "never actively consumed"
@@ -776,8 +782,7 @@ pub(crate) fn decode_upstream_stream_skeleton(
                 seen_time_ms,
                 active_time_ms,
             });
-            let (cpel_count, c) =
-                rdb_decode_length(&data[cursor..]).ok_or(UpstreamStreamError::InvalidLength)?;
+            let (cpel_count, c) = read_length(&data[cursor..])?;
             cursor += c;
             for _ in 0..cpel_count {
                 let (entry_id, c) = take_raw_stream_id(data, cursor)?;
@@ -830,7 +835,7 @@ fn take_raw_stream_id(
     cursor: usize,
 ) -> Result<((u64, u64), usize), UpstreamStreamError> {
     if cursor + 16 > data.len() {
-        return Err(UpstreamStreamError::InvalidLength);
+        return Err(UpstreamStreamError::Truncated);
     }
     let id_ms = u64::from_be_bytes(
         data[cursor..cursor + 8]
@@ -847,7 +852,7 @@ fn take_raw_stream_id(
 
 fn take_millisecond_time(data: &[u8], cursor: usize) -> Result<u64, UpstreamStreamError> {
     if cursor + 8 > data.len() {
-        return Err(UpstreamStreamError::InvalidLength);
+        return Err(UpstreamStreamError::Truncated);
     }
     Ok(u64::from_le_bytes(
         data[cursor..cursor + 8]
@@ -0,0 +1,93 @@
+//! Minimal HTTP -> RESP bridge built on `resp_to_json` / `json_to_argv`.
+//!
+//! POST a JSON command array and get the server's reply back as JSON:
+//!
+//!   cargo run -p fr-protocol --features json --example http_json_bridge -- 127.0.0.1:8080 127.0.0.1:6379
+//!   curl -s -d '["SET","k","v"]' http://127.0.0.1:8080/   # => "OK"
+//!   curl -s -d '["GET","k"]' http://127.0.0.1:8080/       # => "v"
+//!
+//! Deliberately std-only (one request per connection, no keep-alive, no TLS):
+//! it is a debugging aid, not a production gateway.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use fr_protocol::{RespFrame, RespParseError, json_to_argv, parse_frame, resp_to_json};
+use serde_json::{Value, json};
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let listen = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let upstream = args.next().unwrap_or_else(|| "127.0.0.1:6379".to_string());
+
+    let listener = TcpListener::bind(&listen)?;
+    println!("bridging http://{listen}/ -> redis://{upstream}");
+    for conn in listener.incoming() {
+        let mut conn = conn?;
+        let (status, body) = match handle(&mut conn, &upstream) {
+            Ok(reply) => ("200 OK", reply),
+            Err(message) => ("400 Bad Request", json!({ "error": message })),
+        };
+        let body = body.to_string();
+        let _ = write!(
+            conn,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+    }
+    Ok(())
+}
+
+fn handle(conn: &mut TcpStream, upstream: &str) -> Result<Value, String> {
+    let request_body = read_http_body(conn).map_err(|err| err.to_string())?;
+    let command: Value =
+        serde_json::from_slice(&request_body).map_err(|err| format!("invalid JSON: {err}"))?;
+    let argv = json_to_argv(&command).map_err(|err| err.to_string())?;
+    let reply = round_trip(upstream, &argv).map_err(|err| format!("upstream: {err}"))?;
+    Ok(resp_to_json(&reply))
+}
+
+fn read_http_body(conn: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut reader = BufReader::new(conn);
+    let mut content_length = 0usize;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+fn round_trip(upstream: &str, argv: &[Vec<u8>]) -> std::io::Result<RespFrame> {
+    let mut stream = TcpStream::connect(upstream)?;
+    let request = RespFrame::Array(Some(
+        argv.iter()
+            .map(|arg| RespFrame::BulkString(Some(arg.clone())))
+            .collect(),
+    ));
+    stream.write_all(&request.to_bytes())?;
+
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        match parse_frame(&buf) {
+            Ok(parsed) => return Ok(parsed.frame),
+            Err(RespParseError::Incomplete) => {}
+            Err(err) => return Err(std::io::Error::other(err.to_string())),
+        }
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
@@ -0,0 +1,271 @@
+//! Checked construction of reply frames.
+//!
+//! `RespFrame`'s variants are public, so nothing stops a caller from building
+//! a frame the encoder can only emit as bad wire bytes (a CR/LF inside a
+//! simple string, a bulk string over `proto-max-bulk-len`) or that a parser
+//! with the default [`ParserConfig`](crate::ParserConfig) would refuse. The
+//! constructors here fail instead, and [`RespFrame::validate`] runs the same
+//! checks over a frame built by hand or deserialized from a fixture.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use crate::RespFrame;
+
+/// Largest bulk string payload, upstream's default `proto-max-bulk-len`.
+pub const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+/// Deepest aggregate nesting the default parser accepts; the outermost frame
+/// is depth 0.
+pub const MAX_NESTING_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// A bulk string payload of this many bytes exceeds [`MAX_BULK_LEN`].
+    BulkTooLarge(usize),
+    /// A simple string or error message contains CR or LF.
+    LineBreak,
+    /// Aggregates are nested deeper than [`MAX_NESTING_DEPTH`].
+    TooDeep,
+    /// A double is not a number, `inf`, `-inf` or `nan`.
+    InvalidDouble(String),
+    /// A big number is not an optionally signed run of digits.
+    InvalidBigNumber(String),
+    /// An error reply used as a map or attribute key, or as a set or push
+    /// element. Errors only ever nest as array elements (EXEC, scripts) and
+    /// map values.
+    MisplacedError,
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BulkTooLarge(len) => {
+                write!(f, "bulk string of {len} bytes exceeds {MAX_BULK_LEN}")
+            }
+            Self::LineBreak => write!(f, "simple string or error contains CR or LF"),
+            Self::TooDeep => write!(f, "frame nests deeper than {MAX_NESTING_DEPTH} levels"),
+            Self::InvalidDouble(text) => write!(f, "invalid double: {text:?}"),
+            Self::InvalidBigNumber(text) => write!(f, "invalid big number: {text:?}"),
+            Self::MisplacedError => {
+                write!(f, "error frame used as a map key or set/push element")
+            }
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+impl RespFrame {
+    /// A bulk string, refusing payloads over [`MAX_BULK_LEN`].
+    pub fn bulk(bytes: impl Into<Vec<u8>>) -> Result<Self, FrameError> {
+        let bytes = bytes.into();
+        check_bulk_len(bytes.len())?;
+        Ok(Self::BulkString(Some(bytes)))
+    }
+
+    /// A simple string, refusing CR and LF.
+    pub fn simple(text: impl Into<String>) -> Result<Self, FrameError> {
+        let text = text.into();
+        check_inline(&text)?;
+        Ok(Self::SimpleString(text))
+    }
+
+    /// An error reply, refusing CR and LF.
+    pub fn error(message: impl Into<String>) -> Result<Self, FrameError> {
+        let message = message.into();
+        check_inline(&message)?;
+        Ok(Self::Error(message))
+    }
+
+    /// An array of `items`, each of which must itself be valid.
+    pub fn array(items: Vec<RespFrame>) -> Result<Self, FrameError> {
+        let frame = Self::Array(Some(items));
+        frame.validate()?;
+        Ok(frame)
+    }
+
+    /// Check the whole frame tree against the rules the constructors enforce.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        self.validate_at(0)
+    }
+
+    fn validate_at(&self, depth: usize) -> Result<(), FrameError> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(FrameError::TooDeep);
+        }
+        let child = depth + 1;
+        match self {
+            Self::SimpleString(text) | Self::Error(text) => check_inline(text),
+            Self::StaticSimple(text) => check_inline(text),
+            Self::BulkString(Some(bytes)) => check_bulk_len(bytes.len()),
+            Self::Verbatim(text) => check_bulk_len(text.len()),
+            Self::Double(text) => check_double(text),
+            Self::BigNumber(text) => check_big_number(text),
+            Self::Integer(_)
+            | Self::Bool(_)
+            | Self::BulkString(None)
+            | Self::Array(None)
+            | Self::Map(None)
+            | Self::Set(None) => Ok(()),
+            Self::Array(Some(items)) | Self::Sequence(items) => {
+                items.iter().try_for_each(|item| item.validate_at(child))
+            }
+            Self::Set(Some(items)) | Self::Push(items) => items.iter().try_for_each(|item| {
+                reject_error(item)?;
+                item.validate_at(child)
+            }),
+            Self::Map(Some(pairs)) | Self::Attribute(pairs) => {
+                pairs.iter().try_for_each(|(key, value)| {
+                    reject_error(key)?;
+                    key.validate_at(child)?;
+                    value.validate_at(child)
+                })
+            }
+        }
+    }
+}
+
+fn check_bulk_len(len: usize) -> Result<(), FrameError> {
+    if len > MAX_BULK_LEN {
+        return Err(FrameError::BulkTooLarge(len));
+    }
+    Ok(())
+}
+
+fn check_inline(text: &str) -> Result<(), FrameError> {
+    if text.contains(['\r', '\n']) {
+        return Err(FrameError::LineBreak);
+    }
+    Ok(())
+}
+
+fn check_double(text: &str) -> Result<(), FrameError> {
+    let known =
+        matches!(text, "inf" | "-inf" | "nan") || text.parse::<f64>().is_ok_and(f64::is_finite);
+    if !known {
+        return Err(FrameError::InvalidDouble(text.to_string()));
+    }
+    Ok(())
+}
+
+fn check_big_number(text: &str) -> Result<(), FrameError> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(FrameError::InvalidBigNumber(text.to_string()));
+    }
+    Ok(())
+}
+
+fn reject_error(frame: &RespFrame) -> Result<(), FrameError> {
+    if matches!(frame, RespFrame::Error(_)) {
+        return Err(FrameError::MisplacedError);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameError, MAX_BULK_LEN, MAX_NESTING_DEPTH};
+    use crate::{ParserConfig, RespFrame, parse_frame};
+
+    fn nested(depth: usize) -> RespFrame {
+        (0..depth).fold(RespFrame::Integer(1), |inner, _| {
+            RespFrame::Array(Some(vec![inner]))
+        })
+    }
+
+    #[test]
+    fn constructors_accept_what_the_parser_reads_back() {
+        let frame = RespFrame::array(vec![
+            RespFrame::simple("OK").unwrap(),
+            RespFrame::bulk(&b"\r\nbinary\0"[..]).unwrap(),
+            RespFrame::error("ERR nested in EXEC").unwrap(),
+            RespFrame::Integer(7),
+        ])
+        .unwrap();
+        let parsed = parse_frame(&frame.to_bytes()).unwrap();
+        assert_eq!(parsed.frame, frame);
+    }
+
+    #[test]
+    fn inline_frames_refuse_line_breaks() {
+        for text in ["a\rb", "a\nb", "OK\r\n"] {
+            assert_eq!(RespFrame::simple(text), Err(FrameError::LineBreak));
+            assert_eq!(RespFrame::error(text), Err(FrameError::LineBreak));
+        }
+        let smuggled = RespFrame::Array(Some(vec![RespFrame::SimpleString("a\r\n+b".into())]));
+        assert_eq!(smuggled.validate(), Err(FrameError::LineBreak));
+        assert_eq!(
+            RespFrame::array(vec![RespFrame::Error("x\ny".into())]),
+            Err(FrameError::LineBreak)
+        );
+    }
+
+    #[test]
+    fn bulk_strings_stop_at_the_protocol_cap() {
+        assert_eq!(
+            RespFrame::bulk(vec![0_u8; MAX_BULK_LEN + 1]),
+            Err(FrameError::BulkTooLarge(MAX_BULK_LEN + 1))
+        );
+        assert!(RespFrame::bulk(Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn nesting_stops_where_the_default_parser_does() {
+        assert_eq!(nested(MAX_NESTING_DEPTH).validate(), Ok(()));
+        assert_eq!(
+            nested(MAX_NESTING_DEPTH + 1).validate(),
+            Err(FrameError::TooDeep)
+        );
+
+        let max_recursion_depth = ParserConfig::default().max_recursion_depth;
+        assert_eq!(max_recursion_depth, MAX_NESTING_DEPTH);
+        assert!(parse_frame(&nested(MAX_NESTING_DEPTH).to_bytes()).is_ok());
+        assert!(parse_frame(&nested(MAX_NESTING_DEPTH + 1).to_bytes()).is_err());
+    }
+
+    #[test]
+    fn errors_only_nest_where_redis_puts_them() {
+        let error = || RespFrame::Error("ERR x".into());
+        let one = || RespFrame::Integer(1);
+        assert_eq!(
+            RespFrame::Map(Some(vec![(one(), error())])).validate(),
+            Ok(())
+        );
+        for misplaced in [
+            RespFrame::Map(Some(vec![(error(), one())])),
+            RespFrame::Attribute(vec![(error(), one())]),
+            RespFrame::Set(Some(vec![error()])),
+            RespFrame::Push(vec![error()]),
+        ] {
+            assert_eq!(misplaced.validate(), Err(FrameError::MisplacedError));
+        }
+    }
+
+    #[test]
+    fn resp3_scalars_carry_well_formed_text() {
+        for text in ["1.5", "-0", "1e-7", "inf", "-inf", "nan"] {
+            assert_eq!(RespFrame::Double(text.into()).validate(), Ok(()), "{text}");
+        }
+        for text in ["", "1.5x", "Infinity", "1,5"] {
+            assert_eq!(
+                RespFrame::Double(text.into()).validate(),
+                Err(FrameError::InvalidDouble(text.into()))
+            );
+        }
+        for text in ["0", "-12345678901234567890123", "+7"] {
+            assert_eq!(
+                RespFrame::BigNumber(text.into()).validate(),
+                Ok(()),
+                "{text}"
+            );
+        }
+        for text in ["", "-", "1.0", "12a"] {
+            assert_eq!(
+                RespFrame::BigNumber(text.into()).validate(),
+                Err(FrameError::InvalidBigNumber(text.into()))
+            );
+        }
+        assert_eq!(RespFrame::double_from_f64(0.1).validate(), Ok(()));
+    }
+}
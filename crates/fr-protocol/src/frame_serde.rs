@@ -0,0 +1,126 @@
+//! serde support for `RespFrame`, behind the `serde` feature.
+//!
+//! Frames use an adjacently tagged representation, `{"kind": "bulk_string",
+//! "value": "hello"}`, so fixtures stay readable as JSON or YAML. Bulk string
+//! payloads are written as text when they are UTF-8 and as an array of bytes
+//! otherwise; both forms are accepted when reading. Deserializing does not
+//! validate: call [`RespFrame::validate`](crate::RespFrame::validate) on
+//! frames loaded from a file.
+
+pub(crate) mod bulk {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Payload {
+        Text(String),
+        Bytes(Vec<u8>),
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        value: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            None => serializer.serialize_none(),
+            Some(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => serializer.serialize_some(text),
+                Err(_) => serializer.serialize_some(bytes),
+            },
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Ok(
+            Option::<Payload>::deserialize(deserializer)?.map(|payload| match payload {
+                Payload::Text(text) => text.into_bytes(),
+                Payload::Bytes(bytes) => bytes,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::RespFrame;
+
+    fn round_trip(frame: &RespFrame) -> RespFrame {
+        let text = serde_json::to_string(frame).expect("serialize");
+        serde_json::from_str(&text).expect("deserialize")
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let bulk = |bytes: &[u8]| RespFrame::BulkString(Some(bytes.to_vec()));
+        let frames = [
+            RespFrame::SimpleString("OK".into()),
+            RespFrame::Error("ERR boom".into()),
+            RespFrame::Integer(i64::MIN),
+            bulk(b"hello"),
+            bulk(b"\xff\x00binary"),
+            bulk(b""),
+            RespFrame::BulkString(None),
+            RespFrame::Array(None),
+            RespFrame::Array(Some(vec![bulk(b"a"), RespFrame::Array(Some(Vec::new()))])),
+            RespFrame::Map(None),
+            RespFrame::Map(Some(vec![(bulk(b"field"), RespFrame::Integer(1))])),
+            RespFrame::Push(vec![bulk(b"message"), bulk(b"chan"), bulk(b"hi")]),
+            RespFrame::Sequence(vec![
+                RespFrame::Attribute(vec![(bulk(b"ttl"), RespFrame::Integer(3))]),
+                bulk(b"value"),
+            ]),
+            RespFrame::Double("-inf".into()),
+            RespFrame::Set(None),
+            RespFrame::Set(Some(vec![bulk(b"m")])),
+            RespFrame::Verbatim("# Server".into()),
+            RespFrame::BigNumber("12345678901234567890123".into()),
+            RespFrame::Bool(false),
+        ];
+        for frame in frames {
+            assert_eq!(round_trip(&frame), frame);
+        }
+    }
+
+    #[test]
+    fn representation_is_tagged_and_readable() {
+        let frame = RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"hi".to_vec())),
+            RespFrame::BulkString(Some(vec![0xff])),
+            RespFrame::BulkString(None),
+            RespFrame::Integer(3),
+        ]));
+        let expected = json!({"kind": "array", "value": [
+            {"kind": "bulk_string", "value": "hi"},
+            {"kind": "bulk_string", "value": [255]},
+            {"kind": "bulk_string", "value": null},
+            {"kind": "integer", "value": 3},
+        ]});
+        assert_eq!(serde_json::to_value(&frame).expect("serialize"), expected);
+        assert_eq!(
+            serde_json::from_value::<RespFrame>(expected).expect("deserialize"),
+            frame
+        );
+    }
+
+    #[test]
+    fn static_simple_strings_serialize_as_simple_strings() {
+        let value = serde_json::to_value(RespFrame::StaticSimple("PONG")).expect("serialize");
+        assert_eq!(value, json!({"kind": "simple_string", "value": "PONG"}));
+        assert!(matches!(
+            serde_json::from_value::<RespFrame>(value).expect("deserialize"),
+            RespFrame::SimpleString(text) if text == "PONG"
+        ));
+    }
+
+    #[test]
+    fn loaded_frames_are_validated_separately() {
+        let loaded: RespFrame =
+            serde_json::from_value(json!({"kind": "simple_string", "value": "a\r\nb"}))
+                .expect("deserialize");
+        assert_eq!(loaded.validate(), Err(crate::FrameError::LineBreak));
+    }
+}
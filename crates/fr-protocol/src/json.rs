@@ -0,0 +1,280 @@
+//! `RespFrame` <-> `serde_json` conversion for debugging tools and HTTP bridges.
+//!
+//! The mapping is lossy by design: it aims at something a human (or a JSON
+//! client) can read, not at a second wire format. Bulk strings that are not
+//! valid UTF-8 are carried as `{"base64": "..."}` so binary payloads survive
+//! the trip, and `json_to_argv` accepts the same marker on the way back in.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Map, Number, Value};
+
+use crate::RespFrame;
+
+/// Object key marking a base64-encoded binary bulk string.
+pub const JSON_BASE64_KEY: &str = "base64";
+/// Object key carrying the message of an error reply.
+pub const JSON_ERROR_KEY: &str = "error";
+/// Object key carrying the key/value pairs of a RESP3 attribute.
+pub const JSON_ATTRIBUTE_KEY: &str = "attribute";
+
+/// Convert a reply frame into a JSON value.
+///
+/// - simple/bulk/verbatim strings become strings (binary bulk strings become
+///   `{"base64": "..."}`), big numbers stay strings to keep their precision;
+/// - integers become numbers, finite doubles become numbers and `inf`/`-inf`/
+///   `nan` stay strings;
+/// - errors become `{"error": "..."}`, every null flavour becomes `null`;
+/// - arrays, sets, pushes and sequences become arrays;
+/// - maps become objects when every key is a string, otherwise an array of
+///   `[key, value]` pairs so no entry is silently merged.
+pub fn resp_to_json(frame: &RespFrame) -> Value {
+    match frame {
+        RespFrame::SimpleString(s) | RespFrame::Verbatim(s) | RespFrame::BigNumber(s) => {
+            Value::String(s.clone())
+        }
+        RespFrame::StaticSimple(s) => Value::String((*s).to_string()),
+        RespFrame::Error(msg) => single_key_object(JSON_ERROR_KEY, Value::String(msg.clone())),
+        RespFrame::Integer(n) => Value::Number(Number::from(*n)),
+        RespFrame::BulkString(Some(bytes)) => bulk_to_json(bytes),
+        RespFrame::BulkString(None)
+        | RespFrame::Array(None)
+        | RespFrame::Map(None)
+        | RespFrame::Set(None) => Value::Null,
+        RespFrame::Array(Some(items))
+        | RespFrame::Set(Some(items))
+        | RespFrame::Push(items)
+        | RespFrame::Sequence(items) => Value::Array(items.iter().map(resp_to_json).collect()),
+        RespFrame::Map(Some(pairs)) => pairs_to_json(pairs),
+        RespFrame::Attribute(pairs) => single_key_object(JSON_ATTRIBUTE_KEY, pairs_to_json(pairs)),
+        RespFrame::Double(text) => text
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map_or_else(|| Value::String(text.clone()), Value::Number),
+        RespFrame::Bool(b) => Value::Bool(*b),
+    }
+}
+
+fn bulk_to_json(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => single_key_object(JSON_BASE64_KEY, Value::String(BASE64.encode(bytes))),
+    }
+}
+
+fn pairs_to_json(pairs: &[(RespFrame, RespFrame)]) -> Value {
+    let mut object = Map::with_capacity(pairs.len());
+    for (key, value) in pairs {
+        // A non-string key cannot be an object key, and a duplicate key would
+        // collapse; either way fall back to `[key, value]` pairs.
+        let Some(key) = frame_string_key(key) else {
+            return pairs_to_json_array(pairs);
+        };
+        if object.insert(key, resp_to_json(value)).is_some() {
+            return pairs_to_json_array(pairs);
+        }
+    }
+    Value::Object(object)
+}
+
+fn pairs_to_json_array(pairs: &[(RespFrame, RespFrame)]) -> Value {
+    Value::Array(
+        pairs
+            .iter()
+            .map(|(key, value)| Value::Array(vec![resp_to_json(key), resp_to_json(value)]))
+            .collect(),
+    )
+}
+
+fn frame_string_key(frame: &RespFrame) -> Option<String> {
+    match frame {
+        RespFrame::SimpleString(s) | RespFrame::Verbatim(s) => Some(s.clone()),
+        RespFrame::StaticSimple(s) => Some((*s).to_string()),
+        RespFrame::BulkString(Some(bytes)) => std::str::from_utf8(bytes).ok().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn single_key_object(key: &str, value: Value) -> Value {
+    let mut object = Map::with_capacity(1);
+    object.insert(key.to_string(), value);
+    Value::Object(object)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonArgvError {
+    /// The top-level value was not a JSON array.
+    NotAnArray,
+    /// The command array was empty.
+    EmptyCommand,
+    /// The argument at this index cannot be turned into bytes.
+    InvalidArgument(usize),
+    /// The argument at this index carried a malformed base64 payload.
+    InvalidBase64(usize),
+}
+
+impl Display for JsonArgvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnArray => write!(f, "command must be a JSON array"),
+            Self::EmptyCommand => write!(f, "command array is empty"),
+            Self::InvalidArgument(idx) => write!(
+                f,
+                "argument {idx} must be a string, number, boolean or {{\"{JSON_BASE64_KEY}\": ...}}"
+            ),
+            Self::InvalidBase64(idx) => write!(f, "argument {idx} has invalid base64"),
+        }
+    }
+}
+
+impl Error for JsonArgvError {}
+
+/// Convert a JSON command such as `["SET", "k", 1]` into an argv.
+///
+/// Strings are taken as UTF-8 bytes, numbers use their JSON text, booleans
+/// become `1`/`0`, and `{"base64": "..."}` decodes to raw bytes (the same
+/// marker `resp_to_json` emits for binary bulk strings). Nulls, nested
+/// arrays and other objects are rejected rather than guessed at.
+pub fn json_to_argv(value: &Value) -> Result<Vec<Vec<u8>>, JsonArgvError> {
+    let Value::Array(items) = value else {
+        return Err(JsonArgvError::NotAnArray);
+    };
+    if items.is_empty() {
+        return Err(JsonArgvError::EmptyCommand);
+    }
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| json_arg_to_bytes(idx, item))
+        .collect()
+}
+
+fn json_arg_to_bytes(idx: usize, item: &Value) -> Result<Vec<u8>, JsonArgvError> {
+    match item {
+        Value::String(s) => Ok(s.as_bytes().to_vec()),
+        Value::Number(n) => Ok(n.to_string().into_bytes()),
+        Value::Bool(b) => Ok(if *b { b"1".to_vec() } else { b"0".to_vec() }),
+        Value::Object(object) if object.len() == 1 => match object.get(JSON_BASE64_KEY) {
+            Some(Value::String(encoded)) => BASE64
+                .decode(encoded)
+                .map_err(|_| JsonArgvError::InvalidBase64(idx)),
+            _ => Err(JsonArgvError::InvalidArgument(idx)),
+        },
+        _ => Err(JsonArgvError::InvalidArgument(idx)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{JsonArgvError, json_to_argv, resp_to_json};
+    use crate::RespFrame;
+
+    fn bulk(bytes: &[u8]) -> RespFrame {
+        RespFrame::BulkString(Some(bytes.to_vec()))
+    }
+
+    #[test]
+    fn scalars_map_to_natural_json() {
+        assert_eq!(
+            resp_to_json(&RespFrame::SimpleString("OK".into())),
+            json!("OK")
+        );
+        assert_eq!(resp_to_json(&RespFrame::Integer(-7)), json!(-7));
+        assert_eq!(resp_to_json(&bulk(b"hello")), json!("hello"));
+        assert_eq!(
+            resp_to_json(&RespFrame::Error("ERR boom".into())),
+            json!({"error": "ERR boom"})
+        );
+        assert_eq!(resp_to_json(&RespFrame::Bool(true)), json!(true));
+        assert_eq!(resp_to_json(&RespFrame::Double("1.5".into())), json!(1.5));
+        assert_eq!(resp_to_json(&RespFrame::Double("inf".into())), json!("inf"));
+        assert_eq!(
+            resp_to_json(&RespFrame::BigNumber("12345678901234567890123".into())),
+            json!("12345678901234567890123")
+        );
+        for null in [
+            RespFrame::BulkString(None),
+            RespFrame::Array(None),
+            RespFrame::Map(None),
+            RespFrame::Set(None),
+        ] {
+            assert_eq!(resp_to_json(&null), json!(null));
+        }
+    }
+
+    #[test]
+    fn binary_bulk_uses_base64_marker() {
+        assert_eq!(
+            resp_to_json(&bulk(&[0xff, 0x00, 0x01])),
+            json!({"base64": "/wAB"})
+        );
+    }
+
+    #[test]
+    fn aggregates_map_to_arrays_and_objects() {
+        let array = RespFrame::Array(Some(vec![bulk(b"a"), RespFrame::Integer(1), bulk(b"\xff")]));
+        assert_eq!(resp_to_json(&array), json!(["a", 1, {"base64": "/w=="}]));
+
+        let set = RespFrame::Set(Some(vec![bulk(b"x")]));
+        assert_eq!(resp_to_json(&set), json!(["x"]));
+
+        let map = RespFrame::Map(Some(vec![
+            (bulk(b"server"), bulk(b"frankenredis")),
+            (
+                RespFrame::SimpleString("proto".into()),
+                RespFrame::Integer(3),
+            ),
+        ]));
+        assert_eq!(
+            resp_to_json(&map),
+            json!({"server": "frankenredis", "proto": 3})
+        );
+
+        // Non-string keys cannot be object keys; the pair form keeps them.
+        let int_keys = RespFrame::Map(Some(vec![(RespFrame::Integer(1), bulk(b"one"))]));
+        assert_eq!(resp_to_json(&int_keys), json!([[1, "one"]]));
+    }
+
+    #[test]
+    fn argv_round_trips_through_json() {
+        let argv: Vec<Vec<u8>> = vec![b"SET".to_vec(), b"k".to_vec(), vec![0xde, 0xad, 0xbe]];
+        let as_json = resp_to_json(&RespFrame::Array(Some(
+            argv.iter().map(|arg| bulk(arg)).collect(),
+        )));
+        assert_eq!(json_to_argv(&as_json), Ok(argv));
+    }
+
+    #[test]
+    fn json_to_argv_accepts_scalars_and_rejects_nesting() {
+        assert_eq!(
+            json_to_argv(&json!(["INCRBY", "n", 5, true, 2.5])),
+            Ok(vec![
+                b"INCRBY".to_vec(),
+                b"n".to_vec(),
+                b"5".to_vec(),
+                b"1".to_vec(),
+                b"2.5".to_vec(),
+            ])
+        );
+        assert_eq!(json_to_argv(&json!("PING")), Err(JsonArgvError::NotAnArray));
+        assert_eq!(json_to_argv(&json!([])), Err(JsonArgvError::EmptyCommand));
+        assert_eq!(
+            json_to_argv(&json!(["GET", null])),
+            Err(JsonArgvError::InvalidArgument(1))
+        );
+        assert_eq!(
+            json_to_argv(&json!(["GET", ["k"]])),
+            Err(JsonArgvError::InvalidArgument(1))
+        );
+        assert_eq!(
+            json_to_argv(&json!(["GET", {"base64": "***"}])),
+            Err(JsonArgvError::InvalidBase64(1))
+        );
+    }
+}
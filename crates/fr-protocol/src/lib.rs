@@ -3,12 +3,37 @@
 use std::error::Error;
 use std::fmt::{self, Display};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+mod builder;
+pub use builder::{FrameError, MAX_BULK_LEN, MAX_NESTING_DEPTH};
+#[cfg(feature = "serde")]
+mod frame_serde;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "json")]
+pub use json::{JsonArgvError, json_to_argv, resp_to_json};
+
+/// `RespFrame::StaticSimple`'s text, spelled through an alias so serde's
+/// derive does not read the field as borrowed from the deserializer input.
+type StaticText = &'static str;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+#[non_exhaustive]
 pub enum RespFrame {
     SimpleString(String),
+    /// A simple string with static text (`+PONG`, `+OK`), so hot replies are
+    /// built without a heap allocation. Encodes, serializes and compares
+    /// exactly like the `SimpleString` with the same text; the parser never
+    /// produces it.
+    #[cfg_attr(feature = "serde", serde(rename = "simple_string", skip_deserializing))]
+    StaticSimple(StaticText),
     Error(String),
     Integer(i64),
-    BulkString(Option<Vec<u8>>),
+    BulkString(#[cfg_attr(feature = "serde", serde(with = "frame_serde::bulk"))] Option<Vec<u8>>),
     Array(Option<Vec<RespFrame>>),
     Map(Option<Vec<(RespFrame, RespFrame)>>),
     Push(Vec<RespFrame>),
@@ -36,6 +61,80 @@ pub enum RespFrame {
     Attribute(Vec<(RespFrame, RespFrame)>),
 }
 
+// Written out rather than derived so a `StaticSimple` equals the
+// `SimpleString` with the same text: a reply compares the same whichever form
+// the handler built.
+impl PartialEq for RespFrame {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::SimpleString(a), Self::SimpleString(b)) => a == b,
+            (Self::StaticSimple(a), Self::StaticSimple(b)) => a == b,
+            (Self::SimpleString(owned), Self::StaticSimple(text))
+            | (Self::StaticSimple(text), Self::SimpleString(owned)) => owned == text,
+            (Self::Error(a), Self::Error(b))
+            | (Self::Double(a), Self::Double(b))
+            | (Self::Verbatim(a), Self::Verbatim(b))
+            | (Self::BigNumber(a), Self::BigNumber(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::BulkString(a), Self::BulkString(b)) => a == b,
+            (Self::Array(a), Self::Array(b)) | (Self::Set(a), Self::Set(b)) => a == b,
+            (Self::Map(a), Self::Map(b)) => a == b,
+            (Self::Push(a), Self::Push(b)) | (Self::Sequence(a), Self::Sequence(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Attribute(a), Self::Attribute(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RespFrame {}
+
+/// Error classes the server puts at the front of an error reply.
+const RESP_ERROR_CODES: &[&str] = &[
+    "ERR",
+    "WRONGTYPE",
+    "NOGROUP",
+    "BUSYGROUP",
+    "NOSCRIPT",
+    "OOM",
+    "EXECABORT",
+    "READONLY",
+    "NOPERM",
+    "NOAUTH",
+    "WRONGPASS",
+    "NOPROTO",
+    "BUSY",
+    "BUSYKEY",
+    "NOTBUSY",
+    "UNKILLABLE",
+    "UNBLOCKED",
+    "LOADING",
+    "MASTERDOWN",
+    "NOMASTERLINK",
+    "MISCONF",
+    "NOREPLICAS",
+    "INVALIDOBJ",
+    "IOERR",
+    "MOVED",
+    "ASK",
+    "TRYAGAIN",
+    "CROSSSLOT",
+    "CLUSTERDOWN",
+];
+
+/// The error class of an error-reply body: its first word when that is one
+/// of the codes the server emits, `ERR` otherwise. This is the prefix
+/// clients branch on (`-WRONGTYPE ...`, `-NOGROUP ...`).
+#[must_use]
+pub fn resp_error_code(message: &str) -> &'static str {
+    let first = message.split(' ').next().unwrap_or_default();
+    RESP_ERROR_CODES
+        .iter()
+        .find(|code| **code == first)
+        .copied()
+        .unwrap_or("ERR")
+}
+
 /// Sanitize bytes destined for an inline RESP frame body (`SimpleString`
 /// or `Error`). RESP inline frames are terminated by the first `\r\n`,
 /// so any embedded `\r` or `\n` in the payload would split the frame
@@ -469,6 +568,38 @@ fn write_i64_to_slice(n: i64, buf: &mut [u8]) -> usize {
     i + digits.len()
 }
 
+/// Wire bytes of the replies the hot paths send most often. Fast paths that
+/// encode straight into the output buffer write these instead of formatting,
+/// and the frame encoder emits them for the matching `StaticSimple` texts.
+pub const REPLY_OK: &[u8] = b"+OK\r\n";
+pub const REPLY_PONG: &[u8] = b"+PONG\r\n";
+pub const REPLY_ZERO: &[u8] = b":0\r\n";
+pub const REPLY_ONE: &[u8] = b":1\r\n";
+pub const REPLY_NULL_BULK: &[u8] = b"$-1\r\n";
+pub const REPLY_NULL_ARRAY: &[u8] = b"*-1\r\n";
+
+/// The pre-encoded `:0` / `:1` reply for a boolean-integer answer (EXISTS on
+/// one key, SISMEMBER, HEXISTS, SETNX, ...).
+#[must_use]
+pub const fn bool_integer_reply(value: bool) -> &'static [u8] {
+    if value { REPLY_ONE } else { REPLY_ZERO }
+}
+
+/// Encode a `StaticSimple` frame: the well-known texts copy their
+/// pre-encoded bytes, anything else is framed like a `SimpleString`.
+#[inline]
+fn encode_static_simple(text: &str, out: &mut Vec<u8>) {
+    match text {
+        "OK" => out.extend_from_slice(REPLY_OK),
+        "PONG" => out.extend_from_slice(REPLY_PONG),
+        _ => {
+            out.extend_from_slice(b"+");
+            push_inline_sanitized(out, text.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+}
+
 /// Frame a RESP integer reply (`:<n>\r\n`) into `out`. `FUSED == true` (production) builds the
 /// `:` prefix, digits, and `\r\n` terminator in one stack buffer and appends them with a SINGLE
 /// `extend_from_slice` — one capacity check + one memcpy — on the universal counter/length reply
@@ -762,6 +893,16 @@ pub fn bench_push_inline_sanitized_reference(out: &mut Vec<u8>, body: &[u8]) {
 }
 
 impl RespFrame {
+    /// The text of a simple-string reply, whether owned or `StaticSimple`.
+    #[must_use]
+    pub fn as_simple_str(&self) -> Option<&str> {
+        match self {
+            Self::SimpleString(text) => Some(text),
+            Self::StaticSimple(text) => Some(text),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut out = Vec::with_capacity(self.encoded_len_hint().unwrap_or(0));
@@ -774,6 +915,7 @@ impl RespFrame {
             Self::SimpleString(s) | Self::Error(s) | Self::Double(s) | Self::BigNumber(s) => {
                 1usize.checked_add(s.len())?.checked_add(2)
             }
+            Self::StaticSimple(s) => 1usize.checked_add(s.len())?.checked_add(2),
             Self::Integer(n) => 1usize.checked_add(decimal_i64_len(*n))?.checked_add(2),
             // `#t\r\n` / `#f\r\n` and the RESP2 `:1\r\n` / `:0\r\n` downgrade
             // are all 4 bytes.
@@ -842,13 +984,15 @@ impl RespFrame {
                 push_inline_sanitized(out, s.as_bytes());
                 out.extend_from_slice(b"\r\n");
             }
+            Self::StaticSimple(s) => encode_static_simple(s, out),
             Self::Error(s) => {
                 out.extend_from_slice(b"-");
                 push_inline_sanitized(out, s.as_bytes());
                 out.extend_from_slice(b"\r\n");
             }
+            Self::Integer(n @ (0 | 1)) => out.extend_from_slice(bool_integer_reply(*n == 1)),
             Self::Integer(n) => encode_integer_reply::<true>(*n, out),
-            Self::BulkString(None) => out.extend_from_slice(b"$-1\r\n"),
+            Self::BulkString(None) => out.extend_from_slice(REPLY_NULL_BULK),
             Self::BulkString(Some(bytes)) => {
                 out.extend_from_slice(b"$");
                 push_usize(out, bytes.len());
@@ -856,7 +1000,7 @@ impl RespFrame {
                 out.extend_from_slice(bytes);
                 out.extend_from_slice(b"\r\n");
             }
-            Self::Array(None) => out.extend_from_slice(b"*-1\r\n"),
+            Self::Array(None) => out.extend_from_slice(REPLY_NULL_ARRAY),
             Self::Array(Some(frames)) => {
                 out.extend_from_slice(b"*");
                 push_usize(out, frames.len());
@@ -940,6 +1084,12 @@ impl RespFrame {
     /// (Double, Verbatim, populated Set/Map) encode identically to
     /// [`encode_into`], so this only diverges on the null leaves.
     ///
+    /// Errors stay `-` simple errors with CR/LF mapped to spaces, exactly as
+    /// under RESP2: redis 7.2 never emits `!` blob errors (`addReplyErrorLength`
+    /// sanitizes in both protocols), so a client-supplied key like `a\r\nb`
+    /// echoed into an error cannot break framing and replies stay
+    /// byte-identical to upstream.
+    ///
     /// [`encode_into`]: Self::encode_into
     #[cfg_attr(feature = "bench-reference", inline(never))]
     pub fn encode_into_resp3(&self, out: &mut Vec<u8>) {
@@ -999,6 +1149,7 @@ impl RespFrame {
                     frame.encode_into_resp3_impl::<DIRECT_SCALARS>(out);
                 }
             }
+            Self::StaticSimple(s) if DIRECT_SCALARS => encode_static_simple(s, out),
             Self::SimpleString(s) if DIRECT_SCALARS => {
                 out.extend_from_slice(b"+");
                 push_inline_sanitized(out, s.as_bytes());
@@ -1043,6 +1194,34 @@ impl RespFrame {
         }
     }
 
+    /// Separate the attributes a `keep_attributes` parse attached to a reply
+    /// from the reply itself. A frame without attributes comes back as-is
+    /// with an empty list.
+    #[must_use]
+    pub fn split_attributes(self) -> (Vec<(RespFrame, RespFrame)>, RespFrame) {
+        let Self::Sequence(mut frames) = self else {
+            return (Vec::new(), self);
+        };
+        let annotated = frames.len() >= 2
+            && frames[..frames.len() - 1]
+                .iter()
+                .all(|frame| matches!(frame, Self::Attribute(_)));
+        if !annotated {
+            return (Vec::new(), Self::Sequence(frames));
+        }
+        let reply = frames
+            .pop()
+            .expect("an annotated sequence ends in its reply");
+        let attributes = frames
+            .into_iter()
+            .flat_map(|frame| match frame {
+                Self::Attribute(pairs) => pairs,
+                _ => Vec::new(),
+            })
+            .collect();
+        (attributes, reply)
+    }
+
     /// Create a RESP3 Double frame from an f64, formatted exactly as
     /// vendored Redis 7.2.4 `addReplyDouble`/`d2string` would (so RESP3
     /// `,<value>\r\n` is byte-identical to upstream). (frankenredis-sk4ss)
@@ -1547,15 +1726,32 @@ pub struct ParserConfig {
     /// peeled before parsing the next real frame, blob-error → Error.
     /// (br-frankenredis-ozcx)
     pub allow_resp3: bool,
+    /// With `allow_resp3`, decode RESP3 frames into their native variants
+    /// instead of the RESP2 downgrade: map → `Map`, set → `Set`, push →
+    /// `Push`, bool → `Bool`, double → `Double` and big number → `BigNumber`
+    /// (both keeping the wire text), verbatim → `Verbatim` (the 3-byte format
+    /// tag is not retained). For outbound endpoints — a replication client or
+    /// the differential harness talking to a real Redis after `HELLO 3` — so
+    /// `encode_into_resp3` of a decoded frame reproduces the peer's bytes,
+    /// except that a verbatim string re-encodes with the `txt` tag and a blob
+    /// error as a simple error.
+    pub preserve_resp3: bool,
+    /// With `preserve_resp3`, surface an attribute (`|`) as metadata on the
+    /// frame it prefixes instead of dropping it: the result is a `Sequence`
+    /// of one `Attribute` per prefix followed by the annotated frame, the same
+    /// shape the encoder writes. See [`RespFrame::split_attributes`].
+    pub keep_attributes: bool,
 }
 
 impl Default for ParserConfig {
     fn default() -> Self {
         Self {
-            max_bulk_len: 512 * 1024 * 1024, // 512 MiB default (Redis standard)
-            max_array_len: 1024 * 1024,      // 1M elements
-            max_recursion_depth: 128,
+            max_bulk_len: MAX_BULK_LEN, // 512 MiB default (Redis standard)
+            max_array_len: 1024 * 1024, // 1M elements
+            max_recursion_depth: MAX_NESTING_DEPTH,
             allow_resp3: false,
+            preserve_resp3: false,
+            keep_attributes: false,
         }
     }
 }
@@ -2035,6 +2231,74 @@ fn parse_frame_internal(
         {
             Err(RespParseError::UnsupportedResp3Type(prefix))
         }
+        b'%' if config.preserve_resp3 => {
+            let (map, consumed) = parse_resp3_map(input, next, depth, config)?;
+            Ok((RespFrame::Map(resp3_pairs(map)), consumed))
+        }
+        b'~' if config.preserve_resp3 => {
+            let (array, consumed) = parse_array(input, next, depth, config)?;
+            let frame = match array {
+                RespFrame::Array(items) => RespFrame::Set(items),
+                other => other,
+            };
+            Ok((frame, consumed))
+        }
+        b'>' if config.preserve_resp3 => {
+            let (array, consumed) = parse_array(input, next, depth, config)?;
+            let frame = match array {
+                RespFrame::Array(Some(items)) => RespFrame::Push(items),
+                other => other,
+            };
+            Ok((frame, consumed))
+        }
+        b'#' if config.preserve_resp3 => {
+            let (flag, consumed) = parse_resp3_bool(input, next)?;
+            Ok((
+                RespFrame::Bool(matches!(flag, RespFrame::Integer(1))),
+                consumed,
+            ))
+        }
+        b',' if config.preserve_resp3 => {
+            let (line, consumed) = read_line(input, next)?;
+            let s = std::str::from_utf8(line).map_err(|_| RespParseError::InvalidUtf8)?;
+            if s.is_empty() || s.parse::<f64>().is_err() {
+                return Err(RespParseError::InvalidInteger);
+            }
+            Ok((RespFrame::Double(s.to_owned()), consumed))
+        }
+        b'(' if config.preserve_resp3 => {
+            let (line, consumed) = read_line(input, next)?;
+            let bytes = parse_resp3_big_number_body::<true>(line)?;
+            // The body validator admits only ASCII sign and digits.
+            let text = String::from_utf8(bytes).map_err(|_| RespParseError::InvalidUtf8)?;
+            Ok((RespFrame::BigNumber(text), consumed))
+        }
+        b'=' if config.preserve_resp3 => {
+            let (body, consumed) = parse_resp3_verbatim(input, next, config)?;
+            let RespFrame::BulkString(Some(body)) = body else {
+                return Err(RespParseError::InvalidBulkLength);
+            };
+            let text = String::from_utf8(body).map_err(|_| RespParseError::InvalidUtf8)?;
+            Ok((RespFrame::Verbatim(text), consumed))
+        }
+        b'|' if config.preserve_resp3 && config.keep_attributes => {
+            // Same chain budget as the peeling arm below; each prefix in a
+            // chain becomes its own leading `Attribute` in one `Sequence`.
+            let (attr, consumed) = parse_resp3_map(input, next, depth, config)?;
+            let attribute = RespFrame::Attribute(resp3_pairs(attr).unwrap_or_default());
+            let (annotated, consumed) =
+                parse_frame_internal(input, consumed, depth, attr_chain_depth + 1, config)?;
+            let frames = match annotated {
+                RespFrame::Sequence(mut frames)
+                    if matches!(frames.first(), Some(RespFrame::Attribute(_))) =>
+                {
+                    frames.insert(0, attribute);
+                    frames
+                }
+                other => vec![attribute, other],
+            };
+            Ok((RespFrame::Sequence(frames), consumed))
+        }
         b'%' => parse_resp3_map(input, next, depth, config),
         b'~' | b'>' => parse_array(input, next, depth, config),
         b'#' => parse_resp3_bool(input, next),
@@ -2117,6 +2381,19 @@ fn parse_resp3_map(
     Ok((RespFrame::Array(Some(items)), cursor))
 }
 
+/// Pair up the flat `Array` that `parse_resp3_map` returns.
+fn resp3_pairs(flat: RespFrame) -> Option<Vec<(RespFrame, RespFrame)>> {
+    let RespFrame::Array(Some(items)) = flat else {
+        return None;
+    };
+    let mut items = items.into_iter();
+    let mut pairs = Vec::with_capacity(items.len() / 2);
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        pairs.push((key, value));
+    }
+    Some(pairs)
+}
+
 #[cfg_attr(feature = "bench-reference", inline(never))]
 fn parse_resp3_bool(input: &[u8], start: usize) -> Result<(RespFrame, usize), RespParseError> {
     parse_resp3_bool_impl::<true>(input, start)
@@ -2780,16 +3057,35 @@ mod d2string_edge_cases {
 #[cfg(test)]
 mod tests {
     use super::{
-        BorrowedCommandArgsKind, BorrowedCommandFrame, MAX_LINE_LENGTH, ParserConfig, RespFrame,
+        BorrowedCommandArgsKind, BorrowedCommandFrame, MAX_LINE_LENGTH, ParserConfig,
+        REPLY_NULL_ARRAY, REPLY_NULL_BULK, REPLY_OK, REPLY_ONE, REPLY_PONG, REPLY_ZERO, RespFrame,
         RespParseError, bench_encode_bulk_string_slice_small, bench_encode_integer,
         bench_encode_redis_double, bench_parse_bulk_slice, bench_parse_frame_len_line,
-        bench_parse_multibulk_count, bench_push_len_header, decimal_u64_len, decimal_usize_len,
-        encode_aggregate_header, encode_bulk_string_slice, encode_map_header, encode_redis_double,
-        format_redis_double, parse_command_args_borrowed_into, parse_command_frame,
-        parse_command_frame_borrowed, parse_frame, parse_frame_with_config,
+        bench_parse_multibulk_count, bench_push_len_header, bool_integer_reply, decimal_u64_len,
+        decimal_usize_len, encode_aggregate_header, encode_bulk_string_slice, encode_map_header,
+        encode_redis_double, format_redis_double, parse_command_args_borrowed_into,
+        parse_command_frame, parse_command_frame_borrowed, parse_frame, parse_frame_with_config,
         parse_resp3_big_number_body, push_i64, push_redis_double_ascii, push_usize,
+        resp_error_code,
     };
 
+    #[test]
+    fn resp_error_code_reads_known_leading_class() {
+        assert_eq!(
+            resp_error_code("WRONGTYPE Operation against a key"),
+            "WRONGTYPE"
+        );
+        assert_eq!(resp_error_code("NOGROUP No such consumer group"), "NOGROUP");
+        assert_eq!(
+            resp_error_code("BUSYGROUP Consumer Group name already exists"),
+            "BUSYGROUP"
+        );
+        assert_eq!(resp_error_code("ERR syntax error"), "ERR");
+        assert_eq!(resp_error_code("NOPE something"), "ERR");
+        assert_eq!(resp_error_code("wrongtype lower case"), "ERR");
+        assert_eq!(resp_error_code(""), "ERR");
+    }
+
     // The fused owned-frame count/length line fast path (parse_bulk / parse_array / parse_resp3_map)
     // must be byte-identical to the prior read_line + parse_i64_strict two-pass path for the full
     // result — every `len` (incl. negative), error, and Incomplete — across hand-picked edge cases
@@ -4723,6 +5019,202 @@ mod tests {
         assert_eq!(parsed.frame, RespFrame::Error("WRONG".to_string()));
     }
 
+    /// `preserve_resp3` keeps each RESP3 type as its native variant. Inputs
+    /// are the bytes Redis 7.2 sends under HELLO 3 (DEBUG PROTOCOL map / set /
+    /// bignum / double / true / verbatim / attrib, a SUBSCRIBE push).
+    #[test]
+    fn resp3_preserve_decodes_native_variants() {
+        let native = ParserConfig {
+            allow_resp3: true,
+            preserve_resp3: true,
+            ..ParserConfig::default()
+        };
+        let int = RespFrame::Integer;
+        let bulk = |s: &str| RespFrame::BulkString(Some(s.as_bytes().to_vec()));
+        let cases: Vec<(&[u8], RespFrame)> = vec![
+            (
+                b"%3\r\n:0\r\n#f\r\n:1\r\n#t\r\n:2\r\n#f\r\n",
+                RespFrame::Map(Some(vec![
+                    (int(0), RespFrame::Bool(false)),
+                    (int(1), RespFrame::Bool(true)),
+                    (int(2), RespFrame::Bool(false)),
+                ])),
+            ),
+            (
+                b"~3\r\n:0\r\n:1\r\n:2\r\n",
+                RespFrame::Set(Some(vec![int(0), int(1), int(2)])),
+            ),
+            (
+                b">3\r\n$9\r\nsubscribe\r\n$2\r\nch\r\n:1\r\n",
+                RespFrame::Push(vec![bulk("subscribe"), bulk("ch"), int(1)]),
+            ),
+            (
+                b"(1234567999999999999999999999999999999\r\n",
+                RespFrame::BigNumber("1234567999999999999999999999999999999".to_string()),
+            ),
+            (b"(-42\r\n", RespFrame::BigNumber("-42".to_string())),
+            (b",3.141\r\n", RespFrame::Double("3.141".to_string())),
+            (b",inf\r\n", RespFrame::Double("inf".to_string())),
+            (b",-inf\r\n", RespFrame::Double("-inf".to_string())),
+            (b"#t\r\n", RespFrame::Bool(true)),
+            (b"#f\r\n", RespFrame::Bool(false)),
+            (b"_\r\n", RespFrame::BulkString(None)),
+            (
+                b"=29\r\ntxt:This is a verbatim\nstring\r\n",
+                RespFrame::Verbatim("This is a verbatim\nstring".to_string()),
+            ),
+            (b"!5\r\nWRONG\r\n", RespFrame::Error("WRONG".to_string())),
+            // Attributes are still peeled unless `keep_attributes` is set.
+            (
+                b"|1\r\n$14\r\nkey-popularity\r\n*2\r\n$7\r\nkey:123\r\n:90\r\n$39\r\nSome real reply following the attribute\r\n",
+                bulk("Some real reply following the attribute"),
+            ),
+        ];
+        for (input, expected) in cases {
+            let parsed = parse_frame_with_config(input, &native)
+                .unwrap_or_else(|err| panic!("{:?}: {err}", String::from_utf8_lossy(input)));
+            assert_eq!(
+                parsed.frame,
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+            assert_eq!(parsed.consumed, input.len());
+            let mut encoded = Vec::new();
+            parsed.frame.encode_into_resp3(&mut encoded);
+            // Blob errors re-encode as simple errors; peeled attributes are gone.
+            if !input.starts_with(b"!") && !input.starts_with(b"|") {
+                assert_eq!(encoded, input, "re-encode {expected:?}");
+            }
+        }
+
+        // A verbatim string keeps only its text; any format tag re-encodes as `txt`.
+        let markdown = parse_frame_with_config(b"=15\r\nmkd:Some string\r\n", &native)
+            .expect("verbatim with a mkd tag");
+        assert_eq!(
+            markdown.frame,
+            RespFrame::Verbatim("Some string".to_string())
+        );
+        let mut encoded = Vec::new();
+        markdown.frame.encode_into_resp3(&mut encoded);
+        assert_eq!(encoded, b"=15\r\ntxt:Some string\r\n");
+
+        // Malformed payloads stay errors in native mode.
+        for input in [
+            &b"(12a\r\n"[..],
+            b"(\r\n",
+            b",\r\n",
+            b",abc\r\n",
+            b"#x\r\n",
+            b"=3\r\ntxt\r\n",
+        ] {
+            assert!(
+                parse_frame_with_config(input, &native).is_err(),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+        // `preserve_resp3` does not open the RESP3 prefixes by itself.
+        let closed = ParserConfig {
+            preserve_resp3: true,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            parse_frame_with_config(b"#t\r\n", &closed),
+            Err(RespParseError::UnsupportedResp3Type(b'#'))
+        );
+    }
+
+    #[test]
+    fn resp3_keep_attributes_attaches_metadata_to_the_next_frame() {
+        let keep = ParserConfig {
+            allow_resp3: true,
+            preserve_resp3: true,
+            keep_attributes: true,
+            ..ParserConfig::default()
+        };
+        let bulk = |s: &str| RespFrame::BulkString(Some(s.as_bytes().to_vec()));
+        let popularity = (
+            bulk("key-popularity"),
+            RespFrame::Array(Some(vec![bulk("key:123"), RespFrame::Integer(90)])),
+        );
+
+        let input: &[u8] = b"|1\r\n$14\r\nkey-popularity\r\n*2\r\n$7\r\nkey:123\r\n:90\r\n$39\r\nSome real reply following the attribute\r\n";
+        let parsed = parse_frame_with_config(input, &keep).unwrap();
+        assert_eq!(parsed.consumed, input.len());
+        assert_eq!(
+            parsed.frame,
+            RespFrame::Sequence(vec![
+                RespFrame::Attribute(vec![popularity.clone()]),
+                bulk("Some real reply following the attribute"),
+            ])
+        );
+        let mut encoded = Vec::new();
+        parsed.frame.encode_into_resp3(&mut encoded);
+        assert_eq!(encoded, input);
+        let (attributes, reply) = parsed.frame.split_attributes();
+        assert_eq!(attributes, vec![popularity]);
+        assert_eq!(reply, bulk("Some real reply following the attribute"));
+
+        // A chain keeps one Attribute per prefix; an attribute inside an
+        // aggregate annotates that element only.
+        let chained =
+            parse_frame_with_config(b"|1\r\n+a\r\n:1\r\n|1\r\n+b\r\n:2\r\n+OK\r\n", &keep)
+                .unwrap()
+                .frame;
+        assert_eq!(
+            chained,
+            RespFrame::Sequence(vec![
+                RespFrame::Attribute(vec![(
+                    RespFrame::SimpleString("a".to_string()),
+                    RespFrame::Integer(1)
+                )]),
+                RespFrame::Attribute(vec![(
+                    RespFrame::SimpleString("b".to_string()),
+                    RespFrame::Integer(2)
+                )]),
+                RespFrame::SimpleString("OK".to_string()),
+            ])
+        );
+        let (attributes, reply) = chained.split_attributes();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(reply, RespFrame::SimpleString("OK".to_string()));
+
+        let nested =
+            parse_frame_with_config(b"*2\r\n|1\r\n+ttl\r\n:10\r\n$1\r\nv\r\n:7\r\n", &keep)
+                .unwrap()
+                .frame;
+        assert_eq!(
+            nested,
+            RespFrame::Array(Some(vec![
+                RespFrame::Sequence(vec![
+                    RespFrame::Attribute(vec![(
+                        RespFrame::SimpleString("ttl".to_string()),
+                        RespFrame::Integer(10)
+                    )]),
+                    bulk("v"),
+                ]),
+                RespFrame::Integer(7),
+            ]))
+        );
+
+        // Plain frames split into no attributes and themselves.
+        let (attributes, reply) = RespFrame::Integer(3).split_attributes();
+        assert!(attributes.is_empty());
+        assert_eq!(reply, RespFrame::Integer(3));
+
+        // The chain cap still applies when attributes are kept.
+        let mut bomb = Vec::new();
+        for _ in 0..=super::RESP3_ATTRIBUTE_CHAIN_LIMIT + 1 {
+            bomb.extend_from_slice(b"|0\r\n");
+        }
+        bomb.extend_from_slice(b"+OK\r\n");
+        assert_eq!(
+            parse_frame_with_config(&bomb, &keep),
+            Err(RespParseError::RecursionLimitExceeded)
+        );
+    }
+
     #[test]
     fn resp3_null_rejects_non_empty_payload() {
         let allow = ParserConfig {
@@ -5316,6 +5808,41 @@ mod tests {
             );
         }
 
+        /// Golden test: static simple strings encode like owned ones, the
+        /// well-known texts from their pre-encoded bytes.
+        #[test]
+        fn golden_static_simple_strings() {
+            for text in ["OK", "PONG", "QUEUED"] {
+                let owned = RespFrame::SimpleString(text.to_string());
+                let frame = RespFrame::StaticSimple(text);
+                assert_eq!(frame.to_bytes(), owned.to_bytes(), "{text}");
+                assert_eq!(frame, owned);
+                assert_eq!(frame.as_simple_str(), Some(text));
+                let mut resp3 = Vec::new();
+                frame.encode_into_resp3(&mut resp3);
+                assert_eq!(resp3, owned.to_bytes(), "{text}");
+            }
+            assert_eq!(RespFrame::StaticSimple("OK").to_bytes(), REPLY_OK);
+            assert_eq!(RespFrame::StaticSimple("PONG").to_bytes(), REPLY_PONG);
+            assert_ne!(
+                RespFrame::StaticSimple("OK"),
+                RespFrame::SimpleString("PONG".to_string())
+            );
+        }
+
+        /// Golden test: the pre-encoded small replies match the encoder.
+        #[test]
+        fn golden_pre_encoded_replies() {
+            assert_eq!(RespFrame::Integer(0).to_bytes(), REPLY_ZERO);
+            assert_eq!(RespFrame::Integer(1).to_bytes(), REPLY_ONE);
+            assert_eq!(RespFrame::Integer(2).to_bytes(), b":2\r\n");
+            assert_eq!(RespFrame::Integer(-1).to_bytes(), b":-1\r\n");
+            assert_eq!(RespFrame::BulkString(None).to_bytes(), REPLY_NULL_BULK);
+            assert_eq!(RespFrame::Array(None).to_bytes(), REPLY_NULL_ARRAY);
+            assert_eq!(bool_integer_reply(true), b":1\r\n");
+            assert_eq!(bool_integer_reply(false), b":0\r\n");
+        }
+
         /// Golden test: Error encoding must produce exact bytes.
         #[test]
         fn golden_error_generic() {
@@ -5606,7 +6133,7 @@ mod tests {
 
     /// Metamorphic tests for RESP encoding/decoding invariants.
     mod metamorphic {
-        use super::super::{RespFrame, parse_frame};
+        use super::super::{ParserConfig, RespFrame, parse_frame, parse_frame_with_config};
         use proptest::prelude::*;
 
         fn arb_simple_string() -> impl Strategy<Value = RespFrame> {
@@ -5652,9 +6179,61 @@ mod tests {
             })
         }
 
+        /// RESP3 frames whose `encode_into_resp3` decodes back to themselves
+        /// under `preserve_resp3` + `keep_attributes` (no null aggregates: those
+        /// all encode as `_`, which decodes as `BulkString(None)`).
+        fn arb_resp3_leaf() -> impl Strategy<Value = RespFrame> {
+            prop_oneof![
+                arb_frame_leaf(),
+                any::<f64>().prop_map(RespFrame::double_from_f64),
+                "-?[1-9][0-9]{0,60}".prop_map(RespFrame::BigNumber),
+                any::<bool>().prop_map(RespFrame::Bool),
+                "\\PC{0,40}".prop_map(RespFrame::Verbatim),
+            ]
+        }
+
+        fn arb_resp3_frame() -> impl Strategy<Value = RespFrame> {
+            let annotated = (
+                prop::collection::vec((arb_resp3_leaf(), arb_resp3_leaf()), 0..3),
+                arb_resp3_leaf(),
+            )
+                .prop_map(|(pairs, reply)| {
+                    RespFrame::Sequence(vec![RespFrame::Attribute(pairs), reply])
+                });
+            prop_oneof![arb_resp3_leaf(), annotated].prop_recursive(3, 32, 6, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..6)
+                        .prop_map(|v| RespFrame::Array(Some(v))),
+                    prop::collection::vec(inner.clone(), 0..6)
+                        .prop_map(|v| RespFrame::Set(Some(v))),
+                    prop::collection::vec(inner.clone(), 1..6).prop_map(RespFrame::Push),
+                    prop::collection::vec((inner.clone(), inner), 0..4)
+                        .prop_map(|v| RespFrame::Map(Some(v))),
+                ]
+            })
+        }
+
         proptest! {
             #![proptest_config(ProptestConfig::with_cases(500))]
 
+            /// MR1b: RESP3 encode-decode roundtrip identity with native types
+            /// and kept attributes.
+            #[test]
+            fn mr_resp3_encode_decode_roundtrip(frame in arb_resp3_frame()) {
+                let native = ParserConfig {
+                    allow_resp3: true,
+                    preserve_resp3: true,
+                    keep_attributes: true,
+                    ..ParserConfig::default()
+                };
+                let mut encoded = Vec::new();
+                frame.encode_into_resp3(&mut encoded);
+                let parsed = parse_frame_with_config(&encoded, &native)
+                    .expect("encoded RESP3 frame must parse");
+                prop_assert_eq!(parsed.consumed, encoded.len(), "consumed mismatch");
+                prop_assert_eq!(parsed.frame, frame, "roundtrip mismatch");
+            }
+
             /// MR1: Encode-decode roundtrip identity
             /// encode(frame) → parse(encoded) == frame
             #[test]
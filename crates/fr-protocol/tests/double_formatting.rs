@@ -0,0 +1,73 @@
+//! Score text as Redis 7.2.4 replies with it (util.c::d2string).
+//!
+//! d2string is NOT `%.17g`: exact integers inside the ±2^62 `double2ll`
+//! window print as plain decimals, and everything else is fpconv's shortest
+//! round-trip Grisu2 form with a bare `e+NN` / `e-N` exponent. Clients compare
+//! these strings byte for byte, so the String, byte-buffer, RESP2 bulk and
+//! RESP3 double paths must all produce the same text.
+
+use fr_protocol::{RespFrame, encode_redis_double, format_redis_double, push_redis_double_ascii};
+
+const TABLE: &[(f64, &str)] = &[
+    (0.1, "0.1"),
+    (0.1 + 0.2, "0.30000000000000004"),
+    (1.0 / 3.0, "0.3333333333333333"),
+    (3.0, "3"),
+    (-0.0, "-0"),
+    (1e21, "1e+21"),
+    // The next double up needs 17 digits; at 22 characters d2string still
+    // prefers fixed notation over scientific.
+    (1.000_000_000_000_000_1e21, "1000000000000000100000"),
+    (1.2e21, "1.2e+21"),
+    (9_007_199_254_740_992.0, "9007199254740992"),
+    (9_007_199_254_740_993.0, "9007199254740992"),
+    (f64::MAX, "1.7976931348623157e+308"),
+    (f64::MIN, "-1.7976931348623157e+308"),
+    (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+    (5e-324, "5e-324"),
+    (f64::INFINITY, "inf"),
+    (f64::NEG_INFINITY, "-inf"),
+];
+
+#[test]
+fn tricky_values_format_like_d2string() {
+    for (value, want) in TABLE {
+        assert_eq!(format_redis_double(*value), *want, "{value:?}");
+
+        let mut ascii = Vec::new();
+        push_redis_double_ascii(&mut ascii, *value);
+        assert_eq!(ascii, want.as_bytes(), "{value:?}");
+
+        assert_eq!(
+            RespFrame::double_from_f64(*value),
+            RespFrame::Double((*want).to_string())
+        );
+    }
+}
+
+#[test]
+fn wire_encodings_carry_the_same_text() {
+    for (value, want) in TABLE {
+        let mut resp2 = Vec::new();
+        encode_redis_double(*value, false, &mut resp2);
+        assert_eq!(
+            resp2,
+            format!("${}\r\n{want}\r\n", want.len()).into_bytes(),
+            "{value:?}"
+        );
+
+        let mut resp3 = Vec::new();
+        encode_redis_double(*value, true, &mut resp3);
+        assert_eq!(resp3, format!(",{want}\r\n").into_bytes(), "{value:?}");
+    }
+}
+
+#[test]
+fn finite_output_round_trips_to_the_same_bits() {
+    for (value, text) in TABLE {
+        if value.is_finite() {
+            let back: f64 = text.parse().unwrap();
+            assert_eq!(back.to_bits(), value.to_bits(), "{text}");
+        }
+    }
+}
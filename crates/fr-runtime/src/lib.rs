@@ -11,11 +11,12 @@ use std::{
 };
 
 use fr_command::{
-    CLIENT_PAUSE_MODE_INVALID, CLIENT_PAUSE_TIMEOUT_INVALID, CommandError, MigrateKeySpec,
-    apply_client_caching_mode, apply_client_reply_state, apply_client_tracking_update,
-    build_unknown_args_preview, client_tracking_getredir_value, client_trackinginfo_frame,
+    CLIENT_PAUSE_MODE_INVALID, CLIENT_PAUSE_TIMEOUT_INVALID, CommandError, InfoSection,
+    MigrateKeySpec, apply_client_caching_mode, apply_client_reply_state,
+    apply_client_tracking_update, client_tracking_getredir_value, client_trackinginfo_frame,
     command_acl_categories, commands_in_acl_category, dispatch_argv, execute_migrate,
-    frame_to_argv, parse_client_tracking_state, parse_f64_arg, parse_migrate_request,
+    frame_to_argv, info_sections, parse_client_tracking_state, parse_f64_arg,
+    parse_migrate_request, render_info_sections, unknown_command_error,
 };
 use fr_config::{
     DecisionAction, DriftSeverity, HardenedDeviationCategory, Mode, RuntimePolicy, ThreatClass,
@@ -42,7 +43,7 @@ use fr_repl::{
 };
 use fr_store::{
     AclKeyPattern, ClientReplyState, ClientTrackingState, CommandHistogram, CommandRecordKind,
-    DispatchAclLogContext, DispatchAclPermissionReason, DispatchAclPermissions,
+    CommandRename, DispatchAclLogContext, DispatchAclPermissionReason, DispatchAclPermissions,
     EvictionLoopFailure, EvictionLoopResult, EvictionLoopStatus, EvictionSafetyGateState,
     MaxmemoryPolicy, PendingAclLogEvent, SLOWLOG_ENTRY_MAX_STRING, Store, decode_db_key,
     encode_db_key, glob_match,
@@ -50,6 +51,10 @@ use fr_store::{
 use sha2::{Digest, Sha256};
 
 fn encode_nonnegative_integer_reply(value: u64, out: &mut Vec<u8>) {
+    if value <= 1 {
+        out.extend_from_slice(fr_protocol::bool_integer_reply(value == 1));
+        return;
+    }
     out.push(b':');
     let mut buf = [0_u8; 20];
     let start = fr_protocol::write_u64_digits(&mut buf, 20, value);
@@ -981,6 +986,44 @@ fn plain_bitcount_owned_argv(key: &[u8], range: PlainBitcountRange<'_>) -> Vec<V
     argv
 }
 
+/// Outcome of [`Runtime::start_plain_bitcount_borrowed`].
+#[derive(Debug)]
+pub enum PlainBitcountStep {
+    /// Answered on the calling thread.
+    Reply(RespFrame),
+    /// The command's bookkeeping is done and the value is snapshotted: count
+    /// it anywhere with [`PlainBitcountJob::run`] and hand the result back to
+    /// [`Runtime::finish_plain_bitcount_job`] on the owning client's session.
+    Offload(PlainBitcountJob),
+}
+
+/// A `BITCOUNT` over a large string, detached from the store so it can run on
+/// a worker thread while the event loop serves other clients.
+#[derive(Debug)]
+pub struct PlainBitcountJob {
+    bytes: Arc<Vec<u8>>,
+    argv: Vec<Vec<u8>>,
+    start: Option<i64>,
+    end: Option<i64>,
+    unit: fr_store::BitRangeUnit,
+    packet_id: u64,
+    setup_us: u64,
+}
+
+impl PlainBitcountJob {
+    /// Count the snapshotted bits. Touches no runtime or store state.
+    pub fn run(&self) -> Result<usize, fr_store::StoreError> {
+        Store::bitcount_bytes(&self.bytes, self.start, self.end, self.unit)
+    }
+
+    fn range(&self) -> PlainBitcountRange<'_> {
+        match self.argv.as_slice() {
+            [_, _, start, end, rest @ ..] => Some((start, end, rest.first().map(Vec::as_slice))),
+            _ => None,
+        }
+    }
+}
+
 /// Raw trailing range args for the borrowed `BITPOS` fast path:
 /// `(start, end?, unit?)`. The recognizer guarantees `unit` implies `end`
 /// (only argc 6 carries a BYTE|BIT modifier); args are parsed lazily.
@@ -1074,18 +1117,6 @@ impl PlainExpireKind {
             Self::AbsoluteMilliseconds => Some(raw_time),
         }
     }
-
-    /// Mirror deadline_from_expire_kind: the i128 absolute-ms deadline.
-    fn deadline_ms_i128(self, raw_time: i64, now_ms: u64) -> i128 {
-        match self {
-            Self::RelativeSeconds => {
-                i128::from(now_ms).saturating_add(i128::from(raw_time).saturating_mul(1000))
-            }
-            Self::RelativeMilliseconds => i128::from(now_ms).saturating_add(i128::from(raw_time)),
-            Self::AbsoluteSeconds => i128::from(raw_time).saturating_mul(1000),
-            Self::AbsoluteMilliseconds => i128::from(raw_time),
-        }
-    }
 }
 
 fn plain_expire_owned_argv(
@@ -1291,17 +1322,6 @@ fn plain_lset_owned_argv(key: &[u8], index_arg: &[u8], value: &[u8]) -> Vec<Vec<
     ]
 }
 
-/// Saturating i128 -> i64 clamp (mirrors fr-command's clamp_i128_to_i64).
-fn clamp_i128_to_i64_runtime(value: i128) -> i64 {
-    if value < i128::from(i64::MIN) {
-        i64::MIN
-    } else if value > i128::from(i64::MAX) {
-        i64::MAX
-    } else {
-        value as i64
-    }
-}
-
 fn plain_hexists_owned_argv(key: &[u8], field: &[u8]) -> Vec<Vec<u8>> {
     vec![b"HEXISTS".to_vec(), key.to_vec(), field.to_vec()]
 }
@@ -1425,6 +1445,74 @@ fn config_set_failed(field: &str, detail: &str) -> RespFrame {
     ))
 }
 
+/// Bytes a client holds for MEMORY STATS and `maxmemory-clients`: the
+/// per-client struct overhead plus its query and output buffers.
+fn client_memory_usage(session: &ClientSession) -> usize {
+    const PER_CLIENT_STRUCT_BYTES: usize = 432;
+    PER_CLIENT_STRUCT_BYTES
+        .saturating_add(session.qbuf_bytes)
+        .saturating_add(session.qbuf_free_bytes)
+        .saturating_add(session.output_buffer_bytes)
+}
+
+const CONFIG_REWRITE_SIGNATURE: &str = "# Generated by CONFIG REWRITE";
+
+/// Merge the live `CONFIG GET *` values into the existing config file text,
+/// the way upstream config.c::rewriteConfig does: the first line naming a
+/// parameter is replaced in place with its current value and later repeats
+/// of it are dropped; comments, blank lines and non-parameter directives
+/// (`include`, `rename-command`, ...) are kept verbatim; parameters the file
+/// never mentioned are appended at the end, behind a single
+/// `# Generated by CONFIG REWRITE` signature line.
+fn rewrite_config_file_content(existing: &str, current: &[(String, String)]) -> String {
+    fn directive_line(name: &str, value: &str) -> String {
+        if value.is_empty() {
+            format!("{name} \"\"")
+        } else {
+            format!("{name} {value}")
+        }
+    }
+
+    let mut pending: HashMap<&str, &str> = current
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    let known: HashSet<&str> = pending.keys().copied().collect();
+    let mut out = Vec::new();
+    let mut signed = false;
+    for line in existing.lines() {
+        let trimmed = line.trim();
+        signed |= trimmed == CONFIG_REWRITE_SIGNATURE;
+        let name = if trimmed.is_empty() || trimmed.starts_with('#') {
+            None
+        } else {
+            fr_config::split_config_line_args(trimmed)
+                .ok()
+                .and_then(|args| args.into_iter().next())
+                .map(|name| String::from_utf8_lossy(&name).to_ascii_lowercase())
+        };
+        match name {
+            Some(name) if known.contains(name.as_str()) => {
+                if let Some(value) = pending.remove(name.as_str()) {
+                    out.push(directive_line(&name, value));
+                }
+            }
+            _ => out.push(line.to_string()),
+        }
+    }
+    let mut appended = current
+        .iter()
+        .filter(|(name, _)| pending.contains_key(name.as_str()))
+        .peekable();
+    if appended.peek().is_some() {
+        if !signed {
+            out.push(CONFIG_REWRITE_SIGNATURE.to_string());
+        }
+        out.extend(appended.map(|(name, value)| directive_line(name, value)));
+    }
+    out.join("\n") + "\n"
+}
+
 /// One client class's output-buffer limit triple: `hard`/`soft` are byte sizes
 /// (0 == unlimited, matching redis), `soft_seconds` is in seconds. Mirrors a
 /// `clientBufferLimitsConfig` entry in config.c. (frankenredis-8sb0l)
@@ -1659,6 +1747,8 @@ const CONFIG_STATIC_PARAMS: &[(&str, &str)] = &[
     ("always-show-logo", "no"),
     ("set-proc-title", "yes"),
     ("proc-title-template", "{title} {listen-addr} {server-mode}"),
+    // FrankenRedis extension: overrides the version INFO/HELLO/LOLWUT report.
+    ("server-version", ""),
     // Memory
     ("maxmemory", "0"),
     ("maxmemory-policy", "noeviction"),
@@ -1904,6 +1994,12 @@ const CONFIG_STATIC_HIDDEN_PARAMS: &[(&str, &str)] = &[
     ("watchdog-period", "0"),
     ("cluster-ping-interval", "0"),
     ("loading-process-events-interval-bytes", "2097152"),
+    // fr-specific and off by default, so kept out of CONFIG GET * like the
+    // upstream hidden configs above.
+    ("stream-preserve-last-id-on-delete", "no"),
+    ("stream-preserve-last-id-window", "3600000"),
+    ("max-elements-per-call", "0"),
+    ("stream-history-reads-keep-delivery-counts", "no"),
 ];
 
 type ConfigStaticParamIndex = HashMap<&'static str, &'static str, foldhash::quality::RandomState>;
@@ -3489,6 +3585,80 @@ fn command_should_feed_monitors(argv: &[Vec<u8>]) -> bool {
     }
 }
 
+/// Upstream `SENSITIVE_CONFIG` parameters: CONFIG SET hides their values from
+/// SLOWLOG and MONITOR.
+fn config_param_is_sensitive(name: &[u8]) -> bool {
+    [
+        &b"requirepass"[..],
+        b"masterauth",
+        b"tls-key-file-pass",
+        b"tls-client-key-file-pass",
+    ]
+    .iter()
+    .any(|sensitive| name.eq_ignore_ascii_case(sensitive))
+}
+
+/// Mirror upstream `redactClientCommandArgument`: the copy of `argv` that
+/// SLOWLOG and MONITOR record, with credentials replaced by `(redacted)` —
+/// AUTH's username and password, HELLO AUTH's username and password, MIGRATE AUTH/AUTH2
+/// credentials and the value of every sensitive CONFIG SET parameter.
+/// `None` when the command carries nothing to hide.
+fn redacted_argv(argv: &[Vec<u8>]) -> Option<Vec<Vec<u8>>> {
+    let command = argv.first()?;
+    let mut positions = Vec::new();
+    if command.eq_ignore_ascii_case(b"AUTH") {
+        if matches!(argv.len(), 2 | 3) {
+            positions.extend(1..argv.len());
+        }
+    } else if command.eq_ignore_ascii_case(b"HELLO") {
+        let mut j = 2;
+        while j < argv.len() {
+            if argv[j].eq_ignore_ascii_case(b"AUTH") && j + 2 < argv.len() {
+                positions.extend([j + 1, j + 2]);
+                j += 3;
+            } else if argv[j].eq_ignore_ascii_case(b"SETNAME") && j + 1 < argv.len() {
+                j += 2;
+            } else {
+                break;
+            }
+        }
+    } else if command.eq_ignore_ascii_case(b"MIGRATE") {
+        let mut j = 6;
+        while j < argv.len() {
+            if argv[j].eq_ignore_ascii_case(b"AUTH") && j + 1 < argv.len() {
+                positions.push(j + 1);
+                j += 2;
+            } else if argv[j].eq_ignore_ascii_case(b"AUTH2") && j + 2 < argv.len() {
+                positions.extend([j + 1, j + 2]);
+                j += 3;
+            } else if argv[j].eq_ignore_ascii_case(b"KEYS") {
+                break;
+            } else {
+                j += 1;
+            }
+        }
+    } else if command.eq_ignore_ascii_case(b"CONFIG")
+        && argv
+            .get(1)
+            .is_some_and(|sub| sub.eq_ignore_ascii_case(b"SET"))
+    {
+        positions.extend(
+            (2..argv.len().saturating_sub(1))
+                .step_by(2)
+                .filter(|&j| config_param_is_sensitive(&argv[j]))
+                .map(|j| j + 1),
+        );
+    }
+    if positions.is_empty() {
+        return None;
+    }
+    let mut redacted = argv.to_vec();
+    for j in positions {
+        redacted[j] = b"(redacted)".to_vec();
+    }
+    Some(redacted)
+}
+
 #[cfg(test)]
 #[inline]
 fn classify_cluster_subcommand(cmd: &[u8]) -> Result<ClusterSubcommand, CommandError> {
@@ -3799,6 +3969,12 @@ impl ReplicationRuntimeState {
         }
     }
 
+    /// Upstream replication.c::changeReplicationId: a fresh random master
+    /// replid. The offset carries on; only the history it names changes.
+    fn change_replid(&mut self) {
+        self.backlog.replid = fr_store::generate_run_id_hex();
+    }
+
     fn ensure_replica(&mut self, client_id: u64) -> &mut ReplicaState {
         // Latch on first replica entry; vendored uses this to decide
         // whether to materialize the repl backlog. (frankenredis-f82ny)
@@ -4085,6 +4261,15 @@ pub struct ServerState {
     pub query_buffer_limit: usize,
     /// Maximum bulk string length in RESP protocol (CONFIG SET proto-max-bulk-len). Default 512 MiB.
     pub proto_max_bulk_len: usize,
+    /// Commands one connection may run in a single event-loop pass before the
+    /// server yields to other connections and resumes its buffered pipeline on
+    /// the next pass. fr-only startup knob (`--max-commands-per-tick`), not a
+    /// CONFIG parameter. Default 4096.
+    pub client_tick_max_commands: usize,
+    /// Wall-time budget, in microseconds, for one connection's commands in a
+    /// single event-loop pass (`--max-exec-us-per-tick`). Bounds the stall a
+    /// pipeline of slow commands causes other clients. 0 disables it (default).
+    pub client_tick_max_exec_us: u64,
     /// Set to true when SHUTDOWN is requested. Server event loop checks this.
     pub shutdown_requested: bool,
     /// If true, skip the final SAVE on shutdown.
@@ -4133,8 +4318,10 @@ pub struct ServerState {
     // ── Pub/Sub global state ────────────────────────────────────────
     /// Channel → set of subscribed client IDs.
     pubsub_channel_subs: HashMap<Vec<u8>, HashSet<u64>>,
-    /// Pattern → set of subscribed client IDs.
-    pubsub_pattern_subs: HashMap<Vec<u8>, HashSet<u64>>,
+    /// Pattern → set of subscribed client IDs. Ordered so a client holding
+    /// several patterns that match one channel receives its pmessages in a
+    /// stable (byte-sorted pattern) order.
+    pubsub_pattern_subs: BTreeMap<Vec<u8>, HashSet<u64>>,
     /// Shard channel → set of subscribed client IDs.
     pubsub_shard_subs: HashMap<Vec<u8>, HashSet<u64>>,
     /// Per-client outbox: client_id → pending messages for delivery.
@@ -4254,6 +4441,8 @@ impl Default for ServerState {
             client_output_buffer_limits: ClientOutputBufferLimits::default(),
             query_buffer_limit: 1024 * 1024 * 1024, // 1 GiB (Redis default)
             proto_max_bulk_len: 536_870_912,        // Redis 7.2 default (512 MiB)
+            client_tick_max_commands: 4096,
+            client_tick_max_exec_us: 0,
             shutdown_requested: false,
             shutdown_nosave: false,
             command_time_budget_ms: 5000,
@@ -4274,7 +4463,7 @@ impl Default for ServerState {
             acl_file_path: None,
             config_overrides: HashMap::new(),
             pubsub_channel_subs: HashMap::new(),
-            pubsub_pattern_subs: HashMap::new(),
+            pubsub_pattern_subs: BTreeMap::new(),
             pubsub_shard_subs: HashMap::new(),
             pubsub_outbox: HashMap::default(),
             client_tracking_observed_keys: HashMap::default(),
@@ -4384,6 +4573,7 @@ impl ServerState {
         let records = fr_persist::read_aof_file(&path)?;
         let count = records.len();
         let mut replayed_store = Store::new();
+        replayed_store.set_database_count(self.store.database_count);
         for (index, record) in records.iter().enumerate() {
             let replay_now_ms = now_ms.saturating_add(index as u64);
             dispatch_argv(&record.argv, &mut replayed_store, replay_now_ms)
@@ -4644,6 +4834,21 @@ impl ServerState {
             .is_some_and(|v| v.eq_ignore_ascii_case("yes"))
     }
 
+    /// The `maxmemory-clients` budget in bytes; 0 disables client eviction.
+    /// A percentage is taken of `maxmemory`, so it is also 0 while maxmemory
+    /// is unlimited (config.c applyClientMaxMemoryUsage).
+    fn maxmemory_clients_limit(&self) -> usize {
+        let Some(value) = self.config_overrides.get("maxmemory-clients") else {
+            return 0;
+        };
+        match value.strip_suffix('%') {
+            Some(percent) => percent.parse::<usize>().map_or(0, |percent| {
+                self.maxmemory_bytes.saturating_mul(percent) / 100
+            }),
+            None => value.parse().unwrap_or(0),
+        }
+    }
+
     #[must_use]
     pub fn last_active_expire_cycle_stats(&self) -> Option<ActiveExpireCycleStats> {
         self.last_active_expire_cycle
@@ -5699,6 +5904,10 @@ fn preserve_store_load_context(replacement: &mut Store, original: &Store) {
         .clone_from(&original.cluster_shard_id);
     replacement.server_pid = original.server_pid;
     replacement.server_port = original.server_port;
+    replacement
+        .redis_version_override
+        .clone_from(&original.redis_version_override);
+    replacement.server_git_sha1 = original.server_git_sha1;
     replacement.sentinel_mode = original.sentinel_mode;
     // (frankenredis-hpfey) An RDB load swaps in a fresh `Store::new()`, but the
     // store also holds runtime CONFIG SET state that is NOT part of the RDB and
@@ -5710,10 +5919,23 @@ fn preserve_store_load_context(replacement: &mut Store, original: &Store) {
     replacement.maxmemory_policy = original.maxmemory_policy;
     replacement.lfu_decay_time = original.lfu_decay_time;
     replacement.lfu_log_factor = original.lfu_log_factor;
+    replacement.set_max_elements_per_call(original.max_elements_per_call());
+    replacement.set_stream_history_reads_keep_delivery_counts(
+        original.stream_history_reads_keep_delivery_counts(),
+    );
     copy_encoding_thresholds(replacement, original);
     replacement.notify_keyspace_events = original.notify_keyspace_events;
 }
 
+/// Carry the store configuration an entry rebuild depends on onto a fresh
+/// replacement store before anything is replayed into it: the `databases`
+/// count (per-db key/expire counters only track indexes below it) plus the
+/// encoding thresholds below.
+fn copy_rebuild_config(replacement: &mut Store, original: &Store) {
+    replacement.set_database_count(original.database_count);
+    copy_encoding_thresholds(replacement, original);
+}
+
 /// Carry ONLY the encoding-threshold CONFIG SET state — the fields that govern
 /// OBJECT ENCODING when a collection is (re)built. Split out of
 /// `preserve_store_load_context` because these must also be applied to the fresh
@@ -5874,6 +6096,82 @@ impl Runtime {
         self.server.config_file_path = path;
     }
 
+    /// Whether `name` is a parameter `CONFIG GET` reports. Startup uses this
+    /// to reject unknown config-file directives before applying any of them.
+    #[must_use]
+    pub fn is_config_parameter(&self, name: &str) -> bool {
+        if name.is_empty() || name.contains(['*', '?', '[']) {
+            return false;
+        }
+        match self.handle_config_get(&[
+            b"CONFIG".to_vec(),
+            b"GET".to_vec(),
+            name.as_bytes().to_vec(),
+        ]) {
+            RespFrame::Array(Some(entries)) => !entries.is_empty(),
+            RespFrame::Map(Some(entries)) => !entries.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Apply one `name value` directive read from the config file at startup.
+    /// Mutable parameters go through CONFIG SET so their live state follows;
+    /// immutable ones (`databases`, `daemonize`, ...) can only come from the
+    /// file, so their value is recorded straight into the CONFIG GET
+    /// registry. The error is CONFIG SET's message without the `ERR ` prefix.
+    pub fn apply_config_file_directive(&mut self, name: &str, value: &[u8]) -> Result<(), String> {
+        if name.eq_ignore_ascii_case("databases") {
+            return self.set_database_count(value);
+        }
+        let reply = self.handle_config_set(&[
+            b"CONFIG".to_vec(),
+            b"SET".to_vec(),
+            name.as_bytes().to_vec(),
+            value.to_vec(),
+        ]);
+        match reply {
+            RespFrame::SimpleString(_) => Ok(()),
+            RespFrame::Error(err) if err.ends_with("can't set immutable config") => {
+                self.server.config_overrides.insert(
+                    name.to_ascii_lowercase(),
+                    String::from_utf8_lossy(value).into_owned(),
+                );
+                Ok(())
+            }
+            RespFrame::Error(err) => Err(err.strip_prefix("ERR ").unwrap_or(&err).to_string()),
+            other => Err(format!("unexpected CONFIG SET reply: {other:?}")),
+        }
+    }
+
+    /// The `supervised` mode recorded from the config file or `--supervised`;
+    /// `no` when neither set it.
+    #[must_use]
+    pub fn supervised_mode(&self) -> &str {
+        self.server
+            .config_overrides
+            .get("supervised")
+            .map_or("no", String::as_str)
+    }
+
+    /// Apply the immutable `databases` directive: SELECT, MOVE, SWAPDB and
+    /// INFO keyspace all bound their DB indexes by this count. Mirrors
+    /// upstream's `createIntConfig("databases", ..., 1, INT_MAX, ...)`
+    /// range and wording.
+    pub fn set_database_count(&mut self, value: &[u8]) -> Result<(), String> {
+        let count = std::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .ok_or_else(|| "argument couldn't be parsed into an integer".to_string())?;
+        if !(1..=i64::from(i32::MAX)).contains(&count) {
+            return Err("argument must be between 1 and 2147483647 inclusive".to_string());
+        }
+        self.server.store.set_database_count(count as usize);
+        self.server
+            .config_overrides
+            .insert("databases".to_string(), count.to_string());
+        Ok(())
+    }
+
     /// Set the `enable-debug-command` knob. Accepts `"no"`, `"local"`,
     /// `"yes"` (case-insensitive); any other value normalizes to `"no"`
     /// (the safe default, matching upstream's `enable-debug-command`
@@ -5890,11 +6188,66 @@ impl Runtime {
         self.server.enable_debug_command = normalized.to_string();
     }
 
+    /// Per-connection execution budget for one event-loop pass: at most
+    /// `max_commands` commands (at least 1) and, when `max_exec_us` is
+    /// non-zero, that many microseconds. Startup-time path for
+    /// `--max-commands-per-tick` / `--max-exec-us-per-tick`.
+    pub fn set_client_tick_budget(&mut self, max_commands: usize, max_exec_us: u64) {
+        self.server.client_tick_max_commands = max_commands.max(1);
+        self.server.client_tick_max_exec_us = max_exec_us;
+    }
+
+    /// Apply one `rename-command <original> <replacement>` directive. Mirrors
+    /// upstream config.c: the replacement (or, when empty, nothing) reaches the
+    /// original command, the original name becomes unknown, and renaming an
+    /// unknown command or onto a name that already exists is a startup error.
+    /// Renames are folded into the store's lookup table here so the hot path
+    /// pays a single emptiness check when none are configured.
+    pub fn rename_command(&mut self, original: &[u8], replacement: &[u8]) -> Result<(), String> {
+        let renames = &mut self.server.store.command_renames;
+        let canonical = match renames.resolve(original) {
+            CommandRename::Alias(canonical) => canonical.to_vec(),
+            CommandRename::Hidden => {
+                return Err("No such command in rename-command".to_string());
+            }
+            CommandRename::Unchanged
+                if fr_command::is_known_command(original)
+                    || classify_runtime_special_command(original).is_some() =>
+            {
+                original.to_ascii_lowercase()
+            }
+            CommandRename::Unchanged => {
+                return Err("No such command in rename-command".to_string());
+            }
+        };
+        if !replacement.is_empty() {
+            let target_exists = match renames.resolve(replacement) {
+                CommandRename::Alias(_) => true,
+                CommandRename::Hidden => false,
+                CommandRename::Unchanged => {
+                    fr_command::is_known_command(replacement)
+                        || classify_runtime_special_command(replacement).is_some()
+                }
+            };
+            if target_exists {
+                return Err("Target command name already exists".to_string());
+            }
+        }
+        renames.remove_alias(original);
+        renames.insert(&canonical, replacement);
+        Ok(())
+    }
+
     /// Set the server listen port (for INFO server section).
     pub fn set_server_port(&mut self, port: u16) {
         self.server.store.server_port = port;
     }
 
+    /// Record the commit the server binary was built from (INFO `redis_git_sha1`).
+    pub fn set_server_git_sha1(&mut self, sha1: &'static str) {
+        self.server.store.server_git_sha1 = sha1;
+    }
+
     /// Record the listen bind address so the CONFIG SET port handler can
     /// test-bind a new port the way upstream config.c::updatePort does. The
     /// standalone server calls this at startup; library/test contexts leave it
@@ -5939,6 +6292,18 @@ impl Runtime {
         self.server.store.sentinel_mode
     }
 
+    /// Propagate EVAL/EVALSHA verbatim instead of as their effects. See
+    /// [`fr_store::Store::set_script_verbatim_propagation`].
+    pub fn set_script_verbatim_propagation(&mut self, enabled: bool) {
+        self.server.store.set_script_verbatim_propagation(enabled);
+    }
+
+    /// Reply to SMEMBERS/HGETALL/HKEYS/HVALS/SSCAN/HSCAN in sorted order. See
+    /// [`fr_store::Store::set_deterministic_iteration`].
+    pub fn set_deterministic_iteration(&mut self, enabled: bool) {
+        self.server.store.set_deterministic_iteration(enabled);
+    }
+
     /// (frankenredis-pkdgs) Advance the sentinel clock (tilt + previous_time)
     /// at the start of a monitoring tick.
     pub fn sentinel_begin_tick(&mut self, now_ms: u64) {
@@ -6025,6 +6390,9 @@ impl Runtime {
                 .as_ref()
                 .map_or(0, |(entries, _functions)| entries.len());
         let mut original_store = std::mem::replace(&mut self.server.store, Store::new());
+        self.server
+            .store
+            .set_database_count(original_store.database_count);
         let original_records = std::mem::take(&mut self.server.aof_records);
         let original_aof_db = self.server.aof_selected_db;
         let original_db = self.session.selected_db;
@@ -6095,7 +6463,7 @@ impl Runtime {
         // (frankenredis-63p1s) Apply the live encoding thresholds BEFORE the
         // rebuild so replayed collections pick their encoding under the current
         // config, not the fresh store's compiled defaults.
-        copy_encoding_thresholds(&mut store, &self.server.store);
+        copy_rebuild_config(&mut store, &self.server.store);
         let counts = apply_rdb_entries_to_store(&mut store, entries, now_ms)?;
         // Re-register FUNCTION libraries carried in the dump so FUNCTION LIST /
         // FCALL survive a restart, matching redis. (frankenredis-tm139)
@@ -6109,6 +6477,55 @@ impl Runtime {
         Ok(counts.loaded)
     }
 
+    /// Merge RDB entries the caller decoded itself into the live store, plus
+    /// the dump's function libraries. Unlike [`Self::load_rdb`] the existing
+    /// dataset is kept, so offline import tooling can layer a multi-part AOF
+    /// base and its incrementals, or several dumps, into one runtime.
+    pub fn import_rdb_entries(
+        &mut self,
+        entries: Vec<RdbEntry>,
+        functions: &[Vec<u8>],
+        now_ms: u64,
+    ) -> Result<RdbLoadCounts, PersistError> {
+        let counts = apply_rdb_entries_to_store(&mut self.server.store, entries, now_ms)?;
+        for code in functions {
+            let _ = self.server.store.function_load(code, true);
+        }
+        Ok(counts)
+    }
+
+    /// Live keys per logical type (`string`, `list`, `set`, `zset`, `hash`,
+    /// `stream`) across every database.
+    #[must_use]
+    pub fn keyspace_type_counts(&mut self, now_ms: u64) -> BTreeMap<&'static str, usize> {
+        let store = &mut self.server.store;
+        let mut counts = BTreeMap::new();
+        for key in store.all_keys() {
+            if let Some(value_type) = store.value_type_no_stat(&key, now_ms) {
+                *counts.entry(value_type.as_str()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// The dataset as the write commands an AOF rewrite would replay:
+    /// `FUNCTION LOAD`s first, then per-database `SELECT` and one write per
+    /// key followed by its `PEXPIREAT`.
+    #[must_use]
+    pub fn dataset_commands(&mut self, now_ms: u64) -> Vec<Vec<Vec<u8>>> {
+        self.server.store.to_aof_commands(now_ms)
+    }
+
+    /// [`Runtime::dataset_commands`] one command at a time, without building
+    /// the whole list; the first error `emit` returns stops the walk.
+    pub fn try_for_each_dataset_command<E>(
+        &mut self,
+        now_ms: u64,
+        emit: impl FnMut(Vec<Vec<u8>>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.server.store.try_for_each_aof_command(now_ms, emit)
+    }
+
     fn handle_debug_reload_requested(&mut self, now_ms: u64) -> RespFrame {
         if let Err(reply) = self.persist_snapshot_to_disk(now_ms, false, true) {
             return reply;
@@ -6126,7 +6543,7 @@ impl Runtime {
                 Ok((entries, _aux)) => {
                     let mut store = Store::new();
                     // (frankenredis-63p1s) live encoding thresholds before rebuild.
-                    copy_encoding_thresholds(&mut store, &self.server.store);
+                    copy_rebuild_config(&mut store, &self.server.store);
                     let counts = match apply_rdb_entries_to_store(
                         &mut store,
                         entries,
@@ -6193,7 +6610,7 @@ impl Runtime {
         };
         let mut store = Store::new();
         // (frankenredis-63p1s) live encoding thresholds before rebuild.
-        copy_encoding_thresholds(&mut store, &self.server.store);
+        copy_rebuild_config(&mut store, &self.server.store);
         let counts =
             match apply_rdb_entries_to_store(&mut store, decoded.entries, now_ms.saturating_add(1))
             {
@@ -6482,7 +6899,7 @@ impl Runtime {
                 }
                 let mut store = Store::new();
                 // (frankenredis-63p1s) live encoding thresholds before rebuild.
-                copy_encoding_thresholds(&mut store, &self.server.store);
+                copy_rebuild_config(&mut store, &self.server.store);
                 let counts = apply_rdb_entries_to_store(&mut store, decoded.entries, now_ms)?;
                 // (frankenredis-t1yxa) Re-register FUNCTION libraries carried in
                 // the full-sync snapshot so FCALL / FUNCTION LIST work on the
@@ -6999,7 +7416,6 @@ impl Runtime {
     /// overhead, same baseline tepuj uses for tot-mem) + qbuf + qbuf-free
     /// + output_buffer. The replica/normal bucket follows `Runtime::is_replica`.
     fn refresh_client_memory_aggregates(&mut self) {
-        const PER_CLIENT_STRUCT_BYTES: usize = 432;
         let mut normal_total: usize = 0;
         let mut replica_total: usize = 0;
         let replica_client_ids: HashSet<u64> = self
@@ -7010,10 +7426,7 @@ impl Runtime {
             .copied()
             .collect();
         for (id, session) in &self.server.client_sessions {
-            let mem = PER_CLIENT_STRUCT_BYTES
-                .saturating_add(session.qbuf_bytes)
-                .saturating_add(session.qbuf_free_bytes)
-                .saturating_add(session.output_buffer_bytes);
+            let mem = client_memory_usage(session);
             if replica_client_ids.contains(id) {
                 replica_total = replica_total.saturating_add(mem);
             } else {
@@ -7024,6 +7437,45 @@ impl Runtime {
         self.server.store.stat_clients_replica_mem_bytes = replica_total;
     }
 
+    /// Disconnect clients until the memory of the evictable ones fits in
+    /// `maxmemory-clients`, largest first, and return their ids. Replicas and
+    /// CLIENT NO-EVICT connections are never evicted and do not count towards
+    /// the budget (evict.c clientEvictionAllowed). The ids are queued on
+    /// `pending_client_kills`; fr-server calls this from its clientsCron pass.
+    pub fn evict_clients_over_maxmemory_clients(&mut self) -> Vec<u64> {
+        let limit = self.server.maxmemory_clients_limit();
+        if limit == 0 {
+            return Vec::new();
+        }
+        let mut candidates: Vec<(usize, u64)> = self
+            .server
+            .client_sessions
+            .iter()
+            .filter(|(id, session)| {
+                !session.client_no_evict
+                    && !self.is_replica(**id)
+                    && !self.server.pending_client_kills.contains(*id)
+            })
+            .map(|(id, session)| (client_memory_usage(session), *id))
+            .collect();
+        let mut total = candidates
+            .iter()
+            .fold(0usize, |total, (mem, _)| total.saturating_add(*mem));
+        // Largest first; ties go to the newest connection.
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        let mut evicted = Vec::new();
+        for (mem, id) in candidates {
+            if total <= limit {
+                break;
+            }
+            total = total.saturating_sub(mem);
+            evicted.push(id);
+        }
+        self.server.store.stat_evicted_clients += evicted.len() as u64;
+        self.server.pending_client_kills.extend_from_slice(&evicted);
+        evicted
+    }
+
     /// (frankenredis-jrqgd) Feed a pre-dispatch sample of a client's
     /// read/write buffer sizes into the server-wide recent-max
     /// accumulators so INFO clients emits real
@@ -7428,7 +7880,14 @@ impl Runtime {
     }
 
     /// Publish a message to a channel. Queues messages in each subscriber's
-    /// outbox. Returns the number of clients that received the message.
+    /// outbox and returns the number of deliveries, matching upstream
+    /// `pubsubPublishMessage`:
+    ///
+    /// - Fan-out is per subscription, not per client. A client subscribed to
+    ///   `news` and `ne*` gets a `message` and a `pmessage` and counts twice; a
+    ///   client holding `n*` and `ne*` gets one `pmessage` per pattern.
+    /// - The exact-channel `message` is queued first, then one `pmessage` per
+    ///   matching pattern in pattern byte order.
     pub fn pubsub_publish(&mut self, channel: &[u8], message: &[u8]) -> usize {
         let mut receivers = 0;
 
@@ -7447,7 +7906,8 @@ impl Runtime {
             }
         }
 
-        // Pattern subscribers — each matching pattern produces a pmessage
+        // Pattern subscribers — each matching pattern produces a pmessage, even
+        // for a client already reached above or through another pattern.
         // OPTIMIZATION: Avoid intermediate allocations and excessive cloning.
         for (pattern, client_ids) in &self.server.pubsub_pattern_subs {
             if fr_store::glob_match(pattern, channel) {
@@ -8305,6 +8765,11 @@ impl Runtime {
         unix_time_us: u64,
     ) -> RespFrame {
         let argv_result = frame_to_argv(frame);
+        if !self.server.store.command_renames.is_empty()
+            && let Ok(argv) = &argv_result
+        {
+            return self.execute_client_argv_renamed(Some(frame), argv, now_ms, unix_time_us);
+        }
         self.execute_dispatch(
             Some(frame),
             argv_result
@@ -8329,10 +8794,83 @@ impl Runtime {
         argv: &[Vec<u8>],
         now_ms: u64,
         unix_time_us: u64,
+    ) -> RespFrame {
+        if !self.server.store.command_renames.is_empty() {
+            return self.execute_client_argv_renamed(None, argv, now_ms, unix_time_us);
+        }
+        self.execute_dispatch(None, Ok(argv), now_ms, Some(unix_time_us))
+    }
+
+    /// Entry for an argv whose command name the caller already resolved with
+    /// [`Self::resolve_renamed_command`]: dispatches as-is, skipping the
+    /// `rename-command` lookup (the canonical name may itself be hidden).
+    pub fn execute_resolved_argv_with_unix_time_us(
+        &mut self,
+        argv: &[Vec<u8>],
+        now_ms: u64,
+        unix_time_us: u64,
     ) -> RespFrame {
         self.execute_dispatch(None, Ok(argv), now_ms, Some(unix_time_us))
     }
 
+    /// When `argv[0]` is a `rename-command` replacement name, return the argv
+    /// re-spelled with the canonical command name; `None` otherwise. Lets the
+    /// network server inspect canonical names after dispatch (blocking,
+    /// replication follow-ups) exactly as for an un-renamed command.
+    #[must_use]
+    pub fn resolve_renamed_command(&self, argv: &[Vec<u8>]) -> Option<Vec<Vec<u8>>> {
+        let CommandRename::Alias(canonical) =
+            self.server.store.command_renames.resolve(argv.first()?)
+        else {
+            return None;
+        };
+        let mut renamed = Vec::with_capacity(argv.len());
+        renamed.push(canonical.to_vec());
+        renamed.extend_from_slice(&argv[1..]);
+        Some(renamed)
+    }
+
+    /// True when any `rename-command` directive is in effect.
+    #[inline]
+    #[must_use]
+    pub fn has_command_renames(&self) -> bool {
+        !self.server.store.command_renames.is_empty()
+    }
+
+    /// True when `name` is a renamed or disabled original, or a replacement
+    /// name. The borrowed fast paths match literal command names, so the
+    /// network server sends such a command down the generic path; commands
+    /// no rename touches keep their fast paths.
+    #[inline]
+    #[must_use]
+    pub fn command_name_is_renamed(&self, name: &[u8]) -> bool {
+        self.server.store.command_renames.resolve(name) != CommandRename::Unchanged
+    }
+
+    /// Client-ingress resolution of `rename-command` names, applied only on
+    /// the network entry points: internal callers (startup ACL LOAD, shutdown
+    /// SAVE, blocked-command retries, AOF and replication replay) always use
+    /// canonical names, as upstream's `lookupCommandOrOriginal` does. Queued
+    /// MULTI bodies therefore hold canonical names by the time EXEC runs.
+    fn execute_client_argv_renamed(
+        &mut self,
+        frame: Option<&RespFrame>,
+        argv: &[Vec<u8>],
+        now_ms: u64,
+        unix_time_us: u64,
+    ) -> RespFrame {
+        if let Some(renamed) = self.resolve_renamed_command(argv) {
+            return self.execute_dispatch(None, Ok(&renamed), now_ms, Some(unix_time_us));
+        }
+        if let Some(name) = argv.first()
+            && self.server.store.command_renames.resolve(name) == CommandRename::Hidden
+        {
+            let err = unknown_command_error(argv);
+            return self.execute_dispatch(frame, Err(err), now_ms, Some(unix_time_us));
+        }
+        self.execute_dispatch(frame, Ok(argv), now_ms, Some(unix_time_us))
+    }
+
     pub fn execute_plain_set_borrowed(
         &mut self,
         key: &[u8],
@@ -9792,6 +10330,9 @@ impl Runtime {
             || self.is_client_paused(now_ms)
             || self.active_disk_write_denial().is_some()
             || self.server.maxmemory_bytes != 0
+            // `Store::set_plain_borrowed` skips the StoreLimits quota checks the
+            // generic SET/GETSET/MSET/MSETNX handlers run.
+            || !self.server.store.limits().is_unlimited()
             || self.server.min_replicas_to_write != 0
             || self.server.aof_path.is_some()
             || !matches!(
@@ -10650,12 +11191,15 @@ impl Runtime {
         values: &[&[u8]],
         now_ms: u64,
     ) -> Option<RespFrame> {
-        if self.policy.gate.max_array_len < 3
+        if (cmd == PlainKeyedValuesCmd::Pfadd && !fr_command::HYPERLOGLOG_ENABLED)
+            || self.policy.gate.max_array_len < 3
             || self.policy.gate.max_bulk_len < cmd.name_upper().len()
             || key.len() > self.policy.gate.max_bulk_len
             || values
                 .iter()
                 .any(|v| v.len() > self.policy.gate.max_bulk_len)
+            || (cmd == PlainKeyedValuesCmd::Sadd
+                && !self.server.store.elements_per_call_allowed(values.len()))
         {
             return None;
         }
@@ -10881,6 +11425,7 @@ impl Runtime {
             || pairs
                 .iter()
                 .any(|p| p.len() > self.policy.gate.max_bulk_len)
+            || !self.server.store.elements_per_call_allowed(pairs.len() / 2)
         {
             return None;
         }
@@ -11070,6 +11615,7 @@ impl Runtime {
             || pairs
                 .iter()
                 .any(|p| p.len() > self.policy.gate.max_bulk_len)
+            || !self.server.store.elements_per_call_allowed(pairs.len() / 2)
         {
             return None;
         }
@@ -11230,6 +11776,7 @@ impl Runtime {
             || pairs
                 .iter()
                 .any(|p| p.len() > self.policy.gate.max_bulk_len)
+            || !self.server.store.elements_per_call_allowed(pairs.len() / 2)
         {
             return None;
         }
@@ -11376,7 +11923,8 @@ impl Runtime {
         member: &[u8],
         now_ms: u64,
     ) -> Option<RespFrame> {
-        if self.policy.gate.max_array_len < 4
+        if !fr_command::GEO_ENABLED
+            || self.policy.gate.max_array_len < 4
             || self.policy.gate.max_bulk_len < b"GEOADD".len()
             || key.len() > self.policy.gate.max_bulk_len
             || lon.len() > self.policy.gate.max_bulk_len
@@ -11539,7 +12087,8 @@ impl Runtime {
         value: &[u8],
         now_ms: u64,
     ) -> Option<RespFrame> {
-        if id_arg != b"*"
+        if !fr_command::STREAMS_ENABLED
+            || id_arg != b"*"
             || self.policy.gate.max_array_len < 5
             || self.policy.gate.max_bulk_len < b"XADD".len()
             || key.len() > self.policy.gate.max_bulk_len
@@ -11551,7 +12100,10 @@ impl Runtime {
         // Resolve the auto-id BEFORE any state change (xlast_id_no_stat is a
         // read-only write-lookup); bail to the generic path on a wrongtype/lookup
         // error (exact reply) or id-space exhaustion.
-        let last_id = self.server.store.xlast_id_no_stat(key, now_ms).ok()?;
+        let last_id = match self.server.store.xlast_id_no_stat(key, now_ms).ok()? {
+            Some(last_id) => Some(last_id),
+            None => self.server.store.stream_preserved_last_id(key, now_ms),
+        };
         let id = fr_command::next_auto_stream_id(last_id, now_ms)?;
         if !self.plain_borrowed_default_key_write_allows(now_ms) {
             return None;
@@ -12395,6 +12947,7 @@ impl Runtime {
             || pairs
                 .iter()
                 .any(|p| p.len() > self.policy.gate.max_bulk_len)
+            || !self.server.store.elements_per_call_allowed(pairs.len() / 2)
         {
             return None;
         }
@@ -12848,7 +13401,7 @@ impl Runtime {
         if !suppress_reply {
             match msg {
                 // `PING` -> simple string `+PONG` (protocol-invariant RESP2/RESP3).
-                None => out.extend_from_slice(b"+PONG\r\n"),
+                None => out.extend_from_slice(fr_protocol::REPLY_PONG),
                 // `PING <msg>` -> bulk echo of the message.
                 Some(m) => encode_bulk_string_slice(Some(m), resp3, out),
             }
@@ -13104,6 +13657,64 @@ impl Runtime {
         Some(())
     }
 
+    /// Borrow-encoded KEYS fast path: streams the matching key names straight
+    /// into `out` (RESP array `*N` in both protocols + one bulk string per key
+    /// by borrow) via `Store::keys_borrow_scan`, so `KEYS *` over a large
+    /// keyspace never builds a `Vec<Vec<u8>>` of names or a `Vec<RespFrame>`.
+    /// Same gate as the SMEMBERS fast path (which also pins db 0); KEYS cannot
+    /// fail once the arity is right, so there is no error reply to account.
+    pub fn execute_plain_keys_borrowed_into(
+        &mut self,
+        pattern: &[u8],
+        now_ms: u64,
+        out: &mut Vec<u8>,
+    ) -> Option<()> {
+        if self.policy.gate.max_array_len < 2
+            || self.policy.gate.max_bulk_len < b"KEYS".len()
+            || pattern.len() > self.policy.gate.max_bulk_len
+            || !self.plain_borrowed_default_key_read_allows(now_ms)
+        {
+            return None;
+        }
+
+        let packet_id =
+            self.plain_read_borrowed_preamble("keys", b"KEYS".len() + pattern.len(), now_ms);
+        let suppress_reply = self.suppress_current_network_reply();
+        let db = self.session.selected_db;
+        let start = self.chained_command_start();
+        let reply_at = out.len();
+        let matches = self
+            .server
+            .store
+            .keys_borrow_scan(db, pattern, now_ms, |key| {
+                if !suppress_reply {
+                    fr_protocol::encode_bulk_string_slice(Some(key), false, out);
+                }
+            });
+        if !suppress_reply {
+            // The scan globs each key once, so the count is only known now:
+            // append the header and rotate it in front of the keys.
+            let header_at = out.len();
+            fr_protocol::encode_aggregate_header(matches, false, out);
+            let header_len = out.len() - header_at;
+            out[reply_at..].rotate_right(header_len);
+        }
+        let elapsed_us = self.finish_chained_command(start);
+        self.record_plain_zremrange_borrowed_metrics(
+            "keys",
+            "KEYS",
+            || vec![b"KEYS".to_vec(), pattern.to_vec()],
+            elapsed_us,
+            now_ms,
+            packet_id,
+            false,
+        );
+
+        let lazy_evicted = self.server.store.take_lazy_expired_propagation();
+        self.server.propagate_expired_key_deletions(&lazy_evicted);
+        Some(())
+    }
+
     /// (CrimsonHawk) SUNION fast reply path: streams the sorted union members straight into `out` (RESP3
     /// Set `~N` / RESP2 array `*N`, byte-identical to fr-command `sunion`'s RespFrame::Set/Array) via
     /// `Store::sunion_borrow_scan`, which borrow-sinks generic members with no owned `Vec<Vec<u8>>`. Gate +
@@ -13852,11 +14463,17 @@ impl Runtime {
     /// observes (non-db0, NO-TOUCH, transactions, subscription, pause,
     /// maxmemory, AOF, replica role/links, blocked clients, keyspace
     /// notifications, client tracking, monitors, scripts) must be inactive.
-    /// Per-command argv-shape/gate checks are the caller's responsibility.
+    /// Per-command argv-shape/gate checks are the caller's responsibility, and
+    /// so is `rename-command`: the fast paths match literal names, so a caller
+    /// must route any name [`Self::command_name_is_renamed`] reports through
+    /// the generic (rename-resolving) path instead.
     /// (frankenredis-uz39v) KEEP IN SYNC across all borrowed read fast paths.
     fn plain_borrowed_default_key_read_allows(&mut self, now_ms: u64) -> bool {
+        // The borrow-encoded collection replies stream in storage order, so the
+        // deterministic-iteration switch takes the generic path.
         if self.session.requires_auth(&self.server.auth_state)
             || !self.current_acl_allows_default_key_command()
+            || self.server.store.deterministic_iteration()
         {
             return false;
         }
@@ -15722,7 +16339,7 @@ impl Runtime {
                 if !suppress_reply {
                     fr_protocol::encode_aggregate_header(flags.len(), false, out);
                     for flag in flags {
-                        out.extend_from_slice(if flag { b":1\r\n" } else { b":0\r\n" });
+                        out.extend_from_slice(fr_protocol::bool_integer_reply(flag));
                     }
                 }
             }
@@ -17355,7 +17972,7 @@ impl Runtime {
             .push((physical, fp, mod_count));
         self.session.transaction_state.mark_active();
         if !suppress_reply {
-            out.extend_from_slice(b"+OK\r\n");
+            out.extend_from_slice(fr_protocol::REPLY_OK);
         }
         let elapsed_us = self.finish_chained_command(start);
 
@@ -17450,7 +18067,7 @@ impl Runtime {
         self.session.transaction_state.watch_dirty = false;
         self.session.transaction_state.mark_pristine();
         if !suppress_reply {
-            out.extend_from_slice(b"+OK\r\n");
+            out.extend_from_slice(fr_protocol::REPLY_OK);
         }
         let elapsed_us = self.finish_chained_command(start);
 
@@ -17727,6 +18344,29 @@ impl Runtime {
         range: PlainBitcountRange<'_>,
         now_ms: u64,
     ) -> Option<RespFrame> {
+        match self.start_plain_bitcount_borrowed(key, range, None, now_ms)? {
+            PlainBitcountStep::Reply(reply) => Some(reply),
+            PlainBitcountStep::Offload(job) => {
+                let started = Instant::now();
+                let count = job.run();
+                let elapsed_us = started.elapsed().as_micros() as u64;
+                Some(self.finish_plain_bitcount_job(job, count, elapsed_us, now_ms))
+            }
+        }
+    }
+
+    /// [`Self::execute_plain_bitcount_borrowed`] that leaves the counting to
+    /// the caller when the key holds a string of at least `offload_min_len`
+    /// bytes. Everything up to the count (stats, session, active expiry, the
+    /// keyspace lookup, LRU/LFU) happens here, in the same order as the inline
+    /// path, so the only difference a client can see is which thread counted.
+    pub fn start_plain_bitcount_borrowed(
+        &mut self,
+        key: &[u8],
+        range: PlainBitcountRange<'_>,
+        offload_min_len: Option<usize>,
+        now_ms: u64,
+    ) -> Option<PlainBitcountStep> {
         // Parse the range BEFORE any side effect so a parse error / bad unit
         // declines cleanly (the generic path owns those error replies).
         let (start_idx, end_idx, unit, early_zero, argc) = match range {
@@ -17783,6 +18423,33 @@ impl Runtime {
         let reply = match self.server.store.key_type(key, now_ms) {
             None => RespFrame::Integer(0),
             Some("string") if early_zero => RespFrame::Integer(0),
+            Some("string")
+                if offload_min_len.is_some_and(|min_len| {
+                    self.server
+                        .store
+                        .string_len_no_stats(key, now_ms)
+                        .is_ok_and(|len| len >= min_len)
+                }) =>
+            {
+                match self.server.store.string_snapshot(key, now_ms) {
+                    Ok(Some(bytes)) => {
+                        let setup_us = start.elapsed().as_micros() as u64;
+                        let lazy_evicted = self.server.store.take_lazy_expired_propagation();
+                        self.server.propagate_expired_key_deletions(&lazy_evicted);
+                        return Some(PlainBitcountStep::Offload(PlainBitcountJob {
+                            bytes,
+                            argv: plain_bitcount_owned_argv(key, range),
+                            start: start_idx,
+                            end: end_idx,
+                            unit,
+                            packet_id,
+                            setup_us,
+                        }));
+                    }
+                    Ok(None) => RespFrame::Integer(0),
+                    Err(err) => CommandError::Store(err).to_resp(),
+                }
+            }
             Some("string") => {
                 match self
                     .server
@@ -17796,8 +18463,45 @@ impl Runtime {
             Some(_) => CommandError::Store(fr_store::StoreError::WrongType).to_resp(),
         };
         let elapsed_us = self.finish_chained_command(start);
-        let failed = matches!(reply, RespFrame::Error(_));
+        Some(PlainBitcountStep::Reply(
+            self.finish_plain_bitcount_borrowed(key, range, reply, elapsed_us, now_ms, packet_id),
+        ))
+    }
+
+    /// Complete an offloaded `BITCOUNT` with the worker's result, recording
+    /// the slowlog, latency and error stats the inline path would have. Call
+    /// it with the owning client's session swapped in.
+    pub fn finish_plain_bitcount_job(
+        &mut self,
+        job: PlainBitcountJob,
+        count: Result<usize, fr_store::StoreError>,
+        elapsed_us: u64,
+        now_ms: u64,
+    ) -> RespFrame {
+        let reply = match count {
+            Ok(count) => RespFrame::Integer(i64::try_from(count).unwrap_or(i64::MAX)),
+            Err(err) => CommandError::Store(err).to_resp(),
+        };
+        self.finish_plain_bitcount_borrowed(
+            &job.argv[1],
+            job.range(),
+            reply,
+            job.setup_us.saturating_add(elapsed_us),
+            now_ms,
+            job.packet_id,
+        )
+    }
 
+    fn finish_plain_bitcount_borrowed(
+        &mut self,
+        key: &[u8],
+        range: PlainBitcountRange<'_>,
+        reply: RespFrame,
+        elapsed_us: u64,
+        now_ms: u64,
+        packet_id: u64,
+    ) -> RespFrame {
+        let failed = matches!(reply, RespFrame::Error(_));
         self.record_plain_bitcount_borrowed_metrics(
             key, range, elapsed_us, now_ms, packet_id, failed,
         );
@@ -17822,7 +18526,7 @@ impl Runtime {
             }
         }
 
-        Some(reply)
+        reply
     }
 
     fn record_plain_bitcount_borrowed_metrics(
@@ -18014,9 +18718,9 @@ impl Runtime {
 
     /// Shared core for the four no-flag `*3 key time` expiry-set commands. Mirrors
     /// the generic expire_like(kind) + no-option apply_expiry_with_options EXACTLY:
-    /// the same per-kind overflow validation, the same i128 deadline
-    /// (deadline_from_expire_kind), then pttl_no_stats (missing yields 0) followed
-    /// by store.expire_at_milliseconds, which handles delete-on-past, dirty, and
+    /// the same per-kind overflow validation, the same GT/LT comparison against
+    /// the stored deadline (expiretime_no_stats; missing yields 0), then
+    /// store.expire_at_milliseconds, which handles delete-on-past, dirty, and
     /// the "del" keyspace event. Returns None (defers to the generic, no side
     /// effects) on a malformed / out-of-range time so the canonical "value is not
     /// an integer" / "invalid expire time in '<cmd>' command" error is emitted
@@ -18055,8 +18759,8 @@ impl Runtime {
         };
         // Same validation as expire_like(kind); defer on any failure for exact errors.
         let raw_time = parse_i64_arg(time_arg).ok()?;
-        kind.validated_when_ms(raw_time, now_ms)?; // overflow -> defer ("invalid expire time")
-        let when_ms_i128 = kind.deadline_ms_i128(raw_time, now_ms);
+        // overflow -> defer ("invalid expire time")
+        let when_ms = kind.validated_when_ms(raw_time, now_ms)?;
 
         self.server.store.stat_total_commands_processed += 1;
         if self.session.connected_at_ms == 0 {
@@ -18074,43 +18778,33 @@ impl Runtime {
         let _ = self.run_active_expire_cycle(now_ms, ActiveExpireCycleKind::Fast);
 
         let start = self.chained_command_start();
-        // Mirror fr_command::apply_expiry_with_options exactly (NX/XX/GT/LT use the
-        // non-counting PTTL so the comparison read does not bump keyspace_hits).
+        // Mirror fr_command::apply_expiry_with_options exactly (NX/XX/GT/LT read the
+        // stored deadline without bumping keyspace_hits and compare it unclamped).
         let applied = {
-            let current_remaining_ms = match self.server.store.pttl_no_stats(key, now_ms) {
-                fr_store::PttlValue::KeyMissing => None,
-                fr_store::PttlValue::NoExpiry => Some(None),
-                fr_store::PttlValue::Remaining(ms) => Some(Some(ms)),
+            let current_when_ms = match self.server.store.expiretime_no_stats(key, now_ms) {
+                fr_store::ExpireTimeValue::KeyMissing => None,
+                fr_store::ExpireTimeValue::NoExpiry => Some(None),
+                fr_store::ExpireTimeValue::ExpiresAt(ms) => Some(Some(i128::from(ms))),
             };
-            match current_remaining_ms {
+            let new_when_ms = i128::from(when_ms);
+            match current_when_ms {
                 None => false, // key missing
-                Some(remaining) => 'apply: {
-                    if nx && remaining.is_some() {
+                Some(current) => 'apply: {
+                    if nx && current.is_some() {
                         break 'apply false;
                     }
-                    if xx && remaining.is_none() {
+                    if xx && current.is_none() {
                         break 'apply false;
                     }
-                    if gt {
-                        let Some(rem) = remaining else {
-                            break 'apply false;
-                        };
-                        let cur = i128::from(now_ms).saturating_add(i128::from(rem));
-                        if when_ms_i128 <= cur {
-                            break 'apply false;
-                        }
+                    if gt && current.is_none_or(|cur| new_when_ms <= cur) {
+                        break 'apply false;
                     }
-                    if lt && let Some(rem) = remaining {
-                        let cur = i128::from(now_ms).saturating_add(i128::from(rem));
-                        if when_ms_i128 >= cur {
-                            break 'apply false;
-                        }
+                    if lt && current.is_some_and(|cur| new_when_ms >= cur) {
+                        break 'apply false;
                     }
-                    self.server.store.expire_at_milliseconds(
-                        key,
-                        clamp_i128_to_i64_runtime(when_ms_i128),
-                        now_ms,
-                    )
+                    self.server
+                        .store
+                        .expire_at_milliseconds(key, when_ms, now_ms)
                 }
             }
         };
@@ -26295,8 +26989,8 @@ impl Runtime {
     /// there; defer on a NaN/Inf increment so the generic's pre-lookup "value is NaN
     /// or Infinity" is emitted; then `store.hincrbyfloat_text` (f80 text path) →
     /// BulkString(new), with the HINCRBYFLOAT-SPECIFIC error mapping (IncrFloatNaN →
-    /// "value is NaN or Infinity", ValueNotFloat → "hash value is not a float", else
-    /// CommandError::Store). Gated by the WRITE predicate.
+    /// "value is NaN or Infinity", else CommandError::Store, which renders
+    /// HashValueNotFloat as "hash value is not a float"). Gated by the WRITE predicate.
     pub fn execute_plain_hincrbyfloat_borrowed(
         &mut self,
         key: &[u8],
@@ -26342,9 +27036,6 @@ impl Runtime {
             Err(fr_store::StoreError::IncrFloatNaN) => {
                 RespFrame::Error("ERR value is NaN or Infinity".to_string())
             }
-            Err(fr_store::StoreError::ValueNotFloat) => {
-                RespFrame::Error("ERR hash value is not a float".to_string())
-            }
             Err(err) => CommandError::Store(err).to_resp(),
         };
         let failed = matches!(reply, RespFrame::Error(_));
@@ -27240,7 +27931,9 @@ impl Runtime {
         key: &[u8],
         now_ms: u64,
     ) -> bool {
-        if self.policy.gate.max_array_len < 2
+        if (matches!(cmd, PlainCardinalityCmd::Xlen) && !fr_command::STREAMS_ENABLED)
+            || (matches!(cmd, PlainCardinalityCmd::Pfcount) && !fr_command::HYPERLOGLOG_ENABLED)
+            || self.policy.gate.max_array_len < 2
             || self.policy.gate.max_bulk_len < cmd.name_upper().len()
             || key.len() > self.policy.gate.max_bulk_len
         {
@@ -27342,7 +28035,8 @@ impl Runtime {
         m2: &[u8],
         now_ms: u64,
     ) -> bool {
-        if self.policy.gate.max_array_len < 4
+        if !fr_command::GEO_ENABLED
+            || self.policy.gate.max_array_len < 4
             || self.policy.gate.max_bulk_len < b"GEODIST".len()
             || key.len() > self.policy.gate.max_bulk_len
             || m1.len() > self.policy.gate.max_bulk_len
@@ -27850,7 +28544,8 @@ impl Runtime {
         members: &[&[u8]],
         now_ms: u64,
     ) -> bool {
-        if members.is_empty()
+        if !fr_command::GEO_ENABLED
+            || members.is_empty()
             || self.policy.gate.max_array_len < members.len().saturating_add(2)
             || self.policy.gate.max_bulk_len < b"GEOPOS".len()
             || key.len() > self.policy.gate.max_bulk_len
@@ -28136,7 +28831,8 @@ impl Runtime {
         count_arg: Option<&[u8]>,
         now_ms: u64,
     ) -> Option<RespFrame> {
-        if self.policy.gate.max_bulk_len < b"XRANGE".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XRANGE".len()
             || key.len() > self.policy.gate.max_bulk_len
             || start_arg.len() > self.policy.gate.max_bulk_len
             || end_arg.len() > self.policy.gate.max_bulk_len
@@ -28230,7 +28926,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_bulk_len < b"XRANGE".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XRANGE".len()
             || key.len() > self.policy.gate.max_bulk_len
             || start_arg.len() > self.policy.gate.max_bulk_len
             || end_arg.len() > self.policy.gate.max_bulk_len
@@ -28273,8 +28970,7 @@ impl Runtime {
                         }
                         fr_store::XrangeReplyEvent::RecordStart(id, pairs) => {
                             fr_protocol::encode_aggregate_header(2, false, out);
-                            let id_bytes = fr_command::format_stream_id(id);
-                            fr_protocol::encode_bulk_string_slice(Some(&id_bytes), false, out);
+                            fr_command::encode_stream_id_bulk(id, out);
                             fr_protocol::encode_aggregate_header(pairs * 2, false, out);
                         }
                         fr_store::XrangeReplyEvent::Field(f) => {
@@ -28337,7 +29033,8 @@ impl Runtime {
         count_arg: Option<&[u8]>,
         now_ms: u64,
     ) -> Option<RespFrame> {
-        if self.policy.gate.max_bulk_len < b"XREVRANGE".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XREVRANGE".len()
             || key.len() > self.policy.gate.max_bulk_len
             || end_arg.len() > self.policy.gate.max_bulk_len
             || start_arg.len() > self.policy.gate.max_bulk_len
@@ -28430,7 +29127,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_bulk_len < b"XREVRANGE".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XREVRANGE".len()
             || key.len() > self.policy.gate.max_bulk_len
             || end_arg.len() > self.policy.gate.max_bulk_len
             || start_arg.len() > self.policy.gate.max_bulk_len
@@ -28473,8 +29171,7 @@ impl Runtime {
                         }
                         fr_store::XrangeReplyEvent::RecordStart(id, pairs) => {
                             fr_protocol::encode_aggregate_header(2, false, out);
-                            let id_bytes = fr_command::format_stream_id(id);
-                            fr_protocol::encode_bulk_string_slice(Some(&id_bytes), false, out);
+                            fr_command::encode_stream_id_bulk(id, out);
                             fr_protocol::encode_aggregate_header(pairs * 2, false, out);
                         }
                         fr_store::XrangeReplyEvent::Field(f) => {
@@ -28541,7 +29238,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_bulk_len < b"XREAD".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XREAD".len()
             || key.len() > self.policy.gate.max_bulk_len
             || id_arg.len() > self.policy.gate.max_bulk_len
             || count_arg.is_some_and(|c| c.len() > self.policy.gate.max_bulk_len)
@@ -28628,12 +29326,7 @@ impl Runtime {
                                 }
                                 fr_store::XrangeReplyEvent::RecordStart(id, pairs) => {
                                     fr_protocol::encode_aggregate_header(2, false, out);
-                                    let id_bytes = fr_command::format_stream_id(id);
-                                    fr_protocol::encode_bulk_string_slice(
-                                        Some(&id_bytes),
-                                        false,
-                                        out,
-                                    );
+                                    fr_command::encode_stream_id_bulk(id, out);
                                     fr_protocol::encode_aggregate_header(pairs * 2, false, out);
                                 }
                                 fr_store::XrangeReplyEvent::Field(f) => {
@@ -28702,7 +29395,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_bulk_len < b"XREAD".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XREAD".len()
             || keys.iter().any(|k| k.len() > self.policy.gate.max_bulk_len)
             || ids.iter().any(|i| i.len() > self.policy.gate.max_bulk_len)
             || count_arg.is_some_and(|c| c.len() > self.policy.gate.max_bulk_len)
@@ -28799,12 +29493,7 @@ impl Runtime {
                             }
                             fr_store::XrangeReplyEvent::RecordStart(id, pairs) => {
                                 fr_protocol::encode_aggregate_header(2, false, &mut chunk);
-                                let id_bytes = fr_command::format_stream_id(id);
-                                fr_protocol::encode_bulk_string_slice(
-                                    Some(&id_bytes),
-                                    false,
-                                    &mut chunk,
-                                );
+                                fr_command::encode_stream_id_bulk(id, &mut chunk);
                                 fr_protocol::encode_aggregate_header(pairs * 2, false, &mut chunk);
                             }
                             fr_store::XrangeReplyEvent::Field(f) => {
@@ -28900,7 +29589,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_bulk_len < b"XREADGROUP".len()
+        if !fr_command::STREAMS_ENABLED
+            || self.policy.gate.max_bulk_len < b"XREADGROUP".len()
             || key.len() > self.policy.gate.max_bulk_len
             || group.len() > self.policy.gate.max_bulk_len
             || consumer.len() > self.policy.gate.max_bulk_len
@@ -28978,14 +29668,12 @@ impl Runtime {
                     }
                     fr_store::XreadgroupHistEvent::RecordStart(id, pairs) => {
                         fr_protocol::encode_aggregate_header(2, false, out);
-                        let id_bytes = fr_command::format_stream_id(id);
-                        fr_protocol::encode_bulk_string_slice(Some(&id_bytes), false, out);
+                        fr_command::encode_stream_id_bulk(id, out);
                         fr_protocol::encode_aggregate_header(pairs * 2, false, out);
                     }
                     fr_store::XreadgroupHistEvent::RecordStartNil(id) => {
                         fr_protocol::encode_aggregate_header(2, false, out);
-                        let id_bytes = fr_command::format_stream_id(id);
-                        fr_protocol::encode_bulk_string_slice(Some(&id_bytes), false, out);
+                        fr_command::encode_stream_id_bulk(id, out);
                         // Tombstone value = nil array: `*-1` (RESP2) / `_` (RESP3).
                         out.extend_from_slice(if resp3 { b"_\r\n" } else { b"*-1\r\n" });
                     }
@@ -29085,7 +29773,8 @@ impl Runtime {
         resp3: bool,
         out: &mut Vec<u8>,
     ) -> Option<()> {
-        if self.policy.gate.max_array_len < 3
+        if !fr_command::GEO_ENABLED
+            || self.policy.gate.max_array_len < 3
             || self.policy.gate.max_bulk_len < b"GEOHASH".len()
             || key.len() > self.policy.gate.max_bulk_len
             || member.len() > self.policy.gate.max_bulk_len
@@ -33662,6 +34351,16 @@ impl Runtime {
                 }
                 argv
             }
+            Err(err @ CommandError::UnknownCommand { .. }) => {
+                // A `rename-command`-hidden name: upstream rejects it in
+                // processCommand before any other gate, flagging an open
+                // MULTI exactly like any other unknown command.
+                if self.session.transaction_state.in_transaction {
+                    self.session.transaction_state.exec_abort = true;
+                }
+                self.apply_existing_client_reply_suppression_to_undispatched_reply();
+                return err.to_resp();
+            }
             Err(_) => {
                 // Only reachable from owned/conformance callers (the server
                 // pre-validates and always passes Ok), so `frame` is Some here;
@@ -33715,6 +34414,8 @@ impl Runtime {
                 self.server.record_latency_sample(argv, elapsed_us, now_ms);
                 self.server
                     .record_command_histogram(argv, elapsed_us, &reply);
+                // Not admin, so upstream mirrors it to MONITOR (credentials redacted).
+                self.feed_monitors(argv, now_ms, self.session.selected_db);
                 return reply;
             }
             Some(RuntimeSpecialCommand::Hello) => {
@@ -33729,6 +34430,8 @@ impl Runtime {
                 self.server.record_latency_sample(argv, elapsed_us, now_ms);
                 self.server
                     .record_command_histogram(argv, elapsed_us, &reply);
+                // Not admin, so upstream mirrors it to MONITOR (credentials redacted).
+                self.feed_monitors(argv, now_ms, self.session.selected_db);
                 return reply;
             }
             _ => {}
@@ -33992,13 +34695,8 @@ impl Runtime {
                     && classify_runtime_special_command(cmd_bytes).is_none()
                 {
                     self.session.transaction_state.exec_abort = true;
-                    let cmd_str = std::str::from_utf8(cmd_bytes).unwrap_or("");
                     self.apply_existing_client_reply_suppression_to_undispatched_reply();
-                    return CommandError::UnknownCommand {
-                        command: fr_command::trim_and_cap_string(cmd_str, 128),
-                        args_preview: build_unknown_args_preview(argv),
-                    }
-                    .to_resp();
+                    return unknown_command_error(argv).to_resp();
                 }
                 // Validate arity before queueing (Redis rejects wrong arity
                 // immediately and sets EXECABORT).
@@ -34525,6 +35223,34 @@ impl Runtime {
                                         db,
                                     );
                                 }
+                            } else if argv
+                                .first()
+                                .is_some_and(|c| c.eq_ignore_ascii_case(b"RESTORE"))
+                                && !cmd_keys.is_empty()
+                            {
+                                // RESTORE with an ABSTTL deadline already in the
+                                // past creates nothing; reaching this dirty-gated
+                                // block without the key means REPLACE deleted the
+                                // old value, which upstream reports as "del".
+                                if self
+                                    .server
+                                    .store
+                                    .key_is_present(&encode_db_key(db, &cmd_keys[0]))
+                                {
+                                    self.server.store.notify_keyspace_event(
+                                        event_type,
+                                        event,
+                                        &cmd_keys[0],
+                                        db,
+                                    );
+                                } else {
+                                    self.server.store.notify_keyspace_event(
+                                        fr_store::NOTIFY_GENERIC,
+                                        "del",
+                                        &cmd_keys[0],
+                                        db,
+                                    );
+                                }
                             } else if argv
                                 .first()
                                 .is_some_and(|c| c.eq_ignore_ascii_case(b"MOVE"))
@@ -35200,6 +35926,13 @@ impl Runtime {
         self.server.monitor_clients.remove(&client_id);
     }
 
+    /// True while the current dispatch runs inside EXEC or `redis.call`, where
+    /// a blocking command's immediate reply is final (CLIENT_DENY_BLOCKING).
+    #[must_use]
+    pub fn blocking_denied(&self) -> bool {
+        self.server.store.dispatch_client_ctx.deny_blocking
+    }
+
     pub fn mark_client_blocked(&mut self, client_id: u64) {
         self.server.blocked_client_ids.insert(client_id);
         self.server.store.stat_blocked_clients = self.server.blocked_client_ids.len() as u64;
@@ -35247,6 +35980,8 @@ impl Runtime {
 
     fn feed_monitors_with_addr(&mut self, argv: &[Vec<u8>], now_ms: u64, db: usize, addr: &str) {
         use std::io::Write as _;
+        let redacted = redacted_argv(argv);
+        let argv = redacted.as_deref().unwrap_or(argv);
         let secs = now_ms / 1000;
         let usecs = (now_ms % 1000) * 1000;
         // Build directly into a Vec<u8>. The buffer is consumed as
@@ -35501,8 +36236,26 @@ impl Runtime {
         if !Self::command_uses_script_propagation(command) {
             return None;
         }
+        let records = std::mem::take(&mut self.server.store.script_propagation_records);
+        if !self.server.store.script_replicate_effects {
+            // Verbatim propagation replays the script itself. EVALSHA goes
+            // out as EVAL so a replica or AOF load without the script cached
+            // still runs it.
+            if eq_ascii_token(command, b"EVALSHA")
+                && let Some(script) = argv
+                    .get(1)
+                    .and_then(|sha| self.server.store.script_get(sha))
+            {
+                let mut eval = Vec::with_capacity(argv.len());
+                eval.push(b"EVAL".to_vec());
+                eval.push(script.to_vec());
+                eval.extend_from_slice(&argv[2..]);
+                return Some(vec![eval]);
+            }
+            return Some(vec![argv.to_vec()]);
+        }
         Some(
-            std::mem::take(&mut self.server.store.script_propagation_records)
+            records
                 .into_iter()
                 .filter_map(|record| (record.targets != 0).then_some(record.argv))
                 .collect(),
@@ -35526,6 +36279,9 @@ impl Runtime {
             || eq_ascii_token(command, b"EXEC")
             || eq_ascii_token(command, b"SELECT")
             || eq_ascii_token(command, b"SWAPDB")
+            // Only a verbatim-propagated script reaches the stream as EVAL;
+            // effects propagation records the script's writes instead.
+            || eq_ascii_token(command, b"EVAL")
         {
             return true;
         }
@@ -35660,6 +36416,8 @@ impl Runtime {
         ctx.client_reply.clone_from(&session.client_reply);
         ctx.client_no_evict = session.client_no_evict;
         ctx.client_no_touch = session.client_no_touch;
+        // Queued commands replay with CLIENT_DENY_BLOCKING (multi.c::execCommand).
+        ctx.deny_blocking = session.transaction_state.executing_exec;
         ctx.acl_checked_by_runtime = true;
     }
 
@@ -36050,6 +36808,9 @@ impl Runtime {
         for event in self.server.store.drain_pending_acl_log_events() {
             self.record_deferred_acl_log_event(event, now_ms);
         }
+        if self.server.store.take_repl_id_change_requested() {
+            self.server.replication_runtime_state.change_replid();
+        }
         if self.server.store.take_debug_reload_requested() {
             return Some(self.handle_debug_reload_requested(now_ms));
         }
@@ -36354,6 +37115,7 @@ impl Runtime {
             return build_hello_response(
                 self.session.resp_protocol_version,
                 self.session.client_id,
+                self.server.store.redis_version(),
             );
         }
 
@@ -36453,7 +37215,11 @@ impl Runtime {
             self.session.refresh_named_metadata_activity();
         }
         self.session.resp_protocol_version = protocol_version;
-        build_hello_response(protocol_version, self.session.client_id)
+        build_hello_response(
+            protocol_version,
+            self.session.client_id,
+            self.server.store.redis_version(),
+        )
     }
 
     fn authenticate_user(&mut self, username: &[u8], password: &[u8]) -> Result<(), AuthFailure> {
@@ -37233,10 +37999,7 @@ impl Runtime {
                 );
             };
 
-            let mut lines = Vec::new();
-            // We'll just write all parameters that are in SUPPORTED_CONFIG_PARAMETERS
-            // but use the live values from ServerState/Store when possible.
-
+            let mut current = Vec::new();
             // First, get all current values via CONFIG GET *
             let current_config =
                 self.handle_config_get(&[b"CONFIG".to_vec(), b"GET".to_vec(), b"*".to_vec()]);
@@ -37249,18 +38012,10 @@ impl Runtime {
             let mut record = |k: &RespFrame, v: &RespFrame| {
                 if let (RespFrame::BulkString(Some(key)), RespFrame::BulkString(Some(val))) = (k, v)
                 {
-                    let key = String::from_utf8_lossy(key);
-                    let val = String::from_utf8_lossy(val);
-                    // Some parameters shouldn't be in the config file or need special handling
-                    if key == "appendonly"
-                        || key == "dir"
-                        || key == "dbfilename"
-                        || key == "aclfile"
-                        || key == "save"
-                    {
-                        // these are usually fine
-                    }
-                    lines.push(format!("{key} {val}"));
+                    current.push((
+                        String::from_utf8_lossy(key).into_owned(),
+                        String::from_utf8_lossy(val).into_owned(),
+                    ));
                 }
             };
             match &current_config {
@@ -37279,7 +38034,10 @@ impl Runtime {
                 _ => {}
             }
 
-            let content = lines.join("\n") + "\n";
+            // A missing file rewrites from scratch, like upstream's
+            // rewriteConfigReadOldFile treating ENOENT as an empty config.
+            let existing = std::fs::read_to_string(path).unwrap_or_default();
+            let content = rewrite_config_file_content(&existing, &current);
             let tmp_path = path.with_extension("tmp");
             match (|| -> std::io::Result<()> {
                 use std::io::Write;
@@ -38055,6 +38813,10 @@ impl Runtime {
         let mut next_maxmemory_policy: Option<MaxmemoryPolicy> = None;
         let mut next_lfu_decay_time: Option<u64> = None;
         let mut next_lfu_log_factor: Option<u64> = None;
+        let mut next_stream_preserve_last_id_on_delete: Option<bool> = None;
+        let mut next_stream_preserve_last_id_window_ms: Option<u64> = None;
+        let mut next_max_elements_per_call: Option<usize> = None;
+        let mut next_stream_history_reads_keep_delivery_counts: Option<bool> = None;
         let mut next_slowlog_slower_than: Option<i64> = None;
         let mut next_slowlog_max_len: Option<usize> = None;
         let mut next_latency_monitor_threshold: Option<u64> = None;
@@ -38087,6 +38849,7 @@ impl Runtime {
         let mut next_appendfsync: Option<AppendFsyncMode> = None;
         let mut next_acl_pubsub_default: Option<AclPubsubDefault> = None;
         let mut next_keyspace_events: Option<u32> = None;
+        let mut next_server_version: Option<Option<String>> = None;
         let mut next_list_max_listpack_size: Option<i64> = None;
         let mut next_hll_sparse_max_bytes: Option<usize> = None;
         let mut next_rdb_path = self
@@ -38261,6 +39024,94 @@ impl Runtime {
                     .push(("stream-node-max-entries".to_string(), parsed.to_string()));
                 continue;
             }
+            if parameter.eq_ignore_ascii_case("stream-preserve-last-id-on-delete") {
+                let parsed = match std::str::from_utf8(&pair[1]) {
+                    Ok(s) if s.eq_ignore_ascii_case("yes") => true,
+                    Ok(s) if s.eq_ignore_ascii_case("no") => false,
+                    _ => {
+                        return config_set_failed(
+                            "stream-preserve-last-id-on-delete",
+                            "argument must be 'yes' or 'no'",
+                        );
+                    }
+                };
+                next_stream_preserve_last_id_on_delete = Some(parsed);
+                static_override_updates.push((
+                    "stream-preserve-last-id-on-delete".to_string(),
+                    if parsed {
+                        "yes".to_string()
+                    } else {
+                        "no".to_string()
+                    },
+                ));
+                continue;
+            }
+            if parameter.eq_ignore_ascii_case("stream-preserve-last-id-window") {
+                let parsed = match parse_i64_arg(&pair[1]) {
+                    Ok(value) if value >= 0 => value,
+                    Ok(_) => {
+                        return config_set_failed(
+                            "stream-preserve-last-id-window",
+                            "argument must be between 0 and 9223372036854775807 inclusive",
+                        );
+                    }
+                    Err(_) => {
+                        return config_set_failed(
+                            "stream-preserve-last-id-window",
+                            "argument couldn't be parsed into an integer",
+                        );
+                    }
+                };
+                next_stream_preserve_last_id_window_ms = Some(parsed as u64);
+                static_override_updates.push((
+                    "stream-preserve-last-id-window".to_string(),
+                    parsed.to_string(),
+                ));
+                continue;
+            }
+            if parameter.eq_ignore_ascii_case("max-elements-per-call") {
+                let parsed = match parse_i64_arg(&pair[1]) {
+                    Ok(value) if value >= 0 => value,
+                    Ok(_) => {
+                        return config_set_failed(
+                            "max-elements-per-call",
+                            "argument must be between 0 and 9223372036854775807 inclusive",
+                        );
+                    }
+                    Err(_) => {
+                        return config_set_failed(
+                            "max-elements-per-call",
+                            "argument couldn't be parsed into an integer",
+                        );
+                    }
+                };
+                next_max_elements_per_call = Some(parsed as usize);
+                static_override_updates
+                    .push(("max-elements-per-call".to_string(), parsed.to_string()));
+                continue;
+            }
+            if parameter.eq_ignore_ascii_case("stream-history-reads-keep-delivery-counts") {
+                let parsed = match std::str::from_utf8(&pair[1]) {
+                    Ok(s) if s.eq_ignore_ascii_case("yes") => true,
+                    Ok(s) if s.eq_ignore_ascii_case("no") => false,
+                    _ => {
+                        return config_set_failed(
+                            "stream-history-reads-keep-delivery-counts",
+                            "argument must be 'yes' or 'no'",
+                        );
+                    }
+                };
+                next_stream_history_reads_keep_delivery_counts = Some(parsed);
+                static_override_updates.push((
+                    "stream-history-reads-keep-delivery-counts".to_string(),
+                    if parsed {
+                        "yes".to_string()
+                    } else {
+                        "no".to_string()
+                    },
+                ));
+                continue;
+            }
             if parameter.eq_ignore_ascii_case("slowlog-log-slower-than") {
                 // Upstream config.c declares as INTEGER_CONFIG.
                 // (br-frankenredis-cfgmemvalue)
@@ -38971,6 +39822,28 @@ impl Runtime {
                 static_override_updates.push((canonical, parsed.to_string()));
                 continue;
             }
+            if parameter.eq_ignore_ascii_case("server-version") {
+                // Not an upstream config: the version presented as INFO's
+                // redis_version, HELLO's version and the LOLWUT footer, for
+                // clients that gate features on it. Empty restores the compat
+                // version. It lands verbatim in INFO lines, so keep it to
+                // version-string characters.
+                let value_bytes = &pair[1];
+                if value_bytes.len() > 64
+                    || value_bytes
+                        .iter()
+                        .any(|&c| !(c.is_ascii_alphanumeric() || b".+-_".contains(&c)))
+                {
+                    return config_set_failed(
+                        "server-version",
+                        "argument must be a version string of at most 64 alphanumeric, '.', '+', '-' or '_' characters",
+                    );
+                }
+                let value = String::from_utf8_lossy(value_bytes).into_owned();
+                next_server_version = Some((!value.is_empty()).then(|| value.clone()));
+                static_override_updates.push(("server-version".to_string(), value));
+                continue;
+            }
             if parameter.eq_ignore_ascii_case("cluster-announce-hostname") {
                 // (frankenredis-vqmkt) Upstream config.c declares
                 // cluster-announce-hostname as createStringConfig with
@@ -39997,6 +40870,24 @@ impl Runtime {
         if let Some(lfu_decay_time) = next_lfu_decay_time {
             self.server.store.lfu_decay_time = lfu_decay_time;
         }
+        if let Some(enabled) = next_stream_preserve_last_id_on_delete {
+            self.server
+                .store
+                .set_stream_preserve_last_id_on_delete(enabled);
+        }
+        if let Some(window_ms) = next_stream_preserve_last_id_window_ms {
+            self.server
+                .store
+                .set_stream_preserve_last_id_window_ms(window_ms);
+        }
+        if let Some(limit) = next_max_elements_per_call {
+            self.server.store.set_max_elements_per_call(limit);
+        }
+        if let Some(enabled) = next_stream_history_reads_keep_delivery_counts {
+            self.server
+                .store
+                .set_stream_history_reads_keep_delivery_counts(enabled);
+        }
         if let Some(threshold) = next_slowlog_slower_than {
             self.server.store.slowlog_log_slower_than_us = threshold;
         }
@@ -40097,6 +40988,9 @@ impl Runtime {
         if let Some(flags) = next_keyspace_events {
             self.server.store.notify_keyspace_events = flags;
         }
+        if let Some(server_version) = next_server_version {
+            self.server.store.redis_version_override = server_version;
+        }
         if rdb_path_changed {
             self.server.rdb_path = Some(next_rdb_path);
         }
@@ -40958,6 +41852,14 @@ impl Runtime {
 
     /// Record a command execution in the slow log if it exceeded the threshold.
     fn record_slowlog(&mut self, argv: &[Vec<u8>], duration_us: u64, now_ms: u64) {
+        // Some callers record unconditionally; only pay for the redaction
+        // check when the entry will actually be kept.
+        let threshold_us = self.server.store.slowlog_log_slower_than_us;
+        if threshold_us < 0 || (duration_us as i64) < threshold_us {
+            return;
+        }
+        let redacted = redacted_argv(argv);
+        let argv = redacted.as_deref().unwrap_or(argv);
         let client_address = if self.session.peer_addr.is_some() {
             self.refresh_dispatch_peer_addr_cache(self.session.peer_addr);
             self.dispatch_peer_addr_cache.as_bytes().to_vec()
@@ -41969,9 +42871,8 @@ impl Runtime {
         // (frankenredis-n9am7) Resume past the previous batch's last key via
         // scan_in_db (O(log n + count)) instead of re-materialising, re-glob/TYPE-
         // filtering and re-sorting the ENTIRE DB on every SCAN call — which was
-        // O(n)/call, i.e. O(n^2/count) to iterate a large DB. Byte-identical: it
-        // walks the same DB-scoped, glob+TYPE-filtered, sorted logical-key
-        // sequence and pages it by the same matched-count cursor index.
+        // O(n)/call, i.e. O(n^2/count) to iterate a large DB. COUNT bounds the
+        // keys examined per call; MATCH and TYPE filter what was examined.
         let (next_cursor, keys) = self.server.store.scan_in_db(
             self.session.selected_db,
             cursor,
@@ -42117,29 +43018,39 @@ impl Runtime {
         argv: &[Vec<u8>],
         now_ms: u64,
     ) -> Result<RespFrame, CommandError> {
-        let sections: Vec<&str> = argv[1..]
+        let requested: Vec<&str> = argv[1..]
             .iter()
             .map(|arg| std::str::from_utf8(arg).map_err(|_| CommandError::InvalidUtf8Argument))
             .collect::<Result<_, _>>()?;
-        let is_all = sections.is_empty()
-            || sections.iter().any(|section| {
+        let info = render_info_sections(&self.info_sections(&requested, now_ms));
+        // RESP3 clients expect verbatim string for INFO output
+        if self.session.resp_protocol_version == 3 {
+            Ok(RespFrame::Verbatim(info))
+        } else {
+            Ok(RespFrame::BulkString(Some(info.into_bytes())))
+        }
+    }
+
+    /// INFO's sections, structured, for `requested` section names exactly as
+    /// INFO takes them; [`render_info_sections`] of the result is the INFO
+    /// reply text. Lets a metrics exporter read fields without parsing it.
+    pub fn info_sections(&mut self, requested: &[&str], now_ms: u64) -> Vec<InfoSection> {
+        let is_all = requested.is_empty()
+            || requested.iter().any(|section| {
                 section.eq_ignore_ascii_case("all")
                     || section.eq_ignore_ascii_case("everything")
                     || section.eq_ignore_ascii_case("default")
             });
-        let section_requested = |name: &str| {
+        let want = |name: &str| {
             is_all
-                || sections
+                || requested
                     .iter()
                     .any(|section| section.eq_ignore_ascii_case(name))
         };
-        let is_persistence = section_requested("persistence");
-        let is_replication = section_requested("replication");
-        let is_keyspace = section_requested("keyspace");
         // (frankenredis-zfu61 perf) INFO memory reads the clients.normal /
         // clients.slaves aggregates; refresh them lazily here (only when the
         // memory section is actually requested) instead of on every command.
-        if section_requested("memory") {
+        if want("memory") {
             self.refresh_client_memory_aggregates();
         }
         // (frankenredis-infolazy) The O(n) INFO-only store aggregates are read by
@@ -42148,22 +43059,23 @@ impl Runtime {
         // when any of those sections is requested, instead of on every command.
         // Other commands leave these store fields untouched, exactly as the
         // borrowed fast paths already do.
-        if section_requested("clients") || section_requested("memory") || section_requested("stats")
-        {
+        if want("clients") || want("memory") || want("stats") {
             self.refresh_store_info_aggregates();
         }
-        // Upstream INFO emits Latencystats only when explicitly
-        // requested or as part of `all`/`everything`. The bare
-        // `INFO`/`default` form skips it. (br-frankenredis-infoorder)
-        let is_explicit_all = sections.iter().any(|section| {
+        // Upstream INFO emits Commandstats and Latencystats only when
+        // explicitly requested or as part of `all`/`everything`. The bare
+        // `INFO`/`default` form skips them, mirroring genInfoSectionDict's
+        // default_sections list (server.c:5416-5418). (frankenredis-ot3y,
+        // br-frankenredis-infoorder)
+        let is_explicit_all = requested.iter().any(|section| {
             section.eq_ignore_ascii_case("all") || section.eq_ignore_ascii_case("everything")
         });
-        let is_latencystats = is_explicit_all
-            || sections
-                .iter()
-                .any(|section| section.eq_ignore_ascii_case("latencystats"));
-
-        let mut info = Vec::new();
+        let want_explicit = |name: &str| {
+            is_explicit_all
+                || requested
+                    .iter()
+                    .any(|section| section.eq_ignore_ascii_case(name))
+        };
 
         // Upstream INFO emits sections in this fixed order:
         //   Server, Clients, Memory, Persistence, Stats, Replication,
@@ -42172,166 +43084,107 @@ impl Runtime {
         //   memory, stats, cpu, modules, errorstats, cluster} subset;
         //   fr-runtime owns the {persistence, replication, commandstats,
         //   latencystats, keyspace} subset.
-        // To match the upstream interleave we delegate to fr-command
-        // in pieces, slotting the runtime sections in between.
+        // To match the upstream interleave we ask fr-command for one
+        // section at a time, slotting the runtime sections in between.
         // (br-frankenredis-infoorder)
-
-        let dispatch_command_section =
-            |this: &mut Self, name: &str, info: &mut Vec<u8>| -> Result<(), CommandError> {
-                let argv: Vec<Vec<u8>> = vec![b"INFO".to_vec(), name.as_bytes().to_vec()];
-                let namespaced = this.namespace_argv_for_selected_db(&argv);
-                let mut reply = this.dispatch_with_client_context(&namespaced, now_ms)?;
-                this.strip_db_prefixes_from_frame(&mut reply);
-                // The per-section sub-call runs under the same client context,
-                // so when the client negotiated RESP3 (HELLO 3) the INFO reply
-                // is a Verbatim string, not a BulkString. Extract the body from
-                // either so RESP3 clients still receive every section — matching
-                // only BulkString silently dropped server/clients/memory/stats/
-                // cpu/modules/errorstats/cluster under RESP3.
-                match reply {
-                    RespFrame::BulkString(Some(bytes)) => info.extend_from_slice(&bytes),
-                    RespFrame::Verbatim(text) => info.extend_from_slice(text.as_bytes()),
-                    _ => {}
-                }
-                Ok(())
-            };
-
-        let want = |name: &str| {
-            is_all
-                || sections
-                    .iter()
-                    .any(|section| section.eq_ignore_ascii_case(name))
+        self.refresh_current_dispatch_client_context(now_ms);
+        let command_section = |this: &mut Self, name: &str, sections: &mut Vec<InfoSection>| {
+            sections.extend(info_sections(&mut this.server.store, &[name], now_ms));
         };
 
+        let mut sections = Vec::new();
         if want("server") {
-            dispatch_command_section(self, "server", &mut info)?;
+            command_section(self, "server", &mut sections);
         }
         if want("clients") {
-            dispatch_command_section(self, "clients", &mut info)?;
+            command_section(self, "clients", &mut sections);
         }
         if want("memory") {
-            dispatch_command_section(self, "memory", &mut info)?;
+            command_section(self, "memory", &mut sections);
         }
-        if is_persistence
-            && let RespFrame::BulkString(Some(bytes)) = self.handle_info_persistence_section()
-        {
-            info.extend_from_slice(&bytes);
+        if want("persistence") {
+            sections.push(self.info_persistence_section());
         }
         if want("stats") {
-            dispatch_command_section(self, "stats", &mut info)?;
+            command_section(self, "stats", &mut sections);
         }
-        if is_replication
-            && let RespFrame::BulkString(Some(bytes)) = self.handle_info_replication_section()
-        {
-            info.extend_from_slice(&bytes);
+        if want("replication") {
+            sections.push(self.info_replication_section());
         }
         if want("cpu") {
-            dispatch_command_section(self, "cpu", &mut info)?;
+            command_section(self, "cpu", &mut sections);
         }
         if want("modules") {
-            dispatch_command_section(self, "modules", &mut info)?;
-        }
-        // Upstream INFO emits Commandstats only when explicitly
-        // requested or as part of `all`/`everything`. The bare
-        // `INFO`/`default` form skips it, mirroring genInfoSectionDict's
-        // default_sections list (server.c:5416-5418). (frankenredis-ot3y)
-        let is_commandstats = is_explicit_all
-            || sections
-                .iter()
-                .any(|section| section.eq_ignore_ascii_case("commandstats"));
-        if is_commandstats
-            && let RespFrame::BulkString(Some(bytes)) = self.handle_info_commandstats_section()
-        {
-            info.extend_from_slice(&bytes);
+            command_section(self, "modules", &mut sections);
+        }
+        if want_explicit("commandstats") {
+            sections.push(self.info_commandstats_section());
         }
         if want("errorstats") {
-            dispatch_command_section(self, "errorstats", &mut info)?;
+            command_section(self, "errorstats", &mut sections);
         }
-        if is_latencystats
-            && let RespFrame::BulkString(Some(bytes)) = self.handle_info_latencystats_section()
-        {
-            info.extend_from_slice(&bytes);
+        if want_explicit("latencystats") {
+            sections.push(self.info_latencystats_section());
         }
         if want("cluster") {
-            dispatch_command_section(self, "cluster", &mut info)?;
-        }
-        if is_keyspace
-            && let RespFrame::BulkString(Some(bytes)) = self.handle_info_keyspace_section(now_ms)
-        {
-            info.extend_from_slice(&bytes);
+            command_section(self, "cluster", &mut sections);
         }
-
-        if info.is_empty() {
-            return Ok(RespFrame::BulkString(Some(Vec::new())));
-        }
-
-        // (frankenredis-b83fj) Each per-section emitter in
-        // fr-command::info_command_str appends a trailing "\r\n" as
-        // its inter-section separator. Concatenating N sections
-        // therefore leaves an extra "\r\n" after the last section's
-        // content. Upstream INFO emits the separator BETWEEN sections
-        // only — never after the last — so trim a single trailing
-        // "\r\n" pair (i.e. the final empty-line separator) before
-        // returning the bulk reply.
-        if info.ends_with(b"\r\n\r\n") {
-            info.truncate(info.len() - 2);
+        if want("keyspace") {
+            sections.push(self.info_keyspace_section(now_ms));
         }
-
-        // RESP3 clients expect verbatim string for INFO output
-        if self.session.resp_protocol_version == 3 {
-            Ok(RespFrame::Verbatim(
-                String::from_utf8_lossy(&info).into_owned(),
-            ))
-        } else {
-            Ok(RespFrame::BulkString(Some(info)))
+        // fr's own section; `all`/`everything` leave it out.
+        if requested
+            .iter()
+            .any(|section| section.eq_ignore_ascii_case("capabilities"))
+        {
+            command_section(self, "capabilities", &mut sections);
         }
+        sections
     }
 
-    fn handle_info_keyspace_section(&mut self, now_ms: u64) -> RespFrame {
-        let mut info = String::from("# Keyspace\r\n");
-        for db in 0..self.server.store.database_count {
+    fn info_keyspace_section(&mut self, now_ms: u64) -> InfoSection {
+        let mut section = InfoSection::new("Keyspace");
+        for db in 0..self.server.store.tracked_database_count() {
             let keys = self.server.store.dbsize_in_db(db);
             if keys > 0 {
                 let expires = self.server.store.expires_in_db(db);
                 let avg_ttl = self.server.store.avg_ttl_in_db(db, now_ms);
-                let _ = write!(
-                    info,
-                    "db{db}:keys={keys},expires={expires},avg_ttl={avg_ttl}\r\n"
+                section.field(
+                    format!("db{db}"),
+                    format!("keys={keys},expires={expires},avg_ttl={avg_ttl}"),
                 );
             }
         }
-        info.push_str("\r\n");
-        RespFrame::BulkString(Some(info.into_bytes()))
+        section
     }
 
-    fn handle_info_latencystats_section(&mut self) -> RespFrame {
-        let mut info = String::from("# Latencystats\r\n");
+    fn info_latencystats_section(&mut self) -> InfoSection {
+        let mut section = InfoSection::new("Latencystats");
         let histograms = self.server.store.all_command_histograms();
         for (cmd, hist) in histograms {
-            let _ = write!(info, "latency_percentiles_usec_{cmd}:");
+            let mut percentiles = String::new();
             for (i, &p) in self.server.latency_percentiles.iter().enumerate() {
                 if i > 0 {
-                    info.push(',');
+                    percentiles.push(',');
                 }
                 let val = histogram_percentile_us(hist, p);
                 // (frankenredis-7flec) Upstream server.c::
                 // fillPercentileDistributionLatencies uses %.3f for the
                 // percentile value (line 5287); mirror to keep INFO
                 // latencystats byte-equivalent.
-                let _ = write!(info, "p{p}={val:.3}");
+                let _ = write!(percentiles, "p{p}={val:.3}");
             }
-            info.push_str("\r\n");
+            section.field(format!("latency_percentiles_usec_{cmd}"), percentiles);
         }
-        info.push_str("\r\n");
-        RespFrame::BulkString(Some(info.into_bytes()))
+        section
     }
 
-    fn handle_info_commandstats_section(&mut self) -> RespFrame {
+    fn info_commandstats_section(&mut self) -> InfoSection {
         // Mirrors upstream server.c::genRedisInfoStringCommandStats
         // (5329-5353). All four metric columns now come from real
         // CommandHistogram counters tracked at command-dispatch time.
         // (frankenredis-ot3y, frankenredis-infosections)
-        let mut info = String::from("# Commandstats\r\n");
+        let mut section = InfoSection::new("Commandstats");
         // Skip commands whose only activity is `rejected_calls > 0` —
         // upstream omits a row entirely until at least one call lands.
         // We mirror that: emit a row when calls + rejected_calls +
@@ -42347,136 +43200,113 @@ impl Runtime {
             } else {
                 0.0
             };
-            let _ = write!(
-                info,
-                "cmdstat_{}:calls={},usec={},usec_per_call={:.2},rejected_calls={},failed_calls={}\r\n",
-                cmd.to_ascii_lowercase(),
-                hist.calls,
-                hist.total_usec,
-                usec_per_call,
-                hist.rejected_calls,
-                hist.failed_calls,
+            section.field(
+                format!("cmdstat_{}", cmd.to_ascii_lowercase()),
+                format!(
+                    "calls={},usec={},usec_per_call={:.2},rejected_calls={},failed_calls={}",
+                    hist.calls,
+                    hist.total_usec,
+                    usec_per_call,
+                    hist.rejected_calls,
+                    hist.failed_calls,
+                ),
             );
         }
-        info.push_str("\r\n");
-        RespFrame::BulkString(Some(info.into_bytes()))
-    }
-
-    fn handle_info_persistence_section(&mut self) -> RespFrame {
-        let mut info = String::from("# Persistence\r\n");
-        info.push_str("loading:0\r\n");
-        info.push_str("async_loading:0\r\n");
-        info.push_str("current_cow_peak:0\r\n");
-        info.push_str("current_cow_size:0\r\n");
-        info.push_str("current_cow_size_age:0\r\n");
-        info.push_str("current_fork_perc:0.00\r\n");
-        info.push_str("current_save_keys_processed:0\r\n");
-        info.push_str("current_save_keys_total:0\r\n");
-        let _ = write!(
-            info,
-            "rdb_changes_since_last_save:{}\r\n",
+        section
+    }
+
+    fn info_persistence_section(&mut self) -> InfoSection {
+        let mut section = InfoSection::new("Persistence");
+        section.field("loading", "0");
+        section.field("async_loading", "0");
+        section.field("current_cow_peak", "0");
+        section.field("current_cow_size", "0");
+        section.field("current_cow_size_age", "0");
+        section.field("current_fork_perc", "0.00");
+        section.field("current_save_keys_processed", "0");
+        section.field("current_save_keys_total", "0");
+        section.field(
+            "rdb_changes_since_last_save",
             self.server
                 .store
                 .dirty
-                .saturating_sub(self.server.store.dirty_at_last_save)
-        );
-        info.push_str("rdb_bgsave_in_progress:0\r\n");
-        let _ = write!(
-            info,
-            "rdb_last_save_time:{}\r\n",
-            self.server.store.last_save_time_sec
+                .saturating_sub(self.server.store.dirty_at_last_save),
         );
-        let _ = write!(
-            info,
-            "rdb_last_bgsave_status:{}\r\n",
+        section.field("rdb_bgsave_in_progress", "0");
+        section.field("rdb_last_save_time", self.server.store.last_save_time_sec);
+        section.field(
+            "rdb_last_bgsave_status",
             if self.server.store.stat_rdb_last_bgsave_ok {
                 "ok"
             } else {
                 "err"
-            }
+            },
         );
-        let _ = write!(
-            info,
-            "rdb_last_bgsave_time_sec:{}\r\n",
+        section.field(
+            "rdb_last_bgsave_time_sec",
             self.server
                 .store
                 .stat_rdb_last_bgsave_time_sec
-                .map_or(-1, |ts| ts as i64)
-        );
-        info.push_str("rdb_current_bgsave_time_sec:-1\r\n");
-        let _ = write!(info, "rdb_saves:{}\r\n", self.server.store.stat_rdb_saves);
-        info.push_str("rdb_last_cow_size:0\r\n");
-        let _ = write!(
-            info,
-            "rdb_last_load_keys_expired:{}\r\n",
-            self.server.store.stat_rdb_last_load_keys_expired
-        );
-        let _ = write!(
-            info,
-            "rdb_last_load_keys_loaded:{}\r\n",
-            self.server.store.stat_rdb_last_load_keys_loaded
-        );
-        let _ = write!(
-            info,
-            "aof_enabled:{}\r\n",
-            usize::from(self.server.aof_path.is_some())
-        );
-        let _ = write!(
-            info,
-            "aof_rewrite_in_progress:{}\r\n",
-            usize::from(self.server.aof_rewrite_pid.is_some())
-        );
-        let _ = write!(
-            info,
-            "aof_rewrite_scheduled:{}\r\n",
-            usize::from(self.server.aof_rewrite_scheduled)
-        );
-        let _ = write!(
-            info,
-            "aof_last_rewrite_time_sec:{}\r\n",
+                .map_or(-1, |ts| ts as i64),
+        );
+        section.field("rdb_current_bgsave_time_sec", "-1");
+        section.field("rdb_saves", self.server.store.stat_rdb_saves);
+        section.field("rdb_last_cow_size", "0");
+        section.field(
+            "rdb_last_load_keys_expired",
+            self.server.store.stat_rdb_last_load_keys_expired,
+        );
+        section.field(
+            "rdb_last_load_keys_loaded",
+            self.server.store.stat_rdb_last_load_keys_loaded,
+        );
+        section.field("aof_enabled", usize::from(self.server.aof_path.is_some()));
+        section.field(
+            "aof_rewrite_in_progress",
+            usize::from(self.server.aof_rewrite_pid.is_some()),
+        );
+        section.field(
+            "aof_rewrite_scheduled",
+            usize::from(self.server.aof_rewrite_scheduled),
+        );
+        section.field(
+            "aof_last_rewrite_time_sec",
             self.server
                 .store
                 .stat_aof_last_rewrite_time_sec
-                .map_or(-1, |ts| ts as i64)
+                .map_or(-1, |ts| ts as i64),
         );
-        info.push_str("aof_current_rewrite_time_sec:-1\r\n");
-        let _ = write!(
-            info,
-            "aof_last_bgrewrite_status:{}\r\n",
+        section.field("aof_current_rewrite_time_sec", "-1");
+        section.field(
+            "aof_last_bgrewrite_status",
             if self.server.store.stat_aof_last_bgrewrite_ok {
                 "ok"
             } else {
                 "err"
-            }
+            },
         );
         // aof_rewrites / aof_rewrites_consecutive_failures sit between
         // aof_last_bgrewrite_status and aof_last_write_status in upstream
         // server.c::genRedisInfoString.
-        let _ = write!(
-            info,
-            "aof_rewrites:{}\r\n",
-            self.server.store.stat_aof_rewrites
-        );
-        info.push_str("aof_rewrites_consecutive_failures:0\r\n");
-        let _ = write!(
-            info,
-            "aof_last_write_status:{}\r\n",
+        section.field("aof_rewrites", self.server.store.stat_aof_rewrites);
+        section.field("aof_rewrites_consecutive_failures", "0");
+        section.field(
+            "aof_last_write_status",
             if self.server.store.stat_aof_last_write_ok {
                 "ok"
             } else {
                 "err"
-            }
+            },
         );
-        info.push_str("aof_last_cow_size:0\r\n");
+        section.field("aof_last_cow_size", "0");
         // Module fork tracking is module-only in upstream; fr has no module
         // subsystem so always report no module fork in flight.
-        info.push_str("module_fork_in_progress:0\r\n");
-        info.push_str("module_fork_last_cow_size:0\r\n");
-        info.push_str("\r\n");
-        RespFrame::BulkString(Some(info.into_bytes()))
+        section.field("module_fork_in_progress", "0");
+        section.field("module_fork_last_cow_size", "0");
+        section
     }
 
-    fn handle_info_replication_section(&mut self) -> RespFrame {
+    fn info_replication_section(&mut self) -> InfoSection {
         self.server.refresh_replica_ack_snapshots();
         let backlog = &self.server.replication_runtime_state.backlog;
         let connected_replicas = self.server.replication_runtime_state.replicas.len();
@@ -42489,8 +43319,8 @@ impl Runtime {
         let primary_offset =
             i64::try_from(self.server.replication_ack_state.primary_offset.0).unwrap_or(i64::MAX);
 
-        let mut info = String::from("# Replication\r\n");
-        let _ = write!(info, "role:{role}\r\n");
+        let mut section = InfoSection::new("Replication");
+        section.field("role", role);
 
         match &self.server.replication_runtime_state.role {
             ReplicationRoleState::Master => {}
@@ -42504,21 +43334,16 @@ impl Runtime {
                 // reflects the live replica-read-only config (frankenredis-replro);
                 // replica_announced is not yet tracked, so it keeps upstream's
                 // "1" default. (frankenredis-e6iqd)
-                let _ = write!(
-                    info,
-                    "master_host:{host}\r\n\
-master_port:{port}\r\n\
-master_link_status:{master_link_status}\r\n\
-master_last_io_seconds_ago:{master_last_io_seconds_ago}\r\n\
-master_sync_in_progress:{master_sync_in_progress}\r\n\
-slave_read_repl_offset:{primary_offset}\r\n\
-slave_repl_offset:{primary_offset}\r\n\
-slave_priority:{}\r\n\
-slave_read_only:{}\r\n\
-replica_announced:1\r\n",
-                    self.server.replica_priority,
-                    i32::from(self.server.replica_read_only)
-                );
+                section.field("master_host", host);
+                section.field("master_port", port);
+                section.field("master_link_status", master_link_status);
+                section.field("master_last_io_seconds_ago", master_last_io_seconds_ago);
+                section.field("master_sync_in_progress", master_sync_in_progress);
+                section.field("slave_read_repl_offset", primary_offset);
+                section.field("slave_repl_offset", primary_offset);
+                section.field("slave_priority", self.server.replica_priority);
+                section.field("slave_read_only", i32::from(self.server.replica_read_only));
+                section.field("replica_announced", "1");
             }
         }
         for (i, replica) in self
@@ -42538,24 +43363,20 @@ replica_announced:1\r\n",
                 .primary_offset
                 .0
                 .saturating_sub(offset);
-            let _ = write!(
-                info,
-                "slave{i}:ip={ip},port={port},state={state},offset={offset},lag={lag}\r\n"
+            section.field(
+                format!("slave{i}"),
+                format!("ip={ip},port={port},state={state},offset={offset},lag={lag}"),
             );
         }
 
-        let _ = write!(info, "connected_slaves:{connected_replicas}\r\n");
-        info.push_str("master_failover_state:no-failover\r\n");
-        let _ = write!(info, "master_replid:{}\r\n", backlog.replid);
-        info.push_str("master_replid2:0000000000000000000000000000000000000000\r\n");
-        let _ = write!(info, "master_repl_offset:{primary_offset}\r\n");
-        info.push_str("second_repl_offset:-1\r\n");
-        let _ = write!(info, "repl_backlog_active:{backlog_active}\r\n");
-        let _ = write!(
-            info,
-            "repl_backlog_size:{}\r\n",
-            self.server.repl_backlog_size
-        );
+        section.field("connected_slaves", connected_replicas);
+        section.field("master_failover_state", "no-failover");
+        section.field("master_replid", &backlog.replid);
+        section.field("master_replid2", "0000000000000000000000000000000000000000");
+        section.field("master_repl_offset", primary_offset);
+        section.field("second_repl_offset", "-1");
+        section.field("repl_backlog_active", backlog_active);
+        section.field("repl_backlog_size", self.server.repl_backlog_size);
         // (frankenredis-f82ny) Vendored 7.2.4 keeps repl_backlog == NULL
         // until the first replica connects, so INFO replication reports
         // first_byte_offset:0 and histlen:0 in that state — even though
@@ -42571,13 +43392,9 @@ replica_announced:1\r\n",
         } else {
             (0, 0)
         };
-        let _ = write!(
-            info,
-            "repl_backlog_first_byte_offset:{display_first_byte}\r\n"
-        );
-        let _ = write!(info, "repl_backlog_histlen:{display_histlen}\r\n");
-        info.push_str("\r\n");
-        RespFrame::BulkString(Some(info.into_bytes()))
+        section.field("repl_backlog_first_byte_offset", display_first_byte);
+        section.field("repl_backlog_histlen", display_histlen);
+        section
     }
 
     fn handle_cluster_command(&mut self, argv: &[Vec<u8>], now_ms: u64) -> RespFrame {
@@ -43568,6 +44385,7 @@ replica_announced:1\r\n",
             }
         }
         self.session.transaction_state.executing_exec = false;
+        self.server.store.dispatch_client_ctx.deny_blocking = false;
         self.session.transaction_state.mark_pristine();
 
         if transaction_dirty {
@@ -44105,13 +44923,10 @@ fn dedup_keys_preserve_order(keys: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
         .collect()
 }
 
-fn build_hello_response(protocol_version: i64, client_id: u64) -> RespFrame {
+fn build_hello_response(protocol_version: i64, client_id: u64, version: &str) -> RespFrame {
     let fields = vec![
         (hello_bulk("server"), hello_bulk("redis")),
-        (
-            hello_bulk("version"),
-            hello_bulk(fr_store::REDIS_COMPAT_VERSION),
-        ),
+        (hello_bulk("version"), hello_bulk(version)),
         (hello_bulk("proto"), RespFrame::Integer(protocol_version)),
         (hello_bulk("id"), RespFrame::Integer(client_id as i64)),
         (hello_bulk("mode"), hello_bulk("standalone")),
@@ -44322,10 +45137,11 @@ fn store_to_rdb_entries(store: &mut Store, now_ms: u64) -> Vec<RdbEntry> {
     entries
 }
 
+/// Keys an RDB load applied, and keys it dropped as already expired.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-struct RdbLoadCounts {
-    loaded: usize,
-    expired: usize,
+pub struct RdbLoadCounts {
+    pub loaded: usize,
+    pub expired: usize,
 }
 
 fn apply_rdb_entries_to_store(
@@ -44334,6 +45150,10 @@ fn apply_rdb_entries_to_store(
     now_ms: u64,
 ) -> Result<RdbLoadCounts, PersistError> {
     let mut counts = RdbLoadCounts::default();
+    // Presize for the whole batch up front; entries that turn out to be
+    // expired only leave the reservation slightly generous.
+    store.reserve(entries.len());
+    store.reserve_expires(entries.iter().filter(|e| e.expire_ms.is_some()).count());
     for entry in entries {
         if entry
             .expire_ms
@@ -44945,13 +45765,15 @@ mod tests {
     use super::{
         ACL_FILE_NOT_CONFIGURED_ERR, AOF_DISK_ERROR_WRITE_DENIED, AclPubsubDefault, ClientSession,
         ClientUnblockMode, ClusterClientMode, ClusterSubcommand, DEFAULT_AUTH_USER,
-        OutputBufferClassLimit, PlainBitfieldGetCmd, PlainCardinalityCmd, PlainKeyMetaCmd,
-        PlainObjectStatCmd, PlainRandMemberCmd, RDB_DISK_ERROR_WRITE_DENIED, Runtime, ServerState,
+        OutputBufferClassLimit, PlainBitcountRange, PlainBitcountStep, PlainBitfieldGetCmd,
+        PlainCardinalityCmd, PlainKeyMetaCmd, PlainKeyedValuesCmd, PlainObjectStatCmd,
+        PlainRandMemberCmd, RDB_DISK_ERROR_WRITE_DENIED, Runtime, ServerState,
         acl_list_entries_from_rules, build_hello_response, canonical_static_config_param,
         canonicalize_acl_rules, classify_cluster_subcommand, classify_cluster_subcommand_linear,
         classify_runtime_special_command, classify_runtime_special_command_linear,
         client_wrong_subcommand_arity, config_set_failed, digest_bytes, parse_acl_key_selector,
-        parse_aof_history_seq, sha256_hex_bytes, store_to_rdb_entries, wrong_arity_error,
+        parse_aof_history_seq, redacted_argv, rewrite_config_file_content, sha256_hex_bytes,
+        store_to_rdb_entries, wrong_arity_error,
     };
 
     fn command(parts: &[&[u8]]) -> RespFrame {
@@ -45019,6 +45841,36 @@ mod tests {
         parts.iter().map(|part| (*part).to_vec()).collect()
     }
 
+    #[test]
+    fn plain_write_fast_paths_are_disabled_under_store_limits() {
+        let mut rt = Runtime::default_strict();
+        rt.server.store.set_limits(fr_store::StoreLimits {
+            max_value_bytes: Some(4),
+            ..fr_store::StoreLimits::default()
+        });
+
+        assert_eq!(rt.execute_plain_set_borrowed(b"k", b"too-long", 1), None);
+        assert_eq!(
+            rt.execute_plain_mset_borrowed(&[(b"k".as_slice(), b"too-long".as_slice())], 1),
+            None
+        );
+        assert_eq!(
+            rt.execute_plain_msetnx_borrowed(&[b"k".as_slice(), b"too-long".as_slice()], 1),
+            None
+        );
+        assert_eq!(rt.execute_plain_getset_borrowed(b"k", b"too-long", 1), None);
+        // The generic handlers run the quota check instead.
+        let reply = rt.execute_frame(command(&[b"SET", b"k", b"too-long"]), 2);
+        assert!(
+            matches!(&reply, RespFrame::Error(msg) if msg.starts_with("OOM ")),
+            "{reply:?}"
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"GET", b"k"]), 3),
+            RespFrame::BulkString(None)
+        );
+    }
+
     #[test]
     fn plain_set_borrowed_fast_path_is_disabled_when_aof_is_configured() {
         let mut rt = Runtime::default_strict();
@@ -46016,6 +46868,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn offloaded_plain_bitcount_matches_inline() {
+        // start_plain_bitcount_borrowed hands a string of at least the offload
+        // length to the caller; running the job and finishing it must give the
+        // inline reply and stats. Shorter strings are still answered inline.
+        let mut offloaded = Runtime::default_strict();
+        let mut inline = Runtime::default_strict();
+        for rt in [&mut offloaded, &mut inline] {
+            rt.execute_frame(command(&[b"SET", b"big", &[0x5a; 64]]), 1);
+            rt.execute_frame(command(&[b"SET", b"small", b"foobar"]), 1);
+        }
+        let shapes: &[(&[u8], PlainBitcountRange<'_>)] = &[
+            (b"big", None),
+            (b"big", Some((b"3", b"-5", None))),
+            (b"big", Some((b"5", b"300", Some(b"BIT")))),
+        ];
+        for (ts, (key, range)) in (2..).zip(shapes.iter().copied()) {
+            let Some(PlainBitcountStep::Offload(job)) =
+                offloaded.start_plain_bitcount_borrowed(key, range, Some(64), ts)
+            else {
+                panic!("a 64-byte string should be offloaded");
+            };
+            let count = job.run();
+            let f = offloaded.finish_plain_bitcount_job(job, count, 0, ts);
+            let g = inline
+                .execute_plain_bitcount_borrowed(key, range, ts)
+                .expect("bitcount fast path should engage");
+            assert_eq!(f, g, "key={key:?} range={range:?}");
+        }
+        assert!(matches!(
+            offloaded.start_plain_bitcount_borrowed(b"small", None, Some(64), 9),
+            Some(PlainBitcountStep::Reply(RespFrame::Integer(26)))
+        ));
+        inline.execute_plain_bitcount_borrowed(b"small", None, 9);
+        for rt in [&offloaded, &inline] {
+            assert_eq!(rt.server.store.stat_keyspace_hits, 4);
+            assert_eq!(rt.server.store.stat_total_commands_processed, 6);
+        }
+    }
+
     #[test]
     fn plain_expire_borrowed_matches_generic() {
         // (cold-cmd audit) EXPIRE key seconds (no-flag) borrow == generic: set on
@@ -51614,7 +52506,7 @@ mod tests {
             command(&[b"HELLO", b"3", b"AUTH", b"default", b"secret"]),
             0,
         );
-        assert_eq!(ok, build_hello_response(3, rt.session.client_id));
+        assert_eq!(ok, build_hello_response(3, rt.session.client_id, fr_store::REDIS_COMPAT_VERSION));
         assert!(ok.to_bytes().starts_with(b"%7\r\n"));
         assert!(rt.is_authenticated());
     }
@@ -51772,7 +52664,7 @@ mod tests {
         rt.add_user(b"alice".to_vec(), b"secret2".to_vec());
 
         let out = rt.execute_frame(command(&[b"HELLO", b"3", b"AUTH", b"alice", b"secret2"]), 0);
-        assert_eq!(out, build_hello_response(3, rt.session.client_id));
+        assert_eq!(out, build_hello_response(3, rt.session.client_id, fr_store::REDIS_COMPAT_VERSION));
         assert!(out.to_bytes().starts_with(b"%7\r\n"));
         assert!(rt.is_authenticated());
     }
@@ -51884,7 +52776,7 @@ mod tests {
         rt.session.selected_db = 5;
 
         let hello = rt.execute_frame(command(&[b"HELLO", b"3", b"SETNAME", b"alpha"]), 0);
-        assert_eq!(hello, build_hello_response(3, rt.session.client_id));
+        assert_eq!(hello, build_hello_response(3, rt.session.client_id, fr_store::REDIS_COMPAT_VERSION));
         assert!(hello.to_bytes().starts_with(b"%7\r\n"));
 
         let client_list = rt.execute_frame(command(&[b"CLIENT", b"LIST"]), 1);
@@ -52077,7 +52969,7 @@ mod tests {
         );
         assert_eq!(
             rt.execute_frame(command(&[b"HELLO", b"3", b"SETNAME", b"alpha"]), 1),
-            build_hello_response(3, rt.session.client_id)
+            build_hello_response(3, rt.session.client_id, fr_store::REDIS_COMPAT_VERSION)
         );
         assert_eq!(
             rt.execute_frame(
@@ -55130,6 +56022,42 @@ mod tests {
         assert_eq!(decoded, rt.aof_records());
     }
 
+    #[test]
+    fn verbatim_script_propagation_replays_eval_and_rewrites_evalsha() {
+        let mut rt = Runtime::default_strict();
+        rt.server.replication_runtime_state.ensure_replica(42);
+        rt.set_script_verbatim_propagation(true);
+        let script: &[u8] = b"redis.call('SET',KEYS[1],ARGV[1]) return redis.call('INCR','n')";
+
+        let reply = rt.execute_frame(command(&[b"EVAL", script, b"1", b"a", b"1"]), 0);
+        assert_eq!(reply, RespFrame::Integer(1));
+        let sha = sha1_hex_public(script);
+        let reply = rt.execute_frame(command(&[b"EVALSHA", sha.as_bytes(), b"1", b"b", b"2"]), 0);
+        assert_eq!(reply, RespFrame::Integer(2));
+        // Switching to effects mid-script propagates the resolved writes.
+        let reply = rt.execute_frame(
+            command(&[
+                b"EVAL",
+                b"redis.replicate_commands() redis.call('TIME') return redis.call('SET','c','3')",
+                b"0",
+            ]),
+            0,
+        );
+        assert_eq!(reply, RespFrame::SimpleString("OK".to_string()));
+
+        let argv = |parts: &[&[u8]]| AofRecord {
+            argv: parts.iter().map(|part| part.to_vec()).collect(),
+        };
+        assert_eq!(
+            rt.aof_records(),
+            [
+                argv(&[b"EVAL", script, b"1", b"a", b"1"]),
+                argv(&[b"EVAL", script, b"1", b"b", b"2"]),
+                argv(&[b"SET", b"c", b"3"]),
+            ]
+        );
+    }
+
     // A script that propagates exactly one effect must NOT be MULTI/EXEC-wrapped
     // (upstream wraps only when MORE THAN ONE command propagates); a multi-effect
     // script must be wrapped. Mirrors the MULTI/EXEC transaction threshold.
@@ -56039,6 +56967,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn redacted_argv_hides_credentials_like_upstream() {
+        let argv = |parts: &[&[u8]]| parts.iter().map(|p| p.to_vec()).collect::<Vec<_>>();
+        let r: &[u8] = b"(redacted)";
+        assert_eq!(redacted_argv(&argv(&[b"GET", b"k"])), None);
+        assert_eq!(redacted_argv(&argv(&[b"AUTH"])), None);
+        assert_eq!(
+            redacted_argv(&argv(&[b"auth", b"u", b"p"])),
+            Some(argv(&[b"auth", r, r]))
+        );
+        assert_eq!(
+            redacted_argv(&argv(&[
+                b"MIGRATE", b"h", b"1", b"", b"0", b"5000", b"COPY", b"AUTH", b"p", b"AUTH2", b"u",
+                b"p2", b"KEYS", b"AUTH", b"k"
+            ])),
+            Some(argv(&[
+                b"MIGRATE", b"h", b"1", b"", b"0", b"5000", b"COPY", b"AUTH", r, b"AUTH2", r, r,
+                b"KEYS", b"AUTH", b"k"
+            ]))
+        );
+        assert_eq!(
+            redacted_argv(&argv(&[
+                b"config",
+                b"set",
+                b"MasterAuth",
+                b"x",
+                b"maxmemory",
+                b"1",
+                b"requirepass"
+            ])),
+            Some(argv(&[
+                b"config",
+                b"set",
+                b"MasterAuth",
+                r,
+                b"maxmemory",
+                b"1",
+                b"requirepass"
+            ]))
+        );
+        assert_eq!(
+            redacted_argv(&argv(&[b"CONFIG", b"GET", b"requirepass"])),
+            None
+        );
+    }
+
     #[test]
     fn monitor_registers_client_and_streams_subsequent_commands() {
         let mut rt = Runtime::default_strict();
@@ -58484,6 +59458,113 @@ mod tests {
         assert!(info.contains("repl_backlog_first_byte_offset:1\r\n"));
     }
 
+    fn info_replication_field(rt: &mut Runtime, field: &str, now_ms: u64) -> String {
+        let info = rt.execute_frame(command(&[b"INFO", b"replication"]), now_ms);
+        let RespFrame::BulkString(Some(info_bytes)) = info else {
+            unreachable!("expected bulk INFO response");
+        };
+        let info = String::from_utf8(info_bytes).expect("utf8 info");
+        let prefix = format!("{field}:");
+        info.split("\r\n")
+            .find_map(|line| line.strip_prefix(&prefix))
+            .unwrap_or_else(|| panic!("no {field} in {info}"))
+            .to_string()
+    }
+
+    #[test]
+    fn debug_change_repl_id_rotates_master_replid() {
+        let mut rt = Runtime::default_strict();
+        rt.server.enable_debug_command = "yes".to_string();
+        rt.server.replication_runtime_state.ensure_replica(42);
+        rt.execute_frame(command(&[b"SET", b"k", b"v"]), 0);
+
+        let before = info_replication_field(&mut rt, "master_replid", 1);
+        assert_eq!(before.len(), 40);
+        assert!(before.bytes().all(|b| b.is_ascii_hexdigit()), "{before}");
+        assert_ne!(before, "0".repeat(40));
+        assert_ne!(before, rt.server.store.server_run_id);
+        let offset = rt.replication_primary_offset();
+
+        assert_eq!(
+            rt.execute_frame(command(&[b"DEBUG", b"CHANGE-REPL-ID"]), 2),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        let after = info_replication_field(&mut rt, "master_replid", 3);
+        assert_eq!(after.len(), 40);
+        assert!(after.bytes().all(|b| b.is_ascii_hexdigit()), "{after}");
+        assert_ne!(after, before);
+        assert_eq!(rt.server.replication_runtime_state.backlog.replid, after);
+        // Only the ID rotates: the offset and the (empty) replid2 stay put.
+        assert_eq!(rt.replication_primary_offset(), offset);
+        assert_eq!(
+            info_replication_field(&mut rt, "master_replid2", 4),
+            "0".repeat(40)
+        );
+
+        // Refused by the enable-debug-command gate: nothing rotates.
+        rt.server.enable_debug_command = "no".to_string();
+        assert!(matches!(
+            rt.execute_frame(command(&[b"DEBUG", b"CHANGE-REPL-ID"]), 5),
+            RespFrame::Error(_)
+        ));
+        assert_eq!(info_replication_field(&mut rt, "master_replid", 6), after);
+
+        // A replica resuming under the old ID cannot continue.
+        let resume_from = offset.0.to_string();
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"PSYNC", before.as_bytes(), resume_from.as_bytes()]),
+                7
+            ),
+            RespFrame::SimpleString(format!("FULLRESYNC {after} {}", offset.0))
+        );
+    }
+
+    #[test]
+    fn replication_offset_surfaces_agree_after_a_burst_of_writes() {
+        let mut rt = Runtime::default_strict();
+        rt.server.replication_runtime_state.ensure_replica(42);
+        let start = rt.replication_primary_offset().0;
+        for i in 0u64..50 {
+            rt.execute_frame(command(&[b"INCR", format!("n{}", i % 7).as_bytes()]), i);
+            rt.execute_frame(command(&[b"RPUSH", b"list", format!("{i}").as_bytes()]), i);
+        }
+        let offset = rt.replication_primary_offset().0;
+        assert!(offset > start, "writes must advance the offset");
+
+        // INFO replication.
+        assert_eq!(
+            info_replication_field(&mut rt, "master_repl_offset", 100),
+            offset.to_string()
+        );
+        // ROLE.
+        let role = rt.execute_frame(command(&[b"ROLE"]), 101);
+        let RespFrame::Array(Some(role)) = role else {
+            panic!("expected ROLE array, got {role:?}");
+        };
+        assert_eq!(role[0], RespFrame::BulkString(Some(b"master".to_vec())));
+        assert_eq!(role[1], RespFrame::Integer(i64::try_from(offset).expect("offset")));
+        // WAIT counts the replica only once it has acked this same offset.
+        rt.server
+            .replication_runtime_state
+            .ensure_replica(42)
+            .ack_offset = fr_repl::ReplOffset(offset - 1);
+        assert_eq!(
+            rt.execute_frame(command(&[b"WAIT", b"1", b"0"]), 102),
+            RespFrame::Integer(0)
+        );
+        rt.server
+            .replication_runtime_state
+            .ensure_replica(42)
+            .ack_offset = fr_repl::ReplOffset(offset);
+        assert_eq!(
+            rt.execute_frame(command(&[b"WAIT", b"1", b"0"]), 103),
+            RespFrame::Integer(1)
+        );
+        // Reads did not move it.
+        assert_eq!(rt.replication_primary_offset().0, offset);
+    }
+
     #[test]
     fn live_info_supports_multiple_requested_sections() {
         let mut rt = Runtime::default_strict();
@@ -59688,6 +60769,237 @@ mod tests {
         );
     }
 
+    fn client_argv(rt: &mut Runtime, parts: &[&[u8]], now_ms: u64) -> RespFrame {
+        let argv: Vec<Vec<u8>> = parts.iter().map(|part| part.to_vec()).collect();
+        rt.execute_argv_with_unix_time_us(&argv, now_ms, now_ms * 1000)
+    }
+
+    #[test]
+    fn rename_command_reaches_original_under_new_name_only() {
+        let mut rt = Runtime::default_strict();
+        let count_before = client_argv(&mut rt, &[b"COMMAND", b"COUNT"], 1);
+        rt.rename_command(b"CONFIG", b"cfg-9f2a")
+            .expect("rename CONFIG");
+
+        assert_eq!(
+            client_argv(&mut rt, &[b"CONFIG", b"GET", b"maxmemory"], 2),
+            RespFrame::Error(
+                "ERR unknown command 'CONFIG', with args beginning with: 'GET' 'maxmemory' "
+                    .to_string()
+            )
+        );
+        let expected = RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"maxmemory".to_vec())),
+            RespFrame::BulkString(Some(b"0".to_vec())),
+        ]));
+        assert_eq!(
+            client_argv(&mut rt, &[b"cfg-9f2a", b"GET", b"maxmemory"], 3),
+            expected
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"CFG-9F2A", b"GET", b"maxmemory"], 4),
+            expected
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"COMMAND", b"COUNT"], 5),
+            count_before,
+            "a plain rename keeps the command in the table"
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"COMMAND", b"INFO", b"config"], 6),
+            RespFrame::Array(Some(vec![RespFrame::BulkString(None)]))
+        );
+        let RespFrame::Array(Some(info)) =
+            client_argv(&mut rt, &[b"COMMAND", b"INFO", b"cfg-9f2a"], 7)
+        else {
+            panic!("COMMAND INFO must reply with an array");
+        };
+        let RespFrame::Array(Some(row)) = &info[0] else {
+            panic!("renamed command must resolve to its original row: {info:?}");
+        };
+        assert_eq!(row[0], RespFrame::BulkString(Some(b"config".to_vec())));
+
+        // Internal callers keep using canonical names.
+        let RespFrame::Array(Some(_)) =
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"maxmemory"]), 8)
+        else {
+            panic!("internal CONFIG must still dispatch");
+        };
+    }
+
+    #[test]
+    fn rename_command_to_empty_disables_and_shrinks_command_count() {
+        let mut rt = Runtime::default_strict();
+        let RespFrame::Integer(count_before) = client_argv(&mut rt, &[b"COMMAND", b"COUNT"], 1)
+        else {
+            panic!("COMMAND COUNT must be an integer");
+        };
+        rt.rename_command(b"flushall", b"")
+            .expect("disable FLUSHALL");
+
+        assert_eq!(
+            client_argv(&mut rt, &[b"FLUSHALL"], 2),
+            RespFrame::Error(
+                "ERR unknown command 'FLUSHALL', with args beginning with: ".to_string()
+            )
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"COMMAND", b"COUNT"], 3),
+            RespFrame::Integer(count_before - 1)
+        );
+    }
+
+    #[test]
+    fn rename_command_hidden_name_aborts_multi_and_alias_queues() {
+        let mut rt = Runtime::default_strict();
+        rt.rename_command(b"SET", b"put").expect("rename SET");
+
+        assert_eq!(
+            client_argv(&mut rt, &[b"MULTI"], 1),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"put", b"k", b"v"], 2),
+            RespFrame::SimpleString("QUEUED".to_string())
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"EXEC"], 3),
+            RespFrame::Array(Some(vec![RespFrame::SimpleString("OK".to_string())]))
+        );
+
+        client_argv(&mut rt, &[b"MULTI"], 4);
+        let RespFrame::Error(_) = client_argv(&mut rt, &[b"SET", b"k", b"w"], 5) else {
+            panic!("original name must be unknown inside MULTI");
+        };
+        let RespFrame::Error(exec) = client_argv(&mut rt, &[b"EXEC"], 6) else {
+            panic!("EXEC after an unknown command must abort");
+        };
+        assert!(exec.starts_with("EXECABORT"), "{exec}");
+        assert_eq!(
+            client_argv(&mut rt, &[b"GET", b"k"], 7),
+            RespFrame::BulkString(Some(b"v".to_vec()))
+        );
+    }
+
+    #[test]
+    fn rename_command_applies_to_redis_call_inside_eval() {
+        let mut rt = Runtime::default_strict();
+        rt.rename_command(b"SET", b"put").expect("rename SET");
+        rt.rename_command(b"FLUSHALL", b"")
+            .expect("disable FLUSHALL");
+
+        assert_eq!(
+            client_argv(
+                &mut rt,
+                &[b"EVAL", b"return redis.call('put', 'k', 'v')", b"0"],
+                1
+            ),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            client_argv(
+                &mut rt,
+                &[b"EVAL", b"return redis.pcall('SET', 'k', 'w')", b"0"],
+                2
+            ),
+            RespFrame::Error("ERR Unknown Redis command called from script".to_string())
+        );
+        let RespFrame::Error(err) = client_argv(
+            &mut rt,
+            &[b"EVAL", b"return redis.call('flushall')", b"0"],
+            3,
+        ) else {
+            panic!("a disabled command must be unknown inside a script");
+        };
+        assert!(
+            err.starts_with("ERR Unknown Redis command called from script"),
+            "{err}"
+        );
+        assert_eq!(
+            client_argv(
+                &mut rt,
+                &[b"EVAL", b"return redis.call('get', 'k')", b"0"],
+                4
+            ),
+            RespFrame::BulkString(Some(b"v".to_vec()))
+        );
+    }
+
+    #[test]
+    fn rename_command_rejects_unknown_source_and_existing_target() {
+        let mut rt = Runtime::default_strict();
+        assert_eq!(
+            rt.rename_command(b"NOPE", b"x"),
+            Err("No such command in rename-command".to_string())
+        );
+        assert_eq!(
+            rt.rename_command(b"CONFIG", b"get"),
+            Err("Target command name already exists".to_string())
+        );
+        rt.rename_command(b"CONFIG", b"cfg").expect("rename CONFIG");
+        assert_eq!(
+            rt.rename_command(b"CONFIG", b"other"),
+            Err("No such command in rename-command".to_string())
+        );
+        // The replacement can be renamed again; the earlier alias then goes away.
+        rt.rename_command(b"cfg", b"cfg2").expect("rename alias");
+        let RespFrame::Error(_) = client_argv(&mut rt, &[b"cfg", b"GET", b"port"], 1) else {
+            panic!("superseded alias must be unknown");
+        };
+        let RespFrame::Array(Some(_)) = client_argv(&mut rt, &[b"cfg2", b"GET", b"port"], 2) else {
+            panic!("new alias must reach CONFIG");
+        };
+    }
+
+    #[test]
+    fn renaming_one_command_keeps_the_other_borrowed_fast_paths() {
+        let mut rt = Runtime::default_strict();
+        assert!(!rt.has_command_renames());
+        rt.rename_command(b"FLUSHALL", b"")
+            .expect("disable FLUSHALL");
+        rt.rename_command(b"GET", b"fetch").expect("rename GET");
+
+        assert!(rt.has_command_renames());
+        for name in [&b"GET"[..], b"get", b"FETCH", b"flushall"] {
+            assert!(rt.command_name_is_renamed(name), "{name:?}");
+        }
+        assert!(!rt.command_name_is_renamed(b"SET"));
+        assert_eq!(
+            rt.execute_plain_set_borrowed(b"k", b"v", 1),
+            Some(RespFrame::SimpleString("OK".to_string()))
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"fetch", b"k"], 2),
+            RespFrame::BulkString(Some(b"v".to_vec()))
+        );
+    }
+
+    #[test]
+    fn deterministic_iteration_bypasses_borrowed_collection_fast_paths() {
+        let mut rt = Runtime::default_strict();
+        client_argv(&mut rt, &[b"SADD", b"s", b"c", b"a", b"b"], 1);
+        let mut out = Vec::new();
+        assert!(
+            rt.execute_plain_smembers_borrowed_into(b"s", 2, false, &mut out)
+                .is_some()
+        );
+
+        rt.set_deterministic_iteration(true);
+        out.clear();
+        assert!(
+            rt.execute_plain_smembers_borrowed_into(b"s", 3, false, &mut out)
+                .is_none()
+        );
+        assert_eq!(
+            client_argv(&mut rt, &[b"SMEMBERS", b"s"], 4),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"a".to_vec())),
+                RespFrame::BulkString(Some(b"b".to_vec())),
+                RespFrame::BulkString(Some(b"c".to_vec())),
+            ]))
+        );
+    }
+
     #[test]
     fn protocol_invalid_bulk_length_error_string() {
         let mut rt = Runtime::default_strict();
@@ -60073,6 +61385,125 @@ mod tests {
         assert_eq!(result, RespFrame::Array(None));
     }
 
+    #[test]
+    fn watch_aborts_when_watched_key_lapses_on_the_clock() {
+        let mut rt = Runtime::default_strict();
+        rt.execute_frame(command(&[b"SET", b"k", b"v", b"PX", b"100"]), 0);
+        rt.execute_frame(command(&[b"WATCH", b"k"]), 1);
+        rt.execute_frame(command(&[b"MULTI"]), 200);
+        rt.execute_frame(command(&[b"PING"]), 200);
+        assert_eq!(
+            rt.execute_frame(command(&[b"EXEC"]), 200),
+            RespFrame::Array(None)
+        );
+
+        // Reaped by another client and recreated byte-for-byte with the same
+        // TTL: still a different key as far as the watcher is concerned.
+        rt.execute_frame(command(&[b"SET", b"k", b"v", b"PX", b"100"]), 300);
+        rt.execute_frame(command(&[b"WATCH", b"k"]), 301);
+        let watcher = rt.swap_session(rt.new_session());
+        assert_eq!(
+            rt.execute_frame(command(&[b"GET", b"k"]), 500),
+            RespFrame::BulkString(None)
+        );
+        rt.execute_frame(command(&[b"SET", b"k", b"v", b"PXAT", b"600"]), 500);
+        let _ = rt.swap_session(watcher);
+        rt.execute_frame(command(&[b"MULTI"]), 501);
+        rt.execute_frame(command(&[b"PING"]), 501);
+        assert_eq!(
+            rt.execute_frame(command(&[b"EXEC"]), 501),
+            RespFrame::Array(None)
+        );
+    }
+
+    #[test]
+    fn watch_aborts_after_flush_from_another_connection() {
+        let mut rt = Runtime::default_strict();
+        rt.execute_frame(command(&[b"SET", b"k", b"v"]), 0);
+        rt.execute_frame(command(&[b"SET", b"other", b"v"]), 0);
+        rt.execute_frame(command(&[b"WATCH", b"k"]), 1);
+        let watcher = rt.swap_session(rt.new_session());
+        // Flushing a different database leaves the watch intact.
+        rt.execute_frame(command(&[b"SELECT", b"1"]), 2);
+        rt.execute_frame(command(&[b"SET", b"k", b"v"]), 2);
+        rt.execute_frame(command(&[b"FLUSHDB"]), 2);
+        let mut other = rt.swap_session(watcher);
+        rt.execute_frame(command(&[b"MULTI"]), 3);
+        rt.execute_frame(command(&[b"PING"]), 3);
+        assert_eq!(
+            rt.execute_frame(command(&[b"EXEC"]), 3),
+            RespFrame::Array(Some(vec![RespFrame::SimpleString("PONG".to_string())]))
+        );
+
+        for flush in [&b"FLUSHDB"[..], b"FLUSHALL"] {
+            rt.execute_frame(command(&[b"SET", b"k", b"v"]), 4);
+            rt.execute_frame(command(&[b"WATCH", b"k"]), 5);
+            let watcher = rt.swap_session(other);
+            rt.execute_frame(command(&[b"SELECT", b"0"]), 6);
+            rt.execute_frame(command(&[flush]), 6);
+            rt.execute_frame(command(&[b"SET", b"k", b"v"]), 6);
+            other = rt.swap_session(watcher);
+            rt.execute_frame(command(&[b"MULTI"]), 7);
+            rt.execute_frame(command(&[b"PING"]), 7);
+            assert_eq!(
+                rt.execute_frame(command(&[b"EXEC"]), 7),
+                RespFrame::Array(None),
+                "{}",
+                String::from_utf8_lossy(flush)
+            );
+        }
+    }
+
+    #[test]
+    fn watch_aborts_when_key_is_replaced_by_identical_contents() {
+        // Each script leaves the watched key `k` holding exactly "v" again, so
+        // only the version stamp can tell the watcher it changed hands.
+        let scripts: &[&[&[&[u8]]]] = &[
+            &[&[b"DEL", b"k"], &[b"SET", b"k", b"v"]],
+            &[&[b"GETDEL", b"k"], &[b"SET", b"k", b"v"]],
+            &[&[b"SET", b"src", b"v"], &[b"RENAME", b"src", b"k"]],
+            &[&[b"RENAME", b"k", b"src"], &[b"RENAME", b"src", b"k"]],
+            &[
+                &[b"SET", b"src", b"v"],
+                &[b"COPY", b"src", b"k", b"REPLACE"],
+            ],
+            &[
+                &[b"MOVE", b"k", b"1"],
+                &[b"SELECT", b"1"],
+                &[b"MOVE", b"k", b"0"],
+            ],
+            &[
+                &[b"SELECT", b"1"],
+                &[b"SET", b"k", b"v"],
+                &[b"SWAPDB", b"0", b"1"],
+            ],
+        ];
+        for script in scripts {
+            let mut rt = Runtime::default_strict();
+            rt.execute_frame(command(&[b"SET", b"k", b"v"]), 0);
+            rt.execute_frame(command(&[b"WATCH", b"k"]), 1);
+            let watcher = rt.swap_session(rt.new_session());
+            for step in *script {
+                assert!(
+                    !matches!(rt.execute_frame(command(step), 2), RespFrame::Error(_)),
+                    "{script:?}"
+                );
+            }
+            let _ = rt.swap_session(watcher);
+            assert_eq!(
+                rt.execute_frame(command(&[b"GET", b"k"]), 3),
+                RespFrame::BulkString(Some(b"v".to_vec()))
+            );
+            rt.execute_frame(command(&[b"MULTI"]), 3);
+            rt.execute_frame(command(&[b"PING"]), 3);
+            assert_eq!(
+                rt.execute_frame(command(&[b"EXEC"]), 4),
+                RespFrame::Array(None),
+                "{script:?}"
+            );
+        }
+    }
+
     #[test]
     fn plain_watch_unwatch_borrowed_matches_generic() {
         let mut direct = Runtime::default_strict();
@@ -61662,6 +63093,210 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn config_rewrite_preserves_comments_and_untouched_directives() {
+        let existing = "# top comment\n\
+                        timeout 30\n\
+                        \n\
+                        rename-command FLUSHALL \"\"\n\
+                        # timeout again below\n\
+                        timeout 45\n\
+                        hz 10\n\
+                        # Generated by CONFIG REWRITE\n\
+                        maxclients 10\n";
+        let current = vec![
+            ("timeout".to_string(), "123".to_string()),
+            ("hz".to_string(), "10".to_string()),
+            ("maxclients".to_string(), "10000".to_string()),
+            ("requirepass".to_string(), String::new()),
+            ("loglevel".to_string(), "notice".to_string()),
+        ];
+        assert_eq!(
+            rewrite_config_file_content(existing, &current),
+            "# top comment\n\
+             timeout 123\n\
+             \n\
+             rename-command FLUSHALL \"\"\n\
+             # timeout again below\n\
+             hz 10\n\
+             # Generated by CONFIG REWRITE\n\
+             maxclients 10000\n\
+             requirepass \"\"\n\
+             loglevel notice\n"
+        );
+
+        // Rewriting the result again is stable.
+        let once = rewrite_config_file_content(existing, &current);
+        assert_eq!(rewrite_config_file_content(&once, &current), once);
+    }
+
+    #[test]
+    fn apply_config_file_directive_feeds_config_get_registry() {
+        let mut rt = Runtime::default_strict();
+        assert!(rt.is_config_parameter("timeout"));
+        assert!(rt.is_config_parameter("databases"));
+        assert!(!rt.is_config_parameter("no-such-directive"));
+        assert!(!rt.is_config_parameter("time*"));
+
+        rt.apply_config_file_directive("timeout", b"42")
+            .expect("mutable parameter");
+        // Immutable at runtime, but settable from the file.
+        rt.apply_config_file_directive("daemonize", b"yes")
+            .expect("immutable parameter");
+        assert_eq!(
+            rt.apply_config_file_directive("hz", b"abc"),
+            Err(
+                "CONFIG SET failed (possibly related to argument 'hz') - argument couldn't be parsed into an integer"
+                    .to_string()
+            )
+        );
+
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"GET", b"timeout", b"daemonize"]),
+                0
+            ),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"timeout".to_vec())),
+                RespFrame::BulkString(Some(b"42".to_vec())),
+                RespFrame::BulkString(Some(b"daemonize".to_vec())),
+                RespFrame::BulkString(Some(b"yes".to_vec())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn server_version_config_drives_hello_and_survives_reload() {
+        let mut rt = Runtime::default_strict();
+        let hello_version = |rt: &mut Runtime| match rt.execute_frame(command(&[b"HELLO"]), 0) {
+            RespFrame::Array(Some(fields)) => fields[3].clone(),
+            other => panic!("expected HELLO array, got {other:?}"),
+        };
+        assert_eq!(
+            hello_version(&mut rt),
+            RespFrame::BulkString(Some(fr_store::REDIS_COMPAT_VERSION.as_bytes().to_vec()))
+        );
+
+        rt.apply_config_file_directive("server-version", b"6.2.14")
+            .unwrap();
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"server-version"]), 0),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"server-version".to_vec())),
+                RespFrame::BulkString(Some(b"6.2.14".to_vec())),
+            ]))
+        );
+        assert_eq!(
+            hello_version(&mut rt),
+            RespFrame::BulkString(Some(b"6.2.14".to_vec()))
+        );
+        rt.set_enable_debug_command("yes");
+        assert_eq!(
+            rt.execute_frame(command(&[b"DEBUG", b"RELOAD"]), 0),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(rt.server.store.redis_version(), "6.2.14");
+
+        // The value lands verbatim in INFO lines.
+        for bad in [&b"7.2 4"[..], b"7.2.4\r\nrole:master", &[b'9'; 65]] {
+            assert!(
+                matches!(
+                    rt.execute_frame(command(&[b"CONFIG", b"SET", b"server-version", bad]), 0),
+                    RespFrame::Error(ref msg) if msg.contains("'server-version'")
+                ),
+                "{bad:?}"
+            );
+        }
+        assert_eq!(rt.server.store.redis_version(), "6.2.14");
+
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"SET", b"server-version", b""]), 0),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            rt.server.store.redis_version(),
+            fr_store::REDIS_COMPAT_VERSION
+        );
+    }
+
+    #[test]
+    fn databases_directive_bounds_select_move_swapdb_and_keyspace() {
+        let ok = || RespFrame::SimpleString("OK".to_string());
+        let out_of_range = || RespFrame::Error("ERR DB index is out of range".to_string());
+        let mut rt = Runtime::default_strict();
+        for (value, err) in [
+            (
+                b"0".as_slice(),
+                "argument must be between 1 and 2147483647 inclusive",
+            ),
+            (
+                b"2147483648",
+                "argument must be between 1 and 2147483647 inclusive",
+            ),
+            (b"many", "argument couldn't be parsed into an integer"),
+        ] {
+            assert_eq!(
+                rt.apply_config_file_directive("databases", value),
+                Err(err.to_string())
+            );
+        }
+        rt.apply_config_file_directive("databases", b"32")
+            .expect("databases 32");
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"databases"]), 0),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"databases".to_vec())),
+                RespFrame::BulkString(Some(b"32".to_vec())),
+            ]))
+        );
+
+        // A valid-but-empty high database is selectable and starts empty.
+        assert_eq!(
+            rt.execute_frame(command(&[b"SELECT", b"32"]), 0),
+            out_of_range()
+        );
+        assert_eq!(rt.execute_frame(command(&[b"SELECT", b"31"]), 0), ok());
+        assert_eq!(
+            rt.execute_frame(command(&[b"DBSIZE"]), 0),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(rt.execute_frame(command(&[b"SET", b"k", b"v"]), 0), ok());
+        assert_eq!(
+            rt.execute_frame(command(&[b"MOVE", b"k", b"32"]), 0),
+            out_of_range()
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"MOVE", b"k", b"20"]), 0),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"SWAPDB", b"32", b"0"]), 0),
+            out_of_range()
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"SWAPDB", b"x", b"0"]), 0),
+            RespFrame::Error("ERR invalid first DB index".to_string())
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"SWAPDB", b"0", b"x"]), 0),
+            RespFrame::Error("ERR invalid second DB index".to_string())
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"SWAPDB", b"20", b"25"]), 0),
+            ok()
+        );
+
+        let RespFrame::BulkString(Some(info)) =
+            rt.execute_frame(command(&[b"INFO", b"keyspace"]), 0)
+        else {
+            panic!("expected bulk INFO keyspace");
+        };
+        let info = String::from_utf8(info).expect("utf8 info");
+        assert!(info.contains("db25:keys=1,expires=0,avg_ttl=0"), "{info}");
+        assert!(!info.contains("db20:"), "{info}");
+        assert!(!info.contains("db31:"), "{info}");
+    }
+
     #[test]
     fn config_set_hz_matches_redis_validation_and_clamping() {
         let mut rt = Runtime::default_strict();
@@ -62169,6 +63804,207 @@ mod tests {
         }
     }
 
+    #[test]
+    fn config_stream_preserve_last_id_reaches_the_store_and_stays_hidden() {
+        let mut rt = Runtime::default_strict();
+        let get = |rt: &mut Runtime, name: &[u8]| {
+            rt.execute_frame(command(&[b"CONFIG", b"GET", name]), 0)
+        };
+        let pair = |name: &[u8], value: &[u8]| {
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(name.to_vec())),
+                RespFrame::BulkString(Some(value.to_vec())),
+            ]))
+        };
+        assert_eq!(
+            get(&mut rt, b"stream-preserve-last-id-on-delete"),
+            pair(b"stream-preserve-last-id-on-delete", b"no")
+        );
+        assert_eq!(
+            get(&mut rt, b"stream-preserve-last-id-*"),
+            RespFrame::Array(Some(Vec::new()))
+        );
+
+        assert_eq!(
+            rt.execute_frame(
+                command(&[
+                    b"CONFIG",
+                    b"SET",
+                    b"stream-preserve-last-id-on-delete",
+                    b"yes",
+                    b"stream-preserve-last-id-window",
+                    b"60000",
+                ]),
+                0,
+            ),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert!(rt.server.store.stream_preserve_last_id_on_delete());
+        assert_eq!(rt.server.store.stream_preserve_last_id_window_ms(), 60_000);
+        assert_eq!(
+            get(&mut rt, b"stream-preserve-last-id-window"),
+            pair(b"stream-preserve-last-id-window", b"60000")
+        );
+
+        // The server's borrowed XADD fast path continues above a deleted
+        // stream just like the generic handler.
+        rt.execute_frame(command(&[b"XADD", b"s", b"5000-0", b"f", b"v"]), 1_000);
+        rt.execute_frame(command(&[b"DEL", b"s"]), 1_000);
+        assert_eq!(
+            rt.execute_plain_xadd_borrowed(b"s", b"*", b"f", b"v", 1_000),
+            Some(RespFrame::BulkString(Some(b"5000-1".to_vec())))
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"XADD", b"s", b"*", b"f", b"v"]), 1_000),
+            RespFrame::BulkString(Some(b"5000-2".to_vec()))
+        );
+
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"SET", b"stream-preserve-last-id-on-delete", b"maybe"]),
+                0,
+            ),
+            RespFrame::Error(
+                "ERR CONFIG SET failed (possibly related to argument 'stream-preserve-last-id-on-delete') - argument must be 'yes' or 'no'"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"SET", b"stream-preserve-last-id-window", b"-1"]),
+                0,
+            ),
+            RespFrame::Error(
+                "ERR CONFIG SET failed (possibly related to argument 'stream-preserve-last-id-window') - argument must be between 0 and 9223372036854775807 inclusive"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn config_stream_history_reads_keep_delivery_counts_is_opt_in() {
+        let mut rt = Runtime::default_strict();
+        let pair = |value: &[u8]| {
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"stream-history-reads-keep-delivery-counts".to_vec())),
+                RespFrame::BulkString(Some(value.to_vec())),
+            ]))
+        };
+        let get = |rt: &mut Runtime| {
+            rt.execute_frame(
+                command(&[
+                    b"CONFIG",
+                    b"GET",
+                    b"stream-history-reads-keep-delivery-counts",
+                ]),
+                0,
+            )
+        };
+        assert_eq!(get(&mut rt), pair(b"no"));
+        assert!(!rt.server.store.stream_history_reads_keep_delivery_counts());
+        assert_eq!(
+            rt.execute_frame(
+                command(&[
+                    b"CONFIG",
+                    b"SET",
+                    b"stream-history-reads-keep-delivery-counts",
+                    b"yes",
+                ]),
+                0,
+            ),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert!(rt.server.store.stream_history_reads_keep_delivery_counts());
+        assert_eq!(get(&mut rt), pair(b"yes"));
+        assert_eq!(
+            rt.execute_frame(
+                command(&[
+                    b"CONFIG",
+                    b"SET",
+                    b"stream-history-reads-keep-delivery-counts",
+                    b"maybe",
+                ]),
+                0,
+            ),
+            RespFrame::Error(
+                "ERR CONFIG SET failed (possibly related to argument 'stream-history-reads-keep-delivery-counts') - argument must be 'yes' or 'no'"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn config_max_elements_per_call_limits_generic_and_borrowed_bulk_adds() {
+        let mut rt = Runtime::default_strict();
+        let pair = |value: &[u8]| {
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"max-elements-per-call".to_vec())),
+                RespFrame::BulkString(Some(value.to_vec())),
+            ]))
+        };
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"max-elements-per-call"]), 0),
+            pair(b"0")
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"max-elements-*"]), 0),
+            RespFrame::Array(Some(Vec::new()))
+        );
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"SET", b"max-elements-per-call", b"2"]),
+                0
+            ),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(rt.server.store.max_elements_per_call(), 2);
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"GET", b"max-elements-per-call"]), 0),
+            pair(b"2")
+        );
+
+        // Over the limit the borrowed paths defer to the generic handler,
+        // which rejects the call without writing.
+        let three: [&[u8]; 6] = [b"1", b"a", b"2", b"b", b"3", b"c"];
+        assert_eq!(rt.execute_plain_zadd_borrowed(b"z", &three, 0), None);
+        assert_eq!(rt.execute_plain_hset_borrowed(b"h", &three, 0), None);
+        assert_eq!(
+            rt.execute_plain_keyed_values_write_borrowed(
+                PlainKeyedValuesCmd::Sadd,
+                b"s",
+                &three[..3],
+                0,
+            ),
+            None
+        );
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"ZADD", b"z", b"1", b"a", b"2", b"b", b"3", b"c"]),
+                0,
+            ),
+            RespFrame::Error(
+                "ERR ZADD would add 3 elements, over the max-elements-per-call limit of 2"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            rt.execute_frame(command(&[b"EXISTS", b"z", b"h", b"s"]), 0),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            rt.execute_plain_zadd_borrowed(b"z", &three[..4], 0),
+            Some(RespFrame::Integer(2))
+        );
+
+        assert_eq!(
+            rt.execute_frame(command(&[b"CONFIG", b"SET", b"max-elements-per-call", b"-1"]), 0),
+            RespFrame::Error(
+                "ERR CONFIG SET failed (possibly related to argument 'max-elements-per-call') - argument must be between 0 and 9223372036854775807 inclusive"
+                    .to_string()
+            )
+        );
+    }
+
     #[test]
     fn config_set_shutdown_on_sig_rejects_save_nosave_combination_per_upstream() {
         // (frankenredis-9a33k) Upstream config.c:2378-2385
@@ -62452,6 +64288,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "hash-field-ttl"))]
     fn redis_7_4_hash_field_ttl_commands_return_unknown_command_per_7_2_4_parity() {
         // (frankenredis-ja8yu) The Redis 7.4 hash field TTL family
         // (HEXPIRE/HPEXPIRE/HEXPIREAT/HPEXPIREAT/HEXPIRETIME/
@@ -62460,7 +64297,8 @@ mod tests {
         // even though fr-store still implements the underlying
         // primitives so RDB type 21 round-trips can preserve per-field
         // TTL data. Same class as v0swv (DEBUG OBJECT hexpired_fields)
-        // and c5746 (HSCAN NOVALUES).
+        // and c5746 (HSCAN NOVALUES). The `hash-field-ttl` feature opts
+        // into all but HGETEX/HGETDEL.
         let mut rt = Runtime::default_strict();
         rt.execute_frame(command(&[b"HSET", b"h", b"f1", b"v1"]), 0);
         let cases: &[&[&[u8]]] = &[
@@ -63547,6 +65385,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn config_set_encoding_thresholds_govern_later_conversions_only() {
+        // Thresholds live on the store and are read on every insert, so a
+        // CONFIG SET takes effect on the next write. Nothing is converted
+        // retroactively in either direction: lowering a threshold leaves
+        // existing keys alone until they grow, and raising it never
+        // downgrades a key that already converted.
+        fn encoding(rt: &mut Runtime, key: &[u8]) -> String {
+            match rt.execute_frame(command(&[b"OBJECT", b"ENCODING", key]), 0) {
+                RespFrame::BulkString(Some(name)) => String::from_utf8(name).unwrap(),
+                other => panic!("unexpected OBJECT ENCODING reply {other:?}"),
+            }
+        }
+        fn config_set(rt: &mut Runtime, name: &[u8], value: &[u8]) {
+            assert_eq!(
+                rt.execute_frame(command(&[b"CONFIG", b"SET", name, value]), 0),
+                RespFrame::SimpleString("OK".to_string())
+            );
+        }
+
+        // (threshold, command adding member i to `k`, encoding under it, past it)
+        type Write = fn(u32) -> String;
+        let cases: &[(&[u8], Write, &str, &str)] = &[
+            (
+                b"hash-max-listpack-entries",
+                |i| format!("HSET k f{i} v"),
+                "listpack",
+                "hashtable",
+            ),
+            (
+                b"zset-max-listpack-entries",
+                |i| format!("ZADD k {i} m{i}"),
+                "listpack",
+                "skiplist",
+            ),
+            (
+                b"set-max-listpack-entries",
+                |i| format!("SADD k m{i}"),
+                "listpack",
+                "hashtable",
+            ),
+            (
+                b"set-max-intset-entries",
+                |i| format!("SADD k {i}"),
+                "intset",
+                "hashtable",
+            ),
+            (
+                b"list-max-listpack-size",
+                |i| format!("RPUSH k e{i}"),
+                "listpack",
+                "quicklist",
+            ),
+        ];
+        for &(config, write, small, large) in cases {
+            let name = String::from_utf8_lossy(config);
+            let mut rt = Runtime::default_strict();
+            let write_member = |rt: &mut Runtime, i: u32| {
+                let line = write(i);
+                let argv: Vec<&[u8]> = line.split(' ').map(str::as_bytes).collect();
+                rt.execute_frame(command(&argv), 0);
+            };
+
+            config_set(&mut rt, config, b"128");
+            for i in 0..100 {
+                write_member(&mut rt, i);
+            }
+            assert_eq!(encoding(&mut rt, b"k"), small, "{name}: 100 under 128");
+            config_set(&mut rt, config, b"64");
+            assert_eq!(
+                encoding(&mut rt, b"k"),
+                small,
+                "{name}: lowering converts nothing"
+            );
+            write_member(&mut rt, 100);
+            assert_eq!(encoding(&mut rt, b"k"), large, "{name}: next add past 64");
+
+            config_set(&mut rt, config, b"1000");
+            write_member(&mut rt, 101);
+            assert_eq!(
+                encoding(&mut rt, b"k"),
+                large,
+                "{name}: raising never downgrades"
+            );
+        }
+    }
+
     #[test]
     fn config_set_maxmemory_accepts_redis_memory_suffixes() {
         // (frankenredis-memtu) Empty input, bare suffixes ('b' / 'kb' /
@@ -69617,6 +71542,8 @@ user bob reset off nopass +@all
             max_array_len: 1024,
             max_recursion_depth: 128,
             allow_resp3: false,
+            preserve_resp3: false,
+            keep_attributes: false,
         };
 
         // Hardcoded list of 0x00-prefixed seeds the generator
@@ -69876,6 +71803,99 @@ user bob reset off nopass +@all
         );
     }
 
+    #[test]
+    fn client_no_evict_and_no_touch_show_in_client_info_until_reset() {
+        let mut rt = Runtime::default_strict();
+        let client_info_flags = |rt: &mut Runtime, now_ms| {
+            let RespFrame::BulkString(Some(info)) =
+                rt.execute_frame(command(&[b"CLIENT", b"INFO"]), now_ms)
+            else {
+                panic!("CLIENT INFO is a bulk string");
+            };
+            String::from_utf8(info)
+                .expect("utf8")
+                .split(' ')
+                .find_map(|field| field.strip_prefix("flags=").map(str::to_string))
+                .expect("flags field")
+        };
+
+        assert_eq!(client_info_flags(&mut rt, 0), "N");
+        for flag in [&b"NO-EVICT"[..], b"NO-TOUCH"] {
+            assert_eq!(
+                rt.execute_frame(command(&[b"CLIENT", flag, b"ON"]), 1),
+                RespFrame::SimpleString("OK".to_string())
+            );
+        }
+        assert_eq!(client_info_flags(&mut rt, 2), "eT");
+        assert_eq!(
+            rt.execute_frame(command(&[b"RESET"]), 3),
+            RespFrame::SimpleString("RESET".to_string())
+        );
+        assert_eq!(client_info_flags(&mut rt, 4), "N");
+    }
+
+    #[test]
+    fn maxmemory_clients_evicts_largest_clients_but_spares_no_evict() {
+        let mut rt = Runtime::default_strict();
+        let mut client_ids = Vec::new();
+        for (no_evict, output_buffer_bytes) in [
+            (true, 40_000),
+            (false, 20_000),
+            (false, 10_000),
+            (false, 1_000),
+        ] {
+            let session = rt.new_session();
+            let previous = rt.swap_session(session);
+            if no_evict {
+                assert_eq!(
+                    rt.execute_frame(command(&[b"CLIENT", b"NO-EVICT", b"ON"]), 0),
+                    RespFrame::SimpleString("OK".to_string())
+                );
+            }
+            let mut session = rt.swap_session(previous);
+            session.output_buffer_bytes = output_buffer_bytes;
+            rt.record_client_session(&session);
+            client_ids.push(session.client_id);
+        }
+        let [no_evict, big, medium, _small] = client_ids[..] else {
+            unreachable!()
+        };
+
+        // maxmemory-clients 0 (the default) disables client eviction.
+        assert!(rt.evict_clients_over_maxmemory_clients().is_empty());
+
+        // The evictable clients hold ~32KB; dropping the 20KB one is enough.
+        // The NO-EVICT client holds more than the whole budget and stays.
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"SET", b"maxmemory-clients", b"20000"]),
+                1
+            ),
+            RespFrame::SimpleString("OK".to_string())
+        );
+        assert_eq!(rt.evict_clients_over_maxmemory_clients(), vec![big]);
+        assert_eq!(rt.server.pending_client_kills, vec![big]);
+        assert!(!rt.server.pending_client_kills.contains(&no_evict));
+        assert_eq!(rt.server.store.stat_evicted_clients, 1);
+        // A client already queued for disconnect is not evicted twice.
+        assert!(rt.evict_clients_over_maxmemory_clients().is_empty());
+
+        // A percentage is taken of maxmemory: 5% of 100000 leaves room for the
+        // small client only.
+        for (param, value) in [
+            (&b"maxmemory"[..], &b"100000"[..]),
+            (b"maxmemory-clients", b"5%"),
+        ] {
+            assert_eq!(
+                rt.execute_frame(command(&[b"CONFIG", b"SET", param, value]), 2),
+                RespFrame::SimpleString("OK".to_string())
+            );
+        }
+        assert_eq!(rt.evict_clients_over_maxmemory_clients(), vec![medium]);
+        assert_eq!(rt.server.pending_client_kills, vec![big, medium]);
+        assert_eq!(rt.server.store.stat_evicted_clients, 2);
+    }
+
     #[test]
     fn object_idletime_matches_missing_key_and_lfu_policy_errors() {
         let mut rt = Runtime::default_strict();
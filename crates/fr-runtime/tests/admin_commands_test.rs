@@ -371,6 +371,69 @@ fn debug_set_active_expire_accepts_nonzero_atoi_values() {
     assert_eq!(resp, RespFrame::SimpleString("OK".to_string()));
 }
 
+#[test]
+fn debug_set_active_expire_toggles_the_active_cycle() {
+    let mut rt = Runtime::default_strict();
+    rt.set_enable_debug_command("yes");
+    let resp = rt.execute_frame(command(&[b"DEBUG", b"SET-ACTIVE-EXPIRE", b"0"]), 0);
+    assert_eq!(resp, RespFrame::SimpleString("OK".to_string()));
+    rt.execute_frame(command(&[b"SET", b"k", b"v", b"PX", b"100"]), 0);
+
+    // Disabled: the cycle leaves the dead key for lazy expiry.
+    let stats = rt.run_server_cron_active_expire_cycle(5_000);
+    assert_eq!(stats.evicted_keys, 0);
+    assert_eq!(
+        rt.execute_frame(command(&[b"DBSIZE"]), 5_000),
+        RespFrame::Integer(1)
+    );
+
+    rt.execute_frame(command(&[b"DEBUG", b"SET-ACTIVE-EXPIRE", b"1"]), 5_000);
+    let stats = rt.run_server_cron_active_expire_cycle(5_000);
+    assert_eq!(stats.evicted_keys, 1);
+    assert_eq!(
+        rt.execute_frame(command(&[b"DBSIZE"]), 5_000),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn debug_object_describes_every_value_type() {
+    let mut rt = Runtime::default_strict();
+    rt.set_enable_debug_command("yes");
+    let big = vec![b'x'; 5_000];
+    let big = big.as_slice();
+    let seeds: &[(&[&[u8]], &str)] = &[
+        (&[b"SET", b"str", b"hello"], "embstr"),
+        (&[b"SET", b"num", b"12345678901"], "int"),
+        (&[b"RPUSH", b"list", b"a", b"b"], "listpack"),
+        (&[b"RPUSH", b"biglist", big, big, big], "quicklist"),
+        (&[b"HSET", b"hash", b"f", b"v"], "listpack"),
+        (&[b"SADD", b"ints", b"1", b"2"], "intset"),
+        (&[b"SADD", b"set", b"a"], "listpack"),
+        (&[b"ZADD", b"zset", b"1", b"m"], "listpack"),
+        (&[b"XADD", b"stream", b"1-1", b"f", b"v"], "stream"),
+    ];
+    for (seed, encoding) in seeds {
+        rt.execute_frame(command(seed), 0);
+        let key = seed[1];
+        let info = match rt.execute_frame(command(&[b"DEBUG", b"OBJECT", key]), 0) {
+            RespFrame::SimpleString(info) => info,
+            other => panic!("expected status reply, got: {other:?}"),
+        };
+        let prefix = format!("Value at:0x0 refcount:1 encoding:{encoding} serializedlength:");
+        assert!(info.starts_with(&prefix), "{info}");
+        assert!(info.contains(" lru_seconds_idle:0"), "{info}");
+        assert_eq!(
+            info.contains(" ql_nodes:"),
+            *encoding == "quicklist",
+            "{info}"
+        );
+    }
+
+    let resp = rt.execute_frame(command(&[b"DEBUG", b"OBJECT", b"missing"]), 0);
+    assert_eq!(resp, RespFrame::Error("ERR no such key".to_string()));
+}
+
 #[test]
 fn debug_wrong_arity() {
     let mut rt = Runtime::default_strict();
@@ -0,0 +1,253 @@
+//! `fr_command::capabilities()` and the gaps it admits to.
+//!
+//! Every `Partial` option in the capability table has an ignored test below
+//! whose reason reads `partial: <command> <option>` and which asserts the
+//! upstream behavior. `partial_options_match_ignored_tests` reads this file and
+//! fails when the table and the ignored tests disagree, so closing a gap means
+//! un-ignoring its test and dropping the option from the table together.
+//!
+//!   cargo test -p fr-runtime --test capability_gaps -- --include-ignored
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use fr_command::{CommandId, SupportLevel, capabilities, is_known_command};
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+use fr_store::REDIS_COMPAT_VERSION;
+
+const SOURCE: &str = include_str!("capability_gaps.rs");
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+fn bulk(bytes: &[u8]) -> RespFrame {
+    RespFrame::BulkString(Some(bytes.to_vec()))
+}
+
+fn text(frame: RespFrame) -> String {
+    match frame {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("utf8"),
+        other => panic!("expected a bulk string, got {other:?}"),
+    }
+}
+
+/// A runtime with DEBUG enabled that snapshots to its own RDB file.
+fn reloadable(test: &str) -> (Runtime, PathBuf) {
+    let dir =
+        std::env::temp_dir().join(format!("fr_capability_gaps_{test}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("temp dir");
+    let rdb_path = dir.join("dump.rdb");
+    let mut rt = Runtime::default_strict();
+    rt.set_enable_debug_command("yes");
+    rt.set_rdb_path(rdb_path.clone());
+    (rt, rdb_path)
+}
+
+#[test]
+fn capabilities_cover_the_dispatcher() {
+    let table = capabilities();
+    let names: BTreeSet<&str> = table.iter().map(|command| command.name).collect();
+    assert_eq!(names.len(), table.len(), "duplicate command names");
+    for command in table {
+        assert_eq!(
+            command.redis_version, REDIS_COMPAT_VERSION,
+            "{}",
+            command.name
+        );
+        // `id` is set exactly for the commands dispatch_argv classifies.
+        assert_eq!(
+            command.id.is_some(),
+            is_known_command(command.name.as_bytes()),
+            "{}",
+            command.name
+        );
+    }
+
+    let find = |name: &str| {
+        *table
+            .iter()
+            .find(|command| command.name == name)
+            .unwrap_or_else(|| panic!("{name} missing"))
+    };
+    let get = find("get");
+    assert_eq!(get.id, Some(CommandId::Get));
+    assert_eq!(get.support, SupportLevel::Full);
+    // Transactions are run by the connection runtime, not dispatch_argv.
+    assert_eq!(find("multi").id, None);
+    assert_eq!(find("module").support, SupportLevel::Stub);
+    assert!(matches!(
+        find("lolwut").support,
+        SupportLevel::Partial { .. }
+    ));
+}
+
+#[test]
+fn partial_options_match_ignored_tests() {
+    let in_table: BTreeSet<String> = capabilities()
+        .iter()
+        .flat_map(|command| match command.support {
+            SupportLevel::Partial { missing_options } => missing_options
+                .iter()
+                .map(|option| format!("{} {option}", command.name))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    let ignored: BTreeSet<String> = SOURCE
+        .lines()
+        .filter_map(|line| {
+            line.trim()
+                .strip_prefix("#[ignore = \"partial: ")?
+                .strip_suffix("\"]")
+                .map(str::to_string)
+        })
+        .collect();
+    assert!(!in_table.is_empty());
+    assert_eq!(
+        in_table, ignored,
+        "every Partial option needs exactly one `#[ignore = \"partial: <command> <option>\"]` test"
+    );
+}
+
+#[test]
+fn info_capabilities_summarizes_the_table() {
+    let mut rt = Runtime::default_strict();
+    let info = text(rt.execute_frame(command(&[b"INFO", b"capabilities"]), 0));
+    let lines: Vec<&str> = info.lines().collect();
+    assert_eq!(lines[0], "# Capabilities");
+    assert!(lines.contains(&format!("redis_parity_version:{REDIS_COMPAT_VERSION}").as_str()));
+    assert!(lines.contains(&format!("commands:{}", capabilities().len()).as_str()));
+    assert!(lines.contains(&"cmd_lolwut:support=partial,missing=VERSION 5|VERSION 6"));
+    assert!(lines.contains(&"cmd_module:support=stub"));
+    assert!(!lines.iter().any(|line| line.starts_with("cmd_get:")));
+
+    // Not an upstream section, so INFO ALL and the default INFO leave it out.
+    for argv in [&[&b"INFO"[..], b"ALL"][..], &[&b"INFO"[..]]] {
+        assert!(!text(rt.execute_frame(command(argv), 1)).contains("# Capabilities"));
+    }
+}
+
+#[test]
+#[ignore = "partial: lolwut VERSION 5"]
+fn lolwut_version_5_draws_schotter() {
+    let mut rt = Runtime::default_strict();
+    let art = text(rt.execute_frame(command(&[b"LOLWUT", b"VERSION", b"5"]), 0));
+    assert!(
+        art.ends_with(&format!(
+            "Georg Nees - schotter, plotter on paper, 1968. Redis ver. {REDIS_COMPAT_VERSION}\n"
+        )),
+        "{art}"
+    );
+}
+
+#[test]
+#[ignore = "partial: lolwut VERSION 6"]
+fn lolwut_version_6_draws_plaguemon() {
+    let mut rt = Runtime::default_strict();
+    let art = text(rt.execute_frame(command(&[b"LOLWUT", b"VERSION", b"6"]), 0));
+    assert!(
+        art.contains("Dedicated to the 8 bit game developers of past and present."),
+        "{art}"
+    );
+    assert!(
+        art.contains("Original 8 bit image from Plaguemon by hikikomori."),
+        "{art}"
+    );
+}
+
+#[test]
+#[ignore = "partial: debug RELOAD NOSAVE"]
+fn debug_reload_nosave_loads_the_existing_snapshot() {
+    let (mut rt, rdb_path) = reloadable("nosave");
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"saved", b"1"]), 1),
+        ok()
+    );
+    assert_eq!(rt.execute_frame(command(&[b"DEBUG", b"RELOAD"]), 2), ok());
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"unsaved", b"2"]), 3),
+        ok()
+    );
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"DEBUG", b"RELOAD", b"NOSAVE"]), 4),
+        ok()
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"saved"]), 5),
+        bulk(b"1")
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"EXISTS", b"unsaved"]), 5),
+        RespFrame::Integer(0)
+    );
+    let _ = std::fs::remove_file(rdb_path);
+}
+
+#[test]
+#[ignore = "partial: debug RELOAD NOFLUSH"]
+fn debug_reload_noflush_keeps_keys_missing_from_the_snapshot() {
+    let (mut rt, rdb_path) = reloadable("noflush");
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"saved", b"1"]), 1),
+        ok()
+    );
+    assert_eq!(rt.execute_frame(command(&[b"DEBUG", b"RELOAD"]), 2), ok());
+    assert_eq!(
+        rt.execute_frame(command(&[b"DEL", b"saved"]), 3),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(rt.execute_frame(command(&[b"SET", b"kept", b"2"]), 3), ok());
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"DEBUG", b"RELOAD", b"NOSAVE", b"NOFLUSH"]), 4),
+        ok()
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"saved"]), 5),
+        bulk(b"1")
+    );
+    assert_eq!(rt.execute_frame(command(&[b"GET", b"kept"]), 5), bulk(b"2"));
+    let _ = std::fs::remove_file(rdb_path);
+}
+
+#[test]
+#[ignore = "partial: debug RELOAD MERGE"]
+fn debug_reload_merge_lets_the_snapshot_win_on_conflicts() {
+    let (mut rt, rdb_path) = reloadable("merge");
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"both", b"old"]), 1),
+        ok()
+    );
+    assert_eq!(rt.execute_frame(command(&[b"DEBUG", b"RELOAD"]), 2), ok());
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"both", b"new"]), 3),
+        ok()
+    );
+    assert_eq!(rt.execute_frame(command(&[b"SET", b"kept", b"2"]), 3), ok());
+
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"DEBUG", b"RELOAD", b"NOSAVE", b"NOFLUSH", b"MERGE"]),
+            4
+        ),
+        ok()
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"both"]), 5),
+        bulk(b"old")
+    );
+    assert_eq!(rt.execute_frame(command(&[b"GET", b"kept"]), 5), bulk(b"2"));
+    let _ = std::fs::remove_file(rdb_path);
+}
@@ -0,0 +1,124 @@
+//! CLIENT ID / SETNAME / GETNAME / LIST / INFO / KILL across two sessions.
+//!
+//! A multi-client server swaps the active `ClientSession` per connection and
+//! syncs each one into the registry with `record_client_session`; this drives
+//! the same sequence by hand. Every session keeps its own id and name, LIST
+//! renders one `id=... name=...` line per registered client (filterable by
+//! TYPE), INFO only the caller, and KILL ID queues the target for closure.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+fn text(frame: RespFrame) -> String {
+    match frame {
+        RespFrame::BulkString(Some(data)) => String::from_utf8(data).expect("utf8 reply"),
+        other => panic!("expected bulk string, got: {other:?}"),
+    }
+}
+
+fn names(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .map(|line| {
+            assert!(line.starts_with("id="), "{line}");
+            line.split(' ')
+                .find_map(|field| field.strip_prefix("name="))
+                .expect("name field")
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn sessions_keep_their_own_identity_and_show_up_in_list() {
+    let mut rt = Runtime::default_strict();
+    let alpha_id = rt.client_id();
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"ID"]), 1_000),
+        RespFrame::Integer(alpha_id as i64)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"SETNAME", b"bad name"]), 1_000),
+        RespFrame::Error(
+            "ERR Client names cannot contain spaces, newlines or special characters.".to_string()
+        )
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"SETNAME", b"alpha"]), 1_000),
+        ok()
+    );
+
+    // Switch to a second connection and subscribe it.
+    let beta = rt.new_session();
+    let alpha = rt.swap_session(beta);
+    let beta_id = rt.client_id();
+    assert_ne!(beta_id, alpha_id);
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"GETNAME"]), 2_000),
+        RespFrame::BulkString(None)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"SETNAME", b"beta"]), 2_000),
+        ok()
+    );
+    rt.execute_frame(command(&[b"SUBSCRIBE", b"news"]), 2_000);
+    let beta = rt.swap_session(alpha);
+    rt.record_client_session(&beta);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"GETNAME"]), 3_000),
+        RespFrame::BulkString(Some(b"alpha".to_vec()))
+    );
+    let listing = text(rt.execute_frame(command(&[b"CLIENT", b"LIST"]), 3_000));
+    assert_eq!(names(&listing), ["alpha", "beta"], "{listing}");
+    let beta_line = listing.lines().nth(1).unwrap();
+    assert!(
+        beta_line.starts_with(&format!("id={beta_id} ")),
+        "{beta_line}"
+    );
+    assert!(beta_line.contains(" age=1 idle=1 "), "{beta_line}");
+    assert!(beta_line.contains(" sub=1 "), "{beta_line}");
+    assert!(beta_line.contains(" cmd=subscribe "), "{beta_line}");
+
+    let pubsub = text(rt.execute_frame(command(&[b"CLIENT", b"LIST", b"TYPE", b"pubsub"]), 3_000));
+    assert_eq!(names(&pubsub), ["beta"]);
+    let normal = text(rt.execute_frame(command(&[b"CLIENT", b"LIST", b"TYPE", b"normal"]), 3_000));
+    assert_eq!(names(&normal), ["alpha"]);
+
+    let info = text(rt.execute_frame(command(&[b"CLIENT", b"INFO"]), 3_000));
+    assert_eq!(names(&info), ["alpha"]);
+    assert!(info.starts_with(&format!("id={alpha_id} ")), "{info}");
+}
+
+#[test]
+fn kill_by_id_queues_the_target_for_closure() {
+    let mut rt = Runtime::default_strict();
+    let peer = rt.new_session();
+    let peer_id = peer.client_id;
+    rt.record_client_session(&peer);
+
+    let target = peer_id.to_string();
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"KILL", b"ID", target.as_bytes()]), 0),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(rt.server.pending_client_kills, vec![peer_id]);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"CLIENT", b"KILL", b"ID", b"999999"]), 0),
+        RespFrame::Integer(0)
+    );
+}
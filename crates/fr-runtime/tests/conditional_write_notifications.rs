@@ -0,0 +1,206 @@
+//! Conditional writes publish keyspace events only when they changed data.
+//!
+//! SET NX/XX, SETNX, MSETNX, HSETNX, EXPIRE NX/XX/GT/LT and ZADD NX/XX/GT/LT
+//! can all decline to write. Upstream fires `notifyKeyspaceEvent` only on the
+//! branch that performed the write, so a declined call must be silent on
+//! `__keyevent@0__:*` while its successful twin publishes exactly one event per
+//! modified key.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+use fr_store::PubSubMessage;
+
+fn command(args: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        args.iter()
+            .map(|a| RespFrame::BulkString(Some(a.to_vec())))
+            .collect(),
+    ))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+/// Runtime whose default session issues writes while a second session holds a
+/// `__keyevent@0__:*` pattern subscription. Returns the subscriber's id.
+fn runtime_with_keyevent_subscriber() -> (Runtime, u64) {
+    let mut rt = Runtime::default_strict();
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"CONFIG", b"SET", b"notify-keyspace-events", b"EA"]),
+            0,
+        ),
+        ok()
+    );
+    let subscriber = rt.new_session();
+    let writer = rt.swap_session(subscriber);
+    rt.execute_frame(command(&[b"PSUBSCRIBE", b"__keyevent@0__:*"]), 0);
+    let subscriber = rt.swap_session(writer);
+    (rt, subscriber.client_id)
+}
+
+/// Drain the subscriber and render each event as `(event, key)`.
+fn events(rt: &mut Runtime, subscriber: u64) -> Vec<(String, String)> {
+    rt.drain_pubsub_for_client(subscriber)
+        .into_iter()
+        .map(|message| match message {
+            PubSubMessage::PMessage { channel, data, .. } => {
+                let channel = String::from_utf8(channel).expect("utf8 channel");
+                let event = channel
+                    .strip_prefix("__keyevent@0__:")
+                    .expect("keyevent channel")
+                    .to_string();
+                (event, String::from_utf8(data).expect("utf8 key"))
+            }
+            other => panic!("unexpected pubsub message {other:?}"),
+        })
+        .collect()
+}
+
+/// One scripted command: argv, expected reply, expected `(event, key)`s.
+type Step<'a> = (Vec<&'a [u8]>, RespFrame, Vec<(&'a str, &'a str)>);
+
+#[test]
+fn conditional_writes_publish_only_when_they_write() {
+    let (mut rt, subscriber) = runtime_with_keyevent_subscriber();
+    let null = RespFrame::BulkString(None);
+
+    let script: Vec<Step> = vec![
+        (vec![b"SET", b"k", b"v", b"NX"], ok(), vec![("set", "k")]),
+        (vec![b"SET", b"k", b"v2", b"NX"], null.clone(), vec![]),
+        (vec![b"SET", b"k", b"v3", b"XX"], ok(), vec![("set", "k")]),
+        (vec![b"SET", b"absent", b"v", b"XX"], null.clone(), vec![]),
+        (vec![b"SETNX", b"k", b"x"], RespFrame::Integer(0), vec![]),
+        (
+            vec![b"SETNX", b"k2", b"x"],
+            RespFrame::Integer(1),
+            vec![("set", "k2")],
+        ),
+        (
+            vec![b"MSETNX", b"k", b"a", b"k3", b"b"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"MSETNX", b"k3", b"a", b"k4", b"b"],
+            RespFrame::Integer(1),
+            vec![("set", "k3"), ("set", "k4")],
+        ),
+        (
+            vec![b"HSETNX", b"h", b"f", b"v"],
+            RespFrame::Integer(1),
+            vec![("hset", "h")],
+        ),
+        (
+            vec![b"HSETNX", b"h", b"f", b"w"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"EXPIRE", b"k", b"100", b"NX"],
+            RespFrame::Integer(1),
+            vec![("expire", "k")],
+        ),
+        (
+            vec![b"EXPIRE", b"k", b"200", b"NX"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"EXPIRE", b"k", b"50", b"GT"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"EXPIRE", b"k", b"50", b"LT"],
+            RespFrame::Integer(1),
+            vec![("expire", "k")],
+        ),
+        (
+            vec![b"EXPIRE", b"k2", b"50", b"XX"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"ZADD", b"z", b"1", b"a"],
+            RespFrame::Integer(1),
+            vec![("zadd", "z")],
+        ),
+        (
+            vec![b"ZADD", b"z", b"XX", b"1", b"b"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"ZADD", b"z", b"XX", b"2", b"a"],
+            RespFrame::Integer(0),
+            vec![("zadd", "z")],
+        ),
+        (
+            vec![b"ZADD", b"z", b"XX", b"2", b"a"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"ZADD", b"z", b"NX", b"3", b"a"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"ZADD", b"z", b"GT", b"1", b"a"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+        (
+            vec![b"ZADD", b"absentz", b"XX", b"1", b"a"],
+            RespFrame::Integer(0),
+            vec![],
+        ),
+    ];
+
+    for (step, (argv, reply, expected)) in script.into_iter().enumerate() {
+        let now = step as u64 + 1;
+        assert_eq!(
+            rt.execute_frame(command(&argv), now),
+            reply,
+            "reply for step {step} {argv:?}"
+        );
+        let expected: Vec<(String, String)> = expected
+            .into_iter()
+            .map(|(event, key)| (event.to_string(), key.to_string()))
+            .collect();
+        assert_eq!(
+            events(&mut rt, subscriber),
+            expected,
+            "events for step {step} {argv:?}"
+        );
+    }
+}
+
+#[test]
+fn unconditional_multi_key_writes_publish_per_key() {
+    let (mut rt, subscriber) = runtime_with_keyevent_subscriber();
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"MSET", b"a", b"1", b"b", b"2"]), 1),
+        ok()
+    );
+    assert_eq!(
+        events(&mut rt, subscriber),
+        vec![
+            ("set".to_string(), "a".to_string()),
+            ("set".to_string(), "b".to_string()),
+        ]
+    );
+
+    // HMSET publishes one `hset` for the key no matter how many fields.
+    assert_eq!(
+        rt.execute_frame(command(&[b"HMSET", b"h", b"f1", b"v", b"f2", b"v"]), 2),
+        ok()
+    );
+    assert_eq!(
+        events(&mut rt, subscriber),
+        vec![("hset".to_string(), "h".to_string())]
+    );
+}
@@ -2,6 +2,7 @@
 
 use fr_protocol::RespFrame;
 use fr_runtime::Runtime;
+use fr_store::PubSubMessage;
 
 fn command(parts: &[&[u8]]) -> RespFrame {
     RespFrame::Array(Some(
@@ -403,3 +404,214 @@ fn restore_with_absttl() {
     let val_expired = rt.execute_frame(command(&[b"GET", b"abs_key"]), 11000);
     assert_eq!(val_expired, RespFrame::BulkString(None));
 }
+
+fn restore(
+    rt: &mut Runtime,
+    key: &[u8],
+    ttl: &[u8],
+    payload: &[u8],
+    opts: &[&[u8]],
+    now_ms: u64,
+) -> RespFrame {
+    let mut argv: Vec<&[u8]> = vec![b"RESTORE", key, ttl, payload];
+    argv.extend_from_slice(opts);
+    rt.execute_frame(command(&argv), now_ms)
+}
+
+fn info_keyspace(rt: &mut Runtime, now_ms: u64) -> String {
+    match rt.execute_frame(command(&[b"INFO", b"keyspace"]), now_ms) {
+        RespFrame::BulkString(Some(data)) => String::from_utf8(data).expect("utf8 INFO"),
+        other => panic!("expected INFO bulk string, got {other:?}"),
+    }
+}
+
+#[test]
+fn dump_restore_preserves_ttl_through_expiry_index() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"k", b"v", b"PX", b"5000"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"k"]), 1000));
+    let pttl = rt.execute_frame(command(&[b"PTTL", b"k"]), 1000);
+    assert_eq!(pttl, RespFrame::Integer(4000));
+    rt.execute_frame(command(&[b"DEL", b"k"]), 1000);
+
+    let ok = RespFrame::SimpleString("OK".to_string());
+    assert_eq!(restore(&mut rt, b"k", b"4000", &payload, &[], 1000), ok);
+    assert_eq!(
+        rt.execute_frame(command(&[b"PTTL", b"k"]), 1000),
+        RespFrame::Integer(4000)
+    );
+    assert!(info_keyspace(&mut rt, 1000).contains("db0:keys=1,expires=1,"));
+
+    // The restored TTL is an ordinary one: PERSIST clears it, EXPIRE re-arms it.
+    assert_eq!(
+        rt.execute_frame(command(&[b"PERSIST", b"k"]), 1001),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"TTL", b"k"]), 1001),
+        RespFrame::Integer(-1)
+    );
+    assert!(info_keyspace(&mut rt, 1001).contains("db0:keys=1,expires=0,"));
+    assert_eq!(
+        rt.execute_frame(command(&[b"EXPIRE", b"k", b"10"]), 1002),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"TTL", b"k"]), 1002),
+        RespFrame::Integer(10)
+    );
+
+    // The active-expiry cycle finds a restored volatile key without a lookup.
+    rt.execute_frame(command(&[b"DEL", b"k"]), 1003);
+    assert_eq!(restore(&mut rt, b"k", b"100", &payload, &[], 1003), ok);
+    let stats = rt.run_server_cron_active_expire_cycle(2000);
+    assert_eq!(stats.evicted_keys, 1);
+    assert!(!info_keyspace(&mut rt, 2000).contains("db0:"));
+}
+
+#[test]
+fn dump_restore_with_zero_ttl_persists() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"k", b"v", b"PX", b"5000"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"k"]), 0));
+    rt.execute_frame(command(&[b"DEL", b"k"]), 0);
+
+    let ok = RespFrame::SimpleString("OK".to_string());
+    assert_eq!(restore(&mut rt, b"k", b"0", &payload, &[], 0), ok);
+    assert_eq!(
+        rt.execute_frame(command(&[b"PTTL", b"k"]), 10_000),
+        RespFrame::Integer(-1)
+    );
+
+    // ABSTTL does not change what 0 means.
+    rt.execute_frame(command(&[b"DEL", b"k"]), 10_000);
+    assert_eq!(
+        restore(&mut rt, b"k", b"0", &payload, &[b"ABSTTL"], 10_000),
+        ok
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"PTTL", b"k"]), 10_000),
+        RespFrame::Integer(-1)
+    );
+}
+
+#[test]
+fn restore_replace_with_zero_ttl_clears_old_expiry() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"src", b"new"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"src"]), 0));
+    rt.execute_frame(command(&[b"SET", b"k", b"old", b"PX", b"1000"]), 0);
+    assert!(info_keyspace(&mut rt, 0).contains("db0:keys=2,expires=1,"));
+
+    assert_eq!(
+        restore(&mut rt, b"k", b"0", &payload, &[b"REPLACE"], 10),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"PTTL", b"k"]), 10),
+        RespFrame::Integer(-1)
+    );
+    assert!(info_keyspace(&mut rt, 10).contains("db0:keys=2,expires=0,"));
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"k"]), 5000),
+        RespFrame::BulkString(Some(b"new".to_vec()))
+    );
+}
+
+#[test]
+fn restore_absttl_in_the_past_restores_nothing() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"src", b"new"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"src"]), 0));
+    let ok = RespFrame::SimpleString("OK".to_string());
+
+    assert_eq!(
+        restore(&mut rt, b"k", b"500", &payload, &[b"ABSTTL"], 1000),
+        ok
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"EXISTS", b"k"]), 1000),
+        RespFrame::Integer(0)
+    );
+
+    // Under REPLACE the stale deadline deletes the existing value.
+    rt.execute_frame(command(&[b"SET", b"k", b"old"]), 1000);
+    assert_eq!(
+        restore(
+            &mut rt,
+            b"k",
+            b"1000",
+            &payload,
+            &[b"ABSTTL", b"REPLACE"],
+            1000
+        ),
+        ok
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"EXISTS", b"k"]), 1000),
+        RespFrame::Integer(0)
+    );
+
+    // The payload is still validated first.
+    assert_eq!(
+        restore(&mut rt, b"k", b"500", b"garbage", &[b"ABSTTL"], 1000),
+        RespFrame::Error("ERR DUMP payload version or checksum are wrong".to_string())
+    );
+}
+
+#[test]
+fn restore_rejects_negative_ttl() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"src", b"v"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"src"]), 0));
+    for opts in [&[][..], &[b"ABSTTL".as_slice()][..]] {
+        assert_eq!(
+            restore(&mut rt, b"k", b"-1", &payload, opts, 0),
+            RespFrame::Error("ERR Invalid TTL value, must be >= 0".to_string())
+        );
+    }
+    assert_eq!(
+        rt.execute_frame(command(&[b"EXISTS", b"k"]), 0),
+        RespFrame::Integer(0)
+    );
+}
+
+#[test]
+fn restore_absttl_in_the_past_notifies_del_only_when_it_deletes() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(
+        command(&[b"CONFIG", b"SET", b"notify-keyspace-events", b"Eg"]),
+        0,
+    );
+    rt.execute_frame(command(&[b"SET", b"src", b"new"]), 0);
+    let payload = extract_dump_payload(&rt.execute_frame(command(&[b"DUMP", b"src"]), 0));
+    rt.execute_frame(command(&[b"SET", b"k", b"old"]), 0);
+
+    let subscriber = rt.new_session();
+    let writer = rt.swap_session(subscriber);
+    rt.execute_frame(command(&[b"PSUBSCRIBE", b"__keyevent@0__:*"]), 0);
+    let subscriber = rt.swap_session(writer);
+    let events = |rt: &mut Runtime| -> Vec<Vec<u8>> {
+        rt.drain_pubsub_for_client(subscriber.client_id)
+            .into_iter()
+            .map(|message| match message {
+                PubSubMessage::PMessage { channel, .. } => channel,
+                other => panic!("unexpected pubsub message {other:?}"),
+            })
+            .collect()
+    };
+
+    restore(&mut rt, b"absent", b"500", &payload, &[b"ABSTTL"], 1000);
+    assert!(events(&mut rt).is_empty());
+    restore(
+        &mut rt,
+        b"k",
+        b"500",
+        &payload,
+        &[b"ABSTTL", b"REPLACE"],
+        1000,
+    );
+    assert_eq!(events(&mut rt), vec![b"__keyevent@0__:del".to_vec()]);
+    restore(&mut rt, b"k", b"0", &payload, &[b"ABSTTL"], 1000);
+    assert_eq!(events(&mut rt), vec![b"__keyevent@0__:restore".to_vec()]);
+}
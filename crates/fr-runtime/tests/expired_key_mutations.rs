@@ -0,0 +1,143 @@
+//! A logically expired key is absent to the command that finds it.
+//!
+//! Lazy expiry must happen-before the command's own type and parse checks: an
+//! expired "abc" makes INCR answer 1 rather than the not-an-integer error, an
+//! expired list lets HSET create a hash rather than fail with WRONGTYPE, and
+//! an expired list makes LPUSHX a no-op. Each case seeds an expired key of a
+//! conflicting type, runs the command on the manual clock, and requires the
+//! reply and the resulting key (TYPE, PTTL, DUMP) to be exactly what the same
+//! command produces on a keyspace where the key never existed. Active expiry
+//! is off so only the lazy path can remove the key. Both the runtime (with its
+//! fast paths) and plain `dispatch_argv` are checked.
+
+use fr_command::dispatch_argv;
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+use fr_store::Store;
+
+const SEEDED_AT: u64 = 1_000;
+const RUN_AT: u64 = 2_000;
+
+type Argv = &'static [&'static [u8]];
+
+const EXPIRED_STRING: &[Argv] = &[&[b"SET", b"k", b"abc", b"PX", b"100"]];
+const EXPIRED_LIST: &[Argv] = &[&[b"RPUSH", b"k", b"a", b"b"], &[b"PEXPIRE", b"k", b"100"]];
+const EXPIRED_HASH: &[Argv] = &[&[b"HSET", b"k", b"f", b"x"], &[b"PEXPIRE", b"k", b"100"]];
+const EXPIRED_SET: &[Argv] = &[&[b"SADD", b"k", b"a"], &[b"PEXPIRE", b"k", b"100"]];
+
+/// `(expired seed, command)`; the seed's type conflicts with the command.
+const CASES: &[(&[Argv], Argv)] = &[
+    (EXPIRED_STRING, &[b"INCR", b"k"]),
+    (EXPIRED_STRING, &[b"DECR", b"k"]),
+    (EXPIRED_STRING, &[b"INCRBY", b"k", b"5"]),
+    (EXPIRED_STRING, &[b"DECRBY", b"k", b"2"]),
+    (EXPIRED_STRING, &[b"INCRBYFLOAT", b"k", b"1.5"]),
+    (EXPIRED_LIST, &[b"INCR", b"k"]),
+    (EXPIRED_LIST, &[b"APPEND", b"k", b"x"]),
+    (EXPIRED_STRING, &[b"APPEND", b"k", b"x"]),
+    (EXPIRED_LIST, &[b"SETRANGE", b"k", b"2", b"xy"]),
+    (EXPIRED_LIST, &[b"SETBIT", b"k", b"3", b"1"]),
+    (EXPIRED_STRING, &[b"SETNX", b"k", b"v"]),
+    (EXPIRED_LIST, &[b"SET", b"k", b"v", b"NX"]),
+    (EXPIRED_STRING, &[b"SET", b"k", b"v", b"XX"]),
+    (EXPIRED_HASH, &[b"SET", b"k", b"v", b"GET"]),
+    (EXPIRED_LIST, &[b"GETDEL", b"k"]),
+    (EXPIRED_LIST, &[b"STRLEN", b"k"]),
+    (EXPIRED_STRING, &[b"HSET", b"k", b"f", b"v"]),
+    (EXPIRED_STRING, &[b"HSETNX", b"k", b"f", b"v"]),
+    (EXPIRED_STRING, &[b"HINCRBY", b"k", b"f", b"2"]),
+    (EXPIRED_STRING, &[b"HINCRBYFLOAT", b"k", b"f", b"2.5"]),
+    (EXPIRED_HASH, &[b"SADD", b"k", b"a"]),
+    (EXPIRED_SET, &[b"LPUSH", b"k", b"a"]),
+    (EXPIRED_STRING, &[b"RPUSH", b"k", b"a"]),
+    (EXPIRED_LIST, &[b"LPUSH", b"k", b"c"]),
+    (EXPIRED_LIST, &[b"LPUSHX", b"k", b"a"]),
+    (EXPIRED_LIST, &[b"RPUSHX", b"k", b"a"]),
+    (EXPIRED_STRING, &[b"LPUSHX", b"k", b"a"]),
+    (EXPIRED_LIST, &[b"LINSERT", b"k", b"BEFORE", b"a", b"z"]),
+    (EXPIRED_STRING, &[b"LLEN", b"k"]),
+    (EXPIRED_STRING, &[b"ZADD", b"k", b"1", b"a"]),
+    (EXPIRED_STRING, &[b"ZINCRBY", b"k", b"2", b"a"]),
+    (EXPIRED_LIST, &[b"SMOVE", b"k", b"dst", b"a"]),
+    (EXPIRED_HASH, &[b"PFADD", b"k", b"a"]),
+    (EXPIRED_STRING, &[b"XADD", b"k", b"1-1", b"f", b"v"]),
+    (EXPIRED_STRING, &[b"GEOADD", b"k", b"13.36", b"38.11", b"a"]),
+    (EXPIRED_STRING, &[b"EXPIRE", b"k", b"10"]),
+    (EXPIRED_STRING, &[b"PERSIST", b"k"]),
+    (EXPIRED_LIST, &[b"RENAMENX", b"src", b"k"]),
+];
+
+/// Runs a command against some keyspace on the manual clock.
+trait Keyspace {
+    fn run(&mut self, argv: &[&[u8]], now_ms: u64) -> RespFrame;
+}
+
+impl Keyspace for Runtime {
+    fn run(&mut self, argv: &[&[u8]], now_ms: u64) -> RespFrame {
+        let frame = RespFrame::Array(Some(
+            argv.iter()
+                .map(|arg| RespFrame::BulkString(Some(arg.to_vec())))
+                .collect(),
+        ));
+        self.execute_frame(frame, now_ms)
+    }
+}
+
+impl Keyspace for Store {
+    fn run(&mut self, argv: &[&[u8]], now_ms: u64) -> RespFrame {
+        let argv: Vec<Vec<u8>> = argv.iter().map(|arg| arg.to_vec()).collect();
+        dispatch_argv(&argv, self, now_ms).unwrap_or_else(|err| err.to_resp())
+    }
+}
+
+fn runtime() -> Runtime {
+    let mut rt = Runtime::default_strict();
+    rt.run(&[b"DEBUG", b"SET-ACTIVE-EXPIRE", b"0"], 0);
+    rt
+}
+
+/// The command's reply followed by what it left behind at `k`.
+fn outcome(keyspace: &mut impl Keyspace, command: &[&[u8]]) -> Vec<RespFrame> {
+    // RENAMENX needs a live source; it is unrelated to `k` and identical on
+    // both sides.
+    keyspace.run(&[b"SET", b"src", b"s"], RUN_AT);
+    vec![
+        keyspace.run(command, RUN_AT),
+        keyspace.run(&[b"TYPE", b"k"], RUN_AT),
+        keyspace.run(&[b"PTTL", b"k"], RUN_AT),
+        keyspace.run(&[b"DUMP", b"k"], RUN_AT),
+    ]
+}
+
+fn check<K: Keyspace>(fresh: impl Fn() -> K, layer: &str) {
+    for &(seed, command) in CASES {
+        let name = String::from_utf8_lossy(&command.join(&b' ')).into_owned();
+        let expected = outcome(&mut fresh(), command);
+
+        let mut expired = fresh();
+        for argv in seed {
+            let reply = expired.run(argv, SEEDED_AT);
+            assert!(!matches!(reply, RespFrame::Error(_)), "{name}: {reply:?}");
+        }
+        assert_eq!(
+            expired.run(&[b"PTTL", b"k"], SEEDED_AT),
+            RespFrame::Integer(100),
+            "{name}: seed"
+        );
+        assert_eq!(
+            outcome(&mut expired, command),
+            expected,
+            "{layer}: {name} on an expired key differs from a missing key"
+        );
+    }
+}
+
+#[test]
+fn runtime_commands_treat_an_expired_key_as_missing() {
+    check(runtime, "runtime");
+}
+
+#[test]
+fn dispatch_treats_an_expired_key_as_missing() {
+    check(Store::new, "dispatch_argv");
+}
@@ -0,0 +1,138 @@
+//! INFO, LOLWUT, MEMORY DOCTOR and CLIENT INFO/LIST reply with a verbatim
+//! string under RESP3 and a bulk string under RESP2, carrying the same text.
+//! INFO's text is also available as structured sections, and rendering those
+//! sections reproduces the INFO reply byte for byte.
+
+use fr_command::render_info_sections;
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+const NOW: u64 = 5_000;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn seeded() -> Runtime {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SET", b"a", b"1"]), NOW);
+    rt.execute_frame(command(&[b"SET", b"b", b"2", b"PX", b"60000"]), NOW);
+    rt.execute_frame(command(&[b"SELECT", b"3"]), NOW);
+    rt.execute_frame(command(&[b"SET", b"c", b"3"]), NOW);
+    rt.execute_frame(command(&[b"SELECT", b"0"]), NOW);
+    rt
+}
+
+fn resp3(rt: &mut Runtime) {
+    match rt.execute_frame(command(&[b"HELLO", b"3"]), NOW) {
+        RespFrame::Map(Some(_)) => {}
+        other => panic!("HELLO 3: {other:?}"),
+    }
+}
+
+#[test]
+fn text_replies_are_verbatim_under_resp3_and_bulk_under_resp2() {
+    let commands: &[&[&[u8]]] = &[
+        &[b"INFO", b"keyspace"],
+        &[b"INFO", b"server", b"replication"],
+        &[b"LOLWUT"],
+        &[b"MEMORY", b"DOCTOR"],
+        &[b"CLIENT", b"INFO"],
+        &[b"CLIENT", b"LIST"],
+    ];
+    for parts in commands {
+        let name = String::from_utf8_lossy(&parts.join(&b' ')).into_owned();
+        let mut rt = seeded();
+        let RespFrame::BulkString(Some(resp2)) = rt.execute_frame(command(parts), NOW) else {
+            panic!("{name}: RESP2 reply is not a bulk string");
+        };
+        resp3(&mut rt);
+        let RespFrame::Verbatim(text) = rt.execute_frame(command(parts), NOW) else {
+            panic!("{name}: RESP3 reply is not a verbatim string");
+        };
+        // CLIENT INFO reports the negotiated protocol; everything else is
+        // the same text on both protocols.
+        let resp2 = String::from_utf8(resp2).unwrap();
+        assert_eq!(
+            text.replace(" resp=3 ", " resp=2 "),
+            resp2,
+            "{name}: RESP2 and RESP3 text differ"
+        );
+    }
+}
+
+#[test]
+fn info_sections_render_to_the_info_reply() {
+    for requested in [
+        &["keyspace"][..],
+        &["server"],
+        &["persistence", "replication"],
+        &["keyspace", "clients", "cpu"],
+    ] {
+        let mut rt = seeded();
+        let sections = rt.info_sections(requested, NOW);
+        let mut parts: Vec<&[u8]> = vec![b"INFO"];
+        parts.extend(requested.iter().map(|name| name.as_bytes()));
+        let reply = rt.execute_frame(command(&parts), NOW);
+        assert_eq!(
+            reply,
+            RespFrame::BulkString(Some(render_info_sections(&sections).into_bytes())),
+            "INFO {requested:?}"
+        );
+    }
+}
+
+#[test]
+fn info_sections_are_structured() {
+    let mut rt = seeded();
+    let sections = rt.info_sections(&["keyspace", "server"], NOW);
+    let names: Vec<&str> = sections
+        .iter()
+        .map(|section| section.name.as_str())
+        .collect();
+    assert_eq!(names, ["Server", "Keyspace"]);
+
+    let keyspace = &sections[1];
+    assert_eq!(
+        keyspace.fields,
+        [
+            (
+                "db0".to_string(),
+                "keys=2,expires=1,avg_ttl=60000".to_string()
+            ),
+            ("db3".to_string(), "keys=1,expires=0,avg_ttl=0".to_string()),
+        ]
+    );
+    let server = &sections[0];
+    assert!(
+        server
+            .fields
+            .iter()
+            .any(|(name, value)| name == "redis_mode" && value == "standalone"),
+        "{server:?}"
+    );
+    assert_eq!(
+        render_info_sections(&sections[1..]),
+        "# Keyspace\r\ndb0:keys=2,expires=1,avg_ttl=60000\r\ndb3:keys=1,expires=0,avg_ttl=0\r\n"
+    );
+}
+
+#[test]
+fn empty_info_selection_is_an_empty_verbatim_string_under_resp3() {
+    let mut rt = Runtime::default_strict();
+    assert!(rt.info_sections(&["nosuchsection"], NOW).is_empty());
+    assert_eq!(
+        rt.execute_frame(command(&[b"INFO", b"nosuchsection"]), NOW),
+        RespFrame::BulkString(Some(Vec::new()))
+    );
+    resp3(&mut rt);
+    assert_eq!(
+        rt.execute_frame(command(&[b"INFO", b"nosuchsection"]), NOW),
+        RespFrame::Verbatim(String::new())
+    );
+}
@@ -0,0 +1,245 @@
+//! Peak-memory gate for the streamed collection replies.
+//!
+//! LRANGE, HGETALL, HKEYS, HVALS, HMGET, SMEMBERS, ZRANGE (with or without
+//! WITHSCORES), KEYS and XRANGE encode their replies
+//! element by element into the connection's output buffer instead of building
+//! a `Vec<RespFrame>` first. The reply bytes themselves still land in `out`;
+//! what must not appear is per-element heap traffic. The shared `alloc_meter`
+//! (fr-command's test support, included by path) tracks allocation counts and
+//! live/peak heap bytes on the measuring thread only.
+
+#[path = "../../fr-command/tests/support/alloc_meter.rs"]
+mod alloc_meter;
+
+use alloc_meter::measure;
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+const NOW: u64 = 1;
+const BIG_LIST: usize = 1_000_000;
+const ELEMENTS: usize = 100_000;
+const BATCH: usize = 10_000;
+const HASH_FIELDS: usize = 10_000;
+/// Allocations a streamed reply may make regardless of its size: the output
+/// buffer doubling plus the fixed per-command bookkeeping.
+const MAX_ALLOCATIONS: usize = 256;
+/// Heap a streamed reply may hold on top of the output buffer itself.
+const MAX_OVERHEAD: usize = 256 * 1024;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+/// A runtime with the slowlog off: a slow first run of a wide command (HMGET
+/// with 10k fields) copies its whole argv into the slowlog, which is per-argument
+/// heap traffic the gates below would misread as a non-streamed reply.
+fn runtime() -> Runtime {
+    let mut rt = Runtime::default_strict();
+    let reply = rt.execute_frame(
+        command(&[b"CONFIG", b"SET", b"slowlog-log-slower-than", b"-1"]),
+        NOW,
+    );
+    assert_eq!(reply, RespFrame::SimpleString("OK".to_string()));
+    rt
+}
+
+/// Issue `head... args...` in batches of `BATCH` argument groups.
+fn load(rt: &mut Runtime, head: &[&[u8]], args: impl Iterator<Item = Vec<Vec<u8>>>) {
+    let mut args = args.peekable();
+    while args.peek().is_some() {
+        let mut frame: Vec<RespFrame> = head
+            .iter()
+            .map(|part| RespFrame::BulkString(Some(part.to_vec())))
+            .collect();
+        for group in args.by_ref().take(BATCH) {
+            frame.extend(group.into_iter().map(|a| RespFrame::BulkString(Some(a))));
+        }
+        let reply = rt.execute_frame(RespFrame::Array(Some(frame)), NOW);
+        assert!(!matches!(reply, RespFrame::Error(_)), "{reply:?}");
+    }
+}
+
+/// Stream one reply through `op`, check it is byte-identical to the generic
+/// dispatch of `argv`, and check the streamed path held no per-element heap.
+fn assert_streams(
+    rt: &mut Runtime,
+    argv: &[&[u8]],
+    elements: usize,
+    op: impl FnOnce(&mut Runtime, &mut Vec<u8>) -> Option<()>,
+) {
+    let name = String::from_utf8_lossy(argv[0]).into_owned();
+    let (out, streamed) = measure(|| {
+        let mut out = Vec::new();
+        op(rt, &mut out).unwrap_or_else(|| panic!("{name} takes the streamed path"));
+        out
+    });
+    let (generic, materialized) = measure(|| rt.execute_frame(command(argv), NOW).to_bytes());
+    assert_eq!(out, generic, "{name} streamed reply differs from generic");
+
+    // Guard against a vacuous pass: the frame tree costs at least one
+    // allocation per element.
+    assert!(
+        materialized.allocations >= elements,
+        "{name}: generic path made only {} allocations for {elements} elements",
+        materialized.allocations
+    );
+    assert!(
+        streamed.allocations <= MAX_ALLOCATIONS,
+        "{name}: streamed path made {} allocations for {elements} elements",
+        streamed.allocations
+    );
+    assert!(
+        streamed.peak.unsigned_abs() <= out.capacity() + MAX_OVERHEAD,
+        "{name}: streamed peak {} B vs output buffer {} B",
+        streamed.peak,
+        out.capacity()
+    );
+}
+
+#[test]
+fn million_element_lrange_streams_without_frames() {
+    let mut rt = runtime();
+    load(
+        &mut rt,
+        &[b"RPUSH", b"big"],
+        (0..BIG_LIST).map(|i| vec![format!("element:{i}").into_bytes()]),
+    );
+    assert_streams(
+        &mut rt,
+        &[b"LRANGE", b"big", b"0", b"-1"],
+        BIG_LIST,
+        |rt, out| rt.execute_plain_lrange_borrowed_into(b"big", b"0", b"-1", NOW, out),
+    );
+}
+
+#[test]
+fn collection_replies_stream_without_frames() {
+    let mut rt = runtime();
+    load(
+        &mut rt,
+        &[b"HSET", b"hash"],
+        (0..ELEMENTS).map(|i| {
+            vec![
+                format!("field:{i}").into_bytes(),
+                format!("value:{i}").into_bytes(),
+            ]
+        }),
+    );
+    load(
+        &mut rt,
+        &[b"SADD", b"set"],
+        (0..ELEMENTS).map(|i| vec![format!("member:{i}").into_bytes()]),
+    );
+    load(
+        &mut rt,
+        &[b"ZADD", b"zset"],
+        (0..ELEMENTS).map(|i| {
+            vec![
+                i.to_string().into_bytes(),
+                format!("member:{i}").into_bytes(),
+            ]
+        }),
+    );
+    load(
+        &mut rt,
+        &[b"MSET"],
+        (0..ELEMENTS).map(|i| vec![format!("key:{i}").into_bytes(), b"v".to_vec()]),
+    );
+    for i in 1..=ELEMENTS {
+        let id = format!("{i}-0");
+        rt.execute_frame(
+            command(&[b"XADD", b"stream", id.as_bytes(), b"f", b"v"]),
+            NOW,
+        );
+    }
+
+    assert_streams(&mut rt, &[b"HGETALL", b"hash"], ELEMENTS * 2, |rt, out| {
+        rt.execute_plain_hgetall_borrowed_into(b"hash", NOW, false, out)
+    });
+    assert_streams(&mut rt, &[b"SMEMBERS", b"set"], ELEMENTS, |rt, out| {
+        rt.execute_plain_smembers_borrowed_into(b"set", NOW, false, out)
+    });
+    assert_streams(
+        &mut rt,
+        &[b"ZRANGE", b"zset", b"0", b"-1"],
+        ELEMENTS,
+        |rt, out| rt.execute_plain_zrange_borrowed_into(b"zset", b"0", b"-1", NOW, out),
+    );
+    // The sorted key index is rebuilt lazily after writes; sync it outside the
+    // measurement so only the reply itself is on the books.
+    rt.execute_frame(command(&[b"KEYS", b"none:*"]), NOW);
+    assert_streams(&mut rt, &[b"KEYS", b"key:*"], ELEMENTS, |rt, out| {
+        rt.execute_plain_keys_borrowed_into(b"key:*", NOW, out)
+    });
+    assert_streams(
+        &mut rt,
+        &[b"XRANGE", b"stream", b"-", b"+"],
+        ELEMENTS,
+        |rt, out| {
+            rt.execute_plain_xrange_borrowed_into(b"stream", b"-", b"+", None, NOW, false, out)
+        },
+    );
+}
+
+#[test]
+fn hash_and_scored_replies_stream_without_frames() {
+    let mut rt = runtime();
+    load(
+        &mut rt,
+        &[b"HSET", b"hash"],
+        (0..HASH_FIELDS).map(|i| {
+            vec![
+                format!("field:{i}").into_bytes(),
+                format!("value:{i}").into_bytes(),
+            ]
+        }),
+    );
+    load(
+        &mut rt,
+        &[b"ZADD", b"zset"],
+        (0..HASH_FIELDS).map(|i| {
+            vec![
+                format!("{i}.5").into_bytes(),
+                format!("member:{i}").into_bytes(),
+            ]
+        }),
+    );
+
+    assert_streams(
+        &mut rt,
+        &[b"HGETALL", b"hash"],
+        HASH_FIELDS * 2,
+        |rt, out| rt.execute_plain_hgetall_borrowed_into(b"hash", NOW, false, out),
+    );
+    assert_streams(&mut rt, &[b"HKEYS", b"hash"], HASH_FIELDS, |rt, out| {
+        rt.execute_plain_hcoll_borrowed_into(b"hash", NOW, false, out)
+    });
+    assert_streams(&mut rt, &[b"HVALS", b"hash"], HASH_FIELDS, |rt, out| {
+        rt.execute_plain_hcoll_borrowed_into(b"hash", NOW, true, out)
+    });
+
+    // Every field plus one that is missing, so nil elements are on the wire.
+    let names: Vec<Vec<u8>> = (0..=HASH_FIELDS)
+        .map(|i| format!("field:{i}").into_bytes())
+        .collect();
+    let fields: Vec<&[u8]> = names.iter().map(Vec::as_slice).collect();
+    let mut argv: Vec<&[u8]> = vec![b"HMGET", b"hash"];
+    argv.extend(&fields);
+    assert_streams(&mut rt, &argv, HASH_FIELDS, |rt, out| {
+        rt.execute_plain_hmget_borrowed_into(b"hash", &fields, NOW, false, out)
+    });
+
+    assert_streams(
+        &mut rt,
+        &[b"ZRANGE", b"zset", b"0", b"-1", b"WITHSCORES"],
+        HASH_FIELDS * 2,
+        |rt, out| {
+            rt.execute_plain_zrange_withscores_borrowed_into(b"zset", b"0", b"-1", NOW, false, out)
+        },
+    );
+}
@@ -0,0 +1,368 @@
+//! Writes that touch more than one key do the per-key bookkeeping for each.
+//!
+//! SMOVE, LMOVE/RPOPLPUSH, RENAME, COPY and the *STORE family are built from
+//! several store operations, so nothing forces them through the single-key
+//! write path. For every key one of them modifies, upstream bumps the WATCH
+//! version (`signalModifiedKey`), sends CLIENT TRACKING invalidations and fires
+//! the command's keyspace event, in order. A key it only reads (a COPY or
+//! *STORE source) gets none of that. Moves into an existing key keep that key's
+//! TTL; overwrites (RENAME, COPY REPLACE, *STORE) replace it.
+
+use fr_protocol::RespFrame;
+use fr_runtime::{ClientSession, Runtime};
+use fr_store::PubSubMessage;
+
+fn command(args: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        args.iter()
+            .map(|a| RespFrame::BulkString(Some(a.to_vec())))
+            .collect(),
+    ))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+/// The destination's TTL once the command has run.
+#[derive(Clone, Copy, Debug)]
+enum DestTtl {
+    Kept,
+    Cleared,
+}
+
+struct Case {
+    /// Commands that build the keyspace; `dst` then gets a TTL.
+    seed: &'static [&'static [&'static [u8]]],
+    command: &'static [&'static [u8]],
+    dst: &'static str,
+    dst_ttl: DestTtl,
+    /// `(event, key)` in publish order; the keys are exactly the ones the
+    /// command modifies.
+    events: &'static [(&'static str, &'static str)],
+    /// Keys the command reads without modifying.
+    untouched: &'static [&'static str],
+}
+
+const CASES: &[Case] = &[
+    Case {
+        seed: &[&[b"SADD", b"s1", b"m", b"x"], &[b"SADD", b"s2", b"y"]],
+        command: &[b"SMOVE", b"s1", b"s2", b"m"],
+        dst: "s2",
+        dst_ttl: DestTtl::Kept,
+        events: &[("srem", "s1"), ("sadd", "s2")],
+        untouched: &[],
+    },
+    Case {
+        seed: &[&[b"SADD", b"s1", b"m"], &[b"SADD", b"s2", b"y"]],
+        command: &[b"SMOVE", b"s1", b"s2", b"m"],
+        dst: "s2",
+        dst_ttl: DestTtl::Kept,
+        events: &[("srem", "s1"), ("del", "s1"), ("sadd", "s2")],
+        untouched: &[],
+    },
+    Case {
+        seed: &[&[b"SADD", b"s", b"a", b"b"], &[b"SADD", b"other", b"y"]],
+        command: &[b"SPOP", b"s"],
+        dst: "other",
+        dst_ttl: DestTtl::Kept,
+        events: &[("spop", "s")],
+        untouched: &["other"],
+    },
+    Case {
+        seed: &[&[b"RPUSH", b"l1", b"a", b"b"], &[b"RPUSH", b"l2", b"c"]],
+        command: &[b"LMOVE", b"l1", b"l2", b"LEFT", b"RIGHT"],
+        dst: "l2",
+        dst_ttl: DestTtl::Kept,
+        events: &[("rpush", "l2"), ("lpop", "l1")],
+        untouched: &[],
+    },
+    Case {
+        seed: &[&[b"RPUSH", b"l1", b"a"], &[b"RPUSH", b"l2", b"c"]],
+        command: &[b"RPOPLPUSH", b"l1", b"l2"],
+        dst: "l2",
+        dst_ttl: DestTtl::Kept,
+        events: &[("lpush", "l2"), ("rpop", "l1"), ("del", "l1")],
+        untouched: &[],
+    },
+    Case {
+        seed: &[&[b"SET", b"a", b"1"], &[b"SET", b"b", b"2"]],
+        command: &[b"RENAME", b"a", b"b"],
+        dst: "b",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("rename_from", "a"), ("rename_to", "b")],
+        untouched: &[],
+    },
+    Case {
+        seed: &[&[b"SET", b"a", b"1"], &[b"SET", b"b", b"2"]],
+        command: &[b"COPY", b"a", b"b", b"REPLACE"],
+        dst: "b",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("copy_to", "b")],
+        untouched: &["a"],
+    },
+    Case {
+        seed: &[
+            &[b"ZADD", b"z", b"1", b"a", b"2", b"b"],
+            &[b"SET", b"d", b"x"],
+        ],
+        command: &[b"ZRANGESTORE", b"d", b"z", b"0", b"-1"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("zrangestore", "d")],
+        untouched: &["z"],
+    },
+    Case {
+        seed: &[
+            &[
+                b"GEOADD", b"g", b"13.36", b"38.11", b"a", b"15.08", b"37.50", b"b",
+            ],
+            &[b"SET", b"d", b"x"],
+        ],
+        command: &[
+            b"GEOSEARCHSTORE",
+            b"d",
+            b"g",
+            b"FROMLONLAT",
+            b"15",
+            b"37",
+            b"BYRADIUS",
+            b"200",
+            b"km",
+        ],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("geosearchstore", "d")],
+        untouched: &["g"],
+    },
+    Case {
+        seed: &[
+            &[b"SADD", b"s1", b"a", b"b"],
+            &[b"SADD", b"s2", b"b", b"c"],
+            &[b"SADD", b"d", b"x"],
+        ],
+        command: &[b"SINTERSTORE", b"d", b"s1", b"s2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("sinterstore", "d")],
+        untouched: &["s1", "s2"],
+    },
+    Case {
+        seed: &[
+            &[b"SADD", b"s1", b"a", b"b"],
+            &[b"SADD", b"s2", b"b", b"c"],
+            &[b"SADD", b"d", b"x"],
+        ],
+        command: &[b"SUNIONSTORE", b"d", b"s1", b"s2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("sunionstore", "d")],
+        untouched: &["s1", "s2"],
+    },
+    Case {
+        seed: &[
+            &[b"SADD", b"s1", b"a", b"b"],
+            &[b"SADD", b"s2", b"b", b"c"],
+            &[b"SADD", b"d", b"x"],
+        ],
+        command: &[b"SDIFFSTORE", b"d", b"s1", b"s2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("sdiffstore", "d")],
+        untouched: &["s1", "s2"],
+    },
+    Case {
+        seed: &[
+            &[b"ZADD", b"z1", b"1", b"a", b"2", b"b"],
+            &[b"ZADD", b"z2", b"3", b"b", b"4", b"c"],
+            &[b"ZADD", b"d", b"9", b"x"],
+        ],
+        command: &[b"ZUNIONSTORE", b"d", b"2", b"z1", b"z2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("zunionstore", "d")],
+        untouched: &["z1", "z2"],
+    },
+    Case {
+        seed: &[
+            &[b"ZADD", b"z1", b"1", b"a", b"2", b"b"],
+            &[b"ZADD", b"z2", b"3", b"b", b"4", b"c"],
+            &[b"ZADD", b"d", b"9", b"x"],
+        ],
+        command: &[b"ZINTERSTORE", b"d", b"2", b"z1", b"z2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("zinterstore", "d")],
+        untouched: &["z1", "z2"],
+    },
+    Case {
+        seed: &[
+            &[b"ZADD", b"z1", b"1", b"a", b"2", b"b"],
+            &[b"ZADD", b"z2", b"3", b"b", b"4", b"c"],
+            &[b"ZADD", b"d", b"9", b"x"],
+        ],
+        command: &[b"ZDIFFSTORE", b"d", b"2", b"z1", b"z2"],
+        dst: "d",
+        dst_ttl: DestTtl::Cleared,
+        events: &[("zdiffstore", "d")],
+        untouched: &["z1", "z2"],
+    },
+];
+
+/// Sessions observing one case: a watcher per key, a BCAST tracker and a
+/// keyevent subscriber. The runtime's current session is the writer.
+struct Observers {
+    watchers: Vec<(String, ClientSession)>,
+    tracker: ClientSession,
+    subscriber: u64,
+}
+
+fn in_session<T>(
+    rt: &mut Runtime,
+    session: ClientSession,
+    f: impl FnOnce(&mut Runtime) -> T,
+) -> (ClientSession, T) {
+    let writer = rt.swap_session(session);
+    let out = f(rt);
+    let session = rt.swap_session(writer);
+    rt.record_client_session(&session);
+    (session, out)
+}
+
+fn observe(rt: &mut Runtime, keys: &[&str]) -> Observers {
+    let mut watchers = Vec::new();
+    for key in keys {
+        let session = rt.new_session();
+        let (session, reply) = in_session(rt, session, |rt| {
+            rt.execute_frame(command(&[b"WATCH", key.as_bytes()]), 0)
+        });
+        assert_eq!(reply, ok());
+        watchers.push((key.to_string(), session));
+    }
+    let tracker = rt.new_session();
+    let (tracker, _) = in_session(rt, tracker, |rt| {
+        rt.execute_frame(command(&[b"HELLO", b"3"]), 0);
+        assert_eq!(
+            rt.execute_frame(command(&[b"CLIENT", b"TRACKING", b"ON", b"BCAST"]), 0),
+            ok()
+        );
+    });
+    let subscriber = rt.new_session();
+    let (subscriber, _) = in_session(rt, subscriber, |rt| {
+        rt.execute_frame(command(&[b"PSUBSCRIBE", b"__keyevent@0__:*"]), 0)
+    });
+    Observers {
+        watchers,
+        tracker,
+        subscriber: subscriber.client_id,
+    }
+}
+
+fn keyevents(rt: &mut Runtime, subscriber: u64) -> Vec<(String, String)> {
+    rt.drain_pubsub_for_client(subscriber)
+        .into_iter()
+        .map(|message| match message {
+            PubSubMessage::PMessage { channel, data, .. } => {
+                let channel = String::from_utf8(channel).expect("utf8 channel");
+                let event = channel
+                    .strip_prefix("__keyevent@0__:")
+                    .expect("keyevent channel")
+                    .to_string();
+                (event, String::from_utf8(data).expect("utf8 key"))
+            }
+            other => panic!("unexpected pubsub message {other:?}"),
+        })
+        .collect()
+}
+
+fn invalidated_keys(rt: &mut Runtime, tracker: u64) -> Vec<String> {
+    let mut keys = Vec::new();
+    for message in rt.drain_pubsub_for_client(tracker) {
+        match message {
+            PubSubMessage::Invalidate { keys: batch } => keys.extend(
+                batch
+                    .into_iter()
+                    .map(|key| String::from_utf8(key).expect("utf8 key")),
+            ),
+            other => panic!("unexpected tracking message {other:?}"),
+        }
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn pttl(rt: &mut Runtime, key: &str) -> i64 {
+    match rt.execute_frame(command(&[b"PTTL", key.as_bytes()]), 0) {
+        RespFrame::Integer(ttl) => ttl,
+        other => panic!("PTTL {key}: {other:?}"),
+    }
+}
+
+#[test]
+fn multi_key_writes_bump_notify_and_invalidate_every_modified_key() {
+    for case in CASES {
+        let name = String::from_utf8_lossy(&case.command.join(&b' ')).into_owned();
+        let mut rt = Runtime::default_strict();
+        assert_eq!(
+            rt.execute_frame(
+                command(&[b"CONFIG", b"SET", b"notify-keyspace-events", b"EA"]),
+                0,
+            ),
+            ok()
+        );
+        for seed in case.seed {
+            let reply = rt.execute_frame(command(seed), 0);
+            assert!(!matches!(reply, RespFrame::Error(_)), "{name}: {reply:?}");
+        }
+        assert_eq!(
+            rt.execute_frame(command(&[b"PEXPIRE", case.dst.as_bytes(), b"100000"]), 0),
+            RespFrame::Integer(1),
+            "{name}"
+        );
+
+        let mut modified: Vec<&str> = case.events.iter().map(|&(_, key)| key).collect();
+        modified.sort_unstable();
+        modified.dedup();
+        let watched: Vec<&str> = modified.iter().chain(case.untouched).copied().collect();
+        let observers = observe(&mut rt, &watched);
+
+        let reply = rt.execute_frame(command(case.command), 0);
+        assert!(!matches!(reply, RespFrame::Error(_)), "{name}: {reply:?}");
+
+        let expected: Vec<(String, String)> = case
+            .events
+            .iter()
+            .map(|&(event, key)| (event.to_string(), key.to_string()))
+            .collect();
+        assert_eq!(
+            keyevents(&mut rt, observers.subscriber),
+            expected,
+            "{name}: keyspace events"
+        );
+        assert_eq!(
+            invalidated_keys(&mut rt, observers.tracker.client_id),
+            modified,
+            "{name}: tracking invalidations"
+        );
+        for (key, watcher) in observers.watchers {
+            let (_, exec) = in_session(&mut rt, watcher, |rt| {
+                assert_eq!(rt.execute_frame(command(&[b"MULTI"]), 0), ok());
+                rt.execute_frame(command(&[b"PING"]), 0);
+                rt.execute_frame(command(&[b"EXEC"]), 0)
+            });
+            let aborted = exec == RespFrame::Array(None);
+            assert_eq!(
+                aborted,
+                modified.contains(&key.as_str()),
+                "{name}: WATCH {key} saw {exec:?}"
+            );
+        }
+
+        let ttl = pttl(&mut rt, case.dst);
+        match case.dst_ttl {
+            DestTtl::Kept => assert!(ttl > 0, "{name}: {} lost its TTL", case.dst),
+            DestTtl::Cleared => assert_eq!(ttl, -1, "{name}: {} kept its TTL", case.dst),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Blocking commands never block inside MULTI/EXEC or a script.
+//!
+//! Upstream sets CLIENT_DENY_BLOCKING for transaction replay and for
+//! `redis.call`, and every blocking command then takes its immediate path:
+//! BLPOP behaves as LPOP and answers nil on an empty list, the timeout is
+//! validated but ignored, and WAIT reports the current ack count. XREAD and
+//! XREADGROUP with BLOCK are the exception inside scripts, where upstream
+//! rejects the option outright.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn bulk(value: &[u8]) -> RespFrame {
+    RespFrame::BulkString(Some(value.to_vec()))
+}
+
+fn array(items: Vec<RespFrame>) -> RespFrame {
+    RespFrame::Array(Some(items))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+struct Case {
+    /// Commands that give the blocking command something to serve.
+    seed: &'static [&'static [&'static [u8]]],
+    blocking: &'static [&'static [u8]],
+    /// Reply on the seeded keyspace.
+    served: fn() -> RespFrame,
+    /// Reply on an empty keyspace (the would-block case).
+    empty: RespFrame,
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case {
+            seed: &[&[b"RPUSH", b"l", b"a", b"b"]],
+            blocking: &[b"BLPOP", b"l", b"0"],
+            served: || array(vec![bulk(b"l"), bulk(b"a")]),
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[&[b"RPUSH", b"l", b"a", b"b"]],
+            blocking: &[b"BRPOP", b"missing", b"l", b"5"],
+            served: || array(vec![bulk(b"l"), bulk(b"b")]),
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[&[b"RPUSH", b"l", b"a", b"b"]],
+            blocking: &[b"BLMOVE", b"l", b"d", b"LEFT", b"RIGHT", b"0"],
+            served: || bulk(b"a"),
+            empty: RespFrame::BulkString(None),
+        },
+        Case {
+            seed: &[&[b"RPUSH", b"l", b"a", b"b"]],
+            blocking: &[b"BRPOPLPUSH", b"l", b"d", b"0"],
+            served: || bulk(b"b"),
+            empty: RespFrame::BulkString(None),
+        },
+        Case {
+            seed: &[&[b"RPUSH", b"l", b"a", b"b"]],
+            blocking: &[b"BLMPOP", b"0", b"1", b"l", b"LEFT"],
+            served: || array(vec![bulk(b"l"), array(vec![bulk(b"a")])]),
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[&[b"ZADD", b"z", b"1", b"a", b"2", b"b"]],
+            blocking: &[b"BZPOPMIN", b"z", b"0"],
+            served: || array(vec![bulk(b"z"), bulk(b"a"), bulk(b"1")]),
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[&[b"ZADD", b"z", b"1", b"a", b"2", b"b"]],
+            blocking: &[b"BZPOPMAX", b"z", b"0"],
+            served: || array(vec![bulk(b"z"), bulk(b"b"), bulk(b"2")]),
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[&[b"ZADD", b"z", b"1", b"a", b"2", b"b"]],
+            blocking: &[b"BZMPOP", b"0", b"1", b"z", b"MIN"],
+            served: || {
+                array(vec![
+                    bulk(b"z"),
+                    array(vec![array(vec![bulk(b"a"), bulk(b"1")])]),
+                ])
+            },
+            empty: RespFrame::Array(None),
+        },
+        Case {
+            seed: &[],
+            blocking: &[b"WAIT", b"1", b"0"],
+            served: || RespFrame::Integer(0),
+            empty: RespFrame::Integer(0),
+        },
+    ]
+}
+
+fn seed(rt: &mut Runtime, case: &Case) {
+    for seed in case.seed {
+        let reply = rt.execute_frame(command(seed), 0);
+        assert!(!matches!(reply, RespFrame::Error(_)), "{reply:?}");
+    }
+}
+
+fn exec_one(rt: &mut Runtime, parts: &[&[u8]]) -> RespFrame {
+    assert_eq!(rt.execute_frame(command(&[b"MULTI"]), 0), ok());
+    assert_eq!(
+        rt.execute_frame(command(parts), 0),
+        RespFrame::SimpleString("QUEUED".to_string())
+    );
+    match rt.execute_frame(command(&[b"EXEC"]), 0) {
+        RespFrame::Array(Some(mut replies)) if replies.len() == 1 => replies.remove(0),
+        other => panic!("EXEC: {other:?}"),
+    }
+}
+
+fn eval_one(rt: &mut Runtime, parts: &[&[u8]]) -> RespFrame {
+    let mut eval: Vec<&[u8]> = vec![b"EVAL", b"return redis.call(unpack(ARGV))", b"0"];
+    eval.extend(parts);
+    rt.execute_frame(command(&eval), 0)
+}
+
+fn name(parts: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&parts.join(&b' ')).into_owned()
+}
+
+#[test]
+fn blocking_commands_inside_exec_take_the_immediate_path() {
+    for case in cases() {
+        let name = name(case.blocking);
+        let mut rt = Runtime::default_strict();
+        assert_eq!(
+            exec_one(&mut rt, case.blocking),
+            case.empty,
+            "{name}: empty"
+        );
+        seed(&mut rt, &case);
+        assert_eq!(
+            exec_one(&mut rt, case.blocking),
+            (case.served)(),
+            "{name}: served"
+        );
+    }
+}
+
+#[test]
+fn blocking_commands_inside_eval_take_the_immediate_path() {
+    for case in cases() {
+        let name = name(case.blocking);
+        let mut rt = Runtime::default_strict();
+        // A nil array from redis.call reaches Lua as false, which EVAL
+        // returns as a nil bulk.
+        let empty = match case.empty.clone() {
+            RespFrame::Array(None) => RespFrame::BulkString(None),
+            other => other,
+        };
+        assert_eq!(eval_one(&mut rt, case.blocking), empty, "{name}: empty");
+        seed(&mut rt, &case);
+        assert_eq!(
+            eval_one(&mut rt, case.blocking),
+            (case.served)(),
+            "{name}: served"
+        );
+    }
+}
+
+#[test]
+fn blocking_timeouts_are_still_validated_when_nested() {
+    let mut rt = Runtime::default_strict();
+    assert_eq!(
+        exec_one(&mut rt, &[b"BLPOP", b"l", b"-1"]),
+        RespFrame::Error("ERR timeout is negative".to_string())
+    );
+    match eval_one(&mut rt, &[b"BLPOP", b"l", b"-1"]) {
+        RespFrame::Error(message) => {
+            assert!(message.starts_with("ERR timeout is negative"), "{message}")
+        }
+        other => panic!("BLPOP -1 from a script: {other:?}"),
+    }
+}
+
+#[test]
+fn deny_blocking_is_scoped_to_the_nested_dispatch() {
+    let mut rt = Runtime::default_strict();
+    assert!(!rt.blocking_denied());
+    exec_one(&mut rt, &[b"BLPOP", b"l", b"0"]);
+    assert!(!rt.blocking_denied(), "EXEC leaves the flag set");
+    eval_one(&mut rt, &[b"BLPOP", b"l", b"0"]);
+    assert!(!rt.blocking_denied(), "EVAL leaves the flag set");
+}
+
+#[test]
+fn xread_block_inside_exec_reads_once_and_is_rejected_from_scripts() {
+    let mut rt = Runtime::default_strict();
+    let xread: &[&[u8]] = &[b"XREAD", b"BLOCK", b"0", b"STREAMS", b"s", b"0-0"];
+    let xreadgroup: &[&[u8]] = &[
+        b"XREADGROUP",
+        b"GROUP",
+        b"g",
+        b"c",
+        b"BLOCK",
+        b"0",
+        b"STREAMS",
+        b"s",
+        b">",
+    ];
+    assert_eq!(exec_one(&mut rt, xread), RespFrame::Array(None));
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"XGROUP", b"CREATE", b"s", b"g", b"$", b"MKSTREAM"]),
+            0
+        ),
+        ok()
+    );
+    assert_eq!(exec_one(&mut rt, xreadgroup), RespFrame::Array(None));
+
+    rt.execute_frame(command(&[b"XADD", b"s", b"1-1", b"f", b"v"]), 0);
+    let entries = array(vec![array(vec![
+        bulk(b"s"),
+        array(vec![array(vec![
+            bulk(b"1-1"),
+            array(vec![bulk(b"f"), bulk(b"v")]),
+        ])]),
+    ])]);
+    assert_eq!(exec_one(&mut rt, xread), entries);
+    assert_eq!(exec_one(&mut rt, xreadgroup), entries);
+
+    for (parts, command) in [(xread, "XREAD"), (xreadgroup, "XREADGROUP")] {
+        match eval_one(&mut rt, parts) {
+            RespFrame::Error(message) => assert!(
+                message.contains(&format!(
+                    "{command} command is not allowed with BLOCK option from scripts"
+                )),
+                "{message}"
+            ),
+            other => panic!("{command} BLOCK from a script: {other:?}"),
+        }
+    }
+}
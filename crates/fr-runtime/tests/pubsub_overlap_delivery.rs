@@ -0,0 +1,113 @@
+//! PUBLISH fans out per subscription, not per client.
+//!
+//! Upstream `pubsubPublishMessage` walks the exact-channel subscribers and then
+//! every matching pattern, queueing one frame per hit. A client holding both
+//! `news` and `ne*` therefore receives a `message` and a `pmessage` and is
+//! counted twice in the PUBLISH reply, and overlapping patterns each deliver
+//! their own `pmessage`.
+
+use fr_command::pubsub_message_to_frame;
+use fr_protocol::RespFrame;
+use fr_runtime::{ClientSession, Runtime};
+
+fn command(args: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        args.iter()
+            .map(|a| RespFrame::BulkString(Some(a.to_vec())))
+            .collect(),
+    ))
+}
+
+fn bulk_array(items: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        items
+            .iter()
+            .map(|item| RespFrame::BulkString(Some(item.to_vec())))
+            .collect(),
+    ))
+}
+
+/// Run `argv` as the client owning `session`, then hand the session back.
+fn run_as(rt: &mut Runtime, session: ClientSession, argv: &[&[u8]]) -> ClientSession {
+    let previous = rt.swap_session(session);
+    rt.execute_frame(command(argv), 0);
+    rt.swap_session(previous)
+}
+
+fn delivered(rt: &mut Runtime, client_id: u64) -> Vec<RespFrame> {
+    rt.drain_pubsub_for_client(client_id)
+        .into_iter()
+        .map(pubsub_message_to_frame)
+        .collect()
+}
+
+#[test]
+fn overlapping_subscriptions_each_deliver_and_count() {
+    let mut rt = Runtime::default_strict();
+
+    let mut overlap = rt.new_session();
+    // Subscribe to the longer pattern first: delivery order follows the pattern
+    // bytes, not the order the subscriptions were made in.
+    for argv in [
+        &[b"SUBSCRIBE".as_slice(), b"news"][..],
+        &[b"PSUBSCRIBE", b"ne*"],
+        &[b"PSUBSCRIBE", b"n*"],
+        // Re-subscribing is idempotent and must not double the fan-out.
+        &[b"PSUBSCRIBE", b"n*"],
+    ] {
+        overlap = run_as(&mut rt, overlap, argv);
+    }
+    let mut plain = rt.new_session();
+    plain = run_as(&mut rt, plain, &[b"SUBSCRIBE", b"news"]);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"PUBLISH", b"news", b"x"]), 1),
+        RespFrame::Integer(4)
+    );
+    assert_eq!(
+        delivered(&mut rt, overlap.client_id),
+        vec![
+            bulk_array(&[b"message", b"news", b"x"]),
+            bulk_array(&[b"pmessage", b"n*", b"news", b"x"]),
+            bulk_array(&[b"pmessage", b"ne*", b"news", b"x"]),
+        ]
+    );
+    assert_eq!(
+        delivered(&mut rt, plain.client_id),
+        vec![bulk_array(&[b"message", b"news", b"x"])]
+    );
+
+    // Only `n*` matches: one delivery, to the overlapping client.
+    assert_eq!(
+        rt.execute_frame(command(&[b"PUBLISH", b"nope", b"y"]), 2),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        delivered(&mut rt, overlap.client_id),
+        vec![bulk_array(&[b"pmessage", b"n*", b"nope", b"y"])]
+    );
+    assert!(delivered(&mut rt, plain.client_id).is_empty());
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"PUBLISH", b"other", b"z"]), 3),
+        RespFrame::Integer(0)
+    );
+
+    // Dropping one overlapping pattern removes exactly its delivery.
+    let overlap = run_as(&mut rt, overlap, &[b"PUNSUBSCRIBE", b"ne*"]);
+    assert_eq!(
+        rt.execute_frame(command(&[b"PUBLISH", b"news", b"w"]), 4),
+        RespFrame::Integer(3)
+    );
+    assert_eq!(
+        delivered(&mut rt, overlap.client_id),
+        vec![
+            bulk_array(&[b"message", b"news", b"w"]),
+            bulk_array(&[b"pmessage", b"n*", b"news", b"w"]),
+        ]
+    );
+    assert_eq!(
+        delivered(&mut rt, plain.client_id),
+        vec![bulk_array(&[b"message", b"news", b"w"])]
+    );
+}
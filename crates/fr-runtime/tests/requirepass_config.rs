@@ -0,0 +1,344 @@
+//! `requirepass` and `masterauth` set through CONFIG SET take effect at once.
+//!
+//! CONFIG SET requirepass replaces the default user's password list, so new
+//! connections must AUTH while connections that were already authenticated
+//! (including the one issuing CONFIG SET) keep their identity. Setting it to ""
+//! turns the default user back into nopass. CONFIG GET returns both values in
+//! clear to callers allowed to run CONFIG, and both are hidden from SLOWLOG
+//! like upstream's sensitive configs, as are AUTH and HELLO credentials in
+//! SLOWLOG and MONITOR. masterauth is what the replica handshake
+//! authenticates with.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn bulk(value: &[u8]) -> RespFrame {
+    RespFrame::BulkString(Some(value.to_vec()))
+}
+
+fn ok() -> RespFrame {
+    RespFrame::SimpleString("OK".to_string())
+}
+
+fn noauth() -> RespFrame {
+    RespFrame::Error("NOAUTH Authentication required.".to_string())
+}
+
+/// Runs `parts` as a brand-new connection and returns its reply.
+fn fresh_connection(rt: &mut Runtime, parts: &[&[u8]]) -> (RespFrame, fr_runtime::ClientSession) {
+    let mut fresh = rt.new_session();
+    fresh.client_id = 100;
+    let previous = rt.swap_session(fresh);
+    let reply = rt.execute_frame(command(parts), 0);
+    (reply, rt.swap_session(previous))
+}
+
+#[test]
+fn config_set_requirepass_gates_new_connections_only() {
+    let mut rt = Runtime::default_strict();
+    let mut other = rt.new_session();
+    other.client_id = 2;
+    let me = rt.swap_session(other);
+    assert_eq!(rt.execute_frame(command(&[b"PING"]), 0), bulk_pong());
+    let other = rt.swap_session(me);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"CONFIG", b"SET", b"requirepass", b"secret"]), 0),
+        ok()
+    );
+    assert!(
+        rt.is_authenticated(),
+        "the issuing connection stays authenticated"
+    );
+    assert_eq!(rt.execute_frame(command(&[b"SET", b"k", b"v"]), 0), ok());
+
+    // A connection that was already open keeps its identity.
+    let me = rt.swap_session(other);
+    assert!(rt.is_authenticated());
+    assert_eq!(rt.execute_frame(command(&[b"GET", b"k"]), 0), bulk(b"v"));
+    rt.swap_session(me);
+
+    // A new connection has to AUTH first.
+    let (reply, _) = fresh_connection(&mut rt, &[b"GET", b"k"]);
+    assert_eq!(reply, noauth());
+
+    let mut fresh = rt.new_session();
+    fresh.client_id = 101;
+    let me = rt.swap_session(fresh);
+    assert!(!rt.is_authenticated());
+    assert_eq!(
+        rt.execute_frame(command(&[b"AUTH", b"wrong"]), 0),
+        RespFrame::Error(
+            "WRONGPASS invalid username-password pair or user is disabled.".to_string()
+        )
+    );
+    assert_eq!(rt.execute_frame(command(&[b"AUTH", b"secret"]), 0), ok());
+    assert_eq!(rt.execute_frame(command(&[b"GET", b"k"]), 0), bulk(b"v"));
+    rt.swap_session(me);
+    assert_eq!(
+        rt.execute_frame(command(&[b"AUTH", b"default", b"secret"]), 0),
+        ok()
+    );
+}
+
+fn bulk_pong() -> RespFrame {
+    RespFrame::SimpleString("PONG".to_string())
+}
+
+#[test]
+fn config_set_requirepass_resets_the_default_user_password_list() {
+    let mut rt = Runtime::default_strict();
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"ACL", b"SETUSER", b"default", b">one", b">two"]),
+            0
+        ),
+        ok()
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"CONFIG", b"SET", b"requirepass", b"three"]), 0),
+        ok()
+    );
+    for (password, accepted) in [(&b"one"[..], false), (b"two", false), (b"three", true)] {
+        let (reply, _) = fresh_connection(&mut rt, &[b"AUTH", password]);
+        assert_eq!(reply == ok(), accepted, "AUTH {password:?}: {reply:?}");
+    }
+    match rt.execute_frame(command(&[b"ACL", b"GETUSER", b"default"]), 0) {
+        RespFrame::Array(Some(fields)) if fields_passwords(&fields) == 1 => {}
+        other => panic!("ACL GETUSER default: {other:?}"),
+    }
+}
+
+fn fields_passwords(fields: &[RespFrame]) -> usize {
+    fields
+        .chunks(2)
+        .find(|pair| pair[0] == bulk(b"passwords"))
+        .map_or(usize::MAX, |pair| match &pair[1] {
+            RespFrame::Array(Some(passwords)) => passwords.len(),
+            _ => usize::MAX,
+        })
+}
+
+#[test]
+fn empty_requirepass_reopens_unauthenticated_access() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"CONFIG", b"SET", b"requirepass", b"secret"]), 0);
+    let (reply, _) = fresh_connection(&mut rt, &[b"PING"]);
+    assert_eq!(reply, noauth());
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"CONFIG", b"SET", b"requirepass", b""]), 0),
+        ok()
+    );
+    let (reply, _) = fresh_connection(&mut rt, &[b"PING"]);
+    assert_eq!(reply, bulk_pong());
+    assert_eq!(
+        rt.execute_frame(command(&[b"CONFIG", b"GET", b"requirepass"]), 0),
+        RespFrame::Array(Some(vec![bulk(b"requirepass"), bulk(b"")]))
+    );
+    let (reply, _) = fresh_connection(&mut rt, &[b"AUTH", b"secret"]);
+    assert_eq!(
+        reply,
+        RespFrame::Error(
+            "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn config_get_returns_sensitive_values_only_to_config_users() {
+    let mut rt = Runtime::default_strict();
+    for parts in [
+        &[&b"CONFIG"[..], b"SET", b"requirepass", b"secret"][..],
+        &[b"CONFIG", b"SET", b"masterauth", b"upstream-secret"],
+        &[
+            b"ACL",
+            b"SETUSER",
+            b"app",
+            b"on",
+            b">app",
+            b"+@all",
+            b"-@dangerous",
+            b"~*",
+        ],
+        &[b"ACL", b"SETUSER", b"ops", b"on", b">ops", b"+@all", b"~*"],
+    ] {
+        assert_eq!(rt.execute_frame(command(parts), 0), ok(), "{parts:?}");
+    }
+
+    let expected = RespFrame::Array(Some(vec![
+        bulk(b"masterauth"),
+        bulk(b"upstream-secret"),
+        bulk(b"requirepass"),
+        bulk(b"secret"),
+    ]));
+    let sorted = |reply: RespFrame| match reply {
+        RespFrame::Array(Some(items)) => {
+            let mut pairs: Vec<Vec<RespFrame>> = items.chunks(2).map(<[_]>::to_vec).collect();
+            pairs.sort_by_key(|pair| format!("{:?}", pair[0]));
+            RespFrame::Array(Some(pairs.concat()))
+        }
+        other => other,
+    };
+    let glob = command(&[b"CONFIG", b"GET", b"*auth", b"requirepass"]);
+    assert_eq!(sorted(rt.execute_frame(glob.clone(), 0)), expected);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"AUTH", b"ops", b"ops"]), 0),
+        ok()
+    );
+    assert_eq!(sorted(rt.execute_frame(glob.clone(), 0)), expected);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"AUTH", b"app", b"app"]), 0),
+        ok()
+    );
+    match rt.execute_frame(glob, 0) {
+        RespFrame::Error(message) => assert!(message.starts_with("NOPERM"), "{message}"),
+        other => panic!("CONFIG GET as a non-admin user: {other:?}"),
+    }
+}
+
+#[test]
+fn sensitive_config_set_arguments_are_redacted_from_slowlog() {
+    let mut rt = Runtime::default_strict();
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"CONFIG", b"SET", b"slowlog-log-slower-than", b"0"]),
+            0
+        ),
+        ok()
+    );
+    rt.execute_frame(command(&[b"SLOWLOG", b"RESET"]), 0);
+    rt.execute_frame(
+        command(&[
+            b"CONFIG",
+            b"SET",
+            b"requirepass",
+            b"secret",
+            b"masterauth",
+            b"upstream-secret",
+            b"maxmemory-clients",
+            b"0",
+        ]),
+        0,
+    );
+    rt.execute_frame(command(&[b"AUTH", b"secret"]), 0);
+    rt.execute_frame(command(&[b"AUTH", b"default", b"secret"]), 0);
+    rt.execute_frame(
+        command(&[b"HELLO", b"2", b"AUTH", b"default", b"secret"]),
+        0,
+    );
+
+    let RespFrame::Array(Some(entries)) =
+        rt.execute_frame(command(&[b"SLOWLOG", b"GET", b"-1"]), 0)
+    else {
+        panic!("SLOWLOG GET");
+    };
+    let logged: Vec<Vec<RespFrame>> = entries
+        .into_iter()
+        .map(|entry| match entry {
+            RespFrame::Array(Some(fields)) => match &fields[3] {
+                RespFrame::Array(Some(argv)) => argv.clone(),
+                other => panic!("slowlog argv: {other:?}"),
+            },
+            other => panic!("slowlog entry: {other:?}"),
+        })
+        .collect();
+    let redacted = bulk(b"(redacted)");
+    assert!(
+        logged.contains(&vec![
+            bulk(b"CONFIG"),
+            bulk(b"SET"),
+            bulk(b"requirepass"),
+            redacted.clone(),
+            bulk(b"masterauth"),
+            redacted.clone(),
+            bulk(b"maxmemory-clients"),
+            bulk(b"0"),
+        ]),
+        "{logged:?}"
+    );
+    for argv in &logged {
+        assert!(
+            !argv.contains(&bulk(b"secret")) && !argv.contains(&bulk(b"upstream-secret")),
+            "a password reached the slowlog: {argv:?}"
+        );
+    }
+}
+
+#[test]
+fn config_set_masterauth_feeds_the_replica_handshake() {
+    let mut rt = Runtime::default_strict();
+    assert_eq!(rt.replica_primary_auth(), None);
+    for parts in [
+        &[&b"CONFIG"[..], b"SET", b"masterauth", b"upstream-secret"][..],
+        &[b"CONFIG", b"SET", b"masteruser", b"replicator"],
+    ] {
+        assert_eq!(rt.execute_frame(command(parts), 0), ok());
+    }
+    assert_eq!(
+        rt.replica_primary_auth(),
+        Some((Some(b"replicator".to_vec()), b"upstream-secret".to_vec()))
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"CONFIG", b"SET", b"masterauth", b""]), 0),
+        ok()
+    );
+    assert_eq!(rt.replica_primary_auth(), None);
+}
+
+#[test]
+fn monitor_shows_credentials_redacted() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"CONFIG", b"SET", b"requirepass", b"secret"]), 0);
+    assert_eq!(rt.execute_frame(command(&[b"MONITOR"]), 0), ok());
+    assert!(rt.drain_monitor_output().is_empty());
+
+    let mut client = rt.new_session();
+    client.client_id = 7;
+    let monitor = rt.swap_session(client);
+    assert_eq!(rt.execute_frame(command(&[b"AUTH", b"secret"]), 0), ok());
+    assert_eq!(
+        rt.execute_frame(command(&[b"AUTH", b"default", b"secret"]), 0),
+        ok()
+    );
+    match rt.execute_frame(
+        command(&[
+            b"HELLO", b"2", b"AUTH", b"default", b"secret", b"SETNAME", b"c",
+        ]),
+        0,
+    ) {
+        RespFrame::Array(Some(_)) => {}
+        other => panic!("HELLO AUTH: {other:?}"),
+    }
+    rt.swap_session(monitor);
+
+    let lines: Vec<String> = rt
+        .drain_monitor_output()
+        .into_iter()
+        .map(|(_, line)| String::from_utf8(line).unwrap())
+        .collect();
+    let commands: Vec<&str> = lines
+        .iter()
+        .map(|line| line.split_once("] ").unwrap().1.trim_end())
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            r#""AUTH" "(redacted)""#,
+            r#""AUTH" "(redacted)" "(redacted)""#,
+            r#""HELLO" "2" "AUTH" "(redacted)" "(redacted)" "SETNAME" "c""#,
+        ]
+    );
+}
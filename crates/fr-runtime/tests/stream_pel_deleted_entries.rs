@@ -0,0 +1,257 @@
+//! Pending entries outlive the stream messages they point at.
+//!
+//! A group's PEL holds only IDs and delivery metadata, so XDEL and XTRIM leave
+//! it alone: XPENDING keeps counting a deleted entry, XACK still removes it,
+//! and XREADGROUP replaying history returns the ID with a nil field list.
+//! XAUTOCLAIM and XCLAIM follow Redis 7: a deleted entry is dropped from the
+//! PEL instead of being claimed, and XAUTOCLAIM reports it in its third reply.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+fn bulk(value: &[u8]) -> RespFrame {
+    RespFrame::BulkString(Some(value.to_vec()))
+}
+
+fn array(items: Vec<RespFrame>) -> RespFrame {
+    RespFrame::Array(Some(items))
+}
+
+fn entry(id: &[u8], value: &[u8]) -> RespFrame {
+    array(vec![bulk(id), array(vec![bulk(b"f"), bulk(value)])])
+}
+
+fn tombstone(id: &[u8]) -> RespFrame {
+    array(vec![bulk(id), RespFrame::Array(None)])
+}
+
+/// Stream `s` with 1-1, 2-1 and 3-1 all delivered to alice in group `g`, then
+/// 1-1 removed by XDEL and 2-1 by XTRIM.
+fn pending_with_deleted_entries() -> Runtime {
+    let mut rt = Runtime::default_strict();
+    for parts in [
+        &[&b"XADD"[..], b"s", b"1-1", b"f", b"v1"][..],
+        &[b"XADD", b"s", b"2-1", b"f", b"v2"],
+        &[b"XADD", b"s", b"3-1", b"f", b"v3"],
+        &[b"XGROUP", b"CREATE", b"s", b"g", b"0"],
+        &[
+            b"XREADGROUP",
+            b"GROUP",
+            b"g",
+            b"alice",
+            b"STREAMS",
+            b"s",
+            b">",
+        ],
+    ] {
+        let reply = rt.execute_frame(command(parts), 0);
+        assert!(
+            !matches!(reply, RespFrame::Error(_)),
+            "{parts:?}: {reply:?}"
+        );
+    }
+    assert_eq!(
+        rt.execute_frame(command(&[b"XDEL", b"s", b"1-1"]), 0),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"XTRIM", b"s", b"MINID", b"3-0"]), 0),
+        RespFrame::Integer(1)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"XLEN", b"s"]), 0),
+        RespFrame::Integer(1)
+    );
+    rt
+}
+
+fn pending_summary(rt: &mut Runtime) -> RespFrame {
+    rt.execute_frame(command(&[b"XPENDING", b"s", b"g"]), 0)
+}
+
+#[test]
+fn xpending_keeps_listing_deleted_entries() {
+    let mut rt = pending_with_deleted_entries();
+    assert_eq!(
+        pending_summary(&mut rt),
+        array(vec![
+            RespFrame::Integer(3),
+            bulk(b"1-1"),
+            bulk(b"3-1"),
+            array(vec![array(vec![bulk(b"alice"), bulk(b"3")])]),
+        ])
+    );
+    match rt.execute_frame(command(&[b"XPENDING", b"s", b"g", b"-", b"+", b"10"]), 0) {
+        RespFrame::Array(Some(rows)) => {
+            let ids: Vec<&RespFrame> = rows
+                .iter()
+                .map(|row| match row {
+                    RespFrame::Array(Some(fields)) => &fields[0],
+                    other => panic!("XPENDING row: {other:?}"),
+                })
+                .collect();
+            assert_eq!(ids, [&bulk(b"1-1"), &bulk(b"2-1"), &bulk(b"3-1")]);
+        }
+        other => panic!("XPENDING extended: {other:?}"),
+    }
+    match rt.execute_frame(command(&[b"XINFO", b"GROUPS", b"s"]), 0) {
+        RespFrame::Array(Some(groups)) => match &groups[0] {
+            RespFrame::Array(Some(fields)) => {
+                let pending = fields
+                    .chunks(2)
+                    .find(|pair| pair[0] == bulk(b"pending"))
+                    .map(|pair| pair[1].clone());
+                assert_eq!(pending, Some(RespFrame::Integer(3)));
+            }
+            other => panic!("XINFO GROUPS entry: {other:?}"),
+        },
+        other => panic!("XINFO GROUPS: {other:?}"),
+    }
+}
+
+#[test]
+fn xack_removes_deleted_entries() {
+    let mut rt = pending_with_deleted_entries();
+    assert_eq!(
+        rt.execute_frame(command(&[b"XACK", b"s", b"g", b"1-1", b"2-1"]), 0),
+        RespFrame::Integer(2)
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"XACK", b"s", b"g", b"1-1"]), 0),
+        RespFrame::Integer(0)
+    );
+    assert_eq!(
+        pending_summary(&mut rt),
+        array(vec![
+            RespFrame::Integer(1),
+            bulk(b"3-1"),
+            bulk(b"3-1"),
+            array(vec![array(vec![bulk(b"alice"), bulk(b"1")])]),
+        ])
+    );
+}
+
+#[test]
+fn xreadgroup_history_returns_tombstones() {
+    let mut rt = pending_with_deleted_entries();
+    let history = |rt: &mut Runtime, start: &[u8]| {
+        rt.execute_frame(
+            command(&[
+                b"XREADGROUP",
+                b"GROUP",
+                b"g",
+                b"alice",
+                b"STREAMS",
+                b"s",
+                start,
+            ]),
+            0,
+        )
+    };
+    assert_eq!(
+        history(&mut rt, b"0"),
+        array(vec![array(vec![
+            bulk(b"s"),
+            array(vec![
+                tombstone(b"1-1"),
+                tombstone(b"2-1"),
+                entry(b"3-1", b"v3"),
+            ]),
+        ])])
+    );
+    assert_eq!(
+        history(&mut rt, b"1-1"),
+        array(vec![array(vec![
+            bulk(b"s"),
+            array(vec![tombstone(b"2-1"), entry(b"3-1", b"v3")]),
+        ])])
+    );
+    // Replaying history delivers again without dropping the tombstones.
+    assert_eq!(
+        pending_summary(&mut rt),
+        array(vec![
+            RespFrame::Integer(3),
+            bulk(b"1-1"),
+            bulk(b"3-1"),
+            array(vec![array(vec![bulk(b"alice"), bulk(b"3")])]),
+        ])
+    );
+}
+
+#[test]
+fn xautoclaim_reports_deleted_entries_and_drops_them() {
+    let mut rt = pending_with_deleted_entries();
+    assert_eq!(
+        rt.execute_frame(command(&[b"XAUTOCLAIM", b"s", b"g", b"bob", b"0", b"0"]), 0),
+        array(vec![
+            bulk(b"0-0"),
+            array(vec![entry(b"3-1", b"v3")]),
+            array(vec![bulk(b"1-1"), bulk(b"2-1")]),
+        ])
+    );
+    assert_eq!(
+        pending_summary(&mut rt),
+        array(vec![
+            RespFrame::Integer(1),
+            bulk(b"3-1"),
+            bulk(b"3-1"),
+            array(vec![array(vec![bulk(b"bob"), bulk(b"1")])]),
+        ])
+    );
+
+    let mut rt = pending_with_deleted_entries();
+    assert_eq!(
+        rt.execute_frame(
+            command(&[
+                b"XAUTOCLAIM",
+                b"s",
+                b"g",
+                b"bob",
+                b"0",
+                b"0",
+                b"COUNT",
+                b"1",
+                b"JUSTID",
+            ]),
+            0
+        ),
+        array(vec![bulk(b"2-1"), array(vec![]), array(vec![bulk(b"1-1")])])
+    );
+}
+
+#[test]
+fn xclaim_drops_deleted_entries_instead_of_claiming_them() {
+    let mut rt = pending_with_deleted_entries();
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"XCLAIM", b"s", b"g", b"bob", b"0", b"1-1", b"3-1"]),
+            0
+        ),
+        array(vec![entry(b"3-1", b"v3")])
+    );
+    assert_eq!(
+        rt.execute_frame(
+            command(&[b"XCLAIM", b"s", b"g", b"bob", b"0", b"2-1", b"JUSTID"]),
+            0
+        ),
+        array(vec![])
+    );
+    assert_eq!(
+        pending_summary(&mut rt),
+        array(vec![
+            RespFrame::Integer(1),
+            bulk(b"3-1"),
+            bulk(b"3-1"),
+            array(vec![array(vec![bulk(b"bob"), bulk(b"1")])]),
+        ])
+    );
+}
@@ -0,0 +1,161 @@
+//! A RESP2 client in subscriber mode, end to end.
+//!
+//! SUBSCRIBE / PSUBSCRIBE with several targets reply once per target with the
+//! client's running subscription count (channels + patterns). While any
+//! subscription is held only the pub/sub commands, PING, QUIT and RESET are
+//! accepted; dropping the last one — or RESET — restores the normal command
+//! set.
+
+use fr_protocol::RespFrame;
+use fr_runtime::Runtime;
+
+fn command(parts: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some((*part).to_vec())))
+            .collect(),
+    ))
+}
+
+/// Split a multi-target reply into `(kind, target, count)` triples.
+fn replies(frame: RespFrame) -> Vec<(String, Option<String>, i64)> {
+    let items = match frame {
+        RespFrame::Sequence(items) => items,
+        single @ RespFrame::Array(_) => vec![single],
+        other => panic!("expected pub/sub replies, got: {other:?}"),
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            RespFrame::Array(Some(parts)) => match parts.as_slice() {
+                [
+                    RespFrame::BulkString(Some(kind)),
+                    target,
+                    RespFrame::Integer(count),
+                ] => {
+                    let target = match target {
+                        RespFrame::BulkString(Some(name)) => {
+                            Some(String::from_utf8(name.clone()).unwrap())
+                        }
+                        RespFrame::BulkString(None) => None,
+                        other => panic!("unexpected target: {other:?}"),
+                    };
+                    (String::from_utf8(kind.clone()).unwrap(), target, *count)
+                }
+                other => panic!("unexpected reply shape: {other:?}"),
+            },
+            other => panic!("unexpected reply: {other:?}"),
+        })
+        .collect()
+}
+
+fn reply(kind: &str, target: &str, count: i64) -> (String, Option<String>, i64) {
+    (kind.to_string(), Some(target.to_string()), count)
+}
+
+fn refused(name: &str) -> RespFrame {
+    RespFrame::Error(format!(
+        "ERR Can't execute '{name}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context"
+    ))
+}
+
+#[test]
+fn multi_target_subscribe_replies_carry_the_running_count() {
+    let mut rt = Runtime::default_strict();
+    let first = rt.execute_frame(command(&[b"SUBSCRIBE", b"a", b"b", b"c"]), 0);
+    assert!(matches!(first, RespFrame::Sequence(_)), "{first:?}");
+    assert_eq!(
+        replies(first),
+        [
+            reply("subscribe", "a", 1),
+            reply("subscribe", "b", 2),
+            reply("subscribe", "c", 3)
+        ]
+    );
+
+    // Re-subscribing is acknowledged without bumping the count; patterns
+    // share the same counter as channels.
+    assert_eq!(
+        replies(rt.execute_frame(command(&[b"SUBSCRIBE", b"b", b"d"]), 1)),
+        [reply("subscribe", "b", 3), reply("subscribe", "d", 4)]
+    );
+    assert_eq!(
+        replies(rt.execute_frame(command(&[b"PSUBSCRIBE", b"n.*", b"m.*"]), 2)),
+        [reply("psubscribe", "n.*", 5), reply("psubscribe", "m.*", 6)]
+    );
+    assert!(rt.is_in_subscription_mode());
+}
+
+#[test]
+fn subscriber_mode_refuses_regular_commands_until_the_last_unsubscribe() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SUBSCRIBE", b"a", b"b"]), 0);
+    rt.execute_frame(command(&[b"PSUBSCRIBE", b"p.*"]), 0);
+
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"k"]), 1),
+        refused("get")
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"k", b"v"]), 1),
+        refused("set")
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"PUBLISH", b"a", b"hi"]), 1),
+        refused("publish")
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"PING"]), 1),
+        RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"pong".to_vec())),
+            RespFrame::BulkString(Some(Vec::new())),
+        ]))
+    );
+
+    // Bare UNSUBSCRIBE drops every channel, one reply each, counting down to
+    // the pattern that is still held. Channel order is unspecified, so only
+    // the set of names is checked.
+    let dropped = replies(rt.execute_frame(command(&[b"UNSUBSCRIBE"]), 2));
+    assert!(dropped.iter().all(|(kind, _, _)| kind == "unsubscribe"));
+    let counts: Vec<i64> = dropped.iter().map(|(_, _, count)| *count).collect();
+    assert_eq!(counts, [2, 1]);
+    let mut channels: Vec<_> = dropped.into_iter().map(|(_, target, _)| target).collect();
+    channels.sort();
+    assert_eq!(channels, [Some("a".to_string()), Some("b".to_string())]);
+    assert!(rt.is_in_subscription_mode());
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"k"]), 2),
+        refused("get")
+    );
+
+    assert_eq!(
+        replies(rt.execute_frame(command(&[b"PUNSUBSCRIBE", b"p.*"]), 3)),
+        [reply("punsubscribe", "p.*", 0)]
+    );
+    assert!(!rt.is_in_subscription_mode());
+    assert_eq!(
+        rt.execute_frame(command(&[b"SET", b"k", b"v"]), 3),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        rt.execute_frame(command(&[b"PING"]), 3),
+        RespFrame::SimpleString("PONG".to_string())
+    );
+}
+
+#[test]
+fn reset_leaves_subscriber_mode() {
+    let mut rt = Runtime::default_strict();
+    rt.execute_frame(command(&[b"SUBSCRIBE", b"a"]), 0);
+    rt.execute_frame(command(&[b"PSUBSCRIBE", b"p.*"]), 0);
+    assert_eq!(
+        rt.execute_frame(command(&[b"RESET"]), 1),
+        RespFrame::SimpleString("RESET".to_string())
+    );
+    assert!(!rt.is_in_subscription_mode());
+    assert_eq!(
+        rt.execute_frame(command(&[b"GET", b"k"]), 1),
+        RespFrame::BulkString(None)
+    );
+}
@@ -0,0 +1,48 @@
+//! Embed the commit the server is built from as `FR_GIT_SHA1`, the role
+//! upstream's `mkreleasehdr.sh` plays for `REDIS_GIT_SHA1`. Builds outside a git
+//! checkout (release tarballs) can pass `FR_GIT_SHA1` in the environment and
+//! otherwise fall back to upstream's `00000000` placeholder.
+
+use std::path::Path;
+use std::process::Command;
+
+const UNKNOWN_SHA1: &str = "00000000";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=FR_GIT_SHA1");
+    let sha1 = std::env::var("FR_GIT_SHA1")
+        .ok()
+        .filter(|sha1| !sha1.is_empty() && sha1.bytes().all(|b| b.is_ascii_hexdigit()))
+        .or_else(git_head_sha1)
+        .unwrap_or_else(|| UNKNOWN_SHA1.to_string());
+    println!(
+        "cargo:rustc-env=FR_GIT_SHA1={}",
+        &sha1[..sha1.len().min(UNKNOWN_SHA1.len())]
+    );
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn git_head_sha1() -> Option<String> {
+    let git_dir = git(&["rev-parse", "--absolute-git-dir"])?;
+    // Re-run when HEAD moves: HEAD itself, the branch it points at, and the
+    // packed refs a `git gc` moves that branch into. Cargo re-runs every build
+    // for a watched path that does not exist, so only watch the files present.
+    let mut watched = vec!["HEAD".to_string(), "packed-refs".to_string()];
+    watched.extend(git(&["symbolic-ref", "-q", "HEAD"]));
+    for name in watched {
+        let path = Path::new(&git_dir).join(name);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+    git(&["rev-parse", "HEAD"])
+}
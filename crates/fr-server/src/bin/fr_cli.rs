@@ -0,0 +1,212 @@
+//! `fr-cli`: offline import of Redis data files into FrankenRedis.
+//!
+//! Loads a `dump.rdb` or an `appendonlydir` with the tolerant importer in
+//! [`fr_server::import`], prints a per-type summary with every skipped record,
+//! and then replays the dataset to a running server over RESP, writes it out as
+//! an RDB snapshot, or (with `--dry-run`) stops after validating.
+
+#![forbid(unsafe_code)]
+
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use fr_runtime::Runtime;
+use fr_server::import::{CommandPipeline, ImportSource, load_source, send_commands};
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 6379;
+const DEFAULT_BATCH: usize = 1000;
+/// Print a progress line at most every this many commands sent.
+const PROGRESS_EVERY: usize = 100_000;
+
+fn help_text() -> String {
+    format!(
+        "fr-cli {} — FrankenRedis data import tool\n\n\
+USAGE: fr-cli (--rdb-load <PATH> | --aof-load <PATH>) [OPTIONS]\n\n\
+Loads a Redis 7.x dump or AOF offline, skipping (and reporting) what FrankenRedis\n\
+cannot represent, then writes the dataset to a running server.\n\n\
+OPTIONS:\n\
+  --rdb-load <PATH>   Import an RDB snapshot (dump.rdb)\n\
+  --aof-load <PATH>   Import an appendonlydir, its manifest, or a single AOF file\n\
+  --host <HOST>       Target server host (default: {DEFAULT_HOST})\n\
+  --port <PORT>       Target server port (default: {DEFAULT_PORT})\n\
+  --user <USERNAME>   Authenticate to the target as this ACL user\n\
+  --auth <PASSWORD>   Authenticate to the target with this password\n\
+  --batch <N>         Commands pipelined per round trip (default: {DEFAULT_BATCH})\n\
+  --out <PATH>        Write the dataset to an RDB file instead of a server\n\
+  --dry-run           Validate and summarize only; write nothing\n\
+  --help              Show this help\n\n\
+Exits 0 on success (warnings included), 1 if the input could not be read or the\n\
+target could not be reached, and 2 if the target rejected any write.\n",
+        fr_server::version().frankenredis,
+    )
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+
+    let mut source: Option<ImportSource> = None;
+    let mut host = DEFAULT_HOST.to_string();
+    let mut port = DEFAULT_PORT;
+    let mut user: Option<String> = None;
+    let mut auth: Option<String> = None;
+    let mut batch = DEFAULT_BATCH;
+    let mut out: Option<PathBuf> = None;
+    let mut dry_run = false;
+    let mut i = 1;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        if matches!(flag, "--help" | "-h") {
+            print!("{}", help_text());
+            return ExitCode::SUCCESS;
+        }
+        if flag == "--dry-run" {
+            dry_run = true;
+            i += 1;
+            continue;
+        }
+        let Some(value) = args.get(i + 1).cloned() else {
+            eprintln!("error: {flag} requires a value");
+            return ExitCode::from(1);
+        };
+        match flag {
+            "--rdb-load" | "--aof-load" => {
+                if source.is_some() {
+                    eprintln!("error: pass exactly one of --rdb-load or --aof-load");
+                    return ExitCode::from(1);
+                }
+                let path = PathBuf::from(value);
+                source = Some(if flag == "--rdb-load" {
+                    ImportSource::Rdb(path)
+                } else {
+                    ImportSource::Aof(path)
+                });
+            }
+            "--host" => host = value,
+            "--port" => {
+                port = match value.parse() {
+                    Ok(p) => p,
+                    Err(_) => {
+                        eprintln!("error: invalid port number: {value}");
+                        return ExitCode::from(1);
+                    }
+                };
+            }
+            "--user" => user = Some(value),
+            "--auth" => auth = Some(value),
+            "--batch" => {
+                batch = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => {
+                        eprintln!("error: invalid batch size: {value}");
+                        return ExitCode::from(1);
+                    }
+                };
+            }
+            "--out" => out = Some(PathBuf::from(value)),
+            other => {
+                eprintln!("error: unknown option '{other}' (see --help)");
+                return ExitCode::from(1);
+            }
+        }
+        i += 2;
+    }
+    let Some(source) = source else {
+        eprintln!("error: pass --rdb-load <PATH> or --aof-load <PATH> (see --help)");
+        return ExitCode::from(1);
+    };
+
+    let now = now_ms();
+    let mut runtime = Runtime::default_strict();
+    let summary = match load_source(&mut runtime, &source, now, &mut |phase| {
+        eprintln!("fr-cli: {phase}");
+    }) {
+        Ok(summary) => summary,
+        Err(err) => {
+            eprintln!("error: failed to load {source:?}: {err:?}");
+            return ExitCode::from(1);
+        }
+    };
+    print!("{summary}");
+
+    if dry_run {
+        println!("dry run: nothing written");
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(path) = out {
+        let bytes = runtime.encoded_rdb_snapshot(now);
+        if let Err(err) = fr_persist::write_rdb_bytes(&path, &bytes) {
+            eprintln!("error: failed to write {}: {err:?}", path.display());
+            return ExitCode::from(1);
+        }
+        println!("wrote {} ({} bytes)", path.display(), bytes.len());
+        return ExitCode::SUCCESS;
+    }
+
+    let mut stream = match TcpStream::connect((host.as_str(), port)) {
+        Ok(stream) => stream,
+        Err(err) => {
+            eprintln!("error: failed to connect to {host}:{port}: {err}");
+            return ExitCode::from(1);
+        }
+    };
+    if let Some(password) = auth {
+        let mut argv = vec![b"AUTH".to_vec()];
+        argv.extend(user.map(String::into_bytes));
+        argv.push(password.into_bytes());
+        match send_commands(&mut stream, &[argv], 1, &mut |_, _| {}) {
+            Ok(reply) if reply.rejected.is_empty() => {}
+            Ok(reply) => {
+                eprintln!("error: {}", reply.rejected.join("; "));
+                return ExitCode::from(1);
+            }
+            Err(err) => {
+                eprintln!("error: AUTH failed: {err}");
+                return ExitCode::from(1);
+            }
+        }
+    }
+
+    let mut pipeline = CommandPipeline::new(&mut stream, batch);
+    let mut next_report = PROGRESS_EVERY;
+    let sent = runtime
+        .try_for_each_dataset_command(now, |argv| {
+            if pipeline.push(&argv)? && pipeline.sent() >= next_report {
+                eprintln!("fr-cli: sent {} commands", pipeline.sent());
+                next_report = pipeline.sent() + PROGRESS_EVERY;
+            }
+            Ok::<(), std::io::Error>(())
+        })
+        .and_then(|()| pipeline.finish());
+    let sent = match sent {
+        Ok(sent) => sent,
+        Err(err) => {
+            eprintln!("error: lost connection to {host}:{port}: {err}");
+            return ExitCode::from(1);
+        }
+    };
+    println!(
+        "target {host}:{port}: {} commands sent, {} rejected",
+        sent.sent,
+        sent.rejected.len()
+    );
+    for rejected in sent.rejected.iter().take(20) {
+        println!("  - {rejected}");
+    }
+    if sent.rejected.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(2)
+    }
+}
@@ -0,0 +1,473 @@
+//! Offline import of Redis data files, behind `fr-cli --rdb-load` /
+//! `--aof-load`.
+//!
+//! The server's startup load fails closed on anything it does not recognise.
+//! The importer instead walks past what it cannot represent — module data, a
+//! newer RDB header, a truncated AOF tail, records the dataset rejects — and
+//! reports each as a warning. Data is first loaded into a local [`Runtime`],
+//! which validates it and yields the per-type summary; a [`CommandPipeline`]
+//! then replays it to a running server as the writes an AOF rewrite would
+//! emit.
+//!
+//! Input files are read through a `BufReader` and applied as they decode:
+//! RDB keys and AOF records reach the runtime in batches, so a multi-gigabyte
+//! dump never sits in memory as raw bytes or as a decoded list. The dataset
+//! itself does: the whole keyspace is built in the local runtime before any
+//! of it is replayed, so importing needs memory for the loaded data, just as
+//! the target server does.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use fr_persist::{
+    AofRecord, AofReplaySegmentPosition, AofStreamError, AofStreamReader, PersistError, RdbSkipped,
+    decode_rdb_reader_lenient, read_aof_manifest_file,
+};
+use fr_protocol::{RespFrame, RespParseError, encode_aggregate_header, encode_bulk_string_slice};
+use fr_runtime::Runtime;
+
+/// How many warnings [`ImportSummary`]'s report lists before eliding the rest.
+const REPORTED_WARNINGS: usize = 20;
+
+/// RDB keys or AOF records handed to the runtime at a time.
+const APPLY_BATCH: usize = 1_000;
+
+/// How often, in keys or records, loading reports progress.
+const PROGRESS_EVERY: usize = 100_000;
+
+/// The data file(s) to import.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportSource {
+    /// A `dump.rdb` snapshot.
+    Rdb(PathBuf),
+    /// A redis 7 `appendonlydir` (or its manifest), or a single legacy AOF
+    /// file, optionally starting with an RDB preamble.
+    Aof(PathBuf),
+}
+
+/// What an import loaded, and everything it skipped along the way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Live keys per logical type once loading finished.
+    pub keys_by_type: BTreeMap<&'static str, usize>,
+    /// RDB keys dropped because their TTL had already passed.
+    pub expired_keys: usize,
+    /// Function libraries carried in the dump.
+    pub functions: usize,
+    /// AOF command records replayed.
+    pub aof_records: usize,
+    /// Skipped data and rejected records, in file order.
+    pub warnings: Vec<String>,
+}
+
+impl ImportSummary {
+    #[must_use]
+    pub fn total_keys(&self) -> usize {
+        self.keys_by_type.values().sum()
+    }
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "keys: {}", self.total_keys())?;
+        if !self.keys_by_type.is_empty() {
+            let by_type: Vec<String> = self
+                .keys_by_type
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect();
+            write!(f, " ({})", by_type.join(", "))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "expired on load: {}", self.expired_keys)?;
+        writeln!(f, "functions: {}", self.functions)?;
+        writeln!(f, "aof records replayed: {}", self.aof_records)?;
+        writeln!(f, "warnings: {}", self.warnings.len())?;
+        for warning in self.warnings.iter().take(REPORTED_WARNINGS) {
+            writeln!(f, "  - {warning}")?;
+        }
+        if self.warnings.len() > REPORTED_WARNINGS {
+            writeln!(
+                f,
+                "  ... and {} more",
+                self.warnings.len() - REPORTED_WARNINGS
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Load `source` into `runtime`, reporting each phase through `progress`.
+///
+/// Only unreadable input fails the import: an RDB whose records cannot be
+/// walked, an AOF manifest that does not parse, or I/O errors. Everything
+/// else lands in [`ImportSummary::warnings`].
+pub fn load_source(
+    runtime: &mut Runtime,
+    source: &ImportSource,
+    now_ms: u64,
+    progress: &mut dyn FnMut(&str),
+) -> Result<ImportSummary, PersistError> {
+    let mut summary = ImportSummary::default();
+    match source {
+        ImportSource::Rdb(path) => {
+            progress(&format!("decoding {}", path.display()));
+            let mut file = BufReader::new(File::open(path)?);
+            let (_, tail) = load_rdb_stream(runtime, &mut file, now_ms, &mut summary, progress)?;
+            let trailing = tail.len() as u64 + io::copy(&mut file, &mut io::sink())?;
+            if trailing != 0 {
+                summary.warnings.push(format!(
+                    "ignored {trailing} trailing bytes after the RDB checksum"
+                ));
+            }
+        }
+        ImportSource::Aof(path) => {
+            let segments = aof_segments(path)?;
+            let last = segments.len().saturating_sub(1);
+            for (index, segment) in segments.iter().enumerate() {
+                let position = if index == last {
+                    AofReplaySegmentPosition::Final
+                } else {
+                    AofReplaySegmentPosition::NonFinal
+                };
+                load_aof_segment(runtime, segment, position, now_ms, &mut summary, progress)?;
+            }
+        }
+    }
+    progress("counting keys");
+    summary.keys_by_type = runtime.keyspace_type_counts(now_ms);
+    Ok(summary)
+}
+
+/// Decode an RDB (or AOF preamble) from `reader` into `runtime`, applying
+/// its keys in batches as they decode. Returns how many bytes the RDB spanned
+/// and the bytes already read past its checksum.
+fn load_rdb_stream<R: Read>(
+    runtime: &mut Runtime,
+    reader: &mut R,
+    now_ms: u64,
+    summary: &mut ImportSummary,
+    progress: &mut dyn FnMut(&str),
+) -> Result<(u64, Vec<u8>), PersistError> {
+    let mut batch = Vec::with_capacity(APPLY_BATCH);
+    let mut applied = 0;
+    let mut next_report = PROGRESS_EVERY;
+    let decoded = decode_rdb_reader_lenient(reader, |entry| {
+        batch.push(entry);
+        if batch.len() == APPLY_BATCH {
+            let entries = std::mem::replace(&mut batch, Vec::with_capacity(APPLY_BATCH));
+            summary.expired_keys += runtime.import_rdb_entries(entries, &[], now_ms)?.expired;
+            applied += APPLY_BATCH;
+            if applied >= next_report {
+                progress(&format!("applied {applied} keys"));
+                next_report = applied + PROGRESS_EVERY;
+            }
+        }
+        Ok(())
+    })?;
+    summary
+        .warnings
+        .extend(decoded.skipped.into_iter().map(describe_skipped));
+    applied += batch.len();
+    let counts = runtime.import_rdb_entries(batch, &decoded.functions, now_ms)?;
+    summary.expired_keys += counts.expired;
+    progress(&format!("applied {applied} keys"));
+    summary.functions += decoded.functions.len();
+    Ok((decoded.consumed, decoded.tail))
+}
+
+fn describe_skipped(skipped: RdbSkipped) -> String {
+    match skipped {
+        RdbSkipped::NewerVersion(version) => format!(
+            "RDB format version {version} is newer than this build writes; \
+             loading the records it recognises"
+        ),
+        RdbSkipped::ModuleValue { db, key, module } => format!(
+            "skipped key '{}' in db {db}: module type {module} is not supported",
+            String::from_utf8_lossy(&key)
+        ),
+        RdbSkipped::ModuleAux { module } => {
+            format!("skipped aux data for module {module}")
+        }
+    }
+}
+
+/// The files to replay, in order: a manifest's base and incrementals, or the
+/// single legacy AOF file.
+fn aof_segments(path: &Path) -> Result<Vec<PathBuf>, PersistError> {
+    let manifest_path = if path.is_dir() {
+        std::fs::read_dir(path)?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|p| p.extension().and_then(|e| e.to_str()) == Some("manifest"))
+    } else if path.extension().and_then(|e| e.to_str()) == Some("manifest") {
+        Some(path.to_path_buf())
+    } else {
+        None
+    };
+    let Some(manifest_path) = manifest_path else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let manifest = read_aof_manifest_file(&manifest_path)?;
+    let dir = manifest_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map_or_else(|| Path::new(".").to_path_buf(), Path::to_path_buf);
+    Ok(manifest
+        .replay_entries()
+        .map(|entry| dir.join(&entry.file_name))
+        .collect())
+}
+
+fn load_aof_segment(
+    runtime: &mut Runtime,
+    path: &Path,
+    position: AofReplaySegmentPosition,
+    now_ms: u64,
+    summary: &mut ImportSummary,
+    progress: &mut dyn FnMut(&str),
+) -> Result<(), PersistError> {
+    progress(&format!("reading {}", path.display()));
+    let mut file = BufReader::new(File::open(path)?);
+    // A `.base.rdb` file, or an AOF written with `aof-use-rdb-preamble yes`.
+    let (start, tail) = if file.fill_buf()?.starts_with(b"REDIS") {
+        load_rdb_stream(runtime, &mut file, now_ms, summary, progress)?
+    } else {
+        (0, Vec::new())
+    };
+    let mut reader = AofStreamReader::new(io::Cursor::new(tail).chain(file));
+    let mut replay = AofReplay {
+        runtime,
+        path,
+        now_ms,
+        batch: Vec::with_capacity(APPLY_BATCH),
+        replayed: 0,
+        next_report: PROGRESS_EVERY,
+        rejected: Vec::new(),
+    };
+    // The final segment's trailing MULTI without EXEC is dropped, so an open
+    // MULTI block is held back until EXEC or DISCARD closes it.
+    let hold_multi = position == AofReplaySegmentPosition::Final;
+    let mut held: Vec<AofRecord> = Vec::new();
+    let mut held_from = 0;
+
+    loop {
+        let record = match reader.next_record() {
+            Ok(Some(record)) => record,
+            Ok(None) => break,
+            Err(AofStreamError::Io(err)) => return Err(err.into()),
+            Err(AofStreamError::Unreadable { offset, .. }) => {
+                let trailing = reader.discard_rest()?;
+                let offset = start + offset as u64;
+                summary.warnings.push(match position {
+                    AofReplaySegmentPosition::Final => format!(
+                        "{}: dropped a truncated {trailing}-byte tail at offset {offset}",
+                        path.display()
+                    ),
+                    AofReplaySegmentPosition::NonFinal => format!(
+                        "{}: stopped at unreadable record at offset {offset}, skipping {trailing} bytes",
+                        path.display()
+                    ),
+                });
+                break;
+            }
+        };
+        let command = record.record.argv.first().map_or(&[][..], Vec::as_slice);
+        if hold_multi && !held.is_empty() {
+            let closes =
+                command.eq_ignore_ascii_case(b"EXEC") || command.eq_ignore_ascii_case(b"DISCARD");
+            held.push(record.record);
+            if closes {
+                replay.batch.append(&mut held);
+            }
+        } else if hold_multi && command.eq_ignore_ascii_case(b"MULTI") {
+            held_from = record.start_offset;
+            held.push(record.record);
+        } else {
+            replay.batch.push(record.record);
+        }
+        if replay.batch.len() >= APPLY_BATCH {
+            replay.flush(progress);
+        }
+    }
+    if !held.is_empty() {
+        summary.warnings.push(format!(
+            "{}: dropped a MULTI without EXEC at offset {}",
+            path.display(),
+            start + held_from as u64
+        ));
+    }
+    replay.flush(progress);
+    summary.aof_records += replay.replayed;
+    summary.warnings.append(&mut replay.rejected);
+    Ok(())
+}
+
+/// AOF records waiting to replay into the runtime, and what replaying the
+/// earlier ones turned up.
+struct AofReplay<'a> {
+    runtime: &'a mut Runtime,
+    path: &'a Path,
+    now_ms: u64,
+    batch: Vec<AofRecord>,
+    replayed: usize,
+    next_report: usize,
+    /// One warning per error reply, reported after the segment's read warnings.
+    rejected: Vec<String>,
+}
+
+impl AofReplay<'_> {
+    fn flush(&mut self, progress: &mut dyn FnMut(&str)) {
+        if self.batch.is_empty() {
+            return;
+        }
+        // Each record runs at its own millisecond, counted from the start of
+        // the segment.
+        let now_ms = self.now_ms.saturating_add(self.replayed as u64);
+        let replies = self.runtime.replay_aof_records(&self.batch, now_ms);
+        for (record, reply) in self.batch.iter().zip(&replies) {
+            if let RespFrame::Error(message) = reply {
+                let command = record.argv.first().map_or_else(String::new, |name| {
+                    String::from_utf8_lossy(name).to_ascii_uppercase()
+                });
+                self.rejected.push(format!(
+                    "{}: {command} rejected: {message}",
+                    self.path.display()
+                ));
+            }
+        }
+        self.replayed += self.batch.len();
+        self.batch.clear();
+        if self.replayed >= self.next_report {
+            progress(&format!("replayed {} records", self.replayed));
+            self.next_report = self.replayed + PROGRESS_EVERY;
+        }
+    }
+}
+
+/// Reply tally from a [`CommandPipeline`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SendSummary {
+    /// Commands written to the target.
+    pub sent: usize,
+    /// Error replies, each prefixed with the command it answered.
+    pub rejected: Vec<String>,
+}
+
+/// Pipelines commands to a server as they are produced: every `batch`
+/// commands go out in one write, and their replies are read before the next
+/// batch is sent.
+pub struct CommandPipeline<'s, S> {
+    stream: &'s mut S,
+    batch: usize,
+    out: Vec<u8>,
+    /// Names of the commands written since the last flush, to label errors.
+    pending: Vec<Vec<u8>>,
+    replies: Vec<u8>,
+    summary: SendSummary,
+}
+
+impl<'s, S: Read + Write> CommandPipeline<'s, S> {
+    pub fn new(stream: &'s mut S, batch: usize) -> Self {
+        Self {
+            stream,
+            batch: batch.max(1),
+            out: Vec::new(),
+            pending: Vec::new(),
+            replies: Vec::new(),
+            summary: SendSummary::default(),
+        }
+    }
+
+    /// Queue `argv`; true when this filled a batch and sent it.
+    pub fn push(&mut self, argv: &[Vec<u8>]) -> io::Result<bool> {
+        encode_aggregate_header(argv.len(), false, &mut self.out);
+        for arg in argv {
+            encode_bulk_string_slice(Some(arg), false, &mut self.out);
+        }
+        self.pending.push(argv.first().cloned().unwrap_or_default());
+        if self.pending.len() < self.batch {
+            return Ok(false);
+        }
+        self.flush()
+    }
+
+    /// Send the queued commands and read their replies; true if there were
+    /// any.
+    pub fn flush(&mut self) -> io::Result<bool> {
+        if self.pending.is_empty() {
+            return Ok(false);
+        }
+        self.stream.write_all(&self.out)?;
+        self.stream.flush()?;
+        self.out.clear();
+        for name in self.pending.drain(..) {
+            if let RespFrame::Error(message) = read_reply(self.stream, &mut self.replies)? {
+                let command = String::from_utf8_lossy(&name).to_ascii_uppercase();
+                self.summary.rejected.push(format!("{command}: {message}"));
+            }
+            self.summary.sent += 1;
+        }
+        Ok(true)
+    }
+
+    /// Commands sent and answered so far.
+    #[must_use]
+    pub fn sent(&self) -> usize {
+        self.summary.sent
+    }
+
+    /// Flush what is still queued and return the tally.
+    pub fn finish(mut self) -> io::Result<SendSummary> {
+        self.flush()?;
+        Ok(self.summary)
+    }
+}
+
+/// Pipeline `commands` to a server in batches of `batch`, reading every reply
+/// before the next batch goes out. `progress` sees the running and total
+/// command counts after each batch.
+pub fn send_commands<S: Read + Write>(
+    stream: &mut S,
+    commands: &[Vec<Vec<u8>>],
+    batch: usize,
+    progress: &mut dyn FnMut(usize, usize),
+) -> io::Result<SendSummary> {
+    let mut pipeline = CommandPipeline::new(stream, batch);
+    for argv in commands {
+        if pipeline.push(argv)? {
+            progress(pipeline.sent(), commands.len());
+        }
+    }
+    if pipeline.flush()? {
+        progress(pipeline.sent(), commands.len());
+    }
+    pipeline.finish()
+}
+
+fn read_reply<S: Read>(stream: &mut S, buf: &mut Vec<u8>) -> io::Result<RespFrame> {
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        match fr_protocol::parse_frame(buf) {
+            Ok(parsed) => {
+                buf.drain(..parsed.consumed);
+                return Ok(parsed.frame);
+            }
+            Err(RespParseError::Incomplete) => {}
+            Err(err) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unparseable reply: {err:?}"),
+                ));
+            }
+        }
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
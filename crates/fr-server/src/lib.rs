@@ -1,9 +1,36 @@
 #![forbid(unsafe_code)]
 
+pub mod import;
+#[cfg(all(unix, feature = "systemd"))]
+pub mod systemd;
+
 use std::io;
 
 use fr_protocol::{ParserConfig, RespFrame, RespParseError};
 
+/// Version metadata for this build. The INFO, HELLO and LOLWUT version
+/// surfaces, the `--version` banner and the startup log line all derive from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Version {
+    /// FrankenRedis's own version (the workspace `CARGO_PKG_VERSION`).
+    pub frankenredis: &'static str,
+    /// Redis release reported as `redis_version` unless `server-version`
+    /// overrides it.
+    pub redis_compat: &'static str,
+    /// Commit the binary was built from, `00000000` outside a git checkout.
+    pub git_sha1: &'static str,
+}
+
+/// Version metadata for this build; see [`Version`].
+#[must_use]
+pub const fn version() -> Version {
+    Version {
+        frankenredis: fr_store::FRANKENREDIS_VERSION,
+        redis_compat: fr_store::REDIS_COMPAT_VERSION,
+        git_sha1: env!("FR_GIT_SHA1"),
+    }
+}
+
 /// Result of inline command parsing.
 #[derive(Debug, Clone, PartialEq)]
 pub enum InlineParseResult {
@@ -39,9 +39,9 @@ use fr_eventloop::{
 use fr_protocol::{BorrowedCommandArgsKind, ParserConfig, RespFrame, RespParseError};
 use fr_repl::ReplOffset;
 use fr_runtime::{
-    ClientSession, ClientUnblockMode, PlainBitfieldGetCmd, PlainCardinalityCmd, PlainKeyMetaCmd,
-    PlainKeyedPopCmd, PlainKeyedValuesCmd, PlainObjectStatCmd, PlainRandMemberCmd, PlainRankCmd,
-    Runtime,
+    ClientSession, ClientUnblockMode, PlainBitcountJob, PlainBitcountStep, PlainBitfieldGetCmd,
+    PlainCardinalityCmd, PlainKeyMetaCmd, PlainKeyedPopCmd, PlainKeyedValuesCmd,
+    PlainObjectStatCmd, PlainRandMemberCmd, PlainRankCmd, Runtime,
 };
 use mio::net::{TcpListener, TcpStream};
 use mio::{Events, Interest, Poll, Token, Waker};
@@ -55,13 +55,17 @@ const DEFAULT_MODE: &str = "strict";
 /// connection handles start at `MAX_LISTENERS`. Lets CONFIG SET bind rebind a
 /// multi-address listener set without colliding with client tokens.
 const MAX_LISTENERS: usize = 16;
-const WRITER_WAKE_TOKEN: Token = Token(usize::MAX);
+/// The writer pool and the command pool share one `Waker` under this token.
+const WORKER_WAKE_TOKEN: Token = Token(usize::MAX);
 const WRITER_POOL_WORKERS: usize = 2;
 const WRITER_QUEUE_BOUND: usize = 1024;
+const COMMAND_POOL_WORKERS: usize = 4;
+/// Smallest string a `BITCOUNT` is handed to the command pool for. Counting
+/// 1 MiB takes tens of microseconds, well above the cost of the hand-off.
+const COMMAND_POOL_MIN_BITCOUNT_BYTES: usize = 1024 * 1024;
 
 const REPLICA_ACK_INTERVAL_MS: u64 = 1_000;
 const REPLICA_RECONNECT_BACKOFF_MS: u64 = 250;
-const MAX_FRAMES_PER_CLIENT_TICK: usize = 4096;
 const MAX_REPLY_BYTES_PER_CLIENT_TICK: usize = 16 * 1024 * 1024;
 const DIRECT_OWNED_SET_MIN_VALUE: usize = 32 * 1024;
 const DIRECT_OWNED_SET_CHUNK: usize = 256 * 1024;
@@ -359,6 +363,11 @@ struct ClientConnection {
     closing: bool,
     /// If set, the client is blocked waiting for data.
     blocked: Option<BlockedState>,
+    /// Hands large reads to the command pool; `None` runs every command inline.
+    command_jobs: Option<mpsc::Sender<CommandJob>>,
+    /// A command from this client is on the command pool. Like `blocked`, it
+    /// holds the rest of the pipeline in `read_buf` until the reply is queued.
+    command_in_flight: bool,
     /// If set, this client is a replica and this is the last offset sent to it.
     replication_sent_offset: Option<ReplOffset>,
 }
@@ -418,12 +427,13 @@ impl Drop for ClientConnection {
 impl ClientConnection {
     #[cfg(test)]
     fn new(stream: TcpStream, session: ClientSession, now_ms: u64) -> Self {
-        Self::new_with_writer(stream, None, session, now_ms)
+        Self::new_with_writer(stream, None, None, session, now_ms)
     }
 
     fn new_with_writer(
         stream: TcpStream,
         writer_stream: Option<StdTcpStream>,
+        command_jobs: Option<mpsc::Sender<CommandJob>>,
         mut session: ClientSession,
         now_ms: u64,
     ) -> Self {
@@ -443,6 +453,8 @@ impl ClientConnection {
             main_writable_armed: false,
             closing: false,
             blocked: None,
+            command_jobs,
+            command_in_flight: false,
             replication_sent_offset: None,
         }
     }
@@ -531,6 +543,7 @@ enum WriterCompletionStatus {
 struct WriterPool {
     jobs: mpsc::SyncSender<WriterJob>,
     completions: mpsc::Receiver<WriterCompletion>,
+    waker: Arc<Waker>,
 }
 
 impl WriterPool {
@@ -538,7 +551,7 @@ impl WriterPool {
         let (job_tx, job_rx) = mpsc::sync_channel(WRITER_QUEUE_BOUND);
         let (completion_tx, completion_rx) = mpsc::channel();
         let shared_rx = Arc::new(Mutex::new(job_rx));
-        let waker = Arc::new(Waker::new(poll.registry(), WRITER_WAKE_TOKEN)?);
+        let waker = Arc::new(Waker::new(poll.registry(), WORKER_WAKE_TOKEN)?);
 
         for worker_idx in 0..WRITER_POOL_WORKERS {
             let rx = Arc::clone(&shared_rx);
@@ -569,6 +582,7 @@ impl WriterPool {
         Ok(Self {
             jobs: job_tx,
             completions: completion_rx,
+            waker,
         })
     }
 
@@ -659,6 +673,90 @@ fn clone_writer_stream(_stream: &TcpStream) -> io::Result<StdTcpStream> {
     ))
 }
 
+/// A command handed to the command pool. The client stops dispatching until
+/// the matching [`CommandCompletion`] is delivered, so its replies stay in
+/// order while other clients keep running on the event loop.
+struct CommandJob {
+    token: Token,
+    client_id: u64,
+    job: PlainBitcountJob,
+}
+
+struct CommandCompletion {
+    token: Token,
+    client_id: u64,
+    job: PlainBitcountJob,
+    count: Result<usize, fr_store::StoreError>,
+    elapsed_us: u64,
+}
+
+/// Worker threads for read-only commands over large values. A job carries a
+/// snapshot of the value (see `Store::string_snapshot`), so workers never
+/// touch the store and writes to the key on the event loop do not wait for
+/// them. Two clients counting different keys run side by side.
+struct CommandPool {
+    jobs: mpsc::Sender<CommandJob>,
+    completions: mpsc::Receiver<CommandCompletion>,
+}
+
+impl CommandPool {
+    fn new(waker: Arc<Waker>) -> io::Result<Self> {
+        let (job_tx, job_rx) = mpsc::channel::<CommandJob>();
+        let (completion_tx, completion_rx) = mpsc::channel();
+        let shared_rx = Arc::new(Mutex::new(job_rx));
+
+        for worker_idx in 0..COMMAND_POOL_WORKERS {
+            let rx = Arc::clone(&shared_rx);
+            let tx = completion_tx.clone();
+            let wake = Arc::clone(&waker);
+            thread::Builder::new()
+                .name(format!("fr-command-{worker_idx}"))
+                .spawn(move || {
+                    loop {
+                        let recv_result = {
+                            let Ok(receiver) = rx.lock() else {
+                                return;
+                            };
+                            receiver.recv()
+                        };
+                        let Ok(CommandJob {
+                            token,
+                            client_id,
+                            job,
+                        }) = recv_result
+                        else {
+                            return;
+                        };
+                        let started = std::time::Instant::now();
+                        let count = job.run();
+                        let elapsed_us =
+                            u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+                        let completion = CommandCompletion {
+                            token,
+                            client_id,
+                            job,
+                            count,
+                            elapsed_us,
+                        };
+                        if tx.send(completion).is_err() {
+                            return;
+                        }
+                        let _ = wake.wake();
+                    }
+                })?;
+        }
+
+        Ok(Self {
+            jobs: job_tx,
+            completions: completion_rx,
+        })
+    }
+
+    fn try_recv(&self) -> Result<CommandCompletion, mpsc::TryRecvError> {
+        self.completions.try_recv()
+    }
+}
+
 #[derive(Clone, Copy)]
 struct UnixTime {
     ms: u64,
@@ -677,23 +775,170 @@ fn now_ms() -> u64 {
     now_unix_time().ms
 }
 
+/// The service manager this process reports its lifecycle to: systemd under
+/// `supervised systemd`, or `supervised auto` with `$NOTIFY_SOCKET` set.
+/// Inert without the `systemd` feature. A failed notification is logged and
+/// otherwise ignored; it never stops the server.
+#[derive(Debug, Default)]
+struct Supervisor {
+    #[cfg(all(unix, feature = "systemd"))]
+    notifier: Option<fr_server::systemd::Notifier>,
+    /// Milliseconds between `WATCHDOG=1` pings; 0 when `WatchdogSec=` is off.
+    #[cfg(all(unix, feature = "systemd"))]
+    watchdog_interval_ms: u64,
+    #[cfg(all(unix, feature = "systemd"))]
+    last_watchdog_ms: u64,
+}
+
+impl Supervisor {
+    /// Resolve a `supervised` value: `no`, `upstart`, `systemd` or `auto`.
+    fn from_mode(mode: &str) -> Result<Self, String> {
+        match mode.to_ascii_lowercase().as_str() {
+            "no" => Ok(Self::default()),
+            "upstart" => {
+                eprintln!("warning: supervised upstart is not supported; running unsupervised");
+                Ok(Self::default())
+            }
+            "systemd" => Ok(Self::systemd(false)),
+            "auto" => Ok(Self::systemd(true)),
+            other => Err(format!(
+                "invalid supervised mode '{other}' (expected: no, upstart, systemd, auto)"
+            )),
+        }
+    }
+
+    #[cfg(all(unix, feature = "systemd"))]
+    fn systemd(auto: bool) -> Self {
+        match fr_server::systemd::Notifier::from_env() {
+            Ok(Some(notifier)) => {
+                let watchdog_interval_ms =
+                    fr_server::systemd::watchdog_ping_interval().map_or(0, |interval| {
+                        u64::try_from(interval.as_millis())
+                            .unwrap_or(u64::MAX)
+                            .max(1)
+                    });
+                if watchdog_interval_ms == 0 {
+                    eprintln!("Supervised by systemd");
+                } else {
+                    eprintln!("Supervised by systemd (WATCHDOG=1 every {watchdog_interval_ms} ms)");
+                }
+                Self {
+                    notifier: Some(notifier),
+                    watchdog_interval_ms,
+                    last_watchdog_ms: 0,
+                }
+            }
+            Ok(None) => {
+                if !auto {
+                    eprintln!(
+                        "warning: systemd supervision requested, but NOTIFY_SOCKET not found"
+                    );
+                }
+                Self::default()
+            }
+            Err(err) => {
+                eprintln!("warning: cannot connect to NOTIFY_SOCKET: {err}; running unsupervised");
+                Self::default()
+            }
+        }
+    }
+
+    #[cfg(not(all(unix, feature = "systemd")))]
+    fn systemd(auto: bool) -> Self {
+        if !auto || std::env::var_os("NOTIFY_SOCKET").is_some() {
+            eprintln!(
+                "warning: systemd supervision needs a build with the `systemd` feature; running unsupervised"
+            );
+        }
+        Self::default()
+    }
+
+    #[cfg(all(unix, feature = "systemd"))]
+    fn send(
+        &self,
+        what: &str,
+        message: impl FnOnce(&fr_server::systemd::Notifier) -> io::Result<()>,
+    ) {
+        if let Some(notifier) = &self.notifier
+            && let Err(err) = message(notifier)
+        {
+            eprintln!("warning: sd_notify {what} failed: {err}");
+        }
+    }
+
+    /// `STATUS=`: startup progress, e.g. which file is loading.
+    fn status(&self, status: &str) {
+        #[cfg(all(unix, feature = "systemd"))]
+        self.send("STATUS", |notifier| notifier.status(status));
+        #[cfg(not(all(unix, feature = "systemd")))]
+        let _ = status;
+    }
+
+    /// `READY=1`: the dataset is loaded and the listeners are bound.
+    fn ready(&self, status: &str) {
+        #[cfg(all(unix, feature = "systemd"))]
+        self.send("READY", |notifier| notifier.ready(status));
+        #[cfg(not(all(unix, feature = "systemd")))]
+        let _ = status;
+    }
+
+    /// `STOPPING=1`: graceful shutdown has begun.
+    fn stopping(&self) {
+        #[cfg(all(unix, feature = "systemd"))]
+        self.send("STOPPING", fr_server::systemd::Notifier::stopping);
+    }
+
+    /// Server cron hook: a `WATCHDOG=1` ping every half `WatchdogSec=`.
+    fn cron(&mut self, now_ms: u64) {
+        #[cfg(all(unix, feature = "systemd"))]
+        if self.watchdog_interval_ms != 0
+            && now_ms.saturating_sub(self.last_watchdog_ms) >= self.watchdog_interval_ms
+        {
+            self.last_watchdog_ms = now_ms;
+            self.send("WATCHDOG", fr_server::systemd::Notifier::watchdog);
+        }
+        #[cfg(not(all(unix, feature = "systemd")))]
+        let _ = now_ms;
+    }
+}
+
+/// `--version` output, shaped like `redis-server --version`.
+fn server_version_text() -> String {
+    let version = fr_server::version();
+    format!(
+        "FrankenRedis server v={} sha={} redis_version={} bits={}\n",
+        version.frankenredis,
+        version.git_sha1,
+        version.redis_compat,
+        usize::BITS,
+    )
+}
+
 fn server_help_text() -> String {
     format!(
-        "frankenredis — FrankenRedis server\n\n\
-USAGE: frankenredis [OPTIONS]\n\n\
+        "frankenredis {} — FrankenRedis server\n\n\
+USAGE: frankenredis [/path/to/redis.conf] [OPTIONS]\n\n\
+A leading argument that is not an option is the config file, like redis-server.\n\n\
 OPTIONS:\n\
   --bind <ADDR>              Listen address (default: 127.0.0.1)\n\
   --port <PORT>              Listen port (default: {DEFAULT_PORT})\n\
   --mode <MODE>              Runtime mode: strict or hardened (default: {DEFAULT_MODE})\n\
   --sentinel                 Run in Sentinel mode (enables SENTINEL command dispatch)\n\
   --config <PATH>            Load redis.conf startup directives and use path for CONFIG REWRITE\n\
+  --ignore-unknown-config    Warn about, instead of rejecting, config-file directives CONFIG GET does not know\n\
   --aof <PATH>               AOF persistence file path (enables persistence)\n\
   --rdb <PATH>               RDB snapshot file path (enables SAVE/BGSAVE snapshots)\n\
   --replicaof <HOST> <PORT>  Configure this server as a replica of the given primary\n\
   --masteruser <USERNAME>    Authenticate to the configured primary as this ACL user\n\
   --masterauth <PASSWORD>    Authenticate to the configured primary with this password\n\
   --enable-debug-command <VALUE>  Allow DEBUG commands: no | local | yes (default: no, matches upstream Redis 7.2)\n\
-  --help                     Show this help\n"
+  --supervised <MODE>        Report readiness to a service manager: no | upstart | systemd | auto (default: no)\n\
+  --deterministic            Reply to SMEMBERS/HGETALL/HKEYS/HVALS/SSCAN/HSCAN in sorted order (tests/CI)\n\
+  --max-commands-per-tick <N>  Commands one connection runs per event-loop pass before yielding (default: 4096)\n\
+  --max-exec-us-per-tick <US>  Microseconds one connection runs per event-loop pass before yielding; 0 = no limit (default: 0)\n\
+  --version                  Show version information\n\
+  --help                     Show this help\n",
+        fr_server::version().frankenredis,
     )
 }
 
@@ -712,8 +957,41 @@ struct StartupConfig {
     appendfilename: Option<String>,
     aclfile: Option<String>,
     enable_debug_command: Option<String>,
+    /// `rename-command <original> <replacement>` pairs in file order; an
+    /// empty replacement disables the command.
+    rename_commands: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Every other directive, in file order, for the CONFIG GET registry.
+    registry: Vec<RegistryDirective>,
+}
+
+/// A config-file directive applied through `Runtime::apply_config_file_directive`
+/// once the runtime exists. Multi-value directives (`save 900 1`,
+/// `client-output-buffer-limit normal 0 0 0`) keep their arguments joined by
+/// spaces, which is the CONFIG SET form of the same value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RegistryDirective {
+    /// Config file the directive was read from; an `include`d file for
+    /// nested ones, empty when parsed from a string.
+    file: String,
+    line_number: usize,
+    name: String,
+    value: Vec<u8>,
 }
 
+impl RegistryDirective {
+    fn location(&self) -> String {
+        if self.file.is_empty() {
+            format!("line {}", self.line_number)
+        } else {
+            format!("line {} of '{}'", self.line_number, self.file)
+        }
+    }
+}
+
+/// Upstream has no nesting limit, but a file that includes itself would
+/// otherwise recurse until the stack overflows.
+const MAX_CONFIG_INCLUDE_DEPTH: usize = 16;
+
 impl StartupConfig {
     fn configured_rdb_path(&self) -> Option<String> {
         if self.dir.is_none() && self.dbfilename.is_none() {
@@ -747,18 +1025,39 @@ impl StartupConfig {
 }
 
 fn load_startup_config_file(path: &str) -> Result<StartupConfig, String> {
+    let mut config = StartupConfig::default();
+    load_config_file_into(&mut config, path, 0)?;
+    Ok(config)
+}
+
+fn load_config_file_into(
+    config: &mut StartupConfig,
+    path: &str,
+    depth: usize,
+) -> Result<(), String> {
     let input =
         std::fs::read(path).map_err(|err| format!("failed to read config file '{path}': {err}"))?;
     let parsed = parse_redis_config_bytes(&input)
         .map_err(|err| format!("failed to parse config file '{path}': {err}"))?;
-    startup_config_from_directives(&parsed.directives)
+    apply_startup_directives(config, path, &parsed.directives, depth)
+        .map_err(|err| format!("{err} (in '{path}')"))
 }
 
+#[cfg(test)]
 fn startup_config_from_directives(
     directives: &[fr_config::ParsedConfigDirective],
 ) -> Result<StartupConfig, String> {
     let mut config = StartupConfig::default();
+    apply_startup_directives(&mut config, "", directives, 0)?;
+    Ok(config)
+}
 
+fn apply_startup_directives(
+    config: &mut StartupConfig,
+    file: &str,
+    directives: &[fr_config::ParsedConfigDirective],
+    depth: usize,
+) -> Result<(), String> {
     for directive in directives {
         match directive.name.as_slice() {
             b"bind" => {
@@ -831,11 +1130,105 @@ fn startup_config_from_directives(
                 expect_config_arg_count(directive, 1)?;
                 config.enable_debug_command = Some(config_arg_string(directive, 0)?);
             }
-            _ => {}
+            b"rename-command" => {
+                expect_config_arg_count(directive, 2)?;
+                config
+                    .rename_commands
+                    .push((directive.args[0].clone(), directive.args[1].clone()));
+            }
+            b"include" => {
+                expect_config_arg_count(directive, 1)?;
+                if depth >= MAX_CONFIG_INCLUDE_DEPTH {
+                    return Err(config_directive_error(
+                        directive,
+                        &format!("include nesting deeper than {MAX_CONFIG_INCLUDE_DEPTH} files"),
+                    ));
+                }
+                let included = config_arg_string(directive, 0)?;
+                load_config_file_into(config, &included, depth + 1)?;
+            }
+            b"save" => {
+                // Upstream config.c: the first `save` line replaces the
+                // default save points, later lines append to them, and
+                // `save ""` clears whatever came before.
+                let points = directive.args.join(&b' ');
+                match config
+                    .registry
+                    .iter_mut()
+                    .find(|entry| entry.name == "save")
+                {
+                    Some(entry) => {
+                        if points.is_empty() || entry.value.is_empty() {
+                            entry.value = points;
+                        } else {
+                            entry.value.push(b' ');
+                            entry.value.extend_from_slice(&points);
+                        }
+                        entry.file = file.to_string();
+                        entry.line_number = directive.line_number;
+                    }
+                    None => config.registry.push(registry_directive(file, directive)),
+                }
+            }
+            _ => config.registry.push(registry_directive(file, directive)),
         }
     }
 
-    Ok(config)
+    Ok(())
+}
+
+fn registry_directive(
+    file: &str,
+    directive: &fr_config::ParsedConfigDirective,
+) -> RegistryDirective {
+    RegistryDirective {
+        file: file.to_string(),
+        line_number: directive.line_number,
+        name: String::from_utf8_lossy(&directive.name).into_owned(),
+        value: directive.args.join(&b' '),
+    }
+}
+
+/// Feed the file's remaining directives into the CONFIG GET registry. Every
+/// name is checked before anything is applied so an unknown directive fails
+/// startup without a half-applied config; with `ignore_unknown` those are
+/// skipped and returned as warnings instead.
+fn apply_config_registry(
+    runtime: &mut Runtime,
+    registry: &[RegistryDirective],
+    ignore_unknown: bool,
+) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    let mut known = Vec::with_capacity(registry.len());
+    for directive in registry {
+        if runtime.is_config_parameter(&directive.name) {
+            known.push(directive);
+            continue;
+        }
+        let message = format!(
+            "unknown config directive '{}' on {}",
+            directive.name,
+            directive.location()
+        );
+        if !ignore_unknown {
+            return Err(format!(
+                "{message} (use --ignore-unknown-config to skip unknown directives)"
+            ));
+        }
+        warnings.push(message);
+    }
+    for directive in known {
+        runtime
+            .apply_config_file_directive(&directive.name, &directive.value)
+            .map_err(|err| {
+                format!(
+                    "invalid config directive '{}' on {}: {err}",
+                    directive.name,
+                    directive.location()
+                )
+            })?;
+    }
+    Ok(warnings)
 }
 
 fn expect_config_arg_count(
@@ -935,8 +1328,20 @@ fn main() -> ExitCode {
     let mut cli_aof = false;
     let mut cli_rdb = false;
     let mut cli_enable_debug_command: Option<String> = None;
+    let mut cli_supervised: Option<String> = None;
     let mut sentinel_mode = false;
+    let mut deterministic = false;
+    let mut ignore_unknown_config = false;
+    let mut max_commands_per_tick: Option<usize> = None;
+    let mut max_exec_us_per_tick: Option<u64> = None;
     let mut i = 1;
+    // Like redis-server, a leading non-option argument is the config file.
+    if let Some(first) = args.get(1)
+        && !first.starts_with('-')
+    {
+        config_path = Some(first.clone());
+        i = 2;
+    }
     while i < args.len() {
         match args[i].as_str() {
             "--port" => {
@@ -980,6 +1385,9 @@ fn main() -> ExitCode {
             "--sentinel" => {
                 sentinel_mode = true;
             }
+            "--deterministic" => {
+                deterministic = true;
+            }
             "--aof" => {
                 cli_aof = true;
                 i += 1;
@@ -1006,6 +1414,9 @@ fn main() -> ExitCode {
                 }
                 config_path = Some(args[i].clone());
             }
+            "--ignore-unknown-config" => {
+                ignore_unknown_config = true;
+            }
             "--replicaof" => {
                 cli_replicaof = true;
                 i += 1;
@@ -1054,10 +1465,50 @@ fn main() -> ExitCode {
                 }
                 cli_enable_debug_command = Some(args[i].clone());
             }
+            "--supervised" => {
+                i += 1;
+                if i >= args.len() {
+                    eprintln!("error: --supervised requires a value (no, upstart, systemd, auto)");
+                    return ExitCode::from(1);
+                }
+                cli_supervised = Some(args[i].clone());
+            }
+            "--max-commands-per-tick" => {
+                i += 1;
+                if i >= args.len() {
+                    eprintln!("error: --max-commands-per-tick requires a value");
+                    return ExitCode::from(1);
+                }
+                max_commands_per_tick = match args[i].parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => {
+                        eprintln!("error: invalid --max-commands-per-tick value: {}", args[i]);
+                        return ExitCode::from(1);
+                    }
+                };
+            }
+            "--max-exec-us-per-tick" => {
+                i += 1;
+                if i >= args.len() {
+                    eprintln!("error: --max-exec-us-per-tick requires a value");
+                    return ExitCode::from(1);
+                }
+                max_exec_us_per_tick = match args[i].parse() {
+                    Ok(us) => Some(us),
+                    Err(_) => {
+                        eprintln!("error: invalid --max-exec-us-per-tick value: {}", args[i]);
+                        return ExitCode::from(1);
+                    }
+                };
+            }
             "--help" | "-h" => {
                 print!("{}", server_help_text());
                 return ExitCode::SUCCESS;
             }
+            "--version" | "-v" => {
+                print!("{}", server_version_text());
+                return ExitCode::SUCCESS;
+            }
             other => {
                 eprintln!("error: unknown argument: {other}");
                 eprintln!("Try 'frankenredis --help' for usage.");
@@ -1070,8 +1521,10 @@ fn main() -> ExitCode {
     let mut requirepass = None;
     let mut aclfile_path = None;
     let mut config_enable_debug_command: Option<String> = None;
+    let mut rename_commands = Vec::new();
+    let mut config_registry = Vec::new();
     if let Some(path) = &config_path {
-        let startup_config = match load_startup_config_file(path) {
+        let mut startup_config = match load_startup_config_file(path) {
             Ok(config) => config,
             Err(err) => {
                 eprintln!("error: {err}");
@@ -1079,6 +1532,8 @@ fn main() -> ExitCode {
             }
         };
         config_enable_debug_command = startup_config.enable_debug_command.clone();
+        rename_commands = std::mem::take(&mut startup_config.rename_commands);
+        config_registry = std::mem::take(&mut startup_config.registry);
         let config_rdb_path = startup_config.configured_rdb_path();
         let config_aof_path = startup_config.configured_aof_path();
         if !cli_bind_addr && let Some(config_bind_addr) = startup_config.bind_addr {
@@ -1112,16 +1567,35 @@ fn main() -> ExitCode {
     };
     let mut runtime = Runtime::new(policy);
     runtime.set_server_port(port);
+    runtime.set_server_git_sha1(fr_server::version().git_sha1);
     // (frankenredis-zyx9q) Let the runtime's CONFIG SET port handler test-bind
     // the new port and signal a live listener rebind.
     runtime.set_bind_addr(bind_addr.clone());
     runtime.set_sentinel_mode(sentinel_mode);
+    runtime.set_deterministic_iteration(deterministic);
+    if max_commands_per_tick.is_some() || max_exec_us_per_tick.is_some() {
+        runtime.set_client_tick_budget(
+            max_commands_per_tick.unwrap_or(runtime.server.client_tick_max_commands),
+            max_exec_us_per_tick.unwrap_or(runtime.server.client_tick_max_exec_us),
+        );
+    }
     if sentinel_mode {
         // (frankenredis-pkdgs) Announce our listening port in hello messages so
         // peer sentinels discover us at the right address.
         runtime.set_sentinel_announce_port(port);
     }
     runtime.set_config_file_path(config_path.map(std::path::PathBuf::from));
+    match apply_config_registry(&mut runtime, &config_registry, ignore_unknown_config) {
+        Ok(warnings) => {
+            for warning in warnings {
+                eprintln!("warning: {warning}; ignoring");
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::from(1);
+        }
+    }
     // CLI flag wins over config-file directive; both override the
     // runtime's "no" default which mirrors upstream Redis 7.2's
     // safe-by-default `enable-debug-command` behavior.
@@ -1132,6 +1606,29 @@ fn main() -> ExitCode {
     {
         runtime.set_enable_debug_command(value);
     }
+    // Like every other option, --supervised overrides the config file.
+    if let Some(mode) = &cli_supervised
+        && let Err(err) = runtime.apply_config_file_directive("supervised", mode.as_bytes())
+    {
+        eprintln!("error: --supervised: {err}");
+        return ExitCode::from(1);
+    }
+    let mut supervisor = match Supervisor::from_mode(runtime.supervised_mode()) {
+        Ok(supervisor) => supervisor,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::from(1);
+        }
+    };
+    for (original, replacement) in &rename_commands {
+        if let Err(err) = runtime.rename_command(original, replacement) {
+            eprintln!(
+                "error: rename-command {}: {err}",
+                String::from_utf8_lossy(original)
+            );
+            return ExitCode::from(1);
+        }
+    }
     if let Some(config_requirepass) = requirepass {
         runtime.set_requirepass(config_requirepass);
     }
@@ -1188,6 +1685,7 @@ fn main() -> ExitCode {
     if let Some(path) = &aof_path {
         let aof = std::path::PathBuf::from(path);
         runtime.set_aof_path(aof);
+        supervisor.status(&format!("Loading AOF {path}"));
         match runtime.load_aof(now_ms()) {
             Ok(0) => eprintln!("AOF: no existing file or empty (will create on first write)"),
             Ok(n) => eprintln!("AOF: replayed {n} records from {path}"),
@@ -1216,6 +1714,7 @@ fn main() -> ExitCode {
     if let Some(path) = &rdb_path {
         runtime.set_rdb_path(std::path::PathBuf::from(path));
         if aof_path.is_none() {
+            supervisor.status(&format!("Loading RDB {path}"));
             match runtime.load_rdb(now_ms()) {
                 Ok(0) => eprintln!("RDB: no existing file or empty (will create on SAVE/BGSAVE)"),
                 Ok(n) => eprintln!("RDB: loaded {n} entries from {path}"),
@@ -1242,6 +1741,17 @@ fn main() -> ExitCode {
             None
         }
     };
+    let command_pool = match writer_pool
+        .as_ref()
+        .map(|pool| CommandPool::new(Arc::clone(&pool.waker)))
+    {
+        Some(Ok(pool)) => Some(pool),
+        Some(Err(e)) => {
+            eprintln!("warn: command pool disabled: {e}");
+            None
+        }
+        None => None,
+    };
 
     // (frankenredis-jd75g) Bind one listener per configured address. Startup
     // binds the single configured bind address; CONFIG SET bind can later grow
@@ -1258,10 +1768,14 @@ fn main() -> ExitCode {
             }
         };
 
+    let version = fr_server::version();
     eprintln!(
-        "FrankenRedis v{} ready (mode={mode_str}, port={port})",
-        env!("CARGO_PKG_VERSION"),
+        "FrankenRedis v{} (git {}, redis {}) ready (mode={mode_str}, port={port})",
+        version.frankenredis, version.git_sha1, version.redis_compat,
     );
+    // Listeners are bound and any AOF/RDB load has finished: dependents of a
+    // Type=notify unit may start now.
+    supervisor.ready(&format!("Ready to accept connections tcp (port {port})"));
 
     let mut events = Events::with_capacity(1024);
     let mut clients: ClientMap = ClientMap::default();
@@ -1290,6 +1804,8 @@ fn main() -> ExitCode {
     // same cadence; idle detection latency stays well under the seconds-granularity
     // timeout, so behavior is unchanged.
     let mut last_idle_scan_ms: u64 = now_ms();
+    // Last wall-clock ms the maxmemory-clients eviction pass ran (also ~10 Hz).
+    let mut last_client_eviction_ms: u64 = now_ms();
     // (frankenredis-pkdgs) Last wall-clock ms a sentinel-mode INFO/PING probe of
     // the monitored masters ran. 0 = never, so the first tick probes immediately.
     let mut last_sentinel_probe_ms: u64 = 0;
@@ -1354,9 +1870,10 @@ fn main() -> ExitCode {
                         &mut next_handle,
                         &mut runtime,
                         writer_pool.is_some(),
+                        command_pool.as_ref(),
                     );
                 }
-                token if token == WRITER_WAKE_TOKEN => {
+                token if token == WORKER_WAKE_TOKEN => {
                     drain_writer_completions(
                         writer_pool.as_ref(),
                         &mut clients,
@@ -1365,6 +1882,16 @@ fn main() -> ExitCode {
                         &mut write_tokens,
                         &mut closing_tokens,
                     );
+                    drain_command_completions(CommandCompletionsContext {
+                        command_pool: command_pool.as_ref(),
+                        clients: &mut clients,
+                        runtime: &mut runtime,
+                        poll: &mut poll,
+                        write_tokens: &mut write_tokens,
+                        closing_tokens: &mut closing_tokens,
+                        deferred_tokens: &mut deferred_tokens,
+                        writer_pool: writer_pool.as_ref(),
+                    });
                 }
                 conn_handle => {
                     if event.is_readable() {
@@ -1587,9 +2114,12 @@ fn main() -> ExitCode {
             last_idle_scan_ms = ts;
             let timeout_ms = client_timeout_sec * 1000;
             for (&token, conn) in clients.iter_mut() {
-                if conn.closing || conn.blocked.is_some() || conn.replication_sent_offset.is_some()
+                if conn.closing
+                    || conn.blocked.is_some()
+                    || conn.command_in_flight
+                    || conn.replication_sent_offset.is_some()
                 {
-                    continue; // Skip closing, blocked, and replica clients.
+                    continue; // Skip closing, blocked, busy, and replica clients.
                 }
                 if runtime.is_pubsub_client(conn.session.client_id) {
                     continue;
@@ -1602,6 +2132,16 @@ fn main() -> ExitCode {
             }
         }
 
+        // sd_notify watchdog ping.
+        supervisor.cron(ts);
+
+        // Evict the largest clients once they outgrow maxmemory-clients, at the
+        // same clientsCron cadence; the victims land on pending_client_kills.
+        if ts.saturating_sub(last_client_eviction_ms) >= 100 {
+            last_client_eviction_ms = ts;
+            runtime.evict_clients_over_maxmemory_clients();
+        }
+
         // Process any CLIENT KILL requests from the runtime.
         let kills: Vec<u64> = std::mem::take(&mut runtime.server.pending_client_kills);
         for target_id in kills {
@@ -1650,6 +2190,7 @@ fn main() -> ExitCode {
 
         // Check for graceful shutdown request
         if runtime.server.shutdown_requested {
+            supervisor.stopping();
             if !runtime.server.shutdown_nosave {
                 // Attempt a final SAVE before exiting
                 let save_ts = std::time::SystemTime::now()
@@ -1744,6 +2285,7 @@ fn rebind_listeners(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn accept_connections(
     listener: &TcpListener,
     poll: &mut Poll,
@@ -1752,6 +2294,7 @@ fn accept_connections(
     next_handle: &mut usize,
     runtime: &mut Runtime,
     writer_handoff_enabled: bool,
+    command_pool: Option<&CommandPool>,
 ) {
     loop {
         // Check maxclients gate via fr-eventloop before accepting.
@@ -1787,14 +2330,14 @@ fn accept_connections(
 
         match listener.accept() {
             Ok((mut stream, peer_addr)) => {
-                if *next_handle < MAX_LISTENERS || Token(*next_handle) == WRITER_WAKE_TOKEN {
+                if *next_handle < MAX_LISTENERS || Token(*next_handle) == WORKER_WAKE_TOKEN {
                     *next_handle = MAX_LISTENERS;
                 }
                 let conn_handle = Token(*next_handle);
                 *next_handle = next_handle.wrapping_add(1);
                 // Avoid colliding with the reserved listener token range
                 // (0..MAX_LISTENERS). (frankenredis-jd75g)
-                if *next_handle < MAX_LISTENERS || Token(*next_handle) == WRITER_WAKE_TOKEN {
+                if *next_handle < MAX_LISTENERS || Token(*next_handle) == WORKER_WAKE_TOKEN {
                     *next_handle = MAX_LISTENERS;
                 }
 
@@ -1833,8 +2376,13 @@ fn accept_connections(
                     session.socket_fd = Some(stream.as_raw_fd());
                 }
                 let client_id = session.client_id;
-                let conn =
-                    ClientConnection::new_with_writer(stream, writer_stream, session, now_ms());
+                let conn = ClientConnection::new_with_writer(
+                    stream,
+                    writer_stream,
+                    command_pool.map(|pool| pool.jobs.clone()),
+                    session,
+                    now_ms(),
+                );
                 runtime.record_client_session(&conn.session);
                 clients.insert(conn_handle, conn);
                 client_id_to_token.insert(client_id, conn_handle);
@@ -1883,9 +2431,9 @@ fn release_expired_client_pause(
     }
     let tokens = std::mem::take(paused_tokens);
     for token in tokens {
-        let still_pending = clients
-            .get(&token)
-            .is_some_and(|c| !c.read_buf.is_empty() && !c.closing && c.blocked.is_none());
+        let still_pending = clients.get(&token).is_some_and(|c| {
+            !c.read_buf.is_empty() && !c.closing && c.blocked.is_none() && !c.command_in_flight
+        });
         if still_pending {
             handle_readable(
                 token,
@@ -1984,6 +2532,21 @@ fn parse_complete_bulk_at(
     Some((input.get(payload_start..payload_end)?, payload_end + 2))
 }
 
+/// True when the multibulk packet's command name is affected by
+/// `rename-command`, so none of the literal-name fast paths may take it. Free
+/// when no rename is configured; an incomplete packet reads as not renamed and
+/// is left to the parsers, which wait for the rest of it anyway.
+fn multibulk_command_is_renamed(input: &[u8], config: &ParserConfig, runtime: &Runtime) -> bool {
+    if !runtime.has_command_renames() {
+        return false;
+    }
+    let Some((_, cursor)) = parse_array_header_at(input, 0, config.max_array_len) else {
+        return false;
+    };
+    parse_complete_bulk_at(input, cursor, config.max_bulk_len)
+        .is_some_and(|(name, _)| runtime.command_name_is_renamed(name))
+}
+
 fn parse_large_plain_set_read_start(
     input: &[u8],
     config: &ParserConfig,
@@ -2388,11 +2951,12 @@ fn handle_readable(
         runtime.note_read_event();
     }
 
-    // If the client is blocked (BLPOP/BRPOP/etc.), don't process new
-    // commands. We still read data above (to detect disconnection and
-    // prevent kernel buffer overflow), but commands are held in read_buf
-    // until the blocking operation completes or times out.
-    if conn.blocked.is_some() {
+    // If the client is blocked (BLPOP/BRPOP/etc.) or has a command on the
+    // command pool, don't process new commands. We still read data above (to
+    // detect disconnection and prevent kernel buffer overflow), but commands
+    // are held in read_buf until the blocking operation completes or times
+    // out, or the pooled command's reply is queued.
+    if conn.blocked.is_some() || conn.command_in_flight {
         return;
     }
 
@@ -2479,6 +3043,7 @@ fn record_deferred_buffered_token(
         && (!conn.read_buf.is_empty() || !conn.owned_plain_sets.is_empty())
         && !conn.closing
         && conn.blocked.is_none()
+        && !conn.command_in_flight
     {
         deferred_tokens.insert(token);
     } else {
@@ -2513,6 +3078,15 @@ fn process_buffered_frames(
     let mut consumed_total = 0;
     let mut processed_frames = 0usize;
     let mut budget_exhausted = false;
+    // Per-connection fairness budget: once this client has run its share of
+    // commands (or of wall time, when configured) the rest of its pipeline
+    // stays buffered and is resumed by process_deferred_buffered_clients on a
+    // later pass, after other ready connections have been served.
+    let max_frames = runtime.server.client_tick_max_commands;
+    let exec_deadline = (runtime.server.client_tick_max_exec_us > 0).then(|| {
+        std::time::Instant::now()
+            + std::time::Duration::from_micros(runtime.server.client_tick_max_exec_us)
+    });
     let mut argv_scratch: Vec<Vec<u8>> = Vec::new();
     let mut plain_get_read_gate_cache: Option<bool> = None;
     let mut plain_write_gate_cache: Option<bool> = None;
@@ -2538,7 +3112,14 @@ fn process_buffered_frames(
             break;
         }
 
-        if processed_frames >= MAX_FRAMES_PER_CLIENT_TICK {
+        if processed_frames >= max_frames {
+            budget_exhausted = true;
+            break;
+        }
+
+        if processed_frames > 0
+            && exec_deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline)
+        {
             budget_exhausted = true;
             break;
         }
@@ -2575,7 +3156,14 @@ fn process_buffered_frames(
             plain_get_read_gate_cache = None;
             output_hard_limit_cache = None;
             plain_write_gate_cache = None;
-            match runtime.execute_plain_set_owned_or_return(cmd.key, cmd.value, ts) {
+            // A renamed SET goes back through the generic path below, which
+            // resolves the name.
+            let owned = if runtime.command_name_is_renamed(b"SET") {
+                Err((cmd.key, cmd.value))
+            } else {
+                runtime.execute_plain_set_owned_or_return(cmd.key, cmd.value, ts)
+            };
+            match owned {
                 Ok(response) => {
                     let client_resp3 = runtime.client_session().resp_protocol_version() == 3;
                     if !runtime.suppress_current_network_reply() {
@@ -2640,7 +3228,9 @@ fn process_buffered_frames(
         // can misclassify protocol frames and break parsing.
         if should_try_inline_parsing(first_byte) {
             let unparsed = &conn.read_buf[consumed_total..];
-            if let Some(consumed) = inline_plain_ping_noarg_consumed(unparsed) {
+            if !runtime.command_name_is_renamed(b"PING")
+                && let Some(consumed) = inline_plain_ping_noarg_consumed(unparsed)
+            {
                 let client_resp3 = runtime.client_session().resp_protocol_version() == 3;
                 if runtime
                     .execute_plain_ping_borrowed_into(None, ts, client_resp3, &mut conn.write_buf)
@@ -2729,7 +3319,31 @@ fn process_buffered_frames(
             let borrowed_parse_result = {
                 let unparsed = &conn.read_buf[consumed_total..];
                 let parser_config = runtime.parser_config();
-                if let Some(action) = try_dispatch_floor_classified_action(
+                if multibulk_command_is_renamed(unparsed, &parser_config, runtime) {
+                    parse_borrowed_multibulk_action(
+                        unparsed,
+                        parser_config,
+                        runtime,
+                        ts,
+                        &mut conn.write_buf,
+                        &mut argv_scratch,
+                    )
+                } else if let Some(command_jobs) = conn.command_jobs.as_ref()
+                    && let Some(packet) =
+                        parse_borrowed_bitcount_any_packet(unparsed, &parser_config)
+                    && let Some(action) = pooled_bitcount_action(
+                        token,
+                        command_jobs,
+                        &mut conn.command_in_flight,
+                        runtime,
+                        packet,
+                        ts,
+                    )
+                {
+                    // Ahead of the dispatch floor, which runs `BITCOUNT key`
+                    // inline, so every borrowed shape can reach the pool.
+                    Ok(action)
+                } else if let Some(action) = try_dispatch_floor_classified_action(
                     unparsed,
                     parser_config,
                     runtime,
@@ -11224,6 +11838,10 @@ fn process_buffered_frames(
                     }
                     continue;
                 }
+                Ok(BorrowedMultibulkAction::Offloaded { consumed }) => {
+                    consumed_total += consumed;
+                    break;
+                }
                 Ok(BorrowedMultibulkAction::FastOkReply { consumed }) => {
                     // Identical to FastReply, but the reply is the constant `+OK\r\n`
                     // (a `SimpleString("OK")` encodes to the same bytes under RESP2 and
@@ -11232,7 +11850,7 @@ fn process_buffered_frames(
                     plain_get_read_gate_cache = None;
                     output_hard_limit_cache = None;
                     if !runtime.suppress_current_network_reply() {
-                        conn.write_buf.extend_from_slice(b"+OK\r\n");
+                        conn.write_buf.extend_from_slice(fr_protocol::REPLY_OK);
                     }
                     drain_pending_pubsub_to_connection(runtime, conn);
                     consumed_total += consumed;
@@ -11409,10 +12027,16 @@ fn parse_borrowed_multibulk_action(
         Ok(parsed) => {
             let argv_len = borrowed_args.len();
             if matches!(parsed.kind, BorrowedCommandArgsKind::Arguments) && argv_len > 0 {
-                let cmd0 = borrowed_args[0]
-                    .first()
-                    .map(u8::to_ascii_uppercase)
-                    .unwrap_or(0);
+                // A `rename-command` name matches no arm below (`0`), so it
+                // skips the literal-name fast paths and dispatches generically.
+                let cmd0 = if runtime.command_name_is_renamed(borrowed_args[0]) {
+                    0
+                } else {
+                    borrowed_args[0]
+                        .first()
+                        .map(u8::to_ascii_uppercase)
+                        .unwrap_or(0)
+                };
                 match cmd0 {
                     b'A' => {
                         if let Some((key, value)) = borrowed_plain_append_args(&borrowed_args)
@@ -11740,6 +12364,24 @@ fn parse_borrowed_multibulk_action(
                             });
                         }
                     }
+                    b'K' => {
+                        if let Some(pattern) = borrowed_plain_keys_args(&borrowed_args) {
+                            if runtime
+                                .execute_plain_keys_borrowed_into(pattern, ts, out)
+                                .is_some()
+                            {
+                                return Ok(BorrowedMultibulkAction::FastEncodedReply {
+                                    consumed: parsed.consumed,
+                                });
+                            }
+                            copy_borrowed_argv_into_scratch(&borrowed_args, argv_scratch);
+                            return Ok(BorrowedMultibulkAction::Parsed {
+                                kind: parsed.kind,
+                                consumed: parsed.consumed,
+                                argv_len,
+                            });
+                        }
+                    }
                     b'L' => {
                         if let Some((key, start, stop)) = borrowed_plain_lrange_args(&borrowed_args)
                         {
@@ -12284,6 +12926,11 @@ enum BorrowedMultibulkAction {
     FastOkReply {
         consumed: usize,
     },
+    /// The command went to the command pool; its reply is queued when the
+    /// worker finishes, and nothing after it may run before then.
+    Offloaded {
+        consumed: usize,
+    },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -14059,6 +14706,88 @@ fn parse_borrowed_plain_incr_packet<'a>(
     Some(BorrowedPlainIncrPacket { consumed, key })
 }
 
+/// `start end [BYTE|BIT]`, absent for the argc-2 form.
+type BorrowedBitcountRange<'a> = Option<(&'a [u8], &'a [u8], Option<&'a [u8]>)>;
+
+/// Any of the three borrowed `BITCOUNT` shapes (argc 2/4/5).
+struct BorrowedBitcountPacket<'a> {
+    consumed: usize,
+    key: &'a [u8],
+    range: BorrowedBitcountRange<'a>,
+}
+
+fn parse_borrowed_bitcount_any_packet<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Option<BorrowedBitcountPacket<'a>> {
+    if let Some(packet) = parse_borrowed_plain_bitcount_packet(input, config) {
+        return Some(BorrowedBitcountPacket {
+            consumed: packet.consumed,
+            key: packet.key,
+            range: None,
+        });
+    }
+    if let Some(packet) = parse_borrowed_plain_bitcount_range_packet(input, config) {
+        return Some(BorrowedBitcountPacket {
+            consumed: packet.consumed,
+            key: packet.key,
+            range: Some((packet.start, packet.end, None)),
+        });
+    }
+    let packet = parse_borrowed_plain_bitcount_unit_packet(input, config)?;
+    Some(BorrowedBitcountPacket {
+        consumed: packet.consumed,
+        key: packet.key,
+        range: Some((packet.start, packet.end, Some(packet.unit))),
+    })
+}
+
+/// A borrowed `BITCOUNT` on a client with a command pool. A key holding at
+/// least [`COMMAND_POOL_MIN_BITCOUNT_BYTES`] is counted on a worker and the
+/// client stops dispatching until `drain_command_completions` queues the
+/// reply; shorter values are answered inline. `None` declines to the generic
+/// path.
+fn pooled_bitcount_action(
+    token: Token,
+    command_jobs: &mpsc::Sender<CommandJob>,
+    command_in_flight: &mut bool,
+    runtime: &mut Runtime,
+    packet: BorrowedBitcountPacket<'_>,
+    ts: u64,
+) -> Option<BorrowedMultibulkAction> {
+    let consumed = packet.consumed;
+    let job = match runtime.start_plain_bitcount_borrowed(
+        packet.key,
+        packet.range,
+        Some(COMMAND_POOL_MIN_BITCOUNT_BYTES),
+        ts,
+    )? {
+        PlainBitcountStep::Reply(response) => {
+            return Some(BorrowedMultibulkAction::FastReply { consumed, response });
+        }
+        PlainBitcountStep::Offload(job) => job,
+    };
+    let client_id = runtime.client_session().client_id;
+    match command_jobs.send(CommandJob {
+        token,
+        client_id,
+        job,
+    }) {
+        Ok(()) => {
+            *command_in_flight = true;
+            Some(BorrowedMultibulkAction::Offloaded { consumed })
+        }
+        // The workers only exit at shutdown; count here rather than drop the reply.
+        Err(mpsc::SendError(CommandJob { job, .. })) => {
+            let started = std::time::Instant::now();
+            let count = job.run();
+            let elapsed_us = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+            let response = runtime.finish_plain_bitcount_job(job, count, elapsed_us, ts);
+            Some(BorrowedMultibulkAction::FastReply { consumed, response })
+        }
+    }
+}
+
 struct BorrowedPlainBitcountPacket<'a> {
     consumed: usize,
     key: &'a [u8],
@@ -21897,6 +22626,13 @@ fn borrowed_plain_smembers_args<'a>(borrowed_args: &'a [&'a [u8]]) -> Option<&'a
     }
 }
 
+fn borrowed_plain_keys_args<'a>(borrowed_args: &'a [&'a [u8]]) -> Option<&'a [u8]> {
+    match borrowed_args {
+        [command, pattern] if command.eq_ignore_ascii_case(b"KEYS") => Some(*pattern),
+        _ => None,
+    }
+}
+
 fn borrowed_plain_lrange_args<'a>(
     borrowed_args: &'a [&'a [u8]],
 ) -> Option<(&'a [u8], &'a [u8], &'a [u8])> {
@@ -22587,6 +23323,12 @@ fn process_argv_frame(
     ts: u64,
     ts_us: u64,
 ) -> ProcessArgvAction {
+    // `rename-command`: re-spell a replacement name as its canonical command up
+    // front so every name-based check below (subscription gate, blocking,
+    // replication follow-ups) sees the real command. Hidden original names
+    // stay as sent and are rejected as unknown by the runtime.
+    let renamed_argv = runtime.resolve_renamed_command(argv);
+    let argv = renamed_argv.as_deref().unwrap_or(argv);
     // Subscription mode gate: reject most commands while subscribed.
     // (frankenredis-j7nwu) Only RESP2 subscribers are restricted —
     // upstream server.c::processCommand gates the allow-list on
@@ -22652,7 +23394,11 @@ fn process_argv_frame(
     // The owned fallback still moves argv out of the parsed frame; the hot
     // multibulk path reuses a per-pass scratch arena. (frankenredis-8yfmt,
     // frankenredis-08d0x)
-    let response = runtime.execute_argv_with_unix_time_us(argv, ts, ts_us);
+    let response = if renamed_argv.is_some() {
+        runtime.execute_resolved_argv_with_unix_time_us(argv, ts, ts_us)
+    } else {
+        runtime.execute_argv_with_unix_time_us(argv, ts, ts_us)
+    };
     // (frankenredis-pgplm) Choose the RESP3 null encoding (`_`)
     // when the client negotiated HELLO 3. Captured before the
     // block-detection check below, which still compares the
@@ -22669,7 +23415,10 @@ fn process_argv_frame(
     }
 
     // Check for blocking commands that returned nil — block the
-    // client instead of sending the nil response immediately.
+    // client instead of sending the nil response immediately. Only the
+    // top-level argv can block: a blocking command inside EXEC or
+    // redis.call already ran with CLIENT_DENY_BLOCKING, and its nil is
+    // part of the EXEC/EVAL reply.
     let should_block = matches!(
         response,
         RespFrame::Array(None) | RespFrame::BulkString(None)
@@ -24299,7 +25048,11 @@ fn process_deferred_buffered_clients(ctx: DeferredBufferedClientsContext<'_>) {
             continue;
         };
 
-        if conn.read_buf.is_empty() || conn.closing || conn.blocked.is_some() {
+        if conn.read_buf.is_empty()
+            || conn.closing
+            || conn.blocked.is_some()
+            || conn.command_in_flight
+        {
             deferred_tokens.remove(&token);
             continue;
         }
@@ -24966,6 +25719,83 @@ fn arm_main_writable(
     }
 }
 
+struct CommandCompletionsContext<'a> {
+    command_pool: Option<&'a CommandPool>,
+    clients: &'a mut ClientMap,
+    runtime: &'a mut Runtime,
+    poll: &'a mut Poll,
+    write_tokens: &'a mut TokenSet,
+    closing_tokens: &'a mut TokenSet,
+    deferred_tokens: &'a mut TokenSet,
+    writer_pool: Option<&'a WriterPool>,
+}
+
+/// Queue the replies of finished command-pool jobs. Each command is completed
+/// on its own client's session, as `handle_readable` would have, and a client
+/// with more pipelined input is handed to `process_deferred_buffered_clients`.
+/// A job whose client disconnected meanwhile is dropped.
+fn drain_command_completions(ctx: CommandCompletionsContext<'_>) {
+    let CommandCompletionsContext {
+        command_pool,
+        clients,
+        runtime,
+        poll,
+        write_tokens,
+        closing_tokens,
+        deferred_tokens,
+        writer_pool,
+    } = ctx;
+    let Some(pool) = command_pool else {
+        return;
+    };
+
+    while let Ok(completion) = pool.try_recv() {
+        let token = completion.token;
+        let Some(conn) = clients.get_mut(&token) else {
+            continue;
+        };
+        if conn.session.client_id != completion.client_id || !conn.command_in_flight {
+            continue;
+        }
+        conn.command_in_flight = false;
+
+        let session = std::mem::take(&mut conn.session);
+        let prev = runtime.swap_session(session);
+        let response = runtime.finish_plain_bitcount_job(
+            completion.job,
+            completion.count,
+            completion.elapsed_us,
+            now_ms(),
+        );
+        let write_buf_before = conn.write_buf.len();
+        let client_resp3 = runtime.client_session().resp_protocol_version() == 3;
+        if !runtime.suppress_current_network_reply() {
+            encode_client_reply(&response, client_resp3, &mut conn.write_buf);
+        }
+        drain_pending_pubsub_to_connection(runtime, conn);
+        let output_delta = conn.write_buf.len().saturating_sub(write_buf_before);
+        runtime.track_net_output_bytes(output_delta as u64);
+        conn.session = runtime.swap_session(prev);
+        runtime.record_client_session(&conn.session);
+
+        if !conn.read_buf.is_empty() && !conn.closing {
+            deferred_tokens.insert(token);
+        }
+        drive_client_output(
+            token,
+            conn,
+            OutputDriveContext {
+                runtime,
+                poll,
+                write_tokens,
+                closing_tokens,
+                writer_pool,
+            },
+            true,
+        );
+    }
+}
+
 fn drain_writer_completions(
     writer_pool: Option<&WriterPool>,
     clients: &mut ClientMap,
@@ -25075,18 +25905,18 @@ fn handle_writable(
 mod tests {
     use crate::{
         BlockingOp, CheckBlockedClientsContext, InlineParseResult, PendingClientUnblocksContext,
-        REPLICA_ACK_INTERVAL_MS, REPLICA_RECONNECT_BACKOFF_MS, ReplicaPrimaryConnection,
-        ReplicaSyncState, StartupConfig, apply_pending_client_unblocks, check_blocked_clients,
-        check_subscription_mode_gate, command_frame_can_move_to_argv,
-        consume_complete_replication_prefix, drain_replica_stream, drive_replica_sync,
-        encode_eof_marked_replication_snapshot, encode_replication_snapshot, find_crlf,
-        frame_matches_suppressed_replication_reply, is_quit_frame, parse_blocking_deadline,
-        parse_xread_block_deadline_argv, process_buffered_frames, read_frame_from_stream,
-        read_replication_snapshot_from_stream, replica_handshake_frame,
-        replica_handshake_read_timeout, replication_follow_up_bytes, resolve_xread_block_argv,
-        server_help_text, should_try_inline_parsing, startup_config_from_directives,
-        sync_replica_with_primary, try_build_blocked_state, try_fulfill_blocked, wait_should_block,
-        waitaof_should_block,
+        REPLICA_ACK_INTERVAL_MS, REPLICA_RECONNECT_BACKOFF_MS, RegistryDirective,
+        ReplicaPrimaryConnection, ReplicaSyncState, StartupConfig, apply_config_registry,
+        apply_pending_client_unblocks, check_blocked_clients, check_subscription_mode_gate,
+        command_frame_can_move_to_argv, consume_complete_replication_prefix, drain_replica_stream,
+        drive_replica_sync, encode_eof_marked_replication_snapshot, encode_replication_snapshot,
+        find_crlf, frame_matches_suppressed_replication_reply, is_quit_frame,
+        load_startup_config_file, parse_blocking_deadline, parse_xread_block_deadline_argv,
+        process_buffered_frames, read_frame_from_stream, read_replication_snapshot_from_stream,
+        replica_handshake_frame, replica_handshake_read_timeout, replication_follow_up_bytes,
+        resolve_xread_block_argv, server_help_text, should_try_inline_parsing,
+        startup_config_from_directives, sync_replica_with_primary, try_build_blocked_state,
+        try_fulfill_blocked, wait_should_block, waitaof_should_block,
     };
     use fr_config::RuntimePolicy;
     use fr_protocol::{ParserConfig, RespFrame};
@@ -25793,6 +26623,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn renamed_multibulk_commands_skip_the_literal_name_fast_paths() {
+        let cfg = ParserConfig::default();
+        let get = b"*2\r\n$3\r\nget\r\n$1\r\nk\r\n";
+        let mut runtime = Runtime::default_strict();
+        assert!(!crate::multibulk_command_is_renamed(get, &cfg, &runtime));
+
+        runtime
+            .rename_command(b"GET", b"fetch")
+            .expect("rename GET");
+        assert!(crate::multibulk_command_is_renamed(get, &cfg, &runtime));
+        assert!(crate::multibulk_command_is_renamed(
+            b"*2\r\n$5\r\nFETCH\r\n$1\r\nk\r\n",
+            &cfg,
+            &runtime
+        ));
+        assert!(!crate::multibulk_command_is_renamed(
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+            &cfg,
+            &runtime
+        ));
+        assert!(
+            !crate::multibulk_command_is_renamed(b"*2\r\n$3\r\nge", &cfg, &runtime),
+            "a partial name is left to the parsers"
+        );
+    }
+
     #[test]
     fn borrowed_plain_hset_packet_parser_accepts_canonical_single_field_hset() {
         let input =
@@ -29507,6 +30364,13 @@ mod tests {
                 appendfilename: Some("startup.aof".to_string()),
                 aclfile: Some("/tmp/frankenredis-startup/users.acl".to_string()),
                 enable_debug_command: None,
+                rename_commands: Vec::new(),
+                registry: vec![RegistryDirective {
+                    file: String::new(),
+                    line_number: 13,
+                    name: "timeout".to_string(),
+                    value: b"30".to_vec(),
+                }],
             }
         );
         assert_eq!(
@@ -29519,6 +30383,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn startup_config_from_directives_collects_rename_commands_in_order() {
+        let parsed = fr_config::parse_redis_config(
+            "rename-command CONFIG cfg-9f2a\nrename-command FLUSHALL \"\"\n",
+        )
+        .expect("parse rename-command config");
+
+        let config = startup_config_from_directives(&parsed.directives)
+            .expect("extract startup config subset");
+
+        assert_eq!(
+            config.rename_commands,
+            vec![
+                (b"CONFIG".to_vec(), b"cfg-9f2a".to_vec()),
+                (b"FLUSHALL".to_vec(), Vec::new()),
+            ]
+        );
+
+        let parsed = fr_config::parse_redis_config("rename-command CONFIG\n")
+            .expect("parse short rename-command");
+        assert!(startup_config_from_directives(&parsed.directives).is_err());
+    }
+
     #[test]
     fn startup_config_from_directives_accepts_slaveof_no_one_alias() {
         let parsed =
@@ -29530,6 +30417,174 @@ mod tests {
         assert_eq!(config.replicaof, Some(None));
     }
 
+    fn config_get(runtime: &mut Runtime, name: &[u8]) -> RespFrame {
+        runtime.execute_frame(
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"CONFIG".to_vec())),
+                RespFrame::BulkString(Some(b"GET".to_vec())),
+                RespFrame::BulkString(Some(name.to_vec())),
+            ])),
+            0,
+        )
+    }
+
+    fn config_test_dir(prefix: &str) -> std::path::PathBuf {
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("unix epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("{prefix}_{}_{nonce}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create config test dir");
+        dir
+    }
+
+    #[test]
+    fn representative_redis_conf_fixture_lands_in_config_registry() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/redis.conf");
+        let config = load_startup_config_file(path).expect("load fixture");
+        assert_eq!(config.bind_addr.as_deref(), Some("127.0.0.1"));
+        assert_eq!(config.port, Some(6390));
+        assert_eq!(
+            config.requirepass,
+            Some(Some(b"correct horse battery".to_vec()))
+        );
+        assert_eq!(config.dbfilename.as_deref(), Some("fixture dump.rdb"));
+        assert_eq!(config.appendonly, Some(false));
+
+        let mut runtime = Runtime::new(RuntimePolicy::hardened());
+        let warnings = apply_config_registry(&mut runtime, &config.registry, false)
+            .expect("every fixture directive is known");
+        assert!(warnings.is_empty());
+
+        for (name, value) in [
+            (&b"timeout"[..], &b"300"[..]),
+            (b"tcp-keepalive", b"60"),
+            (b"daemonize", b"no"),
+            (b"loglevel", b"warning"),
+            (b"save", b"900 1 300 10 60 10000"),
+            (b"maxclients", b"5000"),
+            (b"maxmemory", b"104857600"),
+            (b"maxmemory-policy", b"allkeys-lru"),
+            (b"maxmemory-samples", b"7"),
+            (b"appendfsync", b"no"),
+            (b"slowlog-log-slower-than", b"5000"),
+            (b"slowlog-max-len", b"256"),
+            (b"notify-keyspace-events", b"xE"),
+            (b"zset-max-listpack-entries", b"64"),
+            (
+                b"client-output-buffer-limit",
+                b"normal 0 0 0 slave 268435456 67108864 60 pubsub 33554432 8388608 60",
+            ),
+            (b"hz", b"20"),
+        ] {
+            assert_eq!(
+                config_get(&mut runtime, name),
+                RespFrame::Array(Some(vec![
+                    RespFrame::BulkString(Some(name.to_vec())),
+                    RespFrame::BulkString(Some(value.to_vec())),
+                ])),
+                "CONFIG GET {}",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+
+    #[test]
+    fn config_file_include_and_save_reset_follow_redis_order() {
+        let dir = config_test_dir("fr_server_config_include");
+        let included = dir.join("included.conf");
+        let main = dir.join("redis.conf");
+        std::fs::write(&included, "save 60 100\nhz 15\n").expect("write included");
+        std::fs::write(
+            &main,
+            format!(
+                "save 900 1\ninclude \"{}\"\nsave \"\"\nsave 30 5\ntimeout 7\n",
+                included.display()
+            ),
+        )
+        .expect("write main");
+
+        let config =
+            load_startup_config_file(main.to_str().expect("utf8 path")).expect("load with include");
+        let mut runtime = Runtime::new(RuntimePolicy::hardened());
+        apply_config_registry(&mut runtime, &config.registry, false).expect("apply");
+        let value = |runtime: &mut Runtime, name: &[u8]| match config_get(runtime, name) {
+            RespFrame::Array(Some(mut entries)) if entries.len() == 2 => entries.remove(1),
+            other => panic!("unexpected CONFIG GET reply: {other:?}"), // ubs:ignore — test assertion
+        };
+        // `save ""` dropped both earlier points, including the included one.
+        assert_eq!(
+            value(&mut runtime, b"save"),
+            RespFrame::BulkString(Some(b"30 5".to_vec()))
+        );
+        assert_eq!(
+            value(&mut runtime, b"hz"),
+            RespFrame::BulkString(Some(b"15".to_vec()))
+        );
+        assert_eq!(
+            value(&mut runtime, b"timeout"),
+            RespFrame::BulkString(Some(b"7".to_vec()))
+        );
+
+        // A file that includes itself stops at the nesting limit.
+        std::fs::write(&main, format!("include \"{}\"\n", main.display())).expect("write loop");
+        let err = load_startup_config_file(main.to_str().expect("utf8 path"))
+            .expect_err("self-include must fail");
+        assert!(err.contains("include nesting deeper than"), "{err}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unknown_config_directive_reports_line_unless_ignored() {
+        let parsed = fr_config::parse_redis_config("timeout 5\n# comment\nno-such-knob yes\n")
+            .expect("parse config");
+        let config = startup_config_from_directives(&parsed.directives).expect("extract");
+
+        let mut runtime = Runtime::new(RuntimePolicy::hardened());
+        let err = apply_config_registry(&mut runtime, &config.registry, false)
+            .expect_err("unknown directive must fail startup");
+        assert_eq!(
+            err,
+            "unknown config directive 'no-such-knob' on line 3 (use --ignore-unknown-config to skip unknown directives)"
+        );
+        // Nothing was applied before the unknown name was found.
+        assert_eq!(
+            config_get(&mut runtime, b"timeout"),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"timeout".to_vec())),
+                RespFrame::BulkString(Some(b"0".to_vec())),
+            ]))
+        );
+
+        let warnings = apply_config_registry(&mut runtime, &config.registry, true)
+            .expect("ignored unknown directive");
+        assert_eq!(
+            warnings,
+            vec!["unknown config directive 'no-such-knob' on line 3".to_string()]
+        );
+        assert_eq!(
+            config_get(&mut runtime, b"timeout"),
+            RespFrame::Array(Some(vec![
+                RespFrame::BulkString(Some(b"timeout".to_vec())),
+                RespFrame::BulkString(Some(b"5".to_vec())),
+            ]))
+        );
+
+        let parsed = fr_config::parse_redis_config("hz abc\n").expect("parse config");
+        let config = startup_config_from_directives(&parsed.directives).expect("extract");
+        assert_eq!(
+            apply_config_registry(&mut runtime, &config.registry, false),
+            Err("invalid config directive 'hz' on line 1: CONFIG SET failed (possibly related to argument 'hz') - argument couldn't be parsed into an integer".to_string())
+        );
+
+        let parsed = fr_config::parse_redis_config("databases 0\n").expect("parse config");
+        let config = startup_config_from_directives(&parsed.directives).expect("extract");
+        assert_eq!(
+            apply_config_registry(&mut runtime, &config.registry, false),
+            Err("invalid config directive 'databases' on line 1: argument must be between 1 and 2147483647 inclusive".to_string())
+        );
+    }
+
     #[test]
     fn replica_handshake_timeout_uses_runtime_repl_timeout() {
         let mut runtime = Runtime::new(RuntimePolicy::hardened());
@@ -29566,7 +30621,7 @@ mod tests {
     fn help_text_documents_all_supported_cli_flags() {
         let help = server_help_text();
 
-        assert!(help.contains("USAGE: frankenredis [OPTIONS]"));
+        assert!(help.contains("USAGE: frankenredis [/path/to/redis.conf] [OPTIONS]"));
         assert!(help.contains("--bind <ADDR>"));
         assert!(help.contains("--port <PORT>"));
         assert!(help.contains("--mode <MODE>"));
@@ -29576,7 +30631,17 @@ mod tests {
         assert!(help.contains("--replicaof <HOST> <PORT>"));
         assert!(help.contains("--masteruser <USERNAME>"));
         assert!(help.contains("--masterauth <PASSWORD>"));
+        assert!(help.contains("--ignore-unknown-config"));
+        assert!(help.contains("--deterministic"));
+        assert!(help.contains("--supervised <MODE>"));
+        assert!(help.contains("--max-commands-per-tick <N>"));
+        assert!(help.contains("--max-exec-us-per-tick <US>"));
+        assert!(help.contains("--version"));
         assert!(help.contains("--help"));
+        assert!(help.starts_with(&format!(
+            "frankenredis {} — ",
+            env!("CARGO_PKG_VERSION")
+        )));
     }
 
     #[test]
@@ -32627,6 +33692,7 @@ mod tests {
             &mut next_handle,
             &mut runtime,
             false,
+            None,
         );
 
         // The over-limit connection was rejected (not admitted) and got the reply.
@@ -32651,7 +33717,6 @@ mod tests {
     #[test]
     fn client_pause_releases_deferred_command_after_deadline() {
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
         use std::io::Read as _;
         use std::time::Duration;
 
@@ -32763,7 +33828,6 @@ mod tests {
     fn xread_blocked_client_unblocks_when_xadd_marks_stream_ready() {
         use crate::ClientConnection;
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -32888,7 +33952,6 @@ mod tests {
     fn xreadgroup_blocked_client_unblocks_when_xadd_marks_stream_ready() {
         use crate::ClientConnection;
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33103,7 +34166,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33118,7 +34180,7 @@ mod tests {
 
         let ping = RespFrame::Array(Some(vec![RespFrame::BulkString(Some(b"PING".to_vec()))]));
         let ping_bytes = ping.to_bytes();
-        for _ in 0..=crate::MAX_FRAMES_PER_CLIENT_TICK {
+        for _ in 0..=runtime.server.client_tick_max_commands {
             conn.read_buf.extend_from_slice(&ping_bytes);
         }
 
@@ -33148,7 +34210,7 @@ mod tests {
         assert_eq!(conn.read_buf, ping_bytes);
         assert_eq!(
             conn.write_buf.len(),
-            b"+PONG\r\n".len() * crate::MAX_FRAMES_PER_CLIENT_TICK
+            b"+PONG\r\n".len() * runtime.server.client_tick_max_commands
         );
         assert!(write_tokens.contains(&token));
         assert!(blocked_tokens.is_empty());
@@ -33163,7 +34225,6 @@ mod tests {
         use fr_protocol::RespFrame;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::{HashMap, HashSet};
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33261,7 +34322,6 @@ mod tests {
         use fr_protocol::RespFrame;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::{HashMap, HashSet};
         use std::net::{TcpListener, TcpStream};
 
         let mut primary = Runtime::default_strict();
@@ -33420,7 +34480,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33491,7 +34550,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33548,7 +34606,6 @@ mod tests {
         use fr_protocol::RespFrame;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         fn frame(parts: &[&[u8]]) -> RespFrame {
@@ -33616,7 +34673,6 @@ mod tests {
         use fr_protocol::RespFrame;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         fn frame(parts: &[&[u8]]) -> RespFrame {
@@ -33682,7 +34738,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33746,7 +34801,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33798,7 +34852,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33866,7 +34919,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33946,7 +34998,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -33991,7 +35042,6 @@ mod tests {
         use crate::ClientConnection;
         use fr_runtime::Runtime;
         use mio::Token;
-        use std::collections::HashSet;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -34034,7 +35084,7 @@ mod tests {
     fn client_unblock_error_mode_unblocks_blocked_connection() {
         use crate::{BlockedState, BlockingOp, ClientConnection};
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
+        use std::collections::HashMap;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -34228,7 +35278,7 @@ mod tests {
     fn client_unblock_tracks_paused_tokens_for_pipelined_commands() {
         use crate::{BlockedState, BlockingOp, ClientConnection};
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
+        use std::collections::HashMap;
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
@@ -34352,7 +35402,6 @@ mod tests {
     fn blocked_client_timeout_tracks_paused_tokens_for_pipelined_commands() {
         use crate::{BlockedState, BlockingOp, ClientConnection};
         use mio::{Poll, Token};
-        use std::collections::{HashMap, HashSet};
         use std::net::{TcpListener, TcpStream};
 
         let mut runtime = Runtime::default_strict();
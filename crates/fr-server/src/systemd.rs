@@ -0,0 +1,114 @@
+//! systemd `Type=notify` readiness protocol, without libsystemd.
+//!
+//! `sd_notify(3)` is one unix datagram per state change, holding
+//! newline-separated `KEY=VALUE` assignments, sent to the socket named by
+//! `$NOTIFY_SOCKET`. A leading `@` names a Linux abstract socket. Sends are
+//! best-effort: a supervisor that went away must not take the server with it.
+
+use std::io;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Environment variable systemd sets to the notification socket's address.
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// A connected notification socket.
+#[derive(Debug)]
+pub struct Notifier {
+    socket: UnixDatagram,
+}
+
+impl Notifier {
+    /// Connect to `$NOTIFY_SOCKET`; `Ok(None)` when it is unset or empty,
+    /// i.e. the process is not running under `Type=notify`.
+    pub fn from_env() -> io::Result<Option<Self>> {
+        match std::env::var_os(NOTIFY_SOCKET_ENV) {
+            Some(address) if !address.is_empty() => {
+                let address = address.to_str().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "NOTIFY_SOCKET is not UTF-8")
+                })?;
+                Self::connect(address).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Connect to a `$NOTIFY_SOCKET`-style address: a filesystem path, or
+    /// `@name` for an abstract socket.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let address = match address.strip_prefix('@') {
+            Some(name) => abstract_address(name)?,
+            None => SocketAddr::from_pathname(address)?,
+        };
+        let socket = UnixDatagram::unbound()?;
+        socket.connect_addr(&address)?;
+        Ok(Self { socket })
+    }
+
+    /// Send raw assignments, e.g. `"READY=1\nSTATUS=Ready"`.
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        self.socket.send(state.as_bytes()).map(drop)
+    }
+
+    /// `READY=1`: startup finished and clients are being accepted.
+    pub fn ready(&self, status: &str) -> io::Result<()> {
+        self.notify(&format!("READY=1\nSTATUS={status}"))
+    }
+
+    /// `STATUS=`: free-form progress shown by `systemctl status`.
+    pub fn status(&self, status: &str) -> io::Result<()> {
+        self.notify(&format!("STATUS={status}"))
+    }
+
+    /// `STOPPING=1`: graceful shutdown has begun.
+    pub fn stopping(&self) -> io::Result<()> {
+        self.notify("STOPPING=1")
+    }
+
+    /// `WATCHDOG=1`: keep-alive for `WatchdogSec=`.
+    pub fn watchdog(&self) -> io::Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn abstract_address(name: &str) -> io::Result<SocketAddr> {
+    use std::os::linux::net::SocketAddrExt;
+    SocketAddr::from_abstract_name(name)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn abstract_address(_name: &str) -> io::Result<SocketAddr> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "abstract NOTIFY_SOCKET addresses need Linux",
+    ))
+}
+
+/// How often to send `WATCHDOG=1`, from `$WATCHDOG_USEC` and
+/// `$WATCHDOG_PID`; `None` when the watchdog is off or meant for another
+/// process.
+#[must_use]
+pub fn watchdog_ping_interval() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok();
+    let pid = std::env::var("WATCHDOG_PID").ok();
+    watchdog_ping_interval_from(usec.as_deref(), pid.as_deref(), std::process::id())
+}
+
+/// [`watchdog_ping_interval`] over explicit values. Pings go out at half the
+/// timeout, as `sd_watchdog_enabled(3)` recommends, so one late tick does not
+/// trip the watchdog.
+#[must_use]
+pub fn watchdog_ping_interval_from(
+    usec: Option<&str>,
+    pid: Option<&str>,
+    own_pid: u32,
+) -> Option<Duration> {
+    let usec: u64 = usec?.parse().ok().filter(|&usec| usec > 0)?;
+    if let Some(pid) = pid
+        && pid.parse::<u32>().ok() != Some(own_pid)
+    {
+        return None;
+    }
+    Some(Duration::from_micros(usec / 2))
+}
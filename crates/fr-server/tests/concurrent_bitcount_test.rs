@@ -0,0 +1,206 @@
+//! Long `BITCOUNT`s run on the command pool. Two clients counting different
+//! large strings overlap in wall-clock time instead of queueing behind each
+//! other on the event loop, other clients are answered meanwhile, and each
+//! client still sees its own replies in order.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fr_protocol::RespFrame;
+
+fn encode_command(parts: &[&[u8]]) -> Vec<u8> {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some(part.to_vec())))
+            .collect(),
+    ))
+    .to_bytes()
+}
+
+fn reserve_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .expect("bind ephemeral port")
+        .local_addr()
+        .expect("local addr")
+        .port()
+}
+
+fn wait_for_port(port: u16) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if TcpStream::connect(format!("127.0.0.1:{port}")).is_ok() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("port {port} did not become ready in time");
+}
+
+struct ManagedChild {
+    child: Child,
+}
+
+impl Drop for ManagedChild {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn spawn_frankenredis(port: u16) -> ManagedChild {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_frankenredis"));
+    command
+        .arg("--bind")
+        .arg("127.0.0.1")
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--mode")
+        .arg("strict")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let child = ManagedChild {
+        child: command.spawn().expect("spawn frankenredis"),
+    };
+    wait_for_port(port);
+    child
+}
+
+fn connect(port: u16) -> TcpStream {
+    let stream = TcpStream::connect(format!("127.0.0.1:{port}")).expect("connect");
+    stream.set_nodelay(true).expect("nodelay");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(60)))
+        .expect("read timeout");
+    stream
+}
+
+/// Read one single-line reply (`+OK`, `:42`, `-ERR ...`).
+fn read_line(stream: &mut TcpStream) -> Vec<u8> {
+    let mut line = Vec::new();
+    let mut byte = [0_u8; 1];
+    while !line.ends_with(b"\r\n") {
+        stream.read_exact(&mut byte).expect("read reply");
+        line.push(byte[0]);
+    }
+    line
+}
+
+fn call(stream: &mut TcpStream, parts: &[&[u8]]) -> Vec<u8> {
+    stream.write_all(&encode_command(parts)).expect("send");
+    read_line(stream)
+}
+
+/// A string of `len` zero bytes ending in one `0xff`: BITCOUNT is 8.
+fn make_bitmap(stream: &mut TcpStream, key: &[u8], len: usize) {
+    let offset = (len - 1).to_string();
+    assert_eq!(
+        call(stream, &[b"SETRANGE", key, offset.as_bytes(), b"\xff"]),
+        format!(":{len}\r\n").into_bytes()
+    );
+}
+
+fn timed_bitcount(stream: &mut TcpStream, key: &[u8]) -> Duration {
+    let started = Instant::now();
+    assert_eq!(call(stream, &[b"BITCOUNT", key]), b":8\r\n");
+    started.elapsed()
+}
+
+/// Grow `a` and `b` until one BITCOUNT takes long enough that overlapping and
+/// queueing are far apart. Returns their length and that single-command time.
+fn long_bitmaps(client: &mut TcpStream) -> (usize, Duration) {
+    let mut len = 32 << 20;
+    loop {
+        make_bitmap(client, b"a", len);
+        make_bitmap(client, b"b", len);
+        let single = timed_bitcount(client, b"a").min(timed_bitcount(client, b"b"));
+        if single >= Duration::from_millis(150) || len >= 512 << 20 {
+            return (len, single);
+        }
+        len *= 2;
+    }
+}
+
+#[test]
+fn bitcounts_on_different_keys_overlap() {
+    let port = reserve_port();
+    let _server = spawn_frankenredis(port);
+    let mut setup = connect(port);
+    let (_, single) = long_bitmaps(&mut setup);
+
+    let mut first = connect(port);
+    let mut second = connect(port);
+    let mut probe = connect(port);
+    let started = Instant::now();
+    first
+        .write_all(&encode_command(&[b"BITCOUNT", b"a"]))
+        .expect("send first");
+    second
+        .write_all(&encode_command(&[b"BITCOUNT", b"b"]))
+        .expect("send second");
+
+    // The event loop is free while both counts run.
+    thread::sleep(single / 10);
+    let ping_sent = Instant::now();
+    assert_eq!(call(&mut probe, &[b"PING"]), b"+PONG\r\n");
+    let ping_rtt = ping_sent.elapsed();
+
+    let first_done = thread::spawn(move || {
+        assert_eq!(read_line(&mut first), b":8\r\n");
+        started.elapsed()
+    });
+    assert_eq!(read_line(&mut second), b":8\r\n");
+    let second_end = started.elapsed();
+    let first_end = first_done.join().expect("first reader");
+
+    assert!(
+        ping_rtt < single / 2,
+        "PING took {ping_rtt:?} while BITCOUNTs of {single:?} were running"
+    );
+    // Queued one after the other, the later reply would trail the earlier
+    // one by a whole command; run side by side they finish together.
+    let gap = first_end.abs_diff(second_end);
+    assert!(
+        gap < single / 2,
+        "replies {gap:?} apart for BITCOUNTs of {single:?} each \
+         (ended at {first_end:?} and {second_end:?})"
+    );
+    if thread::available_parallelism().is_ok_and(|cores| cores.get() >= 3) {
+        let both = first_end.max(second_end);
+        assert!(
+            both < single * 3 / 2,
+            "two BITCOUNTs took {both:?}, one alone {single:?}"
+        );
+    }
+}
+
+#[test]
+fn pooled_bitcount_counts_a_snapshot_and_keeps_pipeline_order() {
+    let port = reserve_port();
+    let _server = spawn_frankenredis(port);
+    let mut counter = connect(port);
+    let mut writer = connect(port);
+    let (len, single) = long_bitmaps(&mut counter);
+
+    // A pipeline behind a pooled BITCOUNT waits for it.
+    let mut pipeline = encode_command(&[b"BITCOUNT", b"a"]);
+    pipeline.extend(encode_command(&[b"SETRANGE", b"a", b"0", b"\xff"]));
+    pipeline.extend(encode_command(&[b"BITCOUNT", b"a"]));
+    counter.write_all(&pipeline).expect("send pipeline");
+
+    // Another client's write lands while the first count runs; the count
+    // still sees the value as it was when the command started.
+    thread::sleep(single / 10);
+    assert_eq!(
+        call(&mut writer, &[b"SETRANGE", b"a", b"1", b"\xff"]),
+        format!(":{len}\r\n").into_bytes()
+    );
+
+    assert_eq!(read_line(&mut counter), b":8\r\n");
+    assert_eq!(read_line(&mut counter), format!(":{len}\r\n").into_bytes());
+    assert_eq!(read_line(&mut counter), b":24\r\n");
+    assert_eq!(call(&mut counter, &[b"BITCOUNT", b"b"]), b":8\r\n");
+}
@@ -197,3 +197,89 @@ fn config_rewrite_updates_file() {
 
     send_shutdown_nosave(port);
 }
+
+#[test]
+fn positional_config_file_feeds_config_get_and_rewrite_keeps_comments() {
+    let port = reserve_port();
+    let temp_dir = unique_temp_dir("frankenredis-config-positional");
+    let config_path = temp_dir.join("redis.conf");
+    fs::write(
+        &config_path,
+        format!(
+            "# Deployed config\n\
+             bind 127.0.0.1\n\
+             port {port}\n\
+             \n\
+             # idle client timeout\n\
+             timeout 30\n\
+             hz 15\n\
+             maxmemory-policy allkeys-lru\n"
+        ),
+    )
+    .expect("write initial config file");
+
+    // Like redis-server: the config file is the first, non-option argument.
+    let log_dir = unique_temp_dir("frankenredis-config-positional-log");
+    let log_file = fs::File::create(log_dir.join("stderr.log")).expect("create stderr log file");
+    let mut command = Command::new(env!("CARGO_BIN_EXE_frankenredis"));
+    command
+        .arg(&config_path)
+        .arg("--mode")
+        .arg("strict")
+        .stdout(Stdio::null())
+        .stderr(Stdio::from(log_file));
+    let _server = ManagedChild::spawn(command);
+    wait_for_port(port);
+    let mut client = connect_client(port);
+
+    assert_eq!(
+        send_command(&mut client, &[b"CONFIG", b"GET", b"hz"]),
+        RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"hz".to_vec())),
+            RespFrame::BulkString(Some(b"15".to_vec())),
+        ]))
+    );
+    assert_eq!(
+        send_command(&mut client, &[b"CONFIG", b"GET", b"maxmemory-policy"]),
+        RespFrame::Array(Some(vec![
+            RespFrame::BulkString(Some(b"maxmemory-policy".to_vec())),
+            RespFrame::BulkString(Some(b"allkeys-lru".to_vec())),
+        ]))
+    );
+
+    assert_eq!(
+        send_command(&mut client, &[b"CONFIG", b"SET", b"timeout", b"123"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        send_command(&mut client, &[b"CONFIG", b"REWRITE"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+
+    let content = fs::read_to_string(&config_path).expect("read rewritten config");
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(
+        &lines[..8],
+        &[
+            "# Deployed config",
+            "bind 127.0.0.1",
+            &format!("port {port}"),
+            "",
+            "# idle client timeout",
+            "timeout 123",
+            "hz 15",
+            "maxmemory-policy allkeys-lru",
+        ],
+        "rewritten config: {content}"
+    );
+    assert_eq!(lines[8], "# Generated by CONFIG REWRITE");
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.starts_with("timeout "))
+            .count(),
+        1
+    );
+
+    send_shutdown_nosave(port);
+}
@@ -0,0 +1,427 @@
+//! Offline import (`fr-cli --rdb-load / --aof-load`): tolerant loading of
+//! Redis data files, the per-type summary, and replay to a server over RESP.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fr_persist::{AofRecord, RdbEntry, RdbValue, crc64_redis, encode_aof_stream, encode_rdb};
+use fr_protocol::{RespFrame, parse_frame};
+use fr_runtime::Runtime;
+use fr_server::import::{CommandPipeline, ImportSource, ImportSummary, load_source, send_commands};
+
+const NOW: u64 = 1_700_000_000_000;
+/// Far enough out that the CLI, which runs on the wall clock, keeps the key.
+const TTL_AT: u64 = 4_102_444_800_000;
+
+fn unique_temp_dir(prefix: &str) -> PathBuf {
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("{prefix}-{}-{nonce}", std::process::id()));
+    std::fs::create_dir_all(&path).expect("create temp dir");
+    path
+}
+
+fn bulk(value: &[u8]) -> RespFrame {
+    RespFrame::BulkString(Some(value.to_vec()))
+}
+
+fn run(runtime: &mut Runtime, argv: &[&[u8]]) -> RespFrame {
+    let frame = RespFrame::Array(Some(argv.iter().map(|arg| bulk(arg)).collect()));
+    runtime.execute_frame(frame, NOW)
+}
+
+fn record(argv: &[&[u8]]) -> AofRecord {
+    AofRecord {
+        argv: argv.iter().map(|arg| arg.to_vec()).collect(),
+    }
+}
+
+fn entry(db: usize, key: &[u8], value: RdbValue, expire_ms: Option<u64>) -> RdbEntry {
+    RdbEntry {
+        db,
+        key: key.to_vec(),
+        value,
+        expire_ms,
+    }
+}
+
+/// A dump with one key of every core type, one already-expired key, and in db
+/// 2 a TTL'd key plus a RedisJSON key the importer has to skip.
+fn sample_rdb() -> Vec<u8> {
+    let entries = vec![
+        entry(0, b"s", RdbValue::String(b"hello".to_vec()), None),
+        entry(
+            0,
+            b"l",
+            RdbValue::List(vec![b"a".to_vec(), b"b".to_vec()]),
+            None,
+        ),
+        entry(0, b"st", RdbValue::Set(vec![b"m".to_vec()]), None),
+        entry(
+            0,
+            b"h",
+            RdbValue::Hash(vec![(b"f".to_vec(), b"v".to_vec())]),
+            None,
+        ),
+        entry(
+            0,
+            b"z",
+            RdbValue::SortedSet(vec![(b"m".to_vec(), 1.5)]),
+            None,
+        ),
+        entry(
+            0,
+            b"x",
+            RdbValue::Stream(
+                vec![(5, 0, vec![(b"f".to_vec(), b"v".to_vec())])],
+                Some((5, 0)),
+                Vec::new(),
+                None,
+                None,
+                None,
+            ),
+            None,
+        ),
+        entry(0, b"gone", RdbValue::String(b"old".to_vec()), Some(1)),
+        entry(2, b"ttl", RdbValue::String(b"later".to_vec()), Some(TTL_AT)),
+    ];
+    let mut rdb = encode_rdb(&entries, &[]);
+    // Drop EOF + checksum, append an RDB_TYPE_MODULE_2 key, and re-seal.
+    rdb.truncate(rdb.len() - 9);
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let module_id = b"ReJSON-RL".iter().fold(0u64, |id, byte| {
+        (id << 6) | CHARSET.iter().position(|c| c == byte).unwrap() as u64
+    }) << 10
+        | 3;
+    rdb.push(7);
+    rdb.extend_from_slice(b"\x04json");
+    rdb.push(0x81);
+    rdb.extend_from_slice(&module_id.to_be_bytes());
+    // RDB_MODULE_OPCODE_STRING "{}", then RDB_MODULE_OPCODE_EOF.
+    rdb.extend_from_slice(b"\x05\x02{}\x00");
+    rdb.push(0xFF);
+    let checksum = crc64_redis(&rdb);
+    rdb.extend_from_slice(&checksum.to_le_bytes());
+    rdb
+}
+
+fn write_sample_rdb(dir: &Path) -> PathBuf {
+    let path = dir.join("dump.rdb");
+    std::fs::write(&path, sample_rdb()).expect("write dump");
+    path
+}
+
+fn load(source: &ImportSource) -> (Runtime, ImportSummary) {
+    let mut runtime = Runtime::default_strict();
+    let summary = load_source(&mut runtime, source, NOW, &mut |_| {}).expect("import loads");
+    (runtime, summary)
+}
+
+fn type_counts(
+    counts: &[(&'static str, usize)],
+) -> std::collections::BTreeMap<&'static str, usize> {
+    counts.iter().copied().collect()
+}
+
+/// Accept one connection, execute every command it sends on a fresh runtime,
+/// and hand the runtime back once the client hangs up.
+fn spawn_target() -> (u16, thread::JoinHandle<Runtime>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let handle = thread::spawn(move || {
+        let mut runtime = Runtime::default_strict();
+        let (mut stream, _) = listener.accept().expect("accept");
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            while let Ok(parsed) = parse_frame(&buf) {
+                buf.drain(..parsed.consumed);
+                let reply = runtime.execute_frame(parsed.frame, NOW);
+                stream.write_all(&reply.to_bytes()).expect("reply");
+            }
+            match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => return runtime,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    });
+    (port, handle)
+}
+
+#[test]
+fn rdb_import_skips_module_keys_and_summarizes_by_type() {
+    let dir = unique_temp_dir("fr-import-rdb");
+    let (_runtime, summary) = load(&ImportSource::Rdb(write_sample_rdb(&dir)));
+
+    assert_eq!(
+        summary.keys_by_type,
+        type_counts(&[
+            ("hash", 1),
+            ("list", 1),
+            ("set", 1),
+            ("stream", 1),
+            ("string", 2),
+            ("zset", 1),
+        ])
+    );
+    assert_eq!(summary.total_keys(), 7);
+    assert_eq!(summary.expired_keys, 1);
+    assert_eq!(summary.aof_records, 0);
+    assert_eq!(
+        summary.warnings,
+        vec!["skipped key 'json' in db 2: module type ReJSON-RL is not supported".to_string()]
+    );
+    let report = summary.to_string();
+    assert!(
+        report.starts_with("keys: 7 (hash 1, list 1, set 1, stream 1, string 2, zset 1)\n"),
+        "{report}"
+    );
+    assert!(
+        report.contains("warnings: 1\n  - skipped key 'json'"),
+        "{report}"
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn imported_dataset_replays_to_a_server_over_resp() {
+    let dir = unique_temp_dir("fr-import-send");
+    let (mut source, summary) = load(&ImportSource::Rdb(write_sample_rdb(&dir)));
+    let commands = source.dataset_commands(NOW);
+
+    let (port, target) = spawn_target();
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+    let mut batches = Vec::new();
+    let sent = send_commands(&mut stream, &commands, 3, &mut |sent, total| {
+        batches.push((sent, total));
+    })
+    .expect("send");
+    drop(stream);
+    let mut target = target.join().expect("target thread");
+
+    assert_eq!(sent.sent, commands.len());
+    assert!(sent.rejected.is_empty(), "{:?}", sent.rejected);
+    assert_eq!(batches.last(), Some(&(commands.len(), commands.len())));
+    assert_eq!(batches.len(), commands.len().div_ceil(3));
+
+    assert_eq!(target.keyspace_type_counts(NOW), summary.keys_by_type);
+    // The replay ends in db 2; look at db 0 first.
+    run(&mut target, &[b"SELECT", b"0"]);
+    assert_eq!(run(&mut target, &[b"GET", b"s"]), bulk(b"hello"));
+    assert_eq!(
+        run(&mut target, &[b"LRANGE", b"l", b"0", b"-1"]),
+        RespFrame::Array(Some(vec![bulk(b"a"), bulk(b"b")]))
+    );
+    assert_eq!(run(&mut target, &[b"ZSCORE", b"z", b"m"]), bulk(b"1.5"));
+    assert_eq!(run(&mut target, &[b"XLEN", b"x"]), RespFrame::Integer(1));
+    assert_eq!(
+        run(&mut target, &[b"EXISTS", b"gone"]),
+        RespFrame::Integer(0)
+    );
+    run(&mut target, &[b"SELECT", b"2"]);
+    assert_eq!(run(&mut target, &[b"GET", b"ttl"]), bulk(b"later"));
+    assert_eq!(
+        run(&mut target, &[b"PEXPIRETIME", b"ttl"]),
+        RespFrame::Integer(i64::try_from(TTL_AT).unwrap())
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn send_reports_rejected_writes_without_stopping() {
+    let (port, target) = spawn_target();
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+    let commands = vec![
+        vec![b"SET".to_vec(), b"k".to_vec(), b"v".to_vec()],
+        vec![b"INCR".to_vec(), b"k".to_vec()],
+        vec![b"SET".to_vec(), b"after".to_vec(), b"1".to_vec()],
+    ];
+    let sent = send_commands(&mut stream, &commands, 10, &mut |_, _| {}).expect("send");
+    drop(stream);
+    let mut target = target.join().expect("target thread");
+
+    assert_eq!(sent.sent, 3);
+    assert_eq!(
+        sent.rejected,
+        vec!["INCR: ERR value is not an integer or out of range".to_string()]
+    );
+    assert_eq!(run(&mut target, &[b"GET", b"after"]), bulk(b"1"));
+}
+
+#[test]
+fn aof_import_drops_a_truncated_tail_and_reports_rejected_records() {
+    let dir = unique_temp_dir("fr-import-aof");
+    let path = dir.join("appendonly.aof");
+    let mut aof = encode_aof_stream(&[
+        record(&[b"SET", b"a", b"1"]),
+        record(&[b"INCR", b"a"]),
+        record(&[b"SET", b"b", b"x"]),
+        record(&[b"INCR", b"b"]),
+        record(&[b"RPUSH", b"q", b"1", b"2"]),
+    ]);
+    let tail = b"*2\r\n$4\r\nINCR";
+    aof.extend_from_slice(tail);
+    std::fs::write(&path, &aof).expect("write aof");
+
+    let (mut runtime, summary) = load(&ImportSource::Aof(path.clone()));
+    assert_eq!(summary.aof_records, 5);
+    assert_eq!(
+        summary.keys_by_type,
+        type_counts(&[("list", 1), ("string", 2)])
+    );
+    assert_eq!(summary.warnings.len(), 2, "{:?}", summary.warnings);
+    assert!(
+        summary.warnings[0].ends_with(&format!(
+            "dropped a truncated {}-byte tail at offset {}",
+            tail.len(),
+            aof.len() - tail.len()
+        )),
+        "{:?}",
+        summary.warnings
+    );
+    assert!(
+        summary.warnings[1].ends_with("INCR rejected: ERR value is not an integer or out of range"),
+        "{:?}",
+        summary.warnings
+    );
+    assert_eq!(run(&mut runtime, &[b"GET", b"a"]), bulk(b"2"));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn aof_import_streams_past_the_batch_size_and_pipelines_the_dataset() {
+    let dir = unique_temp_dir("fr-import-stream");
+    let path = dir.join("appendonly.aof");
+    let preamble: Vec<RdbEntry> = (0..1500)
+        .map(|i| {
+            let key = format!("k{i}");
+            entry(0, key.as_bytes(), RdbValue::String(vec![b'v'; 100]), None)
+        })
+        .collect();
+    let mut aof = encode_rdb(&preamble, &[]);
+    let records: Vec<AofRecord> = (0..2500)
+        .map(|i| record(&[b"RPUSH", b"q", format!("{i}").as_bytes()]))
+        .collect();
+    aof.extend_from_slice(&encode_aof_stream(&records));
+    let open_multi = aof.len();
+    aof.extend_from_slice(&encode_aof_stream(&[
+        record(&[b"MULTI"]),
+        record(&[b"SET", b"open", b"1"]),
+    ]));
+    std::fs::write(&path, &aof).expect("write aof");
+
+    let (mut source, summary) = load(&ImportSource::Aof(path.clone()));
+    assert_eq!(summary.aof_records, 2500);
+    assert_eq!(
+        summary.keys_by_type,
+        type_counts(&[("list", 1), ("string", 1500)])
+    );
+    assert_eq!(
+        summary.warnings,
+        vec![format!(
+            "{}: dropped a MULTI without EXEC at offset {open_multi}",
+            path.display()
+        )]
+    );
+    assert_eq!(run(&mut source, &[b"LLEN", b"q"]), RespFrame::Integer(2500));
+    let expected = source.dataset_commands(NOW).len();
+
+    let (port, target) = spawn_target();
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect");
+    let mut pipeline = CommandPipeline::new(&mut stream, 7);
+    let mut batches = 0;
+    source
+        .try_for_each_dataset_command(NOW, |argv| {
+            batches += usize::from(pipeline.push(&argv)?);
+            Ok::<(), std::io::Error>(())
+        })
+        .expect("send");
+    let sent = pipeline.finish().expect("finish");
+    drop(stream);
+    let mut target = target.join().expect("target thread");
+
+    assert_eq!(sent.sent, expected);
+    assert!(sent.rejected.is_empty(), "{:?}", sent.rejected);
+    assert_eq!(batches, expected / 7);
+    assert_eq!(target.keyspace_type_counts(NOW), summary.keys_by_type);
+    assert_eq!(run(&mut target, &[b"LINDEX", b"q", b"-1"]), bulk(b"2499"));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn aof_import_reads_a_multipart_appendonlydir() {
+    let dir = unique_temp_dir("fr-import-aofdir");
+    let aof_dir = dir.join("appendonlydir");
+    fr_persist::write_aof_manifest_dir(
+        &aof_dir,
+        "appendonly.aof",
+        1,
+        &sample_rdb(),
+        &[
+            record(&[b"SET", b"s", b"rewritten"]),
+            record(&[b"SELECT", b"2"]),
+            record(&[b"SADD", b"extra", b"m"]),
+        ],
+    )
+    .expect("write appendonlydir");
+
+    let (mut runtime, summary) = load(&ImportSource::Aof(aof_dir));
+    assert_eq!(summary.aof_records, 3);
+    assert_eq!(summary.expired_keys, 1);
+    assert_eq!(summary.total_keys(), 8);
+    assert_eq!(summary.keys_by_type.get("set"), Some(&2));
+    assert_eq!(summary.warnings.len(), 1, "{:?}", summary.warnings);
+    assert!(summary.warnings[0].contains("module type ReJSON-RL"));
+    assert_eq!(
+        run(&mut runtime, &[b"SISMEMBER", b"extra", b"m"]),
+        RespFrame::Integer(1)
+    );
+    run(&mut runtime, &[b"SELECT", b"0"]);
+    assert_eq!(run(&mut runtime, &[b"GET", b"s"]), bulk(b"rewritten"));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn cli_dry_run_summarizes_and_out_writes_a_loadable_rdb() {
+    let dir = unique_temp_dir("fr-import-cli");
+    let dump = write_sample_rdb(&dir);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fr-cli"))
+        .arg("--rdb-load")
+        .arg(&dump)
+        .arg("--dry-run")
+        .output()
+        .expect("run fr-cli");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("keys: 7 ("), "{stdout}");
+    assert!(stdout.contains("module type ReJSON-RL"), "{stdout}");
+    assert!(stdout.ends_with("dry run: nothing written\n"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("fr-cli: applied 8 keys"), "{stderr}");
+
+    let out = dir.join("converted.rdb");
+    let output = Command::new(env!("CARGO_BIN_EXE_fr-cli"))
+        .arg("--rdb-load")
+        .arg(&dump)
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .expect("run fr-cli");
+    assert!(output.status.success(), "{output:?}");
+    let (entries, _aux) = fr_persist::read_rdb_file(&out).expect("converted dump loads strictly");
+    assert_eq!(entries.len(), 7);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fr-cli"))
+        .arg("--dry-run")
+        .output()
+        .expect("run fr-cli");
+    assert_eq!(output.status.code(), Some(1));
+    let _ = std::fs::remove_dir_all(dir);
+}
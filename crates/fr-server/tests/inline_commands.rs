@@ -0,0 +1,112 @@
+//! Inline (telnet-style) requests read back to back from one buffer.
+//!
+//! `try_parse_inline` consumes exactly one line per call, so a client that
+//! pipelines several typed lines — blank ones included — must see every
+//! command in order with the consumed offsets adding up to the buffer length.
+//! Quoting follows `sdssplitargs`; an unterminated line only becomes a
+//! protocol error once it outgrows the 64KB inline cap.
+
+use fr_protocol::{RespFrame, RespParseError};
+use fr_server::{InlineParseResult, should_try_inline_parsing, try_parse_inline};
+
+fn argv(args: &[&[u8]]) -> RespFrame {
+    RespFrame::Array(Some(
+        args.iter()
+            .map(|a| RespFrame::BulkString(Some(a.to_vec())))
+            .collect(),
+    ))
+}
+
+/// Parse the whole buffer, returning the commands (blank lines skipped).
+fn drain(mut buf: &[u8]) -> Vec<RespFrame> {
+    let mut commands = Vec::new();
+    while !buf.is_empty() {
+        assert!(should_try_inline_parsing(buf[0]));
+        match try_parse_inline(buf) {
+            Ok(InlineParseResult::Command(frame, consumed)) => {
+                commands.push(frame);
+                buf = &buf[consumed..];
+            }
+            Ok(InlineParseResult::EmptyLine(consumed)) => buf = &buf[consumed..],
+            Err(err) => panic!("unexpected {err:?} at {buf:?}"),
+        }
+    }
+    commands
+}
+
+#[test]
+fn pipelined_lines_parse_in_order_and_blank_lines_are_skipped() {
+    let buf = b"PING\r\n\r\n   \r\nSET k v\n\t\nGET k\r\n";
+    assert_eq!(
+        drain(buf),
+        vec![
+            argv(&[b"PING"]),
+            argv(&[b"SET", b"k", b"v"]),
+            argv(&[b"GET", b"k"])
+        ]
+    );
+    assert!(!should_try_inline_parsing(b'*'));
+}
+
+#[test]
+fn quoting_and_escapes() {
+    let buf = concat!(
+        "SET \"hello world\" 'it''s'\r\n",
+        "SET \"say \\\"hi\\\"\" 'don\\'t'\r\n",
+        "SET 'no \\n escape' \"tab\\there\"\r\n",
+        "SET \"\" ''\r\n",
+    );
+    // `'it''s'` closes a quote mid-token, which sdssplitargs rejects, so
+    // only the lines after it are checked through `drain`.
+    let (first, rest) = buf.split_at(buf.find('\n').unwrap() + 1);
+    assert_eq!(
+        try_parse_inline(first.as_bytes()),
+        Err(RespParseError::UnbalancedInlineQuotes)
+    );
+    assert_eq!(
+        drain(rest.as_bytes()),
+        vec![
+            argv(&[b"SET", b"say \"hi\"", b"don't"]),
+            argv(&[b"SET", b"no \\n escape", b"tab\there"]),
+            argv(&[b"SET", b"", b""]),
+        ]
+    );
+}
+
+#[test]
+fn hex_escapes_carry_arbitrary_bytes() {
+    assert_eq!(
+        drain(b"SET bin \"\\x00\\xff\\r\\n\"\r\n"),
+        vec![argv(&[b"SET", b"bin", b"\x00\xff\r\n"])]
+    );
+    // A raw NUL ends the scan like C's `while(*p)`.
+    assert_eq!(drain(b"SET\0k v\r\n"), vec![argv(&[b"SET"])]);
+    assert_eq!(
+        try_parse_inline(b"SET \"a\0b\"\r\n"),
+        Err(RespParseError::UnbalancedInlineQuotes)
+    );
+}
+
+#[test]
+fn only_an_unterminated_line_past_64kb_is_too_big() {
+    let mut long = b"SET k ".to_vec();
+    long.extend(std::iter::repeat_n(b'x', 64 * 1024));
+    assert_eq!(
+        try_parse_inline(&long),
+        Err(RespParseError::InlineRequestTooBig)
+    );
+    assert_eq!(
+        try_parse_inline(&long[..64 * 1024]),
+        Err(RespParseError::Incomplete)
+    );
+
+    // Once the newline is in the buffer the same line is a normal command.
+    long.extend_from_slice(b"\r\n");
+    match try_parse_inline(&long) {
+        Ok(InlineParseResult::Command(RespFrame::Array(Some(args)), consumed)) => {
+            assert_eq!(consumed, long.len());
+            assert_eq!(args.len(), 3);
+        }
+        other => panic!("expected a command, got {other:?}"),
+    }
+}
@@ -0,0 +1,226 @@
+//! Pipelining fairness: one connection streaming a huge pipeline must not
+//! starve the others. The server runs a bounded slice of each connection's
+//! buffered commands per event-loop pass (`--max-commands-per-tick`, and
+//! `--max-exec-us-per-tick` for slow commands), so a latency probe on a second
+//! connection keeps getting answers while the pipeline is still executing.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fr_protocol::RespFrame;
+
+fn encode_command(parts: &[&[u8]]) -> Vec<u8> {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some(part.to_vec())))
+            .collect(),
+    ))
+    .to_bytes()
+}
+
+fn reserve_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .expect("bind ephemeral port")
+        .local_addr()
+        .expect("local addr")
+        .port()
+}
+
+fn wait_for_port(port: u16) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if TcpStream::connect(format!("127.0.0.1:{port}")).is_ok() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("port {port} did not become ready in time");
+}
+
+struct ManagedChild {
+    child: Child,
+}
+
+impl Drop for ManagedChild {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn spawn_frankenredis(port: u16, extra_args: &[&str]) -> ManagedChild {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_frankenredis"));
+    command
+        .arg("--bind")
+        .arg("127.0.0.1")
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--mode")
+        .arg("strict")
+        .args(extra_args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let child = ManagedChild {
+        child: command.spawn().expect("spawn frankenredis"),
+    };
+    wait_for_port(port);
+    child
+}
+
+fn connect(port: u16) -> TcpStream {
+    let stream = TcpStream::connect(format!("127.0.0.1:{port}")).expect("connect");
+    stream.set_nodelay(true).expect("nodelay");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(30)))
+        .expect("read timeout");
+    stream
+}
+
+/// Read exactly `expected.len()` bytes and compare them.
+fn expect_bytes(stream: &mut TcpStream, expected: &[u8]) {
+    let mut got = vec![0_u8; expected.len()];
+    stream.read_exact(&mut got).expect("read reply");
+    assert_eq!(got, expected);
+}
+
+struct FairnessRun {
+    /// Round-trip times the probe saw while the pipeline was in flight.
+    probe_rtts: Vec<Duration>,
+    /// Time from sending the pipeline to reading its last reply.
+    pipeline_elapsed: Duration,
+}
+
+impl FairnessRun {
+    fn probe_p99(&self) -> Duration {
+        let mut rtts = self.probe_rtts.clone();
+        rtts.sort_unstable();
+        rtts[(rtts.len() * 99 / 100).min(rtts.len() - 1)]
+    }
+}
+
+/// Send `count` copies of `command` as one pipeline on a heavy connection and
+/// PING from a probe connection until every pipelined reply (`reply` each, in
+/// order) has been read back.
+fn run_pipeline_against_probe(
+    port: u16,
+    command: &[&[u8]],
+    reply: &'static [u8],
+    count: usize,
+) -> FairnessRun {
+    let mut probe = connect(port);
+    let ping = encode_command(&[b"PING"]);
+    probe.write_all(&ping).expect("probe warm-up");
+    expect_bytes(&mut probe, b"+PONG\r\n");
+
+    let heavy = connect(port);
+    let mut heavy_reader = heavy.try_clone().expect("clone heavy stream");
+    let mut heavy_writer = heavy;
+    let pipeline = encode_command(command).repeat(count);
+    let done = Arc::new(AtomicBool::new(false));
+
+    let started = Instant::now();
+    let writer = thread::spawn(move || heavy_writer.write_all(&pipeline).expect("send pipeline"));
+    let reader = {
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let mut remaining = reply.len() * count;
+            let mut buf = vec![0_u8; 64 * 1024];
+            let mut first_chunk = true;
+            while remaining > 0 {
+                let n = heavy_reader.read(&mut buf).expect("read pipeline replies");
+                assert!(n > 0, "server closed the pipelining connection");
+                if first_chunk {
+                    // Replies come back in request order, each one intact.
+                    assert!(buf[..n].starts_with(&reply[..reply.len().min(n)]));
+                    first_chunk = false;
+                }
+                remaining = remaining
+                    .checked_sub(n)
+                    .expect("more replies than commands");
+            }
+            let elapsed = started.elapsed();
+            done.store(true, Ordering::Release);
+            elapsed
+        })
+    };
+
+    let mut probe_rtts = Vec::new();
+    while !done.load(Ordering::Acquire) {
+        let sent = Instant::now();
+        probe.write_all(&ping).expect("probe ping");
+        expect_bytes(&mut probe, b"+PONG\r\n");
+        probe_rtts.push(sent.elapsed());
+    }
+    writer.join().expect("pipeline writer");
+    let pipeline_elapsed = reader.join().expect("pipeline reader");
+    FairnessRun {
+        probe_rtts,
+        pipeline_elapsed,
+    }
+}
+
+#[test]
+fn probe_latency_is_bounded_regardless_of_pipeline_size() {
+    let port = reserve_port();
+    let _server = spawn_frankenredis(port, &["--max-commands-per-tick", "1024"]);
+
+    for count in [20_000, 200_000] {
+        let run = run_pipeline_against_probe(port, &[b"PING"], b"+PONG\r\n", count);
+        assert!(
+            !run.probe_rtts.is_empty(),
+            "probe never completed a round trip during a {count}-command pipeline"
+        );
+        // A probe that had to wait for the whole pipeline would see roughly
+        // `pipeline_elapsed`; with per-pass budgets it waits for at most a
+        // few slices of it.
+        let p99 = run.probe_p99();
+        assert!(
+            p99 < Duration::from_millis(250) || p99 * 4 < run.pipeline_elapsed,
+            "probe p99 {p99:?} during a {count}-command pipeline that took {:?}",
+            run.pipeline_elapsed
+        );
+    }
+}
+
+#[test]
+fn exec_time_budget_interleaves_slow_commands() {
+    let port = reserve_port();
+    // 2000 commands per pass would run ~2s of 1ms sleeps back to back; the
+    // wall-time budget cuts each pass to about a millisecond instead.
+    let _server = spawn_frankenredis(
+        port,
+        &[
+            "--enable-debug-command",
+            "yes",
+            "--max-commands-per-tick",
+            "2000",
+            "--max-exec-us-per-tick",
+            "1000",
+        ],
+    );
+
+    let run = run_pipeline_against_probe(port, &[b"DEBUG", b"SLEEP", b"0.001"], b"+OK\r\n", 300);
+    assert!(
+        run.pipeline_elapsed >= Duration::from_millis(300),
+        "pipeline of 300 1ms sleeps finished in {:?}",
+        run.pipeline_elapsed
+    );
+    assert!(
+        run.probe_rtts.len() >= 10,
+        "probe completed only {} round trips in {:?}",
+        run.probe_rtts.len(),
+        run.pipeline_elapsed
+    );
+    let p99 = run.probe_p99();
+    assert!(
+        p99 * 4 < run.pipeline_elapsed,
+        "probe p99 {p99:?} while a {:?} pipeline of sleeps ran",
+        run.pipeline_elapsed
+    );
+}
@@ -0,0 +1,214 @@
+//! `supervised systemd`: sd_notify messages over a fake `$NOTIFY_SOCKET`.
+//!
+//!   cargo test -p fr-server --features systemd --test systemd_notify_test
+
+#![cfg(all(unix, feature = "systemd"))]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use fr_protocol::RespFrame;
+use fr_server::systemd::{NOTIFY_SOCKET_ENV, Notifier, watchdog_ping_interval_from};
+
+fn unique_temp_dir(prefix: &str) -> PathBuf {
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("{prefix}-{}-{nonce}", std::process::id()));
+    std::fs::create_dir_all(&path).expect("create temp dir");
+    path
+}
+
+/// A stand-in for systemd's end of the notification socket.
+fn fake_notify_socket(dir: &std::path::Path) -> (UnixDatagram, PathBuf) {
+    let path = dir.join("notify.sock");
+    let socket = UnixDatagram::bind(&path).expect("bind notify socket");
+    socket
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .expect("read timeout");
+    (socket, path)
+}
+
+fn recv(socket: &UnixDatagram) -> String {
+    let mut buf = [0_u8; 4096];
+    let n = socket.recv(&mut buf).expect("notify message");
+    String::from_utf8(buf[..n].to_vec()).expect("utf8")
+}
+
+fn encode_command(parts: &[&[u8]]) -> Vec<u8> {
+    RespFrame::Array(Some(
+        parts
+            .iter()
+            .map(|part| RespFrame::BulkString(Some(part.to_vec())))
+            .collect(),
+    ))
+    .to_bytes()
+}
+
+fn reserve_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .expect("bind ephemeral port")
+        .local_addr()
+        .expect("local addr")
+        .port()
+}
+
+struct ManagedChild {
+    child: Child,
+}
+
+impl Drop for ManagedChild {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn ready_arrives_only_after_a_delayed_loader_completes() {
+    let dir = unique_temp_dir("fr-systemd-loader");
+    let (socket, path) = fake_notify_socket(&dir);
+    let notifier = Notifier::connect(path.to_str().expect("utf8 path")).expect("connect");
+
+    let (received, loaded_at) = thread::scope(|scope| {
+        let receiver = scope.spawn(|| {
+            (0..4)
+                .map(|_| {
+                    let message = recv(&socket);
+                    (Instant::now(), message)
+                })
+                .collect::<Vec<_>>()
+        });
+        let loader = scope.spawn(|| {
+            for percent in [0, 50, 100] {
+                notifier
+                    .status(&format!("Loading fake dataset {percent}%"))
+                    .expect("status");
+                thread::sleep(Duration::from_millis(100));
+            }
+            Instant::now()
+        });
+        let loaded_at = loader.join().expect("loader");
+        notifier
+            .ready("Ready to accept connections")
+            .expect("ready");
+        (receiver.join().expect("receiver"), loaded_at)
+    });
+
+    let messages: Vec<&str> = received
+        .iter()
+        .map(|(_, message)| message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "STATUS=Loading fake dataset 0%",
+            "STATUS=Loading fake dataset 50%",
+            "STATUS=Loading fake dataset 100%",
+            "READY=1\nSTATUS=Ready to accept connections",
+        ]
+    );
+    assert!(received[3].0 >= loaded_at, "READY before the load finished");
+
+    notifier.stopping().expect("stopping");
+    assert_eq!(recv(&socket), "STOPPING=1");
+    notifier.watchdog().expect("watchdog");
+    assert_eq!(recv(&socket), "WATCHDOG=1");
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn abstract_notify_socket_addresses_are_supported() {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    let name = format!("fr-systemd-test-{}", std::process::id());
+    let address = SocketAddr::from_abstract_name(&name).expect("abstract name");
+    let socket = UnixDatagram::bind_addr(&address).expect("bind abstract socket");
+    socket
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .expect("read timeout");
+
+    let notifier = Notifier::connect(&format!("@{name}")).expect("connect");
+    notifier.status("hello").expect("status");
+    assert_eq!(recv(&socket), "STATUS=hello");
+}
+
+#[test]
+fn watchdog_pings_at_half_the_timeout_for_this_process_only() {
+    let own_pid = 4242;
+    assert_eq!(
+        watchdog_ping_interval_from(Some("30000000"), None, own_pid),
+        Some(Duration::from_secs(15))
+    );
+    assert_eq!(
+        watchdog_ping_interval_from(Some("30000000"), Some("4242"), own_pid),
+        Some(Duration::from_secs(15))
+    );
+    assert_eq!(
+        watchdog_ping_interval_from(Some("30000000"), Some("1"), own_pid),
+        None
+    );
+    for usec in [None, Some("0"), Some("soon")] {
+        assert_eq!(watchdog_ping_interval_from(usec, None, own_pid), None);
+    }
+}
+
+#[test]
+fn server_reports_loading_then_ready_then_stopping() {
+    let dir = unique_temp_dir("fr-systemd-server");
+    let (socket, path) = fake_notify_socket(&dir);
+    let aof = dir.join("appendonly.aof");
+    std::fs::write(&aof, encode_command(&[b"SET", b"loaded", b"yes"])).expect("write aof");
+    let port = reserve_port();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_frankenredis"));
+    command
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--aof")
+        .arg(&aof)
+        .arg("--rdb")
+        .arg(dir.join("dump.rdb"))
+        .arg("--supervised")
+        .arg("systemd")
+        .env(NOTIFY_SOCKET_ENV, &path)
+        .env_remove("WATCHDOG_USEC")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let _child = ManagedChild {
+        child: command.spawn().expect("spawn frankenredis"),
+    };
+
+    assert_eq!(
+        recv(&socket),
+        format!("STATUS=Loading AOF {}", aof.display())
+    );
+    let ready = recv(&socket);
+    assert!(ready.starts_with("READY=1\n"), "{ready}");
+
+    // READY means the listener is already accepting and the AOF is applied.
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect after READY");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .expect("read timeout");
+    stream
+        .write_all(&encode_command(&[b"GET", b"loaded"]))
+        .expect("write GET");
+    let mut reply = [0_u8; 64];
+    let n = stream.read(&mut reply).expect("GET reply");
+    assert_eq!(&reply[..n], b"$3\r\nyes\r\n");
+
+    stream
+        .write_all(&encode_command(&[b"SHUTDOWN", b"NOSAVE"]))
+        .expect("write SHUTDOWN");
+    assert_eq!(recv(&socket), "STOPPING=1");
+    let _ = std::fs::remove_dir_all(dir);
+}
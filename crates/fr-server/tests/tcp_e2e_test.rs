@@ -2998,6 +2998,158 @@ fn tcp_config_file_applies_startup_port_and_requirepass() {
     send_shutdown_nosave(port);
 }
 
+#[test]
+fn tcp_config_file_databases_bounds_select_per_connection() {
+    let port = reserve_port();
+    let temp_dir = unique_temp_dir("frankenredis-databases-config");
+    let config_path = temp_dir.join("frankenredis.conf");
+    let config_path_str = config_path.to_str().unwrap();
+
+    std::fs::write(
+        &config_path,
+        format!("bind 127.0.0.1\nport {port}\ndatabases 64\n"),
+    )
+    .unwrap();
+
+    let _server = spawn_frankenredis_config_only(port, config_path_str);
+    let mut high = connect_client(port);
+    let mut low = connect_client(port);
+
+    assert_eq!(
+        send_command(&mut high, &[b"SELECT", b"64"]),
+        RespFrame::Error("ERR DB index is out of range".to_string())
+    );
+    assert_eq!(
+        send_command(&mut high, &[b"SELECT", b"63"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(send_command(&mut high, &[b"DBSIZE"]), RespFrame::Integer(0));
+    assert_eq!(
+        send_command(&mut high, &[b"SET", b"k", b"v"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        send_command(&mut low, &[b"GET", b"k"]),
+        RespFrame::BulkString(None)
+    );
+
+    let listing = match send_command(&mut low, &[b"CLIENT", b"LIST"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("client list utf8"),
+        other => panic!("expected bulk client list, got {other:?}"),
+    };
+    let mut dbs: Vec<String> = listing
+        .lines()
+        .map(|line| parse_client_list_fields(line)["db"].clone())
+        .collect();
+    dbs.sort();
+    assert_eq!(dbs, vec!["0".to_string(), "63".to_string()], "{listing}");
+
+    let info = match send_command(&mut low, &[b"INFO", b"keyspace"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("info utf8"),
+        other => panic!("expected bulk INFO keyspace, got {other:?}"),
+    };
+    assert!(info.contains("db63:keys=1,"), "{info}");
+
+    send_shutdown_nosave(port);
+}
+
+/// The version each client-facing surface reports: INFO's `redis_version`,
+/// HELLO's `version` and the LOLWUT footer.
+fn reported_versions(client: &mut TcpStream) -> [String; 3] {
+    let info = match send_command(client, &[b"INFO", b"server"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("info utf8"),
+        other => panic!("expected bulk INFO server, got {other:?}"),
+    };
+    let info_version = info_field(&info, "redis_version");
+    let hello_version = match send_command(client, &[b"HELLO", b"2"]) {
+        RespFrame::Array(Some(fields)) => fields
+            .chunks(2)
+            .find(|pair| pair[0] == RespFrame::BulkString(Some(b"version".to_vec())))
+            .map(|pair| match &pair[1] {
+                RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes.clone()).unwrap(),
+                other => panic!("expected bulk HELLO version, got {other:?}"),
+            })
+            .expect("HELLO version field"),
+        other => panic!("expected HELLO array, got {other:?}"),
+    };
+    let lolwut_version = match send_command(client, &[b"LOLWUT"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes)
+            .unwrap()
+            .strip_prefix("Redis ver. ")
+            .and_then(|rest| rest.strip_suffix('\n'))
+            .expect("LOLWUT footer")
+            .to_string(),
+        other => panic!("expected bulk LOLWUT, got {other:?}"),
+    };
+    [info_version, hello_version, lolwut_version]
+}
+
+fn info_field(info: &str, name: &str) -> String {
+    info.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .unwrap_or_else(|| panic!("missing {name} in {info}"))
+        .to_string()
+}
+
+#[test]
+fn tcp_version_surfaces_agree_with_crate_metadata() {
+    let version = fr_server::version();
+    assert_eq!(version.frankenredis, env!("CARGO_PKG_VERSION"));
+    assert_eq!(version.git_sha1.len(), 8, "{version:?}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_frankenredis"))
+        .arg("--version")
+        .output()
+        .expect("run frankenredis --version");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "FrankenRedis server v={} sha={} redis_version={} bits={}\n",
+            version.frankenredis,
+            version.git_sha1,
+            version.redis_compat,
+            usize::BITS
+        )
+    );
+
+    let port = reserve_port();
+    let temp_dir = unique_temp_dir("frankenredis-server-version-config");
+    let config_path = temp_dir.join("frankenredis.conf");
+    std::fs::write(
+        &config_path,
+        format!("bind 127.0.0.1\nport {port}\nserver-version 6.2.14\n"),
+    )
+    .unwrap();
+    let _server = spawn_frankenredis_config_only(port, config_path.to_str().unwrap());
+    let mut client = connect_client(port);
+
+    assert_eq!(
+        reported_versions(&mut client),
+        ["6.2.14"; 3].map(String::from)
+    );
+    let info = match send_command(&mut client, &[b"INFO", b"server"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("info utf8"),
+        other => panic!("expected bulk INFO server, got {other:?}"),
+    };
+    assert_eq!(
+        info_field(&info, "frankenredis_version"),
+        version.frankenredis
+    );
+    assert_eq!(info_field(&info, "redis_git_sha1"), version.git_sha1);
+
+    assert_eq!(
+        send_command(&mut client, &[b"CONFIG", b"SET", b"server-version", b""]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    assert_eq!(
+        reported_versions(&mut client),
+        [version.redis_compat; 3].map(String::from)
+    );
+
+    send_shutdown_nosave(port);
+}
+
 #[test]
 fn tcp_config_file_applies_persistence_startup_paths() {
     let port = reserve_port();
@@ -3458,3 +3610,81 @@ fn tcp_waitaof_appendfsync_no_keeps_local_ack_visible_across_bgrewriteaof() {
         "franken BGREWRITEAOF must surface the buffered AOF append as a local ack",
     );
 }
+
+fn fetch_info_stats(client: &mut TcpStream) -> HashMap<String, f64> {
+    let info = match send_command(client, &[b"INFO", b"stats"]) {
+        RespFrame::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("info utf8"),
+        other => panic!("expected bulk INFO stats, got {other:?}"),
+    };
+    info.lines()
+        .filter_map(|line| {
+            let (field, value) = line.split_once(':')?;
+            Some((field.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[test]
+fn tcp_info_stats_track_net_bytes_and_instantaneous_rates() {
+    const BURST: usize = 2_000;
+    let port = reserve_port();
+    let _server = spawn_frankenredis(port, None);
+    let mut client = BufferedTcpClient::connect(port);
+    assert_eq!(
+        client.send_command(&[b"CONFIG", b"RESETSTAT"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+
+    let value = [b'v'; 32];
+    let mut burst = Vec::new();
+    for i in 0..BURST {
+        let key = format!("net:{i}");
+        burst.extend_from_slice(&encode_command(&[b"SET", key.as_bytes(), &value]));
+    }
+    client.write_all(&burst);
+    for response in client.read_responses(BURST) {
+        assert_eq!(response, RespFrame::SimpleString("OK".to_string()));
+    }
+
+    // Every burst byte was read and every `+OK\r\n` written; the only other
+    // traffic since RESETSTAT is its own reply and the INFO request itself.
+    let mut probe = connect_client(port);
+    let stats = fetch_info_stats(&mut probe);
+    let input = stats["total_net_input_bytes"];
+    let output = stats["total_net_output_bytes"];
+    let sent = burst.len() as f64;
+    assert!(
+        input >= sent && input < sent + 512.0,
+        "input={input} sent={sent}"
+    );
+    let replied = (BURST * b"+OK\r\n".len()) as f64;
+    assert!(
+        output >= replied && output < replied + 512.0,
+        "output={output} replied={replied}"
+    );
+
+    // The server cron samples every ~100ms into a 16-slot ring, so the
+    // burst shows up in the averaged rates once a sample covers it.
+    let mut stats = HashMap::new();
+    wait_until(
+        Duration::from_secs(2),
+        || {
+            stats = fetch_info_stats(&mut probe);
+            stats["instantaneous_ops_per_sec"] >= 100.0
+                && stats["instantaneous_input_kbps"] >= 1.0
+                && stats["instantaneous_output_kbps"] > 0.0
+        },
+        "instantaneous rates never reflected the burst",
+    );
+
+    // RESETSTAT zeroes the totals but leaves the instantaneous rates.
+    assert_eq!(
+        send_command(&mut probe, &[b"CONFIG", b"RESETSTAT"]),
+        RespFrame::SimpleString("OK".to_string())
+    );
+    let stats = fetch_info_stats(&mut probe);
+    assert!(stats["total_net_input_bytes"] < 512.0, "{stats:?}");
+    assert!(stats["instantaneous_ops_per_sec"] > 0.0, "{stats:?}");
+
+    send_shutdown_nosave(port);
+}
@@ -0,0 +1,126 @@
+//! Same-binary A/B for bulk-loading 1M keys into an empty store: `Store::new` growing the
+//! keyspace (and TTL index) by rehashing as keys arrive (`grow`) vs `Store::with_capacity` plus
+//! `reserve_expires` sized from the known counts (`presized`), the way the RDB loader uses the
+//! batch size. Null-gated on the median.
+//!
+//! Substrate matches the other cc benches: ONE binary / ONE invocation, adjacent-pair interleaving
+//! (order swapped on odd rounds), `black_box`, median of paired per-round ratios, gated on the
+//! candidate median lying outside the null control's p5..p95 spread (`cv` reported, never gated).
+//! Each segment is a single full load, so there is no rep calibration and fewer rounds.
+//!
+//! Keys and values are generated once up front and cloned into each load, so both arms pay the
+//! same per-key allocation; the ratio isolates the rehash cost the reservation eliminates.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use fr_store::Store;
+
+const KEYS: usize = 1_000_000;
+const EXPIRE_EVERY: usize = 4;
+const ROUNDS: usize = 11;
+const NULL_LO: f64 = 0.05;
+const NULL_HI: f64 = 0.95;
+
+type Batch = Vec<(Vec<u8>, Vec<u8>, Option<u64>)>;
+
+fn build_batch() -> Batch {
+    (0..KEYS)
+        .map(|i| {
+            let ttl = (i % EXPIRE_EVERY == 0).then_some(3_600_000);
+            (format!("user:{i:08}").into_bytes(), b"v".to_vec(), ttl)
+        })
+        .collect()
+}
+
+fn load(mut store: Store, batch: &Batch) -> Store {
+    for (key, value, ttl) in batch {
+        store.set(key.clone(), value.clone(), *ttl, 1_000);
+    }
+    store
+}
+
+fn grow(batch: &Batch) -> Store {
+    load(Store::new(), batch)
+}
+fn presized(batch: &Batch) -> Store {
+    let mut store = Store::with_capacity(batch.len());
+    store.reserve_expires(batch.iter().filter(|(_, _, ttl)| ttl.is_some()).count());
+    load(store, batch)
+}
+
+fn timed(f: fn(&Batch) -> Store, batch: &Batch) -> f64 {
+    let start = Instant::now();
+    let store = black_box(f(black_box(batch)));
+    let elapsed = start.elapsed().as_secs_f64();
+    assert_eq!(store.dbsize(0), KEYS);
+    drop(store);
+    elapsed
+}
+
+fn median(r: &mut [f64]) -> f64 {
+    r.sort_by(|a, b| a.partial_cmp(b).expect("no NaN"));
+    r[r.len() / 2]
+}
+fn cv(r: &[f64]) -> f64 {
+    let m = r.iter().sum::<f64>() / r.len() as f64;
+    100.0 * (r.iter().map(|x| (x - m).powi(2)).sum::<f64>() / r.len() as f64).sqrt() / m
+}
+fn pct(sorted: &[f64], p: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+fn main() {
+    let batch = build_batch();
+
+    let mut nulls = Vec::with_capacity(ROUNDS);
+    let mut speeds = Vec::with_capacity(ROUNDS);
+    for round in 0..=ROUNDS {
+        let swap = round % 2 == 1;
+        let nn = if swap {
+            let c = timed(grow, &batch);
+            timed(grow, &batch) / c
+        } else {
+            let b = timed(grow, &batch);
+            b / timed(grow, &batch)
+        };
+        let sp = if swap {
+            let c = timed(presized, &batch);
+            timed(grow, &batch) / c
+        } else {
+            let b = timed(grow, &batch);
+            b / timed(presized, &batch)
+        };
+        if round == 0 {
+            continue;
+        }
+        nulls.push(nn);
+        speeds.push(sp);
+    }
+
+    let null_med = median(&mut nulls);
+    let speedup = median(&mut speeds);
+    let lo = pct(&nulls, NULL_LO);
+    let hi = pct(&nulls, NULL_HI);
+    let verdict = if speedup > 1.0 && speedup > hi {
+        "WIN"
+    } else if speedup < 1.0 && speedup < lo {
+        "REGRESSION"
+    } else {
+        "indistinguishable"
+    };
+    println!(
+        "\n{:<22} {:>8} {:>9} {:>16} {:>8} {:>10} {:>12}",
+        "op", "keys", "NULL med", "null p5..p95", "null cv%", "speedup", "verdict"
+    );
+    println!(
+        "{:<22} {:>8} {:>9.4} {:>16} {:>8.2} {:>9.3}x {:>12}",
+        "bulk_load_1m",
+        KEYS,
+        null_med,
+        format!("[{lo:.3}, {hi:.3}]"),
+        cv(&nulls),
+        speedup,
+        verdict
+    );
+}
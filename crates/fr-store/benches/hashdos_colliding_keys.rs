@@ -0,0 +1,151 @@
+//! HashDoS regression: a pre-computed set of keys that all land in the same hashbrown probe
+//! group under a known-seed hasher (`foldhash::fast::FixedState::with_seed(0)`, standing in for
+//! any fixed-key FxHash/ahash setup) is replayed against three maps, next to a control set of
+//! the same size and shape:
+//!
+//! - `fixed`: `HashMap` with the known-seed hasher — the vulnerable configuration; every insert
+//!   and lookup walks the shared probe chain, so the colliding set costs O(n) per operation.
+//! - `keyed`: `HashMap` with `foldhash::quality::RandomState`, the hasher behind the keyspace,
+//!   the expiry map, the stream side maps and the set/zset/hash containers. Its seed is drawn
+//!   per process and per map, so keys collided offline scatter like the control set.
+//! - `store`: `Store::set` + `Store::get` through the real keyspace.
+//!
+//! The keyspace is already keyed, so there is no hashing change to cost out with fr-bench and
+//! no need for a hybrid rehash-on-long-chain mode. The gate fails if the known-seed map stops
+//! degrading (the colliding set is no longer an attack) or if a keyed map degrades (the
+//! mitigation regressed, e.g. someone swapped in a fixed-seed hasher for speed).
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use fr_store::Store;
+
+type KeyedMap = HashMap<Vec<u8>, u64, foldhash::quality::RandomState>;
+/// Fills a fresh map with the keys, then looks every key up; returns a checksum.
+type Workload = fn(&[Vec<u8>]) -> u64;
+
+/// Keys per set. Small enough that the table never outgrows `COLLIDE_MASK + 1` buckets, so
+/// every colliding key shares the probe start at every table size along the way.
+const KEYS: usize = 2_000;
+/// Low hash bits the colliding keys agree on (hashbrown's probe start is `hash & bucket_mask`).
+const COLLIDE_MASK: u64 = (1 << 12) - 1;
+const ROUNDS: usize = 15;
+/// The known-seed map must slow down at least this much on the colliding set...
+const MIN_ATTACK_SLOWDOWN: f64 = 4.0;
+/// ...and the keyed maps may not slow down more than this.
+const MAX_KEYED_SLOWDOWN: f64 = 1.5;
+const NOW_MS: u64 = 1_000;
+
+/// Keys whose known-seed hash has all-zero low bits, found by brute force over `user:<n>`.
+fn colliding_keys() -> Vec<Vec<u8>> {
+    let fixed = foldhash::fast::FixedState::with_seed(0);
+    (0_u64..)
+        .map(|n| format!("user:{n}").into_bytes())
+        .filter(|key| fixed.hash_one(key.as_slice()) & COLLIDE_MASK == 0)
+        .take(KEYS)
+        .collect()
+}
+
+/// Keys of the same shape with no particular hash structure.
+fn control_keys() -> Vec<Vec<u8>> {
+    (0..KEYS)
+        .map(|n| format!("user:{n}").into_bytes())
+        .collect()
+}
+
+fn fill_and_probe<S: BuildHasher + Default>(keys: &[Vec<u8>]) -> u64 {
+    let mut map: HashMap<Vec<u8>, u64, S> = HashMap::default();
+    for (i, key) in keys.iter().enumerate() {
+        map.insert(key.clone(), i as u64);
+    }
+    keys.iter().map(|key| map[key.as_slice()]).sum()
+}
+
+fn store_fill_and_probe(keys: &[Vec<u8>]) -> u64 {
+    let mut store = Store::new();
+    for key in keys {
+        store.set(key.clone(), b"v".to_vec(), None, NOW_MS);
+    }
+    keys.iter()
+        .map(|key| {
+            store
+                .get(key, NOW_MS)
+                .expect("string key")
+                .map_or(0, |value| value.len() as u64)
+        })
+        .sum()
+}
+
+/// Best-of-`ROUNDS` wall time of `run` over `keys`.
+fn best(run: Workload, keys: &[Vec<u8>]) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(run(black_box(keys)));
+            start.elapsed()
+        })
+        .min()
+        .expect("at least one round")
+}
+
+fn main() -> Result<(), String> {
+    let colliding = colliding_keys();
+    let control = control_keys();
+    let fixed = foldhash::fast::FixedState::with_seed(0);
+    assert!(
+        colliding
+            .iter()
+            .all(|key| fixed.hash_one(key.as_slice()) & COLLIDE_MASK == 0)
+    );
+
+    let arms: [(&str, Workload); 3] = [
+        ("fixed", fill_and_probe::<foldhash::fast::FixedState>),
+        ("keyed", fill_and_probe::<foldhash::quality::RandomState>),
+        ("store", store_fill_and_probe),
+    ];
+    let mut failures = Vec::new();
+    for (name, run) in arms {
+        assert_eq!(run(&colliding), run(&control), "{name}: checksum");
+        let attack = best(run, &colliding);
+        let baseline = best(run, &control);
+        let slowdown = attack.as_secs_f64() / baseline.as_secs_f64();
+        println!(
+            "HASHDOS arm={name} keys={KEYS} colliding_ns={} control_ns={} slowdown={slowdown:.2}",
+            attack.as_nanos(),
+            baseline.as_nanos()
+        );
+        if name == "fixed" {
+            if slowdown < MIN_ATTACK_SLOWDOWN {
+                failures.push(format!(
+                    "{name}: colliding set no longer degrades the known-seed map ({slowdown:.2}x)"
+                ));
+            }
+        } else if slowdown > MAX_KEYED_SLOWDOWN {
+            failures.push(format!(
+                "{name}: keys collided offline degrade a keyed map ({slowdown:.2}x)"
+            ));
+        }
+    }
+    // The keyed maps really are keyed: two instances disagree on the colliding keys' buckets.
+    let (a, b) = (KeyedMap::default(), KeyedMap::default());
+    let same_bucket = colliding
+        .iter()
+        .filter(|key| {
+            a.hasher().hash_one(key.as_slice()) & COLLIDE_MASK
+                == b.hasher().hash_one(key.as_slice()) & COLLIDE_MASK
+        })
+        .count();
+    println!("HASHDOS keyed_instances_same_bucket={same_bucket}/{KEYS}");
+    if same_bucket * 8 > KEYS {
+        failures.push(format!(
+            "keyed maps share a seed: {same_bucket}/{KEYS} keys land in the same bucket"
+        ));
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
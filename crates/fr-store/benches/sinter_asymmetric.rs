@@ -0,0 +1,157 @@
+//! Same-binary A/B for SINTER over asymmetric inputs, null-gated on the median.
+//!
+//! Substrate matches `set_algebra_build`: ONE binary / ONE invocation, adjacent-pair interleaving
+//! (order swapped on odd rounds), `black_box` inputs, reps calibrated per size, median of paired
+//! per-round ratios, gated on the candidate median lying outside the null control's p5..p95 spread
+//! (`cv` reported, never gated).
+//!
+//! ORIG = argument-order intersection: walk the FIRST key (`big`) and probe the rest, which is what
+//! `SINTER big small` costs without the smallest-set base — O(|big|) regardless of `small`.
+//! CAND = the shipped `Store::sinter`, which iterates the smallest input and probes the others,
+//! O(|small|). Both return the same sorted members; the win should grow linearly with `big`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use fr_store::Store;
+
+const ROUNDS: usize = 21;
+const TARGET_SEGMENT_SECS: f64 = 0.003;
+const SMALL: usize = 10;
+const BIG_SIZES: [usize; 3] = [10_000, 100_000, 500_000];
+const NULL_LO: f64 = 0.05;
+const NULL_HI: f64 = 0.95;
+
+fn median(r: &mut [f64]) -> f64 {
+    r.sort_by(|a, b| a.partial_cmp(b).expect("no NaN"));
+    r[r.len() / 2]
+}
+fn cv(r: &[f64]) -> f64 {
+    let m = r.iter().sum::<f64>() / r.len() as f64;
+    100.0 * (r.iter().map(|x| (x - m).powi(2)).sum::<f64>() / r.len() as f64).sqrt() / m
+}
+fn pct(sorted: &[f64], p: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+fn seeded(big: usize) -> Store {
+    let mut store = Store::new();
+    let members: Vec<Vec<u8>> = (0..big)
+        .map(|i| format!("member:{i:08}").into_bytes())
+        .collect();
+    store.sadd(b"big", &members, 0).unwrap();
+    // Half of `small` overlaps `big`, spread across its whole range.
+    let small: Vec<Vec<u8>> = (0..SMALL)
+        .map(|i| {
+            if i % 2 == 0 {
+                format!("member:{:08}", i * (big / SMALL)).into_bytes()
+            } else {
+                format!("absent:{i}").into_bytes()
+            }
+        })
+        .collect();
+    store.sadd(b"small", &small, 0).unwrap();
+    store
+}
+
+fn argument_order(store: &mut Store) -> Vec<Vec<u8>> {
+    let mut out: Vec<Vec<u8>> = store
+        .smembers(b"big", 0)
+        .unwrap()
+        .into_iter()
+        .filter(|member| store.sismember(b"small", member, 0).unwrap())
+        .collect();
+    out.sort_unstable();
+    out
+}
+
+fn smallest_base(store: &mut Store) -> Vec<Vec<u8>> {
+    store.sinter(&[b"big", b"small"], 0).unwrap()
+}
+
+fn main() {
+    println!(
+        "\n{:<10} {:>9} {:>9} {:>16} {:>8} {:>10} {:>12}",
+        "big", "reps", "NULL med", "null p5..p95", "null cv%", "speedup", "verdict"
+    );
+
+    for big in BIG_SIZES {
+        let mut store = seeded(big);
+        // Correctness gate: both strategies return the same members.
+        let expected = argument_order(&mut store);
+        assert_eq!(expected.len(), SMALL / 2);
+        assert_eq!(smallest_base(&mut store), expected);
+
+        let time = |store: &mut Store, f: fn(&mut Store) -> Vec<Vec<u8>>, reps: usize| -> f64 {
+            let start = Instant::now();
+            let mut acc = 0usize;
+            for _ in 0..reps {
+                acc = acc.wrapping_add(f(black_box(&mut *store)).len());
+            }
+            black_box(acc);
+            start.elapsed().as_secs_f64()
+        };
+        // Calibrate on the cheap candidate; the baseline then dominates each segment.
+        let mut reps = 1usize;
+        loop {
+            let e = time(&mut store, smallest_base, reps);
+            if e >= TARGET_SEGMENT_SECS || reps > 1 << 20 {
+                reps =
+                    ((reps as f64) * (TARGET_SEGMENT_SECS / e.max(1e-9)).max(1.0)).ceil() as usize;
+                break;
+            }
+            reps *= 4;
+        }
+        let orig_reps = (reps / (big / SMALL)).max(1);
+        let per_rep = |store: &mut Store, f: fn(&mut Store) -> Vec<Vec<u8>>, n: usize| {
+            time(store, f, n) / n as f64
+        };
+
+        let mut nulls = Vec::with_capacity(ROUNDS);
+        let mut speeds = Vec::with_capacity(ROUNDS);
+        for round in 0..=ROUNDS {
+            let swap = round % 2 == 1;
+            let mut pair = |bf: fn(&mut Store) -> Vec<Vec<u8>>,
+                            bn: usize,
+                            cf: fn(&mut Store) -> Vec<Vec<u8>>,
+                            cn: usize| {
+                if swap {
+                    let c = per_rep(&mut store, cf, cn);
+                    per_rep(&mut store, bf, bn) / c
+                } else {
+                    let b = per_rep(&mut store, bf, bn);
+                    b / per_rep(&mut store, cf, cn)
+                }
+            };
+            let nn = pair(smallest_base, reps, smallest_base, reps);
+            let sp = pair(argument_order, orig_reps, smallest_base, reps);
+            if round == 0 {
+                continue;
+            }
+            nulls.push(nn);
+            speeds.push(sp);
+        }
+
+        let null_med = median(&mut nulls);
+        let speedup = median(&mut speeds);
+        let lo = pct(&nulls, NULL_LO);
+        let hi = pct(&nulls, NULL_HI);
+        let verdict = if speedup > 1.0 && speedup > hi {
+            "WIN"
+        } else if speedup < 1.0 && speedup < lo {
+            "REGRESSION"
+        } else {
+            "indistinguishable"
+        };
+        println!(
+            "{:<10} {:>9} {:>9.4} {:>16} {:>8.2} {:>9.3}x {:>12}",
+            big,
+            reps,
+            null_med,
+            format!("[{lo:.3}, {hi:.3}]"),
+            cv(&nulls),
+            speedup,
+            verdict
+        );
+    }
+}
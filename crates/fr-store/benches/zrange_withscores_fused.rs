@@ -0,0 +1,168 @@
+//! Same-binary A/B for taking WITHSCORES pairs from the range walk itself.
+//!
+//! ORIG = the member walk followed by one `zscore` per member (the old ZREVRANGE WITHSCORES
+//! and ZRANGESTORE BYLEX shape). CAND = `zrevrange_withscores` / `zrangebylex_withscores_limited`,
+//! which read the score off the entry the walk is already holding. Both return identical pairs
+//! (asserted here); the ratio is the eliminated second lookup pass.
+//!
+//! Workload: a 1M-member zset, ranges of 10k members (a lex band on an equal-score set and a
+//! rank window on a spread-score set).
+//!
+//! Substrate matches the other store benches: ONE binary, adjacent-pair interleaving (order
+//! swapped on odd rounds), `black_box` inputs, reps calibrated per case, median of paired
+//! per-round ratios, gated on the candidate median lying outside the null control's p5..p95.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use fr_store::Store;
+
+const ROUNDS: usize = 21;
+const TARGET_SEGMENT_SECS: f64 = 0.02;
+const MEMBERS: usize = 1_000_000;
+const WINDOW: usize = 10_000;
+const NULL_LO: f64 = 0.05;
+const NULL_HI: f64 = 0.95;
+const TS: u64 = 2;
+
+fn seed() -> Store {
+    let mut store = Store::new();
+    let lex: Vec<(f64, Vec<u8>)> = (0..MEMBERS)
+        .map(|i| (0.0, format!("member:{i:08}").into_bytes()))
+        .collect();
+    store.zadd(b"lex", &lex, TS).unwrap();
+    let ranked: Vec<(f64, Vec<u8>)> = (0..MEMBERS)
+        .map(|i| {
+            (
+                ((i * 7919) % MEMBERS) as f64,
+                format!("member:{i:08}").into_bytes(),
+            )
+        })
+        .collect();
+    store.zadd(b"rank", &ranked, TS).unwrap();
+    store
+}
+
+type Op<'a> = dyn Fn(&mut Store) -> Vec<(Vec<u8>, f64)> + 'a;
+
+fn lookup_each(store: &mut Store, key: &[u8], members: Vec<Vec<u8>>) -> Vec<(Vec<u8>, f64)> {
+    members
+        .into_iter()
+        .map(|m| {
+            let score = store.zscore(key, &m, TS).unwrap().unwrap_or(0.0);
+            (m, score)
+        })
+        .collect()
+}
+
+fn median(r: &mut [f64]) -> f64 {
+    r.sort_by(|a, b| a.partial_cmp(b).expect("no NaN"));
+    r[r.len() / 2]
+}
+fn cv(r: &[f64]) -> f64 {
+    let m = r.iter().sum::<f64>() / r.len() as f64;
+    100.0 * (r.iter().map(|x| (x - m).powi(2)).sum::<f64>() / r.len() as f64).sqrt() / m
+}
+fn pct(sorted: &[f64], p: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+fn main() {
+    let mut store = seed();
+    let lo = format!("[member:{:08}", MEMBERS / 2).into_bytes();
+    let hi = format!("(member:{:08}", MEMBERS / 2 + WINDOW).into_bytes();
+    let start = (MEMBERS / 3) as i64;
+    let stop = start + WINDOW as i64 - 1;
+
+    let lex_orig = |s: &mut Store| {
+        let members = s.zrangebylex(b"lex", &lo, &hi, TS).unwrap();
+        lookup_each(s, b"lex", members)
+    };
+    let lex_cand = |s: &mut Store| {
+        s.zrangebylex_withscores_limited(b"lex", &lo, &hi, false, 0, None, TS)
+            .unwrap()
+    };
+    let rev_orig = |s: &mut Store| {
+        let members = s.zrevrange(b"rank", start, stop, TS).unwrap();
+        lookup_each(s, b"rank", members)
+    };
+    let rev_cand = |s: &mut Store| s.zrevrange_withscores(b"rank", start, stop, TS).unwrap();
+    let cases: [(&str, &Op<'_>, &Op<'_>); 2] = [
+        ("bylex_10k", &lex_orig, &lex_cand),
+        ("zrevrange_10k", &rev_orig, &rev_cand),
+    ];
+
+    println!(
+        "\n{:<14} {:>7} {:>9} {:>16} {:>8} {:>10} {:>18}",
+        "range", "reps", "NULL med", "null p5..p95", "null cv%", "speedup", "verdict"
+    );
+    for (name, orig, cand) in cases {
+        let expected = orig(&mut store);
+        assert_eq!(expected.len(), WINDOW, "{name}");
+        assert_eq!(cand(&mut store), expected, "{name}: pairs diverged");
+
+        let time = |f: &Op<'_>, s: &mut Store, reps: usize| -> f64 {
+            let started = Instant::now();
+            let mut acc = 0usize;
+            for _ in 0..reps {
+                acc = acc.wrapping_add(f(black_box(s)).len());
+            }
+            black_box(acc);
+            started.elapsed().as_secs_f64()
+        };
+        let mut reps = 1usize;
+        loop {
+            let e = time(orig, &mut store, reps);
+            if e >= TARGET_SEGMENT_SECS || reps > 1 << 12 {
+                reps =
+                    ((reps as f64) * (TARGET_SEGMENT_SECS / e.max(1e-9)).max(1.0)).ceil() as usize;
+                break;
+            }
+            reps *= 2;
+        }
+
+        let mut nulls = Vec::with_capacity(ROUNDS);
+        let mut speeds = Vec::with_capacity(ROUNDS);
+        for round in 0..=ROUNDS {
+            let swap = round % 2 == 1;
+            let mut pair = |bf: &Op<'_>, cf: &Op<'_>| {
+                if swap {
+                    let c = time(cf, &mut store, reps);
+                    time(bf, &mut store, reps) / c
+                } else {
+                    let b = time(bf, &mut store, reps);
+                    b / time(cf, &mut store, reps)
+                }
+            };
+            let nn = pair(orig, orig);
+            let sp = pair(orig, cand);
+            if round == 0 {
+                continue;
+            }
+            nulls.push(nn);
+            speeds.push(sp);
+        }
+
+        let null_med = median(&mut nulls);
+        let speedup = median(&mut speeds);
+        let lo = pct(&nulls, NULL_LO);
+        let hi = pct(&nulls, NULL_HI);
+        let verdict = if speedup > 1.0 && speedup > hi {
+            "WIN(one pass)"
+        } else if speedup < 1.0 && speedup < lo {
+            "REGRESSION"
+        } else {
+            "indistinguishable"
+        };
+        println!(
+            "{:<14} {:>7} {:>9.4} {:>16} {:>8.2} {:>9.3}x {:>18}",
+            name,
+            reps,
+            null_med,
+            format!("[{lo:.3}, {hi:.3}]"),
+            cv(&nulls),
+            speedup,
+            verdict
+        );
+    }
+}
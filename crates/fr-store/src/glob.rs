@@ -0,0 +1,433 @@
+//! Redis glob matching, as used by `KEYS`, `SCAN`/`HSCAN`/`SSCAN`/`ZSCAN`
+//! `MATCH`, `PSUBSCRIBE` and ACL key patterns.
+//!
+//! Semantics follow redis `stringmatchlen` byte for byte. Matching is over raw
+//! bytes and case-sensitive; there is no notion of UTF-8 characters.
+//!
+//! | Syntax | Matches |
+//! |--------|---------|
+//! | `*` | any run of bytes, including none |
+//! | `?` | exactly one byte |
+//! | `[abc]` | one byte listed in the class |
+//! | `[^abc]` | one byte *not* listed (only `^` negates; `[!a]` lists `!` and `a`) |
+//! | `[a-z]` | one byte in the inclusive range; reversed ranges such as `[z-a]` are swapped |
+//! | `\x` | the byte `x` literally, outside or inside a class |
+//! | any other byte | itself |
+//!
+//! Edge cases that differ from shell globbing:
+//!
+//! - The empty string is matched only by the empty pattern, so `*` does not
+//!   match `""`. `KEYS *` and `SCAN MATCH *` still return an empty key because
+//!   those commands skip the matcher for a lone `*`.
+//! - A class with no closing `]` runs to the end of the pattern: `[abc` matches
+//!   `a`, `b` or `c`.
+//! - `-` forms a range with whatever byte follows it, even `]`: `[a-]` is the
+//!   unterminated range `]`..=`a`, so it matches `^` but not `-`.
+//! - A trailing lone `\` matches a literal backslash.
+//!
+//! Every byte string is a valid pattern, and matching never panics. The
+//! matcher is iterative, so deep runs of `*` cannot overflow the stack.
+//!
+//! [`Pattern`] compiles a pattern once for repeated matching and exposes the
+//! literal shape that callers can use to skip the matcher altogether:
+//!
+//! ```
+//! use fr_store::glob::Pattern;
+//!
+//! let pattern = Pattern::compile(b"user:[0-9]*").unwrap();
+//! assert!(pattern.matches(b"user:42"));
+//! assert!(!pattern.matches(b"user:x"));
+//! assert!(!pattern.is_literal());
+//! // Every match starts with this prefix, so an ordered keyspace can seek to it.
+//! assert_eq!(pattern.literal_prefix(), b"user:");
+//!
+//! let exact = Pattern::compile(b"config").unwrap();
+//! assert!(exact.is_literal());
+//! assert!(exact.matches(b"config"));
+//! ```
+
+/// A glob pattern compiled for repeated matching.
+///
+/// Owns its bytes, so it can be stored and shared across threads. For a
+/// pattern borrowed only for the length of one scan, [`glob_prepare`] avoids
+/// the copy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    bytes: Box<[u8]>,
+    shape: PatternShape,
+}
+
+/// [`LiteralGlob`] without the borrow, so [`Pattern`] can own its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PatternShape {
+    Exact,
+    Prefix,
+    Suffix,
+    Contains,
+    General,
+}
+
+impl Pattern {
+    /// Compile `pattern`. See the [module docs](self) for the syntax.
+    ///
+    /// # Errors
+    ///
+    /// None today: every byte string is a valid pattern, including malformed
+    /// classes, which have defined semantics. The `Result` leaves room for
+    /// validation without a breaking change.
+    pub fn compile(pattern: &[u8]) -> Result<Self, PatternError> {
+        let shape = match literal_glob_shape(pattern) {
+            Some(LiteralGlob::Exact(_)) => PatternShape::Exact,
+            Some(LiteralGlob::Prefix(_)) => PatternShape::Prefix,
+            Some(LiteralGlob::Suffix(_)) => PatternShape::Suffix,
+            Some(LiteralGlob::Contains(_)) => PatternShape::Contains,
+            None => PatternShape::General,
+        };
+        Ok(Self {
+            bytes: pattern.into(),
+            shape,
+        })
+    }
+
+    /// Whether `key` matches. Identical to [`glob_match`] on the source pattern.
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, key: &[u8]) -> bool {
+        PreparedGlob {
+            pattern: &self.bytes,
+            shape: self.literal_shape(),
+        }
+        .matches(key)
+    }
+
+    /// The source pattern.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether the pattern contains no metacharacters (`*`, `?`, `[`) and no
+    /// escapes, so it matches only the key equal to [`Self::as_bytes`].
+    ///
+    /// Conservative: `a\*b` is logically literal but reports `false`.
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.shape == PatternShape::Exact
+    }
+
+    /// The bytes every matching key starts with: the pattern up to its first
+    /// metacharacter or escape. Empty when the pattern opens with one. For a
+    /// literal pattern this is the whole pattern.
+    #[must_use]
+    pub fn literal_prefix(&self) -> &[u8] {
+        glob_literal_prefix(&self.bytes)
+    }
+
+    fn literal_shape(&self) -> Option<LiteralGlob<'_>> {
+        let bytes = &self.bytes[..];
+        let n = bytes.len();
+        match self.shape {
+            PatternShape::Exact => Some(LiteralGlob::Exact(bytes)),
+            PatternShape::Prefix => Some(LiteralGlob::Prefix(&bytes[..n - 1])),
+            PatternShape::Suffix => Some(LiteralGlob::Suffix(&bytes[1..])),
+            PatternShape::Contains => Some(LiteralGlob::Contains(&bytes[1..n - 1])),
+            PatternShape::General => None,
+        }
+    }
+}
+
+/// Why [`Pattern::compile`] rejected a pattern. No pattern is rejected today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatternError {}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {}
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// (frankenredis-2wgom) The longest LITERAL prefix every string matching glob
+/// `pattern` must start with: the bytes before the first unescaped glob
+/// metacharacter (`*`, `?`, `[`) or escape (`\`). Stopping at `\` *under*-
+/// approximates the true prefix (a shorter prefix widens the candidate range),
+/// which is always safe because the per-candidate `glob_match` still filters
+/// exactly. Empty when the pattern opens with a metacharacter — no pruning.
+pub(crate) fn glob_literal_prefix(pattern: &[u8]) -> &[u8] {
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            b'*' | b'?' | b'[' | b'\\' => break,
+            _ => i += 1,
+        }
+    }
+    &pattern[..i]
+}
+
+/// A glob pattern with its literal shape classified ONCE, so a scan can match many strings without
+/// re-running [`literal_glob_shape`] per candidate. `SCAN`/`KEYS` match a fixed pattern against
+/// every key in a database, and the per-key `glob_match` re-classified the pattern each call
+/// (`glob_match` was ~7.3% self on a 20k-key `SCAN MATCH`). [`PreparedGlob::matches`] is
+/// **byte-identical** to `glob_match(pattern, s)` for every `s` (same classification, same
+/// matchers), just hoisted. (cc_fr)
+pub struct PreparedGlob<'a> {
+    pattern: &'a [u8],
+    shape: Option<LiteralGlob<'a>>,
+}
+
+impl<'a> PreparedGlob<'a> {
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, string: &[u8]) -> bool {
+        // Mirror `glob_match`'s empty-string rule exactly: an empty string matches ONLY an empty
+        // pattern, regardless of the literal shape.
+        if string.is_empty() {
+            return self.pattern.is_empty();
+        }
+        match self.shape {
+            Some(LiteralGlob::Exact(lit)) => string == lit,
+            Some(LiteralGlob::Prefix(lit)) => string.starts_with(lit),
+            Some(LiteralGlob::Suffix(lit)) => string.ends_with(lit),
+            Some(LiteralGlob::Contains(lit)) => literal_glob_contains(string, lit),
+            None => glob_match_inner(self.pattern, string, 0, 0),
+        }
+    }
+}
+
+/// Classify `pattern` once for repeated matching (see [`PreparedGlob`]).
+#[inline]
+#[must_use]
+pub fn glob_prepare(pattern: &[u8]) -> PreparedGlob<'_> {
+    PreparedGlob {
+        pattern,
+        shape: literal_glob_shape(pattern),
+    }
+}
+
+/// Whether `string` matches glob `pattern`. See the [module docs](self) for the
+/// syntax; [`Pattern`] or [`glob_prepare`] amortise the pattern classification
+/// across many strings.
+#[must_use]
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    // (frankenredis-z9dc3) redis stringmatchlen never enters its match loop for an
+    // empty string, so an empty string matches ONLY an empty pattern. A single `*`
+    // matches the empty key via the KEYS/SCAN `allkeys` shortcut (is_star), NOT via
+    // this matcher — so `**`/`***`/`?`/etc. must NOT match the empty string here
+    // (else KEYS/SCAN/HSCAN `**` wrongly include the empty key/field, and PSUBSCRIBE
+    // `*` wrongly matches an empty channel). The previous code consumed all trailing
+    // stars, making `**` match "". Non-empty strings (incl. mid-match `a**` vs `a`)
+    // are unaffected.
+    if string.is_empty() {
+        return pattern.is_empty();
+    }
+    // (CrimsonHawk) Literal fast paths: when the pattern is a metachar-free literal
+    // optionally bracketed by a single leading/trailing `*`, the match reduces to a
+    // vectorized memcmp instead of the char-by-char backtracking matcher. These are
+    // the dominant KEYS / SCAN-MATCH / PSUBSCRIBE / keyspace-notify shapes
+    // (`user:*`, `*.tmp`, an exact channel). Byte-exact: the empty-string case is
+    // handled above, so these only see non-empty strings; a single trailing/leading
+    // `*` absorbs the remainder incl. empty, so `starts_with`/`ends_with(b"")` ==
+    // true matches the matcher. Measured isolated A/B vs the backtracker: prefix
+    // -18..25%, suffix -49%, exact -54%, contains -71%/-86% (via the first-byte-skip
+    // `literal_glob_contains`, NOT a naive O(n*m) window scan which measured +66%).
+    match literal_glob_shape(pattern) {
+        Some(LiteralGlob::Exact(lit)) => return string == lit,
+        Some(LiteralGlob::Prefix(lit)) => return string.starts_with(lit),
+        Some(LiteralGlob::Suffix(lit)) => return string.ends_with(lit),
+        Some(LiteralGlob::Contains(lit)) => return literal_glob_contains(string, lit),
+        None => {}
+    }
+    glob_match_inner(pattern, string, 0, 0)
+}
+
+/// Classification of a pattern that is a metachar-free literal optionally framed
+/// by a single leading/trailing `*` — the cases [`glob_match`] can serve with a
+/// `==` / `starts_with` / `ends_with` memcmp instead of the backtracking matcher.
+enum LiteralGlob<'a> {
+    /// No star: matches iff the string equals the literal.
+    Exact(&'a [u8]),
+    /// `<literal>*`: matches iff the string starts with the literal.
+    Prefix(&'a [u8]),
+    /// `*<literal>`: matches iff the string ends with the literal.
+    Suffix(&'a [u8]),
+    /// `*<literal>*`: matches iff the string contains the literal.
+    Contains(&'a [u8]),
+}
+
+/// Dep-free substring search for the `*<literal>*` fast path: scan for the
+/// literal's first byte with a vectorizable `position`, then verify the rest.
+/// Skips non-first-byte positions fast (a naive O(n*m) window-equality scan
+/// MEASURED +66% — slower than the backtracker); this measured -71% (long keys) /
+/// -86% (`*aa*` over `a^n`) vs the matcher. Worst case (first byte recurs at every
+/// position, e.g. `*ab*` over `a^n`) is O(n*m), identical to the backtracker, so
+/// never a regression. Byte-exact.
+#[inline]
+fn literal_glob_contains(hay: &[u8], needle: &[u8]) -> bool {
+    let m = needle.len();
+    if m == 0 {
+        return true;
+    }
+    if m > hay.len() {
+        return false;
+    }
+    let first = needle[0];
+    let limit = hay.len() - m; // last valid start index
+    let mut start = 0usize;
+    while start <= limit {
+        match hay[start..=limit].iter().position(|&b| b == first) {
+            None => return false,
+            Some(off) => {
+                let pos = start + off;
+                if &hay[pos..pos + m] == needle {
+                    return true;
+                }
+                start = pos + 1;
+            }
+        }
+    }
+    false
+}
+
+#[inline]
+fn literal_glob_metachar_free(s: &[u8]) -> bool {
+    !s.iter()
+        .any(|&b| b == b'*' || b == b'?' || b == b'[' || b == b'\\')
+}
+
+#[inline]
+fn literal_glob_shape(pattern: &[u8]) -> Option<LiteralGlob<'_>> {
+    let n = pattern.len();
+    let lead = pattern.first() == Some(&b'*');
+    let trail = n >= 1 && pattern[n - 1] == b'*';
+    if lead && trail {
+        // `*lit*` (contains) when the body is a non-empty metachar-free literal
+        // bracketed by exactly two stars. `*`/`**` (empty body, n<3) keep their
+        // empty-string semantics in the matcher.
+        if n >= 3 {
+            let body = &pattern[1..n - 1];
+            return (!body.is_empty() && literal_glob_metachar_free(body))
+                .then_some(LiteralGlob::Contains(body));
+        }
+        None
+    } else if trail {
+        let lit = &pattern[..n - 1];
+        literal_glob_metachar_free(lit).then_some(LiteralGlob::Prefix(lit))
+    } else if lead {
+        let lit = &pattern[1..];
+        // n>=2 guaranteed (n>=1 and pattern[0]=='*' but not trail), lit non-empty.
+        literal_glob_metachar_free(lit).then_some(LiteralGlob::Suffix(lit))
+    } else {
+        literal_glob_metachar_free(pattern).then_some(LiteralGlob::Exact(pattern))
+    }
+}
+
+fn glob_match_inner(pattern: &[u8], string: &[u8], mut pi: usize, mut si: usize) -> bool {
+    let mut star_pi = usize::MAX;
+    let mut star_si = usize::MAX;
+
+    while si < string.len() {
+        if pi < pattern.len() && pattern[pi] == b'\\' && pi + 1 < pattern.len() {
+            // Escaped character: must match literally.
+            if string[si] == pattern[pi + 1] {
+                pi += 2;
+                si += 1;
+                continue;
+            }
+        } else if pi < pattern.len() && pattern[pi] == b'*' {
+            star_pi = pi;
+            star_si = si;
+            pi += 1;
+            continue;
+        } else if pi < pattern.len() && pattern[pi] == b'?' {
+            pi += 1;
+            si += 1;
+            continue;
+        } else if pi < pattern.len() && pattern[pi] == b'[' {
+            if let Some((matched, end)) = match_character_class(pattern, pi, string[si])
+                && matched
+            {
+                pi = end;
+                si += 1;
+                continue;
+            }
+        } else if pi < pattern.len() && pattern[pi] == string[si] {
+            pi += 1;
+            si += 1;
+            continue;
+        }
+
+        // Backtrack to last star.
+        if star_pi != usize::MAX {
+            pi = star_pi + 1;
+            star_si += 1;
+            si = star_si;
+            continue;
+        }
+
+        return false;
+    }
+
+    // Consume trailing stars.
+    while pi < pattern.len() && pattern[pi] == b'*' {
+        pi += 1;
+    }
+    pi == pattern.len()
+}
+
+/// Match a `[...]` character class at `pattern[pi]`.
+/// Returns `Some((matched, index_after_bracket))` or `None` if malformed.
+fn match_character_class(pattern: &[u8], pi: usize, ch: u8) -> Option<(bool, usize)> {
+    debug_assert_eq!(pattern[pi], b'[');
+    let mut i = pi + 1;
+    let negate = i < pattern.len() && pattern[i] == b'^';
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        if i + 1 < pattern.len() && pattern[i] == b'\\' {
+            i += 1;
+            if pattern[i] == ch {
+                matched = true;
+            }
+            i += 1;
+            continue;
+        }
+
+        if i >= pattern.len() {
+            // Redis malformed-class behavior: treat the final class byte as the terminator.
+            if i > pi + 1 {
+                i -= 1;
+            }
+            break;
+        }
+
+        if pattern[i] == b']' {
+            break;
+        }
+
+        if i + 2 < pattern.len() && pattern[i + 1] == b'-' {
+            let mut lo = pattern[i];
+            let mut hi = pattern[i + 2];
+            if lo > hi {
+                std::mem::swap(&mut lo, &mut hi);
+            }
+            if ch >= lo && ch <= hi {
+                matched = true;
+            }
+            i += 3;
+            continue;
+        }
+
+        if pattern[i] == ch {
+            matched = true;
+        }
+        i += 1;
+    }
+
+    let result = if negate { !matched } else { matched };
+    Some((result, (i + 1).min(pattern.len())))
+}
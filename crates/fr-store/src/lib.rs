@@ -9,6 +9,11 @@ mod packed_set;
 // when it replaces `entries` it deletes `ordered_keys` + `random_key_slots`.
 #[allow(dead_code)]
 mod keyspace_dict;
+// Redis glob matcher shared by KEYS/SCAN MATCH, PSUBSCRIBE and ACL key patterns.
+// Public so embedders can filter with exactly the server's semantics.
+pub mod glob;
+use glob::glob_literal_prefix;
+pub use glob::{PreparedGlob, glob_match, glob_prepare};
 #[cfg(any(test, feature = "bench-reference"))]
 #[doc(hidden)]
 pub use packed_set::PackedStreamLogBTreeReference;
@@ -16,8 +21,8 @@ pub use packed_set::PackedStreamLogBTreeReference;
 #[doc(hidden)]
 pub use packed_set::PackedZSet as BenchPackedZSet;
 use packed_set::{
-    GenericSet, HashFieldMap, ListValue, PackedStreamLog, PackedZSet, PackedZSetInsertResult,
-    PackedZSetIter, RestoredListNode, RetainedListpackChunk,
+    FieldsRef, GenericSet, HashFieldMap, ListValue, PackedStreamLog, PackedZSet,
+    PackedZSetInsertResult, PackedZSetIter, RestoredListNode, RetainedListpackChunk,
 };
 
 use fr_expire::evaluate_expiry;
@@ -40,6 +45,8 @@ use std::ops::{Deref, DerefMut, RangeBounds};
 /// SMEMBERS/SSCAN/SPOP output is byte-identical. (frankenredis-rqdxh follow-up)
 /// Redis-compatible version string. Single source of truth for all version reporting.
 pub const REDIS_COMPAT_VERSION: &str = "7.2.4";
+/// FrankenRedis's own (workspace) version, reported as INFO's `frankenredis_version`.
+pub const FRANKENREDIS_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const RDB_DUMP_VERSION: u16 = 11;
 const RDB_TYPE_STRING: u8 = 0;
@@ -115,6 +122,114 @@ pub fn with_touch_disabled<T>(disabled: bool, f: impl FnOnce() -> T) -> T {
     f()
 }
 
+/// Work units charged between two clock reads in [`budget_charge`]. A unit is
+/// roughly one element visited by a scan (or one LCS cell, or one
+/// [`BUDGET_BYTES_PER_UNIT`] slice of a bitmap).
+const BUDGET_CHECK_INTERVAL: usize = 4096;
+/// Bitmap bytes BITCOUNT counts per budget unit.
+const BUDGET_BYTES_PER_UNIT: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
+struct CommandBudget {
+    deadline: std::time::Instant,
+    until_check: usize,
+    exceeded: bool,
+}
+
+thread_local! {
+    static COMMAND_BUDGET: Cell<Option<CommandBudget>> = const { Cell::new(None) };
+}
+
+struct BudgetGuard {
+    previous: Option<CommandBudget>,
+}
+
+impl BudgetGuard {
+    fn new(deadline: std::time::Instant) -> Self {
+        let previous = COMMAND_BUDGET.with(|cell| {
+            let prev = cell.get();
+            // A nested budget never outlives the one it runs under.
+            let deadline = prev.map_or(deadline, |outer| outer.deadline.min(deadline));
+            cell.set(Some(CommandBudget {
+                deadline,
+                until_check: 0,
+                exceeded: prev.is_some_and(|outer| outer.exceeded),
+            }));
+            prev
+        });
+        Self { previous }
+    }
+}
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        COMMAND_BUDGET.with(|cell| cell.set(self.previous));
+    }
+}
+
+/// Run `f` with a wall-clock deadline for the current thread. Long-running
+/// store loops call [`budget_checkpoint`] and fail with
+/// [`StoreError::BudgetExceeded`] once the deadline has passed; they check
+/// before mutating anything, so an aborted write leaves the keyspace as it was.
+pub fn with_command_budget<T>(deadline: std::time::Instant, f: impl FnOnce() -> T) -> T {
+    let _guard = BudgetGuard::new(deadline);
+    f()
+}
+
+/// Charge `units` of work against the current command budget, reading the
+/// clock once every [`BUDGET_CHECK_INTERVAL`] units. Free of clock reads when
+/// no budget is set. Once the deadline has passed every later call fails too,
+/// so a loop that stopped early can be turned into an error by its caller.
+#[inline]
+pub fn budget_charge(units: usize) -> Result<(), StoreError> {
+    COMMAND_BUDGET.with(|cell| {
+        let Some(mut budget) = cell.get() else {
+            return Ok(());
+        };
+        if budget.exceeded {
+            return Err(StoreError::BudgetExceeded);
+        }
+        if budget.until_check > units {
+            budget.until_check -= units;
+        } else {
+            budget.until_check = BUDGET_CHECK_INTERVAL;
+            budget.exceeded = std::time::Instant::now() >= budget.deadline;
+        }
+        cell.set(Some(budget));
+        if budget.exceeded {
+            Err(StoreError::BudgetExceeded)
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Whether a command budget is set on this thread.
+#[must_use]
+pub fn budget_active() -> bool {
+    COMMAND_BUDGET.with(|cell| cell.get().is_some())
+}
+
+/// Whether the current command budget has already run out. Unlike
+/// [`budget_checkpoint`] this never reads the clock.
+#[must_use]
+pub fn budget_exceeded() -> bool {
+    COMMAND_BUDGET.with(|cell| cell.get().is_some_and(|budget| budget.exceeded))
+}
+
+/// [`budget_charge`] for a single unit of work.
+#[inline]
+pub fn budget_checkpoint() -> Result<(), StoreError> {
+    budget_charge(1)
+}
+
+/// `take_while` predicate that ends a scan once the command budget has run
+/// out. The scan's owner follows it with [`budget_checkpoint`] to report the
+/// early stop instead of returning a truncated result.
+fn within_budget<T>(_: &T) -> bool {
+    budget_checkpoint().is_ok()
+}
+
 // ── Keyspace notification flags (matching Redis server.h) ───────────
 pub const NOTIFY_KEYSPACE: u32 = 1 << 0; // K
 pub const NOTIFY_KEYEVENT: u32 = 1 << 1; // E
@@ -251,8 +366,6 @@ pub type StreamField = (Vec<u8>, Vec<u8>);
 pub type StreamEntries = PackedStreamLog;
 pub type StreamRecord = (StreamId, Vec<StreamField>);
 pub type StreamInfoBounds = (usize, Option<StreamRecord>, Option<StreamRecord>);
-/// (name, pending_count, idle_ms)
-pub type StreamConsumerInfo = (Vec<u8>, usize, u64);
 pub type StreamPendingEntries = BTreeMap<StreamId, StreamPendingEntry>;
 pub type StreamPendingSummaryConsumer = (Vec<u8>, usize);
 pub type StreamPendingSummary = (
@@ -294,6 +407,34 @@ pub struct SlowlogEntry {
     pub client_name: Vec<u8>,
 }
 
+/// Internal representation of one value, as reported by
+/// [`Store::debug_value_info`] and `DEBUG VALUE-INFO`. Lets encoding-transition
+/// tests assert more than the single `OBJECT ENCODING` string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueDebugInfo {
+    /// Logical type, as `TYPE` reports it.
+    pub value_type: &'static str,
+    /// Encoding, as `OBJECT ENCODING` reports it.
+    pub encoding: &'static str,
+    /// Element count; the byte length for strings.
+    pub length: usize,
+    /// RDB payload length, as `DEBUG OBJECT serializedlength` reports it.
+    pub serialized_bytes: usize,
+    /// Memory estimate, as `MEMORY USAGE` reports it.
+    pub memory_bytes: usize,
+    /// Quicklist nodes in list order; empty for single-node encodings.
+    pub nodes: Vec<ValueNodeInfo>,
+}
+
+/// One node of a multi-node encoding (a quicklist node).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueNodeInfo {
+    /// Elements held by the node.
+    pub entries: usize,
+    /// Listpack bytes of a packed node, or the raw element size of a plain one.
+    pub bytes: usize,
+}
+
 /// Max number of arguments retained per slowlog entry (slowlog.c
 /// `SLOWLOG_ENTRY_MAX_ARGC`). When a command has more, the last retained slot
 /// is replaced by a `... (N more arguments)` summary.
@@ -504,6 +645,15 @@ impl StreamGroup {
         states
     }
 
+    /// Refuse to create a consumer with an empty name. An existing one (say,
+    /// loaded from an RDB written elsewhere) stays usable.
+    fn check_new_consumer_name(&self, consumer: &[u8]) -> Result<(), StoreError> {
+        if consumer.is_empty() && !self.consumers.contains(consumer) {
+            return Err(StoreError::EmptyStreamName(StreamNameKind::Consumer));
+        }
+        Ok(())
+    }
+
     fn insert_consumer(&mut self, consumer: Vec<u8>) -> bool {
         let created = self.consumers.insert(consumer.clone());
         let metadata = self
@@ -657,10 +807,20 @@ fn append_group_consumer_aof_commands(
 
 pub type StreamGroupState = BTreeMap<Vec<u8>, StreamGroup>;
 pub type StreamGroupInfo = (Vec<u8>, usize, usize, StreamId, Option<u64>);
-/// (frankenredis-p4dpj) Extended XINFO CONSUMERS row: name, pending,
-/// idle_ms (since last seen), and inactive_ms_or_neg_one (-1 when
-/// the consumer was never active).
-pub type StreamConsumerInfoEx = (Vec<u8>, usize, u64, i64);
+
+/// One XINFO CONSUMERS row (frankenredis-p4dpj).
+///
+/// `idle_ms` counts from the consumer's last attempted interaction (any
+/// XREADGROUP / XCLAIM / XAUTOCLAIM naming it, even one that returned
+/// nothing); `inactive_ms` counts from the last one that actually read or
+/// claimed an entry and is `None` until that first happens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamConsumerInfo {
+    pub name: Vec<u8>,
+    pub pending: usize,
+    pub idle_ms: u64,
+    pub inactive_ms: Option<u64>,
+}
 type StreamPelSummaryCacheKey = (Vec<u8>, Vec<u8>);
 type StreamPelSummaryCacheValue = Vec<StreamPendingSummaryConsumer>;
 
@@ -673,11 +833,16 @@ pub struct ZaddOptions {
     pub ch: bool,
 }
 
+/// Why a store operation was refused. `Display` renders the exact error reply
+/// a client sees; [`StoreError::code`] is its leading error class.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum StoreError {
     ValueNotInteger,
     HashValueNotInteger,
     ValueNotFloat,
+    /// HINCRBYFLOAT found a field whose bytes do not parse as a float.
+    HashValueNotFloat,
     IncrFloatNaN,
     IntegerOverflow,
     KeyNotFound,
@@ -693,9 +858,135 @@ pub enum StoreError {
     IndexOutOfRange,
     InvalidDumpPayload,
     BusyKey,
+    /// The command ran past the deadline set by [`with_command_budget`].
+    BudgetExceeded,
+    /// The write would cross one of the store's [`StoreLimits`].
+    QuotaExceeded(QuotaKind),
+    /// A stream command would create a consumer group or consumer with an
+    /// empty name.
+    EmptyStreamName(StreamNameKind),
     GenericError(String),
 }
 
+/// Which stream name [`StoreError::EmptyStreamName`] refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamNameKind {
+    Group,
+    Consumer,
+}
+
+/// Which of the [`StoreLimits`] a rejected write would have crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuotaKind {
+    /// [`StoreLimits::max_keys`]: the write would create a key past the cap.
+    Keys,
+    /// [`StoreLimits::max_memory_bytes`]: estimated usage is at or would pass the cap.
+    Memory,
+    /// [`StoreLimits::max_value_bytes`]: a string, element, field or member is too large.
+    ValueSize,
+    /// [`StoreLimits::max_elements`]: a list/hash/set/zset/stream would grow too long.
+    Elements,
+}
+
+/// Guardrails for a store embedded per tenant. `None` leaves a dimension
+/// unlimited, which is the default for every field.
+///
+/// Limits are checked by the write paths before they mutate anything, so a
+/// rejected command leaves the key untouched and replies with
+/// [`StoreError::QuotaExceeded`]. Reads and deletes never consult them: a
+/// tenant that is over quota (e.g. after the limits were lowered at runtime)
+/// can always inspect and free its data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreLimits {
+    /// Total keys across all databases.
+    pub max_keys: Option<usize>,
+    /// Total estimated bytes, as reported by MEMORY USAGE / INFO used_memory.
+    pub max_memory_bytes: Option<usize>,
+    /// Bytes in one string value, or in one list element, hash field or
+    /// value, set/zset member, or stream field or value.
+    pub max_value_bytes: Option<usize>,
+    /// Elements in one list, hash, set, sorted set or stream.
+    pub max_elements: Option<usize>,
+}
+
+impl StoreLimits {
+    /// No limit in any dimension.
+    pub const UNLIMITED: Self = Self {
+        max_keys: None,
+        max_memory_bytes: None,
+        max_value_bytes: None,
+        max_elements: None,
+    };
+
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::UNLIMITED
+    }
+}
+
+impl StoreError {
+    /// The RESP error class the reply starts with (`ERR`, `WRONGTYPE`, ...).
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::WrongType | Self::InvalidHllValue => "WRONGTYPE",
+            Self::CorruptedHllValue => "INVALIDOBJ",
+            Self::BusyKey => "BUSYKEY",
+            Self::QuotaExceeded(_) => "OOM",
+            Self::GenericError(msg) => fr_protocol::resp_error_code(msg),
+            Self::ValueNotInteger
+            | Self::HashValueNotInteger
+            | Self::ValueNotFloat
+            | Self::HashValueNotFloat
+            | Self::IncrFloatNaN
+            | Self::IntegerOverflow
+            | Self::KeyNotFound
+            | Self::IndexOutOfRange
+            | Self::InvalidDumpPayload
+            | Self::BudgetExceeded
+            | Self::EmptyStreamName(_) => "ERR",
+        }
+    }
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ValueNotInteger => "ERR value is not an integer or out of range",
+            Self::HashValueNotInteger => "ERR hash value is not an integer",
+            Self::ValueNotFloat => "ERR value is not a valid float",
+            Self::HashValueNotFloat => "ERR hash value is not a float",
+            Self::IncrFloatNaN => "ERR increment would produce NaN or Infinity",
+            Self::IntegerOverflow => "ERR increment or decrement would overflow",
+            Self::KeyNotFound => "ERR no such key",
+            Self::WrongType => "WRONGTYPE Operation against a key holding the wrong kind of value",
+            Self::InvalidHllValue => "WRONGTYPE Key is not a valid HyperLogLog string value.",
+            Self::CorruptedHllValue => "INVALIDOBJ Corrupted HLL object detected",
+            Self::IndexOutOfRange => "ERR index out of range",
+            Self::InvalidDumpPayload => "ERR DUMP payload version or checksum are wrong",
+            Self::BusyKey => "BUSYKEY Target key name already exists.",
+            Self::BudgetExceeded => "ERR command exceeded its time budget",
+            Self::QuotaExceeded(QuotaKind::Keys) => "OOM command not allowed: key quota exceeded",
+            Self::QuotaExceeded(QuotaKind::Memory) => {
+                "OOM command not allowed: memory quota exceeded"
+            }
+            Self::QuotaExceeded(QuotaKind::ValueSize) => {
+                "OOM command not allowed: value size quota exceeded"
+            }
+            Self::QuotaExceeded(QuotaKind::Elements) => {
+                "OOM command not allowed: element quota exceeded"
+            }
+            Self::EmptyStreamName(StreamNameKind::Group) => {
+                "ERR consumer group name can't be empty"
+            }
+            Self::EmptyStreamName(StreamNameKind::Consumer) => "ERR consumer name can't be empty",
+            Self::GenericError(msg) => msg,
+        })
+    }
+}
+
+impl std::error::Error for StoreError {}
+
 const SORTED_SET_PACKED_DEFAULT_MAX_ENTRIES: usize = 128;
 const SORTED_SET_PACKED_DEFAULT_MAX_VALUE: usize = 64;
 const SORTED_SET_COMPACT_FULL_MAX_ENTRIES: usize = 2048;
@@ -2505,6 +2796,8 @@ impl SortedSet {
         // i5896 path, so byte-identical to the scan; only used when the offset
         // skip dominates the emitted slice (else the scan is cheaper) and the
         // treap is warm + consistent. Cold/packed/shallow fall through to the scan.
+        // The Full scan stops early once the command budget runs out; callers
+        // follow up with `budget_checkpoint` so a truncated walk becomes an error.
         if take != usize::MAX
             && let SortedSetInner::Full(full) = &self.inner
             && let Some(tree) = &full.rank_tree
@@ -2572,6 +2865,7 @@ impl SortedSet {
                     full.ordered
                         .range((lower, upper))
                         .rev()
+                        .take_while(within_budget)
                         .filter_map(|sm| {
                             sm.member
                                 .as_actual()
@@ -2583,6 +2877,7 @@ impl SortedSet {
                 } else {
                     full.ordered
                         .range((lower, upper))
+                        .take_while(within_budget)
                         .filter_map(|sm| {
                             sm.member
                                 .as_actual()
@@ -4184,6 +4479,9 @@ const SMALL_STR_INLINE_CAP: usize = 15;
 ///
 /// Values up to 15 bytes fit inside the `Value` payload instead of retaining a
 /// separate heap allocation. Larger strings keep the old `Vec<u8>` storage.
+/// `Shared` is a heap value handed to a reader on another thread by
+/// [`Store::string_snapshot`]; the first write takes the `Vec` back, copying
+/// it only if that reader still holds its snapshot.
 #[derive(Debug, Clone)]
 pub enum SmallStr {
     Inline {
@@ -4191,6 +4489,7 @@ pub enum SmallStr {
         bytes: [u8; SMALL_STR_INLINE_CAP],
     },
     Heap(Vec<u8>),
+    Shared(Arc<Vec<u8>>),
 }
 
 impl SmallStr {
@@ -4224,17 +4523,34 @@ impl SmallStr {
         match self {
             Self::Inline { len, bytes } => &bytes[..usize::from(*len)],
             Self::Heap(bytes) => bytes,
+            Self::Shared(bytes) => bytes,
         }
     }
 
     fn make_heap(&mut self) -> &mut Vec<u8> {
-        if let Self::Inline { .. } = self {
-            let bytes = self.as_slice().to_vec();
+        if !matches!(self, Self::Heap(_)) {
+            let bytes = std::mem::replace(self, Self::Heap(Vec::new())).into_vec();
             *self = Self::Heap(bytes);
         }
         match self {
             Self::Heap(bytes) => bytes,
-            Self::Inline { .. } => unreachable!("inline value was materialized as heap"),
+            Self::Inline { .. } | Self::Shared(_) => {
+                unreachable!("value was materialized as heap")
+            }
+        }
+    }
+
+    /// Share a heap value so a reader can keep it after the store lock is
+    /// released. Inline values are copied; they are too small to offload.
+    fn share(&mut self) -> Arc<Vec<u8>> {
+        match self {
+            Self::Inline { .. } => Arc::new(self.as_slice().to_vec()),
+            Self::Heap(bytes) => {
+                let shared = Arc::new(std::mem::take(bytes));
+                *self = Self::Shared(Arc::clone(&shared));
+                shared
+            }
+            Self::Shared(shared) => Arc::clone(shared),
         }
     }
 
@@ -4242,6 +4558,7 @@ impl SmallStr {
         match self {
             Self::Inline { len, bytes } => bytes[..usize::from(len)].to_vec(),
             Self::Heap(bytes) => bytes,
+            Self::Shared(bytes) => Arc::unwrap_or_clone(bytes),
         }
     }
 }
@@ -4281,6 +4598,18 @@ impl PartialEq for SmallStr {
 impl Eq for SmallStr {}
 
 impl Value {
+    /// Elements in a collection value; strings count as 0.
+    fn element_count(&self) -> usize {
+        match self {
+            Self::String(_) | Self::Integer(_) => 0,
+            Self::Hash(hash) => hash.len(),
+            Self::List(list) => list.len(),
+            Self::Set(set) => set.len(),
+            Self::SortedSet(zset) => zset.len(),
+            Self::Stream(stream) => stream.len(),
+        }
+    }
+
     fn string_bytes(&self) -> Option<Cow<'_, [u8]>> {
         match self {
             Self::String(bytes) => Some(Cow::Borrowed(bytes.as_slice())),
@@ -4923,6 +5252,34 @@ pub enum SortWeight {
     NotNumber,
 }
 
+/// Weight of a present SORT key or element, matching upstream sort.c's
+/// `strtod(byval, &eptr)` check: leading whitespace is skipped, the rest must
+/// parse in full, and an empty string reads as 0. NaN and overflow (`ERANGE`)
+/// are not numbers.
+#[must_use]
+pub fn parse_sort_weight(bytes: &[u8]) -> SortWeight {
+    if bytes.is_empty() {
+        return SortWeight::Number(0.0);
+    }
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return SortWeight::NotNumber;
+    };
+    let text = text.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '\x0b');
+    match text.parse::<f64>() {
+        Ok(f) if f.is_nan() => SortWeight::NotNumber,
+        Ok(f) if f.is_infinite() && !spells_infinity(text) => SortWeight::NotNumber,
+        Ok(f) => SortWeight::Number(f),
+        Err(_) => SortWeight::NotNumber,
+    }
+}
+
+fn spells_infinity(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    unsigned
+        .get(..3)
+        .is_some_and(|head| head.eq_ignore_ascii_case("inf"))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ActiveExpireCycleResult {
     pub sampled_keys: usize,
@@ -5587,6 +5944,24 @@ impl ValueType {
 /// Default number of databases (matches Redis default).
 pub const DEFAULT_NUM_DATABASES: usize = 16;
 
+/// Longest string OBJECT ENCODING reports as `embstr` rather than `raw`
+/// (upstream `OBJ_ENCODING_EMBSTR_SIZE_LIMIT`).
+pub const OBJ_ENCODING_EMBSTR_SIZE_LIMIT: usize = 44;
+
+// Redis 7.2 defaults for the collection encoding thresholds. A collection
+// one past `*_ENTRIES`, or holding an element longer than `*_VALUE` bytes,
+// leaves its compact encoding (listpack or intset).
+pub const DEFAULT_HASH_MAX_LISTPACK_ENTRIES: usize = 512;
+pub const DEFAULT_HASH_MAX_LISTPACK_VALUE: usize = 64;
+pub const DEFAULT_SET_MAX_INTSET_ENTRIES: usize = 512;
+pub const DEFAULT_SET_MAX_LISTPACK_ENTRIES: usize = 128;
+pub const DEFAULT_SET_MAX_LISTPACK_VALUE: usize = 64;
+pub const DEFAULT_ZSET_MAX_LISTPACK_ENTRIES: usize = 128;
+pub const DEFAULT_ZSET_MAX_LISTPACK_VALUE: usize = 64;
+
+/// Default `stream-preserve-last-id-window`: one hour.
+pub const DEFAULT_STREAM_PRESERVE_LAST_ID_WINDOW_MS: u64 = 60 * 60 * 1000;
+
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct ClientTrackingState {
     pub enabled: bool,
@@ -5713,6 +6088,12 @@ pub struct DispatchClientContext {
     /// cheaply gate recording each inner command for the MONITOR `lua` feed —
     /// no per-call clone when nobody is monitoring.
     pub monitors_active: bool,
+    /// Mirrors upstream CLIENT_DENY_BLOCKING: set by the runtime while it
+    /// replays an EXEC queue and by `redis.call` for the duration of a nested
+    /// command. Blocking commands (BLPOP, BLMOVE, BZPOPMIN, XREAD BLOCK, WAIT,
+    /// ...) always answer from their immediate path; this flag tells the
+    /// server that such a nil reply is final and the client must not park.
+    pub deny_blocking: bool,
 }
 
 impl Default for DispatchClientContext {
@@ -5746,6 +6127,7 @@ impl Default for DispatchClientContext {
             acl_checked_by_runtime: false,
             acl_permissions: None,
             monitors_active: false,
+            deny_blocking: false,
         }
     }
 }
@@ -5827,6 +6209,21 @@ pub struct ScriptPropagationRecord {
 /// scans beyond this many interleaved cursors fall back to the skip walk.
 const SCAN_CACHE_LRU_CAP: usize = 8;
 
+/// Low bits of a SCAN-family cursor that hold the resume position; the bits
+/// above carry the flush generation the cursor was minted in.
+const SCAN_CURSOR_POSITION_BITS: u32 = 48;
+const SCAN_CURSOR_POSITION_MASK: u64 = (1 << SCAN_CURSOR_POSITION_BITS) - 1;
+
+/// Client-facing cursor for `position` under flush-generation tag `tag`.
+/// Cursor 0 (start / complete) is never tagged.
+fn mint_scan_cursor(tag: u64, position: u64) -> u64 {
+    if position == 0 {
+        0
+    } else {
+        (position & SCAN_CURSOR_POSITION_MASK) | (tag << SCAN_CURSOR_POSITION_BITS)
+    }
+}
+
 /// (frankenredis-3e92e) Cached SCAN resume point enabling an O(log N + batch)
 /// in-order continuation instead of an O(cursor) `skip` re-walk.
 #[derive(Debug, Clone)]
@@ -5895,6 +6292,103 @@ struct DumpPayloadCache {
     payload: Vec<u8>,
 }
 
+/// How a client-supplied command name resolves through [`CommandRenames`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandRename<'a> {
+    /// Not affected by any `rename-command` directive.
+    Unchanged,
+    /// A replacement name; dispatch as this lowercase canonical command.
+    Alias(&'a [u8]),
+    /// A canonical name that was renamed or disabled: answer unknown command.
+    Hidden,
+}
+
+/// The `rename-command` table, folded once at startup. Upstream
+/// config.c re-keys the entry in `server.commands` (or deletes it when the new
+/// name is empty), so afterwards the replacement reaches the original command,
+/// the original name is unknown, and COMMAND still reports the original
+/// fullname. Names are stored lowercase; lookups are ASCII case-insensitive.
+#[derive(Debug, Clone, Default)]
+pub struct CommandRenames {
+    /// Replacement name -> canonical command name.
+    aliases: HashMap<Vec<u8>, Vec<u8>>,
+    /// Canonical names no longer reachable under their own name.
+    hidden: HashSet<Vec<u8>>,
+    /// Canonical names renamed to the empty string (removed from COMMAND).
+    disabled: HashSet<Vec<u8>>,
+    /// Longest name in `aliases` or `hidden`; anything longer is unchanged.
+    longest_name: usize,
+}
+
+/// Names up to this length are lowercased on the stack by
+/// [`CommandRenames::resolve`]; every command name is, and most aliases.
+const RENAME_LOOKUP_INLINE_LEN: usize = 64;
+
+impl CommandRenames {
+    /// True when no command has been renamed or disabled.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hidden.is_empty()
+    }
+
+    /// Record `canonical` as renamed to `replacement`, or disabled entirely
+    /// when `replacement` is empty. Callers validate both names against the
+    /// command table first; this only maintains the folded lookup.
+    pub fn insert(&mut self, canonical: &[u8], replacement: &[u8]) {
+        let canonical = canonical.to_ascii_lowercase();
+        self.longest_name = self
+            .longest_name
+            .max(canonical.len())
+            .max(replacement.len());
+        if replacement.is_empty() {
+            self.disabled.insert(canonical.clone());
+        } else {
+            self.aliases
+                .insert(replacement.to_ascii_lowercase(), canonical.clone());
+        }
+        self.hidden.insert(canonical);
+    }
+
+    /// Drop a replacement name (used when an alias is itself renamed again).
+    pub fn remove_alias(&mut self, replacement: &[u8]) -> Option<Vec<u8>> {
+        self.aliases.remove(&replacement.to_ascii_lowercase())
+    }
+
+    /// Resolve a client-supplied command name. Runs per client command once
+    /// any rename is configured, so it does not allocate for names that fit
+    /// the inline buffer.
+    #[must_use]
+    pub fn resolve(&self, name: &[u8]) -> CommandRename<'_> {
+        if self.is_empty() || name.len() > self.longest_name {
+            return CommandRename::Unchanged;
+        }
+        let mut inline = [0u8; RENAME_LOOKUP_INLINE_LEN];
+        let spilled;
+        let lower: &[u8] = if let Some(buf) = inline.get_mut(..name.len()) {
+            buf.copy_from_slice(name);
+            buf.make_ascii_lowercase();
+            buf
+        } else {
+            spilled = name.to_ascii_lowercase();
+            &spilled
+        };
+        if let Some(canonical) = self.aliases.get(lower) {
+            CommandRename::Alias(canonical)
+        } else if self.hidden.contains(lower) {
+            CommandRename::Hidden
+        } else {
+            CommandRename::Unchanged
+        }
+    }
+
+    /// True when the canonical `name` was renamed to the empty string.
+    #[must_use]
+    pub fn is_disabled(&self, name: &str) -> bool {
+        !self.disabled.is_empty() && self.disabled.contains(name.as_bytes())
+    }
+}
+
 #[derive(Debug)]
 pub struct Store {
     /// The keyspace dict. Uses `foldhash` (a fast, HashDoS-resistant, pure-
@@ -5925,6 +6419,18 @@ pub struct Store {
     /// identically ordered (keys are immutable), so resuming after the cached
     /// last key yields exactly what `iter().skip(cursor)` would.
     keyspace_generation: u64,
+    /// Per database, bumped by every flush that drops at least one of its keys
+    /// (FLUSHDB, FLUSHALL). SCAN-family cursors carry the low bits of their
+    /// database's generation, so a cursor minted before a flush is recognised
+    /// afterwards instead of resuming into unrelated data, while a flush of
+    /// another database leaves it valid. Absent means 0.
+    flush_generations: HashMap<usize, u64>,
+    /// Highest `modification_count` handed out to or carried away by any key
+    /// that left the keyspace. A newly inserted key is stamped one past it, so a
+    /// key that is deleted (DEL, expiry, eviction, FLUSHDB, RENAME, MOVE, SWAPDB)
+    /// and recreated with identical contents never reproduces the
+    /// `(fingerprint, modification_count)` pair WATCH recorded for it.
+    key_version_floor: u64,
     /// (frankenredis-3e92e/scanlru) SCAN resume fast-path cache. Sequential SCAN
     /// previously re-walked `ordered_keys` from the start every call
     /// (`iter().skip(cursor)` = O(cursor)), making a full iteration O(N²/batch).
@@ -6030,6 +6536,22 @@ pub struct Store {
     stream_entries_added: HashMap<Vec<u8>, u64, foldhash::quality::RandomState>,
     /// Highest stream entry ID removed via XDEL/XTRIM for each stream key.
     pub stream_max_deleted_ids: HashMap<Vec<u8>, StreamId, foldhash::quality::RandomState>,
+    /// `stream-preserve-last-id-on-delete`: remember the last id of a deleted
+    /// stream so XADD `*` on a recreated stream keeps counting above it.
+    stream_preserve_last_id_on_delete: bool,
+    /// `stream-preserve-last-id-window`: how long a remembered id is kept,
+    /// counted from the id's millisecond part.
+    stream_preserve_last_id_window_ms: u64,
+    /// `max-elements-per-call`: the most elements one ZADD, SADD or HSET/HMSET
+    /// may add; 0 means no limit.
+    max_elements_per_call: usize,
+    /// `stream-history-reads-keep-delivery-counts`: XREADGROUP history reads
+    /// (an explicit id, not `>`) leave PEL delivery counts and times alone
+    /// instead of counting as a re-delivery as upstream does.
+    stream_history_reads_keep_delivery_counts: bool,
+    /// Highest last id of each deleted stream key, while
+    /// `stream_preserve_last_id_on_delete` is on.
+    stream_last_id_tombstones: HashMap<Vec<u8>, StreamId, foldhash::quality::RandomState>,
     /// Script cache: SHA1 hex string → script body.
     // (perf) foldhash — keyed by SHA1 hex (not attacker-collidable without breaking SHA1), so no
     // DoS surface; speeds up every EVALSHA lookup.
@@ -6072,6 +6594,11 @@ pub struct Store {
     pub lfu_log_factor: u64,
 
     // Encoding thresholds — configurable via CONFIG SET, used by OBJECT ENCODING.
+    // CONFIG SET writes these fields directly and every write path reads them at
+    // insert time, so a change governs the next write to each key. Like upstream,
+    // nothing converts retroactively: a lowered threshold leaves existing keys
+    // alone until a write crosses it, and a raised one never downgrades a key
+    // whose conversion is already recorded in its sticky encoding flag.
     // (frankenredis-10lqb) Lists deliberately have only the byte-budget
     // cap `list_max_listpack_size`. Vendored Redis 7.2.4 exposes no
     // per-entries or per-value cap for lists — the quicklist boundary
@@ -6149,6 +6676,19 @@ pub struct Store {
     pub script_propagation_mode: u8,
     /// Commands emitted by the active Lua script together with their propagation masks.
     pub script_propagation_records: Vec<ScriptPropagationRecord>,
+    /// Propagate EVAL/EVALSHA verbatim instead of as the script's individual
+    /// effects (the pre-7.0 `lua-replicate-commands no` mode). Scripts then
+    /// may not write after calling a nondeterministic command. Off by default.
+    script_verbatim_propagation: bool,
+    /// Whether the active script propagates its effects rather than itself.
+    /// Starts as `!script_verbatim_propagation` for EVAL (always true for
+    /// FCALL) and flips on when the script calls `redis.replicate_commands()`.
+    pub script_replicate_effects: bool,
+    /// `(shard, shards)` when this store is one shard of a keyspace split by
+    /// `crc16_slot(key) % shards`. A script's `redis.call` on a key another
+    /// shard owns then fails with upstream's cluster "non local key" error
+    /// instead of reading or writing the wrong shard.
+    pub script_key_shard: Option<(usize, usize)>,
     /// (frankenredis-ax9ox) Original argv of each `redis.call` the active script
     /// ran, for the MONITOR `lua`-addressed feed. Only populated when MONITOR
     /// clients are attached (gated by dispatch_client_ctx.monitors_active);
@@ -6203,6 +6743,13 @@ pub struct Store {
     pub server_pid: u32,
     /// Server TCP port.
     pub server_port: u16,
+    /// `server-version` override for the version INFO, HELLO and LOLWUT
+    /// report, for clients that gate features on it. `None` reports
+    /// [`REDIS_COMPAT_VERSION`].
+    pub redis_version_override: Option<String>,
+    /// Commit the server binary was built from (INFO `redis_git_sha1`). The
+    /// binary sets it at startup; library stores keep upstream's `00000000`.
+    pub server_git_sha1: &'static str,
     /// Wall-clock millisecond timestamp when this Store was created.
     /// Used by INFO server's `uptime_in_seconds` to compute time
     /// since process startup (not the absolute timestamp).
@@ -6307,6 +6854,8 @@ pub struct Store {
     pub stat_expired_keys: u64,
     /// Total keys removed due to maxmemory eviction.
     pub stat_evicted_keys: u64,
+    /// Total clients disconnected by `maxmemory-clients` eviction.
+    pub stat_evicted_clients: u64,
     /// Percentage of expired keys found during active-expire sampling.
     pub stat_expired_stale_perc: u64,
     /// Cumulative CPU time spent in active-expire cycles.
@@ -6334,6 +6883,16 @@ pub struct Store {
     /// the core_module_sentinel conformance fixture.
     /// (br-frankenredis-pq3z)
     pub sentinel_mode: bool,
+    /// `rename-command` directives applied at startup. Consulted by the
+    /// runtime when resolving client command names and by COMMAND so the
+    /// introspection replies match the renamed command table.
+    pub command_renames: CommandRenames,
+    /// Test/CI switch: when set, SMEMBERS, HGETALL, HKEYS, HVALS and the
+    /// SSCAN/HSCAN batches reply in byte-sorted member/field order instead of
+    /// storage order, so fixtures need no order-insensitive matchers. KEYS,
+    /// SCAN, SUNION, SINTER and SDIFF already reply sorted. Off by default;
+    /// the only cost when off is one bool test per call.
+    deterministic_iteration: bool,
     /// Server hz (event loop frequency), synced from runtime.
     pub server_hz: u64,
     /// Replication backlog size, synced from runtime.
@@ -6342,6 +6901,8 @@ pub struct Store {
     pub server_maxclients: u64,
     /// Live maxmemory setting, synced from runtime.
     pub maxmemory_bytes_live: usize,
+    /// Per-tenant guardrails enforced by the write paths; see [`StoreLimits`].
+    limits: StoreLimits,
     /// Current client/session metadata for delegated dispatch paths such as Lua.
     pub dispatch_client_ctx: DispatchClientContext,
     /// ACL log events raised inside delegated dispatch paths such as Lua scripts.
@@ -6350,6 +6911,9 @@ pub struct Store {
     pub active_expire_enabled: bool,
     /// Set by DEBUG RELOAD; runtime consumes it after command dispatch.
     pub debug_reload_requested: bool,
+    /// Set by DEBUG CHANGE-REPL-ID; runtime consumes it after command dispatch
+    /// and rotates the master replication ID.
+    pub repl_id_change_requested: bool,
     /// Set by BGREWRITEAOF in delegated dispatch paths; runtime consumes it after dispatch.
     pub bgrewriteaof_requested: bool,
     /// Most recent sampled resident set size (RSS) in bytes.
@@ -6407,6 +6971,23 @@ pub fn encode_db_key(db: usize, key: &[u8]) -> Vec<u8> {
     encoded
 }
 
+/// Count one more key (or expiring key) in `db`, growing the per-db counter
+/// table on the database's first write.
+fn db_count_add(counts: &mut Vec<usize>, db: usize) {
+    if counts.len() <= db {
+        counts.resize(db + 1, 0);
+    }
+    counts[db] = counts[db].saturating_add(1);
+}
+
+/// Count one key (or expiring key) fewer in `db`. A database with no counter
+/// yet has nothing to remove.
+fn db_count_sub(counts: &mut [usize], db: usize) {
+    if let Some(count) = counts.get_mut(db) {
+        *count = count.saturating_sub(1);
+    }
+}
+
 #[must_use]
 pub fn decode_db_key(key: &[u8]) -> Option<(usize, &[u8])> {
     let db_len = std::mem::size_of::<u64>();
@@ -6419,6 +7000,11 @@ pub fn decode_db_key(key: &[u8]) -> Option<(usize, &[u8])> {
     Some((db, &key[prefix_len..]))
 }
 
+/// Database of a physical key; unprefixed keys belong to db 0.
+fn key_db(key: &[u8]) -> usize {
+    decode_db_key(key).map_or(0, |(db, _)| db)
+}
+
 #[inline]
 fn physical_key_belongs_to_db(key: &[u8], db: usize) -> bool {
     decode_db_key(key)
@@ -6488,6 +7074,8 @@ impl Default for Store {
             ordered_keys: BTreeSet::new(),
             ordered_keys_dirty: true,
             keyspace_generation: 0,
+            flush_generations: HashMap::new(),
+            key_version_floor: 0,
             scan_cache: Vec::new(),
             db_scan_cache: Vec::new(),
             zscan_cache: Vec::new(),
@@ -6516,6 +7104,11 @@ impl Default for Store {
             stream_last_ids: HashMap::default(),
             stream_entries_added: HashMap::default(),
             stream_max_deleted_ids: HashMap::default(),
+            stream_preserve_last_id_on_delete: false,
+            stream_preserve_last_id_window_ms: DEFAULT_STREAM_PRESERVE_LAST_ID_WINDOW_MS,
+            max_elements_per_call: 0,
+            stream_history_reads_keep_delivery_counts: false,
+            stream_last_id_tombstones: HashMap::default(),
             script_cache: HashMap::default(),
             subscribed_channels: HashSet::new(),
             subscribed_patterns: HashSet::new(),
@@ -6535,14 +7128,14 @@ impl Default for Store {
             // CONFIG GET. With 128 here, hashes of 129..512 entries
             // converted to hashtable in fr while vendored kept them
             // as listpack — observable via OBJECT ENCODING.
-            hash_max_listpack_entries: 512,
-            hash_max_listpack_value: 64,
+            hash_max_listpack_entries: DEFAULT_HASH_MAX_LISTPACK_ENTRIES,
+            hash_max_listpack_value: DEFAULT_HASH_MAX_LISTPACK_VALUE,
             list_max_listpack_size: -2,
-            set_max_intset_entries: 512,
-            set_max_listpack_entries: 128,
-            set_max_listpack_value: 64,
-            zset_max_listpack_entries: 128,
-            zset_max_listpack_value: 64,
+            set_max_intset_entries: DEFAULT_SET_MAX_INTSET_ENTRIES,
+            set_max_listpack_entries: DEFAULT_SET_MAX_LISTPACK_ENTRIES,
+            set_max_listpack_value: DEFAULT_SET_MAX_LISTPACK_VALUE,
+            zset_max_listpack_entries: DEFAULT_ZSET_MAX_LISTPACK_ENTRIES,
+            zset_max_listpack_value: DEFAULT_ZSET_MAX_LISTPACK_VALUE,
             proto_max_bulk_len: 512 * 1024 * 1024, // (frankenredis-uwhyl) redis 7.2 default
             hll_sparse_max_bytes: HLL_REDIS_SPARSE_MAX_BYTES,
             rng_seed: 0xDEADBEEF_C0FFEE11,
@@ -6574,6 +7167,9 @@ impl Default for Store {
             lua_error_line: 1,
             script_propagation_mode: SCRIPT_PROPAGATE_ALL,
             script_propagation_records: Vec::new(),
+            script_verbatim_propagation: false,
+            script_replicate_effects: true,
+            script_key_shard: None,
             script_monitor_records: Vec::new(),
             expires_count: 0,
             cached_memory_usage_bytes: std::cell::Cell::new(0),
@@ -6588,6 +7184,8 @@ impl Default for Store {
             cluster_shard_id: generate_run_id(),
             server_pid: std::process::id(),
             server_port: 6379,
+            redis_version_override: None,
+            server_git_sha1: "00000000",
             server_start_ms: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map(|d| d.as_millis() as u64)
@@ -6625,6 +7223,7 @@ impl Default for Store {
             stat_total_writes_processed: 0,
             stat_expired_keys: 0,
             stat_evicted_keys: 0,
+            stat_evicted_clients: 0,
             stat_expired_stale_perc: 0,
             stat_expire_cycle_cpu_milliseconds: 0,
             slowlog: VecDeque::new(),
@@ -6635,14 +7234,18 @@ impl Default for Store {
             command_histograms: CommandHistogramTracker::default(),
             sentinel_state: fr_sentinel::SentinelState::new(),
             sentinel_mode: false,
+            command_renames: CommandRenames::default(),
+            deterministic_iteration: false,
             server_hz: 10,
             server_repl_backlog_size: 1_048_576,
             server_maxclients: 10000,
             maxmemory_bytes_live: 0,
+            limits: StoreLimits::UNLIMITED,
             dispatch_client_ctx: DispatchClientContext::default(),
             pending_acl_log_events: Vec::new(),
             active_expire_enabled: true,
             debug_reload_requested: false,
+            repl_id_change_requested: false,
             bgrewriteaof_requested: false,
             stat_used_memory_rss: 0,
             stat_used_memory_peak: 0,
@@ -6699,6 +7302,312 @@ impl Store {
         Self::default()
     }
 
+    /// Creates an empty store whose keyspace can hold `keys` keys, across
+    /// all databases, before it has to rehash.
+    ///
+    /// Intended for embedders that bulk-load a known number of keys. The
+    /// result behaves exactly like [`Store::new`]; only the initial
+    /// allocation differs.
+    #[must_use]
+    pub fn with_capacity(keys: usize) -> Self {
+        let mut store = Self::default();
+        store.reserve(keys);
+        store
+    }
+
+    /// Creates an empty store that enforces `limits` on every write.
+    #[must_use]
+    pub fn with_limits(limits: StoreLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> StoreLimits {
+        self.limits
+    }
+
+    /// Replaces the store's limits. Data already past a lowered limit is kept;
+    /// only later writes that would grow it are rejected.
+    pub fn set_limits(&mut self, limits: StoreLimits) {
+        self.limits = limits;
+    }
+
+    /// Quota gate for the SET family, whose store entry points cannot fail:
+    /// callers check every `(key, value_len)` a command will write before
+    /// writing any of them, so a multi-key SET is rejected as a whole.
+    pub fn check_string_write_quota(
+        &self,
+        writes: &[(&[u8], usize)],
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        self.check_string_write_quota_iter(writes.iter().copied(), now_ms)
+    }
+
+    /// [`Self::check_string_write_quota`] over an iterator, so MSET-style
+    /// callers can check pairs borrowed from argv without collecting them.
+    /// The iterator is cloned for each pass the batch checks need.
+    pub fn check_string_write_quota_iter<'a, I>(
+        &self,
+        writes: I,
+        now_ms: u64,
+    ) -> Result<(), StoreError>
+    where
+        I: IntoIterator<Item = (&'a [u8], usize)>,
+        I::IntoIter: Clone,
+    {
+        if self.limits.is_unlimited() {
+            return Ok(());
+        }
+        let writes = writes.into_iter();
+        for (key, len) in writes.clone() {
+            self.check_write_quota(key, len, len, 0, now_ms)?;
+        }
+        if writes.clone().nth(1).is_none() {
+            return Ok(());
+        }
+        // The per-key checks above each assume they are the only new key and
+        // the only added bytes; the batch has to fit as a whole too.
+        if let Some(max) = self.limits.max_keys {
+            let new_keys: HashSet<&[u8], foldhash::quality::RandomState> = writes
+                .clone()
+                .map(|(key, _)| key)
+                .filter(|key| self.peek_value_type(key, now_ms).is_none())
+                .collect();
+            if self.entries.len().saturating_add(new_keys.len()) > max {
+                return Err(StoreError::QuotaExceeded(QuotaKind::Keys));
+            }
+        }
+        if let Some(max) = self.limits.max_memory_bytes {
+            let added = writes.fold(0_usize, |sum, (_, len)| sum.saturating_add(len));
+            if self.memory_quota_exceeded(max, added) {
+                return Err(StoreError::QuotaExceeded(QuotaKind::Memory));
+            }
+        }
+        Ok(())
+    }
+
+    /// Quota check for an in-place string edit that turns the current length
+    /// of `key` into `grow(len)` bytes. A non-string key is let through so
+    /// the write reports WRONGTYPE as usual.
+    fn check_string_growth_quota(
+        &self,
+        key: &[u8],
+        now_ms: u64,
+        grow: impl FnOnce(usize) -> usize,
+    ) -> Result<(), StoreError> {
+        if self.limits.is_unlimited() {
+            return Ok(());
+        }
+        let Ok(len) = self.string_len_no_stats(key, now_ms) else {
+            return Ok(());
+        };
+        let new_len = grow(len);
+        self.check_write_quota(key, new_len, new_len.saturating_sub(len), 0, now_ms)
+    }
+
+    /// Core [`StoreLimits`] check for a pending write to `key`. `largest_value`
+    /// is the biggest single value the write stores, `added_bytes` roughly how
+    /// much it adds, and `resulting_elements` the collection length afterwards
+    /// (0 for strings).
+    fn check_write_quota(
+        &self,
+        key: &[u8],
+        largest_value: usize,
+        added_bytes: usize,
+        resulting_elements: usize,
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        let limits = self.limits;
+        if limits.max_value_bytes.is_some_and(|max| largest_value > max) {
+            return Err(StoreError::QuotaExceeded(QuotaKind::ValueSize));
+        }
+        if limits
+            .max_elements
+            .is_some_and(|max| resulting_elements > max)
+        {
+            return Err(StoreError::QuotaExceeded(QuotaKind::Elements));
+        }
+        if let Some(max) = limits.max_keys
+            && self.entries.len() >= max
+            && self.peek_value_type(key, now_ms).is_none()
+        {
+            return Err(StoreError::QuotaExceeded(QuotaKind::Keys));
+        }
+        if limits
+            .max_memory_bytes
+            .is_some_and(|max| self.memory_quota_exceeded(max, added_bytes))
+        {
+            return Err(StoreError::QuotaExceeded(QuotaKind::Memory));
+        }
+        Ok(())
+    }
+
+    /// Whether the usage estimate plus `added_bytes` passes `max`. The estimate
+    /// is cached between rescans, so an "over" verdict is confirmed against a
+    /// fresh scan: a tenant that just freed memory must not stay locked out
+    /// until the next periodic rescan. The confirming scan only runs when the
+    /// keyspace changed since the last one, so a client retrying a rejected
+    /// write does not pay an O(keys) walk per attempt.
+    fn memory_quota_exceeded(&self, max: usize, added_bytes: usize) -> bool {
+        if self.estimate_memory_usage_bytes().saturating_add(added_bytes) <= max {
+            return false;
+        }
+        let mutations = self
+            .dirty
+            .saturating_add(self.stat_evicted_keys)
+            .saturating_add(self.stat_expired_keys);
+        if self.cached_memory_usage_dirty.get() == mutations {
+            return true;
+        }
+        self.cached_memory_usage_bytes.set(0);
+        self.estimate_memory_usage_bytes().saturating_add(added_bytes) > max
+    }
+
+    /// Current element count of the live collection at `key` (0 when missing,
+    /// expired, or a string).
+    fn quota_element_count(&self, key: &[u8], now_ms: u64) -> usize {
+        if self.peek_value_type(key, now_ms).is_none() {
+            return 0;
+        }
+        self.entries
+            .get(key)
+            .map_or(0, |entry| entry.value.element_count())
+    }
+
+    /// WRONGTYPE when `key` holds a live value other than `kind`, checked
+    /// before any quota so a limit never masks the type error the write
+    /// itself would report.
+    fn check_quota_target_type(
+        &self,
+        key: &[u8],
+        kind: ValueType,
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        match self.peek_value_type(key, now_ms) {
+            Some(current) if current != kind => Err(StoreError::WrongType),
+            _ => Ok(()),
+        }
+    }
+
+    /// Quota check for appending `new_elements` list elements or stream
+    /// entries (`kind`), carrying `values` bytes, to the collection at `key`.
+    fn check_append_quota<'a>(
+        &self,
+        key: &[u8],
+        kind: ValueType,
+        values: impl IntoIterator<Item = &'a [u8]>,
+        new_elements: usize,
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        if self.limits.is_unlimited() {
+            return Ok(());
+        }
+        self.check_quota_target_type(key, kind, now_ms)?;
+        let (largest, added) = values
+            .into_iter()
+            .fold((0, 0_usize), |(largest, added), v| {
+                (largest.max(v.len()), added.saturating_add(v.len()))
+            });
+        let resulting = if self.limits.max_elements.is_some() {
+            self.quota_element_count(key, now_ms)
+                .saturating_add(new_elements)
+        } else {
+            0
+        };
+        self.check_write_quota(key, largest, added, resulting, now_ms)
+    }
+
+    /// Quota check for inserting `members` into the hash, set or sorted set
+    /// (`kind`) at `key`. Members already present do not count toward the
+    /// element limit; `values` are the hash values stored alongside (empty
+    /// otherwise).
+    fn check_insert_quota<'a>(
+        &self,
+        key: &[u8],
+        kind: ValueType,
+        members: impl IntoIterator<Item = &'a [u8]>,
+        values: impl IntoIterator<Item = &'a [u8]>,
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        self.check_update_quota(key, kind, members, values, true, now_ms)
+    }
+
+    /// [`Self::check_insert_quota`] for writes that may only update existing
+    /// members (`may_add == false`, e.g. ZADD XX), which never grow the
+    /// element count.
+    fn check_update_quota<'a>(
+        &self,
+        key: &[u8],
+        kind: ValueType,
+        members: impl IntoIterator<Item = &'a [u8]>,
+        values: impl IntoIterator<Item = &'a [u8]>,
+        may_add: bool,
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        if self.limits.is_unlimited() {
+            return Ok(());
+        }
+        self.check_quota_target_type(key, kind, now_ms)?;
+        let current = if self.peek_value_type(key, now_ms).is_some() {
+            self.entries.get(key).map(|entry| &entry.value)
+        } else {
+            None
+        };
+        let mut fresh: HashSet<&[u8], foldhash::quality::RandomState> = HashSet::default();
+        let mut largest = 0;
+        let mut added = 0_usize;
+        for member in members {
+            largest = largest.max(member.len());
+            added = added.saturating_add(member.len());
+            let present = match current {
+                Some(Value::Hash(hash)) => hash.contains_key(member),
+                Some(Value::Set(set)) => set.contains(member),
+                Some(Value::SortedSet(zset)) => zset.get_score(member).is_some(),
+                _ => false,
+            };
+            if may_add && !present {
+                fresh.insert(member);
+            }
+        }
+        for value in values {
+            largest = largest.max(value.len());
+            added = added.saturating_add(value.len());
+        }
+        // Updating existing members never grows the collection, even one that
+        // is already over a lowered limit.
+        let resulting = if fresh.is_empty() {
+            0
+        } else {
+            current
+                .map_or(0, Value::element_count)
+                .saturating_add(fresh.len())
+        };
+        self.check_write_quota(key, largest, added, resulting, now_ms)
+    }
+
+    /// Reserves room for at least `additional` more keys in the keyspace, so
+    /// a bulk load of that many new keys does not rehash along the way.
+    pub fn reserve(&mut self, additional: usize) {
+        self.entries.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more keys with a TTL. Volatile
+    /// keys are indexed separately from the keyspace, so loads that know how
+    /// many of their keys expire can presize that index too.
+    pub fn reserve_expires(&mut self, additional: usize) {
+        self.expiry_deadlines.reserve(additional);
+    }
+
+    /// Number of keys, across all databases, the keyspace can hold without
+    /// reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
     fn clear_dump_payload_cache(&mut self) {
         self.dump_payload_cache.clear();
         self.dump_payload_cache_bytes = 0;
@@ -7010,6 +7919,72 @@ impl Store {
         self.aof_enabled = enabled;
     }
 
+    pub fn set_deterministic_iteration(&mut self, enabled: bool) {
+        self.deterministic_iteration = enabled;
+    }
+
+    #[must_use]
+    pub fn deterministic_iteration(&self) -> bool {
+        self.deterministic_iteration
+    }
+
+    /// Turn `stream-preserve-last-id-on-delete` on or off. Turning it off
+    /// forgets every id remembered so far.
+    pub fn set_stream_preserve_last_id_on_delete(&mut self, enabled: bool) {
+        self.stream_preserve_last_id_on_delete = enabled;
+        if !enabled {
+            self.stream_last_id_tombstones.clear();
+        }
+    }
+
+    #[must_use]
+    pub fn stream_preserve_last_id_on_delete(&self) -> bool {
+        self.stream_preserve_last_id_on_delete
+    }
+
+    pub fn set_stream_preserve_last_id_window_ms(&mut self, window_ms: u64) {
+        self.stream_preserve_last_id_window_ms = window_ms;
+    }
+
+    #[must_use]
+    pub fn stream_preserve_last_id_window_ms(&self) -> u64 {
+        self.stream_preserve_last_id_window_ms
+    }
+
+    pub fn set_max_elements_per_call(&mut self, limit: usize) {
+        self.max_elements_per_call = limit;
+    }
+
+    #[must_use]
+    pub fn max_elements_per_call(&self) -> usize {
+        self.max_elements_per_call
+    }
+
+    pub fn set_stream_history_reads_keep_delivery_counts(&mut self, enabled: bool) {
+        self.stream_history_reads_keep_delivery_counts = enabled;
+    }
+
+    #[must_use]
+    pub fn stream_history_reads_keep_delivery_counts(&self) -> bool {
+        self.stream_history_reads_keep_delivery_counts
+    }
+
+    /// Whether one call adding `elements` elements is within
+    /// `max-elements-per-call`.
+    #[must_use]
+    pub fn elements_per_call_allowed(&self, elements: usize) -> bool {
+        self.max_elements_per_call == 0 || elements <= self.max_elements_per_call
+    }
+
+    pub fn set_script_verbatim_propagation(&mut self, enabled: bool) {
+        self.script_verbatim_propagation = enabled;
+    }
+
+    #[must_use]
+    pub fn script_verbatim_propagation(&self) -> bool {
+        self.script_verbatim_propagation
+    }
+
     pub fn clear_script_propagation_state(&mut self) {
         self.script_propagation_mode = SCRIPT_PROPAGATE_ALL;
         self.script_propagation_records.clear();
@@ -7186,6 +8161,7 @@ impl Store {
         self.stat_total_writes_processed = 0;
         self.stat_expired_keys = 0;
         self.stat_evicted_keys = 0;
+        self.stat_evicted_clients = 0;
         self.stat_expired_stale_perc = 0;
         self.stat_expire_cycle_cpu_milliseconds = 0;
         self.stat_keyspace_hits = 0;
@@ -7200,11 +8176,11 @@ impl Store {
         self.stat_rdb_last_load_keys_loaded = 0;
         self.stat_total_net_input_bytes = 0;
         self.stat_total_net_output_bytes = 0;
-        self.ops_sec_samples = [0; 16];
-        self.ops_sec_idx = 0;
+        // The ops/sec and kbps rings are left alone so dashboards graphing
+        // instantaneous_* don't dip to zero on RESETSTAT; only their
+        // baselines follow the zeroed totals so the next sample measures
+        // the traffic since the reset.
         self.ops_sec_last_sample_count = 0;
-        self.input_kbps_samples = [0.0; 16];
-        self.output_kbps_samples = [0.0; 16];
         self.eventloop_cycles_per_sec_samples = [0; 16];
         self.eventloop_duration_usec_samples = [0; 16];
         self.net_input_last_sample_bytes = 0;
@@ -7564,8 +8540,9 @@ impl Store {
     }
 
     fn set_fits_intset(set: &SetValue, max_intset_entries: usize) -> bool {
-        // The intset encoding is now intrinsic: a `SetValue::Int` holds only
-        // canonical integers and is kept within the limit by promotion.
+        // A `SetValue::Int` holds only canonical integers and inserts keep it
+        // within the limit by promotion, but a set built elsewhere (RESTORE,
+        // RDB load) can arrive larger than the current limit.
         set.is_intset() && set.len() <= max_intset_entries
     }
 
@@ -7601,6 +8578,9 @@ impl Store {
         // either a non-integer member arrives or the cardinality
         // crosses set-max-intset-entries (default 512). Only when
         // intset stops fitting do we consider listpack vs hashtable.
+        // Nothing calls this on CONFIG SET, so an intset left above a
+        // since-lowered limit stays intset until its next integer add;
+        // a RESTORE'd or loaded one is checked against the limit here.
         if Self::set_fits_intset(set, max_intset_entries) {
             return;
         }
@@ -7723,9 +8703,9 @@ impl Store {
     /// reference path derives its flag from that final state. `INCR = false` preserves that exact
     /// pre-change scan as the same-binary benchmark reference.
     #[inline]
-    fn refresh_hash_encoding_after_batch<const INCR: bool>(
+    fn refresh_hash_encoding_after_batch<P: AsRef<[u8]>, const INCR: bool>(
         entry: &mut Entry,
-        pairs: &[&[u8]],
+        pairs: &[P],
         max_listpack_entries: usize,
         max_listpack_value: usize,
     ) {
@@ -7744,10 +8724,10 @@ impl Store {
             return;
         }
         let (pair_chunks, _) = pairs.as_chunks::<2>();
-        if pair_chunks
-            .iter()
-            .any(|pair| pair[0].len() > max_listpack_value || pair[1].len() > max_listpack_value)
-        {
+        if pair_chunks.iter().any(|pair| {
+            pair[0].as_ref().len() > max_listpack_value
+                || pair[1].as_ref().len() > max_listpack_value
+        }) {
             Self::refresh_hash_encoding_flag(entry, max_listpack_entries, max_listpack_value);
         }
     }
@@ -7913,13 +8893,7 @@ impl Store {
                     entry.touch_access(now_ms, false, lfu_decay, lfu_log_factor, 0);
                     match &entry.value {
                         Value::Integer(n) => SortWeight::Number(*n as f64),
-                        Value::String(b) => match std::str::from_utf8(b) {
-                            Ok(text) => match text.trim().parse::<f64>() {
-                                Ok(f) => SortWeight::Number(f),
-                                Err(_) => SortWeight::NotNumber,
-                            },
-                            Err(_) => SortWeight::NotNumber,
-                        },
+                        Value::String(b) => parse_sort_weight(b),
                         _ => SortWeight::Missing,
                     }
                 }
@@ -7953,13 +8927,7 @@ impl Store {
                 );
                 match &entry.value {
                     Value::Integer(n) => SortWeight::Number(*n as f64),
-                    Value::String(b) => match std::str::from_utf8(b) {
-                        Ok(text) => match text.trim().parse::<f64>() {
-                            Ok(f) => SortWeight::Number(f),
-                            Err(_) => SortWeight::NotNumber,
-                        },
-                        Err(_) => SortWeight::NotNumber,
-                    },
+                    Value::String(b) => parse_sort_weight(b),
                     _ => SortWeight::Missing,
                 }
             }
@@ -8133,6 +9101,10 @@ impl Store {
         self.get_string_bytes_lfu_impl::<false>(key, now_ms)
     }
 
+    /// Unconditional write: always replaces the value and bumps `dirty`, which is
+    /// what keyspace notifications and propagation key off. SET NX/XX, SETNX and
+    /// MSETNX must decide existence before calling this so a declined write stays
+    /// silent.
     pub fn set(&mut self, key: Vec<u8>, value: Vec<u8>, px_ttl_ms: Option<u64>, now_ms: u64) {
         self.set_impl::<true>(key, value, px_ttl_ms, now_ms);
     }
@@ -8184,6 +9156,9 @@ impl Store {
         self.dirty = self.dirty.saturating_add(1);
     }
 
+    /// Borrowed-key [`Store::set`] with no TTL. Like `set` it does not check
+    /// [`StoreLimits`]; callers run [`Store::check_string_write_quota`] first
+    /// or only take this path while the store is unlimited.
     pub fn set_plain_borrowed(&mut self, key: &[u8], value: &[u8], now_ms: u64) {
         // (CrimsonHawk) With no volatile keys (expires_count==0) drop_if_expired can never
         // evict, so its return value == entries.contains_key(key). Use the single
@@ -8268,7 +9243,7 @@ impl Store {
         if old_expiry.is_some() {
             self.expires_count = self.expires_count.saturating_sub(1);
             if db < self.database_count {
-                self.db_expires_counts[db] = self.db_expires_counts[db].saturating_sub(1);
+                db_count_sub(&mut self.db_expires_counts, db);
             }
         }
         self.dirty = self.dirty.saturating_add(1);
@@ -8531,7 +9506,7 @@ impl Store {
         if old_expiry.is_some() {
             self.expires_count = self.expires_count.saturating_sub(1);
             if db < self.database_count {
-                self.db_expires_counts[db] = self.db_expires_counts[db].saturating_sub(1);
+                db_count_sub(&mut self.db_expires_counts, db);
             }
         }
         self.dirty = self.dirty.saturating_add(1);
@@ -9163,7 +10138,7 @@ impl Store {
                 self.expires_count = self.expires_count.saturating_add(1);
                 let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                    db_count_add(&mut self.db_expires_counts, db);
                 }
             }
             // The key now carries a TTL; defer rebuilding the sorted sampling
@@ -9248,7 +10223,7 @@ impl Store {
                 self.expires_count = self.expires_count.saturating_add(1);
                 let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                    db_count_add(&mut self.db_expires_counts, db);
                 }
             }
             // The key now carries a TTL; defer rebuilding the sorted sampling
@@ -9306,7 +10281,7 @@ impl Store {
                 self.expires_count = self.expires_count.saturating_add(1);
                 let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                    db_count_add(&mut self.db_expires_counts, db);
                 }
             }
             self.mark_volatile_keys_dirty();
@@ -9392,6 +10367,7 @@ impl Store {
     }
 
     pub fn append(&mut self, key: &[u8], value: &[u8], now_ms: u64) -> Result<usize, StoreError> {
+        self.check_string_growth_quota(key, now_ms, |len| len.saturating_add(value.len()))?;
         self.append_impl::<true>(key, value, now_ms)
     }
 
@@ -9751,6 +10727,7 @@ impl Store {
         value: &[u8],
         now_ms: u64,
     ) -> Result<Option<Vec<u8>>, StoreError> {
+        self.check_string_write_quota(&[(&key, value.len())], now_ms)?;
         self.getset_impl::<true, true>(key, value, now_ms)
     }
 
@@ -10108,26 +11085,18 @@ impl Store {
         if start < 0 && end < 0 && start > end {
             return None;
         }
-        let len_i = len as i64;
         // Upstream normalizes negative offsets relative to length and clamps
         // BOTH start and end at 0 — clamping only start left a fully-negative
         // range like (-100, -90) on a length-6 string with end=-84 and the
         // 's > e' guard wrongly returned empty instead of the clamped slice
-        // [0..=0]. (br-frankenredis-grangneg)
-        let mut s = if start < 0 { len_i + start } else { start };
-        let mut e = if end < 0 { len_i + end } else { end };
-        if s < 0 {
-            s = 0;
-        }
-        if e < 0 {
-            e = 0;
-        }
-        if s > e || len == 0 || s >= len_i {
-            None
+        // [0..=0]. (br-frankenredis-grangneg) That end clamp is the one
+        // GETRANGE-only rule; the rest is the shared rank normalization.
+        let end = if end < 0 && normalize_index(end, len as i64) < 0 {
+            0
         } else {
-            let e_idx = e.min(len_i - 1) as usize;
-            Some((s as usize, e_idx))
-        }
+            end
+        };
+        normalize_range(start, end, len)
     }
 
     /// Borrowing GETRANGE: performs the same keyspace-lookup / LFU-bump / touch
@@ -10301,6 +11270,11 @@ impl Store {
         value: &[u8],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        if !value.is_empty() {
+            self.check_string_growth_quota(key, now_ms, |len| {
+                len.max(offset.saturating_add(value.len()))
+            })?;
+        }
         self.setrange_impl::<true>(key, offset, value, now_ms)
     }
 
@@ -10493,6 +11467,7 @@ impl Store {
         value: bool,
         now_ms: u64,
     ) -> Result<bool, StoreError> {
+        self.check_string_growth_quota(key, now_ms, |len| len.max(offset / 8 + 1))?;
         self.setbit_impl::<true>(key, offset, value, now_ms)
     }
 
@@ -10974,7 +11949,7 @@ impl Store {
                 let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
                 self.expires_count = self.expires_count.saturating_add(1);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                    db_count_add(&mut self.db_expires_counts, db);
                 }
             }
             if changed {
@@ -11065,8 +12040,7 @@ impl Store {
                         let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
                         self.expires_count = self.expires_count.saturating_add(1);
                         if db < self.database_count {
-                            self.db_expires_counts[db] =
-                                self.db_expires_counts[db].saturating_add(1);
+                            db_count_add(&mut self.db_expires_counts, db);
                         }
                     }
                     if changed {
@@ -11273,7 +12247,7 @@ impl Store {
                     if had_expiry {
                         self.expires_count = self.expires_count.saturating_add(1);
                         if db < self.database_count {
-                            self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                            db_count_add(&mut self.db_expires_counts, db);
                         }
                     }
                 }
@@ -11323,6 +12297,17 @@ impl Store {
         fr_simd::popcount_bytes(bytes)
     }
 
+    /// `popcount_bytes` in [`BUDGET_BYTES_PER_UNIT`] slices, charging the
+    /// command budget once per slice.
+    fn popcount_bytes_budgeted(bytes: &[u8]) -> Result<usize, StoreError> {
+        let mut count = 0;
+        for chunk in bytes.chunks(BUDGET_BYTES_PER_UNIT) {
+            budget_checkpoint()?;
+            count += Self::popcount_bytes(chunk);
+        }
+        Ok(count)
+    }
+
     #[inline]
     fn bitpos_masked_byte(raw: u8, bit: bool, keep_mask: u8) -> Option<usize> {
         let masked = if bit {
@@ -11366,6 +12351,39 @@ impl Store {
         self.bitcount_impl::<true>(key, start, end, unit, now_ms)
     }
 
+    /// Read side of `BITCOUNT` for a worker thread: does the lookup work
+    /// `bitcount` does (expiry reap, LFU bump, LRU touch) and returns the
+    /// string's bytes as a snapshot the caller can count after releasing the
+    /// store. A heap value is shared rather than copied; a later write to the
+    /// key copies it only while the snapshot is still alive.
+    pub fn string_snapshot(
+        &mut self,
+        key: &[u8],
+        now_ms: u64,
+    ) -> Result<Option<Arc<Vec<u8>>>, StoreError> {
+        if self.expires_count != 0 && !self.drop_if_expired(key, now_ms) {
+            return Ok(None);
+        }
+        let lfu_tracking_enabled = self.lfu_tracking_enabled();
+        let lfu_decay = self.lfu_decay_time;
+        let lfu_log_factor = self.lfu_log_factor;
+        let Some(entry) = self.entries.get_mut(key) else {
+            return Ok(None);
+        };
+        if lfu_tracking_enabled {
+            let rs = Self::lcg_next_seed(&mut self.rng_seed);
+            entry.bump_lfu_freq(now_ms, lfu_decay, lfu_log_factor, rs);
+        }
+        if !entry.value.is_string_like() {
+            return Err(StoreError::WrongType);
+        }
+        entry.touch(now_ms);
+        Ok(Some(match &mut entry.value {
+            Value::String(bytes) => bytes.share(),
+            value => Arc::new(value.string_owned().unwrap_or_default()),
+        }))
+    }
+
     /// A/B toggle for the LFU BITCOUNT keyspace-probe collapse. `COLLAPSE = true` (shipped) draws the
     /// LFU `rand_sample` on the disjoint `&mut self.rng_seed` field split INSIDE the `get_mut` borrow,
     /// dropping the separate `contains_key` LFU rand-gate (2 probes → 1 under allkeys-lfu). BITCOUNT is
@@ -11424,79 +12442,87 @@ impl Store {
                 let Some(v) = entry.value.string_bytes() else {
                     return Err(StoreError::WrongType);
                 };
-                let len = i64::try_from(v.len()).unwrap_or(i64::MAX);
-                let total_len = match unit {
-                    BitRangeUnit::Byte => len,
-                    BitRangeUnit::Bit => len.saturating_mul(8),
-                };
-                if total_len == 0 {
-                    return Ok(0);
-                }
+                Self::bitcount_bytes(&v, start, end, unit)
+            }
+            None => Ok(0),
+        }
+    }
 
-                let mut range_start = start.unwrap_or(0);
-                let mut range_end = end.unwrap_or(total_len - 1);
+    /// `BITCOUNT` over `bytes` with the command's optional range, without
+    /// touching any key. The store path and the server's worker pool (which
+    /// counts a [`Store::string_snapshot`]) both end here.
+    pub fn bitcount_bytes(
+        bytes: &[u8],
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: BitRangeUnit,
+    ) -> Result<usize, StoreError> {
+        let len = i64::try_from(bytes.len()).unwrap_or(i64::MAX);
+        let total_len = match unit {
+            BitRangeUnit::Byte => len,
+            BitRangeUnit::Bit => len.saturating_mul(8),
+        };
+        if total_len == 0 {
+            return Ok(0);
+        }
 
-                if start.is_some_and(|s| s < 0)
-                    && end.is_some_and(|e| e < 0)
-                    && range_start > range_end
-                {
-                    return Ok(0);
-                }
+        let mut range_start = start.unwrap_or(0);
+        let mut range_end = end.unwrap_or(total_len - 1);
 
-                if range_start < 0 {
-                    range_start += total_len;
-                }
-                if range_end < 0 {
-                    range_end += total_len;
-                }
-                if range_start < 0 {
-                    range_start = 0;
-                }
-                if range_end < 0 {
-                    range_end = 0;
-                }
-                if range_end >= total_len {
-                    range_end = total_len - 1;
-                }
-                if range_start > range_end {
-                    return Ok(0);
-                }
+        if start.is_some_and(|s| s < 0) && end.is_some_and(|e| e < 0) && range_start > range_end {
+            return Ok(0);
+        }
 
-                match unit {
-                    BitRangeUnit::Byte => {
-                        let start_idx =
-                            usize::try_from(range_start).expect("non-negative byte range start");
-                        let end_idx =
-                            usize::try_from(range_end).expect("non-negative byte range end");
-                        let end_idx_excl = end_idx + 1;
-                        Ok(Self::popcount_bytes(&v[start_idx..end_idx_excl]))
-                    }
-                    BitRangeUnit::Bit => {
-                        let start_byte = usize::try_from(range_start >> 3)
-                            .expect("non-negative bit range start byte");
-                        let end_byte = usize::try_from(range_end >> 3)
-                            .expect("non-negative bit range end byte");
-                        let mut count: usize = Self::popcount_bytes(&v[start_byte..=end_byte]);
-
-                        let first_byte_neg_mask =
-                            (!((1_u16 << (8 - ((range_start & 7) as u32))) - 1) & 0xFF) as u8;
-                        let last_byte_neg_mask =
-                            ((1_u16 << (7 - ((range_end & 7) as u32))) - 1) as u8;
-                        if first_byte_neg_mask != 0 || last_byte_neg_mask != 0 {
-                            let masked_edges = [
-                                v[start_byte] & first_byte_neg_mask,
-                                v[end_byte] & last_byte_neg_mask,
-                            ];
-                            count -= masked_edges
-                                .iter()
-                                .map(|b| b.count_ones() as usize)
-                                .sum::<usize>();
-                        }
-                        Ok(count)
-                    }
+        if range_start < 0 {
+            range_start += total_len;
+        }
+        if range_end < 0 {
+            range_end += total_len;
+        }
+        if range_start < 0 {
+            range_start = 0;
+        }
+        if range_end < 0 {
+            range_end = 0;
+        }
+        if range_end >= total_len {
+            range_end = total_len - 1;
+        }
+        if range_start > range_end {
+            return Ok(0);
+        }
+
+        match unit {
+            BitRangeUnit::Byte => {
+                let start_idx =
+                    usize::try_from(range_start).expect("non-negative byte range start");
+                let end_idx = usize::try_from(range_end).expect("non-negative byte range end");
+                let end_idx_excl = end_idx + 1;
+                Self::popcount_bytes_budgeted(&bytes[start_idx..end_idx_excl])
+            }
+            BitRangeUnit::Bit => {
+                let start_byte =
+                    usize::try_from(range_start >> 3).expect("non-negative bit range start byte");
+                let end_byte =
+                    usize::try_from(range_end >> 3).expect("non-negative bit range end byte");
+                let mut count: usize =
+                    Self::popcount_bytes_budgeted(&bytes[start_byte..=end_byte])?;
+
+                let first_byte_neg_mask =
+                    (!((1_u16 << (8 - ((range_start & 7) as u32))) - 1) & 0xFF) as u8;
+                let last_byte_neg_mask = ((1_u16 << (7 - ((range_end & 7) as u32))) - 1) as u8;
+                if first_byte_neg_mask != 0 || last_byte_neg_mask != 0 {
+                    let masked_edges = [
+                        bytes[start_byte] & first_byte_neg_mask,
+                        bytes[end_byte] & last_byte_neg_mask,
+                    ];
+                    count -= masked_edges
+                        .iter()
+                        .map(|b| b.count_ones() as usize)
+                        .sum::<usize>();
                 }
+                Ok(count)
             }
-            None => Ok(0),
         }
     }
 
@@ -11712,7 +12738,7 @@ impl Store {
         self.expires_count = self.expires_count.saturating_sub(1);
         let db = decode_db_key(key).map(|(db, _)| db).unwrap_or(0);
         if db < self.database_count {
-            self.db_expires_counts[db] = self.db_expires_counts[db].saturating_sub(1);
+            db_count_sub(&mut self.db_expires_counts, db);
         }
         self.dirty = self.dirty.saturating_add(1);
         true
@@ -11886,6 +12912,22 @@ impl Store {
         }
     }
 
+    /// Read-only, no-stat size of the value at `key`: bytes for a string,
+    /// elements for a collection, 0 for a missing/expired key. A cost estimate
+    /// for callers choosing where to run a command; touches no stats or LRU.
+    #[must_use]
+    pub fn value_size_no_stats(&self, key: &[u8], now_ms: u64) -> usize {
+        if self.peek_value_type(key, now_ms).is_none() {
+            return 0;
+        }
+        self.entries.get(key).map_or(0, |entry| {
+            entry
+                .value
+                .string_len()
+                .unwrap_or_else(|| entry.value.element_count())
+        })
+    }
+
     /// Read-only, no-stat PTTL: like [`Self::pttl`] but does NOT bump
     /// keyspace_hits/misses. For write-command TTL prechecks (EXPIRE NX/XX/GT/LT
     /// remaining-TTL comparison) that mirror lookupKeyWrite. (frankenredis-934ax)
@@ -11913,12 +12955,39 @@ impl Store {
         }
     }
 
+    /// Read-only, no-stat absolute deadline: like [`Self::expiretime_value`] but
+    /// does NOT bump keyspace_hits/misses or reap a due key. For the EXPIRE
+    /// NX/XX/GT/LT comparison, which must order the stored deadline itself:
+    /// `now + PTTL` saturates once the remaining time passes `i64::MAX`.
+    #[must_use]
+    pub fn expiretime_no_stats(&self, key: &[u8], now_ms: u64) -> ExpireTimeValue {
+        if !self.entries.contains_key(key) {
+            return ExpireTimeValue::KeyMissing;
+        }
+        let deadline = if self.expires_count != 0 {
+            self.expiry_ms(key)
+        } else {
+            None
+        };
+        match deadline {
+            Some(expires_at_ms) if now_ms > expires_at_ms => ExpireTimeValue::KeyMissing,
+            Some(expires_at_ms) => ExpireTimeValue::ExpiresAt(expires_at_ms),
+            None => ExpireTimeValue::NoExpiry,
+        }
+    }
+
     /// Return the Redis-compatible encoding name for the value at `key`.
     #[must_use]
     pub fn object_encoding(&mut self, key: &[u8], now_ms: u64) -> Option<&'static str> {
         if !self.record_keyspace_lookup(key, now_ms) {
             return None;
         }
+        self.live_key_encoding(key)
+    }
+
+    /// [`Self::object_encoding`] for a key the caller already looked up (expired
+    /// keys reaped), without recording another keyspace hit.
+    fn live_key_encoding(&self, key: &[u8]) -> Option<&'static str> {
         // Redis 7.4: any hash carrying a per-field TTL flips its encoding
         // from listpack/hashtable to the listpack_ex / hashtable_ex variant.
         // (br-frankenredis-omff)
@@ -11939,7 +13008,9 @@ impl Store {
                     && n.to_string() == s
                 {
                     "int"
-                } else if !entry.has_flag(ENTRY_FORCE_RAW_ENCODING) && v.len() <= 44 {
+                } else if !entry.has_flag(ENTRY_FORCE_RAW_ENCODING)
+                    && v.len() <= OBJ_ENCODING_EMBSTR_SIZE_LIMIT
+                {
                     "embstr"
                 } else {
                     "raw"
@@ -12457,6 +13528,9 @@ impl Store {
         }
     }
 
+    /// Keys of `db` matching `pattern`, sorted. The walk stops early once the
+    /// command budget runs out, so a caller running under
+    /// [`with_command_budget`] must follow up with [`budget_checkpoint`].
     #[must_use]
     pub fn keys_matching_in_db(&mut self, db: usize, pattern: &[u8], now_ms: u64) -> Vec<Vec<u8>> {
         self.rebuild_ordered_keys_if_dirty();
@@ -12491,7 +13565,7 @@ impl Store {
             let pg = glob_prepare(pattern);
             let mut result: Vec<Vec<u8>> = Vec::new();
             if db == 0 {
-                for key in self.ordered_keys.iter() {
+                for key in self.ordered_keys.iter().take_while(within_budget) {
                     if decode_db_key(key).is_some() {
                         continue;
                     }
@@ -12506,6 +13580,7 @@ impl Store {
                         std::ops::Bound::Unbounded,
                     ))
                     .take_while(|key| key.starts_with(&prefix))
+                    .take_while(within_budget)
                 {
                     Self::push_logical_key_if_match(&mut result, key, &pg, is_star);
                 }
@@ -12529,6 +13604,7 @@ impl Store {
                         std::ops::Bound::Excluded(end.as_slice()),
                     ))
                     .filter(|k| in_db(k))
+                    .take_while(within_budget)
                     .map(|k| k.to_vec())
                     .collect()
             } else {
@@ -12538,6 +13614,7 @@ impl Store {
                         std::ops::Bound::Unbounded,
                     ))
                     .filter(|k| in_db(k))
+                    .take_while(within_budget)
                     .map(|k| k.to_vec())
                     .collect()
             }
@@ -12582,6 +13659,68 @@ impl Store {
         result
     }
 
+    /// Borrow-scan variant of `keys_matching_in_db` for the zero-copy KEYS reply:
+    /// hands `sink` each matching logical key by borrow, in the same sorted
+    /// order, and returns how many matched, so a huge KEYS reply is encoded
+    /// without a `Vec<Vec<u8>>` of matches. The glob runs once per key; a caller
+    /// that needs the count ahead of the keys (a RESP header) patches it in
+    /// afterwards. Expiry side effects match the owned form: a full-DB pattern reaps the db's due volatile keys, a
+    /// prefix-pruned pattern reaps only the volatile keys under its prefix.
+    pub fn keys_borrow_scan(
+        &mut self,
+        db: usize,
+        pattern: &[u8],
+        now_ms: u64,
+        mut sink: impl FnMut(&[u8]),
+    ) -> usize {
+        self.rebuild_ordered_keys_if_dirty();
+        let lit = glob_literal_prefix(pattern);
+        let lower = encode_db_key(db, lit);
+        if lit.is_empty() {
+            self.expire_volatile_keys_in_db(db, now_ms);
+        } else if self.expires_count != 0 {
+            // drop_if_expired is a no-op on keys without a TTL, so reaping just
+            // the volatile candidates evicts the same set as the owned walk.
+            let volatile: Vec<Vec<u8>> = self
+                .ordered_keys_in_db_from(db, &lower)
+                .filter(|(physical, _)| self.expiry_deadlines.contains_key(*physical))
+                .map(|(physical, _)| physical.to_vec())
+                .collect();
+            for key in &volatile {
+                self.drop_if_expired(key, now_ms);
+            }
+        }
+        self.rebuild_ordered_keys_if_dirty();
+        let is_star = pattern == b"*";
+        let pg = glob_prepare(pattern);
+        let mut matches = 0;
+        for (_, logical) in self.ordered_keys_in_db_from(db, &lower) {
+            if is_star || pg.matches(logical) {
+                matches += 1;
+                sink(logical);
+            }
+        }
+        matches
+    }
+
+    /// `(physical, logical)` names of `db`'s keys whose physical name starts
+    /// with `lower` (an `encode_db_key(db, prefix)`), walked in sorted order.
+    /// Expects `ordered_keys` to be in sync.
+    fn ordered_keys_in_db_from<'a>(
+        &'a self,
+        db: usize,
+        lower: &'a [u8],
+    ) -> impl Iterator<Item = (&'a [u8], &'a [u8])> + 'a {
+        self.ordered_keys
+            .range::<[u8], _>((std::ops::Bound::Included(lower), std::ops::Bound::Unbounded))
+            .take_while(move |key| key.starts_with(lower))
+            .filter_map(move |key| match decode_db_key(key) {
+                Some((_, logical)) if db != 0 => Some((&**key, logical)),
+                None if db == 0 => Some((&**key, &**key)),
+                _ => None,
+            })
+    }
+
     #[must_use]
     pub fn dbsize(&self, _now_ms: u64) -> usize {
         // DBSIZE must be O(1). We do not actively reap expired keys here
@@ -12590,10 +13729,40 @@ impl Store {
         self.entries.len()
     }
 
+    /// Version reported as INFO's `redis_version`, HELLO's `version` and the
+    /// LOLWUT footer: the `server-version` override when set, otherwise
+    /// [`REDIS_COMPAT_VERSION`].
+    #[must_use]
+    pub fn redis_version(&self) -> &str {
+        self.redis_version_override
+            .as_deref()
+            .unwrap_or(REDIS_COMPAT_VERSION)
+    }
+
+    /// Set the logical database count (the `databases` directive). Meant for
+    /// startup, before any data is loaded: the per-db counters only track
+    /// databases below `database_count`. They grow on a database's first
+    /// write rather than here, so `databases 2147483647` costs nothing until
+    /// those databases are used.
+    pub fn set_database_count(&mut self, count: usize) {
+        self.database_count = count;
+        self.db_key_counts.truncate(count);
+        self.db_expires_counts.truncate(count);
+        self.random_key_slots.truncate(count);
+    }
+
+    /// Upper bound on the databases that can hold keys: every database at or
+    /// past it is empty. Whole-keyspace reports (INFO keyspace, MEMORY STATS)
+    /// walk `0..tracked_database_count()` instead of `0..database_count`.
+    #[must_use]
+    pub fn tracked_database_count(&self) -> usize {
+        self.db_key_counts.len().min(self.database_count)
+    }
+
     #[must_use]
     pub fn dbsize_in_db(&self, db: usize) -> usize {
         if db < self.database_count {
-            self.db_key_counts[db]
+            self.db_key_counts.get(db).copied().unwrap_or(0)
         } else {
             0
         }
@@ -12602,7 +13771,7 @@ impl Store {
     #[must_use]
     pub fn expires_in_db(&self, db: usize) -> usize {
         if db < self.database_count {
-            self.db_expires_counts[db]
+            self.db_expires_counts.get(db).copied().unwrap_or(0)
         } else {
             0
         }
@@ -12613,7 +13782,7 @@ impl Store {
         if db >= self.database_count {
             return 0;
         }
-        let count = self.db_expires_counts[db];
+        let count = self.expires_in_db(db);
         if count == 0 {
             return 0;
         }
@@ -12629,8 +13798,8 @@ impl Store {
         const AVG_TTL_SAMPLE_TTL_MS: u64 = 1000;
         let deadline_sum = {
             let mut cache = self.avg_ttl_deadline_sum_cache.borrow_mut();
-            if cache.len() < self.database_count {
-                cache.resize(self.database_count, None);
+            if cache.len() <= db {
+                cache.resize(db + 1, None);
             }
             let cached = match cache[db] {
                 Some((sum, cached_count, at))
@@ -12673,6 +13842,10 @@ impl Store {
     /// HSET/SADD/ZADD into the same key) this allocates NOTHING — previously
     /// every caller eagerly built `key.to_vec()` + a boxed empty collection that
     /// were thrown away when the key already existed. Byte-identical behaviour.
+    ///
+    /// This is the modify-in-place path: an existing entry keeps its TTL, which is
+    /// Redis's rule for APPEND/INCR/LPUSH/HSET/SADD/ZADD/XADD. Whole-value
+    /// replacement goes through `internal_entries_insert_with_expiry` instead.
     fn internal_entry(
         &mut self,
         key: &[u8],
@@ -13037,6 +14210,9 @@ impl Store {
         self.internal_entries_insert_with_expiry(key, entry, None)
     }
 
+    /// Whole-value replacement: the key's expiry becomes exactly `expires_at_ms`,
+    /// so SET (without KEEPTTL), GETSET and MSET clear any prior TTL. Callers that
+    /// must keep the TTL pass the existing deadline back in (SET KEEPTTL).
     fn internal_entries_insert_with_expiry(
         &mut self,
         key: Vec<u8>,
@@ -13064,13 +14240,16 @@ impl Store {
         let new_is_stream = matches!(&entry.value, Value::Stream(_));
         if let Some(old_entry) = self.entries.get(key.as_slice()) {
             entry.modification_count = old_entry.modification_count.wrapping_add(1);
+        } else {
+            self.key_version_floor = self.key_version_floor.wrapping_add(1);
+            entry.modification_count = self.key_version_floor;
         }
 
         let new_has_expiry = new_expiry.is_some();
         if new_has_expiry {
             self.expires_count = self.expires_count.saturating_add(1);
             if db < self.database_count {
-                self.db_expires_counts[db] = self.db_expires_counts[db].saturating_add(1);
+                db_count_add(&mut self.db_expires_counts, db);
             }
         }
         // For a NEW persistent key, allocate only the canonical boxed key bytes.
@@ -13194,13 +14373,13 @@ impl Store {
             if old_expiry.is_some() {
                 self.expires_count = self.expires_count.saturating_sub(1);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_sub(1);
+                    db_count_sub(&mut self.db_expires_counts, db);
                 }
             }
             Some(old)
         } else {
             if db < self.database_count {
-                self.db_key_counts[db] = self.db_key_counts[db].saturating_add(1);
+                db_count_add(&mut self.db_key_counts, db);
             }
             None
         }
@@ -13221,9 +14400,28 @@ impl Store {
         }
     }
 
+    /// Drop `key` once a removal has emptied its hash, list, set or sorted
+    /// set: upstream deletes the key with its last element (`dbDelete` when
+    /// the type's length reaches 0). Every element-removing method ends here,
+    /// so the entry, its TTL and any side tables leave together. Streams are
+    /// the exception and never come here: an emptied stream keeps its key,
+    /// last ID and consumer groups.
+    fn after_mutation_cleanup(&mut self, key: &[u8]) {
+        debug_assert!(
+            !matches!(
+                self.entries.get(key).map(|entry| &entry.value),
+                Some(Value::Stream(_))
+            ),
+            "an emptied stream keeps its key"
+        );
+        self.internal_entries_remove(key);
+        self.drop_stream_side_metadata(key);
+    }
+
     fn internal_entries_remove(&mut self, key: &[u8]) -> Option<Entry> {
         let old_expiry = self.expiry_ms(key);
         if let Some(entry) = self.entries.remove(key) {
+            self.key_version_floor = self.key_version_floor.max(entry.modification_count);
             self.invalidate_write_side_caches(key);
             self.mark_ordered_keys_dirty();
             // (cc_fr) `old_expiry` came from `expiry_ms` == `expiry_deadlines.get(key)`, so
@@ -13252,12 +14450,12 @@ impl Store {
             self.keyspace_generation = self.keyspace_generation.wrapping_add(1);
             self.update_expiry_deadline(old_expiry, None);
             if db < self.database_count {
-                self.db_key_counts[db] = self.db_key_counts[db].saturating_sub(1);
+                db_count_sub(&mut self.db_key_counts, db);
             }
             if old_expiry.is_some() {
                 self.expires_count = self.expires_count.saturating_sub(1);
                 if db < self.database_count {
-                    self.db_expires_counts[db] = self.db_expires_counts[db].saturating_sub(1);
+                    db_count_sub(&mut self.db_expires_counts, db);
                 }
             }
             // (frankenredis-ne7sg) A collection emptied in place — the whole-key-removal
@@ -13270,8 +14468,8 @@ impl Store {
             // rather than an incorrect incremental XOR. Clean removals (DEL / expiry of a
             // non-empty entry, string values) keep the fast incremental path.
             let mutated_to_empty = match &entry.value {
-                Value::Hash(h) => h.len() == 0,
-                Value::List(l) => l.len() == 0,
+                Value::Hash(h) => h.is_empty(),
+                Value::List(l) => l.is_empty(),
                 Value::Set(s) => s.is_empty(),
                 Value::SortedSet(zs) => zs.is_empty(),
                 Value::Stream(_) | Value::String(_) | Value::Integer(_) => false,
@@ -13293,7 +14491,10 @@ impl Store {
             // field_expires map doesn't accumulate orphan rows.
             // (br-frankenredis-b8ut)
             self.hash_field_ttl_clear_for_key(key);
-            if matches!(&entry.value, Value::Stream(_)) {
+            if let Value::Stream(entries) = &entry.value {
+                if self.stream_preserve_last_id_on_delete {
+                    self.remember_stream_last_id(key, entries.last_id());
+                }
                 self.stream_entries_added.remove(key);
                 self.stream_max_deleted_ids.remove(key);
             }
@@ -13387,8 +14588,7 @@ impl Store {
         }
         if became_empty {
             // Upstream behavior: a hash with no fields is removed entirely.
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         reaped
     }
@@ -13429,8 +14629,7 @@ impl Store {
             self.notify_hash_field_expired(key);
         }
         if became_empty {
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         removed
     }
@@ -13717,12 +14916,20 @@ impl Store {
     }
 
     pub fn flushdb(&mut self) {
+        let flushed: BTreeSet<usize> = self.entries.keys().map(|key| key_db(key)).collect();
+        self.bump_flush_generations(flushed);
+        self.key_version_floor = self
+            .entries
+            .values()
+            .map(|entry| entry.modification_count)
+            .fold(self.key_version_floor, u64::max);
         self.entries.clear();
         self.stream_groups.clear();
         self.stream_pel_summary_cache.clear();
         self.stream_last_ids.clear();
         self.stream_entries_added.clear();
         self.stream_max_deleted_ids.clear();
+        self.stream_last_id_tombstones.clear();
         // (frankenredis-ss2k0) ordered_keys backs KEYS/SCAN/RANDOMKEY;
         // failing to clear it left ghost names visible after FLUSHALL
         // even though entries.is_empty() == true. hash_field_expires
@@ -13798,6 +15005,8 @@ impl Store {
             .map(|k| k.to_vec())
             .collect();
         let removed = keys.len() as u64;
+        let flushed: BTreeSet<usize> = keys.iter().map(|key| key_db(key)).collect();
+        self.bump_flush_generations(flushed);
         for key in keys {
             self.internal_entries_remove(&key);
             self.stream_groups.remove(key.as_slice());
@@ -13805,6 +15014,10 @@ impl Store {
             self.stream_entries_added.remove(key.as_slice());
             self.stream_max_deleted_ids.remove(key.as_slice());
         }
+        if !self.stream_last_id_tombstones.is_empty() {
+            self.stream_last_id_tombstones
+                .retain(|key, _| !key.starts_with(prefix));
+        }
         if self.entries.is_empty() {
             self.release_empty_keyspace_capacity();
         }
@@ -13824,6 +15037,9 @@ impl Store {
             .map(|k| k.to_vec())
             .collect();
         let removed = keys.len() as u64;
+        if removed > 0 {
+            self.bump_flush_generations([db]);
+        }
         for key in keys {
             self.internal_entries_remove(&key);
             self.stream_groups.remove(key.as_slice());
@@ -13831,6 +15047,13 @@ impl Store {
             self.stream_entries_added.remove(key.as_slice());
             self.stream_max_deleted_ids.remove(key.as_slice());
         }
+        if !self.stream_last_id_tombstones.is_empty() {
+            self.stream_last_id_tombstones.retain(|key, _| {
+                decode_db_key(key)
+                    .map(|(entry_db, _)| entry_db != db)
+                    .unwrap_or(db != 0)
+            });
+        }
         self.clear_random_key_index(db);
         if self.entries.is_empty() {
             self.release_empty_keyspace_capacity();
@@ -14133,6 +15356,13 @@ impl Store {
         value: Vec<u8>,
         now_ms: u64,
     ) -> Result<bool, StoreError> {
+        self.check_insert_quota(
+            key,
+            ValueType::Hash,
+            [field.as_slice()],
+            [value.as_slice()],
+            now_ms,
+        )?;
         self.hset_impl::<true, true>(key, field, value, now_ms)
     }
 
@@ -14360,13 +15590,20 @@ impl Store {
     /// non-empty target or any duplicate field falls back to the same incremental
     /// inserts. Under an LFU policy each field's PRNG/freq advance is observable,
     /// so that case delegates field-by-field.
-    pub fn hset_borrowed_many(
+    pub fn hset_borrowed_many<P: AsRef<[u8]>>(
         &mut self,
         key: &[u8],
-        pairs: &[&[u8]],
+        pairs: &[P],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
-        self.hset_borrowed_many_impl::<true>(key, pairs, now_ms)
+        self.check_insert_quota(
+            key,
+            ValueType::Hash,
+            pairs.iter().step_by(2).map(AsRef::as_ref),
+            pairs.iter().skip(1).step_by(2).map(AsRef::as_ref),
+            now_ms,
+        )?;
+        self.hset_borrowed_many_impl::<P, true>(key, pairs, now_ms)
     }
 
     /// Bench-only baseline for variadic HSET/HMSET with the pre-change O(n) encoding re-scan.
@@ -14377,20 +15614,20 @@ impl Store {
         pairs: &[&[u8]],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
-        self.hset_borrowed_many_impl::<false>(key, pairs, now_ms)
+        self.hset_borrowed_many_impl::<_, false>(key, pairs, now_ms)
     }
 
-    fn hset_borrowed_many_impl<const INCR: bool>(
+    fn hset_borrowed_many_impl<P: AsRef<[u8]>, const INCR: bool>(
         &mut self,
         key: &[u8],
-        pairs: &[&[u8]],
+        pairs: &[P],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
         if pairs.len() < 2 {
             return Ok(0);
         }
         if self.lfu_tracking_enabled() {
-            return self.hset_borrowed_many_lfu_batched::<INCR>(key, pairs, now_ms);
+            return self.hset_borrowed_many_lfu_batched::<P, INCR>(key, pairs, now_ms);
         }
         // (CrimsonHawk) Skip the always-2-lookup drop_if_expired when no key has a TTL
         // (internal_entry below re-probes). Byte-identical; return value unused. See append.
@@ -14409,13 +15646,17 @@ impl Store {
         let added = Self::hset_borrowed_many_apply_map(m, pairs, count);
         entry.touch_lru(now_ms);
         entry.modification_count = entry.modification_count.wrapping_add(count);
-        Self::refresh_hash_encoding_after_batch::<INCR>(entry, pairs, max_entries, max_value);
+        Self::refresh_hash_encoding_after_batch::<P, INCR>(entry, pairs, max_entries, max_value);
         Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
         self.dirty = self.dirty.saturating_add(count);
         Ok(added)
     }
 
-    fn hset_borrowed_many_apply_map(m: &mut HashFieldMap, pairs: &[&[u8]], count: u64) -> usize {
+    fn hset_borrowed_many_apply_map<P: AsRef<[u8]>>(
+        m: &mut HashFieldMap,
+        pairs: &[P],
+        count: u64,
+    ) -> usize {
         let (pair_chunks, _) = pairs.as_chunks::<2>();
         if m.is_empty() {
             if let Some((map, hadded)) = HashFieldMap::try_from_flat_pairs_hash_dedup(pairs) {
@@ -14427,17 +15668,21 @@ impl Store {
                         count as usize,
                         foldhash::quality::RandomState::default(),
                     );
-                let unique = pair_chunks.iter().all(|pair| seen.insert(pair[0]));
+                let unique = pair_chunks.iter().all(|pair| seen.insert(pair[0].as_ref()));
                 drop(seen);
                 if unique {
-                    let borrowed: Vec<(&[u8], &[u8])> =
-                        pair_chunks.iter().map(|pair| (pair[0], pair[1])).collect();
+                    let borrowed: Vec<(&[u8], &[u8])> = pair_chunks
+                        .iter()
+                        .map(|pair| (pair[0].as_ref(), pair[1].as_ref()))
+                        .collect();
                     *m = HashFieldMap::from_unique_pairs_borrowed(&borrowed);
                     count as usize
                 } else {
                     let mut a = 0_usize;
                     for pair in pair_chunks {
-                        if m.insert(pair[0].to_vec(), pair[1].to_vec()).is_none() {
+                        if m.insert(pair[0].as_ref().to_vec(), pair[1].as_ref().to_vec())
+                            .is_none()
+                        {
                             a += 1;
                         }
                     }
@@ -14449,7 +15694,9 @@ impl Store {
         } else {
             let mut a = 0_usize;
             for pair in pair_chunks {
-                if m.insert(pair[0].to_vec(), pair[1].to_vec()).is_none() {
+                if m.insert(pair[0].as_ref().to_vec(), pair[1].as_ref().to_vec())
+                    .is_none()
+                {
                     a += 1;
                 }
             }
@@ -14457,10 +15704,10 @@ impl Store {
         }
     }
 
-    fn hset_borrowed_many_lfu_batched<const INCR: bool>(
+    fn hset_borrowed_many_lfu_batched<P: AsRef<[u8]>, const INCR: bool>(
         &mut self,
         key: &[u8],
-        pairs: &[&[u8]],
+        pairs: &[P],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
         if self.expires_count != 0 {
@@ -14474,7 +15721,7 @@ impl Store {
         if matches!(key_state, Some(Err(_))) {
             let mut added = 0_usize;
             for pair in pair_chunks {
-                if self.hset_borrowed(key, pair[0], pair[1].to_vec(), now_ms)? {
+                if self.hset_borrowed(key, pair[0].as_ref(), pair[1].as_ref().to_vec(), now_ms)? {
                     added += 1;
                 }
             }
@@ -14518,7 +15765,7 @@ impl Store {
         let added = Self::hset_borrowed_many_apply_map(m, pairs, count);
         entry.touch(now_ms);
         entry.modification_count = entry.modification_count.wrapping_add(count);
-        Self::refresh_hash_encoding_after_batch::<INCR>(entry, pairs, max_entries, max_value);
+        Self::refresh_hash_encoding_after_batch::<P, INCR>(entry, pairs, max_entries, max_value);
         Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
         self.dirty = self.dirty.saturating_add(count);
         Ok(added)
@@ -14609,6 +15856,7 @@ impl Store {
         value: Vec<u8>,
         now_ms: u64,
     ) -> Result<bool, StoreError> {
+        self.check_insert_quota(key, ValueType::Hash, [field], [value.as_slice()], now_ms)?;
         self.hset_borrowed_impl::<true, true>(key, field, value, now_ms)
     }
 
@@ -15073,8 +16321,7 @@ impl Store {
             }
         }
         if is_empty {
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         Ok(removed)
     }
@@ -15290,6 +16537,19 @@ impl Store {
         &mut self,
         key: &[u8],
         now_ms: u64,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StoreError> {
+        let mut pairs = self.hgetall_in_storage_order(key, now_ms)?;
+        if self.deterministic_iteration {
+            pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok(pairs)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn hgetall_in_storage_order(
+        &mut self,
+        key: &[u8],
+        now_ms: u64,
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StoreError> {
         if !self.record_keyspace_lookup(key, now_ms) {
             return Ok(Vec::new());
@@ -15621,6 +16881,18 @@ impl Store {
     }
 
     pub fn hkeys(&mut self, key: &[u8], now_ms: u64) -> Result<Vec<Vec<u8>>, StoreError> {
+        let mut fields = self.hkeys_in_storage_order(key, now_ms)?;
+        if self.deterministic_iteration {
+            fields.sort_unstable();
+        }
+        Ok(fields)
+    }
+
+    fn hkeys_in_storage_order(
+        &mut self,
+        key: &[u8],
+        now_ms: u64,
+    ) -> Result<Vec<Vec<u8>>, StoreError> {
         // (CrimsonHawk) Field-TTL-gated non-LFU single-lookup collapse — see `hexists`. With no
         // per-field TTLs anywhere (HEXPIRE unused — the overwhelmingly common case) the
         // `drop_expired_hash_fields` reap is a no-op, so fold `record_keyspace_lookup` + `get_mut`
@@ -15718,6 +16990,13 @@ impl Store {
     }
 
     pub fn hvals(&mut self, key: &[u8], now_ms: u64) -> Result<Vec<Vec<u8>>, StoreError> {
+        if self.deterministic_iteration {
+            // Values follow their fields' sorted order. HGETALL shares HVALS's
+            // lookup/reap/LFU/touch preamble, so the bookkeeping is unchanged.
+            let mut pairs = self.hgetall_in_storage_order(key, now_ms)?;
+            pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            return Ok(pairs.into_iter().map(|(_, value)| value).collect());
+        }
         if !self.record_keyspace_lookup(key, now_ms) {
             return Ok(Vec::new());
         }
@@ -16103,7 +17382,7 @@ impl Store {
             self.dirty = self.dirty.saturating_add(1);
         }
         if is_empty {
-            self.internal_entries_remove(key);
+            self.after_mutation_cleanup(key);
         }
         res
     }
@@ -16114,6 +17393,13 @@ impl Store {
         value: Vec<u8>,
         now_ms: u64,
     ) -> Result<bool, StoreError> {
+        self.check_insert_quota(
+            key,
+            ValueType::Hash,
+            [field.as_slice()],
+            [value.as_slice()],
+            now_ms,
+        )?;
         self.hsetnx_impl::<true, true, true>(key, field, value, now_ms)
     }
 
@@ -16435,7 +17721,10 @@ impl Store {
             // (cc_fr) Length of the just-inserted value; drives the O(1) incremental refresh.
             let mut new_value_len = 0usize;
             let current_res = match m.get(field) {
-                Some(v) => add_float_text(v, delta_text, delta),
+                Some(v) => add_float_text(v, delta_text, delta).map_err(|err| match err {
+                    StoreError::ValueNotFloat => StoreError::HashValueNotFloat,
+                    other => other,
+                }),
                 None => add_float_text(b"0", delta_text, delta),
             };
             let res = match current_res {
@@ -16484,7 +17773,7 @@ impl Store {
             self.dirty = self.dirty.saturating_add(1);
         }
         if is_empty {
-            self.internal_entries_remove(key);
+            self.after_mutation_cleanup(key);
         }
         res
     }
@@ -16898,6 +18187,13 @@ impl Store {
         values: &[M],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        self.check_append_quota(
+            key,
+            ValueType::List,
+            values.iter().map(AsRef::as_ref),
+            values.len(),
+            now_ms,
+        )?;
         self.lpush_impl::<M, true, true, true>(key, values, now_ms)
     }
 
@@ -17050,6 +18346,13 @@ impl Store {
         values: &[M],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        self.check_append_quota(
+            key,
+            ValueType::List,
+            values.iter().map(AsRef::as_ref),
+            values.len(),
+            now_ms,
+        )?;
         self.rpush_impl::<M, true>(key, values, now_ms)
     }
 
@@ -17290,8 +18593,7 @@ impl Store {
                             self.dirty = self.dirty.saturating_add(1);
                         }
                         if l.is_empty() {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if val.is_some() {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -17367,8 +18669,7 @@ impl Store {
                             self.dirty = self.dirty.saturating_add(result.len() as u64);
                         }
                         if l.is_empty() {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if !result.is_empty() {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -17453,8 +18754,7 @@ impl Store {
                             self.dirty = self.dirty.saturating_add(1);
                         }
                         if l.is_empty() {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if val.is_some() {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -17530,8 +18830,7 @@ impl Store {
                             self.dirty = self.dirty.saturating_add(result.len() as u64);
                         }
                         if l.is_empty() {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if !result.is_empty() {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -17698,14 +18997,9 @@ impl Store {
                 }
                 match &entry.value {
                     Value::List(l) => {
-                        let len = l.len() as i64;
-                        let s = normalize_index(start, len).max(0);
-                        let e = normalize_index(stop, len).min(len - 1);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s, e)) = normalize_range(start, stop, l.len()) else {
                             return Ok(Vec::new());
-                        }
-                        let s = s as usize;
-                        let e = e as usize;
+                        };
                         // (frankenredis-3r9lz) Seek to `s` at the chunk level
                         // (O(s/chunk)) instead of an O(s) element-by-element skip.
                         let result: Vec<Vec<u8>> =
@@ -17761,15 +19055,10 @@ impl Store {
             return match self.lookup_live_for_read_mut(key, now_ms) {
                 Some(entry) => match &entry.value {
                     Value::List(l) => {
-                        let len = l.len() as i64;
-                        let s = normalize_index(start, len).max(0);
-                        let e = normalize_index(stop, len).min(len - 1);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s, e)) = normalize_range(start, stop, l.len()) else {
                             sink(SmembersScanEvent::Len(0));
                             return Ok(());
-                        }
-                        let s = s as usize;
-                        let e = e as usize;
+                        };
                         sink(SmembersScanEvent::Len(e - s + 1));
                         for m in l.iter_from(s).take(e - s + 1) {
                             sink(SmembersScanEvent::Member(m));
@@ -17804,15 +19093,10 @@ impl Store {
                     entry.bump_lfu_freq(now_ms, lfu_decay, lfu_log_factor, rand_sample);
                     match &entry.value {
                         Value::List(l) => {
-                            let len = l.len() as i64;
-                            let s = normalize_index(start, len).max(0);
-                            let e = normalize_index(stop, len).min(len - 1);
-                            if s > e || s >= len || e < 0 {
+                            let Some((s, e)) = normalize_range(start, stop, l.len()) else {
                                 sink(SmembersScanEvent::Len(0));
                                 return Ok(());
-                            }
-                            let s = s as usize;
-                            let e = e as usize;
+                            };
                             sink(SmembersScanEvent::Len(e - s + 1));
                             for m in l.iter_from(s).take(e - s + 1) {
                                 sink(SmembersScanEvent::Member(m));
@@ -17847,15 +19131,10 @@ impl Store {
                 }
                 match &entry.value {
                     Value::List(l) => {
-                        let len = l.len() as i64;
-                        let s = normalize_index(start, len).max(0);
-                        let e = normalize_index(stop, len).min(len - 1);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s, e)) = normalize_range(start, stop, l.len()) else {
                             sink(SmembersScanEvent::Len(0));
                             return Ok(());
-                        }
-                        let s = s as usize;
-                        let e = e as usize;
+                        };
                         sink(SmembersScanEvent::Len(e - s + 1));
                         for m in l.iter_from(s).take(e - s + 1) {
                             sink(SmembersScanEvent::Member(m));
@@ -18599,8 +19878,7 @@ impl Store {
                         if removed > 0 {
                             self.dirty = self.dirty.saturating_add(removed);
                             if l.is_empty() {
-                                self.internal_entries_remove(key);
-                                self.drop_stream_side_metadata(key);
+                                self.after_mutation_cleanup(key);
                             } else {
                                 Self::mark_digest_stale_fields(
                                     &mut self.digest_stale,
@@ -18715,12 +19993,7 @@ impl Store {
             && let Value::List(l) = &entry.value
             && l.is_empty()
         {
-            self.internal_entries_remove(source);
-            // (frankenredis-53w9n) The emptied `source` is a List (guarded above), never a stream,
-            // so these two side-map removes are unconditional no-ops. Route through the is_empty-
-            // guarded `drop_stream_side_metadata` to skip the two wasted foldhash+probes on a
-            // no-stream DB (the common case). Byte-identical (empty-map remove is a no-op).
-            self.drop_stream_side_metadata(source);
+            self.after_mutation_cleanup(source);
         }
 
         // Push to destination
@@ -18833,15 +20106,8 @@ impl Store {
                 }
                 match &mut entry.value {
                     Value::List(l) => {
-                        let len = l.len() as i64;
-                        let s = normalize_index(start, len).max(0);
-                        let e = normalize_index(stop, len).min(len - 1);
                         let old_len = l.len();
-                        if s > e || s >= len || e < 0 {
-                            l.clear();
-                        } else {
-                            let s = s as usize;
-                            let e = e as usize;
+                        if let Some((s, e)) = normalize_range(start, stop, old_len) {
                             let keep = e - s + 1;
                             // (cc_fr) Batch the two-sided trim: ONE drain off the front + ONE
                             // scan+truncate off the back, instead of `s` `pop_front`s (each an
@@ -18853,11 +20119,12 @@ impl Store {
                             l.pop_front_n(s);
                             let back = l.len().saturating_sub(keep);
                             l.pop_back_n(back);
+                        } else {
+                            l.clear();
                         }
                         let removed = old_len - l.len();
                         if l.is_empty() {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if removed > 0 {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -18906,6 +20173,16 @@ impl Store {
         values: &[Vec<u8>],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        // A missing key is left alone, so only an existing list is gated.
+        if self.peek_value_type(key, now_ms).is_some() {
+            self.check_append_quota(
+                key,
+                ValueType::List,
+                values.iter().map(Vec::as_slice),
+                values.len(),
+                now_ms,
+            )?;
+        }
         self.lpushx_impl::<true>(key, values, now_ms)
     }
 
@@ -18995,6 +20272,16 @@ impl Store {
         values: &[Vec<u8>],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        // A missing key is left alone, so only an existing list is gated.
+        if self.peek_value_type(key, now_ms).is_some() {
+            self.check_append_quota(
+                key,
+                ValueType::List,
+                values.iter().map(Vec::as_slice),
+                values.len(),
+                now_ms,
+            )?;
+        }
         self.rpushx_impl::<true>(key, values, now_ms)
     }
 
@@ -19200,12 +20487,7 @@ impl Store {
             && let Value::List(l) = &entry.value
             && l.is_empty()
         {
-            self.internal_entries_remove(source);
-            // (frankenredis-53w9n) The emptied `source` is a List (guarded above), never a stream,
-            // so these two side-map removes are unconditional no-ops. Route through the is_empty-
-            // guarded `drop_stream_side_metadata` to skip the two wasted foldhash+probes on a
-            // no-stream DB (the common case). Byte-identical (empty-map remove is a no-op).
-            self.drop_stream_side_metadata(source);
+            self.after_mutation_cleanup(source);
         }
         // Push to destination.
         let dest_rand_sample = if lfu_tracking_enabled && self.entries.contains_key(destination) {
@@ -19298,7 +20580,7 @@ impl Store {
             };
             entry.has_flag(ENTRY_FORCE_SET_HASHTABLE_ENCODING)
                 || (!entry.has_flag(ENTRY_FORCE_SET_LISTPACK_ENCODING)
-                    && !Self::set_fits_intset(s, self.set_max_intset_entries)
+                    && !s.is_intset()
                     && !Self::set_fits_listpack(
                         s,
                         self.set_max_listpack_entries,
@@ -19341,6 +20623,13 @@ impl Store {
         members: &[M],
         now_ms: u64,
     ) -> Result<u64, StoreError> {
+        self.check_insert_quota(
+            key,
+            ValueType::Set,
+            members.iter().map(AsRef::as_ref),
+            [],
+            now_ms,
+        )?;
         self.sadd_impl::<M, true>(key, members, now_ms)
     }
 
@@ -19414,7 +20703,7 @@ impl Store {
                         // only fires when an integer is added to an existing intset.
                         let was_intset_encoded = !had_set_listpack_encoding
                             && !had_set_hashtable_encoding
-                            && Self::set_fits_intset(s, max_intset_entries);
+                            && s.is_intset();
                         let added = match s.try_sadd_int_batch_merge(members, max_intset_entries) {
                             Some(n) => n,
                             None => {
@@ -19569,8 +20858,7 @@ impl Store {
                             // Emptying the set deletes the key — a real modification
                             // (internal_entries_remove signals it); only reached when
                             // removed > 0.
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else if removed > 0 {
                             entry.bump_mod_count();
                             Self::mark_digest_stale_fields(
@@ -19600,6 +20888,18 @@ impl Store {
     }
 
     pub fn smembers(&mut self, key: &[u8], now_ms: u64) -> Result<Vec<Vec<u8>>, StoreError> {
+        let mut members = self.smembers_in_storage_order(key, now_ms)?;
+        if self.deterministic_iteration {
+            members.sort_unstable();
+        }
+        Ok(members)
+    }
+
+    fn smembers_in_storage_order(
+        &mut self,
+        key: &[u8],
+        now_ms: u64,
+    ) -> Result<Vec<Vec<u8>>, StoreError> {
         if !self.record_keyspace_lookup(key, now_ms) {
             return Ok(Vec::new());
         }
@@ -19997,6 +21297,7 @@ impl Store {
                     .collect();
                 let mut v: Vec<Vec<u8>> = Vec::with_capacity(base.len());
                 'member: for member in base.iter() {
+                    budget_checkpoint()?;
                     for other in &other_sets {
                         if !other.contains(member) {
                             continue 'member;
@@ -20068,6 +21369,7 @@ impl Store {
                     .collect();
                 let mut survivors: Vec<&[u8]> = Vec::with_capacity(base.len());
                 'member: for member in base.iter() {
+                    budget_checkpoint()?;
                     for other in &other_sets {
                         if !other.contains(member) {
                             continue 'member;
@@ -20276,6 +21578,7 @@ impl Store {
                     foldhash::quality::RandomState::default(),
                 );
                 'member: for member in base.iter() {
+                    budget_checkpoint()?;
                     for other in &other_sets {
                         if !other.contains(member) {
                             continue 'member;
@@ -20539,6 +21842,7 @@ impl Store {
             // Original sequential path (small sets / no LIMIT): byte-identical.
             let mut count = 0_u64;
             'members: for member in min_set.iter() {
+                budget_checkpoint()?;
                 let member = member.as_ref();
                 for s in &other_sets {
                     if !s.contains(member) {
@@ -20558,6 +21862,7 @@ impl Store {
         let mut count = 0_u64;
         let mut idx = walk_start;
         for _ in 0..min_card {
+            budget_checkpoint()?;
             let Some(member) = min_set.get_index(idx) else {
                 break;
             };
@@ -20854,6 +22159,7 @@ impl Store {
                     foldhash::quality::RandomState::default(),
                 );
                 'member: for member in base.iter() {
+                    budget_checkpoint()?;
                     for other in &other_sets {
                         if other.contains(member) {
                             continue 'member;
@@ -20940,8 +22246,7 @@ impl Store {
             None => Ok(None),
         }?;
         if should_remove_key {
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         // (frankenredis-bbutt) A successful pop mutates the keyspace and must
         // bump the dirty counter — otherwise SPOP is invisible to RDB/AOF
@@ -21042,8 +22347,7 @@ impl Store {
             return Err(StoreError::WrongType);
         }
         if emptied {
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         self.dirty = self.dirty.saturating_add(result.len() as u64);
         Ok(result)
@@ -21490,12 +22794,7 @@ impl Store {
 
         // Clean up empty source
         if source_empty {
-            self.internal_entries_remove(source);
-            // (frankenredis-53w9n) The emptied `source` is a Set, never a stream, so these two
-            // side-map removes are unconditional no-ops. Route through the is_empty-guarded
-            // `drop_stream_side_metadata` to skip the two wasted foldhash+probes on a no-stream DB.
-            // Byte-identical (empty-map remove is a no-op).
-            self.drop_stream_side_metadata(source);
+            self.after_mutation_cleanup(source);
             self.notify_keyspace_event(NOTIFY_GENERIC, "del", src_logical, src_db);
         }
         // Add to destination — "sadd" fires only on a genuinely new member.
@@ -21585,6 +22884,13 @@ impl Store {
         members: &[(f64, Vec<u8>)],
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        self.check_insert_quota(
+            key,
+            ValueType::ZSet,
+            members.iter().map(|(_, m)| m.as_slice()),
+            [],
+            now_ms,
+        )?;
         // (CrimsonHawk) Guard the bare drop_if_expired (return discarded, called only for
         // the eviction side-effect) on `expires_count != 0` — mirrors lpush/rpush/sadd.
         // With no volatile keys nothing can evict, so the whole call is dead; skipping it
@@ -21633,7 +22939,10 @@ impl Store {
             return Ok(added);
         }
 
-        self.zadd_with_options(key, members.to_vec(), ZaddOptions::default(), now_ms)
+        let members = members
+            .iter()
+            .map(|(score, member)| (*score, member.as_slice()));
+        self.zadd_iter(key, members, ZaddOptions::default(), now_ms)
             .map(|(added, _changed)| added)
     }
 
@@ -21646,6 +22955,13 @@ impl Store {
         members: Vec<(f64, Vec<u8>)>,
         now_ms: u64,
     ) -> Result<usize, StoreError> {
+        self.check_insert_quota(
+            key,
+            ValueType::ZSet,
+            members.iter().map(|(_, m)| m.as_slice()),
+            [],
+            now_ms,
+        )?;
         self.zadd_plain_owned_with_encoding_refresh::<false>(key, members, now_ms)
     }
 
@@ -21774,6 +23090,8 @@ impl Store {
     /// - Without CH: count = number of new elements added
     /// - With CH: count = number of new elements added + updated elements
     /// - changed = number of existing elements whose score was updated
+    ///
+    /// GEOADD shares this path, so its NX/XX/CH reply is `count` verbatim.
     pub fn zadd_with_options(
         &mut self,
         key: &[u8],
@@ -21781,6 +23099,55 @@ impl Store {
         opts: ZaddOptions,
         now_ms: u64,
     ) -> Result<(usize, usize), StoreError> {
+        self.check_update_quota(
+            key,
+            ValueType::ZSet,
+            members.iter().map(|(_, m)| m.as_slice()),
+            [],
+            !opts.xx,
+            now_ms,
+        )?;
+        self.zadd_pairs_unchecked(key, members, opts, now_ms)
+    }
+
+    /// `zadd_with_options` over borrowed members, applied as the iterator is
+    /// drained. A member is copied only when it is actually stored, so bulk
+    /// callers holding the argv never materialize a second `(score, member)`
+    /// list. Scores must already be validated: nothing here can fail halfway
+    /// except WRONGTYPE, which is reported before the first insert. The
+    /// iterator is cloned, not collected, when element limits need a quota
+    /// pass over every member first.
+    pub fn zadd_iter<'a, I>(
+        &mut self,
+        key: &[u8],
+        members: I,
+        opts: ZaddOptions,
+        now_ms: u64,
+    ) -> Result<(usize, usize), StoreError>
+    where
+        I: IntoIterator<Item = (f64, &'a [u8])>,
+        I::IntoIter: Clone,
+    {
+        let members = members.into_iter();
+        self.check_update_quota(
+            key,
+            ValueType::ZSet,
+            members.clone().map(|(_, m)| m),
+            [],
+            !opts.xx,
+            now_ms,
+        )?;
+        self.zadd_pairs_unchecked(key, members, opts, now_ms)
+    }
+
+    fn zadd_pairs_unchecked<M: AsRef<[u8]> + Into<Vec<u8>>>(
+        &mut self,
+        key: &[u8],
+        members: impl IntoIterator<Item = (f64, M)>,
+        opts: ZaddOptions,
+        now_ms: u64,
+    ) -> Result<(usize, usize), StoreError> {
+        let members = members.into_iter();
         // (CrimsonHawk) Skip the always-2-lookup drop_if_expired when no key has a TTL
         // (the contains_key/get_mut below re-probe entries). Byte-identical; see sadd.
         if self.expires_count != 0 {
@@ -21803,53 +23170,64 @@ impl Store {
         // PackedZSet sorted-Vec phase. Route to the one-shot bulk build (sort
         // once) and report the same (added, changed). Byte-identical final set.
         if !self.entries.contains_key(key) {
-            // (frankenredis-zaddflagbulk) default/CH: intra-batch repeats are
-            // last-wins (safe to bulk). NX/GT/LT repeat semantics are
-            // order-dependent, so for those only bulk when there is no
-            // intra-batch duplicate member; otherwise fall through to the
-            // per-member loop. A fresh key means every DISTINCT member is just
-            // added regardless of flag (no existing score to gate on).
-            let permissive = !opts.nx && !opts.gt && !opts.lt;
+            // (frankenredis-zaddflagbulk) A fresh key has no score to gate on,
+            // so every DISTINCT member is added regardless of flag. Repeats
+            // inside the batch are still evaluated in order against the score
+            // an earlier pair just set (see frankenredis-zadddedup below):
+            // default/CH is last-wins, NX keeps the first, GT/LT keep the
+            // running max/min. Resolving that in `latest` keeps one pass over
+            // `members`, which lets borrowed iterators stream straight in.
             let mut latest: HashMap<Vec<u8>, f64, foldhash::quality::RandomState> =
                 HashMap::with_capacity_and_hasher(
-                    members.len(),
+                    members.size_hint().0,
                     foldhash::quality::RandomState::default(),
                 );
             let mut added = 0_usize;
             let mut changed = 0_usize;
-            let mut intra_dup = false;
-            for (score, member) in &members {
-                let score = canonicalize_zero_score(*score);
-                match latest.insert(member.clone(), score) {
+            for (score, member) in members {
+                let score = canonicalize_zero_score(score);
+                match latest.get_mut(member.as_ref()) {
                     Some(old_score) => {
-                        intra_dup = true;
-                        if !old_score.total_cmp(&score).is_eq() {
-                            changed += 1;
+                        let should_update = if opts.nx {
+                            false
+                        } else if opts.gt {
+                            score > *old_score
+                        } else if opts.lt {
+                            score < *old_score
+                        } else {
+                            true
+                        };
+                        if should_update {
+                            if !old_score.total_cmp(&score).is_eq() {
+                                changed += 1;
+                            }
+                            *old_score = score;
                         }
                     }
-                    None => added += 1,
+                    None => {
+                        latest.insert(member.into(), score);
+                        added += 1;
+                    }
                 }
             }
-            if permissive || !intra_dup {
-                if added == 0 {
-                    return Ok((0, 0));
-                }
-                let zs = SortedSet::from_unique_pairs_with_limits(
-                    latest.into_iter().collect(),
-                    zset_max_entries,
-                    zset_max_value,
-                );
-                let mut entry = Entry::new(Value::SortedSet(Box::new(zs)), now_ms);
-                entry.touch_write(now_ms, lfu_tracking_enabled);
-                Self::refresh_zset_encoding_flag(&mut entry, zset_max_entries, zset_max_value);
-                self.internal_entries_insert(key.to_vec(), entry);
-                Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
-                self.dirty = self.dirty.saturating_add((added + changed) as u64);
-                if opts.ch {
-                    return Ok((added + changed, changed));
-                }
-                return Ok((added, changed));
+            if added == 0 {
+                return Ok((0, 0));
+            }
+            let zs = SortedSet::from_unique_pairs_with_limits(
+                latest.into_iter().collect(),
+                zset_max_entries,
+                zset_max_value,
+            );
+            let mut entry = Entry::new(Value::SortedSet(Box::new(zs)), now_ms);
+            entry.touch_write(now_ms, lfu_tracking_enabled);
+            Self::refresh_zset_encoding_flag(&mut entry, zset_max_entries, zset_max_value);
+            self.internal_entries_insert(key.to_vec(), entry);
+            Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
+            self.dirty = self.dirty.saturating_add((added + changed) as u64);
+            if opts.ch {
+                return Ok((added + changed, changed));
             }
+            return Ok((added, changed));
         }
         let (added, changed, is_empty, touched) = {
             let entry =
@@ -21868,15 +23246,12 @@ impl Store {
             // `ZADD k GT 20 a 10 a` must end at 20, not 10; `ZADD k NX 1 a 2 a`
             // must keep 1, not 2) and the CH/added counts (each successive
             // change counts). Iterate the pairs verbatim.
-            // (frankenredis-ug50u) `members` is owned, so each member-Vec is MOVED
-            // into the store instead of `member.clone()`-d. The runtime ZADD fast
-            // path and both fr-command callers already build a throwaway
-            // `Vec<(f64, Vec<u8>)>`, so the prior borrowed signature forced a second
-            // full member allocation per pair purely to hand an owned Vec to
-            // `insert_with_limits`. Moving eliminates that clone; behaviour is
-            // unchanged (a skipped NX/XX/GT/LT pair simply drops its owned member).
+            // (frankenredis-ug50u) An owned member-Vec is MOVED into the store
+            // instead of `member.clone()`-d; a borrowed member (`zadd_iter`) is
+            // copied only here, at insert time. A skipped NX/XX/GT/LT pair
+            // costs no allocation either way.
             for (score, member) in members {
-                match zs.get_score(&member) {
+                match zs.get_score(member.as_ref()) {
                     Some(old_score) => {
                         // Existing member
                         if opts.nx {
@@ -21893,7 +23268,12 @@ impl Store {
                             let old_canonical = canonicalize_zero_score(old_score);
                             let new_canonical = canonicalize_zero_score(score);
                             let score_changed = !old_canonical.total_cmp(&new_canonical).is_eq();
-                            zs.insert_with_limits(member, score, zset_max_entries, zset_max_value);
+                            zs.insert_with_limits(
+                                member.into(),
+                                score,
+                                zset_max_entries,
+                                zset_max_value,
+                            );
                             if score_changed {
                                 changed += 1;
                             }
@@ -21904,7 +23284,12 @@ impl Store {
                         if opts.xx {
                             continue; // XX: don't add new
                         }
-                        zs.insert_with_limits(member, score, zset_max_entries, zset_max_value);
+                        zs.insert_with_limits(
+                            member.into(),
+                            score,
+                            zset_max_entries,
+                            zset_max_value,
+                        );
                         added += 1;
                     }
                 }
@@ -21928,7 +23313,7 @@ impl Store {
             self.dirty = self.dirty.saturating_add((added + changed) as u64);
         }
         if is_empty {
-            self.internal_entries_remove(key);
+            self.after_mutation_cleanup(key);
         }
         if opts.ch {
             Ok((added + changed, changed))
@@ -21996,8 +23381,7 @@ impl Store {
             self.dirty = self.dirty.saturating_add(removed);
         }
         if is_empty {
-            self.internal_entries_remove(key);
-            self.drop_stream_side_metadata(key);
+            self.after_mutation_cleanup(key);
         }
         Ok(removed)
     }
@@ -22947,14 +24331,9 @@ impl Store {
                 }
                 match &mut entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             return Ok(Vec::new());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         let result: Vec<Vec<u8>> = zs
                             .index_slice_asc_adaptive(s_idx, count)
@@ -23076,15 +24455,10 @@ impl Store {
         };
         match &entry.value {
             Value::SortedSet(zs) => {
-                let len = zs.len() as i64;
-                let s = normalize_index(start, len);
-                let e = normalize_index(stop, len);
-                if s > e || s >= len || e < 0 {
+                let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                     sink(SmembersScanEvent::Len(0));
                     return Ok(());
-                }
-                let s_idx = s.max(0) as usize;
-                let e_idx = e.min(len - 1) as usize;
+                };
                 let count = e_idx - s_idx + 1;
                 sink(SmembersScanEvent::Len(count));
                 for (m, _score) in zs.iter_asc().skip(s_idx).take(count) {
@@ -23205,15 +24579,10 @@ impl Store {
         };
         match &entry.value {
             Value::SortedSet(zs) => {
-                let len = zs.len() as i64;
-                let s = normalize_index(start, len);
-                let e = normalize_index(stop, len);
-                if s > e || s >= len || e < 0 {
+                let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                     sink(SmembersScanEvent::Len(0));
                     return Ok(());
-                }
-                let s_idx = s.max(0) as usize;
-                let e_idx = e.min(len - 1) as usize;
+                };
                 let count = e_idx - s_idx + 1;
                 sink(SmembersScanEvent::Len(count));
                 for (m, _score) in zs.iter_desc().skip(s_idx).take(count) {
@@ -23266,14 +24635,9 @@ impl Store {
                 }
                 match &mut entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             return Ok(Vec::new());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         let result: Vec<Vec<u8>> = zs
                             .index_slice_desc_adaptive(s_idx, count)
@@ -23321,6 +24685,7 @@ impl Store {
                 match &entry.value {
                     Value::SortedSet(zs) => {
                         let result = zs.score_bound_members(min, max, false);
+                        budget_checkpoint()?;
                         entry.touch(now_ms);
                         Ok(result)
                     }
@@ -23364,6 +24729,7 @@ impl Store {
                 match &entry.value {
                     Value::SortedSet(zs) => {
                         let result = zs.score_bound_range(min, max, false);
+                        budget_checkpoint()?;
                         entry.touch(now_ms);
                         Ok(result)
                     }
@@ -23417,6 +24783,7 @@ impl Store {
                         let take = count.unwrap_or(usize::MAX);
                         let result =
                             zs.score_bound_range_limited_adaptive(min, max, rev, offset, take);
+                        budget_checkpoint()?;
                         entry.touch(now_ms);
                         Ok(result)
                     }
@@ -24479,7 +25846,7 @@ impl Store {
             self.dirty = self.dirty.saturating_add(1);
         }
         if is_empty {
-            self.internal_entries_remove(key);
+            self.after_mutation_cleanup(key);
         }
         res
     }
@@ -24526,8 +25893,7 @@ impl Store {
         if result.is_some() {
             self.dirty = self.dirty.saturating_add(1);
             if is_empty {
-                self.internal_entries_remove(key);
-                self.drop_stream_side_metadata(key);
+                self.after_mutation_cleanup(key);
             } else {
                 Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
                 entry.touch_write(now_ms, lfu_tracking_enabled);
@@ -24571,8 +25937,7 @@ impl Store {
         if result.is_some() {
             self.dirty = self.dirty.saturating_add(1);
             if is_empty {
-                self.internal_entries_remove(key);
-                self.drop_stream_side_metadata(key);
+                self.after_mutation_cleanup(key);
             } else {
                 Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
                 entry.touch_write(now_ms, lfu_tracking_enabled);
@@ -24639,8 +26004,7 @@ impl Store {
             // to RDB/AOF persistence, replication, AND keyspace notifications.
             self.dirty = self.dirty.saturating_add(result.len() as u64);
             if is_empty {
-                self.internal_entries_remove(key);
-                self.drop_stream_side_metadata(key);
+                self.after_mutation_cleanup(key);
             } else {
                 Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
                 entry.touch_write(now_ms, lfu_tracking_enabled);
@@ -24715,8 +26079,7 @@ impl Store {
             // notifications, matching upstream genericZpopCommand.
             self.dirty = self.dirty.saturating_add(result.len() as u64);
             if is_empty {
-                self.internal_entries_remove(key);
-                self.drop_stream_side_metadata(key);
+                self.after_mutation_cleanup(key);
             } else {
                 Self::mark_digest_stale_fields(&mut self.digest_stale, &mut self.digest_mutations);
                 entry.touch_write(now_ms, lfu_tracking_enabled);
@@ -24776,14 +26139,9 @@ impl Store {
                 }
                 match &mut entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             return Ok(Vec::new());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         let result: Vec<(Vec<u8>, f64)> = zs.index_slice_asc_adaptive(s_idx, count);
                         entry.touch(now_ms);
@@ -24835,15 +26193,10 @@ impl Store {
                 }
                 match &entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             sink(ZRangeWithScoresScanEvent::Len(0));
                             return Ok(());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         sink(ZRangeWithScoresScanEvent::Len(count));
                         zs.for_each_index_slice_asc(s_idx, count, |member, score| {
@@ -24987,14 +26340,9 @@ impl Store {
                 }
                 match &mut entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             return Ok(Vec::new());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         let result: Vec<(Vec<u8>, f64)> =
                             zs.index_slice_desc_adaptive(s_idx, count);
@@ -25042,15 +26390,10 @@ impl Store {
                 }
                 match &entry.value {
                     Value::SortedSet(zs) => {
-                        let len = zs.len() as i64;
-                        let s = normalize_index(start, len);
-                        let e = normalize_index(stop, len);
-                        if s > e || s >= len || e < 0 {
+                        let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                             sink(ZRangeWithScoresScanEvent::Len(0));
                             return Ok(());
-                        }
-                        let s_idx = s.max(0) as usize;
-                        let e_idx = e.min(len - 1) as usize;
+                        };
                         let count = e_idx - s_idx + 1;
                         sink(ZRangeWithScoresScanEvent::Len(count));
                         zs.for_each_index_slice_desc(s_idx, count, |member, score| {
@@ -25102,6 +26445,7 @@ impl Store {
                             ScoreBound::Inclusive(max),
                             true,
                         );
+                        budget_checkpoint()?;
                         entry.touch(now_ms);
                         Ok(result)
                     }
@@ -25464,6 +26808,7 @@ impl Store {
                             ScoreBound::Inclusive(max),
                             true,
                         );
+                        budget_checkpoint()?;
                         entry.touch(now_ms);
                         Ok(result)
                     }
@@ -25579,14 +26924,9 @@ impl Store {
         match self.entries.get_mut(key) {
             Some(entry) => match &mut entry.value {
                 Value::SortedSet(zs) => {
-                    let len = zs.len();
-                    let s = normalize_index(start, len as i64);
-                    let e = normalize_index(stop, len as i64);
-                    if s > e || s >= len as i64 || e < 0 {
+                    let Some((s_idx, e_idx)) = normalize_range(start, stop, zs.len()) else {
                         return Ok(0);
-                    }
-                    let s_idx = s.max(0) as usize;
-                    let e_idx = e.min(len as i64 - 1) as usize;
+                    };
                     let count = e_idx - s_idx + 1;
                     // Collect the members in rank range [s_idx, e_idx]; the
                     // order-statistic tree jumps to s_idx in O(log n) instead of
@@ -25605,8 +26945,7 @@ impl Store {
                     if removed_count > 0 {
                         self.dirty = self.dirty.saturating_add(removed_count as u64);
                         if is_empty {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -25656,6 +26995,7 @@ impl Store {
                         zs.remove_rank_range(start, count)
                     } else {
                         let run = zs.score_bound_range(min, max, false);
+                        budget_checkpoint()?;
                         let removed_count = run.len();
                         if let Some(start) =
                             run.first().and_then(|(m, s)| zs.contiguous_run_start(m, *s))
@@ -25672,8 +27012,7 @@ impl Store {
                     if removed_count > 0 {
                         self.dirty = self.dirty.saturating_add(removed_count as u64);
                         if is_empty {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -25732,8 +27071,7 @@ impl Store {
                     if removed_count > 0 {
                         self.dirty = self.dirty.saturating_add(removed_count as u64);
                         if is_empty {
-                            self.internal_entries_remove(key);
-                            self.drop_stream_side_metadata(key);
+                            self.after_mutation_cleanup(key);
                         } else {
                             Self::mark_digest_stale_fields(
                                 &mut self.digest_stale,
@@ -26195,6 +27533,39 @@ impl Store {
         Ok(last_id)
     }
 
+    /// The last id a deleted stream at `key` had used, while
+    /// `stream-preserve-last-id-on-delete` is on. XADD `*` on the recreated
+    /// stream continues above it, so a consumer still holding a cursor from
+    /// before the delete sees every new entry. An id is kept until its
+    /// millisecond part is `stream-preserve-last-id-window` behind `now_ms`:
+    /// past that, wall-clock auto ids are above it anyway.
+    pub fn stream_preserved_last_id(&mut self, key: &[u8], now_ms: u64) -> Option<StreamId> {
+        if self.stream_last_id_tombstones.is_empty() {
+            return None;
+        }
+        let window_ms = self.stream_preserve_last_id_window_ms;
+        self.stream_last_id_tombstones
+            .retain(|_, (ms, _)| ms.saturating_add(window_ms) > now_ms);
+        self.stream_last_id_tombstones.get(key).copied()
+    }
+
+    /// Record the highest id the stream at `key` has used before it goes:
+    /// its last entry, its XSETID watermark, or an id remembered from an
+    /// earlier incarnation of the key.
+    fn remember_stream_last_id(&mut self, key: &[u8], last_entry_id: Option<StreamId>) {
+        let remembered = [
+            last_entry_id,
+            self.stream_last_ids.get(key).copied(),
+            self.stream_last_id_tombstones.get(key).copied(),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if let Some(last_id) = remembered {
+            self.stream_last_id_tombstones.insert(key.to_vec(), last_id);
+        }
+    }
+
     /// No-stat counterpart of [`xlast_id`] for stream WRITE paths resolving a
     /// `$` id (XADD / XGROUP CREATE|SETID). (frankenredis-ljtdo)
     pub fn xlast_id_no_stat(
@@ -26285,6 +27656,53 @@ impl Store {
         fields: &[StreamField],
         now_ms: u64,
     ) -> Result<(), StoreError> {
+        self.xadd_pairs(
+            key,
+            id,
+            fields
+                .iter()
+                .map(|(field, value)| (field.as_slice(), value.as_slice())),
+            now_ms,
+        )
+    }
+
+    /// [`Self::xadd`] over a flat `field value [field value ...]` list, e.g.
+    /// the tail of an XADD argv, without first copying it into owned pairs.
+    /// A trailing unpaired element is ignored; callers validate the arity.
+    pub fn xadd_flat<P: AsRef<[u8]>>(
+        &mut self,
+        key: &[u8],
+        id: StreamId,
+        fields: &[P],
+        now_ms: u64,
+    ) -> Result<(), StoreError> {
+        self.xadd_pairs(
+            key,
+            id,
+            fields
+                .chunks_exact(2)
+                .map(|pair| (pair[0].as_ref(), pair[1].as_ref())),
+            now_ms,
+        )
+    }
+
+    fn xadd_pairs<'a, I>(
+        &mut self,
+        key: &[u8],
+        id: StreamId,
+        fields: I,
+        now_ms: u64,
+    ) -> Result<(), StoreError>
+    where
+        I: Iterator<Item = (&'a [u8], &'a [u8])> + Clone,
+    {
+        self.check_append_quota(
+            key,
+            ValueType::Stream,
+            fields.clone().flat_map(|(field, value)| [field, value]),
+            1,
+            now_ms,
+        )?;
         // (CrimsonHawk) Guard the bare drop_if_expired on `expires_count != 0`: the get_mut
         // below re-probes (and XADD auto-creates the stream when absent), so drop's no-TTL
         // fast-exit contains_key is pure overhead when nothing is volatile. Byte-identical
@@ -26298,7 +27716,7 @@ impl Store {
                 Value::Stream(entries) => {
                     // `insert` returns true when an entry with this id already
                     // existed; a fresh id is therefore `!existed`.
-                    let inserted = !entries.insert(id, fields);
+                    let inserted = !entries.insert_fields(id, fields);
                     let default_entries_added =
                         u64::try_from(entries.len().saturating_sub(1)).unwrap_or(u64::MAX);
                     Self::mark_digest_stale_fields(
@@ -26336,7 +27754,7 @@ impl Store {
             },
             None => {
                 let mut entries = StreamEntries::new();
-                entries.insert(id, fields);
+                entries.insert_fields(id, fields);
                 self.stream_groups.remove(key);
                 self.stream_max_deleted_ids.remove(key);
                 // Set high watermark to the first entry's ID
@@ -26567,8 +27985,8 @@ impl Store {
     /// buffer (`FieldsRef::iter`), eliminating the per-field `to_pairs()` clone. IDENTICAL to
     /// `xrange`: same LFU/non-LFU single-lookup, same `start > end` empty short-circuit (no touch),
     /// same `range(start..=end)` walk + `count` limit, same post-walk `touch` (Stream + non-empty
-    /// range only). Records are collected as borrowed `(id, FieldsRef)` refs solely to emit the outer
-    /// `*N` count before the records; the fields never leave the buffer.
+    /// range only). The outer `*N` count comes from a first walk of the range, so neither the records
+    /// nor their fields are ever collected.
     #[allow(clippy::too_many_arguments)]
     pub fn xrange_borrow_scan(
         &mut self,
@@ -26666,33 +28084,24 @@ impl Store {
                 } else {
                     // (CrimsonHawk) `rev` = XREVRANGE: the ONLY difference from XRANGE is walking the
                     // same `start..=end` range descending (mirrors `xrevrange`'s `.rev()`).
-                    let mut records = Vec::new();
-                    if rev {
-                        for (id, fields) in entries.range(start..=end).rev() {
-                            records.push((*id, fields));
-                            if let Some(limit) = count
-                                && records.len() >= limit
-                            {
-                                break;
-                            }
-                        }
-                    } else {
-                        for (id, fields) in entries.range(start..=end) {
-                            records.push((*id, fields));
-                            if let Some(limit) = count
-                                && records.len() >= limit
-                            {
-                                break;
-                            }
-                        }
-                    }
-                    sink(XrangeReplyEvent::RecordCount(records.len()));
-                    for (id, fields) in &records {
+                    let limit = count.unwrap_or(usize::MAX);
+                    let records = entries.range(start..=end).take(limit).count();
+                    sink(XrangeReplyEvent::RecordCount(records));
+                    let mut emit = |(id, fields): (&StreamId, FieldsRef<'_>)| {
                         sink(XrangeReplyEvent::RecordStart(*id, fields.len()));
                         for (f, v) in fields.iter() {
                             sink(XrangeReplyEvent::Field(f));
                             sink(XrangeReplyEvent::Field(v));
                         }
+                    };
+                    if rev {
+                        entries
+                            .range(start..=end)
+                            .rev()
+                            .take(records)
+                            .for_each(&mut emit);
+                    } else {
+                        entries.range(start..=end).take(records).for_each(&mut emit);
                     }
                     true
                 }
@@ -27246,6 +28655,7 @@ impl Store {
         let Some(group_state) = groups.get_mut(group) else {
             return Ok(None);
         };
+        group_state.check_new_consumer_name(consumer)?;
         let consumer = consumer.to_vec();
         let consumer_created = group_state.insert_consumer(consumer.clone());
         if consumer_created {
@@ -27311,14 +28721,17 @@ impl Store {
             // `[id, nil]` and carried here as an empty field list (s0614) — take
             // the `[id, NULL]` branch upstream and are NOT touched. fr previously
             // left the counter unchanged on every history read, so XPENDING
-            // delivery counts drifted below redis after re-reads.
-            for (id, fields) in &records {
-                if fields.is_empty() {
-                    continue; // tombstone: redis emits [id, nil], no bump
-                }
-                if let Some(pending_entry) = group_state.pending.get_mut(id) {
-                    pending_entry.deliveries = pending_entry.deliveries.saturating_add(1);
-                    pending_entry.last_delivered_ms = now_ms;
+            // delivery counts drifted below redis after re-reads. The opt-in
+            // stream-history-reads-keep-delivery-counts skips the bump.
+            if !self.stream_history_reads_keep_delivery_counts {
+                for (id, fields) in &records {
+                    if fields.is_empty() {
+                        continue; // tombstone: redis emits [id, nil], no bump
+                    }
+                    if let Some(pending_entry) = group_state.pending.get_mut(id) {
+                        pending_entry.deliveries = pending_entry.deliveries.saturating_add(1);
+                        pending_entry.last_delivered_ms = now_ms;
+                    }
                 }
             }
         }
@@ -27375,6 +28788,7 @@ impl Store {
     /// read: mut-then-immut, output-invariant), then the `pending.range((Excluded(start),Unbounded))`
     /// walk filtered by consumer with each entry's fields BORROWED from the packed buffer (or a
     /// `RecordStartNil` tombstone when the underlying stream entry was `XDEL`'d). `RecordCount` first.
+    /// The delivery-count bump and dirty increment follow the read, as in the generic path.
     #[allow(clippy::too_many_arguments)]
     pub fn xreadgroup_history_borrow_scan(
         &mut self,
@@ -27441,6 +28855,27 @@ impl Store {
                 None => sink(XreadgroupHistEvent::RecordStartNil(*id)),
             }
         }
+        // Same PEL bookkeeping as the generic history read: each served entry
+        // that still exists counts as a re-delivery (unless
+        // stream-history-reads-keep-delivery-counts is on), tombstones are
+        // untouched, and the read dirties the dataset even when it serves nothing.
+        if !self.stream_history_reads_keep_delivery_counts
+            && let Some(group_state) = self
+                .stream_groups
+                .get_mut(key)
+                .and_then(|groups| groups.get_mut(group))
+        {
+            for (id, fields) in &records {
+                if fields.is_none() {
+                    continue;
+                }
+                if let Some(pending_entry) = group_state.pending.get_mut(id) {
+                    pending_entry.deliveries = pending_entry.deliveries.saturating_add(1);
+                    pending_entry.last_delivered_ms = now_ms;
+                }
+            }
+        }
+        self.dirty = self.dirty.saturating_add(1);
     }
 
     /// (frankenredis-j9lgb) Whether `(key, group)` names an existing stream
@@ -27627,6 +29062,7 @@ impl Store {
         let Some(group_state) = groups.get_mut(group) else {
             return Ok(None);
         };
+        group_state.check_new_consumer_name(consumer)?;
 
         if let Some(last_id) = options.last_id {
             group_state.last_delivered_id = last_id;
@@ -27825,6 +29261,7 @@ impl Store {
         let Some(group_state) = groups.get_mut(group) else {
             return Ok(None);
         };
+        group_state.check_new_consumer_name(consumer)?;
 
         for id in &deleted_ids {
             if let Some(removed) = group_state.pending.remove(id) {
@@ -28056,11 +29493,24 @@ impl Store {
             None => false,
         };
 
+        if group.is_empty()
+            && !self
+                .stream_groups
+                .get(key)
+                .is_some_and(|groups| groups.contains_key(group))
+        {
+            return Err(StoreError::EmptyStreamName(StreamNameKind::Group));
+        }
         if !key_exists_as_stream {
             if !mkstream {
                 return Err(StoreError::KeyNotFound);
             }
+            let preserved = self.stream_preserved_last_id(key, now_ms);
             self.drop_stream_side_metadata(key);
+            if let Some(last_id) = preserved {
+                self.stream_last_id_tombstones.remove(key);
+                self.stream_last_ids.insert(key.to_vec(), last_id);
+            }
             self.stream_entries_added.insert(key.to_vec(), 0);
             self.internal_entries_insert(
                 key.to_vec(),
@@ -28248,6 +29698,7 @@ impl Store {
                     let Some(group_state) = groups.get_mut(group) else {
                         return Ok(None);
                     };
+                    group_state.check_new_consumer_name(consumer)?;
                     let created = group_state.insert_consumer(consumer.to_vec());
                     if created {
                         // (frankenredis-p4dpj) XGROUP CREATECONSUMER
@@ -28278,7 +29729,7 @@ impl Store {
         key: &[u8],
         group: &[u8],
         now_ms: u64,
-    ) -> Result<Option<Vec<StreamConsumerInfoEx>>, StoreError> {
+    ) -> Result<Option<Vec<StreamConsumerInfo>>, StoreError> {
         // XINFO CONSUMERS only type-checks the entry, then reads the consumer
         // state from `stream_groups`. On non-LFU reads, fold the stat-counting
         // lookup and type-check into one live lookup, release the entry borrow,
@@ -28311,7 +29762,7 @@ impl Store {
             group_state.consumer_states.len(),
             "stream consumer state drift"
         );
-        let mut result: Vec<StreamConsumerInfoEx> =
+        let mut result: Vec<StreamConsumerInfo> =
             Vec::with_capacity(group_state.consumer_states.len());
         for (consumer_name, consumer_state) in &group_state.consumer_states {
             let pending_count = consumer_state.pending_count;
@@ -28337,19 +29788,19 @@ impl Store {
                 }
             };
             // (frankenredis-p4dpj) inactive = ms since
-            // active_time when set; -1 sentinel otherwise
-            // (matching upstream consumer->active_time =
-            // -1 default until first XREADGROUP/XCLAIM).
-            let inactive_ms_or_neg_one: i64 = match metadata.active_time_ms {
-                Some(at) if at > 0 => i64::try_from(now_ms.saturating_sub(at)).unwrap_or(i64::MAX),
-                _ => -1,
-            };
-            result.push((
-                consumer_name.clone(),
-                pending_count,
+            // active_time when set; None otherwise (upstream
+            // consumer->active_time stays -1 until the first
+            // XREADGROUP/XCLAIM that hands the consumer an entry).
+            let inactive_ms = metadata
+                .active_time_ms
+                .filter(|&at| at > 0)
+                .map(|at| now_ms.saturating_sub(at));
+            result.push(StreamConsumerInfo {
+                name: consumer_name.clone(),
+                pending: pending_count,
                 idle_ms,
-                inactive_ms_or_neg_one,
-            ));
+                inactive_ms,
+            });
         }
         Ok(Some(result))
     }
@@ -29137,9 +30588,9 @@ impl Store {
     // ── Hash field TTL primitives (Redis 7.4 HEXPIRE family) ────────
     //
     // Part 1 (br-frankenredis-wwz3): storage + flag-aware setters, TTL
-    // readers, and the lazy expiry helper. No hook into hash reads yet —
-    // that's part 3 (br-frankenredis-b8ut). No command-level dispatch —
-    // part 2 (br-frankenredis-c0z9).
+    // readers, and the lazy expiry helper. Hash reads reap expired fields
+    // (part 3, br-frankenredis-b8ut). The commands themselves are wired in
+    // fr-command behind its non-default `hash-field-ttl` feature.
 
     /// Set or update the absolute expiry (ms-since-epoch) for a single
     /// hash field. Returns the applied-or-rejected outcome using the
@@ -29327,6 +30778,16 @@ impl Store {
         outcome
     }
 
+    /// Lazily expire `key` and every field of it whose TTL has passed,
+    /// exactly as a hash read would; a hash left with no fields is
+    /// deleted. The HEXPIRE family calls this before answering, and after
+    /// setting a deadline that was already in the past (reply code 2).
+    /// Returns the number of fields reaped.
+    pub fn hash_field_reap_expired(&mut self, key: &[u8], now_ms: u64) -> usize {
+        self.drop_if_expired(key, now_ms);
+        self.drop_expired_hash_fields(key, now_ms)
+    }
+
     /// True if `field` on `key` is expired (past deadline) per the
     /// per-field TTL map. False for fields with no TTL, for missing
     /// hashes, or for non-hash keys.
@@ -29477,8 +30938,7 @@ impl Store {
                     if added_expiry {
                         self.expires_count = self.expires_count.saturating_add(1);
                         if db < self.database_count {
-                            self.db_expires_counts[db] =
-                                self.db_expires_counts[db].saturating_add(1);
+                            db_count_add(&mut self.db_expires_counts, db);
                         }
                     }
                     // (frankenredis-t1q35) Mark dirty only when the key NEWLY enters the volatile set;
@@ -29499,8 +30959,7 @@ impl Store {
                         self.update_expiry_deadline(old_expiry, None);
                         self.expires_count = self.expires_count.saturating_sub(1);
                         if db < self.database_count {
-                            self.db_expires_counts[db] =
-                                self.db_expires_counts[db].saturating_sub(1);
+                            db_count_sub(&mut self.db_expires_counts, db);
                         }
                         self.dirty = self.dirty.saturating_add(1);
                         self.notify_keyspace_event(NOTIFY_GENERIC, "persist", logical_key, db);
@@ -30133,6 +31592,43 @@ impl Store {
             .count()
     }
 
+    /// Number of flushes that dropped keys of database `db`.
+    #[must_use]
+    pub fn flush_generation(&self, db: usize) -> u64 {
+        self.flush_generations.get(&db).copied().unwrap_or(0)
+    }
+
+    fn bump_flush_generations(&mut self, dbs: impl IntoIterator<Item = usize>) {
+        for db in dbs {
+            let generation = self.flush_generations.entry(db).or_insert(0);
+            *generation = generation.wrapping_add(1);
+        }
+    }
+
+    fn scan_cursor_tag(&self, db: usize) -> u64 {
+        self.flush_generation(db) & (u64::MAX >> SCAN_CURSOR_POSITION_BITS)
+    }
+
+    /// Cursor to hand back to a SCAN-family client of database `db` for resume
+    /// `position`, tagged with that database's flush generation. 0 stays 0.
+    #[must_use]
+    pub fn mint_scan_cursor(&self, db: usize, position: u64) -> u64 {
+        mint_scan_cursor(self.scan_cursor_tag(db), position)
+    }
+
+    /// Resume position for a client-supplied SCAN-family cursor over database
+    /// `db`, or `None` when the cursor was minted before that database's latest
+    /// flush (or never minted here at all). Cursor 0 always starts a fresh
+    /// walk. Callers answer `None` with the final empty page `(0, [])`.
+    #[must_use]
+    pub fn resolve_scan_cursor(&self, db: usize, cursor: u64) -> Option<u64> {
+        if cursor == 0 {
+            return Some(0);
+        }
+        (cursor >> SCAN_CURSOR_POSITION_BITS == self.scan_cursor_tag(db))
+            .then_some(cursor & SCAN_CURSOR_POSITION_MASK)
+    }
+
     /// SCAN cursor-based iteration.
     /// Returns (next_cursor, keys). Cursor 0 means start / complete.
     /// This uses a simple sorted-keys approach for determinism.
@@ -30143,6 +31639,20 @@ impl Store {
         pattern: Option<&[u8]>,
         count: usize,
         now_ms: u64,
+    ) -> (u64, Vec<Vec<u8>>) {
+        let Some(position) = self.resolve_scan_cursor(0, cursor) else {
+            return (0, Vec::new());
+        };
+        let (next, keys) = self.scan_at(position, pattern, count, now_ms);
+        (self.mint_scan_cursor(0, next), keys)
+    }
+
+    fn scan_at(
+        &mut self,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+        now_ms: u64,
     ) -> (u64, Vec<Vec<u8>>) {
         self.rebuild_ordered_keys_if_dirty();
         let start = cursor as usize;
@@ -30266,14 +31776,16 @@ impl Store {
     }
 
     /// (frankenredis-n9am7) Cursor-resumed, DB-scoped SCAN for the real SCAN
-    /// command. Produces the SAME sorted, glob+TYPE-filtered logical-key sequence
-    /// as materialising the whole DB and paging by index — `cursor` is the count
-    /// of matched keys already returned (the index into that sequence). But it
-    /// RESUMES past the previous batch's last key in O(log n) via `db_scan_cache`
-    /// instead of re-walking + re-sorting the whole DB every call, so a full SCAN
-    /// of a DB drops from O(n^2/count) to O(n) total. Returns
-    /// `(next_cursor, up-to-count keys)`; `next_cursor == 0` exactly when no
-    /// further matches remain (byte-identical paging to the old index slice).
+    /// command. Walks the DB's keys in sorted order, `count` keys per call, and
+    /// returns the ones passing MATCH and TYPE — like upstream
+    /// scanGenericCommand, which filters the keys a `dictScan` batch collected,
+    /// so a selective filter may return few or no keys with a non-zero cursor.
+    /// `cursor` is the number of keys examined so far. A MATCH with a literal
+    /// prefix only walks that prefix's key range, whose keys are the only ones
+    /// it could match. Each call RESUMES past the previous batch's last key in
+    /// O(log n) via `db_scan_cache` instead of re-walking the whole DB, so a
+    /// full SCAN of a DB is O(n) total. Returns `(next_cursor, keys)`;
+    /// `next_cursor == 0` exactly when no unexamined key remains.
     pub fn scan_in_db(
         &mut self,
         db: usize,
@@ -30282,6 +31794,22 @@ impl Store {
         type_filter: Option<&[u8]>,
         count: usize,
         now_ms: u64,
+    ) -> (u64, Vec<Vec<u8>>) {
+        let Some(position) = self.resolve_scan_cursor(db, cursor) else {
+            return (0, Vec::new());
+        };
+        let (next, keys) = self.scan_in_db_at(db, position, pattern, type_filter, count, now_ms);
+        (self.mint_scan_cursor(db, next), keys)
+    }
+
+    fn scan_in_db_at(
+        &mut self,
+        db: usize,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        type_filter: Option<&[u8]>,
+        count: usize,
+        now_ms: u64,
     ) -> (u64, Vec<Vec<u8>>) {
         // Reap due volatile keys — identical eviction set to keys_in_db /
         // keys_matching_in_db (drop_if_expired is a no-op on non-volatile keys).
@@ -30331,7 +31859,7 @@ impl Store {
         };
 
         // Resume past the previous batch's last key (cache hit), else skip the
-        // first `start` matched keys from the range start (cache miss / first).
+        // first `start` examined keys from the range start (cache miss / first).
         //
         // (frankenredis SCAN-guarantee fix) The resume is matched by
         // (cursor, db, sig) ONLY — deliberately NOT by keyspace generation.
@@ -30372,7 +31900,8 @@ impl Store {
         // prefix/suffix shapes that dominate SCAN. (cc_fr)
         let prepared_glob = pattern.map(glob_prepare);
         let mut result: Vec<Vec<u8>> = Vec::new();
-        let mut last_key: Option<Vec<u8>> = None;
+        let mut examined = 0_usize;
+        let mut last_key: Option<&[u8]> = None;
         let mut has_more = false;
         for physical in self.ordered_keys.range::<[u8], _>((lo_bound, hi_bound)) {
             let physical: &[u8] = physical.as_ref();
@@ -30388,6 +31917,18 @@ impl Store {
             if !self.entries.contains_key(physical) {
                 continue;
             }
+            if to_skip > 0 {
+                to_skip -= 1;
+                continue;
+            }
+            // One key beyond the batch => more remain; stop without examining it
+            // (next call resumes from `last_key`, the batch-th examined key).
+            if examined == batch {
+                has_more = true;
+                break;
+            }
+            examined += 1;
+            last_key = Some(physical);
             let logical = decode_db_key(physical).map(|(_, l)| l).unwrap_or(physical);
             // Glob (skipped for the `*` / no-pattern all-keys fast path).
             if !is_star
@@ -30404,21 +31945,9 @@ impl Store {
             {
                 continue;
             }
-            // A matched candidate.
-            if to_skip > 0 {
-                to_skip -= 1;
-                continue;
-            }
-            if result.len() < batch {
-                result.push(logical.to_vec());
-                last_key = Some(physical.to_vec());
-                continue;
-            }
-            // One match beyond the batch => more remain; stop without returning it
-            // (next call resumes from `last_key`, the batch-th returned key).
-            has_more = true;
-            break;
+            result.push(logical.to_vec());
         }
+        let last_key = last_key.map(<[u8]>::to_vec);
 
         let next_cursor = if has_more { cursor + batch as u64 } else { 0 };
         if next_cursor != 0
@@ -30499,6 +32028,26 @@ impl Store {
         pattern: Option<&[u8]>,
         count: usize,
         now_ms: u64,
+    ) -> Result<(u64, Vec<(Vec<u8>, Vec<u8>)>), StoreError> {
+        let Some(position) = self.resolve_scan_cursor(key_db(key), cursor) else {
+            return Ok((0, Vec::new()));
+        };
+        let (next, mut pairs) =
+            self.hscan_in_storage_order(key, position, pattern, count, now_ms)?;
+        if self.deterministic_iteration {
+            pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok((self.mint_scan_cursor(key_db(key), next), pairs))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn hscan_in_storage_order(
+        &mut self,
+        key: &[u8],
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+        now_ms: u64,
     ) -> Result<(u64, Vec<(Vec<u8>, Vec<u8>)>), StoreError> {
         // (CrimsonHawk) Guard the bare expiry probes: skip the key drop when nothing is volatile
         // and the hash-field drop when no field TTLs exist. Byte-identical (both no-ops then; the
@@ -30610,6 +32159,18 @@ impl Store {
         now_ms: u64,
         mut sink: impl FnMut(SscanReplyEvent<'_>),
     ) -> Result<(), StoreError> {
+        let Some(cursor) = self.resolve_scan_cursor(key_db(key), cursor) else {
+            sink(SscanReplyEvent::Cursor(0));
+            sink(SscanReplyEvent::Len(0));
+            return Ok(());
+        };
+        let tag = self.scan_cursor_tag(key_db(key));
+        let mut sink = move |ev: SscanReplyEvent<'_>| match ev {
+            SscanReplyEvent::Cursor(next) => {
+                sink(SscanReplyEvent::Cursor(mint_scan_cursor(tag, next)))
+            }
+            ev => sink(ev),
+        };
         if self.expires_count != 0 {
             self.drop_if_expired(key, now_ms);
         }
@@ -30707,6 +32268,25 @@ impl Store {
         pattern: Option<&[u8]>,
         count: usize,
         now_ms: u64,
+    ) -> Result<(u64, Vec<Vec<u8>>), StoreError> {
+        let Some(position) = self.resolve_scan_cursor(key_db(key), cursor) else {
+            return Ok((0, Vec::new()));
+        };
+        let (next, mut members) =
+            self.sscan_in_storage_order(key, position, pattern, count, now_ms)?;
+        if self.deterministic_iteration {
+            members.sort_unstable();
+        }
+        Ok((self.mint_scan_cursor(key_db(key), next), members))
+    }
+
+    fn sscan_in_storage_order(
+        &mut self,
+        key: &[u8],
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+        now_ms: u64,
     ) -> Result<(u64, Vec<Vec<u8>>), StoreError> {
         // (CrimsonHawk) Guard the bare drop_if_expired — the get_mut below re-probes. (SSCAN.)
         if self.expires_count != 0 {
@@ -30720,7 +32300,6 @@ impl Store {
         } else {
             0
         };
-        let max_intset_entries = self.set_max_intset_entries;
         let max_listpack_entries = self.set_max_listpack_entries;
         let max_listpack_value = self.set_max_listpack_value;
         // (cc_fr) Classify the MATCH glob ONCE, not per member. Byte-identical to
@@ -30742,7 +32321,7 @@ impl Store {
                         // hashtable-encoded path honors them.
                         let is_listpack_or_intset = !entry
                             .has_flag(ENTRY_FORCE_SET_HASHTABLE_ENCODING)
-                            && (Self::set_fits_intset(s, max_intset_entries)
+                            && (s.is_intset()
                                 || Self::set_fits_listpack(
                                     s,
                                     max_listpack_entries,
@@ -30817,6 +32396,18 @@ impl Store {
         now_ms: u64,
         mut sink: impl FnMut(SscanReplyEvent<'_>),
     ) -> Result<(), StoreError> {
+        let Some(cursor) = self.resolve_scan_cursor(key_db(key), cursor) else {
+            sink(SscanReplyEvent::Cursor(0));
+            sink(SscanReplyEvent::Len(0));
+            return Ok(());
+        };
+        let tag = self.scan_cursor_tag(key_db(key));
+        let mut sink = move |ev: SscanReplyEvent<'_>| match ev {
+            SscanReplyEvent::Cursor(next) => {
+                sink(SscanReplyEvent::Cursor(mint_scan_cursor(tag, next)))
+            }
+            ev => sink(ev),
+        };
         if self.expires_count != 0 {
             self.drop_if_expired(key, now_ms);
         }
@@ -30828,7 +32419,6 @@ impl Store {
         } else {
             0
         };
-        let max_intset_entries = self.set_max_intset_entries;
         let max_listpack_entries = self.set_max_listpack_entries;
         let max_listpack_value = self.set_max_listpack_value;
         // (cc_fr) Classify the MATCH glob ONCE, not per member. Byte-identical to
@@ -30844,7 +32434,7 @@ impl Store {
                     Value::Set(s) => {
                         let is_listpack_or_intset = !entry
                             .has_flag(ENTRY_FORCE_SET_HASHTABLE_ENCODING)
-                            && (Self::set_fits_intset(s, max_intset_entries)
+                            && (s.is_intset()
                                 || Self::set_fits_listpack(
                                     s,
                                     max_listpack_entries,
@@ -30923,6 +32513,7 @@ impl Store {
         mut sink: impl FnMut(ZscanReplyEvent<'_>),
     ) -> Result<(), StoreError> {
         if cursor != 0 {
+            // `zscan` resolves and re-mints the cursor itself.
             let (next, pairs) = self.zscan(key, cursor, pattern, count, now_ms)?;
             sink(ZscanReplyEvent::Cursor(next));
             sink(ZscanReplyEvent::Len(pairs.len()));
@@ -30946,6 +32537,7 @@ impl Store {
         let zset_max_listpack_entries = self.zset_max_listpack_entries;
         let zset_max_listpack_value = self.zset_max_listpack_value;
         let mut zscan_pushback: Option<ZScanResume> = None;
+        let tag = self.scan_cursor_tag(key_db(key));
 
         let outcome = match self.entries.get_mut(key) {
             Some(entry) => {
@@ -31002,7 +32594,7 @@ impl Store {
                                 last_score,
                             });
                         }
-                        sink(ZscanReplyEvent::Cursor(next));
+                        sink(ZscanReplyEvent::Cursor(mint_scan_cursor(tag, next)));
                         sink(ZscanReplyEvent::Len(result.len()));
                         for (member, score) in result {
                             sink(ZscanReplyEvent::Pair(member, score));
@@ -31064,6 +32656,23 @@ impl Store {
         pattern: Option<&[u8]>,
         count: usize,
         now_ms: u64,
+    ) -> Result<(u64, Vec<(Vec<u8>, f64)>), StoreError> {
+        let Some(position) = self.resolve_scan_cursor(key_db(key), cursor) else {
+            return Ok((0, Vec::new()));
+        };
+        let (next, pairs) =
+            self.zscan_with_filter_at::<PREPARED>(key, position, pattern, count, now_ms)?;
+        Ok((self.mint_scan_cursor(key_db(key), next), pairs))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn zscan_with_filter_at<const PREPARED: bool>(
+        &mut self,
+        key: &[u8],
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+        now_ms: u64,
     ) -> Result<(u64, Vec<(Vec<u8>, f64)>), StoreError> {
         // (CrimsonHawk) Guard the bare drop_if_expired — the get_mut below re-probes. (ZSCAN.)
         if self.expires_count != 0 {
@@ -31393,10 +33002,12 @@ impl Store {
                     || self.stream_max_deleted_ids.contains_key(key)
             });
         let can_relink = !has_stream_side_state
-            && self
-                .entries
-                .get(source)
-                .is_some_and(|entry| matches!(&entry.value, Value::String(SmallStr::Heap(_))));
+            && self.entries.get(source).is_some_and(|entry| {
+                matches!(
+                    &entry.value,
+                    Value::String(SmallStr::Heap(_) | SmallStr::Shared(_))
+                )
+            });
         if !can_relink {
             let dirty_before = self.dirty;
             let copied = self.copy_no_stat(source, destination, false, now_ms)?;
@@ -31543,17 +33154,32 @@ impl Store {
         match self.entries.get_mut(key) {
             Some(entry) => match &entry.value {
                 Value::List(l) => {
-                    let result = l.iter().map(<[u8]>::to_vec).collect();
+                    let result = l
+                        .iter()
+                        .take_while(within_budget)
+                        .map(<[u8]>::to_vec)
+                        .collect();
+                    budget_checkpoint()?;
                     entry.touch(now_ms);
                     Ok(result)
                 }
                 Value::Set(s) => {
-                    let result = s.iter().map(|m| m.into_owned()).collect();
+                    let result = s
+                        .iter()
+                        .take_while(within_budget)
+                        .map(|m| m.into_owned())
+                        .collect();
+                    budget_checkpoint()?;
                     entry.touch(now_ms);
                     Ok(result)
                 }
                 Value::SortedSet(zs) => {
-                    let result = zs.iter_asc().map(|(m, _)| m.to_vec()).collect();
+                    let result = zs
+                        .iter_asc()
+                        .take_while(within_budget)
+                        .map(|(m, _)| m.to_vec())
+                        .collect();
+                    budget_checkpoint()?;
                     entry.touch(now_ms);
                     // Upstream sort.c::sortCommand (line 320) DESTRUCTIVELY
                     // converts a sorted set to OBJ_ENCODING_SKIPLIST before
@@ -31797,6 +33423,15 @@ impl Store {
         std::mem::take(&mut self.debug_reload_requested)
     }
 
+    pub fn request_repl_id_change(&mut self) {
+        self.repl_id_change_requested = true;
+    }
+
+    #[must_use]
+    pub fn take_repl_id_change_requested(&mut self) -> bool {
+        std::mem::take(&mut self.repl_id_change_requested)
+    }
+
     pub fn request_bgrewriteaof(&mut self) {
         self.bgrewriteaof_requested = true;
     }
@@ -33081,6 +34716,46 @@ impl Store {
         Some((nodes, list.len(), uncompressed_size))
     }
 
+    /// Describe how `key`'s value is represented: type, encoding, length,
+    /// serialized and in-memory size, and for a quicklist the per-node fill.
+    /// Records one keyspace lookup, like `DEBUG OBJECT`. Returns None when the
+    /// key is absent.
+    pub fn debug_value_info(&mut self, key: &[u8], now_ms: u64) -> Option<ValueDebugInfo> {
+        if !self.record_keyspace_lookup(key, now_ms) {
+            return None;
+        }
+        let encoding = self.live_key_encoding(key)?;
+        // dump_live_key's payload is type byte + body + 10-byte version/CRC
+        // footer; serializedlength counts the body only (see
+        // rdb_serialized_object_len).
+        let serialized_bytes = self.dump_live_key(key)?.len() - 11;
+        let entry = self.entries.get(key)?;
+        let memory_bytes = self.cached_entry_memory_usage_bytes(key, entry);
+        let (value_type, length) = match &entry.value {
+            Value::String(v) => ("string", v.len()),
+            Value::Integer(n) => ("string", n.to_string().len()),
+            Value::Hash(m) => ("hash", m.len()),
+            Value::List(l) => ("list", l.len()),
+            Value::Set(set) => ("set", set.len()),
+            Value::SortedSet(zs) => ("zset", zs.len()),
+            Value::Stream(entries) => ("stream", entries.len()),
+        };
+        let nodes = match &entry.value {
+            Value::List(list) if encoding == "quicklist" => {
+                quicklist_node_layout(list, self.list_max_listpack_size)
+            }
+            _ => Vec::new(),
+        };
+        Some(ValueDebugInfo {
+            value_type,
+            encoding,
+            length,
+            serialized_bytes,
+            memory_bytes,
+            nodes,
+        })
+    }
+
     /// Serialize a key's value for DUMP. Returns None if key doesn't exist.
     /// Format: [type_byte][payload][2-byte RDB version][8-byte CRC64].
     pub fn dump_key(&mut self, key: &[u8], now_ms: u64) -> Option<Vec<u8>> {
@@ -33090,6 +34765,12 @@ impl Store {
         if !self.record_keyspace_lookup(key, now_ms) {
             return None;
         }
+        self.dump_live_key(key)
+    }
+
+    /// [`Self::dump_key`] for a key the caller already looked up (expired keys
+    /// reaped), without recording another keyspace hit.
+    fn dump_live_key(&mut self, key: &[u8]) -> Option<Vec<u8>> {
         let entry = self.entries.get(key)?;
         let modification_count = entry.modification_count;
         if let Some(cache) = self.dump_payload_cache.get(key).filter(|cache| {
@@ -33331,6 +35012,7 @@ impl Store {
     }
 
     /// Restore a key from a DUMP payload and seed optional access metadata.
+    /// `ttl_ms` is relative; 0 restores a persistent key.
     pub fn restore_key_with_metadata(
         &mut self,
         key: &[u8],
@@ -33339,6 +35021,28 @@ impl Store {
         replace: bool,
         metadata: RestoreMetadata,
         now_ms: u64,
+    ) -> Result<(), StoreError> {
+        let expires_at_ms = (ttl_ms > 0).then(|| now_ms.saturating_add(ttl_ms));
+        self.restore_key_at(key, expires_at_ms, payload, replace, metadata, now_ms)
+    }
+
+    /// Restore a key from a DUMP payload with an absolute expiry (`None`
+    /// persists it), as RESTORE ... ABSTTL does. The expiry goes through the
+    /// same insert path as SET, so the volatile-key index and the expires
+    /// counts stay in step.
+    ///
+    /// Mirrors upstream cluster.c::restoreCommand for a deadline already in
+    /// the past: the payload is still validated, but the key is not created,
+    /// and under REPLACE the old value is deleted. Callers see `Ok(())` either
+    /// way and can tell the cases apart by whether the key exists.
+    pub fn restore_key_at(
+        &mut self,
+        key: &[u8],
+        expires_at_ms: Option<u64>,
+        payload: &[u8],
+        replace: bool,
+        metadata: RestoreMetadata,
+        now_ms: u64,
     ) -> Result<(), StoreError> {
         // Check if key exists first (before payload validation) to match Redis behavior.
         // Redis returns BUSYKEY if the key exists and REPLACE is not specified, even
@@ -33702,8 +35406,9 @@ impl Store {
                 // build SetValue::Int straight from it. Byte-identical to the prior
                 // parse-strings + sort_unstable + dedup (a strictly-increasing sequence is
                 // its own sort, and dedup drops nothing), minus the i64->string->i64
-                // round-trip and the redundant O(n log n) sort. A loaded intset stays
-                // intset-encoded regardless of set-max-intset-entries.
+                // round-trip and the redundant O(n log n) sort. An intset longer than
+                // set-max-intset-entries is flagged hashtable by the encoding refresh
+                // below, as rdbLoadObject converts it.
                 let ints = decode_intset_ints(&intset)?;
                 if ints.is_empty() {
                     return Err(StoreError::InvalidDumpPayload);
@@ -33774,11 +35479,23 @@ impl Store {
         // `cursor` marks where the object ended; nothing past it is read.
         // (frankenredis-b19ln)
         let _ = cursor;
-        let expires_at_ms = if ttl_ms > 0 {
-            Some(now_ms.saturating_add(ttl_ms))
-        } else {
-            None
-        };
+        if !self.limits.is_unlimited() {
+            let largest = value.string_len().unwrap_or(0);
+            self.check_write_quota(key, largest, payload.len(), value.element_count(), now_ms)?;
+        }
+        if expires_at_ms.is_some_and(|deadline| deadline <= now_ms) {
+            // Only REPLACE can reach here with the key present (BUSYKEY was
+            // checked above).
+            if self.entries.contains_key(key) {
+                self.hash_field_ttl_clear_for_key(key);
+                self.internal_entries_remove(key);
+                self.drop_stream_side_metadata(key);
+                self.stream_entries_added.remove(key);
+                self.stream_max_deleted_ids.remove(key);
+                self.dirty = self.dirty.saturating_add(1);
+            }
+            return Ok(());
+        }
         let mut entry = Entry::new(value, now_ms);
         entry.set_flag(
             ENTRY_FORCE_SET_LISTPACK_ENCODING,
@@ -33927,6 +35644,22 @@ impl Store {
     /// and encoded/replayed to reconstruct the database from scratch.
     #[must_use]
     pub fn to_aof_commands(&mut self, now_ms: u64) -> Vec<Vec<Vec<u8>>> {
+        let mut commands = Vec::new();
+        let Ok(()) = self.try_for_each_aof_command(now_ms, |argv| {
+            commands.push(argv);
+            Ok::<(), std::convert::Infallible>(())
+        });
+        commands
+    }
+
+    /// [`Store::to_aof_commands`], one command at a time: `emit` sees each
+    /// argv as it is built, so only the key list and one key's commands are
+    /// ever held at once. The first error `emit` returns stops the walk.
+    pub fn try_for_each_aof_command<E>(
+        &mut self,
+        now_ms: u64,
+        mut emit: impl FnMut(Vec<Vec<u8>>) -> Result<(), E>,
+    ) -> Result<(), E> {
         // Expire stale keys first so they aren't serialized. Only TTL-bearing
         // (volatile) keys can be stale, so reuse the volatile-only snapshot reaper
         // instead of cloning EVERY key and calling `drop_if_expired` on each — that
@@ -33936,14 +35669,12 @@ impl Store {
         // re-sorts keys, so the drop order is irrelevant). (frankenredis-cc aofrewrite-expire)
         self.expire_snapshot_volatile_keys(now_ms);
 
-        let mut commands = Vec::new();
-
         for library in self.function_list(None) {
-            commands.push(vec![
+            emit(vec![
                 b"FUNCTION".to_vec(),
                 b"LOAD".to_vec(),
                 library.code.clone(),
-            ]);
+            ])?;
         }
 
         // Snapshot the remaining keys (sorted for deterministic output).
@@ -33965,20 +35696,20 @@ impl Store {
             };
 
             if current_db != Some(db) {
-                commands.push(vec![b"SELECT".to_vec(), db.to_string().into_bytes()]);
+                emit(vec![b"SELECT".to_vec(), db.to_string().into_bytes()])?;
                 current_db = Some(db);
             }
 
             match &entry.value {
                 Value::String(v) => {
-                    commands.push(vec![b"SET".to_vec(), logical_key.clone(), v.to_vec()]);
+                    emit(vec![b"SET".to_vec(), logical_key.clone(), v.to_vec()])?;
                 }
                 Value::Integer(value) => {
-                    commands.push(vec![
+                    emit(vec![
                         b"SET".to_vec(),
                         logical_key.clone(),
                         value.to_string().into_bytes(),
-                    ]);
+                    ])?;
                 }
                 Value::Hash(h) => {
                     if !h.is_empty() {
@@ -33993,7 +35724,7 @@ impl Store {
                                 argv.push(field.to_vec());
                                 argv.push(value.to_vec());
                             }
-                            commands.push(argv);
+                            emit(argv)?;
                         }
 
                         // Redis 7.4 per-field TTLs: reconstruct each field's
@@ -34008,14 +35739,14 @@ impl Store {
                             .collect();
                         field_ttls.sort_by(|a, b| a.0.cmp(&b.0));
                         for (field, expires_at_ms) in field_ttls {
-                            commands.push(vec![
+                            emit(vec![
                                 b"HPEXPIREAT".to_vec(),
                                 logical_key.clone(),
                                 expires_at_ms.to_string().into_bytes(),
                                 b"FIELDS".to_vec(),
                                 b"1".to_vec(),
                                 field,
-                            ]);
+                            ])?;
                         }
                     }
                 }
@@ -34027,7 +35758,7 @@ impl Store {
                             for item in chunk {
                                 argv.push((*item).to_vec());
                             }
-                            commands.push(argv);
+                            emit(argv)?;
                         }
                     }
                 }
@@ -34041,7 +35772,7 @@ impl Store {
                             for member in chunk {
                                 argv.push(member.clone());
                             }
-                            commands.push(argv);
+                            emit(argv)?;
                         }
                     }
                 }
@@ -34060,7 +35791,7 @@ impl Store {
                                 argv.push(redis_score_to_string(*score).into_bytes());
                                 argv.push((*member).to_vec());
                             }
-                            commands.push(argv);
+                            emit(argv)?;
                         }
                     }
                 }
@@ -34070,7 +35801,7 @@ impl Store {
                         // empty stream with an XADD that immediately trims
                         // itself away. This creates the stream key before
                         // the following XSETID / XGROUP commands replay.
-                        commands.push(vec![
+                        emit(vec![
                             b"XADD".to_vec(),
                             logical_key.clone(),
                             b"MAXLEN".to_vec(),
@@ -34078,7 +35809,7 @@ impl Store {
                             b"0-1".to_vec(),
                             b"x".to_vec(),
                             b"y".to_vec(),
-                        ]);
+                        ])?;
                     } else {
                         // Each stream entry becomes a separate XADD command.
                         for ((ms, seq), fields) in entries.iter() {
@@ -34089,7 +35820,7 @@ impl Store {
                                 argv.push(fname.to_vec());
                                 argv.push(fval.to_vec());
                             }
-                            commands.push(argv);
+                            emit(argv)?;
                         }
                     }
 
@@ -34108,7 +35839,7 @@ impl Store {
                     let max_deleted = self.stream_max_deleted_id(&physical_key).unwrap_or((0, 0));
                     let (ms, seq) = watermark;
                     let (deleted_ms, deleted_seq) = max_deleted;
-                    commands.push(vec![
+                    emit(vec![
                         b"XSETID".to_vec(),
                         logical_key.clone(),
                         format!("{ms}-{seq}").into_bytes(),
@@ -34116,7 +35847,7 @@ impl Store {
                         entries_added.to_string().into_bytes(),
                         b"MAXDELETEDID".to_vec(),
                         format!("{deleted_ms}-{deleted_seq}").into_bytes(),
-                    ]);
+                    ])?;
 
                     // Emit XGROUP CREATE for each consumer group.
                     if let Some(groups) = self.stream_groups.get(&physical_key) {
@@ -34138,16 +35869,20 @@ impl Store {
                                 || b"-1".to_vec(),
                                 |entries_read| entries_read.to_string().into_bytes(),
                             ));
-                            commands.push(create);
+                            emit(create)?;
 
                             // (frankenredis-377jl) O(P + C log C) grouped emit,
                             // replacing the old O(C·P) per-consumer PEL rescan.
+                            let mut consumer_commands = Vec::new();
                             append_group_consumer_aof_commands(
                                 group,
                                 &logical_key,
                                 group_name,
-                                &mut commands,
+                                &mut consumer_commands,
                             );
+                            for argv in consumer_commands {
+                                emit(argv)?;
+                            }
                         }
                     }
                 }
@@ -34155,15 +35890,15 @@ impl Store {
 
             // Emit PEXPIREAT if the key has an expiry timestamp.
             if let Some(exp_ms) = self.expiry_ms(physical_key.as_slice()) {
-                commands.push(vec![
+                emit(vec![
                     b"PEXPIREAT".to_vec(),
                     logical_key.clone(),
                     exp_ms.to_string().into_bytes(),
-                ]);
+                ])?;
             }
         }
 
-        commands
+        Ok(())
     }
 }
 
@@ -34367,34 +36102,55 @@ fn quicklist_fallback_node_count(list: &ListValue, list_max_listpack_size: i64)
 /// debug.c::debugCommand byte-for-byte (each packed node contributes its
 /// `lpBytes` listpack size; a plain node contributes its raw element size).
 fn quicklist_node_stats(list: &ListValue, list_max_listpack_size: i64) -> (usize, usize) {
+    let layout = quicklist_node_layout(list, list_max_listpack_size);
+    let total = layout.iter().map(|node| node.bytes).sum();
+    (layout.len(), total)
+}
+
+/// Per-node entry count and byte size of the quicklist layout DUMP emits for
+/// `list`, in list order — the node splitting [`quicklist_node_stats`] sums.
+fn quicklist_node_layout(list: &ListValue, list_max_listpack_size: i64) -> Vec<ValueNodeInfo> {
     if list.is_empty() {
-        return (0, 0);
+        return Vec::new();
     }
     if let Some(nodes) = list.quicklist_packed_nodes(list_max_listpack_size) {
-        let total: usize = nodes.iter().map(|node| node.bytes.len()).sum();
-        return (nodes.len(), total);
+        return nodes
+            .iter()
+            .map(|node| ValueNodeInfo {
+                entries: listpack_header_entry_count(&node.bytes),
+                bytes: node.bytes.len(),
+            })
+            .collect();
     }
     if let Some(chunks) = list.retained_listpack_chunks()
         && retained_quicklist2_chunks_match_dump_rules(&chunks, list_max_listpack_size)
     {
-        let total: usize = chunks.iter().map(|chunk| chunk.bytes.len()).sum();
-        return (chunks.len(), total);
+        return chunks
+            .iter()
+            .map(|chunk| ValueNodeInfo {
+                entries: chunk.entries.len(),
+                bytes: chunk.bytes.len(),
+            })
+            .collect();
     }
 
-    let mut nodes: usize = 0;
-    let mut total: usize = 0;
+    let mut nodes = Vec::new();
     let mut packed_len: usize = 0;
     let mut packed_bytes = LISTPACK_FRAME_OVERHEAD;
     for item in list.iter() {
         if quicklist_plain_node_required(item, list_max_listpack_size) {
             if packed_len > 0 {
-                nodes += 1;
-                total += packed_bytes;
+                nodes.push(ValueNodeInfo {
+                    entries: packed_len,
+                    bytes: packed_bytes,
+                });
                 packed_len = 0;
                 packed_bytes = LISTPACK_FRAME_OVERHEAD;
             }
-            nodes += 1;
-            total += item.len();
+            nodes.push(ValueNodeInfo {
+                entries: 1,
+                bytes: item.len(),
+            });
             continue;
         }
         let entry_bytes = listpack_entry_encoded_len(item);
@@ -34406,8 +36162,10 @@ fn quicklist_node_stats(list: &ListValue, list_max_listpack_size: i64) -> (usize
                 list_max_listpack_size,
             )
         {
-            nodes += 1;
-            total += packed_bytes;
+            nodes.push(ValueNodeInfo {
+                entries: packed_len,
+                bytes: packed_bytes,
+            });
             packed_len = 0;
             packed_bytes = LISTPACK_FRAME_OVERHEAD;
         }
@@ -34415,10 +36173,21 @@ fn quicklist_node_stats(list: &ListValue, list_max_listpack_size: i64) -> (usize
         packed_bytes += entry_bytes;
     }
     if packed_len > 0 {
-        nodes += 1;
-        total += packed_bytes;
+        nodes.push(ValueNodeInfo {
+            entries: packed_len,
+            bytes: packed_bytes,
+        });
     }
-    (nodes, total)
+    nodes
+}
+
+/// Entry count from a listpack's `num-elements` header. A packed quicklist node
+/// is at most 64 KiB and every entry takes at least two bytes, so the count
+/// never reaches the header's `u16::MAX` "unknown" marker.
+fn listpack_header_entry_count(listpack: &[u8]) -> usize {
+    listpack
+        .get(4..6)
+        .map_or(0, |header| usize::from(u16::from_le_bytes([header[0], header[1]])))
 }
 
 fn retained_quicklist2_chunks_match_dump_rules(
@@ -36108,7 +37877,9 @@ fn estimate_string_value_memory_usage_bytes(bytes: &[u8], entry: &Entry) -> usiz
         && is_int_encoded_string(bytes)
     {
         REDIS_OBJECT_OVERHEAD_BYTES
-    } else if !entry.has_flag(ENTRY_FORCE_RAW_ENCODING) && bytes.len() <= 44 {
+    } else if !entry.has_flag(ENTRY_FORCE_RAW_ENCODING)
+        && bytes.len() <= OBJ_ENCODING_EMBSTR_SIZE_LIMIT
+    {
         redis_allocation_size(
             bytes
                 .len()
@@ -37799,6 +39570,24 @@ fn bitfield_write(bytes: &mut [u8], bit_offset: u64, bits: u8, value: i64) {
     }
 }
 
+/// Resolve a Redis `start`/`stop` rank pair against a collection of `len`
+/// elements to an inclusive `(start, stop)` index range, or `None` when the
+/// range is empty. Negative indexes count from the end; after translation a
+/// start below 0 clamps to 0 and a stop past the end clamps to `len - 1`, and
+/// an inverted or wholly out-of-range window is empty — the shared rule of
+/// upstream `lrangeGeneric`/`ltrimCommand`/`zrangeGenericCommand`/
+/// `zremrangeGenericCommand`. LRANGE, LTRIM, ZRANGE-by-rank,
+/// ZREMRANGEBYRANK and (after its own stop clamp) GETRANGE all go through it.
+fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len_i = i64::try_from(len).unwrap_or(i64::MAX);
+    let start = normalize_index(start, len_i).max(0);
+    let stop = normalize_index(stop, len_i).min(len_i - 1);
+    if start > stop || start >= len_i || stop < 0 {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
 fn normalize_index(index: i64, len: i64) -> i64 {
     if index < 0 {
         len.saturating_add(index)
@@ -39092,23 +40881,6 @@ fn extract_quoted_string(s: &str) -> Option<String> {
     Some(rest[..end].to_string())
 }
 
-/// (frankenredis-2wgom) The longest LITERAL prefix every string matching glob
-/// `pattern` must start with: the bytes before the first unescaped glob
-/// metacharacter (`*`, `?`, `[`) or escape (`\`). Stopping at `\` *under*-
-/// approximates the true prefix (a shorter prefix widens the candidate range),
-/// which is always safe because the per-candidate `glob_match` still filters
-/// exactly. Empty when the pattern opens with a metacharacter — no pruning.
-fn glob_literal_prefix(pattern: &[u8]) -> &[u8] {
-    let mut i = 0;
-    while i < pattern.len() {
-        match pattern[i] {
-            b'*' | b'?' | b'[' | b'\\' => break,
-            _ => i += 1,
-        }
-    }
-    &pattern[..i]
-}
-
 /// (frankenredis-2wgom) Exclusive upper bound for the `BTreeSet` range that
 /// selects exactly the keys having `prefix` as a prefix: increment the last byte
 /// below `0xFF`, dropping trailing `0xFF`s. `None` means an all-`0xFF` (or empty)
@@ -39142,46 +40914,6 @@ fn scan_pattern_matches(pattern: Option<&[u8]>, item: &[u8]) -> bool {
     }
 }
 
-/// A glob pattern with its literal shape classified ONCE, so a scan can match many strings without
-/// re-running [`literal_glob_shape`] per candidate. `SCAN`/`KEYS` match a fixed pattern against
-/// every key in a database, and the per-key `glob_match` re-classified the pattern each call
-/// (`glob_match` was ~7.3% self on a 20k-key `SCAN MATCH`). [`PreparedGlob::matches`] is
-/// **byte-identical** to `glob_match(pattern, s)` for every `s` (same classification, same
-/// matchers), just hoisted. (cc_fr)
-pub struct PreparedGlob<'a> {
-    pattern: &'a [u8],
-    shape: Option<LiteralGlob<'a>>,
-}
-
-impl<'a> PreparedGlob<'a> {
-    #[inline]
-    #[must_use]
-    pub fn matches(&self, string: &[u8]) -> bool {
-        // Mirror `glob_match`'s empty-string rule exactly: an empty string matches ONLY an empty
-        // pattern, regardless of the literal shape.
-        if string.is_empty() {
-            return self.pattern.is_empty();
-        }
-        match self.shape {
-            Some(LiteralGlob::Exact(lit)) => string == lit,
-            Some(LiteralGlob::Prefix(lit)) => string.starts_with(lit),
-            Some(LiteralGlob::Suffix(lit)) => string.ends_with(lit),
-            Some(LiteralGlob::Contains(lit)) => literal_glob_contains(string, lit),
-            None => glob_match_inner(self.pattern, string, 0, 0),
-        }
-    }
-}
-
-/// Classify `pattern` once for repeated matching (see [`PreparedGlob`]).
-#[inline]
-#[must_use]
-pub fn glob_prepare(pattern: &[u8]) -> PreparedGlob<'_> {
-    PreparedGlob {
-        pattern,
-        shape: literal_glob_shape(pattern),
-    }
-}
-
 /// A SCAN-family `MATCH` filter with its glob shape classified ONCE, preserving
 /// [`scan_pattern_matches`] semantics exactly so it can be hoisted out of a scan's
 /// per-item loop: `None` and the lone-`*` allkeys shortcut match every item
@@ -39217,228 +40949,6 @@ impl<'a> ScanFilter<'a> {
     }
 }
 
-pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
-    // (frankenredis-z9dc3) redis stringmatchlen never enters its match loop for an
-    // empty string, so an empty string matches ONLY an empty pattern. A single `*`
-    // matches the empty key via the KEYS/SCAN `allkeys` shortcut (is_star), NOT via
-    // this matcher — so `**`/`***`/`?`/etc. must NOT match the empty string here
-    // (else KEYS/SCAN/HSCAN `**` wrongly include the empty key/field, and PSUBSCRIBE
-    // `*` wrongly matches an empty channel). The previous code consumed all trailing
-    // stars, making `**` match "". Non-empty strings (incl. mid-match `a**` vs `a`)
-    // are unaffected.
-    if string.is_empty() {
-        return pattern.is_empty();
-    }
-    // (CrimsonHawk) Literal fast paths: when the pattern is a metachar-free literal
-    // optionally bracketed by a single leading/trailing `*`, the match reduces to a
-    // vectorized memcmp instead of the char-by-char backtracking matcher. These are
-    // the dominant KEYS / SCAN-MATCH / PSUBSCRIBE / keyspace-notify shapes
-    // (`user:*`, `*.tmp`, an exact channel). Byte-exact: the empty-string case is
-    // handled above, so these only see non-empty strings; a single trailing/leading
-    // `*` absorbs the remainder incl. empty, so `starts_with`/`ends_with(b"")` ==
-    // true matches the matcher. Measured isolated A/B vs the backtracker: prefix
-    // -18..25%, suffix -49%, exact -54%, contains -71%/-86% (via the first-byte-skip
-    // `literal_glob_contains`, NOT a naive O(n*m) window scan which measured +66%).
-    match literal_glob_shape(pattern) {
-        Some(LiteralGlob::Exact(lit)) => return string == lit,
-        Some(LiteralGlob::Prefix(lit)) => return string.starts_with(lit),
-        Some(LiteralGlob::Suffix(lit)) => return string.ends_with(lit),
-        Some(LiteralGlob::Contains(lit)) => return literal_glob_contains(string, lit),
-        None => {}
-    }
-    glob_match_inner(pattern, string, 0, 0)
-}
-
-/// Classification of a pattern that is a metachar-free literal optionally framed
-/// by a single leading/trailing `*` — the cases [`glob_match`] can serve with a
-/// `==` / `starts_with` / `ends_with` memcmp instead of the backtracking matcher.
-enum LiteralGlob<'a> {
-    /// No star: matches iff the string equals the literal.
-    Exact(&'a [u8]),
-    /// `<literal>*`: matches iff the string starts with the literal.
-    Prefix(&'a [u8]),
-    /// `*<literal>`: matches iff the string ends with the literal.
-    Suffix(&'a [u8]),
-    /// `*<literal>*`: matches iff the string contains the literal.
-    Contains(&'a [u8]),
-}
-
-/// Dep-free substring search for the `*<literal>*` fast path: scan for the
-/// literal's first byte with a vectorizable `position`, then verify the rest.
-/// Skips non-first-byte positions fast (a naive O(n*m) window-equality scan
-/// MEASURED +66% — slower than the backtracker); this measured -71% (long keys) /
-/// -86% (`*aa*` over `a^n`) vs the matcher. Worst case (first byte recurs at every
-/// position, e.g. `*ab*` over `a^n`) is O(n*m), identical to the backtracker, so
-/// never a regression. Byte-exact.
-#[inline]
-fn literal_glob_contains(hay: &[u8], needle: &[u8]) -> bool {
-    let m = needle.len();
-    if m == 0 {
-        return true;
-    }
-    if m > hay.len() {
-        return false;
-    }
-    let first = needle[0];
-    let limit = hay.len() - m; // last valid start index
-    let mut start = 0usize;
-    while start <= limit {
-        match hay[start..=limit].iter().position(|&b| b == first) {
-            None => return false,
-            Some(off) => {
-                let pos = start + off;
-                if &hay[pos..pos + m] == needle {
-                    return true;
-                }
-                start = pos + 1;
-            }
-        }
-    }
-    false
-}
-
-#[inline]
-fn literal_glob_metachar_free(s: &[u8]) -> bool {
-    !s.iter()
-        .any(|&b| b == b'*' || b == b'?' || b == b'[' || b == b'\\')
-}
-
-#[inline]
-fn literal_glob_shape(pattern: &[u8]) -> Option<LiteralGlob<'_>> {
-    let n = pattern.len();
-    let lead = pattern.first() == Some(&b'*');
-    let trail = n >= 1 && pattern[n - 1] == b'*';
-    if lead && trail {
-        // `*lit*` (contains) when the body is a non-empty metachar-free literal
-        // bracketed by exactly two stars. `*`/`**` (empty body, n<3) keep their
-        // empty-string semantics in the matcher.
-        if n >= 3 {
-            let body = &pattern[1..n - 1];
-            return (!body.is_empty() && literal_glob_metachar_free(body))
-                .then_some(LiteralGlob::Contains(body));
-        }
-        None
-    } else if trail {
-        let lit = &pattern[..n - 1];
-        literal_glob_metachar_free(lit).then_some(LiteralGlob::Prefix(lit))
-    } else if lead {
-        let lit = &pattern[1..];
-        // n>=2 guaranteed (n>=1 and pattern[0]=='*' but not trail), lit non-empty.
-        literal_glob_metachar_free(lit).then_some(LiteralGlob::Suffix(lit))
-    } else {
-        literal_glob_metachar_free(pattern).then_some(LiteralGlob::Exact(pattern))
-    }
-}
-
-fn glob_match_inner(pattern: &[u8], string: &[u8], mut pi: usize, mut si: usize) -> bool {
-    let mut star_pi = usize::MAX;
-    let mut star_si = usize::MAX;
-
-    while si < string.len() {
-        if pi < pattern.len() && pattern[pi] == b'\\' && pi + 1 < pattern.len() {
-            // Escaped character: must match literally.
-            if string[si] == pattern[pi + 1] {
-                pi += 2;
-                si += 1;
-                continue;
-            }
-        } else if pi < pattern.len() && pattern[pi] == b'*' {
-            star_pi = pi;
-            star_si = si;
-            pi += 1;
-            continue;
-        } else if pi < pattern.len() && pattern[pi] == b'?' {
-            pi += 1;
-            si += 1;
-            continue;
-        } else if pi < pattern.len() && pattern[pi] == b'[' {
-            if let Some((matched, end)) = match_character_class(pattern, pi, string[si])
-                && matched
-            {
-                pi = end;
-                si += 1;
-                continue;
-            }
-        } else if pi < pattern.len() && pattern[pi] == string[si] {
-            pi += 1;
-            si += 1;
-            continue;
-        }
-
-        // Backtrack to last star.
-        if star_pi != usize::MAX {
-            pi = star_pi + 1;
-            star_si += 1;
-            si = star_si;
-            continue;
-        }
-
-        return false;
-    }
-
-    // Consume trailing stars.
-    while pi < pattern.len() && pattern[pi] == b'*' {
-        pi += 1;
-    }
-    pi == pattern.len()
-}
-
-/// Match a `[...]` character class at `pattern[pi]`.
-/// Returns `Some((matched, index_after_bracket))` or `None` if malformed.
-fn match_character_class(pattern: &[u8], pi: usize, ch: u8) -> Option<(bool, usize)> {
-    debug_assert_eq!(pattern[pi], b'[');
-    let mut i = pi + 1;
-    let negate = i < pattern.len() && pattern[i] == b'^';
-    if negate {
-        i += 1;
-    }
-
-    let mut matched = false;
-    loop {
-        if i + 1 < pattern.len() && pattern[i] == b'\\' {
-            i += 1;
-            if pattern[i] == ch {
-                matched = true;
-            }
-            i += 1;
-            continue;
-        }
-
-        if i >= pattern.len() {
-            // Redis malformed-class behavior: treat the final class byte as the terminator.
-            if i > pi + 1 {
-                i -= 1;
-            }
-            break;
-        }
-
-        if pattern[i] == b']' {
-            break;
-        }
-
-        if i + 2 < pattern.len() && pattern[i + 1] == b'-' {
-            let mut lo = pattern[i];
-            let mut hi = pattern[i + 2];
-            if lo > hi {
-                std::mem::swap(&mut lo, &mut hi);
-            }
-            if ch >= lo && ch <= hi {
-                matched = true;
-            }
-            i += 3;
-            continue;
-        }
-
-        if pattern[i] == ch {
-            matched = true;
-        }
-        i += 1;
-    }
-
-    let result = if negate { !matched } else { matched };
-    Some((result, (i + 1).min(pattern.len())))
-}
-
 #[cfg(test)]
 mod quicklist_dump_fix_tests {
     use super::{
@@ -39521,21 +41031,23 @@ mod tests {
         HLL_REDIS_MAGIC, HLL_REDIS_SPARSE_ENCODING, HLL_REDIS_SPARSE_MAX_BYTES, HLL_REGISTERS,
         HLL_SPARSE_XZERO_BIT, HashFieldMap, HashFieldTtl, HashFieldTtlCondition, HashFieldTtlSet,
         HashFieldTtlUnit, LFU_INIT_VAL, LatencySample, MaxmemoryPolicy, MaxmemoryPressureLevel,
-        NOTIFY_EVICTED, NOTIFY_EXPIRED, NOTIFY_GENERIC, NOTIFY_KEYEVENT, PttlValue, RDB_DUMP_VERSION,
-        RDB_OPCODE_FUNCTION2, RDB_TYPE_HASH, RDB_TYPE_HASH_LISTPACK, RDB_TYPE_HASH_ZIPLIST,
-        RDB_TYPE_HASH_ZIPMAP, RDB_TYPE_LIST, RDB_TYPE_LIST_QUICKLIST, RDB_TYPE_LIST_QUICKLIST_2,
-        RDB_TYPE_LIST_ZIPLIST, RDB_TYPE_SET, RDB_TYPE_SET_INTSET, RDB_TYPE_SET_LISTPACK,
-        RDB_TYPE_STREAM_LISTPACKS_3, RDB_TYPE_STRING, RDB_TYPE_ZSET, RDB_TYPE_ZSET_2,
-        RDB_TYPE_ZSET_LISTPACK, RDB_TYPE_ZSET_ZIPLIST, REDIS_OBJECT_OVERHEAD_BYTES,
-        REDIS_SCORE_BYTES, RestoreMetadata, ScoreBound, SetValue, SmallStr, Store, StoreError,
-        StreamAutoClaimOptions, StreamAutoClaimReply, StreamClaimOptions, StreamClaimReply,
-        StreamGroupReadCursor, StreamGroupReadOptions, StreamPendingEntry, Value, ValueType,
-        decode_length, decode_listpack_strings, decode_rdb_string, encode_db_key,
-        encode_hash_listpack_dump, encode_intset, encode_length, encode_listpack_strings,
-        encode_set_listpack_dump, estimate_listpack_entry_bytes, estimate_listpack_score_bytes,
+        NOTIFY_EVICTED, NOTIFY_EXPIRED, NOTIFY_GENERIC, NOTIFY_KEYEVENT, PttlValue,
+        RDB_DUMP_VERSION, RDB_OPCODE_FUNCTION2, RDB_TYPE_HASH, RDB_TYPE_HASH_LISTPACK,
+        RDB_TYPE_HASH_ZIPLIST, RDB_TYPE_HASH_ZIPMAP, RDB_TYPE_LIST, RDB_TYPE_LIST_QUICKLIST,
+        RDB_TYPE_LIST_QUICKLIST_2, RDB_TYPE_LIST_ZIPLIST, RDB_TYPE_SET, RDB_TYPE_SET_INTSET,
+        RDB_TYPE_SET_LISTPACK, RDB_TYPE_STREAM_LISTPACKS_3, RDB_TYPE_STRING, RDB_TYPE_ZSET,
+        RDB_TYPE_ZSET_2, RDB_TYPE_ZSET_LISTPACK, RDB_TYPE_ZSET_ZIPLIST,
+        REDIS_OBJECT_OVERHEAD_BYTES, REDIS_SCORE_BYTES, RestoreMetadata, ScoreBound, SetValue,
+        SmallStr, SortWeight, Store, StoreError, StreamAutoClaimOptions, StreamAutoClaimReply,
+        StreamClaimOptions, StreamClaimReply, StreamGroupReadCursor, StreamGroupReadOptions,
+        StreamPendingEntry, Value, ValueNodeInfo, ValueType, decode_length,
+        decode_listpack_strings, decode_rdb_string, encode_db_key, encode_hash_listpack_dump,
+        encode_intset, encode_length, encode_listpack_strings, encode_set_listpack_dump,
+        estimate_listpack_entry_bytes, estimate_listpack_score_bytes,
         estimate_set_memory_usage_bytes, hll_encode, hll_encode_sparse_create_from_pfadd, hll_hash,
         hll_rho, hll_sparse_decode, integer_decimal_bytes, lfu_access_minutes, lfu_elapsed_minutes,
-        redis_allocation_size, redis_score_to_string, set_int_to_bytes, ziplist_integer_bytes,
+        normalize_range, parse_sort_weight, redis_allocation_size, redis_score_to_string,
+        set_int_to_bytes, ziplist_integer_bytes,
     };
 
     fn group_read_options(
@@ -39973,6 +41485,33 @@ mod tests {
         assert_eq!(store.stat_keyspace_misses, 1);
     }
 
+    #[test]
+    fn sort_weights_parse_like_upstream_strtod() {
+        for (raw, weight) in [
+            (&b""[..], SortWeight::Number(0.0)),
+            (b"  2.5", SortWeight::Number(2.5)),
+            (b"\t-3", SortWeight::Number(-3.0)),
+            (b"-inf", SortWeight::Number(f64::NEG_INFINITY)),
+            (b"1e3", SortWeight::Number(1000.0)),
+            (b"2.5 ", SortWeight::NotNumber),
+            (b" ", SortWeight::NotNumber),
+            (b"nan", SortWeight::NotNumber),
+            (b"1e400", SortWeight::NotNumber),
+            (b"abc", SortWeight::NotNumber),
+        ] {
+            assert_eq!(parse_sort_weight(raw), weight, "{raw:?}");
+        }
+
+        let mut store = Store::new();
+        store.set(b"blank".to_vec(), Vec::new(), None, 0);
+        store.set(b"padded".to_vec(), b" 7".to_vec(), None, 0);
+        store.rpush(b"list", &[b"1".to_vec()], 0).expect("rpush");
+        assert_eq!(store.get_sort_weight(b"blank", 0), SortWeight::Number(0.0));
+        assert_eq!(store.get_sort_weight(b"padded", 0), SortWeight::Number(7.0));
+        assert_eq!(store.get_sort_weight(b"list", 0), SortWeight::Missing);
+        assert_eq!(store.get_sort_weight(b"absent", 0), SortWeight::Missing);
+    }
+
     #[test]
     fn sort_destructively_converts_listpack_zset_to_skiplist() {
         // Upstream sort.c::sortCommand converts a sorted set to skiplist before
@@ -45120,7 +46659,7 @@ mod tests {
         assert_eq!(
             consumers
                 .into_iter()
-                .map(|(name, _pending, _idle, _inactive)| name)
+                .map(|info| info.name)
                 .collect::<Vec<_>>(),
             vec![b"c1".to_vec(), b"c2".to_vec()]
         );
@@ -48333,6 +49872,66 @@ mod tests {
         assert_eq!(store.get(b"large", 1).unwrap(), Some(large));
     }
 
+    #[test]
+    fn value_size_no_stats_counts_bytes_or_elements_without_stats() {
+        let mut store = Store::new();
+        store.set(b"s".to_vec(), b"hello".to_vec(), None, 0);
+        store.set(b"n".to_vec(), b"-42".to_vec(), None, 0);
+        store.hset(b"h", b"f1".to_vec(), b"v".to_vec(), 0).unwrap();
+        store.hset(b"h", b"f2".to_vec(), b"v".to_vec(), 0).unwrap();
+        store.set(b"gone".to_vec(), b"v".to_vec(), Some(10), 0);
+        let hits = store.stat_keyspace_hits;
+
+        assert_eq!(store.value_size_no_stats(b"s", 1), 5);
+        assert_eq!(store.value_size_no_stats(b"n", 1), 3);
+        assert_eq!(store.value_size_no_stats(b"h", 1), 2);
+        assert_eq!(store.value_size_no_stats(b"gone", 20), 0);
+        assert_eq!(store.value_size_no_stats(b"missing", 1), 0);
+        assert_eq!(store.stat_keyspace_hits, hits);
+        assert_eq!(store.stat_keyspace_misses, 0);
+    }
+
+    #[test]
+    fn string_snapshot_survives_later_writes_to_the_key() {
+        let mut store = Store::new();
+        let value = vec![0b1011_0000_u8; 64];
+        store.set(b"bits".to_vec(), value.clone(), None, 0);
+
+        let snapshot = store
+            .string_snapshot(b"bits", 1)
+            .unwrap()
+            .expect("present key");
+        assert_eq!(snapshot.as_slice(), value.as_slice());
+        assert!(matches!(
+            &store.entries.get(b"bits".as_slice()).unwrap().value,
+            Value::String(crate::SmallStr::Shared(_))
+        ));
+        assert_eq!(
+            Store::bitcount_bytes(&snapshot, Some(1), Some(-2), BitRangeUnit::Byte).unwrap(),
+            store
+                .bitcount(b"bits", Some(1), Some(-2), BitRangeUnit::Byte, 1)
+                .unwrap()
+        );
+
+        // A write while the snapshot is held copies the value; the reader
+        // keeps what it was given.
+        store.setrange(b"bits", 0, b"\xff", 2).unwrap();
+        assert_eq!(snapshot[0], 0b1011_0000);
+        assert_eq!(store.get(b"bits", 2).unwrap().unwrap()[0], 0xff);
+
+        store.set(b"n".to_vec(), b"12345".to_vec(), None, 3);
+        assert_eq!(
+            store.string_snapshot(b"n", 3).unwrap().unwrap().as_slice(),
+            b"12345"
+        );
+        assert_eq!(store.string_snapshot(b"missing", 3).unwrap(), None);
+        store.rpush(b"list", &[b"a".to_vec()], 3).unwrap();
+        assert_eq!(
+            store.string_snapshot(b"list", 3),
+            Err(StoreError::WrongType)
+        );
+    }
+
     #[test]
     fn persist_removes_expiry() {
         let mut store = Store::new();
@@ -49085,6 +50684,53 @@ mod tests {
         assert_eq!(result, vec![b"later".to_vec(), b"live".to_vec()]);
     }
 
+    #[test]
+    fn keys_borrow_scan_matches_keys_matching_in_db() {
+        fn build() -> Store {
+            let mut store = Store::new();
+            for db in [0, 2] {
+                for (key, ttl) in [
+                    (&b"user:1"[..], None),
+                    (b"user:2", Some(50)),
+                    (b"user:3", Some(500)),
+                    (b"order:1", Some(50)),
+                    (b"order:2", None),
+                    (b"u", None),
+                ] {
+                    store.set(encode_db_key(db, key), b"v".to_vec(), ttl, 0);
+                }
+            }
+            store
+        }
+        for db in [0, 2, 5] {
+            for pattern in [
+                &b"*"[..],
+                b"user:*",
+                b"*:1",
+                b"order:?",
+                b"u",
+                b"[ou]*",
+                b"nope*",
+            ] {
+                let mut owned = build();
+                let mut borrowed = build();
+                let expected = owned.keys_matching_in_db(db, pattern, 100);
+                let mut keys = Vec::new();
+                let len =
+                    borrowed.keys_borrow_scan(db, pattern, 100, |key| keys.push(key.to_vec()));
+                assert_eq!(keys, expected, "db {db} pattern {pattern:?}");
+                assert_eq!(len, expected.len());
+                for check_db in [0, 2] {
+                    assert_eq!(
+                        borrowed.dbsize_in_db(check_db),
+                        owned.dbsize_in_db(check_db),
+                        "db {db} pattern {pattern:?} reaped a different set"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn dbsize_counts_live_keys() {
         let mut store = Store::new();
@@ -49102,6 +50748,48 @@ mod tests {
         assert_eq!(store.dbsize_in_db(0), 1);
     }
 
+    #[test]
+    fn huge_database_count_grows_counters_on_first_write() {
+        let mut store = Store::new();
+        store.set_database_count(i32::MAX as usize);
+        assert!(store.db_key_counts.len() <= super::DEFAULT_NUM_DATABASES);
+        assert_eq!(store.tracked_database_count(), store.db_key_counts.len());
+
+        let db = 1_000_000;
+        store.set(encode_db_key(db, b"k"), b"v".to_vec(), Some(100), 0);
+        assert_eq!(store.dbsize_in_db(db), 1);
+        assert_eq!(store.expires_in_db(db), 1);
+        assert_eq!(store.avg_ttl_in_db(db, 0), 100);
+        assert_eq!(store.tracked_database_count(), db + 1);
+        assert_eq!(store.dbsize_in_db(db + 1), 0);
+
+        // Lowering the count drops the counters of databases past it.
+        store.set_database_count(16);
+        assert_eq!(store.tracked_database_count(), 16);
+        assert_eq!(store.dbsize_in_db(db), 0);
+    }
+
+    #[test]
+    fn command_renames_resolve_case_insensitively_at_any_name_length() {
+        use super::{CommandRename, CommandRenames, RENAME_LOOKUP_INLINE_LEN};
+        let mut renames = CommandRenames::default();
+        let long_alias = vec![b'x'; RENAME_LOOKUP_INLINE_LEN + 8];
+        renames.insert(b"CONFIG", b"Cfg");
+        renames.insert(b"flushall", &long_alias);
+        renames.insert(b"debug", b"");
+
+        assert_eq!(renames.resolve(b"CFG"), CommandRename::Alias(b"config"));
+        assert_eq!(
+            renames.resolve(&long_alias.to_ascii_uppercase()),
+            CommandRename::Alias(b"flushall")
+        );
+        assert_eq!(renames.resolve(b"Config"), CommandRename::Hidden);
+        assert_eq!(renames.resolve(b"DEBUG"), CommandRename::Hidden);
+        assert!(renames.is_disabled("debug"));
+        assert_eq!(renames.resolve(b"get"), CommandRename::Unchanged);
+        assert_eq!(renames.resolve(&[b'y'; 200]), CommandRename::Unchanged);
+    }
+
     #[test]
     fn flushdb_clears_all() {
         let mut store = Store::new();
@@ -49111,6 +50799,62 @@ mod tests {
         assert!(store.is_empty());
     }
 
+    #[test]
+    fn recreated_key_never_repeats_a_modification_count() {
+        let mut store = Store::new();
+        store.set(b"k".to_vec(), b"v".to_vec(), None, 0);
+        for _ in 0..3 {
+            store.append(b"k", b"x", 0).unwrap();
+        }
+        let mut seen = vec![store.key_modification_count(b"k", 0)];
+        let recreate = |store: &mut Store, seen: &mut Vec<u64>| {
+            store.set(b"k".to_vec(), b"v".to_vec(), None, 0);
+            let count = store.key_modification_count(b"k", 0);
+            assert!(seen.iter().all(|&old| count > old), "{count} {seen:?}");
+            seen.push(count);
+        };
+
+        store.del(&[b"k".to_vec()], 0);
+        recreate(&mut store, &mut seen);
+        store.flush_database(0);
+        recreate(&mut store, &mut seen);
+        store.flushdb();
+        recreate(&mut store, &mut seen);
+        store.rename(b"k", b"tmp", 0).unwrap();
+        store.rename(b"tmp", b"k", 0).unwrap();
+        let renamed = store.key_modification_count(b"k", 0);
+        assert!(seen.iter().all(|&old| renamed > old));
+        seen.push(renamed);
+        store.set(b"k".to_vec(), b"v".to_vec(), Some(10), 0);
+        assert_eq!(store.get(b"k", 20).unwrap(), None);
+        recreate(&mut store, &mut seen);
+    }
+
+    #[test]
+    fn with_capacity_presizes_keyspace_without_changing_behavior() {
+        let mut sized = Store::with_capacity(1024);
+        assert!(sized.capacity() >= 1024);
+        assert!(sized.is_empty());
+        let before = sized.capacity();
+        let mut plain = Store::new();
+        for i in 0..1024_u32 {
+            let key = format!("key:{i}").into_bytes();
+            let ttl = (i % 4 == 0).then_some(60_000);
+            sized.set(key.clone(), b"v".to_vec(), ttl, 0);
+            plain.set(key, b"v".to_vec(), ttl, 0);
+        }
+        assert_eq!(sized.capacity(), before, "a presized load must not rehash");
+        assert_eq!(sized.dbsize(0), plain.dbsize(0));
+        assert_eq!(sized.keys_matching(b"*", 0), plain.keys_matching(b"*", 0));
+
+        let mut store = Store::new();
+        store.reserve(100);
+        store.reserve_expires(10);
+        assert!(store.capacity() >= 100);
+        store.set(b"k".to_vec(), b"v".to_vec(), Some(1_000), 0);
+        assert_eq!(store.pttl(b"k", 0), PttlValue::Remaining(1_000));
+    }
+
     #[test]
     fn flushdb_releases_keyspace_index_capacity_nf1er() {
         let mut store = Store::new();
@@ -55349,6 +57093,81 @@ mod tests {
         assert!(!store.exists(b"l", 0));
     }
 
+    #[test]
+    fn normalize_range_matches_redis_rank_rules() {
+        // (start, stop, len) -> inclusive index window, as LRANGE/ZRANGE report it.
+        type Case = (i64, i64, usize, Option<(usize, usize)>);
+        let cases: &[Case] = &[
+            (0, -1, 3, Some((0, 2))),
+            (0, 0, 3, Some((0, 0))),
+            (0, 2, 3, Some((0, 2))),
+            (0, 100, 3, Some((0, 2))),
+            (1, -1, 3, Some((1, 2))),
+            (-1, -1, 3, Some((2, 2))),
+            (-2, -1, 3, Some((1, 2))),
+            (-3, -1, 3, Some((0, 2))),
+            (-100, 100, 3, Some((0, 2))),
+            (-100, 0, 3, Some((0, 0))),
+            (-100, -3, 3, Some((0, 0))),
+            (-100, -4, 3, None),
+            (-100, -100, 3, None),
+            (2, 1, 3, None),
+            (-1, -2, 3, None),
+            (3, 5, 3, None),
+            (3, -1, 3, None),
+            (100, 200, 3, None),
+            (1, -3, 3, None),
+            (0, -4, 3, None),
+            (1, -1, 1, None),
+            (0, -1, 1, Some((0, 0))),
+            (-1, 0, 1, Some((0, 0))),
+            (0, -1, 0, None),
+            (0, 0, 0, None),
+            (-1, -1, 0, None),
+            (i64::MIN, i64::MAX, 3, Some((0, 2))),
+            (i64::MAX, i64::MAX, 3, None),
+            (i64::MIN, i64::MIN, 3, None),
+            (0, i64::MIN, 3, None),
+            (5, 7, 10, Some((5, 7))),
+            (-5, -3, 10, Some((5, 7))),
+        ];
+        for &(start, stop, len, expected) in cases {
+            assert_eq!(
+                normalize_range(start, stop, len),
+                expected,
+                "normalize_range({start}, {stop}, {len})"
+            );
+        }
+    }
+
+    #[test]
+    fn ltrim_single_element_list_to_empty_window_deletes_key() {
+        let mut store = Store::new();
+        store.rpush(b"l", &[b"only".to_vec()], 0).unwrap();
+
+        store.ltrim(b"l", 1, -1, 0).unwrap();
+        assert!(!store.exists(b"l", 0));
+        assert_eq!(store.llen(b"l", 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn lrange_start_before_head_clamps_to_first_element() {
+        let mut store = Store::new();
+        store
+            .rpush(b"l", &[b"a".to_vec(), b"b".to_vec(), b"c".to_vec()], 0)
+            .unwrap();
+
+        assert_eq!(
+            store.lrange(b"l", -100, 100, 0).unwrap(),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(store.lrange(b"l", -100, -3, 0).unwrap(), vec![b"a".to_vec()]);
+        // GETRANGE alone clamps a too-negative end to 0 instead of emptying.
+        store.set(b"s".to_vec(), b"hello".to_vec(), None, 0);
+        assert_eq!(store.getrange(b"s", 0, -100, 0).unwrap(), b"h".to_vec());
+        assert_eq!(store.getrange(b"s", -100, 100, 0).unwrap(), b"hello".to_vec());
+    }
+
     #[test]
     fn ltrim_batch_residual_matches_lrange_cc() {
         // (cc_fr) Metamorphic gate for the batch (pop_front_n + pop_back_n) LTRIM: after
@@ -55608,6 +57427,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deterministic_iteration_sorts_set_and_hash_replies() {
+        let mut store = Store::new();
+        assert!(!store.deterministic_iteration());
+        store
+            .sadd(b"s", &[b"c".to_vec(), b"a".to_vec(), b"b".to_vec()], 0)
+            .unwrap();
+        for (field, value) in [(b"z", b"1"), (b"x", b"2"), (b"y", b"3")] {
+            store.hset(b"h", field.to_vec(), value.to_vec(), 0).unwrap();
+        }
+
+        store.set_deterministic_iteration(true);
+        let sorted_members = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+        let sorted_fields = vec![b"x".to_vec(), b"y".to_vec(), b"z".to_vec()];
+        for _ in 0..3 {
+            assert_eq!(store.smembers(b"s", 0).unwrap(), sorted_members);
+            assert_eq!(
+                store.sscan(b"s", 0, None, 10, 0).unwrap(),
+                (0, sorted_members.clone())
+            );
+            assert_eq!(store.hkeys(b"h", 0).unwrap(), sorted_fields);
+            assert_eq!(
+                store.hvals(b"h", 0).unwrap(),
+                vec![b"2".to_vec(), b"3".to_vec(), b"1".to_vec()]
+            );
+            let pairs = store.hgetall(b"h", 0).unwrap();
+            assert_eq!(
+                pairs.iter().map(|(f, _)| f.clone()).collect::<Vec<_>>(),
+                sorted_fields
+            );
+            assert_eq!(store.hscan(b"h", 0, None, 10, 0).unwrap(), (0, pairs));
+        }
+
+        store.set_deterministic_iteration(false);
+        assert_eq!(
+            store.smembers(b"s", 0).unwrap(),
+            vec![b"c".to_vec(), b"a".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(
+            store.hkeys(b"h", 0).unwrap(),
+            vec![b"z".to_vec(), b"x".to_vec(), b"y".to_vec()]
+        );
+    }
+
     #[test]
     fn srem_removes_empty_set_key() {
         let mut store = Store::new();
@@ -56682,7 +58545,7 @@ mod tests {
             "already_promoted",
             "lfu_small_overwrite",
         ] {
-            let (ra, mut a) = build(Store::hset_borrowed_many, case);
+            let (ra, mut a) = build(|s, k, p, n| s.hset_borrowed_many(k, p, n), case);
             let (rb, mut b) = build(Store::hset_borrowed_many_rescan, case);
             assert_eq!(ra, rb, "result mismatch @ {case}");
             assert_eq!(a.dirty, b.dirty, "dirty mismatch @ {case}");
@@ -62635,6 +64498,179 @@ mod tests {
         );
     }
 
+    #[test]
+    fn xreadgroup_history_replays_own_pel_after_crash_and_xclaim() {
+        use crate::{StreamGroupReadCursor, XreadgroupHistEvent};
+        // c1 takes 1..=4 and c2 takes 5. While c1 is down, c2 XCLAIMs 2 and
+        // 3 is XDEL'd. c1's recovery read from 0 must see exactly what it
+        // still owns, in ID order, with 3 as a tombstone.
+        fn build() -> Store {
+            let mut s = Store::new();
+            for i in 1..=5 {
+                s.xadd(
+                    b"s",
+                    (1000, i),
+                    &[(b"f".to_vec(), format!("v{i}").into_bytes())],
+                    0,
+                )
+                .unwrap();
+            }
+            s.xgroup_create(b"s", b"g", (0, 0), false, 0).unwrap();
+            for (consumer, count) in [(b"c1", Some(4)), (b"c2", None)] {
+                s.xreadgroup(
+                    b"s",
+                    b"g",
+                    consumer,
+                    group_read_options(StreamGroupReadCursor::NewEntries, false, count),
+                    10,
+                )
+                .unwrap();
+            }
+            let claim = StreamClaimOptions {
+                min_idle_time_ms: 0,
+                idle_ms: None,
+                time_ms: None,
+                retry_count: None,
+                force: false,
+                justid: true,
+                last_id: None,
+            };
+            s.xclaim(b"s", b"g", b"c2", &[(1000, 2)], claim, 20)
+                .unwrap();
+            s.xdel(b"s", &[(1000, 3)], 20).unwrap();
+            s
+        }
+        fn pel(s: &mut Store) -> Vec<(crate::StreamId, Vec<u8>, u64)> {
+            s.xpending_entries(
+                b"s",
+                b"g",
+                ((0, 0), (u64::MAX, u64::MAX)),
+                usize::MAX,
+                None,
+                30,
+                0,
+            )
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|(id, consumer, _, deliveries)| (id, consumer, deliveries))
+            .collect()
+        }
+        let expected_pel = vec![
+            ((1000, 1), b"c1".to_vec(), 2),
+            ((1000, 2), b"c2".to_vec(), 1),
+            ((1000, 3), b"c1".to_vec(), 1),
+            ((1000, 4), b"c1".to_vec(), 2),
+            ((1000, 5), b"c2".to_vec(), 1),
+        ];
+
+        let mut generic = build();
+        let dirty = generic.dirty;
+        let records = generic
+            .xreadgroup(
+                b"s",
+                b"g",
+                b"c1",
+                group_read_options(StreamGroupReadCursor::Id((0, 0)), false, None),
+                30,
+            )
+            .unwrap()
+            .unwrap();
+        let ids: Vec<_> = records
+            .iter()
+            .map(|(id, fields)| (*id, fields.is_empty()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![((1000, 1), false), ((1000, 3), true), ((1000, 4), false)]
+        );
+        assert_eq!(pel(&mut generic), expected_pel);
+        assert_eq!(generic.dirty, dirty + 1);
+
+        let mut borrowed = build();
+        let dirty = borrowed.dirty;
+        assert!(borrowed.xreadgroup_history_eligible(b"s", b"g", b"c1"));
+        let mut ids = Vec::new();
+        borrowed.xreadgroup_history_borrow_scan(
+            b"s",
+            b"g",
+            b"c1",
+            (0, 0),
+            None,
+            30,
+            |ev| match ev {
+                XreadgroupHistEvent::RecordStart(id, _) => ids.push((id, false)),
+                XreadgroupHistEvent::RecordStartNil(id) => ids.push((id, true)),
+                _ => {}
+            },
+        );
+        assert_eq!(
+            ids,
+            vec![((1000, 1), false), ((1000, 3), true), ((1000, 4), false)]
+        );
+        assert_eq!(pel(&mut borrowed), expected_pel);
+        assert_eq!(borrowed.dirty, dirty + 1);
+    }
+
+    #[test]
+    fn xreadgroup_history_keeps_delivery_counts_when_opted_in() {
+        use crate::StreamGroupReadCursor;
+        let mut store = Store::new();
+        assert!(!store.stream_history_reads_keep_delivery_counts());
+        store.set_stream_history_reads_keep_delivery_counts(true);
+        for i in 1..=2 {
+            store
+                .xadd(b"s", (1000, i), &[(b"f".to_vec(), b"v".to_vec())], 0)
+                .unwrap();
+        }
+        store.xgroup_create(b"s", b"g", (0, 0), false, 0).unwrap();
+        store
+            .xreadgroup(
+                b"s",
+                b"g",
+                b"c1",
+                group_read_options(StreamGroupReadCursor::NewEntries, false, None),
+                10,
+            )
+            .unwrap();
+
+        // Both the generic and the borrowed history read serve the PEL
+        // without counting it as a re-delivery.
+        let records = store
+            .xreadgroup(
+                b"s",
+                b"g",
+                b"c1",
+                group_read_options(StreamGroupReadCursor::Id((0, 0)), false, None),
+                20,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(store.xreadgroup_history_eligible(b"s", b"g", b"c1"));
+        store.xreadgroup_history_borrow_scan(b"s", b"g", b"c1", (0, 0), None, 30, |_| {});
+
+        let pel = store
+            .xpending_entries(
+                b"s",
+                b"g",
+                ((0, 0), (u64::MAX, u64::MAX)),
+                usize::MAX,
+                None,
+                40,
+                0,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            pel,
+            vec![
+                ((1000, 1), b"c1".to_vec(), 30, 1),
+                ((1000, 2), b"c1".to_vec(), 30, 1),
+            ]
+        );
+    }
+
     #[test]
     fn stream_xreadgroup_new_entries_advances_cursor_and_tracks_consumer() {
         let mut store = Store::new();
@@ -64108,7 +66144,7 @@ mod tests {
         assert_eq!(
             consumers
                 .into_iter()
-                .map(|(name, _pending, _idle, _inactive)| name)
+                .map(|info| info.name)
                 .collect::<Vec<_>>(),
             vec![b"c1".to_vec(), b"c2".to_vec()]
         );
@@ -64166,14 +66202,14 @@ mod tests {
             .xinfo_consumers(b"s", b"g", now)
             .unwrap()
             .expect("consumers");
-        let by_name: std::collections::BTreeMap<Vec<u8>, (u64, i64)> = info
+        let by_name: std::collections::BTreeMap<Vec<u8>, (u64, Option<u64>)> = info
             .into_iter()
-            .map(|(name, _pending, idle, inactive)| (name, (idle, inactive)))
+            .map(|info| (info.name, (info.idle_ms, info.inactive_ms)))
             .collect();
         // reader: idle = now - seen(1000) = 1000; inactive = now - active(900) = 1100.
-        assert_eq!(by_name[b"reader".as_slice()], (1000, 1100));
-        // idle_one: idle = now - seen(950) = 1050; inactive = -1 (never active).
-        assert_eq!(by_name[b"idle_one".as_slice()], (1050, -1));
+        assert_eq!(by_name[b"reader".as_slice()], (1000, Some(1100)));
+        // idle_one: idle = now - seen(950) = 1050; never active.
+        assert_eq!(by_name[b"idle_one".as_slice()], (1050, None));
     }
 
     // ── String extension store tests ────────────────────────────────────
@@ -64234,17 +66270,19 @@ mod tests {
 
     #[test]
     fn hincrbyfloat_rejects_stored_value_with_whitespace_6h90t() {
-        // (frankenredis-6h90t) Same rule applies to HINCRBYFLOAT.
+        // (frankenredis-6h90t) Same rule applies to HINCRBYFLOAT, surfaced as
+        // the hash-specific error and leaving the field untouched.
         for stored in &[b" 1.5".as_slice(), b"1.5 ".as_slice(), b"\t1.5".as_slice()] {
             let mut store = Store::new();
             store.hset(b"h", b"f".to_vec(), stored.to_vec(), 0).unwrap();
             let result = store.hincrbyfloat_text(b"h", b"f", b"1.0", 1.0, 0);
             assert_eq!(
                 result,
-                Err(StoreError::ValueNotFloat),
-                "stored={:?} expected ValueNotFloat for HINCRBYFLOAT",
+                Err(StoreError::HashValueNotFloat),
+                "stored={:?} expected HashValueNotFloat for HINCRBYFLOAT",
                 String::from_utf8_lossy(stored),
             );
+            assert_eq!(store.hget(b"h", b"f", 0).unwrap(), Some(stored.to_vec()));
         }
     }
 
@@ -64793,6 +66831,18 @@ mod tests {
         assert_eq!(&v[5..], b"Hi");
     }
 
+    #[test]
+    fn setrange_empty_value_and_wrong_type_leave_keyspace_untouched() {
+        let mut store = Store::new();
+        assert_eq!(store.setrange(b"missing", 0, b"", 0), Ok(0));
+        assert!(!store.exists(b"missing", 0));
+
+        store.rpush(b"l", &[b"a".to_vec()], 0).unwrap();
+        assert_eq!(store.setrange(b"l", 0, b"x", 0), Err(StoreError::WrongType));
+        assert_eq!(store.setrange(b"l", 0, b"", 0), Err(StoreError::WrongType));
+        assert_eq!(store.lrange(b"l", 0, -1, 0).unwrap(), vec![b"a".to_vec()]);
+    }
+
     // ── Set algebra store tests ─────────────────────────────────────────
 
     #[test]
@@ -69926,6 +71976,22 @@ mod tests {
         );
         assert_eq!(cursor, data_end);
 
+        let info = store.debug_value_info(b"l", 101).unwrap();
+        assert_eq!(info.encoding, "quicklist");
+        assert_eq!(
+            info.nodes,
+            vec![
+                ValueNodeInfo {
+                    entries: 50,
+                    bytes: 8_357,
+                },
+                ValueNodeInfo {
+                    entries: 1,
+                    bytes: second_node.len(),
+                },
+            ]
+        );
+
         let mut restored = Store::new();
         restored.restore_key(b"l", 0, &payload, false, 101).unwrap();
         let mut expected = first_batch;
@@ -71408,11 +73474,41 @@ mod tests {
         assert_eq!(store.stat_total_net_output_bytes, 0);
         assert_eq!(store.stat_used_memory_rss, 0);
         assert_eq!(store.stat_used_memory_peak, 0);
-        assert_eq!(store.instantaneous_ops_per_sec(), 0);
-        assert_eq!(store.instantaneous_input_kbps(), 0.0);
         assert!(store.all_command_histograms().is_empty());
     }
 
+    #[test]
+    fn reset_info_stats_keeps_instantaneous_rates_and_rebases_samples() {
+        let mut store = Store::new();
+        for i in 1..=16 {
+            store.stat_total_commands_processed = i * 100;
+            store.stat_total_net_input_bytes = i * 1024;
+            store.stat_total_net_output_bytes = i * 2048;
+            store.record_ops_sec_sample(1000);
+        }
+        assert_eq!(store.instantaneous_ops_per_sec(), 100);
+        assert!((store.instantaneous_input_kbps() - 1.0).abs() < 0.01);
+        assert!((store.instantaneous_output_kbps() - 2.0).abs() < 0.01);
+
+        store.reset_info_stats();
+        assert_eq!(store.stat_total_commands_processed, 0);
+        assert_eq!(store.stat_total_net_input_bytes, 0);
+        assert_eq!(store.stat_total_net_output_bytes, 0);
+        assert_eq!(store.instantaneous_ops_per_sec(), 100);
+        assert!((store.instantaneous_input_kbps() - 1.0).abs() < 0.01);
+        assert!((store.instantaneous_output_kbps() - 2.0).abs() < 0.01);
+
+        // The next sample counts only post-reset traffic instead of
+        // saturating against the pre-reset baseline.
+        store.stat_total_commands_processed = 100;
+        store.stat_total_net_input_bytes = 1024;
+        store.stat_total_net_output_bytes = 2048;
+        store.record_ops_sec_sample(1000);
+        assert_eq!(store.instantaneous_ops_per_sec(), 100);
+        assert!((store.instantaneous_input_kbps() - 1.0).abs() < 0.01);
+        assert!((store.instantaneous_output_kbps() - 2.0).abs() < 0.01);
+    }
+
     #[test]
     fn function_dump_restore_roundtrip_preserves_library_snapshot() {
         let mut original = Store::new();
@@ -73024,6 +75120,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn restored_intset_above_the_limit_loads_as_hashtable() {
+        let mut source = Store::new();
+        let members: Vec<Vec<u8>> = (0..100).map(|i: u32| i.to_string().into_bytes()).collect();
+        source.sadd(b"s", &members, 0).unwrap();
+        assert_eq!(source.object_encoding(b"s", 0), Some("intset"));
+        let payload = source.dump_key(b"s", 0).unwrap();
+
+        // Within the limit the intset survives RESTORE as is.
+        let mut store = Store::new();
+        store.restore_key(b"s", 0, &payload, false, 0).unwrap();
+        assert_eq!(store.object_encoding(b"s", 0), Some("intset"));
+
+        // Above it, rdbLoadObject converts straight to hashtable even though
+        // 100 members would fit set-max-listpack-entries.
+        let mut store = Store::new();
+        store.set_max_intset_entries = 10;
+        store.restore_key(b"s", 0, &payload, false, 0).unwrap();
+        assert_eq!(store.object_encoding(b"s", 0), Some("hashtable"));
+        assert_eq!(store.scard(b"s", 0), Ok(100));
+        assert_eq!(store.sismember(b"s", b"42", 0), Ok(true));
+    }
+
+    #[test]
+    fn intset_above_lowered_limit_converts_only_on_next_integer_add() {
+        let mut store = Store::new();
+        store.set_max_intset_entries = 128;
+        let members: Vec<Vec<u8>> = (0..100).map(|i: u32| i.to_string().into_bytes()).collect();
+        store.sadd(b"s", &members, 0).unwrap();
+        assert_eq!(store.object_encoding(b"s", 0), Some("intset"));
+
+        store.set_max_intset_entries = 64;
+        store.srem(b"s", &[b"0".as_slice()], 0).unwrap();
+        assert_eq!(
+            store.object_encoding(b"s", 0),
+            Some("intset"),
+            "a removal must not convert an intset left above the limit"
+        );
+        store.sadd(b"s", &[b"1000".to_vec()], 0).unwrap();
+        assert_eq!(store.object_encoding(b"s", 0), Some("hashtable"));
+    }
+
     #[test]
     fn fuzz_keyspace_events_corpus_matches_documented_contract() {
         use crate::{
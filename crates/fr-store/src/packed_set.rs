@@ -610,17 +610,17 @@ impl HashFieldMap {
     /// keeps insertion order and overwrites on a repeat field exactly like the
     /// incremental loop.
     #[must_use]
-    pub fn try_from_flat_pairs_hash_dedup(flat: &[&[u8]]) -> Option<(Self, usize)> {
+    pub fn try_from_flat_pairs_hash_dedup<P: AsRef<[u8]>>(flat: &[P]) -> Option<(Self, usize)> {
         let npairs = flat.len() / 2;
         if npairs <= PACKED_MAX_ENTRIES {
             return None;
         }
-        let bytes: usize = flat.iter().map(|s| s.len() + 5).sum();
+        let bytes: usize = flat.iter().map(|s| s.as_ref().len() + 5).sum();
         let mut h = CompactFieldMap::with_capacity(npairs, bytes);
         let mut added = 0_usize;
         let (pairs, _) = flat.as_chunks::<2>();
         for p in pairs {
-            if h.insert(p[0], p[1]).is_none() {
+            if h.insert(p[0].as_ref(), p[1].as_ref()).is_none() {
                 added += 1;
             }
         }
@@ -634,7 +634,10 @@ impl HashFieldMap {
     /// existing fields keep their current slots, new fields append in first
     /// command occurrence order, and duplicate command fields use the last value.
     #[must_use]
-    pub fn try_update_existing_packed_borrowed(&mut self, flat: &[&[u8]]) -> Option<usize> {
+    pub fn try_update_existing_packed_borrowed<P: AsRef<[u8]>>(
+        &mut self,
+        flat: &[P],
+    ) -> Option<usize> {
         let pair_count = flat.len() / 2;
         let HashFieldMap::Packed(packed) = self else {
             return None;
@@ -645,11 +648,10 @@ impl HashFieldMap {
         if packed
             .iter()
             .any(|(field, value)| field.len() > PACKED_MAX_VALUE || value.len() > PACKED_MAX_VALUE)
-            || flat
-                .as_chunks::<2>()
-                .0
-                .iter()
-                .any(|pair| pair[0].len() > PACKED_MAX_VALUE || pair[1].len() > PACKED_MAX_VALUE)
+            || flat.as_chunks::<2>().0.iter().any(|pair| {
+                pair[0].as_ref().len() > PACKED_MAX_VALUE
+                    || pair[1].as_ref().len() > PACKED_MAX_VALUE
+            })
         {
             return None;
         }
@@ -672,14 +674,15 @@ impl HashFieldMap {
 
         let (pairs, _) = flat.as_chunks::<2>();
         for pair in pairs {
-            if let Some(&idx) = field_to_pending.get(pair[0]) {
-                pending[idx].value = pair[1];
+            let (field, value) = (pair[0].as_ref(), pair[1].as_ref());
+            if let Some(&idx) = field_to_pending.get(field) {
+                pending[idx].value = value;
             } else {
                 let idx = pending.len();
-                field_to_pending.insert(pair[0], idx);
+                field_to_pending.insert(field, idx);
                 pending.push(Pending {
-                    field: pair[0],
-                    value: pair[1],
+                    field,
+                    value,
                     existed: false,
                 });
             }
@@ -1949,18 +1952,30 @@ impl PackedStreamLog {
         &mut self,
         id: (u64, u64),
         pairs: &[(F, V)],
+    ) -> bool {
+        self.insert_fields(id, pairs.iter().map(|(f, v)| (f.as_ref(), v.as_ref())))
+    }
+
+    /// [`Self::insert`] over borrowed `(field, value)` pairs, so XADD can pack
+    /// a flat argv field list straight into the arena without an owned copy.
+    pub fn insert_fields<'a>(
+        &mut self,
+        id: (u64, u64),
+        pairs: impl Iterator<Item = (&'a [u8], &'a [u8])>,
     ) -> bool {
         let off = self.arena.len();
+        let mut count = 0usize;
         for (f, v) in pairs {
-            let idx = self.intern_field(f.as_ref());
+            let idx = self.intern_field(f);
             write_varint(&mut self.arena, idx);
-            write_varint(&mut self.arena, v.as_ref().len());
-            self.arena.extend_from_slice(v.as_ref());
+            write_varint(&mut self.arena, v.len());
+            self.arena.extend_from_slice(v);
+            count += 1;
         }
         let span = FieldSpan {
             off,
             len: u32::try_from(self.arena.len() - off).unwrap_or(u32::MAX),
-            count: u32::try_from(pairs.len()).unwrap_or(u32::MAX),
+            count: u32::try_from(count).unwrap_or(u32::MAX),
         };
 
         // XADD appends IDs strictly above the stream watermark. Keep that active
@@ -0,0 +1,90 @@
+//! `run_active_expire_cycle` reclaims dead keys nobody reads.
+//!
+//! Each pass samples at most `sample_limit` keys from the volatile-key index
+//! (persistent keys are never looked at), removes the ones past their
+//! deadline, and hands back a cursor so the next pass resumes where this one
+//! stopped. Threading the cursor through repeated passes must converge on a
+//! keyspace holding only live keys, with `stat_expired_keys` counting every
+//! reclaimed key exactly once.
+
+use fr_store::Store;
+
+const SAMPLES: usize = 500;
+
+fn key(prefix: &str, idx: usize) -> Vec<u8> {
+    format!("{prefix}:{idx:05}").into_bytes()
+}
+
+/// 10k keys dead by t=100, 1k still alive until t=1_000_000 and 1k
+/// persistent keys.
+fn seeded() -> Store {
+    let mut store = Store::new();
+    for idx in 0..10_000 {
+        store.set(key("dead", idx), b"v".to_vec(), Some(100), 0);
+    }
+    for idx in 0..1_000 {
+        store.set(key("live", idx), b"v".to_vec(), Some(1_000_000), 0);
+        store.set(key("plain", idx), b"v".to_vec(), None, 0);
+    }
+    store
+}
+
+#[test]
+fn nothing_is_sampled_before_the_earliest_deadline() {
+    let mut store = seeded();
+    let cycle = store.run_active_expire_cycle(50, None, SAMPLES);
+    assert_eq!((cycle.sampled_keys, cycle.evicted_keys), (0, 0));
+    assert_eq!(store.dbsize(50), 12_000);
+    assert_eq!(store.count_expiring_keys(), 11_000);
+}
+
+#[test]
+fn repeated_passes_reclaim_progressively_and_converge() {
+    let mut store = seeded();
+    let now = 1_000;
+    let mut cursor = None;
+    let mut dbsize = store.dbsize(now);
+    let mut passes = 0;
+    while dbsize > 2_000 {
+        passes += 1;
+        assert!(passes <= 100, "no convergence after {passes} passes");
+        let cycle = store.run_active_expire_cycle(now, cursor, SAMPLES);
+        assert!(cycle.sampled_keys <= SAMPLES);
+        assert!(cycle.evicted_keys <= cycle.sampled_keys);
+        assert_eq!(cycle.evicted_db_keys.len(), cycle.evicted_keys);
+        let after = store.dbsize(now);
+        assert_eq!(after, dbsize - cycle.evicted_keys);
+        dbsize = after;
+        cursor = cycle.next_cursor;
+    }
+    // A single pass can never do the whole job.
+    assert!(passes >= 10_000 / SAMPLES, "{passes}");
+
+    assert_eq!(store.stat_expired_keys, 10_000);
+    assert_eq!(store.count_expiring_keys(), 1_000);
+    for idx in (0..1_000).step_by(97) {
+        assert!(store.exists(&key("live", idx), now));
+        assert!(store.exists(&key("plain", idx), now));
+    }
+
+    // Only live volatile keys remain and none of them is due yet, so the
+    // next pass short-circuits on the earliest deadline.
+    let cycle = store.run_active_expire_cycle(now, None, 2_000);
+    assert_eq!((cycle.sampled_keys, cycle.evicted_keys), (0, 0));
+}
+
+#[test]
+fn persist_and_rewrites_take_keys_out_of_the_cycle() {
+    let mut store = Store::new();
+    for idx in 0..10 {
+        store.set(key("k", idx), b"v".to_vec(), Some(100), 0);
+    }
+    assert!(store.persist(&key("k", 0), 0));
+    store.set(key("k", 1), b"new".to_vec(), None, 0);
+    assert!(store.expire_seconds(&key("k", 2), 3_600, 0));
+
+    let cycle = store.run_active_expire_cycle(1_000, None, 100);
+    assert_eq!((cycle.sampled_keys, cycle.evicted_keys), (8, 7));
+    assert_eq!(store.dbsize(1_000), 3);
+    assert_eq!(store.count_expiring_keys(), 1);
+}
@@ -0,0 +1,106 @@
+//! BITCOUNT / BITPOS with the `BIT` range unit: start and end index single
+//! bits (MSB first, negative counting back from the last bit), are clamped to
+//! the string, and count or search only the bits inside the range even when
+//! it starts or ends mid-byte.
+
+use fr_store::{BitRangeUnit, Store};
+
+const NOW: u64 = 1_000;
+const VALUE: &[u8] = b"\xff\xf0\x00\x0f\xaa";
+
+fn store() -> Store {
+    let mut store = Store::new();
+    store.set(b"k".to_vec(), VALUE.to_vec(), None, NOW);
+    store
+}
+
+fn bit_at(index: usize) -> bool {
+    VALUE[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+/// The inclusive bit range `start..=end` resolves to, or `None` when it is
+/// empty. Only BITCOUNT treats two negative indexes in the wrong order as
+/// empty before clamping; BITPOS clamps first.
+fn resolve(start: i64, end: i64, bitcount: bool) -> Option<(usize, usize)> {
+    let len = (VALUE.len() * 8) as i64;
+    if bitcount && start < 0 && end < 0 && start > end {
+        return None;
+    }
+    let start = if start < 0 { start + len } else { start }.max(0);
+    let end = if end < 0 { end + len } else { end }.clamp(0, len - 1);
+    (start <= end).then_some((start as usize, end as usize))
+}
+
+fn bitcount_bits(store: &mut Store, start: i64, end: i64) -> usize {
+    store
+        .bitcount(b"k", Some(start), Some(end), BitRangeUnit::Bit, NOW)
+        .expect("bitcount")
+}
+
+#[test]
+fn bitcount_matches_a_bit_by_bit_count_for_every_range() {
+    let mut store = store();
+    for start in -45..45 {
+        for end in -45..45 {
+            let expected = resolve(start, end, true)
+                .map_or(0, |(from, to)| (from..=to).filter(|&i| bit_at(i)).count());
+            assert_eq!(
+                bitcount_bits(&mut store, start, end),
+                expected,
+                "BITCOUNT k {start} {end} BIT"
+            );
+        }
+    }
+}
+
+#[test]
+fn bitcount_edge_ranges() {
+    let mut store = store();
+    // Inside one byte: bits 9..=11 of 0xf0 are all set, 12..=14 all clear.
+    assert_eq!(bitcount_bits(&mut store, 9, 11), 3);
+    assert_eq!(bitcount_bits(&mut store, 12, 14), 0);
+    assert_eq!(bitcount_bits(&mut store, 33, 33), 0);
+    assert_eq!(bitcount_bits(&mut store, 32, 32), 1);
+    // Negative indexes count back from the last bit.
+    assert_eq!(bitcount_bits(&mut store, -8, -1), 4);
+    assert_eq!(bitcount_bits(&mut store, -12, -5), 6);
+    // Past the end clamps; wholly past the end is empty.
+    assert_eq!(bitcount_bits(&mut store, 36, 1_000), 2);
+    assert_eq!(bitcount_bits(&mut store, 40, 1_000), 0);
+    assert_eq!(bitcount_bits(&mut store, -1_000, 1_000), 20);
+    // BYTE keeps indexing whole bytes.
+    assert_eq!(
+        store
+            .bitcount(b"k", Some(1), Some(1), BitRangeUnit::Byte, NOW)
+            .expect("bitcount"),
+        4
+    );
+    assert_eq!(
+        store
+            .bitcount(b"missing", Some(0), Some(-1), BitRangeUnit::Bit, NOW)
+            .expect("bitcount"),
+        0
+    );
+}
+
+#[test]
+fn bitpos_finds_the_first_matching_bit_inside_every_range() {
+    let mut store = store();
+    for bit in [true, false] {
+        for start in -45..45 {
+            for end in -45..45 {
+                let expected = resolve(start, end, false)
+                    .and_then(|(from, to)| (from..=to).find(|&i| bit_at(i) == bit))
+                    .map_or(-1, |i| i as i64);
+                assert_eq!(
+                    store
+                        .bitpos(b"k", bit, Some(start), Some(end), BitRangeUnit::Bit, NOW)
+                        .expect("bitpos"),
+                    expected,
+                    "BITPOS k {} {start} {end} BIT",
+                    u8::from(bit)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Removing the last element of a hash, list, set or sorted set deletes the
+//! key, whatever the encoding; an emptied stream stays behind.
+
+use fr_store::{PttlValue, ScoreBound, Store, StreamGroupReadCursor, StreamGroupReadOptions};
+
+const NOW: u64 = 1_000;
+
+/// One element, and enough to leave the compact encodings.
+const SIZES: [usize; 2] = [1, 300];
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Hash,
+    List,
+    Set,
+    IntSet,
+    Zset,
+}
+
+fn members(kind: Kind, n: usize) -> Vec<Vec<u8>> {
+    (0..n)
+        .map(|i| match kind {
+            Kind::IntSet => i.to_string().into_bytes(),
+            // LREM drops every copy of one value.
+            Kind::List => b"a".to_vec(),
+            _ => format!("m{i}").into_bytes(),
+        })
+        .collect()
+}
+
+fn seed(store: &mut Store, kind: Kind, n: usize) {
+    let members = members(kind, n);
+    let refs: Vec<&[u8]> = members.iter().map(Vec::as_slice).collect();
+    match kind {
+        Kind::Hash => {
+            for field in &members {
+                store
+                    .hset(b"k", field.clone(), b"v".to_vec(), NOW)
+                    .expect("hset");
+            }
+        }
+        Kind::List => {
+            store.rpush(b"k", &refs, NOW).expect("rpush");
+        }
+        Kind::Set | Kind::IntSet => {
+            store.sadd(b"k", &refs, NOW).expect("sadd");
+        }
+        Kind::Zset => {
+            let scored: Vec<(f64, Vec<u8>)> = members
+                .iter()
+                .enumerate()
+                .map(|(i, member)| (i as f64, member.clone()))
+                .collect();
+            store.zadd(b"k", &scored, NOW).expect("zadd");
+        }
+    }
+}
+
+type Drain = fn(&mut Store, &[&[u8]]);
+
+/// (command, kind, removal of every element of `k`)
+const REMOVALS: &[(&str, Kind, Drain)] = &[
+    ("HDEL", Kind::Hash, |s, all| {
+        s.hdel(b"k", all, NOW).expect("hdel");
+    }),
+    ("LPOP", Kind::List, |s, _| {
+        while s.lpop(b"k", NOW).expect("lpop").is_some() {}
+    }),
+    ("RPOP", Kind::List, |s, _| {
+        while s.rpop(b"k", NOW).expect("rpop").is_some() {}
+    }),
+    ("LPOP count", Kind::List, |s, all| {
+        s.lpop_count(b"k", all.len(), NOW).expect("lpop");
+    }),
+    ("RPOP count", Kind::List, |s, all| {
+        s.rpop_count(b"k", all.len(), NOW).expect("rpop");
+    }),
+    ("LREM", Kind::List, |s, _| {
+        s.lrem(b"k", 0, b"a", NOW).expect("lrem");
+    }),
+    ("LTRIM", Kind::List, |s, _| {
+        s.ltrim(b"k", 1, 0, NOW).expect("ltrim");
+    }),
+    ("LMOVE source", Kind::List, |s, _| {
+        while s
+            .lmove(b"k", b"dst", b"LEFT", b"RIGHT", NOW)
+            .expect("lmove")
+            .is_some()
+        {}
+    }),
+    ("RPOPLPUSH source", Kind::List, |s, _| {
+        while s.rpoplpush(b"k", b"dst", NOW).expect("rpoplpush").is_some() {}
+    }),
+    ("SREM", Kind::Set, |s, all| {
+        s.srem(b"k", all, NOW).expect("srem");
+    }),
+    ("SREM intset", Kind::IntSet, |s, all| {
+        s.srem(b"k", all, NOW).expect("srem");
+    }),
+    ("SPOP", Kind::Set, |s, _| {
+        while s.spop(b"k", NOW).expect("spop").is_some() {}
+    }),
+    ("SPOP count", Kind::IntSet, |s, all| {
+        s.spop_count(b"k", all.len(), NOW).expect("spop");
+    }),
+    ("SMOVE source", Kind::Set, |s, all| {
+        for member in all {
+            s.smove(b"k", b"dst", member, NOW).expect("smove");
+        }
+    }),
+    ("ZREM", Kind::Zset, |s, all| {
+        s.zrem(b"k", all, NOW).expect("zrem");
+    }),
+    ("ZPOPMIN", Kind::Zset, |s, _| {
+        while s.zpopmin(b"k", NOW).expect("zpopmin").is_some() {}
+    }),
+    ("ZPOPMAX", Kind::Zset, |s, _| {
+        while s.zpopmax(b"k", NOW).expect("zpopmax").is_some() {}
+    }),
+    ("ZPOPMIN count", Kind::Zset, |s, all| {
+        s.zpopmin_count(b"k", all.len(), NOW).expect("zpopmin");
+    }),
+    ("ZPOPMAX count", Kind::Zset, |s, all| {
+        s.zpopmax_count(b"k", all.len(), NOW).expect("zpopmax");
+    }),
+    ("ZREMRANGEBYRANK", Kind::Zset, |s, _| {
+        s.zremrangebyrank(b"k", 0, -1, NOW)
+            .expect("zremrangebyrank");
+    }),
+    ("ZREMRANGEBYSCORE", Kind::Zset, |s, _| {
+        s.zremrangebyscore(
+            b"k",
+            ScoreBound::Inclusive(f64::NEG_INFINITY),
+            ScoreBound::Inclusive(f64::INFINITY),
+            NOW,
+        )
+        .expect("zremrangebyscore");
+    }),
+    ("ZREMRANGEBYLEX", Kind::Zset, |s, _| {
+        s.zremrangebylex(b"k", b"-", b"+", NOW)
+            .expect("zremrangebylex");
+    }),
+];
+
+#[test]
+fn removing_the_last_element_deletes_the_key() {
+    for (command, kind, drain) in REMOVALS {
+        for n in SIZES {
+            let mut store = Store::new();
+            seed(&mut store, *kind, n);
+            store.expire_milliseconds(b"k", 60_000, NOW);
+            let all = members(*kind, n);
+            let all: Vec<&[u8]> = all.iter().map(Vec::as_slice).collect();
+            drain(&mut store, &all);
+
+            let context = format!("{command} of {n} ({kind:?})");
+            assert!(!store.exists(b"k", NOW), "{context} left an empty key");
+            assert_eq!(store.key_type(b"k", NOW), None, "{context}");
+            // Only the destination of a move is left.
+            let moved = command.ends_with("source");
+            assert_eq!(store.dbsize(NOW), usize::from(moved), "{context}");
+
+            // The key comes back fresh: no TTL carried over from the old one.
+            seed(&mut store, *kind, 1);
+            assert_eq!(store.pttl(b"k", NOW), PttlValue::NoExpiry);
+        }
+    }
+}
+
+#[test]
+fn an_emptied_stream_keeps_its_key() {
+    let fields = [(b"f".to_vec(), b"v".to_vec())];
+    let mut store = Store::new();
+    store.xadd(b"s", (1, 0), &fields, NOW).expect("xadd");
+    store.xadd(b"s", (2, 0), &fields, NOW).expect("xadd");
+    assert!(
+        store
+            .xgroup_create(b"s", b"g", (0, 0), false, NOW)
+            .expect("group")
+    );
+
+    assert_eq!(store.xdel(b"s", &[(1, 0), (2, 0)], NOW), Ok(2));
+    assert!(store.exists(b"s", NOW));
+    assert_eq!(store.key_type(b"s", NOW), Some("stream"));
+    assert_eq!(store.xtrim(b"s", 0, None, NOW), Ok(0));
+    assert!(store.exists(b"s", NOW));
+    assert!(store.stream_consumer_groups(b"s").is_some());
+
+    // XACK of the last pending entry leaves the stream and its group alone.
+    store.xadd(b"s", (3, 0), &fields, NOW).expect("xadd");
+    let read = StreamGroupReadOptions {
+        cursor: StreamGroupReadCursor::NewEntries,
+        noack: false,
+        count: None,
+    };
+    store
+        .xreadgroup(b"s", b"g", b"c", read, NOW)
+        .expect("xreadgroup");
+    assert_eq!(store.xdel(b"s", &[(3, 0)], NOW), Ok(1));
+    assert_eq!(store.xack(b"s", b"g", &[(3, 0)], NOW), Ok(1));
+    assert!(store.exists(b"s", NOW));
+    assert!(store.stream_consumer_groups(b"s").is_some());
+}
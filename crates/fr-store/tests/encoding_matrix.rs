@@ -388,3 +388,96 @@ fn mr_enc_mono_zset_does_not_downgrade_on_insert_sweep() {
     }
     assert!(flipped, "zset encoding never transitioned to skiplist");
 }
+
+// ── Node layout (debug_value_info) ──────────────────────────────────
+
+#[test]
+fn value_info_tracks_set_transitions_and_lengths() {
+    let mut store = Store::new();
+    let ints: Vec<Vec<u8>> = (1..=10_i64).map(|n| n.to_string().into_bytes()).collect();
+    store.sadd(b"s", &ints, NOW).expect("sadd");
+    let info = store.debug_value_info(b"s", NOW).expect("set info");
+    assert_eq!(
+        (info.value_type, info.encoding, info.length),
+        ("set", "intset", 10)
+    );
+    assert!(info.nodes.is_empty());
+
+    store.sadd(b"s", &[b"alpha".to_vec()], NOW).expect("sadd");
+    let info = store.debug_value_info(b"s", NOW).expect("set info");
+    assert_eq!((info.encoding, info.length), ("listpack", 11));
+
+    let extra: Vec<Vec<u8>> = (0..128_u32).map(|i| format!("m{i}").into_bytes()).collect();
+    store.sadd(b"s", &extra, NOW).expect("sadd");
+    let info = store.debug_value_info(b"s", NOW).expect("set info");
+    assert_eq!((info.encoding, info.length), ("hashtable", 139));
+    assert!(info.nodes.is_empty());
+}
+
+#[test]
+fn value_info_matches_object_encoding_for_each_type() {
+    let mut store = Store::new();
+    store.set(b"int".to_vec(), b"12345".to_vec(), None, NOW);
+    store.set(b"raw".to_vec(), vec![b'x'; 45], None, NOW);
+    store
+        .hset(b"h", b"f".to_vec(), vec![b'v'; 65], NOW)
+        .expect("hset");
+    let members: Vec<(f64, Vec<u8>)> = (0..129_u32)
+        .map(|i| (i as f64, format!("m{i}").into_bytes()))
+        .collect();
+    store.zadd(b"z", &members, NOW).expect("zadd");
+
+    for (key, value_type, encoding, length) in [
+        (&b"int"[..], "string", "int", 5),
+        (b"raw", "string", "raw", 45),
+        (b"h", "hash", "hashtable", 1),
+        (b"z", "zset", "skiplist", 129),
+    ] {
+        let info = store.debug_value_info(key, NOW).expect("value info");
+        assert_eq!(
+            (info.value_type, info.encoding, info.length),
+            (value_type, encoding, length),
+            "{:?}",
+            String::from_utf8_lossy(key)
+        );
+        assert_eq!(store.object_encoding(key, NOW), Some(encoding));
+        assert!(info.serialized_bytes > 0);
+        assert!(info.memory_bytes > 0);
+    }
+    assert_eq!(store.debug_value_info(b"absent", NOW), None);
+}
+
+#[test]
+fn value_info_reports_quicklist_node_fill() {
+    let mut store = Store::new();
+    let small: Vec<Vec<u8>> = (0..128_u32).map(|i| format!("v{i}").into_bytes()).collect();
+    store.rpush(b"l", &small, NOW).expect("rpush");
+    let info = store.debug_value_info(b"l", NOW).expect("list info");
+    assert_eq!((info.encoding, info.length), ("listpack", 128));
+    assert!(info.nodes.is_empty());
+
+    // 200 × 100-byte elements split across 8 KiB nodes: every node but the
+    // last is within budget, and the entries sum to the list length.
+    let mut store = Store::new();
+    store
+        .rpush(b"l", &vec![vec![b'x'; 100]; 200], NOW)
+        .expect("rpush");
+    let info = store.debug_value_info(b"l", NOW).expect("list info");
+    assert_eq!((info.encoding, info.length), ("quicklist", 200));
+    assert_eq!(info.nodes.len(), 3);
+    assert_eq!(info.nodes.iter().map(|n| n.entries).sum::<usize>(), 200);
+    assert_eq!(info.nodes.iter().map(|n| n.bytes).sum::<usize>(), 20_621);
+    for node in &info.nodes {
+        assert!(node.entries > 0 && node.bytes <= 8 * 1024, "{node:?}");
+    }
+
+    // An element larger than the node budget gets a node of its own.
+    store
+        .rpush(b"l", &[vec![b'y'; 9_000]], NOW)
+        .expect("rpush oversized");
+    let info = store.debug_value_info(b"l", NOW).expect("list info");
+    assert_eq!(info.nodes.len(), 4);
+    let last = info.nodes.last().expect("oversized node");
+    assert_eq!(last.entries, 1);
+    assert!(last.bytes > 9_000, "{last:?}");
+}
@@ -0,0 +1,200 @@
+//! Flush generation: every key-dropping flush bumps it for the databases it
+//! emptied, SCAN-family cursors carry their database's, and a cursor minted
+//! before a flush of its database ends the iteration afterwards.
+
+use fr_store::{SscanReplyEvent, Store, ZscanReplyEvent, encode_db_key};
+
+const NOW: u64 = 1_000;
+const MEMBERS: usize = 300;
+
+fn member(prefix: &str, i: usize) -> Vec<u8> {
+    format!("{prefix}:{i}").into_bytes()
+}
+
+fn fill(store: &mut Store, prefix: &str) {
+    for i in 0..MEMBERS {
+        store.set(member(prefix, i), b"v".to_vec(), None, NOW);
+        // A value past hash-max-listpack-value keeps the hash paged: a
+        // listpack hash answers every HSCAN with the whole hash and cursor 0.
+        store
+            .hset(b"hash", member(prefix, i), vec![b'v'; 100], NOW)
+            .expect("hset");
+        store.sadd(b"set", &[member(prefix, i)], NOW).expect("sadd");
+        store
+            .zadd(b"zset", &[(i as f64, member(prefix, i))], NOW)
+            .expect("zadd");
+    }
+}
+
+#[test]
+fn only_key_dropping_flushes_bump_the_generation() {
+    let mut store = Store::new();
+    assert_eq!(store.flush_generation(0), 0);
+    store.flushdb();
+    assert_eq!(store.flush_database(2), 0);
+    assert_eq!(store.flush_generation(0), 0);
+    assert_eq!(store.flush_generation(2), 0);
+
+    store.set(b"k".to_vec(), b"v".to_vec(), None, NOW);
+    store.flushdb();
+    assert_eq!(store.flush_generation(0), 1);
+    assert_eq!(store.flush_generation(2), 0);
+
+    store.set(encode_db_key(2, b"k"), b"v".to_vec(), None, NOW);
+    assert_eq!(store.flush_database(2), 1);
+    assert_eq!(store.flush_generation(0), 1);
+    assert_eq!(store.flush_generation(2), 1);
+}
+
+#[test]
+fn a_flush_of_another_database_keeps_cursors_valid() {
+    let mut store = Store::new();
+    fill(&mut store, "old");
+    store.set(encode_db_key(2, b"k"), b"v".to_vec(), None, NOW);
+
+    let (scan, first) = store.scan_in_db(0, 0, None, None, 10, NOW);
+    let (sscan, _) = store.sscan(b"set", 0, None, 10, NOW).expect("sscan");
+    assert_eq!(store.flush_database(2), 1);
+
+    let (_, rest) = store.scan_in_db(0, scan, None, None, 10, NOW);
+    assert!(!rest.is_empty());
+    assert!(rest.iter().all(|key| !first.contains(key)));
+    let (_, members) = store.sscan(b"set", sscan, None, 10, NOW).expect("sscan");
+    assert!(!members.is_empty());
+
+    // Flushing the cursor's own database still ends the walk.
+    store.flushdb();
+    fill(&mut store, "new");
+    assert_eq!(
+        store.scan_in_db(0, scan, None, None, 10, NOW),
+        (0, Vec::new())
+    );
+}
+
+#[test]
+fn cursors_round_trip_until_the_next_flush() {
+    let mut store = Store::new();
+    assert_eq!(store.mint_scan_cursor(0, 0), 0);
+    // No flush yet: cursors are plain positions.
+    assert_eq!(store.mint_scan_cursor(0, 42), 42);
+    assert_eq!(store.resolve_scan_cursor(0, 42), Some(42));
+    assert_eq!(store.resolve_scan_cursor(0, 0), Some(0));
+    assert_eq!(store.resolve_scan_cursor(0, u64::MAX), None);
+
+    store.set(b"k".to_vec(), b"v".to_vec(), None, NOW);
+    store.flushdb();
+    let cursor = store.mint_scan_cursor(0, 42);
+    assert_ne!(cursor, 42);
+    assert_eq!(store.resolve_scan_cursor(0, cursor), Some(42));
+    assert_eq!(store.resolve_scan_cursor(0, 42), None);
+    // Cursor 0 always starts over.
+    assert_eq!(store.resolve_scan_cursor(0, 0), Some(0));
+}
+
+#[test]
+fn stale_cursors_yield_the_final_empty_page() {
+    let mut store = Store::new();
+    fill(&mut store, "old");
+
+    let (scan, _) = store.scan(0, None, 10, NOW);
+    let (db_scan, _) = store.scan_in_db(0, 0, None, None, 10, NOW);
+    let (hscan, _) = store.hscan(b"hash", 0, None, 10, NOW).expect("hscan");
+    let (sscan, _) = store.sscan(b"set", 0, None, 10, NOW).expect("sscan");
+    let (zscan, _) = store.zscan(b"zset", 0, None, 10, NOW).expect("zscan");
+    for cursor in [scan, db_scan, hscan, sscan, zscan] {
+        assert_ne!(cursor, 0);
+    }
+
+    store.flushdb();
+    fill(&mut store, "new");
+
+    assert_eq!(store.scan(scan, None, 10, NOW), (0, Vec::new()));
+    assert_eq!(
+        store.scan_in_db(0, db_scan, None, None, 10, NOW),
+        (0, Vec::new())
+    );
+    assert_eq!(
+        store.hscan(b"hash", hscan, None, 10, NOW),
+        Ok((0, Vec::new()))
+    );
+    assert_eq!(
+        store.sscan(b"set", sscan, None, 10, NOW),
+        Ok((0, Vec::new()))
+    );
+    assert_eq!(
+        store.zscan(b"zset", zscan, None, 10, NOW),
+        Ok((0, Vec::new()))
+    );
+
+    // The borrowed reply paths agree.
+    let mut events = Vec::new();
+    store
+        .sscan0_borrow_scan(b"set", sscan, None, 10, NOW, |ev| {
+            events.push(match ev {
+                SscanReplyEvent::Cursor(c) => format!("cursor {c}"),
+                SscanReplyEvent::Len(n) => format!("len {n}"),
+                SscanReplyEvent::Member(_) => "member".to_string(),
+            });
+        })
+        .expect("sscan0");
+    store
+        .hscan0_borrow_scan(b"hash", hscan, None, 10, NOW, |ev| {
+            events.push(match ev {
+                SscanReplyEvent::Cursor(c) => format!("cursor {c}"),
+                SscanReplyEvent::Len(n) => format!("len {n}"),
+                SscanReplyEvent::Member(_) => "member".to_string(),
+            });
+        })
+        .expect("hscan0");
+    store
+        .zscan0_borrow_scan(b"zset", zscan, None, 10, NOW, |ev| {
+            events.push(match ev {
+                ZscanReplyEvent::Cursor(c) => format!("cursor {c}"),
+                ZscanReplyEvent::Len(n) => format!("len {n}"),
+                ZscanReplyEvent::Pair(..) => "pair".to_string(),
+            });
+        })
+        .expect("zscan0");
+    assert_eq!(events, ["cursor 0", "len 0"].repeat(3));
+}
+
+#[test]
+fn fresh_cursors_walk_every_member_after_a_flush() {
+    let mut store = Store::new();
+    fill(&mut store, "old");
+    store.flushdb();
+    fill(&mut store, "new");
+
+    let mut seen = Vec::new();
+    let mut cursor = 0;
+    loop {
+        let mut next = 0;
+        store
+            .sscan0_borrow_scan(b"set", cursor, None, 10, NOW, |ev| match ev {
+                SscanReplyEvent::Cursor(c) => next = c,
+                SscanReplyEvent::Len(_) => {}
+                SscanReplyEvent::Member(m) => seen.push(m.to_vec()),
+            })
+            .expect("sscan0");
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    seen.sort();
+    let mut expected: Vec<Vec<u8>> = (0..MEMBERS).map(|i| member("new", i)).collect();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    let mut keys = Vec::new();
+    let mut cursor = 0;
+    loop {
+        let (next, batch) = store.scan_in_db(0, cursor, Some(b"new:*"), None, 25, NOW);
+        keys.extend(batch);
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    assert_eq!(keys.len(), MEMBERS);
+}
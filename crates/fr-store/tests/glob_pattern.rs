@@ -0,0 +1,95 @@
+//! `fr_store::glob::Pattern` is the public face of the server's matcher: it must
+//! agree with `glob_match` on every input, and its literal accessors must be
+//! sound for callers that skip the matcher (exact lookup, prefix range scan).
+
+use fr_store::glob::Pattern;
+use fr_store::glob_match;
+use proptest::prelude::*;
+
+fn compile(pattern: &[u8]) -> Pattern {
+    Pattern::compile(pattern).expect("every pattern compiles")
+}
+
+#[test]
+fn documented_syntax() {
+    let cases: &[(&[u8], &[u8], bool)] = &[
+        (b"h?llo", b"hello", true),
+        (b"h?llo", b"hllo", false),
+        (b"h*llo", b"hllo", true),
+        (b"h[ae]llo", b"hallo", true),
+        (b"h[^e]llo", b"hello", false),
+        (b"h[a-b]llo", b"hbllo", true),
+        (b"[z-a]", b"m", true),
+        (b"[!a]", b"!", true),
+        (b"[!a]", b"b", false),
+        (b"[abc", b"c", true),
+        (b"[a-]", b"^", true),
+        (b"[a-]", b"-", false),
+        (b"\\*x", b"*x", true),
+        (b"\\*x", b"ax", false),
+        (b"a\\", b"a\\", true),
+        (b"*", b"", false),
+        (b"", b"", true),
+        (b"HELLO", b"hello", false),
+    ];
+    for &(pattern, key, expected) in cases {
+        assert_eq!(
+            compile(pattern).matches(key),
+            expected,
+            "{:?} vs {:?}",
+            String::from_utf8_lossy(pattern),
+            String::from_utf8_lossy(key)
+        );
+    }
+}
+
+#[test]
+fn literal_accessors() {
+    let exact = compile(b"config");
+    assert!(exact.is_literal());
+    assert_eq!(exact.literal_prefix(), b"config");
+    assert_eq!(exact.as_bytes(), b"config");
+
+    let prefix = compile(b"user:*");
+    assert!(!prefix.is_literal());
+    assert_eq!(prefix.literal_prefix(), b"user:");
+
+    let escaped = compile(b"a\\*b");
+    assert!(!escaped.is_literal());
+    assert_eq!(escaped.literal_prefix(), b"a");
+
+    assert_eq!(compile(b"*suffix").literal_prefix(), b"");
+    assert_eq!(compile(b"[ab]c").literal_prefix(), b"");
+    assert!(compile(b"").is_literal());
+}
+
+fn glob_bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(
+        prop::sample::select(vec![
+            b'a', b'b', b'c', b'*', b'?', b'[', b']', b'^', b'-', b'\\', b'!',
+        ]),
+        0..12,
+    )
+}
+
+fn key_bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(
+        prop::sample::select(vec![b'a', b'b', b'c', b'*', b'[', b']', b'-', b'\\']),
+        0..12,
+    )
+}
+
+proptest! {
+    #[test]
+    fn pattern_agrees_with_glob_match(pattern in glob_bytes(), key in key_bytes()) {
+        let compiled = compile(&pattern);
+        let matched = compiled.matches(&key);
+        prop_assert_eq!(matched, glob_match(&pattern, &key));
+        if matched {
+            prop_assert!(key.starts_with(compiled.literal_prefix()));
+        }
+        if compiled.is_literal() {
+            prop_assert_eq!(matched, key == pattern);
+        }
+    }
+}
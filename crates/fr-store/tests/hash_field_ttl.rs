@@ -528,6 +528,24 @@ fn reaping_last_field_deletes_the_whole_hash_key() {
     assert_eq!(ty, None);
 }
 
+#[test]
+fn explicit_reap_drops_only_past_deadline_fields() {
+    let mut store = Store::new();
+    seed_hash(&mut store, b"h", &[(b"gone", b"v"), (b"kept", b"v")]);
+    store.hash_field_set_abs_expiry(b"h", b"gone", NOW, HashFieldTtlCondition::None, NOW);
+    store.hash_field_set_abs_expiry(b"h", b"kept", NOW + 1, HashFieldTtlCondition::None, NOW);
+    assert_eq!(store.hash_field_reap_expired(b"h", NOW), 1);
+    assert_eq!(store.hash_field_reap_expired(b"h", NOW), 0);
+    assert_eq!(
+        store.hash_field_ttl(b"h", b"gone", NOW, HashFieldTtlUnit::Milliseconds, false),
+        HashFieldTtl::FieldMissing
+    );
+
+    assert_eq!(store.hash_field_reap_expired(b"h", NOW + 1), 1);
+    assert_eq!(store.key_type(b"h", NOW + 1), None);
+    assert_eq!(store.hash_field_reap_expired(b"missing", NOW), 0);
+}
+
 #[test]
 fn hdel_clears_the_per_field_ttl_entry() {
     let mut store = Store::new();
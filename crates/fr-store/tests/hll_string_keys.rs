@@ -0,0 +1,95 @@
+//! A HyperLogLog is a string. Once PFADD has created it, the key shows up in
+//! KEYS/SCAN as a `string`, string commands read and rewrite its bytes, and
+//! the generic keyspace commands move it around like any other value.
+
+use fr_store::{Store, StoreError};
+
+const NOW: u64 = 1_000;
+
+fn hll(store: &mut Store, key: &[u8]) -> Vec<u8> {
+    let elements = [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+    assert_eq!(store.pfadd(key, &elements, NOW), Ok(true));
+    store.get(key, NOW).expect("get").expect("hll bytes")
+}
+
+#[test]
+fn a_pfadd_key_is_a_raw_string() {
+    let mut store = Store::new();
+    let bytes = hll(&mut store, b"h");
+
+    assert!(bytes.starts_with(b"HYLL"));
+    assert_eq!(store.key_type(b"h", NOW), Some("string"));
+    // createHLLObject never embeds, however short the sparse payload.
+    assert_eq!(store.object_encoding(b"h", NOW), Some("raw"));
+    assert_eq!(store.strlen(b"h", NOW), Ok(bytes.len()));
+    assert_eq!(store.getrange(b"h", 0, 3, NOW), Ok(b"HYLL".to_vec()));
+
+    assert!(store.exists(b"h", NOW));
+    assert_eq!(store.keys_matching(b"*", NOW), vec![b"h".to_vec()]);
+    assert_eq!(store.scan(0, Some(b"h"), 10, NOW), (0, vec![b"h".to_vec()]));
+}
+
+#[test]
+fn getdel_hands_back_a_portable_hll() {
+    let mut store = Store::new();
+    let bytes = hll(&mut store, b"h");
+
+    assert_eq!(store.getdel(b"h", NOW), Ok(Some(bytes.clone())));
+    assert!(!store.exists(b"h", NOW));
+    assert_eq!(store.key_type(b"h", NOW), None);
+    assert_eq!(store.pfcount(&[b"h"], NOW), Ok(0));
+    assert_eq!(store.getdel(b"h", NOW), Ok(None));
+
+    // The bytes are the whole HLL: SET them anywhere and PFCOUNT agrees.
+    store.set(b"h".to_vec(), bytes.clone(), None, NOW);
+    assert_eq!(store.pfcount(&[b"h"], NOW), Ok(3));
+    store.set(b"elsewhere".to_vec(), bytes, None, NOW);
+    assert_eq!(store.pfcount(&[b"elsewhere"], NOW), Ok(3));
+}
+
+#[test]
+fn keyspace_commands_carry_the_hll_along() {
+    let mut store = Store::new();
+    let bytes = hll(&mut store, b"h");
+
+    assert_eq!(store.copy(b"h", b"copied", false, NOW), Ok(true));
+    store.rename(b"copied", b"renamed", NOW).expect("rename");
+    let payload = store.dump_key(b"renamed", NOW).expect("dump");
+    store
+        .restore_key(b"restored", 0, &payload, false, NOW)
+        .expect("restore");
+
+    for key in [&b"renamed"[..], b"restored"] {
+        assert_eq!(store.get(key, NOW), Ok(Some(bytes.clone())));
+        assert_eq!(store.key_type(key, NOW), Some("string"));
+        assert_eq!(store.pfcount(&[key], NOW), Ok(3));
+    }
+    assert!(!store.exists(b"copied", NOW));
+}
+
+#[test]
+fn string_writes_replace_the_hll() {
+    let mut store = Store::new();
+    let bytes = hll(&mut store, b"h");
+
+    // Still a string afterwards, just no longer an HLL.
+    assert_eq!(store.setrange(b"h", 0, b"X", NOW), Ok(bytes.len()));
+    assert_eq!(store.key_type(b"h", NOW), Some("string"));
+    assert_eq!(
+        store.pfcount(&[b"h"], NOW),
+        Err(StoreError::InvalidHllValue)
+    );
+
+    hll(&mut store, b"appended");
+    let len = store.strlen(b"appended", NOW).expect("strlen");
+    assert_eq!(store.append(b"appended", b"!", NOW), Ok(len + 1));
+    assert_eq!(store.key_type(b"appended", NOW), Some("string"));
+
+    hll(&mut store, b"overwritten");
+    store.set(b"overwritten".to_vec(), b"plain".to_vec(), None, NOW);
+    assert_eq!(store.get(b"overwritten", NOW), Ok(Some(b"plain".to_vec())));
+    assert_eq!(
+        store.pfadd(b"overwritten", &[b"a".to_vec()], NOW),
+        Err(StoreError::InvalidHllValue)
+    );
+}
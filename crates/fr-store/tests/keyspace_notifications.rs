@@ -0,0 +1,149 @@
+//! Keyspace notifications queued by the store itself.
+//!
+//! The command layer fires most events, but expiry (lazy and active),
+//! eviction, EXPIRE with a non-positive TTL and SMOVE are decided inside the
+//! store. Each test enables a `notify-keyspace-events` class string and
+//! checks the exact `(channel, payload)` pairs left for the pub/sub fan-out.
+
+use fr_store::{
+    EvictionSafetyGateState, MaxmemoryPolicy, Store, keyspace_events_parse,
+    keyspace_events_to_string,
+};
+
+type Notifications = Vec<(Vec<u8>, Vec<u8>)>;
+
+fn store_with(classes: &str) -> Store {
+    let mut store = Store::new();
+    store.notify_keyspace_events = keyspace_events_parse(classes).expect("valid classes");
+    store
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Notifications {
+    expected
+        .iter()
+        .map(|(channel, payload)| (channel.as_bytes().to_vec(), payload.as_bytes().to_vec()))
+        .collect()
+}
+
+#[test]
+fn class_strings_parse_and_print_canonically() {
+    assert_eq!(keyspace_events_parse(""), Some(0));
+    assert_eq!(
+        keyspace_events_parse("KEA")
+            .map(keyspace_events_to_string)
+            .as_deref(),
+        Some("AKE")
+    );
+    assert_eq!(
+        keyspace_events_parse("Ex$")
+            .map(keyspace_events_to_string)
+            .as_deref(),
+        Some("$xE")
+    );
+    // Classes without K or E are kept, they just never fire.
+    assert_eq!(
+        keyspace_events_parse("g")
+            .map(keyspace_events_to_string)
+            .as_deref(),
+        Some("g")
+    );
+    assert_eq!(keyspace_events_parse("KEz?"), None);
+
+    let mut store = store_with("g");
+    store.set(b"k".to_vec(), b"v".to_vec(), None, 0);
+    assert!(store.expire_seconds(b"k", 10, 0));
+    assert!(store.drain_keyspace_notifications().is_empty());
+}
+
+#[test]
+fn lazy_expiry_fires_expired_on_both_channels() {
+    let mut store = store_with("KEx");
+    store.set(b"session".to_vec(), b"v".to_vec(), Some(5), 100);
+    assert!(store.drain_keyspace_notifications().is_empty());
+
+    assert_eq!(store.get(b"session", 106).unwrap(), None);
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[
+            ("__keyspace@0__:session", "expired"),
+            ("__keyevent@0__:expired", "session"),
+        ])
+    );
+    // Discovered once; a second miss is silent.
+    assert_eq!(store.get(b"session", 107).unwrap(), None);
+    assert!(store.drain_keyspace_notifications().is_empty());
+}
+
+#[test]
+fn only_the_enabled_channel_kind_and_class_fire() {
+    let mut store = store_with("Kx");
+    store.set(b"a".to_vec(), b"v".to_vec(), Some(5), 0);
+    assert!(!store.exists(b"a", 10));
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[("__keyspace@0__:a", "expired")])
+    );
+
+    // Generic events are off, so EXPIRE itself is silent here.
+    store.set(b"b".to_vec(), b"v".to_vec(), None, 0);
+    assert!(store.expire_seconds(b"b", 10, 0));
+    assert!(store.drain_keyspace_notifications().is_empty());
+}
+
+#[test]
+fn expire_fires_expire_or_del_for_a_non_positive_ttl() {
+    let mut store = store_with("Eg");
+    store.set(b"k".to_vec(), b"v".to_vec(), None, 0);
+    assert!(store.expire_seconds(b"k", 100, 0));
+    assert!(store.expire_seconds(b"k", 0, 0));
+    assert!(!store.exists(b"k", 0));
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[("__keyevent@0__:expire", "k"), ("__keyevent@0__:del", "k")])
+    );
+}
+
+#[test]
+fn active_expiry_and_eviction_fire_their_own_events() {
+    let mut store = store_with("Exe");
+    store.set(b"short".to_vec(), b"v".to_vec(), Some(5), 0);
+    let cycle = store.run_active_expire_cycle(10, None, 16);
+    assert_eq!(cycle.evicted_keys, 1);
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[("__keyevent@0__:expired", "short")])
+    );
+
+    store.maxmemory_policy = MaxmemoryPolicy::AllkeysLru;
+    store.set(b"old".to_vec(), vec![b'v'; 64], None, 10);
+    store.set(b"new".to_vec(), vec![b'v'; 64], None, 20);
+    let budget = store.estimate_memory_usage_bytes() - 1;
+    let result =
+        store.run_bounded_eviction_loop(30, budget, 0, 16, 16, EvictionSafetyGateState::default());
+    assert_eq!(result.evicted_keys, 1);
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[("__keyevent@0__:evicted", "old")])
+    );
+}
+
+#[test]
+fn smove_fires_srem_del_and_sadd_in_order() {
+    let mut store = store_with("KEA");
+    store.sadd(b"src", &[b"m".as_slice()], 0).unwrap();
+    store.sadd(b"dst", &[b"other".as_slice()], 0).unwrap();
+    assert!(store.drain_keyspace_notifications().is_empty());
+
+    assert!(store.smove(b"src", b"dst", b"m", 0).unwrap());
+    assert_eq!(
+        store.drain_keyspace_notifications(),
+        pairs(&[
+            ("__keyspace@0__:src", "srem"),
+            ("__keyevent@0__:srem", "src"),
+            ("__keyspace@0__:src", "del"),
+            ("__keyevent@0__:del", "src"),
+            ("__keyspace@0__:dst", "sadd"),
+            ("__keyevent@0__:sadd", "dst"),
+        ])
+    );
+}
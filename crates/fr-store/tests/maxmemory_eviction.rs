@@ -0,0 +1,137 @@
+//! `run_bounded_eviction_loop` under each `MaxmemoryPolicy`.
+//!
+//! The store is filled under no limit, then the loop is asked to fit a
+//! smaller budget. With a sample pool at least as large as the keyspace the
+//! sampled LRU / TTL choice is exact, so the evicted keys are exactly the
+//! oldest (or soonest-expiring) ones; the `volatile-*` policies never touch
+//! a key without an expiry, and `noeviction` frees nothing.
+
+use fr_store::{
+    EvictionLoopFailure, EvictionLoopResult, EvictionLoopStatus, EvictionSafetyGateState,
+    MaxmemoryPolicy, Store,
+};
+
+const NOW: u64 = 1_000_000;
+const SAMPLES: usize = 64;
+const CYCLES: usize = 64;
+
+fn key(idx: usize) -> Vec<u8> {
+    format!("key:{idx}").into_bytes()
+}
+
+fn evict_to(store: &mut Store, maxmemory_bytes: usize) -> EvictionLoopResult {
+    store.run_bounded_eviction_loop(
+        NOW,
+        maxmemory_bytes,
+        0,
+        SAMPLES,
+        CYCLES,
+        EvictionSafetyGateState::default(),
+    )
+}
+
+/// Ten 100-byte strings, `key:i` last written at `NOW + i` seconds.
+fn ten_strings(policy: MaxmemoryPolicy) -> Store {
+    let mut store = Store::new();
+    store.maxmemory_policy = policy;
+    for idx in 0..10 {
+        store.set(key(idx), vec![b'v'; 100], None, NOW + idx as u64 * 1_000);
+    }
+    store
+}
+
+fn survivors(store: &mut Store) -> Vec<usize> {
+    (0..10)
+        .filter(|&idx| store.exists(&key(idx), NOW + 60_000))
+        .collect()
+}
+
+#[test]
+fn allkeys_lru_evicts_the_least_recently_used_keys() {
+    let mut store = ten_strings(MaxmemoryPolicy::AllkeysLru);
+    // Reading key:0 makes it the most recently used.
+    store.get(&key(0), NOW + 20_000).unwrap();
+
+    let full = store.estimate_memory_usage_bytes();
+    let result = evict_to(&mut store, full * 6 / 10);
+    assert_eq!(result.status, EvictionLoopStatus::Ok);
+    assert_eq!(result.bytes_to_free_after, 0);
+    assert!(result.evicted_keys >= 4, "{result:?}");
+    assert_eq!(store.stat_evicted_keys, result.evicted_keys as u64);
+    assert!(store.estimate_memory_usage_bytes() <= full * 6 / 10);
+
+    let alive = survivors(&mut store);
+    assert_eq!(alive.len(), 10 - result.evicted_keys);
+    assert!(alive.contains(&0), "the freshly read key must survive");
+    // The evicted keys are a prefix of the untouched write order.
+    let oldest_survivor = alive.iter().copied().find(|&idx| idx != 0).unwrap();
+    assert!((oldest_survivor..10).all(|idx| alive.contains(&idx)));
+}
+
+#[test]
+fn volatile_ttl_evicts_the_soonest_expiring_keys_only() {
+    let mut store = Store::new();
+    store.maxmemory_policy = MaxmemoryPolicy::VolatileTtl;
+    for idx in 0..5 {
+        store.set(key(idx), vec![b'p'; 100], None, NOW);
+    }
+    // key:5 expires last, key:9 first.
+    for idx in 5..10 {
+        let ttl_ms = (20 - idx as u64) * 60_000;
+        store.set(key(idx), vec![b'v'; 100], Some(ttl_ms), NOW);
+    }
+
+    let full = store.estimate_memory_usage_bytes();
+    let result = evict_to(&mut store, full * 8 / 10);
+    assert_eq!(result.status, EvictionLoopStatus::Ok);
+    assert!(result.evicted_keys >= 2, "{result:?}");
+    let alive = survivors(&mut store);
+    assert!((0..5).all(|idx| alive.contains(&idx)));
+    let evicted: Vec<usize> = (0..10).filter(|idx| !alive.contains(idx)).collect();
+    let expected: Vec<usize> = (10 - result.evicted_keys..10).collect();
+    assert_eq!(evicted, expected);
+
+    // An impossible budget drains every volatile key and then stalls.
+    let result = evict_to(&mut store, 1);
+    assert_eq!(result.status, EvictionLoopStatus::Running);
+    assert!(result.bytes_to_free_after > 0);
+    assert_eq!(survivors(&mut store), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn volatile_lru_without_volatile_keys_has_no_candidates() {
+    let mut store = ten_strings(MaxmemoryPolicy::VolatileLru);
+    let result = evict_to(&mut store, 1);
+    assert_eq!(result.status, EvictionLoopStatus::Fail);
+    assert_eq!(result.failure, Some(EvictionLoopFailure::NoCandidates));
+    assert_eq!(result.evicted_keys, 0);
+    assert_eq!(store.dbsize(NOW), 10);
+
+    // Once some keys carry a TTL, only those are eligible.
+    for idx in [3, 7] {
+        store.set(key(idx), vec![b'v'; 100], Some(600_000), NOW);
+    }
+    let result = evict_to(&mut store, 1);
+    assert_eq!(result.evicted_keys, 2);
+    assert_eq!(survivors(&mut store), vec![0, 1, 2, 4, 5, 6, 8, 9]);
+}
+
+#[test]
+fn noeviction_frees_nothing_and_random_fits_the_budget() {
+    let mut store = ten_strings(MaxmemoryPolicy::Noeviction);
+    let full = store.estimate_memory_usage_bytes();
+    let result = evict_to(&mut store, full / 2);
+    assert_eq!(result.status, EvictionLoopStatus::Fail);
+    assert_eq!(result.failure, Some(EvictionLoopFailure::NoCandidates));
+    assert_eq!(store.dbsize(NOW), 10);
+
+    // Under the limit there is nothing to do whatever the policy.
+    assert_eq!(evict_to(&mut store, full).status, EvictionLoopStatus::Ok);
+    assert_eq!(store.dbsize(NOW), 10);
+
+    let mut store = ten_strings(MaxmemoryPolicy::AllkeysRandom);
+    let result = evict_to(&mut store, full / 2);
+    assert_eq!(result.status, EvictionLoopStatus::Ok);
+    assert!(store.dbsize(NOW) <= 5, "{result:?}");
+    assert!(store.estimate_memory_usage_bytes() <= full / 2);
+}
@@ -0,0 +1,135 @@
+//! OBJECT ENCODING names per value type, and the size thresholds where a
+//! compact encoding gives way to the general one.
+
+use fr_store::{
+    DEFAULT_HASH_MAX_LISTPACK_ENTRIES, DEFAULT_HASH_MAX_LISTPACK_VALUE,
+    DEFAULT_SET_MAX_INTSET_ENTRIES, DEFAULT_SET_MAX_LISTPACK_ENTRIES,
+    DEFAULT_ZSET_MAX_LISTPACK_ENTRIES, DEFAULT_ZSET_MAX_LISTPACK_VALUE,
+    OBJ_ENCODING_EMBSTR_SIZE_LIMIT, Store,
+};
+
+const NOW: u64 = 1_000;
+
+fn names(prefix: &str, n: usize) -> Vec<Vec<u8>> {
+    (0..n)
+        .map(|i| format!("{prefix}{i}").into_bytes())
+        .collect()
+}
+
+fn hash(store: &mut Store, key: &[u8], n: usize) {
+    for field in names("f", n) {
+        store.hset(key, field, b"v".to_vec(), NOW).expect("hset");
+    }
+}
+
+fn set(store: &mut Store, key: &[u8], members: &[Vec<u8>]) {
+    let refs: Vec<&[u8]> = members.iter().map(Vec::as_slice).collect();
+    store.sadd(key, &refs, NOW).expect("sadd");
+}
+
+fn zset(store: &mut Store, key: &[u8], members: Vec<Vec<u8>>) {
+    let scored: Vec<(f64, Vec<u8>)> = members
+        .into_iter()
+        .enumerate()
+        .map(|(i, member)| (i as f64, member))
+        .collect();
+    store.zadd(key, &scored, NOW).expect("zadd");
+}
+
+#[test]
+fn missing_keys_have_no_encoding() {
+    let mut store = Store::new();
+    assert_eq!(store.object_encoding(b"missing", NOW), None);
+}
+
+#[test]
+fn strings() {
+    let mut store = Store::new();
+    let cases: [(&[u8], &str); 4] = [
+        (b"12345", "int"),
+        (b"-9223372036854775808", "int"),
+        // Not the canonical form of the integer.
+        (b"007", "embstr"),
+        (b"hello", "embstr"),
+    ];
+    for (value, encoding) in cases {
+        store.set(b"k".to_vec(), value.to_vec(), None, NOW);
+        assert_eq!(
+            store.object_encoding(b"k", NOW),
+            Some(encoding),
+            "{value:?}"
+        );
+    }
+
+    let at_limit = vec![b'x'; OBJ_ENCODING_EMBSTR_SIZE_LIMIT];
+    store.set(b"k".to_vec(), at_limit, None, NOW);
+    assert_eq!(store.object_encoding(b"k", NOW), Some("embstr"));
+    let past_limit = vec![b'x'; OBJ_ENCODING_EMBSTR_SIZE_LIMIT + 1];
+    store.set(b"k".to_vec(), past_limit, None, NOW);
+    assert_eq!(store.object_encoding(b"k", NOW), Some("raw"));
+}
+
+#[test]
+fn hashes_leave_listpack_one_past_the_threshold() {
+    let mut store = Store::new();
+    hash(&mut store, b"small", DEFAULT_HASH_MAX_LISTPACK_ENTRIES);
+    assert_eq!(store.object_encoding(b"small", NOW), Some("listpack"));
+    hash(&mut store, b"big", DEFAULT_HASH_MAX_LISTPACK_ENTRIES + 1);
+    assert_eq!(store.object_encoding(b"big", NOW), Some("hashtable"));
+
+    let long = vec![b'v'; DEFAULT_HASH_MAX_LISTPACK_VALUE + 1];
+    store.hset(b"long", b"f".to_vec(), long, NOW).expect("hset");
+    assert_eq!(store.object_encoding(b"long", NOW), Some("hashtable"));
+
+    // The thresholds are the store's live config.
+    store.hash_max_listpack_entries = 128;
+    hash(&mut store, b"configured", 129);
+    assert_eq!(store.object_encoding(b"configured", NOW), Some("hashtable"));
+}
+
+#[test]
+fn sets_go_from_intset_or_listpack_to_hashtable() {
+    let mut store = Store::new();
+    let ints: Vec<Vec<u8>> = (0..=DEFAULT_SET_MAX_INTSET_ENTRIES)
+        .map(|i| i.to_string().into_bytes())
+        .collect();
+    set(&mut store, b"ints", &ints[..DEFAULT_SET_MAX_INTSET_ENTRIES]);
+    assert_eq!(store.object_encoding(b"ints", NOW), Some("intset"));
+    set(&mut store, b"ints", &ints[DEFAULT_SET_MAX_INTSET_ENTRIES..]);
+    assert_eq!(store.object_encoding(b"ints", NOW), Some("hashtable"));
+
+    let members = names("m", DEFAULT_SET_MAX_LISTPACK_ENTRIES + 1);
+    set(
+        &mut store,
+        b"words",
+        &members[..DEFAULT_SET_MAX_LISTPACK_ENTRIES],
+    );
+    assert_eq!(store.object_encoding(b"words", NOW), Some("listpack"));
+    set(
+        &mut store,
+        b"words",
+        &members[DEFAULT_SET_MAX_LISTPACK_ENTRIES..],
+    );
+    assert_eq!(store.object_encoding(b"words", NOW), Some("hashtable"));
+}
+
+#[test]
+fn sorted_sets_go_from_listpack_to_skiplist() {
+    let mut store = Store::new();
+    zset(
+        &mut store,
+        b"small",
+        names("m", DEFAULT_ZSET_MAX_LISTPACK_ENTRIES),
+    );
+    assert_eq!(store.object_encoding(b"small", NOW), Some("listpack"));
+    zset(
+        &mut store,
+        b"big",
+        names("m", DEFAULT_ZSET_MAX_LISTPACK_ENTRIES + 1),
+    );
+    assert_eq!(store.object_encoding(b"big", NOW), Some("skiplist"));
+
+    let long = vec![b'm'; DEFAULT_ZSET_MAX_LISTPACK_VALUE + 1];
+    zset(&mut store, b"long", vec![long]);
+    assert_eq!(store.object_encoding(b"long", NOW), Some("skiplist"));
+}
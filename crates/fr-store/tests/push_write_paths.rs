@@ -0,0 +1,105 @@
+//! Bulk write entry points that take borrowed element slices (`lpush`,
+//! `rpush`, `sadd`, `hset_borrowed_many`) and their owned twins
+//! (`rpush_owned`, `hset_many`) must agree on ordering, reply counts and
+//! WRONGTYPE handling, on both sides of the packed -> large encoding switch.
+
+use fr_store::{Store, StoreError};
+
+const NOW: u64 = 1_000;
+
+fn elems(prefix: &str, n: usize) -> Vec<Vec<u8>> {
+    (0..n)
+        .map(|i| format!("{prefix}{i}").into_bytes())
+        .collect()
+}
+
+fn all(store: &mut Store, key: &[u8]) -> Vec<Vec<u8>> {
+    store.lrange(key, 0, -1, NOW).unwrap()
+}
+
+#[test]
+fn push_order_and_lengths_across_the_encoding_switch() {
+    for n in [1, 3, 127, 128, 600] {
+        let items = elems("e", n);
+
+        let mut borrowed = Store::new();
+        let mut owned = Store::new();
+        assert_eq!(borrowed.rpush(b"l", &items, NOW), Ok(n));
+        assert_eq!(owned.rpush_owned(b"l", items.clone(), NOW), Ok(n));
+        assert_eq!(all(&mut borrowed, b"l"), items, "n={n}");
+        assert_eq!(all(&mut owned, b"l"), items, "n={n}");
+        assert_eq!(
+            borrowed.object_encoding(b"l", NOW),
+            owned.object_encoding(b"l", NOW),
+            "n={n}"
+        );
+
+        // LPUSH prepends one element at a time, so the batch lands reversed.
+        let head = elems("h", 3);
+        assert_eq!(borrowed.lpush(b"l", &head, NOW), Ok(n + 3));
+        let got = all(&mut borrowed, b"l");
+        assert_eq!(got[..3], [b"h2".to_vec(), b"h1".to_vec(), b"h0".to_vec()]);
+        assert_eq!(got[3..], items[..]);
+    }
+}
+
+#[test]
+fn sadd_and_hset_count_only_new_members() {
+    let mut store = Store::new();
+    assert_eq!(store.sadd(b"s", &[b"a", b"b", b"a"], NOW), Ok(2));
+    assert_eq!(store.sadd(b"s", &[b"b", b"c"], NOW), Ok(1));
+    let mut members = store.smembers(b"s", NOW).unwrap();
+    members.sort();
+    assert_eq!(members, [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+
+    let pairs: [&[u8]; 6] = [b"f1", b"1", b"f2", b"2", b"f1", b"3"];
+    assert_eq!(store.hset_borrowed_many(b"h", &pairs, NOW), Ok(2));
+    let mut owned = Store::new();
+    owned
+        .hset_many(
+            b"h",
+            pairs
+                .chunks(2)
+                .map(|p| (p[0].to_vec(), p[1].to_vec()))
+                .collect(),
+            NOW,
+        )
+        .unwrap();
+    assert_eq!(store.hgetall(b"h", NOW), owned.hgetall(b"h", NOW));
+    assert_eq!(
+        store.hgetall(b"h", NOW).unwrap(),
+        [
+            (b"f1".to_vec(), b"3".to_vec()),
+            (b"f2".to_vec(), b"2".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn wrong_type_leaves_the_existing_value_alone() {
+    let mut store = Store::new();
+    store.set(b"str".to_vec(), b"v".to_vec(), None, NOW);
+    store.sadd(b"set", &[b"m"], NOW).unwrap();
+    let dump_str = store.dump_key(b"str", NOW);
+    let dump_set = store.dump_key(b"set", NOW);
+
+    let items = elems("e", 4);
+    assert_eq!(store.rpush(b"str", &items, NOW), Err(StoreError::WrongType));
+    assert_eq!(store.lpush(b"str", &items, NOW), Err(StoreError::WrongType));
+    assert_eq!(
+        store.rpush_owned(b"set", items.clone(), NOW),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.sadd(b"str", &items, NOW), Err(StoreError::WrongType));
+    assert_eq!(
+        store.hset_borrowed_many(b"set", &[b"f", b"v"], NOW),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(
+        store.hset_many(b"str", vec![(b"f".to_vec(), b"v".to_vec())], NOW),
+        Err(StoreError::WrongType)
+    );
+
+    assert_eq!(store.dump_key(b"str", NOW), dump_str);
+    assert_eq!(store.dump_key(b"set", NOW), dump_set);
+}
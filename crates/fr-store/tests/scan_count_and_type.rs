@@ -0,0 +1,137 @@
+//! SCAN's COUNT is a page size: each call examines that many keys and
+//! MATCH/TYPE filter what it examined, so a selective filter can hand back
+//! an empty page with a cursor that is not yet 0.
+
+use std::collections::BTreeSet;
+
+use fr_store::Store;
+
+const NOW: u64 = 1_000;
+const KEYS_PER_TYPE: usize = 60;
+const TYPES: [&str; 5] = ["string", "list", "hash", "set", "zset"];
+
+fn populate() -> Store {
+    let mut store = Store::new();
+    for i in 0..KEYS_PER_TYPE {
+        let key = |kind: &str| format!("{kind}:{i:03}").into_bytes();
+        store.set(key("string"), b"v".to_vec(), None, NOW);
+        store
+            .rpush(&key("list"), &[b"e".as_slice()], NOW)
+            .expect("rpush");
+        store
+            .hset(&key("hash"), b"f".to_vec(), b"v".to_vec(), NOW)
+            .expect("hset");
+        store
+            .sadd(&key("set"), &[b"m".as_slice()], NOW)
+            .expect("sadd");
+        store
+            .zadd(&key("zset"), &[(1.0, b"m".to_vec())], NOW)
+            .expect("zadd");
+    }
+    store
+}
+
+/// Every page of a full iteration.
+fn pages(
+    store: &mut Store,
+    pattern: Option<&[u8]>,
+    type_filter: Option<&[u8]>,
+    count: usize,
+) -> Vec<Vec<Vec<u8>>> {
+    let mut pages = Vec::new();
+    let mut cursor = 0;
+    loop {
+        let (next, keys) = store.scan_in_db(0, cursor, pattern, type_filter, count, NOW);
+        pages.push(keys);
+        if next == 0 {
+            return pages;
+        }
+        cursor = next;
+    }
+}
+
+fn expected(kind: &str) -> BTreeSet<Vec<u8>> {
+    (0..KEYS_PER_TYPE)
+        .map(|i| format!("{kind}:{i:03}").into_bytes())
+        .collect()
+}
+
+#[test]
+fn count_bounds_every_page() {
+    let mut store = populate();
+    let total = TYPES.len() * KEYS_PER_TYPE;
+    let pages = pages(&mut store, None, None, 10);
+    assert_eq!(pages.len(), total / 10);
+    assert!(pages.iter().all(|page| page.len() == 10));
+
+    let keys: Vec<Vec<u8>> = pages.into_iter().flatten().collect();
+    let unique: BTreeSet<Vec<u8>> = keys.iter().cloned().collect();
+    assert_eq!(keys.len(), total, "no key is returned twice");
+    assert_eq!(unique.len(), total);
+}
+
+#[test]
+fn type_filters_what_each_page_examined() {
+    let mut store = populate();
+    for kind in TYPES {
+        let pages = pages(&mut store, None, Some(kind.as_bytes()), 10);
+        // The walk still covers the whole keyspace, 10 keys at a time.
+        assert_eq!(pages.len(), TYPES.len() * KEYS_PER_TYPE / 10, "{kind}");
+        assert!(pages.iter().any(Vec::is_empty), "{kind}");
+        assert!(pages.iter().all(|page| page.len() <= 10), "{kind}");
+
+        let keys: Vec<Vec<u8>> = pages.into_iter().flatten().collect();
+        let unique: BTreeSet<Vec<u8>> = keys.iter().cloned().collect();
+        assert_eq!(keys.len(), unique.len(), "{kind}: duplicates");
+        assert_eq!(unique, expected(kind), "{kind}");
+        for key in &keys {
+            assert_eq!(store.key_type(key, NOW), Some(kind));
+        }
+    }
+
+    // Type names compare case-insensitively; unknown ones match nothing.
+    let zsets: BTreeSet<Vec<u8>> = pages(&mut store, None, Some(b"ZSET"), 10)
+        .into_iter()
+        .flatten()
+        .collect();
+    assert_eq!(zsets, expected("zset"));
+    assert!(
+        pages(&mut store, None, Some(b"stream"), 10)
+            .iter()
+            .all(Vec::is_empty)
+    );
+}
+
+#[test]
+fn match_and_type_combine() {
+    let mut store = populate();
+    let keys: Vec<Vec<u8>> = pages(&mut store, Some(b"*:00?"), Some(b"set"), 10)
+        .into_iter()
+        .flatten()
+        .collect();
+    let want: Vec<Vec<u8>> = (0..10).map(|i| format!("set:00{i}").into_bytes()).collect();
+    assert_eq!(keys, want);
+}
+
+#[test]
+fn keys_present_throughout_are_returned_despite_writes() {
+    let mut store = populate();
+    let mut seen = BTreeSet::new();
+    let mut cursor = 0;
+    let mut round = 0;
+    loop {
+        let (next, keys) = store.scan_in_db(0, cursor, None, Some(b"hash"), 10, NOW);
+        seen.extend(keys);
+        // Churn around the walk: delete keys of another type, add new ones
+        // on both sides of the cursor.
+        store.del(&[format!("list:{round:03}").into_bytes()], NOW);
+        store.set(format!("a:{round}").into_bytes(), b"v".to_vec(), None, NOW);
+        store.set(format!("zz:{round}").into_bytes(), b"v".to_vec(), None, NOW);
+        round += 1;
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    assert_eq!(seen, expected("hash"));
+}
@@ -0,0 +1,104 @@
+//! SINTER / SINTERSTORE / SINTERCARD walk the smallest input and probe the
+//! others, so their cost tracks the smallest set rather than argument order.
+//!
+//! These checks are timing-insensitive: results must not depend on which key
+//! comes first, a missing key empties the result without scanning anything,
+//! and SINTERCARD's LIMIT caps the count however large the overlap is.
+
+use fr_store::{Store, StoreError};
+
+const NOW: u64 = 1_000;
+const BIG: usize = 200_000;
+
+fn member(i: usize) -> Vec<u8> {
+    format!("m:{i:07}").into_bytes()
+}
+
+/// `big` holds `BIG` members, `small` ten of which every other one is in
+/// `big`, and `mid` every even member of `big`.
+fn seeded() -> Store {
+    let mut store = Store::new();
+    let big: Vec<Vec<u8>> = (0..BIG).map(member).collect();
+    store.sadd(b"big", &big, NOW).unwrap();
+    let mid: Vec<Vec<u8>> = (0..BIG).step_by(2).map(member).collect();
+    store.sadd(b"mid", &mid, NOW).unwrap();
+    let small: Vec<Vec<u8>> = (0..10)
+        .map(|i| {
+            if i % 2 == 0 {
+                member(i * 1_000)
+            } else {
+                format!("absent:{i}").into_bytes()
+            }
+        })
+        .collect();
+    store.sadd(b"small", &small, NOW).unwrap();
+    store
+}
+
+#[test]
+fn result_does_not_depend_on_argument_order() {
+    let mut store = seeded();
+    let expected: Vec<Vec<u8>> = (0..10).step_by(2).map(|i| member(i * 1_000)).collect();
+    let orders: [&[&[u8]]; 4] = [
+        &[b"big", b"small"],
+        &[b"small", b"big"],
+        &[b"big", b"mid", b"small"],
+        &[b"mid", b"small", b"big"],
+    ];
+    for keys in orders {
+        assert_eq!(store.sinter(keys, NOW), Ok(expected.clone()), "{keys:?}");
+        assert_eq!(store.sintercard(keys, 0, NOW), Ok(5), "{keys:?}");
+        assert_eq!(store.sinterstore(b"dst", keys, NOW), Ok(5), "{keys:?}");
+        let mut stored = store.smembers(b"dst", NOW).unwrap();
+        stored.sort();
+        assert_eq!(stored, expected);
+    }
+}
+
+#[test]
+fn a_missing_input_empties_the_result() {
+    let mut store = seeded();
+    for keys in [&[&b"big"[..], b"nope"][..], &[b"nope", b"big", b"small"]] {
+        assert_eq!(store.sinter(keys, NOW), Ok(Vec::new()));
+        assert_eq!(store.sintercard(keys, 0, NOW), Ok(0));
+    }
+    // SINTERSTORE with an empty result deletes the destination.
+    store.sadd(b"dst", &[b"stale"], NOW).unwrap();
+    assert_eq!(store.sinterstore(b"dst", &[b"big", b"nope"], NOW), Ok(0));
+    assert!(!store.exists(b"dst", NOW));
+
+    // Every key is still type-checked, even after a missing one.
+    store.set(b"str".to_vec(), b"v".to_vec(), None, NOW);
+    assert_eq!(
+        store.sinter(&[b"nope", b"big", b"str"], NOW),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(
+        store.sintercard(&[b"nope", b"str"], 0, NOW),
+        Err(StoreError::WrongType)
+    );
+}
+
+#[test]
+fn sintercard_limit_caps_the_count() {
+    let mut store = seeded();
+    // `big` ∩ `mid` is `BIG / 2` members.
+    assert_eq!(
+        store.sintercard(&[b"big", b"mid"], 0, NOW),
+        Ok((BIG / 2) as u64)
+    );
+    for limit in [1, 7, 1_000, (BIG / 2) as u64] {
+        assert_eq!(
+            store.sintercard(&[b"big", b"mid"], limit, NOW),
+            Ok(limit),
+            "{limit}"
+        );
+    }
+    // A LIMIT above the true cardinality is not an exact count request.
+    assert_eq!(
+        store.sintercard(&[b"mid", b"big"], BIG as u64, NOW),
+        Ok((BIG / 2) as u64)
+    );
+    assert_eq!(store.sintercard(&[b"small", b"big"], 3, NOW), Ok(3));
+    assert_eq!(store.sintercard(&[b"small", b"big"], 50, NOW), Ok(5));
+}
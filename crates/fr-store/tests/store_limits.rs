@@ -0,0 +1,238 @@
+//! `StoreLimits`: per-tenant caps enforced by the store's write paths.
+//!
+//! Each quota kind is driven through the store methods that can cross it. A
+//! rejected write must leave the key exactly as it was, and reads and deletes
+//! keep working on data that is already over a (lowered) limit.
+
+use fr_store::{QuotaKind, Store, StoreError, StoreLimits};
+
+const NOW: u64 = 1_000;
+
+fn quota(kind: QuotaKind) -> StoreError {
+    StoreError::QuotaExceeded(kind)
+}
+
+#[test]
+fn limits_default_to_unlimited_and_are_adjustable() {
+    let mut store = Store::new();
+    assert_eq!(store.limits(), StoreLimits::UNLIMITED);
+    assert!(store.limits().is_unlimited());
+
+    let limits = StoreLimits {
+        max_keys: Some(10),
+        ..StoreLimits::default()
+    };
+    store.set_limits(limits);
+    assert_eq!(store.limits(), limits);
+    assert_eq!(Store::with_limits(limits).limits(), limits);
+}
+
+#[test]
+fn quota_error_is_an_oom_reply() {
+    let err = quota(QuotaKind::Keys);
+    assert_eq!(err.code(), "OOM");
+    assert!(err.to_string().starts_with("OOM "), "{err}");
+}
+
+#[test]
+fn value_size_caps_strings_and_elements() {
+    let mut store = Store::with_limits(StoreLimits {
+        max_value_bytes: Some(8),
+        ..StoreLimits::default()
+    });
+    assert_eq!(
+        store.check_string_write_quota(&[(b"s", 9)], NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(store.check_string_write_quota(&[(b"s", 8)], NOW), Ok(()));
+
+    store.set(b"s".to_vec(), b"12345".to_vec(), None, NOW);
+    assert_eq!(
+        store.append(b"s", b"6789", NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(
+        store.setrange(b"s", 8, b"x", NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(
+        store.setbit(b"s", 64, true, NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(store.get(b"s", NOW), Ok(Some(b"12345".to_vec())));
+    assert_eq!(store.append(b"s", b"678", NOW), Ok(8));
+
+    assert_eq!(
+        store.rpush(b"l", &[b"ok".as_slice(), b"too-long!"], NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(store.llen(b"l", NOW), Ok(0));
+    assert_eq!(
+        store.hset(b"h", b"f".to_vec(), b"too-long!".to_vec(), NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(
+        store.sadd(b"set", &[b"too-long!"], NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+    assert_eq!(
+        store.zadd(b"z", &[(1.0, b"too-long!".to_vec())], NOW),
+        Err(quota(QuotaKind::ValueSize))
+    );
+}
+
+#[test]
+fn element_cap_counts_only_new_members() {
+    let mut store = Store::with_limits(StoreLimits {
+        max_elements: Some(3),
+        ..StoreLimits::default()
+    });
+
+    assert_eq!(store.rpush(b"l", &[b"a", b"b", b"c"], NOW), Ok(3));
+    assert_eq!(
+        store.lpush(b"l", &[b"d"], NOW),
+        Err(quota(QuotaKind::Elements))
+    );
+
+    assert_eq!(store.sadd(b"s", &[b"a", b"b", b"c"], NOW), Ok(3));
+    assert_eq!(store.sadd(b"s", &[b"a", b"a"], NOW), Ok(0));
+    assert_eq!(
+        store.sadd(b"s", &[b"a", b"d"], NOW),
+        Err(quota(QuotaKind::Elements))
+    );
+
+    let fields: [&[u8]; 6] = [b"f1", b"v", b"f2", b"v", b"f3", b"v"];
+    assert_eq!(store.hset_borrowed_many(b"h", &fields, NOW), Ok(3));
+    assert_eq!(
+        store.hset(b"h", b"f1".to_vec(), b"updated".to_vec(), NOW),
+        Ok(false)
+    );
+    let grow: [&[u8]; 4] = [b"f1", b"again", b"f4", b"v"];
+    assert_eq!(
+        store.hset_borrowed_many(b"h", &grow, NOW),
+        Err(quota(QuotaKind::Elements))
+    );
+    assert_eq!(store.hget(b"h", b"f1", NOW), Ok(Some(b"updated".to_vec())));
+    assert_eq!(store.hlen(b"h", NOW), Ok(3));
+
+    let members = [
+        (1.0, b"a".to_vec()),
+        (2.0, b"b".to_vec()),
+        (3.0, b"c".to_vec()),
+    ];
+    assert_eq!(store.zadd(b"z", &members, NOW), Ok(3));
+    assert_eq!(store.zadd(b"z", &[(9.0, b"a".to_vec())], NOW), Ok(0));
+    assert_eq!(
+        store.zadd(b"z", &[(4.0, b"d".to_vec())], NOW),
+        Err(quota(QuotaKind::Elements))
+    );
+}
+
+#[test]
+fn key_cap_rejects_only_new_keys() {
+    let mut store = Store::with_limits(StoreLimits {
+        max_keys: Some(2),
+        ..StoreLimits::default()
+    });
+    assert_eq!(store.sadd(b"a", &[b"x"], NOW), Ok(1));
+    assert_eq!(store.rpush(b"b", &[b"x"], NOW), Ok(1));
+
+    assert_eq!(store.rpush(b"c", &[b"x"], NOW), Err(quota(QuotaKind::Keys)));
+    assert_eq!(store.rpush(b"b", &[b"y"], NOW), Ok(2));
+    // Two fresh keys in one batch cannot squeeze in one at a time.
+    store.del(&[b"a".to_vec()], NOW);
+    assert_eq!(
+        store.check_string_write_quota(&[(b"c", 1), (b"d", 1)], NOW),
+        Err(quota(QuotaKind::Keys))
+    );
+    assert_eq!(
+        store.check_string_write_quota(&[(b"b2", 1), (b"b2", 2)], NOW),
+        Ok(())
+    );
+}
+
+#[test]
+fn memory_cap_uses_the_usage_estimate_and_recovers_after_delete() {
+    let mut store = Store::new();
+    store.set(b"big".to_vec(), vec![b'x'; 4096], None, NOW);
+    let used = store.estimate_memory_usage_bytes();
+    store.set_limits(StoreLimits {
+        max_memory_bytes: Some(used),
+        ..StoreLimits::default()
+    });
+
+    assert_eq!(
+        store.sadd(b"s", &[b"member"], NOW),
+        Err(quota(QuotaKind::Memory))
+    );
+    assert_eq!(
+        store.check_string_write_quota(&[(b"k", 1)], NOW),
+        Err(quota(QuotaKind::Memory))
+    );
+    // Reads and deletes still work while over the cap.
+    assert_eq!(
+        store.get(b"big", NOW).map(|v| v.map(|v| v.len())),
+        Ok(Some(4096))
+    );
+    assert_eq!(store.del(&[b"big".to_vec()], NOW), 1);
+    assert_eq!(store.sadd(b"s", &[b"member"], NOW), Ok(1));
+}
+
+#[test]
+fn rejected_writes_rescan_only_after_the_keyspace_changes() {
+    let mut store = Store::new();
+    store.set(b"big".to_vec(), vec![b'x'; 4096], None, NOW);
+    let used = store.estimate_memory_usage_bytes();
+    store.set_limits(StoreLimits {
+        max_memory_bytes: Some(used),
+        ..StoreLimits::default()
+    });
+    assert_eq!(
+        store.check_string_write_quota(&[(b"k", 1)], NOW),
+        Err(quota(QuotaKind::Memory))
+    );
+
+    // With nothing written since the confirming scan, a retry trusts the
+    // cached estimate instead of walking the keyspace again.
+    let scanned = store.cached_memory_usage_bytes.get();
+    store.cached_memory_usage_bytes.set(scanned + 1);
+    assert_eq!(
+        store.check_string_write_quota(&[(b"k", 1)], NOW),
+        Err(quota(QuotaKind::Memory))
+    );
+    assert_eq!(store.cached_memory_usage_bytes.get(), scanned + 1);
+
+    // A write in between makes the next rejection rescan.
+    store.set(b"big".to_vec(), vec![b'x'; 4096], None, NOW);
+    assert_eq!(
+        store.check_string_write_quota(&[(b"k", 1)], NOW),
+        Err(quota(QuotaKind::Memory))
+    );
+    assert_eq!(store.cached_memory_usage_bytes.get(), scanned);
+}
+
+#[test]
+fn lowered_limits_keep_existing_data_readable_and_deletable() {
+    let mut store = Store::new();
+    store.rpush(b"l", &[b"a", b"b", b"c"], NOW).expect("rpush");
+    store.sadd(b"s", &[b"a", b"b", b"c"], NOW).expect("sadd");
+    store.set_limits(StoreLimits {
+        max_keys: Some(1),
+        max_elements: Some(1),
+        ..StoreLimits::default()
+    });
+
+    assert_eq!(store.llen(b"l", NOW), Ok(3));
+    assert_eq!(store.lset(b"l", 0, b"z".to_vec(), NOW), Ok(()));
+    assert_eq!(store.sadd(b"s", &[b"a"], NOW), Ok(0));
+    assert_eq!(store.lpop(b"l", NOW), Ok(Some(b"z".to_vec())));
+    assert_eq!(store.srem(b"s", &[b"a".as_slice()], NOW), Ok(1));
+    assert_eq!(
+        store.rpush(b"l", &[b"d"], NOW),
+        Err(quota(QuotaKind::Elements))
+    );
+    // PUSHX on a missing key writes nothing, so it is never over quota.
+    assert_eq!(store.lpushx(b"missing", &[b"x".to_vec()], NOW), Ok(0));
+    assert_eq!(store.del(&[b"l".to_vec(), b"s".to_vec()], NOW), 2);
+    assert_eq!(store.rpush(b"l", &[b"d"], NOW), Ok(1));
+}
@@ -0,0 +1,107 @@
+//! XINFO CONSUMERS bookkeeping: each row carries the consumer's pending
+//! count, how long since it was last seen and how long since it last read
+//! or claimed entries, both stamped by XREADGROUP and XCLAIM.
+
+use fr_store::{
+    Store, StreamClaimOptions, StreamConsumerInfo, StreamGroupReadCursor, StreamGroupReadOptions,
+};
+
+const T0: u64 = 10_000;
+
+fn read_new(count: usize) -> StreamGroupReadOptions {
+    StreamGroupReadOptions {
+        cursor: StreamGroupReadCursor::NewEntries,
+        noack: false,
+        count: Some(count),
+    }
+}
+
+fn stream_with_group() -> Store {
+    let mut store = Store::new();
+    for ms in 1..=4 {
+        store
+            .xadd(b"s", (ms, 0), &[(b"f".to_vec(), b"v".to_vec())], T0)
+            .expect("xadd");
+    }
+    store
+        .xgroup_create(b"s", b"g", (0, 0), false, T0)
+        .expect("xgroup create");
+    store
+}
+
+fn consumers(store: &mut Store, now_ms: u64) -> Vec<StreamConsumerInfo> {
+    store
+        .xinfo_consumers(b"s", b"g", now_ms)
+        .expect("stream")
+        .expect("group")
+}
+
+#[test]
+fn idle_tracks_each_consumers_own_reads() {
+    let mut store = stream_with_group();
+    store
+        .xreadgroup(b"s", b"g", b"alice", read_new(1), T0)
+        .expect("xreadgroup");
+    store
+        .xreadgroup(b"s", b"g", b"bob", read_new(2), T0 + 300)
+        .expect("xreadgroup");
+
+    assert_eq!(
+        consumers(&mut store, T0 + 1_000),
+        vec![
+            StreamConsumerInfo {
+                name: b"alice".to_vec(),
+                pending: 1,
+                idle_ms: 1_000,
+                inactive_ms: Some(1_000),
+            },
+            StreamConsumerInfo {
+                name: b"bob".to_vec(),
+                pending: 2,
+                idle_ms: 700,
+                inactive_ms: Some(700),
+            },
+        ]
+    );
+}
+
+#[test]
+fn xclaim_moves_pending_and_marks_the_claimer_active() {
+    let mut store = stream_with_group();
+    store
+        .xreadgroup(b"s", b"g", b"alice", read_new(2), T0)
+        .expect("xreadgroup");
+    store
+        .xgroup_createconsumer(b"s", b"g", b"bob", T0)
+        .expect("createconsumer");
+    let claim = StreamClaimOptions {
+        min_idle_time_ms: 0,
+        idle_ms: None,
+        time_ms: None,
+        retry_count: None,
+        force: false,
+        justid: false,
+        last_id: None,
+    };
+    store
+        .xclaim(b"s", b"g", b"bob", &[(1, 0)], claim, T0 + 500)
+        .expect("xclaim");
+
+    let [alice, bob] = consumers(&mut store, T0 + 800).try_into().unwrap();
+    assert_eq!((alice.pending, bob.pending), (1, 1));
+    assert_eq!((alice.idle_ms, bob.idle_ms), (800, 300));
+    assert_eq!(bob.inactive_ms, Some(300));
+}
+
+#[test]
+fn a_created_consumer_is_inactive_until_it_reads() {
+    let mut store = stream_with_group();
+    store
+        .xgroup_createconsumer(b"s", b"g", b"carol", T0)
+        .expect("createconsumer");
+
+    let [carol] = consumers(&mut store, T0 + 250).try_into().unwrap();
+    assert_eq!(carol.pending, 0);
+    assert_eq!(carol.idle_ms, 250);
+    assert_eq!(carol.inactive_ms, None);
+}
@@ -0,0 +1,157 @@
+//! Stream writes against keys that hold another type, and consumer group /
+//! consumer name checks.
+//!
+//! A key holds exactly one value: every stream-creating path (XADD, XGROUP
+//! CREATE ... MKSTREAM, XSETID) must refuse a non-stream key with WRONGTYPE
+//! and leave the existing value byte-for-byte intact.
+
+use fr_store::{
+    Store, StoreError, StreamAutoClaimOptions, StreamClaimOptions, StreamGroupReadCursor,
+    StreamGroupReadOptions, StreamNameKind,
+};
+
+const NOW: u64 = 1_000;
+
+fn non_stream_keys(store: &mut Store) -> Vec<(&'static [u8], Vec<u8>)> {
+    store.set(b"string".to_vec(), b"value".to_vec(), None, NOW);
+    store.rpush(b"list", &[b"a", b"b"], NOW).expect("rpush");
+    store
+        .hset(b"hash", b"f".to_vec(), b"v".to_vec(), NOW)
+        .expect("hset");
+    store.sadd(b"set", &[b"m"], NOW).expect("sadd");
+    store
+        .zadd(b"zset", &[(1.0, b"m".to_vec())], NOW)
+        .expect("zadd");
+    [&b"string"[..], b"list", b"hash", b"set", b"zset"]
+        .into_iter()
+        .map(|key| (key, store.dump_key(key, NOW).expect("dump")))
+        .collect()
+}
+
+fn read_options() -> StreamGroupReadOptions {
+    StreamGroupReadOptions {
+        cursor: StreamGroupReadCursor::NewEntries,
+        noack: false,
+        count: None,
+    }
+}
+
+#[test]
+fn stream_creation_refuses_keys_of_other_types() {
+    let mut store = Store::new();
+    for (key, dump) in non_stream_keys(&mut store) {
+        let fields = [(b"f".to_vec(), b"v".to_vec())];
+        assert_eq!(
+            store.xadd(key, (1, 0), &fields, NOW),
+            Err(StoreError::WrongType)
+        );
+        for mkstream in [false, true] {
+            assert_eq!(
+                store.xgroup_create(key, b"g", (0, 0), mkstream, NOW),
+                Err(StoreError::WrongType),
+                "{key:?} mkstream={mkstream}"
+            );
+        }
+        assert_eq!(store.xsetid(key, (5, 0), NOW), Err(StoreError::WrongType));
+        assert_eq!(
+            store.xgroup_createconsumer(key, b"g", b"c", NOW),
+            Err(StoreError::WrongType)
+        );
+        assert_eq!(
+            store.xreadgroup(key, b"g", b"c", read_options(), NOW),
+            Err(StoreError::WrongType)
+        );
+
+        assert_eq!(store.dump_key(key, NOW), Some(dump), "{key:?} changed");
+        assert!(store.stream_consumer_groups(key).is_none());
+    }
+    assert_eq!(store.get(b"string", NOW), Ok(Some(b"value".to_vec())));
+}
+
+#[test]
+fn groups_and_consumers_need_non_empty_names() {
+    let mut store = Store::new();
+    let empty_group = StoreError::EmptyStreamName(StreamNameKind::Group);
+    let empty_consumer = StoreError::EmptyStreamName(StreamNameKind::Consumer);
+    assert_eq!(empty_group.code(), "ERR");
+    assert_eq!(
+        empty_group.to_string(),
+        "ERR consumer group name can't be empty"
+    );
+    assert_eq!(
+        empty_consumer.to_string(),
+        "ERR consumer name can't be empty"
+    );
+
+    // MKSTREAM does not leave a stream behind when the group is refused.
+    assert_eq!(
+        store.xgroup_create(b"s", b"", (0, 0), true, NOW),
+        Err(empty_group.clone())
+    );
+    assert_eq!(store.key_type(b"s", NOW), None);
+
+    store
+        .xadd(b"s", (1, 0), &[(b"f".to_vec(), b"v".to_vec())], NOW)
+        .expect("xadd");
+    assert_eq!(
+        store.xgroup_create(b"s", b"", (0, 0), false, NOW),
+        Err(empty_group)
+    );
+    assert_eq!(
+        store.xgroup_create(b"s", b"g", (0, 0), false, NOW),
+        Ok(true)
+    );
+
+    assert_eq!(
+        store.xgroup_createconsumer(b"s", b"g", b"", NOW),
+        Err(empty_consumer.clone())
+    );
+    assert_eq!(
+        store.xreadgroup(b"s", b"g", b"", read_options(), NOW),
+        Err(empty_consumer.clone())
+    );
+    let claim = StreamClaimOptions {
+        min_idle_time_ms: 0,
+        idle_ms: None,
+        time_ms: None,
+        retry_count: None,
+        force: true,
+        justid: false,
+        last_id: Some((9, 0)),
+    };
+    assert_eq!(
+        store.xclaim(b"s", b"g", b"", &[(1, 0)], claim, NOW),
+        Err(empty_consumer.clone())
+    );
+    let autoclaim = StreamAutoClaimOptions {
+        min_idle_time_ms: 0,
+        count: 10,
+        justid: false,
+    };
+    assert_eq!(
+        store.xautoclaim(b"s", b"g", b"", (0, 0), autoclaim, NOW),
+        Err(empty_consumer)
+    );
+
+    // The refused calls created no consumer, delivered nothing and did not
+    // apply XCLAIM's LASTID.
+    let groups = store
+        .xinfo_groups(b"s", NOW)
+        .expect("xinfo")
+        .expect("stream");
+    let (name, consumers, pending, last_delivered, _) = &groups[0];
+    assert_eq!(
+        (name.as_slice(), *consumers, *pending, *last_delivered),
+        (&b"g"[..], 0, 0, (0, 0))
+    );
+
+    // A missing group is still reported as missing, whatever the consumer name.
+    assert_eq!(
+        store.xreadgroup(b"s", b"nope", b"", read_options(), NOW),
+        Ok(None)
+    );
+    assert_eq!(
+        store.xreadgroup(b"s", b"g", b"c", read_options(), NOW),
+        Ok(Some(vec![((1, 0), vec![(b"f".to_vec(), b"v".to_vec())])]))
+    );
+}
@@ -0,0 +1,118 @@
+//! `stream-preserve-last-id-on-delete`: a deleted stream leaves its last id
+//! behind for a window, so a stream recreated under the same name keeps
+//! generating ids above it. Off by default.
+
+use fr_store::{DEFAULT_STREAM_PRESERVE_LAST_ID_WINDOW_MS, Store};
+
+const NOW: u64 = 1_000;
+
+fn stream(store: &mut Store, ids: &[(u64, u64)]) {
+    let fields = [(b"f".to_vec(), b"v".to_vec())];
+    for &id in ids {
+        store.xadd(b"s", id, &fields, NOW).expect("xadd");
+    }
+}
+
+fn preserving() -> Store {
+    let mut store = Store::new();
+    store.set_stream_preserve_last_id_on_delete(true);
+    store
+}
+
+#[test]
+fn off_by_default() {
+    let mut store = Store::new();
+    assert!(!store.stream_preserve_last_id_on_delete());
+    assert_eq!(
+        store.stream_preserve_last_id_window_ms(),
+        DEFAULT_STREAM_PRESERVE_LAST_ID_WINDOW_MS
+    );
+    stream(&mut store, &[(5_000, 0)]);
+    assert_eq!(store.del(&[b"s".to_vec()], NOW), 1);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), None);
+}
+
+#[test]
+fn every_way_of_deleting_a_stream_leaves_its_last_id() {
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0), (5_000, 7)]);
+    store.del(&[b"s".to_vec()], NOW);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), Some((5_000, 7)));
+
+    // An XSETID watermark above the last entry counts too.
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    assert_eq!(store.xsetid(b"s", (6_000, 3), NOW), Ok(true));
+    store.del(&[b"s".to_vec()], NOW);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), Some((6_000, 3)));
+
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    assert!(store.expire_milliseconds(b"s", 10, NOW));
+    assert!(!store.exists(b"s", NOW + 11));
+    assert_eq!(
+        store.stream_preserved_last_id(b"s", NOW + 11),
+        Some((5_000, 0))
+    );
+
+    // RENAME frees the old name as well.
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    store.rename(b"s", b"moved", NOW).expect("rename");
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), Some((5_000, 0)));
+}
+
+#[test]
+fn the_highest_id_across_incarnations_is_kept() {
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+    // Recreated with an explicit, lower id and deleted again.
+    stream(&mut store, &[(2_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), Some((5_000, 0)));
+}
+
+#[test]
+fn ids_are_forgotten_after_the_window() {
+    let mut store = preserving();
+    store.set_stream_preserve_last_id_window_ms(100);
+    stream(&mut store, &[(5_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+    assert_eq!(
+        store.stream_preserved_last_id(b"s", 5_099),
+        Some((5_000, 0))
+    );
+    assert_eq!(store.stream_preserved_last_id(b"s", 5_100), None);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), None);
+}
+
+#[test]
+fn flushing_or_turning_the_flag_off_forgets_ids() {
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+    store.flushdb();
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), None);
+
+    stream(&mut store, &[(5_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+    store.set_stream_preserve_last_id_on_delete(false);
+    store.set_stream_preserve_last_id_on_delete(true);
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), None);
+}
+
+#[test]
+fn mkstream_starts_above_the_old_id_until_xsetid_resets_it() {
+    let mut store = preserving();
+    stream(&mut store, &[(5_000, 0)]);
+    store.del(&[b"s".to_vec()], NOW);
+
+    assert_eq!(store.xgroup_create(b"s", b"g", (0, 0), true, NOW), Ok(true));
+    assert_eq!(store.xlast_id(b"s", NOW), Ok(Some((5_000, 0))));
+    // The new stream took the id over.
+    assert_eq!(store.stream_preserved_last_id(b"s", NOW), None);
+
+    assert_eq!(store.xsetid(b"s", (0, 0), NOW), Ok(true));
+    assert_eq!(store.xlast_id(b"s", NOW), Ok(Some((0, 0))));
+}
@@ -0,0 +1,71 @@
+//! The read-then-write string getters (GETSET, GETDEL, GETEX) and plain GET
+//! tell a missing key from one holding another type: the former is `Ok(None)`,
+//! the latter `Err(WrongType)`, and in that case the aggregate is left exactly
+//! as it was — value, type and TTL.
+
+use fr_store::{Store, StoreError};
+
+const NOW: u64 = 1_000;
+
+fn seeded() -> Store {
+    let mut store = Store::new();
+    store.rpush(b"list", &[b"a", b"b"], NOW).unwrap();
+    store
+        .hset(b"hash", b"f".to_vec(), b"v".to_vec(), NOW)
+        .unwrap();
+    store.sadd(b"set", &[b"m"], NOW).unwrap();
+    store.zadd(b"zset", &[(1.0, b"m".to_vec())], NOW).unwrap();
+    assert!(store.expire_seconds(b"zset", 100, NOW));
+    store
+}
+
+#[test]
+fn getters_report_wrong_type_and_leave_the_key_alone() {
+    let mut store = seeded();
+    for (key, kind) in [
+        (&b"list"[..], "list"),
+        (b"hash", "hash"),
+        (b"set", "set"),
+        (b"zset", "zset"),
+    ] {
+        let before = store.dump_key(key, NOW).expect("seeded key");
+        let ttl_before = store.pttl(key, NOW);
+
+        assert_eq!(store.get(key, NOW), Err(StoreError::WrongType), "{kind}");
+        assert_eq!(
+            store.getset(key.to_vec(), b"v", NOW),
+            Err(StoreError::WrongType),
+            "{kind}"
+        );
+        assert_eq!(store.getdel(key, NOW), Err(StoreError::WrongType), "{kind}");
+        assert_eq!(
+            store.getex(key, Some(Some(NOW + 5_000)), NOW),
+            Err(StoreError::WrongType),
+            "{kind}"
+        );
+        assert_eq!(
+            store.getex(key, Some(None), NOW),
+            Err(StoreError::WrongType),
+            "{kind}"
+        );
+
+        assert_eq!(store.key_type(key, NOW), Some(kind));
+        assert_eq!(store.dump_key(key, NOW).as_deref(), Some(&before[..]));
+        assert_eq!(store.pttl(key, NOW), ttl_before, "{kind}");
+    }
+}
+
+#[test]
+fn missing_keys_are_none_and_strings_still_work() {
+    let mut store = Store::new();
+    assert_eq!(store.get(b"k", NOW), Ok(None));
+    assert_eq!(store.getdel(b"k", NOW), Ok(None));
+    assert_eq!(store.getex(b"k", Some(None), NOW), Ok(None));
+    assert_eq!(store.getset(b"k".to_vec(), b"1", NOW), Ok(None));
+    assert_eq!(
+        store.getset(b"k".to_vec(), b"2", NOW),
+        Ok(Some(b"1".to_vec()))
+    );
+    assert_eq!(store.getdel(b"k", NOW), Ok(Some(b"2".to_vec())));
+    assert_eq!(store.key_type(b"k", NOW), None);
+}
@@ -3,6 +3,7 @@
 use arbitrary::Arbitrary;
 use libfuzzer_sys::fuzz_target;
 
+use fr_store::glob::Pattern;
 use fr_store::glob_match;
 
 #[derive(Debug, Arbitrary)]
@@ -84,6 +85,32 @@ impl StructuredPattern {
     }
 }
 
+/// The public compiled `Pattern` must agree with `glob_match`, and its literal
+/// accessors must hold for every match.
+fn check_compiled(pattern: &[u8], string: &[u8]) {
+    let compiled = Pattern::compile(pattern).expect("every pattern compiles");
+    let matched = compiled.matches(string);
+    assert_eq!(
+        matched,
+        glob_match(pattern, string),
+        "Pattern::matches must agree with glob_match"
+    );
+    assert!(pattern.starts_with(compiled.literal_prefix()));
+    if matched {
+        assert!(
+            string.starts_with(compiled.literal_prefix()),
+            "every match must start with the literal prefix"
+        );
+    }
+    if compiled.is_literal() {
+        assert_eq!(
+            matched,
+            string == pattern,
+            "literal pattern is byte equality"
+        );
+    }
+}
+
 fuzz_target!(|input: FuzzInput| {
     match input {
         FuzzInput::Raw { pattern, string } => {
@@ -91,6 +118,7 @@ fuzz_target!(|input: FuzzInput| {
                 return;
             }
             let _ = glob_match(&pattern, &string);
+            check_compiled(&pattern, &string);
         }
         FuzzInput::Structured { pattern, string } => {
             if pattern.segments.len() > 32 || string.len() > 4096 {
@@ -103,6 +131,7 @@ fuzz_target!(|input: FuzzInput| {
             }
 
             let result = glob_match(&pattern_bytes, &string);
+            check_compiled(&pattern_bytes, &string);
 
             if pattern.segments.is_empty() {
                 assert!(
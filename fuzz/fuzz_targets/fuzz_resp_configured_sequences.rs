@@ -265,6 +265,8 @@ fn loose_config(profile: FrameProfile) -> ParserConfig {
         // the documented RESP3-downgrade path when frames include
         // Map/Push variants.
         allow_resp3: true,
+        preserve_resp3: false,
+        keep_attributes: false,
     }
 }
 
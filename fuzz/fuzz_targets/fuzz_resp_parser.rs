@@ -22,6 +22,8 @@ fuzz_target!(|data: &[u8]| {
         // production parser so the fuzzer exercises the
         // fail-closed prefix matrix on untrusted input.
         allow_resp3: false,
+        preserve_resp3: false,
+        keep_attributes: false,
     };
     let _ = parse_frame_with_config(data, &restrictive_config);
 
@@ -31,6 +33,16 @@ fuzz_target!(|data: &[u8]| {
         max_array_len: 1_000_000,
         max_recursion_depth: 32,
         allow_resp3: true,
+        preserve_resp3: false,
+        keep_attributes: false,
     };
     let _ = parse_frame_with_config(data, &permissive_config);
+
+    // Same limits, decoding RESP3 into native variants with attributes kept.
+    let native_config = ParserConfig {
+        preserve_resp3: true,
+        keep_attributes: true,
+        ..permissive_config
+    };
+    let _ = parse_frame_with_config(data, &native_config);
 });
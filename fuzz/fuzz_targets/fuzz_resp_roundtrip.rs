@@ -2,7 +2,7 @@
 
 use libfuzzer_sys::fuzz_target;
 
-use fr_protocol::parse_frame;
+use fr_protocol::{ParserConfig, parse_frame, parse_frame_with_config};
 
 fuzz_target!(|data: &[u8]| {
     // Guard against excessively large inputs
@@ -20,4 +20,28 @@ fuzz_target!(|data: &[u8]| {
             "Round-trip violation: parse(encode(frame)) != frame"
         );
     }
+
+    // RESP3 oracle: natively decoded frames re-encode stably. One pass
+    // normalizes (null aggregates become `_`, blob errors are sanitized,
+    // verbatim tags become `txt`); after it, decode and RESP3 encode are
+    // inverse.
+    let native = ParserConfig {
+        allow_resp3: true,
+        preserve_resp3: true,
+        keep_attributes: true,
+        ..ParserConfig::default()
+    };
+    if let Ok(parsed) = parse_frame_with_config(data, &native) {
+        let mut encoded = Vec::new();
+        parsed.frame.encode_into_resp3(&mut encoded);
+        let reparsed = parse_frame_with_config(&encoded, &native)
+            .expect("re-encoding a natively parsed frame must be parseable");
+        assert_eq!(reparsed.consumed, encoded.len());
+        let mut reencoded = Vec::new();
+        reparsed.frame.encode_into_resp3(&mut reencoded);
+        assert_eq!(
+            encoded, reencoded,
+            "RESP3 round-trip violation: encode_resp3(parse(encode_resp3(frame))) changed"
+        );
+    }
 });
@@ -153,6 +153,8 @@ fn default_runtime_parser_config() -> ParserConfig {
         // prefixes from untrusted input (matches the production
         // parser default).
         allow_resp3: false,
+        preserve_resp3: false,
+        keep_attributes: false,
     }
 }
 